@@ -0,0 +1,134 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::{
+        Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::SystemTime,
+};
+
+// Decoded images and audio are much larger than their files, so only sources that are slow
+// to decode are worth a trip to disk.
+const MIN_CACHED_SOURCE_BYTES: usize = 64 * 1024;
+const CACHE_SIZE_LIMIT_BYTES: u64 = 1024 * 1024 * 1024;
+const CACHE_DIR: &str = "cache";
+
+static EVICTION_LOCK: Mutex<()> = Mutex::new(());
+static NEXT_TMP_ID: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Clone, Copy)]
+pub enum CacheKind {
+    Texture,
+    Pcm,
+}
+
+impl CacheKind {
+    fn dir_name(&self) -> &'static str {
+        match self {
+            CacheKind::Texture => "textures",
+            CacheKind::Pcm => "pcm",
+        }
+    }
+}
+
+// FNV-1a, spelled out so keys stay the same across Rust versions.
+fn content_key(source: &[u8]) -> String {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in source {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    format!("{:016x}-{}", hash, source.len())
+}
+
+fn entry_path(kind: CacheKind, source: &[u8]) -> PathBuf {
+    Path::new(CACHE_DIR)
+        .join(kind.dir_name())
+        .join(content_key(source))
+}
+
+// Decoded data previously stored for these exact source bytes.
+pub fn load(kind: CacheKind, source: &[u8]) -> Option<Vec<u8>> {
+    if source.len() < MIN_CACHED_SOURCE_BYTES {
+        return None;
+    }
+    let path = entry_path(kind, source);
+    let data = fs::read(&path).ok()?;
+    // The modification time doubles as last use for eviction.
+    if let Ok(file) = fs::File::options().write(true).open(&path) {
+        let _ = file.set_modified(SystemTime::now());
+    }
+    log!("[cache] hit {}", path.display());
+    Some(data)
+}
+
+// Writes happen on a background thread, so a first open is not slowed down by the cache.
+pub fn store(kind: CacheKind, source: &[u8], data: Vec<u8>) {
+    if source.len() < MIN_CACHED_SOURCE_BYTES {
+        return;
+    }
+    let path = entry_path(kind, source);
+    let spawned = std::thread::Builder::new()
+        .name("asset-cache-store".to_string())
+        .spawn(move || {
+            let Some(parent) = path.parent() else {
+                return;
+            };
+            if let Err(err) = fs::create_dir_all(parent) {
+                log!("[cache] failed to create {}: {}", parent.display(), err);
+                return;
+            }
+            // Readers only ever see complete entries.
+            let tmp_id = NEXT_TMP_ID.fetch_add(1, Ordering::Relaxed);
+            let tmp_path = path.with_extension(format!("tmp{}", tmp_id));
+            let written = fs::write(&tmp_path, &data).and_then(|_| fs::rename(&tmp_path, &path));
+            if let Err(err) = written {
+                log!("[cache] failed to write {}: {}", path.display(), err);
+                let _ = fs::remove_file(&tmp_path);
+                return;
+            }
+            evict_to_size_limit();
+        });
+    if let Err(err) = spawned {
+        log!("[cache] failed to spawn store thread: {}", err);
+    }
+}
+
+// Drops the least recently used entries until the cache fits its size limit again.
+fn evict_to_size_limit() {
+    let Ok(_guard) = EVICTION_LOCK.lock() else {
+        return;
+    };
+    let mut entries: Vec<(PathBuf, u64, SystemTime)> = Vec::new();
+    for kind in [CacheKind::Texture, CacheKind::Pcm] {
+        let Ok(dir) = fs::read_dir(Path::new(CACHE_DIR).join(kind.dir_name())) else {
+            continue;
+        };
+        for entry in dir.flatten() {
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            // Entries have no extension, anything else is a store still in progress.
+            if !metadata.is_file() || entry.path().extension().is_some() {
+                continue;
+            }
+            let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+            entries.push((entry.path(), metadata.len(), modified));
+        }
+    }
+    let mut total: u64 = entries.iter().map(|(_, len, _)| len).sum();
+    if total <= CACHE_SIZE_LIMIT_BYTES {
+        return;
+    }
+    entries.sort_by_key(|(_, _, modified)| *modified);
+    for (path, len, _) in entries {
+        if total <= CACHE_SIZE_LIMIT_BYTES {
+            break;
+        }
+        if fs::remove_file(&path).is_ok() {
+            log!("[cache] evicted {}", path.display());
+            total -= len;
+        }
+    }
+}
@@ -284,7 +284,7 @@ fn render_from_base(base: &RenderedAudio, speed: f64, fix_pitch: bool) -> Option
     });
 }
 
-fn render_decoded(
+pub fn render_decoded(
     decoded: crate::audio::decode::DecodedAudio,
     target_sr: u32,
     target_channels: usize,
@@ -1,15 +1,18 @@
-use std::time::Instant;
+use std::{sync::Arc, time::Instant};
 
 use symphonia::core::{
     audio::{AudioBufferRef, SampleBuffer, Signal},
     codecs::DecoderOptions,
     errors::Error as SymphoniaError,
-    formats::FormatOptions,
+    formats::{FormatOptions, FormatReader, SeekMode, SeekTo},
     io::MediaSourceStream,
     meta::MetadataOptions,
     probe::Hint,
+    units::Time,
 };
 
+use crate::asset_cache::{self, CacheKind};
+
 pub struct DecodedAudio {
     pub sample_rate: u32,
     pub channels: usize,
@@ -18,6 +21,55 @@ pub struct DecodedAudio {
 }
 
 pub fn decode_audio_from_bytes(bytes: Vec<u8>, hint_ext: Option<&str>) -> Option<DecodedAudio> {
+    if let Some(decoded) = asset_cache::load(CacheKind::Pcm, &bytes).and_then(pcm_from_cache) {
+        return Some(decoded);
+    }
+    let decoded = decode_with_symphonia(&bytes, hint_ext)?;
+    asset_cache::store(CacheKind::Pcm, &bytes, pcm_to_cache(&decoded));
+    Some(decoded)
+}
+
+// Cached as sample rate, channel count (u32 LE) and frame count (u64 LE), followed by the
+// planar f32 LE samples.
+fn pcm_to_cache(decoded: &DecodedAudio) -> Vec<u8> {
+    let frames = decoded.samples.first().map_or(0, |c| c.len());
+    let mut data = Vec::with_capacity(16 + decoded.channels * frames * 4);
+    data.extend_from_slice(&decoded.sample_rate.to_le_bytes());
+    data.extend_from_slice(&(decoded.channels as u32).to_le_bytes());
+    data.extend_from_slice(&(frames as u64).to_le_bytes());
+    for channel in &decoded.samples {
+        for sample in channel {
+            data.extend_from_slice(&sample.to_le_bytes());
+        }
+    }
+    data
+}
+
+fn pcm_from_cache(data: Vec<u8>) -> Option<DecodedAudio> {
+    let sample_rate = u32::from_le_bytes(data.get(0..4)?.try_into().ok()?);
+    let channels = u32::from_le_bytes(data.get(4..8)?.try_into().ok()?) as usize;
+    let frames = u64::from_le_bytes(data.get(8..16)?.try_into().ok()?) as usize;
+    let body = data.get(16..)?;
+    if channels == 0 || frames == 0 || body.len() != channels * frames * 4 {
+        return None;
+    }
+    let samples = body
+        .chunks_exact(frames * 4)
+        .map(|channel| {
+            channel
+                .chunks_exact(4)
+                .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+                .collect()
+        })
+        .collect();
+    Some(DecodedAudio {
+        sample_rate,
+        channels,
+        samples,
+    })
+}
+
+fn decode_with_symphonia(bytes: &[u8], hint_ext: Option<&str>) -> Option<DecodedAudio> {
     let t0 = Instant::now();
     log!(
         "[audio] decode start (bytes={}, hint_ext={:?})",
@@ -35,7 +87,10 @@ pub fn decode_audio_from_bytes(bytes: Vec<u8>, hint_ext: Option<&str>) -> Option
     let probe = symphonia::default::get_probe();
     let probed = match probe.format(
         &hint,
-        MediaSourceStream::new(Box::new(std::io::Cursor::new(bytes.clone())), Default::default()),
+        MediaSourceStream::new(
+            Box::new(std::io::Cursor::new(bytes.to_vec())),
+            Default::default(),
+        ),
         &FormatOptions::default(),
         &MetadataOptions::default(),
     ) {
@@ -53,7 +108,7 @@ pub fn decode_audio_from_bytes(bytes: Vec<u8>, hint_ext: Option<&str>) -> Option
                 match probe.format(
                     &empty_hint,
                     MediaSourceStream::new(
-                        Box::new(std::io::Cursor::new(bytes.clone())),
+                        Box::new(std::io::Cursor::new(bytes.to_vec())),
                         Default::default(),
                     ),
                     &FormatOptions::default(),
@@ -135,42 +190,7 @@ pub fn decode_audio_from_bytes(bytes: Vec<u8>, hint_ext: Option<&str>) -> Option
             }
         };
 
-        match decoded {
-            AudioBufferRef::F32(buf) => {
-                let ch = buf.spec().channels.count();
-                let frames = buf.frames();
-                if ch != channels {
-                    println!(
-                        "[audio] decode_audio_from_bytes: channel count changed mid-stream: {channels} -> {ch}"
-                    );
-                    return None;
-                }
-                for c in 0..channels {
-                    samples[c].extend_from_slice(&buf.chan(c)[..frames]);
-                }
-            }
-            other => {
-                let spec = *other.spec();
-                let frames = other.frames();
-                let mut sb = SampleBuffer::<f32>::new(frames as u64, spec);
-                sb.copy_interleaved_ref(other);
-
-                let ch = spec.channels.count();
-                if ch != channels {
-                    println!(
-                        "[audio] decode_audio_from_bytes: channel count changed mid-stream: {channels} -> {ch}"
-                    );
-                    return None;
-                }
-
-                let interleaved = sb.samples();
-                for frame in 0..frames {
-                    for c in 0..channels {
-                        samples[c].push(interleaved[frame * channels + c]);
-                    }
-                }
-            }
-        }
+        append_planar(decoded, &mut samples)?;
     }
 
     if samples.is_empty() || samples[0].is_empty() {
@@ -193,3 +213,206 @@ pub fn decode_audio_from_bytes(bytes: Vec<u8>, hint_ext: Option<&str>) -> Option
         samples,
     })
 }
+
+fn append_planar(decoded: AudioBufferRef, samples: &mut [Vec<f32>]) -> Option<()> {
+    let channels = samples.len();
+    match decoded {
+        AudioBufferRef::F32(buf) => {
+            let ch = buf.spec().channels.count();
+            let frames = buf.frames();
+            if ch != channels {
+                println!(
+                    "[audio] decode_audio_from_bytes: channel count changed mid-stream: {channels} -> {ch}"
+                );
+                return None;
+            }
+            for c in 0..channels {
+                samples[c].extend_from_slice(&buf.chan(c)[..frames]);
+            }
+        }
+        other => {
+            let spec = *other.spec();
+            let frames = other.frames();
+            let mut sb = SampleBuffer::<f32>::new(frames as u64, spec);
+            sb.copy_interleaved_ref(other);
+
+            let ch = spec.channels.count();
+            if ch != channels {
+                println!(
+                    "[audio] decode_audio_from_bytes: channel count changed mid-stream: {channels} -> {ch}"
+                );
+                return None;
+            }
+
+            let interleaved = sb.samples();
+            for frame in 0..frames {
+                for c in 0..channels {
+                    samples[c].push(interleaved[frame * channels + c]);
+                }
+            }
+        }
+    }
+    Some(())
+}
+
+// Lets several decoders read the same file without copying it.
+struct SharedBytes(Arc<Vec<u8>>);
+
+impl AsRef<[u8]> for SharedBytes {
+    fn as_ref(&self) -> &[u8] {
+        self.0.as_slice()
+    }
+}
+
+fn probe_shared(bytes: &Arc<Vec<u8>>, hint_ext: Option<&str>) -> Option<Box<dyn FormatReader>> {
+    let probe = |hint: &Hint| {
+        symphonia::default::get_probe()
+            .format(
+                hint,
+                MediaSourceStream::new(
+                    Box::new(std::io::Cursor::new(SharedBytes(Arc::clone(bytes)))),
+                    Default::default(),
+                ),
+                &FormatOptions::default(),
+                &MetadataOptions::default(),
+            )
+            .ok()
+    };
+    let mut hint = Hint::new();
+    if let Some(ext) = hint_ext.map(|ext| ext.trim().trim_start_matches('.')) {
+        if !ext.is_empty() {
+            hint.with_extension(ext);
+        }
+    }
+    let probed = probe(&hint).or_else(|| probe(&Hint::new()));
+    match probed {
+        Some(probed) => Some(probed.format),
+        None => {
+            println!("[audio] failed to probe audio format");
+            None
+        }
+    }
+}
+
+// Sample rate and length in frames, without decoding when the container knows its length.
+pub fn probe_audio_length(bytes: &Arc<Vec<u8>>, hint_ext: Option<&str>) -> Option<(u32, u64)> {
+    let mut format = probe_shared(bytes, hint_ext)?;
+    let track = format.default_track()?;
+    let track_id = track.id;
+    let sample_rate = track.codec_params.sample_rate?;
+    if let Some(n_frames) = track.codec_params.n_frames {
+        return Some((sample_rate, n_frames));
+    }
+    let time_base = track.codec_params.time_base;
+    let mut duration = 0u64;
+    loop {
+        match format.next_packet() {
+            Ok(packet) if packet.track_id() == track_id => duration += packet.dur(),
+            Ok(_) => {}
+            Err(_) => break,
+        }
+    }
+    let frames = match time_base {
+        Some(time_base) => {
+            let time = time_base.calc_time(duration);
+            ((time.seconds as f64 + time.frac) * sample_rate as f64).round() as u64
+        }
+        None => duration,
+    };
+    Some((sample_rate, frames))
+}
+
+// Decodes `duration_s` seconds from `start_s` on, so long files can be played a piece at a time.
+pub fn decode_audio_range(
+    bytes: &Arc<Vec<u8>>,
+    hint_ext: Option<&str>,
+    start_s: f64,
+    duration_s: f64,
+) -> Option<DecodedAudio> {
+    let mut format = probe_shared(bytes, hint_ext)?;
+    let track = format.default_track()?;
+    let track_id = track.id;
+    let codec_params = track.codec_params.clone();
+    let sample_rate = codec_params.sample_rate?;
+    let channels = codec_params.channels?.count();
+    let ts_to_frame = |ts: u64| match codec_params.time_base {
+        Some(time_base) => {
+            let time = time_base.calc_time(ts);
+            ((time.seconds as f64 + time.frac) * sample_rate as f64).round() as u64
+        }
+        None => ts,
+    };
+
+    let start_frame = (start_s.max(0.0) * sample_rate as f64).round() as u64;
+    let frames_wanted = (duration_s.max(0.0) * sample_rate as f64).round() as usize;
+    if start_frame > 0 {
+        let seek_to = SeekTo::Time {
+            time: Time::from(start_s),
+            track_id: Some(track_id),
+        };
+        if let Err(e) = format.seek(SeekMode::Accurate, seek_to) {
+            log!("[audio] decode_audio_range: seek to {start_s:.2}s failed: {e}");
+            return None;
+        }
+    }
+
+    let mut decoder = symphonia::default::get_codecs()
+        .make(&codec_params, &DecoderOptions::default())
+        .ok()?;
+    let mut samples: Vec<Vec<f32>> = (0..channels).map(|_| Vec::new()).collect();
+    let mut first_frame: Option<u64> = None;
+    loop {
+        let decoded_frames = samples[0].len() as u64;
+        if first_frame.is_some_and(|f| f + decoded_frames >= start_frame + frames_wanted as u64) {
+            break;
+        }
+        let packet = match format.next_packet() {
+            Ok(p) => p,
+            Err(_) => break,
+        };
+        if packet.track_id() != track_id {
+            continue;
+        }
+        let decoded = match decoder.decode(&packet) {
+            Ok(audio_buf) => audio_buf,
+            Err(SymphoniaError::DecodeError(_)) => continue,
+            Err(_) => break,
+        };
+        first_frame.get_or_insert(ts_to_frame(packet.ts()));
+        append_planar(decoded, &mut samples)?;
+    }
+
+    // Seeking lands on a packet boundary at or before `start_frame`.
+    let skip = start_frame.saturating_sub(first_frame.unwrap_or(start_frame)) as usize;
+    for channel in samples.iter_mut() {
+        channel.drain(..skip.min(channel.len()));
+        channel.truncate(frames_wanted);
+    }
+    if samples[0].is_empty() {
+        return None;
+    }
+    Some(DecodedAudio {
+        sample_rate,
+        channels,
+        samples,
+    })
+}
+
+// Peak below this (-60 dB) can't be heard over the music.
+const BLANK_SAMPLE_PEAK: f32 = 0.001;
+
+// True for 0-byte files and samples that decode to (near) silence. Files that fail to
+// decode are not reported here, osu! may still play them.
+pub fn is_blank_sample(bytes: &[u8], hint_ext: Option<&str>) -> bool {
+    if bytes.is_empty() {
+        return true;
+    }
+    let Some(decoded) = decode_audio_from_bytes(bytes.to_vec(), hint_ext) else {
+        return false;
+    };
+    decoded
+        .samples
+        .iter()
+        .flatten()
+        .all(|sample| sample.abs() < BLANK_SAMPLE_PEAK)
+}
@@ -1,7 +1,7 @@
 use std::{
-    path::Path,
+    path::{Path, PathBuf},
     sync::{
-        Arc,
+        Arc, Mutex, RwLock,
         atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering},
     },
     time::{Duration, Instant},
@@ -17,8 +17,14 @@ use ringbuf::{
 
 use crate::audio::{
     audio_processor::{AudioProcessor, render_music},
+    music_stream::{MusicSource, MusicStream},
+    onsets::{detect_onsets, detect_onsets_in_file},
     sample::RenderedAudio,
+    spectrum::{band_levels, push_mono_samples},
+    time_units::{AudioFrames, PlaybackClock, SongTimeMs, WallClockMs},
+    wav::encode_wav_pcm16,
 };
+use crate::files::write_bytes_to_file;
 
 #[derive(Clone)]
 pub struct AudioEngineConfig {
@@ -31,6 +37,27 @@ pub struct AudioEngineConfig {
     pub fix_pitch: bool,
 }
 
+/// Lowers the music while auditioned samples play.
+#[derive(Clone, Copy)]
+pub struct DuckingConfig {
+    pub enabled: bool,
+    /// Music gain while ducked, 0..1.
+    pub level: f64,
+    pub attack_ms: f64,
+    pub release_ms: f64,
+}
+
+impl Default for DuckingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            level: 0.4,
+            attack_ms: 15.0,
+            release_ms: 250.0,
+        }
+    }
+}
+
 impl Default for AudioEngineConfig {
     fn default() -> Self {
         Self {
@@ -66,6 +93,20 @@ enum Command {
     SetHitsoundsOffset(f64),
     SeekMapTime(f64),
     SetFixPitch(bool),
+    SetStreamMusic(bool),
+    SetDucking(DuckingConfig),
+    Audition {
+        index: usize,
+        volume: f64,
+        position_x: f64,
+    },
+    ExportHitsoundStem(PathBuf),
+    ExportPlaybackMix {
+        path: PathBuf,
+        start_ms: f64,
+        end_ms: f64,
+        metronome: Vec<(f64, bool)>,
+    },
 }
 
 enum HitsoundEditCommand {
@@ -116,7 +157,7 @@ struct Shared {
     // Map time offset (ms). Typically -AudioLeadIn.
     map_time_offset_ms_bits: AtomicU64,
 
-    // Hitsound timing offset (ms). Applied on scheduling.
+    // Hitsound timing offset in real-time ms, independent of playback rate. Applied on scheduling.
     hitsounds_offset_ms_bits: AtomicU64,
 
     // f32 bits.
@@ -131,6 +172,21 @@ struct Shared {
     loading: AtomicBool,
 
     underruns: AtomicU64,
+
+    // Onsets detected in the 1.0x music, in ms from the start of the audio.
+    onsets_ms: RwLock<Arc<Vec<f64>>>,
+    // Bumped for every loaded song, so onsets detected for an older one are dropped.
+    onsets_generation: AtomicU64,
+
+    // Newest mono samples handed to the device, only written when the lock is free.
+    spectrum_history: Mutex<Vec<f32>>,
+
+    // Map time range (start, end) in ms that playback jumps back to the start of.
+    loop_ms: RwLock<Option<(f64, f64)>>,
+
+    // Seeks sent but not handled by the engine thread yet, and where the last one goes.
+    pending_seeks: AtomicU32,
+    pending_seek_ms_bits: AtomicU64,
 }
 
 impl Shared {
@@ -169,6 +225,13 @@ impl Shared {
         return f64::from_bits(self.hitsounds_offset_ms_bits.load(Ordering::Relaxed));
     }
 
+    fn playback_clock(&self) -> PlaybackClock {
+        PlaybackClock {
+            music_start: SongTimeMs(self.map_time_offset_ms()),
+            speed: self.speed(),
+        }
+    }
+
     fn current_map_time_ms(&self) -> f64 {
         if !self.playing.load(Ordering::Acquire) {
             return self.paused_map_time_ms();
@@ -195,17 +258,18 @@ impl Shared {
             }
 
             let origin = self.origin_frame_abs.load(Ordering::Acquire);
-            let speed = f32::from_bits(self.speed_bits.load(Ordering::Acquire)) as f64;
-            let offset = self.map_time_offset_ms();
+            let clock = PlaybackClock {
+                music_start: SongTimeMs(self.map_time_offset_ms()),
+                speed: f32::from_bits(self.speed_bits.load(Ordering::Acquire)) as f64,
+            };
 
             let v2 = self.time_params_version.load(Ordering::Acquire);
             if v1 != v2 {
                 continue;
             }
 
-            let sr = self.sample_rate as f64;
-            let rel = played_interp.saturating_sub(origin) as f64;
-            return (rel / sr) * 1000.0 * speed + offset;
+            let rel = AudioFrames(played_interp.saturating_sub(origin) as i64);
+            return clock.song_time_at_frames(rel, self.sample_rate).0;
         }
     }
 
@@ -322,6 +386,12 @@ impl AudioEngine {
             flush_requested: AtomicBool::new(false),
             loading: AtomicBool::new(false),
             underruns: AtomicU64::new(0),
+            onsets_ms: RwLock::new(Arc::new(Vec::new())),
+            onsets_generation: AtomicU64::new(0),
+            spectrum_history: Mutex::new(Vec::new()),
+            loop_ms: RwLock::new(None),
+            pending_seeks: AtomicU32::new(0),
+            pending_seek_ms_bits: AtomicU64::new(0f64.to_bits()),
         });
 
         std::thread::Builder::new()
@@ -397,26 +467,26 @@ impl AudioEngine {
         log!("[audio] sent remove_all_hitsounds");
     }
 
-    pub fn add_hitsound(&self, map_time_ms: f64, index: usize, volume: f64, position_x: f64) {
-        if !map_time_ms.is_finite() || !position_x.is_finite() {
+    pub fn add_hitsound(&self, time: SongTimeMs, index: usize, volume: f64, position_x: f64) {
+        if !time.0.is_finite() || !position_x.is_finite() {
             return;
         }
         let volume = volume.clamp(0.0, 1.0);
         let _ = self.hitsound_edits_tx.send(HitsoundEditCommand::Add {
-            map_time_ms,
+            map_time_ms: time.0,
             index,
             volume,
             position_x,
         });
     }
 
-    pub fn remove_hitsound(&self, map_time_ms: f64, index: usize, volume: f64, position_x: f64) {
-        if !map_time_ms.is_finite() || !position_x.is_finite() {
+    pub fn remove_hitsound(&self, time: SongTimeMs, index: usize, volume: f64, position_x: f64) {
+        if !time.0.is_finite() || !position_x.is_finite() {
             return;
         }
         let volume = volume.clamp(0.0, 1.0);
         let _ = self.hitsound_edits_tx.send(HitsoundEditCommand::Remove {
-            map_time_ms,
+            map_time_ms: time.0,
             index,
             volume,
             position_x,
@@ -467,20 +537,21 @@ impl AudioEngine {
         log!("[audio] sent set_volume to {:.3}", volume);
     }
 
-    pub fn set_map_time_offset_ms(&self, offset_ms: f64) {
-        if !offset_ms.is_finite() {
+    // The song time the first frame of the music plays at.
+    pub fn set_music_start(&self, music_start: SongTimeMs) {
+        if !music_start.0.is_finite() {
             return;
         }
-        let _ = self.tx.send(Command::SetMapTimeOffset(offset_ms));
-        log!("[audio] sent set_map_time_offset_ms to {:.2}ms", offset_ms);
+        let _ = self.tx.send(Command::SetMapTimeOffset(music_start.0));
+        log!("[audio] sent set_music_start to {:.2}ms", music_start.0);
     }
 
-    pub fn set_hitsounds_offset_ms(&self, offset_ms: f64) {
-        if !offset_ms.is_finite() {
+    pub fn set_hitsounds_offset(&self, offset: WallClockMs) {
+        if !offset.0.is_finite() {
             return;
         }
-        let _ = self.tx.send(Command::SetHitsoundsOffset(offset_ms));
-        log!("[audio] sent set_hitsounds_offset_ms to {:.2}ms", offset_ms);
+        let _ = self.tx.send(Command::SetHitsoundsOffset(offset.0));
+        log!("[audio] sent set_hitsounds_offset to {:.2}ms", offset.0);
     }
 
     pub fn set_hitsound_volume(&self, volume: f64) {
@@ -492,6 +563,56 @@ impl AudioEngine {
         log!("[audio] sent set_hitsound_volume to {:.3}", volume);
     }
 
+    pub fn set_ducking(&self, ducking: DuckingConfig) {
+        let _ = self.tx.send(Command::SetDucking(ducking));
+        log!(
+            "[audio] sent set_ducking enabled={} level={:.2}",
+            ducking.enabled,
+            ducking.level
+        );
+    }
+
+    // Plays a hitsound sample right away, also while paused.
+    pub fn audition_hitsound(&self, index: usize, volume: f64, position_x: f64) {
+        if !volume.is_finite() || !position_x.is_finite() {
+            return;
+        }
+        let _ = self.tx.send(Command::Audition {
+            index,
+            volume,
+            position_x,
+        });
+        log!("[audio] sent audition index={}", index);
+    }
+
+    // Renders the scheduled hitsounds without music into a WAV at `path`, in the background.
+    pub fn export_hitsound_stem(&self, path: PathBuf) {
+        let _ = self.tx.send(Command::ExportHitsoundStem(path));
+        log!("[audio] sent export_hitsound_stem");
+    }
+
+    // Renders what playback sounds like from `start` to `end` at the current rate and
+    // volumes into a WAV at `path`, in the background. `metronome` holds the beats to click
+    // on and whether each starts a measure.
+    pub fn export_playback_mix(
+        &self,
+        path: PathBuf,
+        start: SongTimeMs,
+        end: SongTimeMs,
+        metronome: Vec<(SongTimeMs, bool)>,
+    ) {
+        if !start.0.is_finite() || !end.0.is_finite() || end <= start {
+            return;
+        }
+        let _ = self.tx.send(Command::ExportPlaybackMix {
+            path,
+            start_ms: start.0,
+            end_ms: end.0,
+            metronome: metronome.into_iter().map(|(time, downbeat)| (time.0, downbeat)).collect(),
+        });
+        log!("[audio] sent export_playback_mix");
+    }
+
     pub fn set_spacial_audio(&self, spacial_audio: f64) {
         if !spacial_audio.is_finite() {
             return;
@@ -506,35 +627,89 @@ impl AudioEngine {
         log!("[audio] sent set_fix_pitch={}", fix_pitch);
     }
 
-    pub fn seek_map_time_ms(&self, map_time_ms: f64) {
-        if !map_time_ms.is_finite() {
+    /// Takes effect on the next `load_music`.
+    pub fn set_stream_music(&self, stream_music: bool) {
+        let _ = self.tx.send(Command::SetStreamMusic(stream_music));
+        log!("[audio] sent set_stream_music={}", stream_music);
+    }
+
+    pub fn seek(&self, time: SongTimeMs) {
+        if !time.0.is_finite() {
             return;
         }
-        let _ = self.tx.send(Command::SeekMapTime(map_time_ms));
-        log!("[audio] sent seek_map_time_ms to {:.2}ms", map_time_ms);
+        self.shared
+            .pending_seek_ms_bits
+            .store(time.0.to_bits(), Ordering::Release);
+        self.shared.pending_seeks.fetch_add(1, Ordering::AcqRel);
+        let _ = self.tx.send(Command::SeekMapTime(time.0));
+        log!("[audio] sent seek to {:.2}ms", time.0);
+    }
+
+    // While playing, reaching `end` of the range seeks back to its `start`. None plays on.
+    pub fn set_loop(&self, range: Option<(SongTimeMs, SongTimeMs)>) {
+        let loop_ms = range
+            .map(|(start, end)| (start.0, end.0))
+            .filter(|(start, end)| start.is_finite() && end > start);
+        *self.shared.loop_ms.write().expect("loop lock poisoned") = loop_ms;
+        log!("[audio] set loop to {:?}", loop_ms);
+    }
+
+    pub fn loop_range(&self) -> Option<(SongTimeMs, SongTimeMs)> {
+        let loop_ms = *self.shared.loop_ms.read().expect("loop lock poisoned");
+        loop_ms.map(|(start, end)| (SongTimeMs(start), SongTimeMs(end)))
     }
 
     pub fn is_playing(&self) -> bool {
         self.shared.playing.load(Ordering::Acquire)
     }
 
-    pub fn song_total_ms(&self) -> f64 {
+    // How much song time the music lasts, the end of the timeline counted from 0.
+    pub fn song_total(&self) -> SongTimeMs {
         let frames = self.shared.music_frames_len.load(Ordering::Acquire);
         if frames == 0 {
-            return 0.0;
+            return SongTimeMs(0.0);
         }
-        let sr = self.shared.sample_rate as f64;
-        let speed = self.shared.speed();
-        (frames as f64 / sr) * 1000.0 * speed
+        let length = AudioFrames(frames as i64).to_wall_clock(self.shared.sample_rate);
+        SongTimeMs(self.shared.playback_clock().song_span_ms(length))
     }
 
-    pub fn current_time_ms(&self) -> f64 {
-        self.shared.current_map_time_ms()
+    // A seek that is still on its way counts as done, so stepping from here twice in a row
+    // moves twice.
+    pub fn current_time(&self) -> SongTimeMs {
+        if self.shared.pending_seeks.load(Ordering::Acquire) > 0 {
+            let bits = self.shared.pending_seek_ms_bits.load(Ordering::Acquire);
+            return SongTimeMs(f64::from_bits(bits));
+        }
+        SongTimeMs(self.shared.current_map_time_ms())
     }
 
     pub fn is_loading(&self) -> bool {
         self.shared.is_loading()
     }
+
+    // Detected music onsets between `from` and `to`.
+    pub fn onsets_between(&self, from: SongTimeMs, to: SongTimeMs) -> Vec<SongTimeMs> {
+        let onsets = Arc::clone(&self.shared.onsets_ms.read().expect("onsets lock poisoned"));
+        let offset = self.shared.map_time_offset_ms();
+        let start = onsets.partition_point(|t| t + offset < from.0);
+        onsets[start..]
+            .iter()
+            .map(|t| SongTimeMs(t + offset))
+            .take_while(|t| *t <= to)
+            .collect()
+    }
+
+    // Bass, low mid, high mid and treble levels in 0..1 of the audio being played right now.
+    pub fn spectrum_band_levels(&self) -> [f32; 4] {
+        if !self.is_playing() {
+            return [0.0; 4];
+        }
+        let samples = match self.shared.spectrum_history.lock() {
+            Ok(history) => history.clone(),
+            Err(_) => return [0.0; 4],
+        };
+        band_levels(&samples, self.shared.sample_rate)
+    }
 }
 
 struct Voice {
@@ -559,9 +734,11 @@ struct EngineState {
     music_source_bytes: Option<Vec<u8>>,
     music_hint_ext: Option<String>,
     audio_processor: AudioProcessor,
-    music: Option<RenderedAudio>,
+    music: Option<MusicSource>,
     playing: bool,
     fix_pitch: bool,
+    // Decode music around the playhead instead of keeping the whole song as PCM.
+    stream_music: bool,
 
     hitsound_samples: Vec<Option<RenderedAudio>>,
     hitsound_events: Vec<HitsoundEvent>,
@@ -571,9 +748,35 @@ struct EngineState {
 
     scheduled: Vec<Voice>,
 
+    // Samples played on request rather than from the map. While paused the stream is
+    // started just for these (`previewing`).
+    auditions: Vec<Voice>,
+    previewing: bool,
+    ducking: DuckingConfig,
+    duck_gain: f32,
+
+    // Whether onset detection was started for the current music base.
+    onsets_ready: bool,
+
     cfg: AudioEngineConfig,
 }
 
+// Output frame at which a hitsound at `time` should start playing. Song time maps to output
+// time through `clock`, the hitsound offset is real time and added after.
+fn hitsound_start_frame(
+    origin_abs: u64,
+    time: SongTimeMs,
+    clock: &PlaybackClock,
+    hitsounds_offset: WallClockMs,
+    sr: u32,
+) -> Option<u64> {
+    let rel = (clock.wall_clock_at(time) + hitsounds_offset).max(WallClockMs(0.0));
+    if !rel.0.is_finite() {
+        return None;
+    }
+    Some(origin_abs.saturating_add(rel.to_frames(sr).0.max(0) as u64))
+}
+
 fn ensure_music_base(state: &mut EngineState, sr: u32, channels: usize) -> anyhow::Result<bool> {
     if state.audio_processor.base().is_some() {
         return Ok(true);
@@ -602,14 +805,146 @@ fn ensure_music_base(state: &mut EngineState, sr: u32, channels: usize) -> anyho
     Ok(true)
 }
 
+fn refresh_onsets(shared: &Arc<Shared>, state: &mut EngineState) {
+    if state.onsets_ready {
+        return;
+    }
+    let Some(base) = state.audio_processor.base() else {
+        return;
+    };
+    let base = base.clone();
+    spawn_onset_detection(shared, move || detect_onsets(&base));
+    state.onsets_ready = true;
+}
+
+// Detection takes a while on long songs, so it runs on its own thread instead of holding up
+// playback. A result that arrives after another song was loaded is dropped.
+fn spawn_onset_detection<F>(shared: &Arc<Shared>, detect: F)
+where
+    F: 'static + Send + FnOnce() -> Vec<f64>,
+{
+    let generation = shared.onsets_generation.load(Ordering::Acquire);
+    let shared = Arc::clone(shared);
+    let spawned = std::thread::Builder::new()
+        .name("onsets".to_string())
+        .spawn(move || {
+            let onsets = detect();
+            let mut current = shared.onsets_ms.write().expect("onsets lock poisoned");
+            if shared.onsets_generation.load(Ordering::Acquire) == generation {
+                log!("[audio] detected {} onsets", onsets.len());
+                *current = Arc::new(onsets);
+            }
+        });
+    if let Err(err) = spawned {
+        log!("[audio] failed to start onset detection: {err}");
+    }
+}
+
+fn load_rendered_music(
+    shared: &Arc<Shared>,
+    state: &mut EngineState,
+    bytes: Vec<u8>,
+    hint_ext: Option<String>,
+    sr: u32,
+    channels: usize,
+) {
+    // Try to load cached base (1.0x, original pitch) to avoid decoding when possible.
+    if let Some(cached_base) = state
+        .audio_processor
+        .get_cached_only(1.0, false, sr, channels)
+    {
+        state.audio_processor.set_base(cached_base);
+    }
+
+    let speed = shared.speed();
+    let mut rendered = state
+        .audio_processor
+        .get_or_render(speed, state.fix_pitch, sr, channels);
+
+    if rendered.is_none() {
+        // Always render a 1.0x reference (original pitch) and derive playback-rate
+        // variants from it. This avoids accumulating artifacts and improves pitch stability.
+        match render_music(
+            bytes,
+            sr,
+            channels,
+            1.0,
+            false,
+            "song".to_string(),
+            hint_ext.as_deref(),
+        ) {
+            Some(base) => {
+                state.audio_processor.set_base(base);
+                rendered =
+                    state
+                        .audio_processor
+                        .get_or_render(speed, state.fix_pitch, sr, channels);
+            }
+            None => {
+                println!("Failed to decode/render music");
+                shared.music_frames_len.store(0, Ordering::Release);
+                state.audio_processor.clear();
+                state.music = None;
+            }
+        }
+    }
+
+    match rendered {
+        Some(rendered) => {
+            shared
+                .music_frames_len
+                .store(rendered.frames_len() as u64, Ordering::Release);
+            state.music = Some(MusicSource::Rendered(rendered));
+        }
+        None => {
+            shared.music_frames_len.store(0, Ordering::Release);
+            state.music = None;
+        }
+    }
+}
+
+// Streaming keeps only the encoded file around, so onsets are detected straight from it.
+fn load_streamed_music(
+    shared: &Arc<Shared>,
+    state: &mut EngineState,
+    bytes: Vec<u8>,
+    hint_ext: Option<String>,
+    sr: u32,
+    channels: usize,
+) {
+    let stream = MusicStream::new(
+        bytes.clone(),
+        hint_ext.clone(),
+        sr,
+        channels,
+        shared.speed(),
+        state.fix_pitch,
+    );
+    let Some(stream) = stream else {
+        println!("Failed to open music for streaming");
+        shared.music_frames_len.store(0, Ordering::Release);
+        state.music = None;
+        return;
+    };
+    shared
+        .music_frames_len
+        .store(stream.frames_len() as u64, Ordering::Release);
+    state.music = Some(MusicSource::Streamed(stream));
+
+    spawn_onset_detection(shared, move || {
+        detect_onsets_in_file(bytes, hint_ext.as_deref()).unwrap_or_default()
+    });
+    state.onsets_ready = true;
+}
+
 fn prime_music_queue(
     shared: &Arc<Shared>,
-    state: &EngineState,
+    state: &mut EngineState,
     prod: &mut impl Producer<Item = f32>,
     channels: usize,
     target_frames: usize,
 ) {
-    let Some(music) = state.music.as_ref() else {
+    let Some(music) = state.music.as_mut() else {
         return;
     };
 
@@ -633,9 +968,12 @@ fn prime_music_queue(
             break;
         }
 
-        let start = rel * channels;
-        let end = (rel + frames_to_gen) * channels;
-        let slice = &music.data[start..end];
+        let mut block = vec![0.0f32; frames_to_gen * channels];
+        let frames = music.read(rel, &mut block);
+        if frames == 0 {
+            break;
+        }
+        let slice = &block[..frames * channels];
 
         let pushed = prod.push_slice(slice);
         if pushed < slice.len() {
@@ -678,11 +1016,17 @@ fn audio_thread_main(
         music: None,
         playing: false,
         fix_pitch: cfg.fix_pitch,
+        stream_music: false,
         hitsound_samples: Vec::new(),
         hitsound_events: Vec::new(),
         last_hitsound_map_time_ms: None,
         voices: Vec::new(),
         scheduled: Vec::new(),
+        auditions: Vec::new(),
+        previewing: false,
+        ducking: DuckingConfig::default(),
+        duck_gain: 1.0,
+        onsets_ready: false,
         cfg,
     };
 
@@ -707,64 +1051,23 @@ fn audio_thread_main(
                 Command::LoadMusic { bytes, map_dir_name, hint_ext } => {
                     // New map: avoid reusing base from previous song.
                     state.audio_processor.clear();
+                    state.onsets_ready = false;
+                    shared.onsets_generation.fetch_add(1, Ordering::AcqRel);
+                    *shared.loop_ms.write().expect("loop lock poisoned") = None;
+                    *shared.onsets_ms.write().expect("onsets lock poisoned") = Arc::new(Vec::new());
                     state.music_source_bytes = Some(bytes.clone());
                     state.music_hint_ext = hint_ext.clone();
                     let cache_dir = Path::new("saves").join(&map_dir_name).join("cache");
                     state.audio_processor.set_cache_dir(Some(cache_dir));
 
-                    // Try to load cached base (1.0x, original pitch) to avoid decoding when possible.
-                    if let Some(cached_base) = state
-                        .audio_processor
-                        .get_cached_only(1.0, false, sr, channels)
-                    {
-                        state.audio_processor.set_base(cached_base);
-                    }
-
-                    let speed = shared.speed();
-                    let mut rendered =
-                        state
-                            .audio_processor
-                            .get_or_render(speed, state.fix_pitch, sr, channels);
-
-                    if rendered.is_none() {
-                        // Always render a 1.0x reference (original pitch) and derive playback-rate
-                        // variants from it. This avoids accumulating artifacts and improves pitch stability.
-                        match render_music(
-                            bytes,
-                            sr,
-                            channels,
-                            1.0,
-                            false,
-                            "song".to_string(),
-                            hint_ext.as_deref(),
-                        ) {
-                            Some(base) => {
-                                state.audio_processor.set_base(base);
-                                rendered = state
-                                    .audio_processor
-                                    .get_or_render(speed, state.fix_pitch, sr, channels);
-                            }
-                            None => {
-                                println!("Failed to decode/render music");
-                                shared.music_frames_len.store(0, Ordering::Release);
-                                state.audio_processor.clear();
-                                state.music = None;
-                            }
-                        }
+                    if state.stream_music {
+                        state.music_source_bytes = None;
+                        load_streamed_music(&shared, &mut state, bytes, hint_ext, sr, channels);
+                    } else {
+                        load_rendered_music(&shared, &mut state, bytes, hint_ext, sr, channels);
                     }
 
-                    match rendered {
-                        Some(rendered) => {
-                            shared
-                                .music_frames_len
-                                .store(rendered.frames_len() as u64, Ordering::Release);
-                            state.music = Some(rendered);
-                        }
-                        None => {
-                            shared.music_frames_len.store(0, Ordering::Release);
-                            state.music = None;
-                        }
-                    }
+                    refresh_onsets(&shared, &mut state);
 
                     // Keep origin at current absolute frame.
                     let now = shared.played_frames_abs.load(Ordering::Acquire);
@@ -820,13 +1123,18 @@ fn audio_thread_main(
                     state.last_hitsound_map_time_ms = None;
                 }
                 Command::Play => {
+                    if state.previewing {
+                        // Queued preview frames would otherwise count as played music.
+                        state.previewing = false;
+                        shared.flush_requested.store(true, Ordering::Release);
+                    }
                     state.playing = true;
                     shared.playing.store(true, Ordering::Release);
 
                     // Prime the queue before starting the stream callback to avoid an immediate
                     // underrun (especially when fix_pitch rendering was slow).
                     let target_frames = ((sr as u64) * (state.cfg.queue_ms as u64) / 1000) as usize;
-                    prime_music_queue(&shared, &state, &mut prod, channels, target_frames);
+                    prime_music_queue(&shared, &mut state, &mut prod, channels, target_frames);
 
                     if let Err(err) = stream.play() {
                         log!("Failed to play audio stream: {err:?}");
@@ -841,6 +1149,8 @@ fn audio_thread_main(
                         .paused_map_time_ms_bits
                         .store(t_ms.to_bits(), Ordering::Release);
                     state.playing = false;
+                    state.previewing = false;
+                    state.auditions.clear();
                     shared.playing.store(false, Ordering::Release);
                     shared.flush_requested.store(true, Ordering::Release);
                     if let Err(err) = stream.pause() {
@@ -850,6 +1160,8 @@ fn audio_thread_main(
                 }
                 Command::Stop => {
                     state.playing = false;
+                    state.previewing = false;
+                    state.auditions.clear();
                     shared.playing.store(false, Ordering::Release);
                     shared
                         .paused_map_time_ms_bits
@@ -873,11 +1185,10 @@ fn audio_thread_main(
                     // so changing speed doesn't jump forward.
                     let played_abs = shared.played_frames_abs.load(Ordering::Acquire);
                     let origin_abs = shared.origin_frame_abs.load(Ordering::Acquire);
-                    let offset_ms = shared.map_time_offset_ms();
-                    let speed_old = shared.speed();
-                    let sr_f = sr as f64;
-                    let rel_frames = played_abs.saturating_sub(origin_abs) as f64;
-                    let t_old_ms = (rel_frames / sr_f) * 1000.0 * speed_old + offset_ms;
+                    let old_clock = shared.playback_clock();
+                    let rel_frames = AudioFrames(played_abs.saturating_sub(origin_abs) as i64);
+                    let t_old = old_clock.song_time_at_frames(rel_frames, sr);
+                    let t_old_ms = t_old.0;
                     shared
                         .paused_map_time_ms_bits
                         .store(t_old_ms.to_bits(), Ordering::Release);
@@ -902,10 +1213,14 @@ fn audio_thread_main(
                     // Update speed + origin together to keep map time continuous.
                     shared.time_params_version.fetch_add(1, Ordering::AcqRel);
 
-                    // origin_new = played_abs - ((t_old - offset)/speed_new) * sr
-                    let rel_new_frames_f = ((t_old_ms - offset_ms) / (1000.0 * new_speed)) * sr_f;
-                    let rel_new_frames = if rel_new_frames_f.is_finite() {
-                        rel_new_frames_f.round().max(0.0) as u64
+                    // origin_new = played_abs - frames of t_old at the new speed
+                    let new_clock = PlaybackClock {
+                        speed: new_speed,
+                        ..old_clock
+                    };
+                    let rel_new_wall_clock = new_clock.wall_clock_at(t_old);
+                    let rel_new_frames = if rel_new_wall_clock.0.is_finite() {
+                        rel_new_wall_clock.to_frames(sr).0.max(0) as u64
                     } else {
                         0
                     };
@@ -930,9 +1245,16 @@ fn audio_thread_main(
 
                     shared.time_params_version.fetch_add(1, Ordering::Release);
 
+                    if let Some(MusicSource::Streamed(stream)) = state.music.as_mut() {
+                        stream.set_rate(new_speed, state.fix_pitch);
+                        shared
+                            .music_frames_len
+                            .store(stream.frames_len() as u64, Ordering::Release);
+                    }
                     // Derive from 1.0x base when available; fallback to decoding otherwise.
                     match ensure_music_base(&mut state, sr, channels) {
                         Ok(true) => {
+                            refresh_onsets(&shared, &mut state);
                             match state
                                 .audio_processor
                                 .get_or_render(new_speed, state.fix_pitch, sr, channels)
@@ -941,7 +1263,7 @@ fn audio_thread_main(
                                     shared
                                         .music_frames_len
                                         .store(rendered.frames_len() as u64, Ordering::Release);
-                                    state.music = Some(rendered);
+                                    state.music = Some(MusicSource::Rendered(rendered));
                                 }
                                 None => {
                                     shared.music_frames_len.store(0, Ordering::Release);
@@ -969,7 +1291,7 @@ fn audio_thread_main(
 
                         let target_frames =
                             ((sr as u64) * (state.cfg.queue_ms as u64) / 1000) as usize;
-                        prime_music_queue(&shared, &state, &mut prod, channels, target_frames);
+                        prime_music_queue(&shared, &mut state, &mut prod, channels, target_frames);
 
                         if let Err(err) = stream.play() {
                             log!("Failed to play audio stream: {err:?}");
@@ -1033,8 +1355,15 @@ fn audio_thread_main(
                     state.fix_pitch = fix_pitch;
 
                     let speed = shared.speed();
+                    if let Some(MusicSource::Streamed(stream)) = state.music.as_mut() {
+                        stream.set_rate(speed, state.fix_pitch);
+                        shared
+                            .music_frames_len
+                            .store(stream.frames_len() as u64, Ordering::Release);
+                    }
                     match ensure_music_base(&mut state, sr, channels) {
                         Ok(true) => {
+                            refresh_onsets(&shared, &mut state);
                             match state
                                 .audio_processor
                                 .get_or_render(speed, state.fix_pitch, sr, channels)
@@ -1043,7 +1372,7 @@ fn audio_thread_main(
                                     shared
                                         .music_frames_len
                                         .store(rendered.frames_len() as u64, Ordering::Release);
-                                    state.music = Some(rendered);
+                                    state.music = Some(MusicSource::Rendered(rendered));
                                 }
                                 None => {
                                     shared.music_frames_len.store(0, Ordering::Release);
@@ -1063,51 +1392,136 @@ fn audio_thread_main(
                     shared.flush_requested.store(true, Ordering::Release);
                     shared.loading.store(false, Ordering::Release);
                 }
-                Command::SeekMapTime(map_time_ms) => {
-                    let Some(music) = state.music.as_ref() else {
+                Command::SetStreamMusic(stream_music) => {
+                    state.stream_music = stream_music;
+                }
+                Command::SetDucking(ducking) => {
+                    state.ducking = ducking;
+                }
+                Command::Audition {
+                    index,
+                    volume,
+                    position_x,
+                } => {
+                    let sample = state.hitsound_samples.get(index).and_then(|s| s.clone());
+                    let Some(sample) = sample else {
                         continue;
                     };
-
-                    // Convert desired beatmap map time -> relative music time (ms).
-                    // current_map_time_ms = rel_ms * speed + offset
-                    // => rel_ms = (map_time_ms - offset) / speed
-                    let offset_ms = shared.map_time_offset_ms();
-                    let speed = shared.speed();
-                    if !speed.is_finite() || speed <= 1e-9 {
-                        continue;
+                    state.auditions.push(Voice {
+                        audio: sample,
+                        frame_pos: 0,
+                        gain: volume.clamp(0.0, 1.0),
+                        position_x: position_x.clamp(0.0, 1.0),
+                        start_abs_frame: 0,
+                        sample_index: index,
+                        map_time_ms: 0.0,
+                    });
+                    if !state.playing && !state.previewing {
+                        // The map clock only advances while `playing`, so running the stream
+                        // for a preview doesn't move the paused map time.
+                        state.previewing = true;
+                        if let Err(err) = stream.play() {
+                            log!("Failed to play audio stream: {err:?}");
+                            state.previewing = false;
+                            state.auditions.clear();
+                        }
                     }
-
-                    let rel_ms = ((map_time_ms - offset_ms) / speed).max(0.0);
-                    let mut rel_frames = ((rel_ms / 1000.0) * (sr as f64)).round() as i64;
-                    rel_frames = rel_frames.clamp(0, music.frames_len() as i64);
-
-                    let origin_abs = shared.origin_frame_abs.load(Ordering::Acquire);
-                    let new_played_abs = origin_abs.saturating_add(rel_frames as u64);
-                    shared
-                        .played_frames_abs
-                        .store(new_played_abs, Ordering::Release);
-
-                    if !state.playing {
-                        shared
-                            .paused_map_time_ms_bits
-                            .store(map_time_ms.to_bits(), Ordering::Release);
+                }
+                Command::ExportHitsoundStem(path) => {
+                    let samples = state.hitsound_samples.clone();
+                    let events = state.hitsound_events.clone();
+                    // The music is rendered at the playback rate, the stem is always 1.0x.
+                    let song_frames = state
+                        .music
+                        .as_ref()
+                        .map(|music| (music.frames_len() as f64 * shared.speed()).round() as usize)
+                        .unwrap_or(0);
+                    let spacial_audio = shared.spacial_audio();
+                    let spawned = std::thread::Builder::new()
+                        .name("hitsound-stem".to_string())
+                        .spawn(move || {
+                            let data = render_hitsound_stem(
+                                &samples,
+                                &events,
+                                sr,
+                                channels,
+                                song_frames,
+                                spacial_audio,
+                            );
+                            let bytes = encode_wav_pcm16(sr, channels, &data);
+                            match write_bytes_to_file(&path, &bytes) {
+                                Ok(()) => println!("Wrote {}", path.display()),
+                                Err(err) => println!("Failed to write {}: {}", path.display(), err),
+                            }
+                        });
+                    if let Err(err) = spawned {
+                        println!("Failed to start hitsound stem export: {err}");
                     }
-
-                    state.voices.clear();
-                    state.scheduled.clear();
-                    state.last_hitsound_map_time_ms = None;
-                    shared.flush_requested.store(true, Ordering::Release);
-                    log!(
-                        "[audio] cmd seek map_time_ms={:.2} => rel_ms={:.2} rel_frames={} played_abs={}",
-                        map_time_ms,
-                        rel_ms,
-                        rel_frames,
-                        new_played_abs
-                    );
+                }
+                Command::ExportPlaybackMix {
+                    path,
+                    start_ms,
+                    end_ms,
+                    metronome,
+                } => {
+                    if state.stream_music {
+                        println!("Streamed music can't be exported, turn audio.stream_music off.");
+                    } else {
+                        let music = match state.music.as_ref() {
+                            Some(MusicSource::Rendered(audio)) => Some(audio.clone()),
+                            _ => None,
+                        };
+                        let samples = state.hitsound_samples.clone();
+                        let events = state.hitsound_events.clone();
+                        let clock = shared.playback_clock();
+                        let music_volume = shared.volume();
+                        let hitsound_volume = shared.hitsound_volume();
+                        let spacial_audio = shared.spacial_audio().clamp(0.0, 1.0);
+                        let spawned = std::thread::Builder::new()
+                            .name("playback-mix".to_string())
+                            .spawn(move || {
+                                let to_frame = |map_time_ms: f64| {
+                                    clock.frames_at(SongTimeMs(map_time_ms), sr).0
+                                };
+                                let data = render_playback_mix(
+                                    music.as_ref(),
+                                    &samples,
+                                    &events,
+                                    &metronome,
+                                    to_frame,
+                                    (to_frame(start_ms), to_frame(end_ms)),
+                                    sr,
+                                    channels,
+                                    (music_volume, hitsound_volume, spacial_audio),
+                                );
+                                let bytes = encode_wav_pcm16(sr, channels, &data);
+                                match write_bytes_to_file(&path, &bytes) {
+                                    Ok(()) => println!("Wrote {}", path.display()),
+                                    Err(err) => {
+                                        println!("Failed to write {}: {}", path.display(), err)
+                                    }
+                                }
+                            });
+                        if let Err(err) = spawned {
+                            println!("Failed to start playback mix export: {err}");
+                        }
+                    }
+                }
+                Command::SeekMapTime(map_time_ms) => {
+                    seek_map_time(&shared, &mut state, sr, map_time_ms);
+                    shared.pending_seeks.fetch_sub(1, Ordering::AcqRel);
                 }
             }
         }
 
+        if state.playing {
+            let loop_ms = *shared.loop_ms.read().expect("loop lock poisoned");
+            let time_ms = shared.current_map_time_ms();
+            if let Some((start_ms, _)) = loop_ms.filter(|(_, end_ms)| time_ms >= *end_ms) {
+                seek_map_time(&shared, &mut state, sr, start_ms);
+            }
+        }
+
         // Apply hitsound edits in bounded chunks so control commands stay responsive.
         const MAX_HITSOUND_EDITS_PER_TICK: usize = 256;
         let mut edits_applied = 0usize;
@@ -1178,7 +1592,12 @@ fn audio_thread_main(
         }
 
         if !state.playing {
-            std::thread::sleep(Duration::from_millis(2));
+            if state.previewing {
+                mix_preview_block(&shared, &mut state, &mut prod, &stream, channels, sr);
+                std::thread::sleep(Duration::from_millis(1));
+            } else {
+                std::thread::sleep(Duration::from_millis(2));
+            }
             continue;
         }
 
@@ -1205,27 +1624,24 @@ fn audio_thread_main(
         let origin_abs = shared.origin_frame_abs.load(Ordering::Acquire);
 
         // Mix music (apply music volume only).
-        if let Some(music) = state.music.as_ref() {
+        if let Some(music) = state.music.as_mut() {
             let rel = abs_cursor.saturating_sub(origin_abs) as usize;
-            let available = music.frames_len().saturating_sub(rel);
-            let frames = frames_to_gen.min(available);
-            if frames > 0 {
-                let start = rel * channels;
-                let end = (rel + frames) * channels;
-                let music_volume = shared.volume();
-                if (music_volume - 1.0).abs() > f32::EPSILON {
-                    for (dst, src) in out[..(frames * channels)]
-                        .iter_mut()
-                        .zip(music.data[start..end].iter())
-                    {
-                        *dst = *src * music_volume;
-                    }
-                } else {
-                    out[..(frames * channels)].copy_from_slice(&music.data[start..end]);
+            let frames = music.read(rel, &mut out);
+            let music_volume = shared.volume();
+            if frames > 0 && (music_volume - 1.0).abs() > f32::EPSILON {
+                for sample in out[..(frames * channels)].iter_mut() {
+                    *sample *= music_volume;
                 }
             }
         }
 
+        let duck_target = if state.ducking.enabled && !state.auditions.is_empty() {
+            state.ducking.level as f32
+        } else {
+            1.0
+        };
+        apply_ducking(&mut out, channels, &mut state.duck_gain, duck_target, &state.ducking, sr);
+
         // Activate any scheduled hitsounds whose start is within (or before) this block's end.
         let abs_end = abs_cursor + frames_to_gen as u64;
         let mut i = 0;
@@ -1238,37 +1654,41 @@ fn audio_thread_main(
         }
 
         // Schedule hitsounds for this block based on map time window.
-        let speed = shared.speed();
-        if speed.is_finite() && speed > 1e-9 {
-            let sr_f = sr as f64;
-            let offset_ms = shared.map_time_offset_ms();
-            let hitsounds_offset_ms = shared.hitsounds_offset_ms();
-            let rel_start = abs_cursor.saturating_sub(origin_abs) as f64;
-            let rel_end = rel_start + frames_to_gen as f64;
-            let map_start = (rel_start / sr_f) * 1000.0 * speed + offset_ms;
-            let map_end = (rel_end / sr_f) * 1000.0 * speed + offset_ms;
+        let clock = shared.playback_clock();
+        if clock.speed.is_finite() && clock.speed > 1e-9 {
+            let hitsounds_offset = WallClockMs(shared.hitsounds_offset_ms());
+            let rel_start = AudioFrames(abs_cursor.saturating_sub(origin_abs) as i64);
+            let rel_end = AudioFrames(rel_start.0 + frames_to_gen as i64);
+            let map_start = clock.song_time_at_frames(rel_start, sr).0;
+            let map_end = clock.song_time_at_frames(rel_end, sr).0;
 
             let mut last_end = state.last_hitsound_map_time_ms.unwrap_or(map_start - 1e-3);
             if map_start + 1.0 < last_end || (map_start - last_end).abs() > 200.0 {
                 last_end = map_start - 1e-3;
             }
 
+            // Event times stay in song time. The hitsound offset is output latency, so it
+            // is real time and only converted into song time with the rate at enqueue time.
+            let hitsounds_offset_song_ms = clock.song_span_ms(hitsounds_offset);
+
             if map_end >= map_start {
                 for ev in &state.hitsound_events {
-                    let ev_time_ms = ev.map_time_ms + hitsounds_offset_ms;
+                    let ev_time_ms = ev.map_time_ms + hitsounds_offset_song_ms;
                     if ev_time_ms > last_end && ev_time_ms <= map_end + 1e-6 {
                         let sample = state.hitsound_samples.get(ev.index).and_then(|s| s.clone());
                         let Some(sample) = sample else {
                             continue;
                         };
 
-                        let rel_ms = ((ev_time_ms - offset_ms) / speed).max(0.0);
-                        let rel_frames_f = (rel_ms / 1000.0) * sr_f;
-                        if !rel_frames_f.is_finite() {
+                        let Some(start_abs) = hitsound_start_frame(
+                            origin_abs,
+                            SongTimeMs(ev.map_time_ms),
+                            &clock,
+                            hitsounds_offset,
+                            sr,
+                        ) else {
                             continue;
-                        }
-                        let rel_frames = rel_frames_f.round().max(0.0) as u64;
-                        let start_abs = origin_abs.saturating_add(rel_frames);
+                        };
 
                         state.voices.push(Voice {
                             audio: sample,
@@ -1301,64 +1721,329 @@ fn audio_thread_main(
             } else {
                 0
             };
+            mix_voice(
+                &mut out,
+                voice,
+                start_off_frames,
+                channels,
+                hitsound_volume,
+                spacial_audio,
+            );
+        }
+        state.voices.retain(|v| v.frame_pos < v.audio.frames_len());
+        for voice in &mut state.auditions {
+            mix_voice(&mut out, voice, 0, channels, hitsound_volume, spacial_audio);
+        }
+        state.auditions.retain(|v| v.frame_pos < v.audio.frames_len());
 
-            if start_off_frames >= frames_to_gen {
-                continue;
-            }
+        // Soft clip.
+        for s in &mut out {
+            *s = s.clamp(-1.0, 1.0);
+        }
 
-            let frames_in_block = frames_to_gen - start_off_frames;
-            let available = voice.audio.frames_len().saturating_sub(voice.frame_pos);
-            let frames = frames_in_block.min(available);
-            if frames == 0 {
-                continue;
-            }
+        let pushed = prod.push_slice(&out);
+        if pushed < out.len() {
+            // Should be rare; we sized queue to avoid this.
+            std::thread::sleep(Duration::from_millis(1));
+        }
+    }
+}
 
-            let src_start = voice.frame_pos * channels;
-            let src_end = (voice.frame_pos + frames) * channels;
-            let dst_start = start_off_frames * channels;
+fn seek_map_time(shared: &Arc<Shared>, state: &mut EngineState, sr: u32, map_time_ms: f64) {
+    let Some(music) = state.music.as_ref() else {
+        return;
+    };
 
-            let src = &voice.audio.data[src_start..src_end];
-            let base_gain = (voice.gain as f32) * hitsound_volume;
+    let clock = shared.playback_clock();
+    if !clock.speed.is_finite() || clock.speed <= 1e-9 {
+        return;
+    }
 
-            if channels >= 2 {
-                let x = voice.position_x as f32;
-                let left_factor = ((1.0 - spacial_audio) + spacial_audio * (1.0 - x)).clamp(0.0, 1.0);
-                let right_factor = ((1.0 - spacial_audio) + spacial_audio * x).clamp(0.0, 1.0);
-                let left_gain = base_gain * left_factor;
-                let right_gain = base_gain * right_factor;
+    let rel = clock.wall_clock_at(SongTimeMs(map_time_ms)).max(WallClockMs(0.0));
+    let rel_frames = rel.to_frames(sr).0.clamp(0, music.frames_len() as i64);
 
-                for frame in 0..frames {
-                    let frame_base = frame * channels;
-                    out[dst_start + frame_base] += src[frame_base] * left_gain;
-                    out[dst_start + frame_base + 1] += src[frame_base + 1] * right_gain;
+    let origin_abs = shared.origin_frame_abs.load(Ordering::Acquire);
+    let new_played_abs = origin_abs.saturating_add(rel_frames as u64);
+    shared
+        .played_frames_abs
+        .store(new_played_abs, Ordering::Release);
 
-                    for ch in 2..channels {
-                        out[dst_start + frame_base + ch] += src[frame_base + ch] * base_gain;
-                    }
-                }
-            } else {
-                for j in 0..(frames * channels) {
-                    out[dst_start + j] += src[j] * base_gain;
-                }
+    if !state.playing {
+        shared
+            .paused_map_time_ms_bits
+            .store(map_time_ms.to_bits(), Ordering::Release);
+    }
+
+    state.voices.clear();
+    state.scheduled.clear();
+    state.last_hitsound_map_time_ms = None;
+    shared.flush_requested.store(true, Ordering::Release);
+    log!(
+        "[audio] cmd seek map_time_ms={:.2} => rel_ms={:.2} rel_frames={} played_abs={}",
+        map_time_ms,
+        rel.0,
+        rel_frames,
+        new_played_abs
+    );
+}
+
+// Adds `voice` into `out` starting `start_off_frames` into the block and advances it.
+fn mix_voice(
+    out: &mut [f32],
+    voice: &mut Voice,
+    start_off_frames: usize,
+    channels: usize,
+    hitsound_volume: f32,
+    spacial_audio: f32,
+) {
+    let frames_to_gen = out.len() / channels;
+    if start_off_frames >= frames_to_gen {
+        return;
+    }
+
+    let frames_in_block = frames_to_gen - start_off_frames;
+    let available = voice.audio.frames_len().saturating_sub(voice.frame_pos);
+    let frames = frames_in_block.min(available);
+    if frames == 0 {
+        return;
+    }
+
+    let src_start = voice.frame_pos * channels;
+    let src_end = (voice.frame_pos + frames) * channels;
+    let dst_start = start_off_frames * channels;
+
+    let src = &voice.audio.data[src_start..src_end];
+    let base_gain = (voice.gain as f32) * hitsound_volume;
+
+    if channels >= 2 {
+        let x = voice.position_x as f32;
+        let left_factor = ((1.0 - spacial_audio) + spacial_audio * (1.0 - x)).clamp(0.0, 1.0);
+        let right_factor = ((1.0 - spacial_audio) + spacial_audio * x).clamp(0.0, 1.0);
+        let left_gain = base_gain * left_factor;
+        let right_gain = base_gain * right_factor;
+
+        for frame in 0..frames {
+            let frame_base = frame * channels;
+            out[dst_start + frame_base] += src[frame_base] * left_gain;
+            out[dst_start + frame_base + 1] += src[frame_base + 1] * right_gain;
+
+            for ch in 2..channels {
+                out[dst_start + frame_base + ch] += src[frame_base + ch] * base_gain;
             }
+        }
+    } else {
+        for j in 0..(frames * channels) {
+            out[dst_start + j] += src[j] * base_gain;
+        }
+    }
 
-            voice.frame_pos += frames;
+    voice.frame_pos += frames;
+}
+
+// All hitsounds at 1.0x with frame 0 at the start of the song file, at least `min_frames`
+// long. Audio and hitsound offsets are left out, they only compensate for output latency.
+fn render_hitsound_stem(
+    samples: &[Option<RenderedAudio>],
+    events: &[HitsoundEvent],
+    sr: u32,
+    channels: usize,
+    min_frames: usize,
+    spacial_audio: f32,
+) -> Vec<f32> {
+    let start_frame = |event: &HitsoundEvent| {
+        ((event.map_time_ms / 1000.0) * sr as f64).round().max(0.0) as usize
+    };
+    let mut total_frames = min_frames;
+    for event in events {
+        if let Some(Some(sample)) = samples.get(event.index) {
+            total_frames = total_frames.max(start_frame(event) + sample.frames_len());
         }
-        state.voices.retain(|v| v.frame_pos < v.audio.frames_len());
+    }
 
-        // Soft clip.
-        for s in &mut out {
-            *s = s.clamp(-1.0, 1.0);
+    let mut out = vec![0.0f32; total_frames * channels];
+    for event in events {
+        let Some(Some(sample)) = samples.get(event.index) else {
+            continue;
+        };
+        let mut voice = Voice {
+            audio: sample.clone(),
+            frame_pos: 0,
+            gain: event.volume,
+            position_x: event.position_x,
+            start_abs_frame: 0,
+            sample_index: event.index,
+            map_time_ms: event.map_time_ms,
+        };
+        mix_voice(&mut out, &mut voice, start_frame(event), channels, 1.0, spacial_audio);
+    }
+    out
+}
+
+// Output frames `frames.0..frames.1` of playback, `to_frame` turns map time into output
+// frames. `levels` are the music volume, hitsound volume and spacial audio amount.
+fn render_playback_mix(
+    music: Option<&RenderedAudio>,
+    samples: &[Option<RenderedAudio>],
+    events: &[HitsoundEvent],
+    metronome: &[(f64, bool)],
+    to_frame: impl Fn(f64) -> i64,
+    frames: (i64, i64),
+    sr: u32,
+    channels: usize,
+    levels: (f32, f32, f32),
+) -> Vec<f32> {
+    let (music_volume, hitsound_volume, spacial_audio) = levels;
+    let first = frames.0.max(0);
+    let len = (frames.1 - first).max(0) as usize;
+    let mut out = vec![0.0f32; len * channels];
+
+    if let Some(music) = music {
+        let available = music.frames_len().saturating_sub(first as usize).min(len);
+        let src = &music.data[first as usize * channels..(first as usize + available) * channels];
+        for (dst, src) in out.iter_mut().zip(src) {
+            *dst = *src * music_volume;
         }
+    }
 
-        let pushed = prod.push_slice(&out);
-        if pushed < out.len() {
-            // Should be rare; we sized queue to avoid this.
-            std::thread::sleep(Duration::from_millis(1));
+    let mut mix_at = |audio: &RenderedAudio, map_time_ms: f64, gain: f64, position_x: f64| {
+        let start = to_frame(map_time_ms) - first;
+        if start >= len as i64 || start + (audio.frames_len() as i64) <= 0 {
+            return;
+        }
+        let mut voice = Voice {
+            audio: audio.clone(),
+            frame_pos: (-start).max(0) as usize,
+            gain,
+            position_x,
+            start_abs_frame: 0,
+            sample_index: 0,
+            map_time_ms,
+        };
+        mix_voice(
+            &mut out,
+            &mut voice,
+            start.max(0) as usize,
+            channels,
+            hitsound_volume,
+            spacial_audio,
+        );
+    };
+    for event in events {
+        if let Some(Some(sample)) = samples.get(event.index) {
+            mix_at(sample, event.map_time_ms, event.volume, event.position_x);
+        }
+    }
+    let clicks = [false, true].map(|downbeat| metronome_click(sr, channels, downbeat));
+    for (time_ms, downbeat) in metronome {
+        mix_at(&clicks[*downbeat as usize], *time_ms, 1.0, 0.5);
+    }
+
+    for s in &mut out {
+        *s = s.clamp(-1.0, 1.0);
+    }
+    out
+}
+
+// A short decaying beep, higher on the first beat of a measure.
+fn metronome_click(sr: u32, channels: usize, downbeat: bool) -> RenderedAudio {
+    const CLICK_MS: f64 = 40.0;
+    let freq = if downbeat { 1500.0 } else { 1000.0 };
+    let frames = (CLICK_MS / 1000.0 * sr as f64) as usize;
+    let mut data = Vec::with_capacity(frames * channels);
+    for frame in 0..frames {
+        let t = frame as f64 / sr as f64;
+        let envelope = (-t * 1000.0 / (CLICK_MS / 4.0)).exp();
+        let value = (0.6 * envelope * (std::f64::consts::TAU * freq * t).sin()) as f32;
+        data.extend(std::iter::repeat_n(value, channels));
+    }
+    RenderedAudio {
+        sample_rate: sr,
+        channels,
+        data: Arc::new(data),
+    }
+}
+
+// One-pole envelope towards `target`, stepped per frame so the music never jumps.
+fn apply_ducking(
+    out: &mut [f32],
+    channels: usize,
+    gain: &mut f32,
+    target: f32,
+    ducking: &DuckingConfig,
+    sr: u32,
+) {
+    if target >= 1.0 && *gain >= 0.9999 {
+        *gain = 1.0;
+        return;
+    }
+    let time_ms = if target < *gain {
+        ducking.attack_ms
+    } else {
+        ducking.release_ms
+    };
+    let coef = if time_ms > 0.0 {
+        (1.0 - (-1000.0 / (time_ms * sr as f64)).exp()) as f32
+    } else {
+        1.0
+    };
+    for frame in out.chunks_mut(channels) {
+        *gain += (target - *gain) * coef;
+        for sample in frame {
+            *sample *= *gain;
         }
     }
 }
 
+// Feeds auditions to the stream while the map is paused and stops it once they're done.
+fn mix_preview_block(
+    shared: &Arc<Shared>,
+    state: &mut EngineState,
+    prod: &mut impl Producer<Item = f32>,
+    stream: &cpal::Stream,
+    channels: usize,
+    sr: u32,
+) {
+    // A pending flush would drop whatever we queue now.
+    if shared.flush_requested.load(Ordering::Acquire) {
+        return;
+    }
+    let occupied_frames = prod.occupied_len() / channels;
+    if state.auditions.is_empty() {
+        if occupied_frames == 0 {
+            state.previewing = false;
+            if let Err(err) = stream.pause() {
+                log!("Failed to pause audio stream: {err:?}");
+            }
+        }
+        return;
+    }
+
+    let target_frames = ((sr as u64) * (state.cfg.queue_ms as u64) / 1000) as usize;
+    if occupied_frames >= target_frames {
+        return;
+    }
+    let frames_to_gen = (target_frames - occupied_frames).min(1024);
+    let mut out = vec![0.0f32; frames_to_gen * channels];
+    // Nothing to duck, but keep the envelope moving so playback resumes smoothly.
+    let duck_target = if state.ducking.enabled {
+        state.ducking.level as f32
+    } else {
+        1.0
+    };
+    apply_ducking(&mut out, channels, &mut state.duck_gain, duck_target, &state.ducking, sr);
+
+    let hitsound_volume = shared.hitsound_volume();
+    let spacial_audio = shared.spacial_audio().clamp(0.0, 1.0);
+    for voice in &mut state.auditions {
+        mix_voice(&mut out, voice, 0, channels, hitsound_volume, spacial_audio);
+    }
+    state.auditions.retain(|v| v.frame_pos < v.audio.frames_len());
+    for s in &mut out {
+        *s = s.clamp(-1.0, 1.0);
+    }
+    prod.push_slice(&out);
+}
+
 fn build_stream(
     device: &cpal::Device,
     config: &cpal::StreamConfig,
@@ -1402,6 +2087,9 @@ fn build_stream(
                     log!("[audio] underrun (queue starved)");
                 }
             }
+            if let Ok(mut history) = shared.spectrum_history.try_lock() {
+                push_mono_samples(&mut history, &scratch[..got], channels);
+            }
 
             match data.sample_format() {
                 cpal::SampleFormat::F32 => {
@@ -1,6 +1,17 @@
 mod audio_processor;
 mod decode;
 mod engine;
+mod music_stream;
+mod onsets;
 mod sample;
+mod spectrum;
+mod time_units;
+mod wav;
+mod waveform;
 
-pub use engine::{AudioEngine, AudioEngineConfig};
+pub use decode::is_blank_sample;
+pub use engine::{AudioEngine, AudioEngineConfig, DuckingConfig};
+pub use onsets::detect_onsets_in_file;
+pub use time_units::{AudioFrames, PlaybackClock, SongTimeMs, WallClockMs};
+pub use wav::encode_wav_pcm16;
+pub use waveform::Waveform;
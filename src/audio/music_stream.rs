@@ -0,0 +1,272 @@
+use std::{
+    collections::VecDeque,
+    sync::{Arc, mpsc},
+};
+
+use crate::audio::{
+    audio_processor::render_decoded,
+    decode::{decode_audio_range, probe_audio_length},
+    sample::RenderedAudio,
+};
+
+// Song seconds per chunk, plus some audio decoded on both sides so resampling and
+// time-stretching have context at the chunk edges.
+const CHUNK_SECONDS: f64 = 4.0;
+const CHUNK_PAD_SECONDS: f64 = 0.1;
+// The chunk being played, the one before it for short seeks back and the prefetched next one.
+const MAX_CHUNKS: usize = 3;
+
+#[derive(Clone, Copy, PartialEq)]
+struct Rate {
+    speed: f64,
+    fix_pitch: bool,
+}
+
+struct ChunkRequest {
+    index: usize,
+    rate: Rate,
+}
+
+struct Chunk {
+    index: usize,
+    rate: Rate,
+    // Exactly the output frames of this chunk, at the rate it was rendered for.
+    audio: RenderedAudio,
+}
+
+// Output frame at which chunk `index` starts when played at `speed`.
+fn chunk_start_frame(index: usize, sample_rate: u32, speed: f64) -> usize {
+    (index as f64 * CHUNK_SECONDS * sample_rate as f64 / speed).round() as usize
+}
+
+fn render_chunk(
+    bytes: &Arc<Vec<u8>>,
+    hint_ext: Option<&str>,
+    request: ChunkRequest,
+    sample_rate: u32,
+    channels: usize,
+) -> Chunk {
+    let Rate { speed, fix_pitch } = request.rate;
+    let start_s = request.index as f64 * CHUNK_SECONDS;
+    let window_start_s = (start_s - CHUNK_PAD_SECONDS).max(0.0);
+    let window_s = start_s - window_start_s + CHUNK_SECONDS + CHUNK_PAD_SECONDS;
+    let frames = chunk_start_frame(request.index + 1, sample_rate, speed)
+        - chunk_start_frame(request.index, sample_rate, speed);
+    let skip = ((start_s - window_start_s) * sample_rate as f64 / speed).round() as usize;
+
+    // A chunk that fails to decode plays as silence instead of stopping playback.
+    let mut data = vec![0.0f32; frames * channels];
+    let rendered = decode_audio_range(bytes, hint_ext, window_start_s, window_s)
+        .and_then(|decoded| render_decoded(decoded, sample_rate, channels, speed, fix_pitch));
+    match rendered {
+        Some(rendered) => {
+            let copied = rendered.frames_len().saturating_sub(skip).min(frames);
+            data[..copied * channels]
+                .copy_from_slice(&rendered.data[skip * channels..(skip + copied) * channels]);
+        }
+        None => log!("[audio] failed to render music chunk {}", request.index),
+    }
+    Chunk {
+        index: request.index,
+        rate: request.rate,
+        audio: RenderedAudio {
+            sample_rate,
+            channels,
+            data: Arc::new(data),
+        },
+    }
+}
+
+// Plays music from the encoded file, decoding a few seconds around the playhead at a time on
+// a worker thread instead of keeping the whole song as PCM.
+pub struct MusicStream {
+    sample_rate: u32,
+    channels: usize,
+    song_seconds: f64,
+    rate: Rate,
+    chunks: VecDeque<Chunk>,
+    pending: Vec<usize>,
+    request_tx: mpsc::Sender<ChunkRequest>,
+    chunk_rx: mpsc::Receiver<Chunk>,
+}
+
+impl MusicStream {
+    pub fn new(
+        bytes: Vec<u8>,
+        hint_ext: Option<String>,
+        sample_rate: u32,
+        channels: usize,
+        speed: f64,
+        fix_pitch: bool,
+    ) -> Option<Self> {
+        let bytes = Arc::new(bytes);
+        let (source_sample_rate, source_frames) = probe_audio_length(&bytes, hint_ext.as_deref())?;
+        if source_sample_rate == 0 || source_frames == 0 {
+            return None;
+        }
+        let (request_tx, request_rx) = mpsc::channel::<ChunkRequest>();
+        let (chunk_tx, chunk_rx) = mpsc::channel::<Chunk>();
+        // Exits once the stream, and with it `request_tx`, is dropped.
+        let spawned = std::thread::Builder::new()
+            .name("music-stream".to_string())
+            .spawn(move || {
+                while let Ok(request) = request_rx.recv() {
+                    let chunk =
+                        render_chunk(&bytes, hint_ext.as_deref(), request, sample_rate, channels);
+                    if chunk_tx.send(chunk).is_err() {
+                        break;
+                    }
+                }
+            });
+        if let Err(err) = spawned {
+            println!("[audio] failed to start music stream: {err}");
+            return None;
+        }
+        log!(
+            "[audio] streaming music ({:.1}s at {}Hz)",
+            source_frames as f64 / source_sample_rate as f64,
+            source_sample_rate
+        );
+        Some(Self {
+            sample_rate,
+            channels,
+            song_seconds: source_frames as f64 / source_sample_rate as f64,
+            rate: Rate { speed, fix_pitch },
+            chunks: VecDeque::new(),
+            pending: Vec::new(),
+            request_tx,
+            chunk_rx,
+        })
+    }
+
+    pub fn frames_len(&self) -> usize {
+        (self.song_seconds * self.sample_rate as f64 / self.rate.speed).round() as usize
+    }
+
+    // Chunks rendered for the old rate are dropped, also those still in flight.
+    pub fn set_rate(&mut self, speed: f64, fix_pitch: bool) {
+        self.rate = Rate { speed, fix_pitch };
+        self.chunks.clear();
+        self.pending.clear();
+    }
+
+    fn chunk_count(&self) -> usize {
+        (self.song_seconds / CHUNK_SECONDS).ceil() as usize
+    }
+
+    fn chunk_index_at(&self, frame: usize) -> usize {
+        let start = |index| chunk_start_frame(index, self.sample_rate, self.rate.speed);
+        let estimate = frame as f64 * self.rate.speed / (self.sample_rate as f64 * CHUNK_SECONDS);
+        let mut index = estimate.floor().max(0.0) as usize;
+        while index > 0 && start(index) > frame {
+            index -= 1;
+        }
+        while start(index + 1) <= frame {
+            index += 1;
+        }
+        index
+    }
+
+    fn accept(&mut self, chunk: Chunk) {
+        if chunk.rate != self.rate {
+            return;
+        }
+        self.pending.retain(|index| *index != chunk.index);
+        self.chunks.push_back(chunk);
+        while self.chunks.len() > MAX_CHUNKS {
+            self.chunks.pop_front();
+        }
+    }
+
+    fn request(&mut self, index: usize) {
+        if index >= self.chunk_count()
+            || self.pending.contains(&index)
+            || self.chunks.iter().any(|c| c.index == index)
+        {
+            return;
+        }
+        let request = ChunkRequest {
+            index,
+            rate: self.rate,
+        };
+        if self.request_tx.send(request).is_ok() {
+            self.pending.push(index);
+        }
+    }
+
+    // Waits for the chunk when it isn't there yet, which is the seek latency of streaming.
+    fn chunk(&mut self, index: usize) -> Option<&Chunk> {
+        if index >= self.chunk_count() {
+            return None;
+        }
+        while let Ok(chunk) = self.chunk_rx.try_recv() {
+            self.accept(chunk);
+        }
+        if !self.chunks.iter().any(|c| c.index == index) {
+            self.request(index);
+            while !self.chunks.iter().any(|c| c.index == index) {
+                let chunk = self.chunk_rx.recv().ok()?;
+                self.accept(chunk);
+            }
+        }
+        self.chunks.iter().find(|c| c.index == index)
+    }
+
+    // Copies interleaved frames from `rel_frame` on into `out`, returns the frames written.
+    pub fn read(&mut self, rel_frame: usize, out: &mut [f32]) -> usize {
+        let channels = self.channels;
+        let frames_wanted = (out.len() / channels).min(self.frames_len().saturating_sub(rel_frame));
+        let mut written = 0;
+        while written < frames_wanted {
+            let frame = rel_frame + written;
+            let index = self.chunk_index_at(frame);
+            let offset = frame - chunk_start_frame(index, self.sample_rate, self.rate.speed);
+            let Some(chunk) = self.chunk(index) else {
+                break;
+            };
+            let frames = chunk
+                .audio
+                .frames_len()
+                .saturating_sub(offset)
+                .min(frames_wanted - written);
+            if frames == 0 {
+                break;
+            }
+            let source = &chunk.audio.data[offset * channels..(offset + frames) * channels];
+            out[written * channels..(written + frames) * channels].copy_from_slice(source);
+            written += frames;
+        }
+        let next = self.chunk_index_at(rel_frame + written) + 1;
+        self.request(next);
+        written
+    }
+}
+
+pub enum MusicSource {
+    Rendered(RenderedAudio),
+    Streamed(MusicStream),
+}
+
+impl MusicSource {
+    pub fn frames_len(&self) -> usize {
+        match self {
+            MusicSource::Rendered(audio) => audio.frames_len(),
+            MusicSource::Streamed(stream) => stream.frames_len(),
+        }
+    }
+
+    pub fn read(&mut self, rel_frame: usize, out: &mut [f32]) -> usize {
+        match self {
+            MusicSource::Rendered(audio) => {
+                let channels = audio.channels;
+                let available = audio.frames_len().saturating_sub(rel_frame);
+                let frames = (out.len() / channels).min(available);
+                out[..frames * channels].copy_from_slice(
+                    &audio.data[rel_frame * channels..(rel_frame + frames) * channels],
+                );
+                frames
+            }
+            MusicSource::Streamed(stream) => stream.read(rel_frame, out),
+        }
+    }
+}
@@ -0,0 +1,91 @@
+use std::sync::Arc;
+
+use crate::audio::{decode::decode_audio_from_bytes, sample::RenderedAudio};
+
+const FRAME_SIZE: usize = 1024;
+const HOP_SIZE: usize = 441;
+const THRESHOLD_RADIUS: usize = 8;
+const MIN_GAP_MS: f64 = 40.0;
+
+// Detects note onsets in `audio` and returns their times in ms from the start of the audio.
+// Uses the rise of log energy in a low and a high (first difference) band, which is cheap
+// and good enough to place snap suggestions; it is not meant as a beat tracker.
+pub fn detect_onsets(audio: &RenderedAudio) -> Vec<f64> {
+    let channels = audio.channels.max(1);
+    let frames_len = audio.frames_len();
+    if frames_len < FRAME_SIZE || audio.sample_rate == 0 {
+        return Vec::new();
+    }
+
+    let mono: Vec<f32> = audio
+        .data
+        .chunks_exact(channels)
+        .map(|frame| frame.iter().sum::<f32>() / channels as f32)
+        .collect();
+
+    let window_count = (frames_len - FRAME_SIZE) / HOP_SIZE + 1;
+    let mut low_energy = Vec::with_capacity(window_count);
+    let mut high_energy = Vec::with_capacity(window_count);
+    for w in 0..window_count {
+        let window = &mono[w * HOP_SIZE..w * HOP_SIZE + FRAME_SIZE];
+        let mut low = 0.0f64;
+        let mut high = 0.0f64;
+        let mut prev = window[0] as f64;
+        for &sample in window.iter() {
+            let sample = sample as f64;
+            low += sample * sample;
+            high += (sample - prev) * (sample - prev);
+            prev = sample;
+        }
+        low_energy.push((1e-9 + low / FRAME_SIZE as f64).ln());
+        high_energy.push((1e-9 + high / FRAME_SIZE as f64).ln());
+    }
+
+    // Onset strength: positive energy rise, with percussive (high band) content weighted up.
+    let mut strength = vec![0.0f64; window_count];
+    for w in 1..window_count {
+        let low_rise = (low_energy[w] - low_energy[w - 1]).max(0.0);
+        let high_rise = (high_energy[w] - high_energy[w - 1]).max(0.0);
+        strength[w] = low_rise + 2.0 * high_rise;
+    }
+
+    let hop_ms = HOP_SIZE as f64 * 1000.0 / audio.sample_rate as f64;
+    let mut onsets: Vec<f64> = Vec::new();
+    for w in 1..window_count.saturating_sub(1) {
+        if strength[w] <= strength[w - 1] || strength[w] < strength[w + 1] {
+            continue;
+        }
+        let from = w.saturating_sub(THRESHOLD_RADIUS);
+        let to = (w + THRESHOLD_RADIUS + 1).min(window_count);
+        let mean = strength[from..to].iter().sum::<f64>() / (to - from) as f64;
+        if strength[w] < 1.5 * mean + 0.1 {
+            continue;
+        }
+        // The attack entered window `w` during its last hop.
+        let time_ms = (w as f64 - 0.5) * hop_ms + FRAME_SIZE as f64 * hop_ms / HOP_SIZE as f64;
+        if onsets.last().is_some_and(|last| time_ms - last < MIN_GAP_MS) {
+            continue;
+        }
+        onsets.push(time_ms);
+    }
+    onsets
+}
+
+// Decodes an audio file that isn't loaded into the engine and detects its onsets.
+pub fn detect_onsets_in_file(bytes: Vec<u8>, hint_ext: Option<&str>) -> Option<Vec<f64>> {
+    let decoded = decode_audio_from_bytes(bytes, hint_ext)?;
+    let channels = decoded.channels.max(1);
+    let frames = decoded.samples.first().map(|c| c.len()).unwrap_or(0);
+    let mut data = Vec::with_capacity(frames * channels);
+    for frame in 0..frames {
+        for channel in 0..channels {
+            let sample = decoded.samples.get(channel).and_then(|c| c.get(frame));
+            data.push(sample.copied().unwrap_or(0.0));
+        }
+    }
+    Some(detect_onsets(&RenderedAudio {
+        sample_rate: decoded.sample_rate,
+        channels,
+        data: Arc::new(data),
+    }))
+}
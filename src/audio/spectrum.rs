@@ -0,0 +1,93 @@
+use std::f32::consts::PI;
+
+// Samples the output callback keeps for the spectrum, about 23 ms at 44.1 kHz.
+pub const SPECTRUM_SIZE: usize = 1024;
+
+// Upper edges in Hz of the bass, low mid, high mid and treble bands.
+const BAND_EDGES_HZ: [f32; 4] = [150.0, 800.0, 4000.0, 16000.0];
+// Band levels are mapped from this many dB below full scale up to 0 dB onto 0..1.
+const FLOOR_DB: f32 = 60.0;
+
+// Keeps the newest mono samples written by the output callback, oldest first.
+pub fn push_mono_samples(history: &mut Vec<f32>, interleaved: &[f32], channels: usize) {
+    let channels = channels.max(1);
+    history.extend(
+        interleaved
+            .chunks_exact(channels)
+            .map(|frame| frame.iter().sum::<f32>() / channels as f32),
+    );
+    if history.len() > SPECTRUM_SIZE {
+        history.drain(..history.len() - SPECTRUM_SIZE);
+    }
+}
+
+// In-place radix-2 FFT, `re` and `im` must have the same power of two length.
+fn fft(re: &mut [f32], im: &mut [f32]) {
+    let n = re.len();
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            re.swap(i, j);
+            im.swap(i, j);
+        }
+    }
+    let mut len = 2;
+    while len <= n {
+        let angle = -2.0 * PI / len as f32;
+        for start in (0..n).step_by(len) {
+            for k in 0..len / 2 {
+                let (sin, cos) = (angle * k as f32).sin_cos();
+                let a = start + k;
+                let b = a + len / 2;
+                let t_re = re[b] * cos - im[b] * sin;
+                let t_im = re[b] * sin + im[b] * cos;
+                re[b] = re[a] - t_re;
+                im[b] = im[a] - t_im;
+                re[a] += t_re;
+                im[a] += t_im;
+            }
+        }
+        len <<= 1;
+    }
+}
+
+// Loudness of the bass, low mid, high mid and treble bands in 0..1. Silence until the history
+// is full.
+pub fn band_levels(samples: &[f32], sample_rate: u32) -> [f32; 4] {
+    if samples.len() < SPECTRUM_SIZE || sample_rate == 0 {
+        return [0.0; 4];
+    }
+    let samples = &samples[samples.len() - SPECTRUM_SIZE..];
+    // Hann window.
+    let mut re: Vec<f32> = samples
+        .iter()
+        .enumerate()
+        .map(|(i, sample)| {
+            sample * (0.5 - 0.5 * (2.0 * PI * i as f32 / (SPECTRUM_SIZE - 1) as f32).cos())
+        })
+        .collect();
+    let mut im = vec![0.0f32; SPECTRUM_SIZE];
+    fft(&mut re, &mut im);
+
+    let bin_hz = sample_rate as f32 / SPECTRUM_SIZE as f32;
+    let mut energy = [0.0f32; 4];
+    for bin in 1..SPECTRUM_SIZE / 2 {
+        let hz = bin as f32 * bin_hz;
+        let Some(band) = BAND_EDGES_HZ.iter().position(|edge| hz < *edge) else {
+            break;
+        };
+        energy[band] += re[bin] * re[bin] + im[bin] * im[bin];
+    }
+    // A full scale sine through the Hann window peaks at (N / 4)^2 in its bin.
+    let full_scale = (SPECTRUM_SIZE as f32 / 4.0).powi(2);
+    energy.map(|band_energy| {
+        let db = 10.0 * (band_energy / full_scale + 1e-12).log10();
+        ((db + FLOOR_DB) / FLOOR_DB).clamp(0.0, 1.0)
+    })
+}
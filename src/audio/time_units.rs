@@ -0,0 +1,99 @@
+use std::ops::Add;
+
+// Time on the beatmap's own timeline, the one objects and timing points are in. It runs
+// faster than the wall clock above 1x playback rate.
+#[derive(Clone, Copy, PartialEq, PartialOrd, Debug, Default)]
+pub struct SongTimeMs(pub f64);
+
+// Real elapsed time since the start of the music as it is played, at the current rate.
+// Output latency and the hitsound offset are in these.
+#[derive(Clone, Copy, PartialEq, PartialOrd, Debug, Default)]
+pub struct WallClockMs(pub f64);
+
+// Output frames at the device sample rate since the start of the music, the unit the
+// engine thread mixes and schedules in. Negative before the music starts.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, Default)]
+pub struct AudioFrames(pub i64);
+
+impl Add for WallClockMs {
+    type Output = WallClockMs;
+
+    fn add(self, rhs: WallClockMs) -> WallClockMs {
+        WallClockMs(self.0 + rhs.0)
+    }
+}
+
+impl WallClockMs {
+    pub fn max(self, other: WallClockMs) -> WallClockMs {
+        WallClockMs(self.0.max(other.0))
+    }
+
+    // Rounded to the nearest frame.
+    pub fn to_frames(self, sample_rate: u32) -> AudioFrames {
+        AudioFrames((self.0 / 1000.0 * sample_rate as f64).round() as i64)
+    }
+}
+
+impl AudioFrames {
+    pub fn to_wall_clock(self, sample_rate: u32) -> WallClockMs {
+        WallClockMs(self.0 as f64 / sample_rate.max(1) as f64 * 1000.0)
+    }
+}
+
+// How song time maps onto the music being played: `music_start` is the song time its first
+// frame plays at, the map time offset (usually -AudioLeadIn), and `speed` the playback rate.
+#[derive(Clone, Copy, Debug)]
+pub struct PlaybackClock {
+    pub music_start: SongTimeMs,
+    pub speed: f64,
+}
+
+impl PlaybackClock {
+    pub fn wall_clock_at(&self, time: SongTimeMs) -> WallClockMs {
+        WallClockMs((time.0 - self.music_start.0) / self.speed)
+    }
+
+    pub fn song_time_at(&self, wall_clock: WallClockMs) -> SongTimeMs {
+        SongTimeMs(self.song_span_ms(wall_clock) + self.music_start.0)
+    }
+
+    // How much song time passes while `wall_clock` does.
+    pub fn song_span_ms(&self, wall_clock: WallClockMs) -> f64 {
+        wall_clock.0 * self.speed
+    }
+
+    pub fn frames_at(&self, time: SongTimeMs, sample_rate: u32) -> AudioFrames {
+        self.wall_clock_at(time).to_frames(sample_rate)
+    }
+
+    pub fn song_time_at_frames(&self, frames: AudioFrames, sample_rate: u32) -> SongTimeMs {
+        self.song_time_at(frames.to_wall_clock(sample_rate))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn playback_clock_round_trips_song_time_through_frames() {
+        let clock = PlaybackClock {
+            music_start: SongTimeMs(-200.0),
+            speed: 1.5,
+        };
+        assert_eq!(clock.wall_clock_at(SongTimeMs(1300.0)), WallClockMs(1000.0));
+        assert_eq!(clock.frames_at(SongTimeMs(1300.0), 48_000), AudioFrames(48_000));
+        assert_eq!(clock.frames_at(SongTimeMs(-500.0), 48_000), AudioFrames(-9_600));
+        assert_eq!(
+            clock.song_time_at_frames(AudioFrames(48_000), 48_000),
+            SongTimeMs(1300.0)
+        );
+
+        // The hitsound offset is real time, so it moves a hitsound by less song time at 0.5x.
+        let slowed = PlaybackClock { speed: 0.5, ..clock };
+        let offset = WallClockMs(20.0);
+        let shifted = slowed.wall_clock_at(SongTimeMs(800.0)) + offset;
+        assert_eq!(slowed.song_time_at(shifted), SongTimeMs(810.0));
+        assert_eq!(slowed.song_span_ms(offset), 10.0);
+    }
+}
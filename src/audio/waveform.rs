@@ -0,0 +1,56 @@
+use super::decode::decode_audio_from_bytes;
+
+// The loudest sample of every millisecond of a song over all channels, which is all drawing
+// its waveform needs.
+pub struct Waveform {
+    peaks: Vec<f32>,
+}
+
+impl Waveform {
+    pub fn from_file(bytes: Vec<u8>, hint_ext: Option<&str>) -> Option<Self> {
+        let decoded = decode_audio_from_bytes(bytes, hint_ext)?;
+        let frames_per_ms = (decoded.sample_rate as f64 / 1000.0).max(1e-9);
+        let frames = decoded.samples.first().map_or(0, |c| c.len());
+        let mut peaks = vec![0.0f32; (frames as f64 / frames_per_ms).ceil() as usize];
+        for channel in &decoded.samples {
+            for (frame, sample) in channel.iter().enumerate() {
+                if let Some(peak) = peaks.get_mut((frame as f64 / frames_per_ms) as usize) {
+                    *peak = peak.max(sample.abs());
+                }
+            }
+        }
+        Some(Waveform { peaks })
+    }
+
+    pub fn len_ms(&self) -> f64 {
+        self.peaks.len() as f64
+    }
+
+    // Loudest sample from `from_ms` to `to_ms`, silence outside the song.
+    pub fn peak_between(&self, from_ms: f64, to_ms: f64) -> f32 {
+        let len = self.peaks.len();
+        if to_ms <= 0.0 || from_ms >= len as f64 {
+            return 0.0;
+        }
+        let from = from_ms.max(0.0) as usize;
+        let to = (to_ms.ceil() as usize).clamp(from + 1, len);
+        self.peaks[from..to].iter().copied().fold(0.0, f32::max)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn peaks_outside_the_song_are_silent() {
+        let waveform = Waveform {
+            peaks: vec![0.1, 0.5, 0.2],
+        };
+        assert_eq!(waveform.peak_between(0.0, 2.0), 0.5);
+        assert_eq!(waveform.peak_between(2.2, 2.4), 0.2);
+        assert_eq!(waveform.peak_between(-5.0, 0.5), 0.1);
+        assert_eq!(waveform.peak_between(-5.0, -1.0), 0.0);
+        assert_eq!(waveform.peak_between(3.0, 10.0), 0.0);
+    }
+}
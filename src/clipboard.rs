@@ -0,0 +1,86 @@
+use serde::{Deserialize, Serialize};
+
+use crate::map_format::objects::HitObject;
+
+// Objects copied to the system clipboard are JSON tagged with this key, so another editor
+// instance can tell them apart from whatever text happens to be on the clipboard.
+#[derive(Serialize, Deserialize)]
+struct ClipboardObjects {
+    osu_editor_objects: Vec<HitObject>,
+}
+
+// Kept open for the editor's lifetime: on X11 and Wayland the copied text is only served
+// while the clipboard that set it is alive.
+pub struct SystemClipboard {
+    clipboard: Option<arboard::Clipboard>,
+}
+
+impl SystemClipboard {
+    pub fn new() -> Self {
+        let clipboard = match arboard::Clipboard::new() {
+            Ok(clipboard) => Some(clipboard),
+            Err(err) => {
+                println!("System clipboard unavailable: {}", err);
+                None
+            }
+        };
+        SystemClipboard { clipboard }
+    }
+
+    pub fn get_text(&mut self) -> Option<String> {
+        match self.clipboard.as_mut()?.get_text() {
+            Ok(text) => Some(text),
+            Err(arboard::Error::ContentNotAvailable) => None,
+            Err(err) => {
+                println!("Failed to read the clipboard: {}", err);
+                None
+            }
+        }
+    }
+
+    pub fn set_text(&mut self, text: String) -> bool {
+        let Some(clipboard) = self.clipboard.as_mut() else {
+            return false;
+        };
+        match clipboard.set_text(text) {
+            Ok(()) => true,
+            Err(err) => {
+                println!("Failed to write the clipboard: {}", err);
+                false
+            }
+        }
+    }
+}
+
+pub fn objects_to_text(objects: &[HitObject]) -> String {
+    let clipboard_objects = ClipboardObjects {
+        osu_editor_objects: objects.to_vec(),
+    };
+    serde_json::to_string(&clipboard_objects).unwrap_or_default()
+}
+
+// None unless the text is objects copied by an editor.
+pub fn objects_from_text(text: &str) -> Option<Vec<HitObject>> {
+    serde_json::from_str::<ClipboardObjects>(text.trim())
+        .ok()
+        .map(|clipboard_objects| clipboard_objects.osu_editor_objects)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_harness::{TEST_MAP, parse_test_map};
+
+    #[test]
+    fn clipboard_objects_round_trip_as_text() {
+        let (_, beatmap) = parse_test_map(TEST_MAP);
+        let objects = &beatmap.objects.objects;
+        let text = objects_to_text(objects);
+        let pasted = objects_from_text(&text).expect("objects do not parse");
+        let times = |objects: &[HitObject]| -> Vec<f64> {
+            objects.iter().map(|object| object.time()).collect()
+        };
+        assert_eq!(times(&pasted), times(objects));
+        assert!(objects_from_text("256,192,1000,5,0,0:0:0:0:").is_none());
+    }
+}
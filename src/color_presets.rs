@@ -0,0 +1,186 @@
+use crate::config::{AppearanceColorsConfig, SelectionColors};
+
+pub const COLOR_PRESET_NAMES: [&str; 4] = ["default", "deuteranopia", "protanopia", "tritanopia"];
+
+// Colors that stay apart under the given color vision deficiency. Only the hues are taken
+// from here, the alphas configured in config.json are kept.
+struct Palette {
+    left_selection: [f64; 3],
+    left_selection_locked: [f64; 3],
+    right_selection: [f64; 3],
+    right_selection_locked: [f64; 3],
+    snap_marker: [f64; 3],
+    timeline_repeat_point: [f64; 3],
+    timeline_end_point: [f64; 3],
+    offscreen_playfield_tint: [f64; 3],
+    offscreen_osu_tint: [f64; 3],
+    fallback_combo_colors: &'static [[f64; 3]],
+    timeline_snap_divisors: &'static [[f64; 3]],
+    timeline_unsnapped_head: [f64; 3],
+}
+
+// Okabe-Ito colors, distinguishable for both red-green deficiencies.
+const ORANGE: [f64; 3] = [230.0, 159.0, 0.0];
+const SKY_BLUE: [f64; 3] = [86.0, 180.0, 233.0];
+const BLUISH_GREEN: [f64; 3] = [0.0, 158.0, 115.0];
+const YELLOW: [f64; 3] = [240.0, 228.0, 66.0];
+const BLUE: [f64; 3] = [0.0, 114.0, 178.0];
+const VERMILLION: [f64; 3] = [213.0, 94.0, 0.0];
+const REDDISH_PURPLE: [f64; 3] = [204.0, 121.0, 167.0];
+const WHITE: [f64; 3] = [255.0, 255.0, 255.0];
+// Blue-yellow deficiencies keep the red-cyan axis.
+const RED: [f64; 3] = [230.0, 50.0, 60.0];
+const CYAN: [f64; 3] = [0.0, 200.0, 210.0];
+const PINK: [f64; 3] = [255.0, 150.0, 190.0];
+const DARK_TEAL: [f64; 3] = [0.0, 110.0, 120.0];
+const LIGHT_GRAY: [f64; 3] = [160.0, 160.0, 160.0];
+const DARK_GRAY: [f64; 3] = [110.0, 110.0, 110.0];
+
+const DEUTERANOPIA: Palette = Palette {
+    left_selection: ORANGE,
+    left_selection_locked: REDDISH_PURPLE,
+    right_selection: SKY_BLUE,
+    right_selection_locked: WHITE,
+    snap_marker: YELLOW,
+    timeline_repeat_point: YELLOW,
+    timeline_end_point: SKY_BLUE,
+    offscreen_playfield_tint: SKY_BLUE,
+    offscreen_osu_tint: ORANGE,
+    fallback_combo_colors: &[ORANGE, SKY_BLUE, YELLOW, BLUE, REDDISH_PURPLE],
+    timeline_snap_divisors: &[
+        WHITE,
+        ORANGE,
+        REDDISH_PURPLE,
+        SKY_BLUE,
+        BLUISH_GREEN,
+        YELLOW,
+        LIGHT_GRAY,
+        DARK_GRAY,
+    ],
+    timeline_unsnapped_head: BLUE,
+};
+
+// Reds look dark to protanopes, so nothing depends on vermillion or purple being bright.
+const PROTANOPIA: Palette = Palette {
+    left_selection: YELLOW,
+    left_selection_locked: WHITE,
+    right_selection: SKY_BLUE,
+    right_selection_locked: BLUISH_GREEN,
+    snap_marker: ORANGE,
+    timeline_repeat_point: YELLOW,
+    timeline_end_point: SKY_BLUE,
+    offscreen_playfield_tint: SKY_BLUE,
+    offscreen_osu_tint: YELLOW,
+    fallback_combo_colors: &[YELLOW, SKY_BLUE, ORANGE, BLUE, WHITE],
+    timeline_snap_divisors: &[
+        WHITE,
+        YELLOW,
+        REDDISH_PURPLE,
+        SKY_BLUE,
+        BLUISH_GREEN,
+        ORANGE,
+        LIGHT_GRAY,
+        DARK_GRAY,
+    ],
+    timeline_unsnapped_head: BLUE,
+};
+
+const TRITANOPIA: Palette = Palette {
+    left_selection: CYAN,
+    left_selection_locked: WHITE,
+    right_selection: RED,
+    right_selection_locked: PINK,
+    snap_marker: VERMILLION,
+    timeline_repeat_point: PINK,
+    timeline_end_point: CYAN,
+    offscreen_playfield_tint: CYAN,
+    offscreen_osu_tint: RED,
+    fallback_combo_colors: &[RED, CYAN, WHITE, PINK, DARK_TEAL],
+    timeline_snap_divisors: &[
+        WHITE,
+        RED,
+        PINK,
+        CYAN,
+        REDDISH_PURPLE,
+        VERMILLION,
+        LIGHT_GRAY,
+        DARK_GRAY,
+    ],
+    timeline_unsnapped_head: DARK_TEAL,
+};
+
+pub enum ColorPreset {
+    Default,
+    Deuteranopia,
+    Protanopia,
+    Tritanopia,
+}
+
+impl ColorPreset {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "default" | "" => Some(ColorPreset::Default),
+            "deuteranopia" => Some(ColorPreset::Deuteranopia),
+            "protanopia" => Some(ColorPreset::Protanopia),
+            "tritanopia" => Some(ColorPreset::Tritanopia),
+            _ => None,
+        }
+    }
+
+    fn palette(&self) -> Option<&'static Palette> {
+        match self {
+            ColorPreset::Default => None,
+            ColorPreset::Deuteranopia => Some(&DEUTERANOPIA),
+            ColorPreset::Protanopia => Some(&PROTANOPIA),
+            ColorPreset::Tritanopia => Some(&TRITANOPIA),
+        }
+    }
+
+    // Overwrites the hues of the configured colors. The default preset leaves them as is.
+    pub fn apply(&self, colors: &mut AppearanceColorsConfig) {
+        let Some(palette) = self.palette() else {
+            return;
+        };
+        recolor_selection(
+            &mut colors.left_selection_colors,
+            palette.left_selection,
+            palette.left_selection_locked,
+        );
+        recolor_selection(
+            &mut colors.right_selection_colors,
+            palette.right_selection,
+            palette.right_selection_locked,
+        );
+        recolor(&mut colors.snap_marker_rgba, palette.snap_marker, 0.0);
+        recolor(&mut colors.timeline_slider_repeat_point_rgba, palette.timeline_repeat_point, 0.0);
+        recolor(&mut colors.timeline_slider_end_point_rgba, palette.timeline_end_point, 0.0);
+        colors.offscreen_playfield_tint_rgb = palette.offscreen_playfield_tint;
+        colors.offscreen_osu_tint_rgb = palette.offscreen_osu_tint;
+        colors.fallback_combo_colors_rgb = palette.fallback_combo_colors.to_vec();
+        colors.timeline_snap_divisor_rgb = palette.timeline_snap_divisors.to_vec();
+        colors.timeline_unsnapped_head_rgb = palette.timeline_unsnapped_head;
+    }
+}
+
+// Mixes `rgb` towards white by `lighten` and keeps the alpha of `rgba`.
+fn recolor(rgba: &mut [f64; 4], rgb: [f64; 3], lighten: f64) {
+    for i in 0..3 {
+        rgba[i] = rgb[i] + (255.0 - rgb[i]) * lighten;
+    }
+}
+
+// Hovered and dragging states are lighter versions of the base color, like the defaults.
+fn recolor_selection(colors: &mut SelectionColors, base: [f64; 3], locked: [f64; 3]) {
+    recolor(&mut colors.drag_rectangle, base, 0.0);
+    recolor(&mut colors.selection_border, base, 0.0);
+    recolor(&mut colors.selection_border_hovered, base, 0.3);
+    recolor(&mut colors.selection_border_dragging, base, 0.6);
+    recolor(&mut colors.selection_tint, base, 0.0);
+    recolor(&mut colors.selection_tint_hovered, base, 0.25);
+    recolor(&mut colors.selection_tint_dragging, base, 0.45);
+    recolor(&mut colors.selection_origin, base, 0.0);
+    recolor(&mut colors.selection_origin_hovered, base, 0.4);
+    recolor(&mut colors.selection_origin_clicked, base, 0.7);
+    recolor(&mut colors.selection_origin_locked, locked, 0.0);
+    recolor(&mut colors.selection_combo_color, base, 0.0);
+}
@@ -15,6 +15,12 @@ pub struct PerformanceConfig{
     pub msaa_samples: u32,
     pub fps_limiter: f64,
     pub prefer_vrr: bool,
+    // Lowers slider rendering detail while frames take longer than the target.
+    pub adaptive_slider_quality: bool,
+    pub slider_quality_target_frame_ms: f64,
+    // No MSAA, flat circles and sliders instead of skin textures, and fewer timeline objects.
+    // Toggled with Shift + F4.
+    pub potato_mode: bool,
 }
 
 // no default values and no aliases, everything is required.
@@ -23,6 +29,53 @@ pub struct GeneralConfig{
     pub playfield_scale: f64,
     pub fix_pitch: bool,
     pub speed: f64,
+    pub overlap_warning_percent: f64,
+    // Shift + F5 lists sliders osu!stable and osu!lazer may draw differently: bezier segments
+    // with more anchors than this, and near-straight perfect circles whose two readings end
+    // up further apart than slider_curve_warning_px.
+    pub slider_curve_max_bezier_anchors: usize,
+    pub slider_curve_warning_px: f64,
+    pub beat_divisor: u32,
+    pub snap_to_object_centers: bool,
+    pub snap_to_slider_control_points: bool,
+    pub snap_to_slider_ends: bool,
+    pub snap_to_mirrored_positions: bool,
+    // Where pressing play starts from, see PlayStartSnap.
+    pub play_start_snap: PlayStartSnap,
+    // Grid in osu! pixels that dragged slider anchors snap to, 0 turns it off.
+    pub slider_anchor_grid_px: f64,
+    // Zoom levels stepped through with - and =, between 0.1 and 10.
+    pub timeline_zoom_presets: Vec<f64>,
+    // Listed with Ctrl + T. See ExternalToolConfig.
+    pub external_tools: Vec<ExternalToolConfig>,
+    // Copies of config.json kept in backups/config/ from before the editor rewrote it, 0 for
+    // none.
+    pub config_backups: usize,
+    // Also writes every editing session of a map to saves/<map>/sessions.csv, for keeping
+    // track of progress in a spreadsheet.
+    pub write_session_csv: bool,
+}
+
+// Written as "off", "tick" or "downbeat" in config.json.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum PlayStartSnap {
+    // From the playhead.
+    Off,
+    // From the previous beat divisor tick.
+    Tick,
+    // From the start of the measure.
+    Downbeat,
+}
+
+// A program and its arguments, split on spaces outside double quotes and run without a shell.
+// "{osu}" in an argument is replaced with the path of the current difficulty's .osu and
+// "{folder}" with the song folder around it. The difficulty is reloaded if the tool changed the
+// .osu.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ExternalToolConfig {
+    pub name: String,
+    pub command: String,
 }
 
 // no default values and no aliases, everything is required.
@@ -37,11 +90,56 @@ pub struct AppearanceConfig{
 #[derive(Serialize, Deserialize, Clone)]
 pub struct AppearanceGeneralConfig {
     pub skin: String,
+    // "default", "deuteranopia", "protanopia" or "tritanopia", see color_presets.rs.
+    pub color_preset: String,
     pub use_custom_slider_end_color: bool,
+    pub show_slider_lazy_end: bool,
     pub break_time_lightness: f64,
     pub selected_fade_in_opacity_cap: f64,
     pub selected_fade_out_opacity_cap: f64,
     pub selection_color_mix_strength: f64,
+    // 0 keeps the AR-accurate fade-in (2/3 of the preempt window).
+    pub object_fade_in_ms_override: f64,
+    pub object_fade_out_ms: f64,
+    // "off" or "auto". Auto draws the cursor of the Auto mod during playback. A replay loaded
+    // with Ctrl + R draws its own cursor instead.
+    pub cursor_trace_mode: String,
+    pub auto_cursor_trail_ms: f64,
+    pub show_flow_overlay: bool,
+    // Scrolling CPU / GPU pass times under the fps numbers, toggled with `.
+    pub show_frame_graph: bool,
+    // Fountains, flashes and a background pulse following the music's spectrum during kiai,
+    // toggled with Insert.
+    pub show_kiai_effects: bool,
+    // While paused, this many objects before and after the playhead stay visible at
+    // ghost_object_opacity even outside their fade window. 0 turns the ghosts off.
+    pub ghost_object_count: u32,
+    pub ghost_object_opacity: f64,
+    // While paused, objects among the last past_object_count before the playhead whose time
+    // has passed stay visible at past_object_opacity, tinted with past_object_tint_rgba, so
+    // the pattern leading up to the playhead stays in view. 0 turns it off.
+    pub past_object_count: u32,
+    pub past_object_opacity: f64,
+    // Preview with the skin's combo colours instead of the map's, like "ignore beatmap skin" in
+    // game. Toggled with ' and never written to the map.
+    pub ignore_beatmap_combo_colors: bool,
+    // "design", "compose" or "timing", cycled with Tab in the editor.
+    pub view_preset: String,
+    // View presets whose approach circles close as fast as in game at any playback rate below
+    // 1x, so slowed-down review still shows how readable the AR is.
+    pub realtime_approach_view_presets: Vec<String>,
+    // "off", "taiko", "catch" or "mania", cycled with [. Draws the converted notes in a strip
+    // under the timeline.
+    pub convert_preview: String,
+    // One line above the bottom timeline with the shortcuts of the active tool mode.
+    pub show_hint_line: bool,
+    // Angles at an object below this are highlighted as sharp (180 is a straight line).
+    pub flow_sharp_angle_deg: f64,
+    // Minimum turn on both sides of a direction reversal to highlight it as anti-flow.
+    pub flow_anti_flow_turn_deg: f64,
+    // Objects and slider paths leaving the screen at this width:height are tinted with
+    // offscreen_osu_tint_rgb. 4:3 is the narrowest screen osu! supports.
+    pub offscreen_warning_aspect_ratio: [f64; 2],
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -53,8 +151,14 @@ pub struct AppearanceLayoutConfig {
     pub timeline_height_percent: f64,
     pub timeline_second_box_width_percent: f64,
     pub timeline_third_box_width_percent: f64,
+    // Moves the stats box and the volume boxes next to it from under the top timeline.
+    pub stats_box_offset_px: [f64; 2],
     pub slider_border_thickness: f64,
     pub slider_outer_thickness: f64,
+    pub auto_cursor_radius_px: f64,
+    pub auto_cursor_trail_width_px: f64,
+    pub flow_line_width_px: f64,
+    pub flow_angle_text_px: f64,
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -66,6 +170,8 @@ pub struct AppearanceTimelineConfig {
     pub milliseconds_per_object_radius: f64,
     pub current_timestamp_position_percent: f64,
     pub timeline_past_grayscale_strength: f64,
+    // Shades the 300/100/50 hit windows around the current time on the top timeline.
+    pub show_hit_windows: bool,
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -73,6 +179,14 @@ pub struct AppearanceColorsConfig {
     pub snap_marker_rgba: [f64; 4],
     pub movable_snap_hitbox_rgba: [f64; 4],
     pub drag_state_marker_rgba: [f64; 4],
+    pub auto_cursor_rgba: [f64; 4],
+    pub flow_line_rgba: [f64; 4],
+    pub flow_sharp_rgba: [f64; 4],
+    pub flow_anti_flow_rgba: [f64; 4],
+    pub replay_hit_rgba: [f64; 4],
+    pub replay_miss_rgba: [f64; 4],
+    pub unsnapped_object_rgba: [f64; 4],
+    pub kiai_effect_rgba: [f64; 4],
     pub slider_end_rgba: [f64; 4],
     pub timeline_slider_outline_rgba: [f64; 4],
     pub timeline_slider_head_body_rgba: [f64; 4],
@@ -82,8 +196,17 @@ pub struct AppearanceColorsConfig {
     pub timeline_slider_head_point_rgba: [f64; 4],
     pub timeline_slider_repeat_point_rgba: [f64; 4],
     pub timeline_slider_end_point_rgba: [f64; 4],
+    // Head outlines on the timeline for 1/1, 1/2, 1/3, 1/4, 1/6, 1/8, 1/12 and 1/16 snaps.
+    pub timeline_snap_divisor_rgb: Vec<[f64; 3]>,
+    pub timeline_unsnapped_head_rgb: [f64; 3],
     pub timeline_past_tint_rgba: [f64; 4],
     pub timeline_past_object_tint_rgba: [f64; 4],
+    // Alpha is how strongly past objects on the playfield take the tint, see past_object_count.
+    pub past_object_tint_rgba: [f64; 4],
+    pub timeline_hit_window_300_rgba: [f64; 4],
+    pub timeline_hit_window_100_rgba: [f64; 4],
+    pub timeline_hit_window_50_rgba: [f64; 4],
+    pub timeline_selection_loop_rgba: [f64; 4],
     pub playfield_rgba: [f64; 4],
     pub playfield_border_rgba: [f64; 4],
     pub gameplay_rgba: [f64; 4],
@@ -93,6 +216,8 @@ pub struct AppearanceColorsConfig {
     pub slider_body_rgba: [f64; 4],
     pub offscreen_playfield_tint_rgb: [f64; 3],
     pub offscreen_osu_tint_rgb: [f64; 3],
+    // Used for maps that define no combo colors.
+    pub fallback_combo_colors_rgb: Vec<[f64; 3]>,
     pub left_selection_colors: SelectionColors,
     pub right_selection_colors: SelectionColors,
 }
@@ -121,4 +246,11 @@ pub struct AudioConfig{
     pub sound_volume: f64,
     pub hitsound_volume: f64,
     pub spacial_audio: f64,
+    pub duck_music_on_audition: bool,
+    pub duck_level: f64,
+    pub duck_attack_ms: f64,
+    pub duck_release_ms: f64,
+    pub stream_music: bool,
+    // Lead-in before the first selected object when looping the selection with Ctrl + U.
+    pub selection_loop_preroll_ms: f64,
 }
\ No newline at end of file
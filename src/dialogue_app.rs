@@ -26,6 +26,7 @@ const INPUT_BOX_BOTTOM_PADDING: f32 = 10.0;
 const WINDOW_TITLE: &str = "osu-editor dialogue";
 const IMAGE_INSET: f32 = 4.0;
 const IMAGE_TEXT_GAP: f32 = 10.0;
+const PREVIEW_HEIGHT: f32 = 128.0;
 
 #[derive(Clone, Copy, PartialEq, Eq)]
 enum DialogMode {
@@ -82,6 +83,28 @@ impl DialogueApp {
         self.app.selected_index.take()
     }
 
+    // A selection under one image as wide as the window, for a look before choosing.
+    pub fn select_with_preview(
+        &mut self,
+        event_loop: &mut EventLoop<()>,
+        question: &str,
+        preview: &[u8],
+        entries: &[String],
+    ) -> Option<usize> {
+        if entries.is_empty() {
+            return None;
+        }
+
+        self.app.prepare_selection(question, entries);
+        self.app.preview_image = Some(preview.to_vec());
+        self.app.preview_image_dirty = true;
+        if let Err(err) = event_loop.run_app_on_demand(&mut self.app) {
+            println!("Selector event loop error: {err:?}");
+            return None;
+        }
+        self.app.selected_index.take()
+    }
+
     pub fn prompt_text(
         &mut self,
         event_loop: &mut EventLoop<()>,
@@ -121,6 +144,8 @@ struct DialogueRuntime {
     selection_with_images: bool,
     selection_images: Vec<Option<Vec<u8>>>,
     selection_images_dirty: bool,
+    preview_image: Option<Vec<u8>>,
+    preview_image_dirty: bool,
     submitted_text: Option<String>,
     selected_index: Option<usize>,
 }
@@ -155,6 +180,8 @@ impl DialogueRuntime {
             selection_with_images: false,
             selection_images: Vec::new(),
             selection_images_dirty: true,
+            preview_image: None,
+            preview_image_dirty: false,
             submitted_text: None,
             selected_index: None,
         }
@@ -173,7 +200,28 @@ impl DialogueRuntime {
     }
 
     fn list_top(&self) -> f64 {
-        TOP_PADDING as f64 + QUESTION_HEIGHT as f64
+        let preview_height = if self.preview_image.is_some() {
+            PREVIEW_HEIGHT as f64 + ROW_GAP as f64
+        } else {
+            0.0
+        };
+        TOP_PADDING as f64 + QUESTION_HEIGHT as f64 + preview_height
+    }
+
+    fn preview_rect(&self) -> Option<[f32; 4]> {
+        self.preview_image.as_ref()?;
+        Some([
+            SIDE_PADDING,
+            TOP_PADDING + QUESTION_HEIGHT,
+            (self.width as f32 - SIDE_PADDING * 2.0).max(0.0),
+            PREVIEW_HEIGHT,
+        ])
+    }
+
+    fn clear_preview_image(&mut self) {
+        if self.preview_image.take().is_some() {
+            self.preview_image_dirty = true;
+        }
     }
 
     fn total_content_height(&self) -> f64 {
@@ -195,6 +243,7 @@ impl DialogueRuntime {
         self.selection_with_images = false;
         self.selection_images.clear();
         self.selection_images_dirty = true;
+        self.clear_preview_image();
 
         if let Some(renderer) = self.renderer.as_mut() {
             renderer.sync_selection_images(&self.selection_images);
@@ -228,6 +277,7 @@ impl DialogueRuntime {
             .map(|i| image_entries.get(i).cloned().flatten())
             .collect();
         self.selection_images_dirty = true;
+        self.clear_preview_image();
 
         if let Some(renderer) = self.renderer.as_mut() {
             renderer.sync_selection_images(&self.selection_images);
@@ -259,6 +309,7 @@ impl DialogueRuntime {
         self.selection_with_images = false;
         self.selection_images.clear();
         self.selection_images_dirty = true;
+        self.clear_preview_image();
 
         if let Some(renderer) = self.renderer.as_mut() {
             renderer.sync_selection_images(&self.selection_images);
@@ -315,6 +366,13 @@ impl DialogueRuntime {
             }
             self.selection_images_dirty = false;
         }
+        if self.preview_image_dirty {
+            if let Some(renderer) = self.renderer.as_mut() {
+                renderer.sync_preview_image(self.preview_image.as_deref());
+            }
+            self.preview_image_dirty = false;
+        }
+        let preview_rect = self.preview_rect();
         let mut option_rows: Vec<OptionRowCoords> = Vec::new();
         if is_selection_mode {
             for i in 0..self.entries.len() {
@@ -346,6 +404,7 @@ impl DialogueRuntime {
             question_text.as_str(),
             is_selection_mode,
             self.selection_with_images,
+            preview_rect,
         );
     }
 
@@ -397,6 +456,7 @@ impl DialogueRuntime {
         self.selection_with_images = false;
         self.selection_images.clear();
         self.selection_images_dirty = true;
+        self.clear_preview_image();
 
         self.render();
     }
@@ -641,6 +701,7 @@ struct SelectorGpu {
     image_instance_buffer: wgpu::Buffer,
     image_instance_capacity: usize,
     selection_images: Vec<Option<RowImageTexture>>,
+    preview_image: Option<RowImageTexture>,
 }
 
 impl SelectorGpu {
@@ -948,6 +1009,7 @@ impl SelectorGpu {
             image_instance_buffer,
             image_instance_capacity,
             selection_images: Vec::new(),
+            preview_image: None,
         })
     }
 
@@ -1073,6 +1135,10 @@ impl SelectorGpu {
             .collect();
     }
 
+    fn sync_preview_image(&mut self, image_bytes: Option<&[u8]>) {
+        self.preview_image = image_bytes.and_then(|bytes| self.create_row_image_texture(bytes));
+    }
+
     fn render(
         &mut self,
         width: u32,
@@ -1086,6 +1152,7 @@ impl SelectorGpu {
         question_text: &str,
         is_selection_mode: bool,
         selection_with_images: bool,
+        preview_rect: Option<[f32; 4]>,
     ) {
         if width == 0 || height == 0 {
             return;
@@ -1109,6 +1176,15 @@ impl SelectorGpu {
             width as f32 - SIDE_PADDING,
         );
 
+        let mut preview_instance_idx = None;
+        if let Some([x, y, w, h]) = preview_rect.filter(|_| self.preview_image.is_some()) {
+            image_instances.push(ImageInstance {
+                pos: [x, y],
+                size: [w, h],
+            });
+            preview_instance_idx = Some(image_instances.len() - 1);
+        }
+
         if is_selection_mode {
             for row in option_rows {
                 let i = row.index;
@@ -1289,7 +1365,7 @@ impl SelectorGpu {
                 pass.draw(0..6, 0..text_vertices.len() as u32);
             }
 
-            if !image_draws.is_empty() {
+            if !image_draws.is_empty() || preview_instance_idx.is_some() {
                 pass.set_pipeline(&self.image_pipeline);
                 pass.set_bind_group(0, &self.uniform_bind_group, &[]);
 
@@ -1303,6 +1379,14 @@ impl SelectorGpu {
                     pass.set_vertex_buffer(0, self.image_instance_buffer.slice(offset..offset + stride));
                     pass.draw(0..6, 0..1);
                 }
+                let preview = preview_instance_idx.zip(self.preview_image.as_ref());
+                if let Some((idx, image)) = preview {
+                    pass.set_bind_group(1, &image.bind_group, &[]);
+                    let offset = idx as u64 * stride;
+                    let instance = self.image_instance_buffer.slice(offset..offset + stride);
+                    pass.set_vertex_buffer(0, instance);
+                    pass.draw(0..6, 0..1);
+                }
             }
         }
 
@@ -1,3 +1,5 @@
 pub mod helpers;
+pub mod osu_diff;
 pub mod osu_file;
+pub mod osr_file;
 pub mod sections;
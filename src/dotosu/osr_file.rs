@@ -0,0 +1,177 @@
+use std::io::Read;
+
+use lzma_rust2::LzmaReader;
+
+use crate::geometry::vec2::Vec2;
+
+// Replay frames carrying this delta hold the RNG seed instead of cursor data.
+const SEED_FRAME_DELTA: i64 = -12345;
+
+pub const MOD_EASY: u32 = 1 << 1;
+pub const MOD_HARD_ROCK: u32 = 1 << 4;
+// M1 and M2. K1 and K2 set them too, so these two bits cover every way to click.
+pub const KEYS_CLICK_MASK: u32 = 1 | 2;
+
+pub struct OsrFrame {
+    pub time_ms: f64,
+    pub pos: Vec2,
+    pub keys: u32,
+}
+
+pub struct OsrFile {
+    pub mode: u8,
+    pub player: String,
+    pub count_300: u16,
+    pub count_100: u16,
+    pub count_50: u16,
+    pub count_miss: u16,
+    pub max_combo: u16,
+    pub mods: u32,
+    // Map time order, cursor in osu! pixels as played (Hard Rock is not flipped back).
+    pub frames: Vec<OsrFrame>,
+}
+
+struct OsrReader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> OsrReader<'a> {
+    fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+        let end = self.pos.checked_add(len)?;
+        let slice = self.bytes.get(self.pos..end)?;
+        self.pos = end;
+        Some(slice)
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        Some(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Option<u16> {
+        Some(u16::from_le_bytes(self.take(2)?.try_into().ok()?))
+    }
+
+    fn i32(&mut self) -> Option<i32> {
+        Some(i32::from_le_bytes(self.take(4)?.try_into().ok()?))
+    }
+
+    fn i64(&mut self) -> Option<i64> {
+        Some(i64::from_le_bytes(self.take(8)?.try_into().ok()?))
+    }
+
+    fn uleb128(&mut self) -> Option<usize> {
+        let mut value = 0usize;
+        let mut shift = 0;
+        loop {
+            let byte = self.u8()?;
+            value |= ((byte & 0x7f) as usize).checked_shl(shift)?;
+            if byte & 0x80 == 0 {
+                return Some(value);
+            }
+            shift += 7;
+        }
+    }
+
+    // 0x00 for no string, else 0x0b, a ULEB128 length and UTF-8 bytes.
+    fn string(&mut self) -> Option<String> {
+        match self.u8()? {
+            0x00 => Some(String::new()),
+            0x0b => {
+                let len = self.uleb128()?;
+                Some(String::from_utf8_lossy(self.take(len)?).into_owned())
+            }
+            _ => None,
+        }
+    }
+}
+
+// "w|x|y|z" frames separated by commas, w being ms since the previous frame.
+fn parse_frames(text: &str) -> Vec<OsrFrame> {
+    let mut frames: Vec<OsrFrame> = Vec::new();
+    let mut time_ms = 0i64;
+    for frame in text.split(',') {
+        let parts: Vec<&str> = frame.split('|').collect();
+        let [delta, x, y, keys] = parts.as_slice() else {
+            continue;
+        };
+        let (Ok(delta), Ok(x), Ok(y), Ok(keys)) = (
+            delta.trim().parse::<i64>(),
+            x.trim().parse::<f64>(),
+            y.trim().parse::<f64>(),
+            keys.trim().parse::<u32>(),
+        ) else {
+            continue;
+        };
+        if delta == SEED_FRAME_DELTA {
+            continue;
+        }
+        time_ms += delta;
+        // The leading frames of a replay can step backwards; keep times non-decreasing.
+        if frames.last().is_some_and(|last| (time_ms as f64) < last.time_ms) {
+            continue;
+        }
+        frames.push(OsrFrame {
+            time_ms: time_ms as f64,
+            pos: Vec2 { x, y },
+            keys,
+        });
+    }
+    frames
+}
+
+// Everything before the replay data, and the LZMA compressed replay data itself.
+fn read_header<'a>(reader: &mut OsrReader<'a>) -> Option<(OsrFile, &'a [u8])> {
+    let mode = reader.u8()?;
+    let _version = reader.i32()?;
+    let _beatmap_md5 = reader.string()?;
+    let player = reader.string()?;
+    let _replay_md5 = reader.string()?;
+    let count_300 = reader.u16()?;
+    let count_100 = reader.u16()?;
+    let count_50 = reader.u16()?;
+    let _count_geki = reader.u16()?;
+    let _count_katu = reader.u16()?;
+    let count_miss = reader.u16()?;
+    let _score = reader.i32()?;
+    let max_combo = reader.u16()?;
+    let _perfect = reader.u8()?;
+    let mods = reader.i32()? as u32;
+    let _life_bar = reader.string()?;
+    let _timestamp = reader.i64()?;
+    let compressed_len = usize::try_from(reader.i32()?).ok()?;
+    let compressed = reader.take(compressed_len)?;
+    let osr_file = OsrFile {
+        mode,
+        player,
+        count_300,
+        count_100,
+        count_50,
+        count_miss,
+        max_combo,
+        mods,
+        frames: Vec::new(),
+    };
+    Some((osr_file, compressed))
+}
+
+pub fn parse_osr_file(osr_data: &[u8]) -> Option<OsrFile> {
+    let mut reader = OsrReader {
+        bytes: osr_data,
+        pos: 0,
+    };
+    let Some((mut osr_file, compressed)) = read_header(&mut reader) else {
+        println!("Failed to parse .osr file: truncated or malformed header.");
+        return None;
+    };
+
+    let mut text = String::new();
+    let decoded = LzmaReader::new_mem_limit(compressed, u32::MAX, None)
+        .and_then(|mut lzma| lzma.read_to_string(&mut text));
+    if let Err(err) = decoded {
+        println!("Failed to decompress .osr replay data: {}", err);
+        return None;
+    }
+    osr_file.frames = parse_frames(&text);
+    Some(osr_file)
+}
@@ -0,0 +1,176 @@
+use std::collections::HashMap;
+
+use crate::dotosu::helpers::get_section;
+
+// What changed between two exports of a difficulty. Objects are matched by their time, so a
+// retimed object counts as removed and added.
+#[derive(Default, PartialEq, Debug)]
+pub struct OsuChanges {
+    pub added: usize,
+    pub removed: usize,
+    pub moved: usize,
+    pub combo_changed: usize,
+    pub hitsounds_changed: usize,
+    pub timing_points_changed: usize,
+}
+
+impl OsuChanges {
+    pub fn is_empty(&self) -> bool {
+        *self == OsuChanges::default()
+    }
+
+    pub fn describe(&self) -> String {
+        if self.is_empty() {
+            return "no changes".to_string();
+        }
+        let parts = [
+            (self.added, "added"),
+            (self.removed, "removed"),
+            (self.moved, "moved or reshaped"),
+            (self.combo_changed, "with new combo changes"),
+            (self.hitsounds_changed, "with hitsound changes"),
+        ];
+        let mut description: Vec<String> = parts
+            .iter()
+            .filter(|(count, _)| *count > 0)
+            .map(|(count, what)| format!("{} object(s) {}", count, what))
+            .collect();
+        if self.timing_points_changed > 0 {
+            description.push(format!(
+                "timing changed ({} point(s))",
+                self.timing_points_changed
+            ));
+        }
+        description.join(", ")
+    }
+}
+
+// Fields of a [HitObjects] line, split into what decides its placement, its combo and its
+// hitsounds. Spinner end times count as placement.
+struct ObjectFields<'a> {
+    placement: Vec<&'a str>,
+    combo: u32,
+    hitsounds: Vec<&'a str>,
+}
+
+fn object_fields(line: &str) -> Option<ObjectFields<'_>> {
+    let fields: Vec<&str> = line.split(',').collect();
+    if fields.len() < 5 {
+        return None;
+    }
+    let object_type: u32 = fields[3].trim().parse().ok()?;
+    let (placement, hitsounds) = if object_type & 2 != 0 {
+        // x,y,time,type,hitSound,curve,slides,length,edgeSounds,edgeSets,hitSample
+        let placement = [&fields[0..2], fields.get(5..8).unwrap_or(&[])].concat();
+        let hitsounds = [&fields[4..5], fields.get(8..).unwrap_or(&[])].concat();
+        (placement, hitsounds)
+    } else if object_type & 8 != 0 {
+        // x,y,time,type,hitSound,endTime,hitSample
+        let placement = [&fields[0..2], fields.get(5..6).unwrap_or(&[])].concat();
+        let hitsounds = [&fields[4..5], fields.get(6..).unwrap_or(&[])].concat();
+        (placement, hitsounds)
+    } else {
+        (fields[0..2].to_vec(), fields[4..].to_vec())
+    };
+    Some(ObjectFields {
+        placement,
+        combo: object_type & 0b0111_0100,
+        hitsounds,
+    })
+}
+
+fn objects_by_time(osu_text: &str) -> HashMap<&str, Vec<&str>> {
+    let mut objects: HashMap<&str, Vec<&str>> = HashMap::new();
+    for line in get_section(osu_text, "HitObjects").unwrap_or("").lines() {
+        let line = line.trim();
+        if let Some(time) = line.split(',').nth(2) {
+            objects.entry(time.trim()).or_default().push(line);
+        }
+    }
+    objects
+}
+
+fn timing_lines(osu_text: &str) -> Vec<&str> {
+    get_section(osu_text, "TimingPoints")
+        .unwrap_or("")
+        .lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty())
+        .collect()
+}
+
+pub fn diff_osu_texts(old_text: &str, new_text: &str) -> OsuChanges {
+    let mut changes = OsuChanges::default();
+    let old_objects = objects_by_time(old_text);
+    let new_objects = objects_by_time(new_text);
+    for (time, new_lines) in new_objects.iter() {
+        let old_lines = old_objects
+            .get(time)
+            .map(|lines| lines.as_slice())
+            .unwrap_or(&[]);
+        changes.added += new_lines.len().saturating_sub(old_lines.len());
+        for (old_line, new_line) in old_lines.iter().zip(new_lines.iter()) {
+            if old_line == new_line {
+                continue;
+            }
+            let (Some(old), Some(new)) = (object_fields(old_line), object_fields(new_line)) else {
+                changes.moved += 1;
+                continue;
+            };
+            if old.placement != new.placement {
+                changes.moved += 1;
+            }
+            if old.combo != new.combo {
+                changes.combo_changed += 1;
+            }
+            if old.hitsounds != new.hitsounds {
+                changes.hitsounds_changed += 1;
+            }
+        }
+    }
+    for (time, old_lines) in old_objects.iter() {
+        let new_count = new_objects.get(time).map_or(0, |lines| lines.len());
+        changes.removed += old_lines.len().saturating_sub(new_count);
+    }
+
+    // Added or edited points, plus removed ones whose time no longer has a point at all.
+    let old_timing = timing_lines(old_text);
+    let new_timing = timing_lines(new_text);
+    let time_of = |line: &str| line.split(',').next().unwrap_or("").trim().to_string();
+    let new_times: Vec<String> = new_timing.iter().map(|line| time_of(line)).collect();
+    changes.timing_points_changed = new_timing
+        .iter()
+        .filter(|line| !old_timing.contains(line))
+        .count()
+        + old_timing
+            .iter()
+            .filter(|line| !new_times.contains(&time_of(line)))
+            .count();
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_harness::TEST_MAP;
+
+    #[test]
+    fn export_diff_counts_object_and_timing_changes() {
+        let edited = TEST_MAP
+            .replace("356,192,1500,1,2,", "300,192,1500,1,0,")
+            .replace("3000,-100,4,2,0,60,0,0", "3000,-50,4,2,0,60,0,0")
+            .replace("200,300,9500,5,0,0:0:0:0:\n", "");
+        let changes = diff_osu_texts(TEST_MAP, &edited);
+        assert_eq!(
+            changes,
+            OsuChanges {
+                removed: 1,
+                moved: 1,
+                hitsounds_changed: 1,
+                timing_points_changed: 1,
+                ..OsuChanges::default()
+            }
+        );
+        assert!(diff_osu_texts(TEST_MAP, TEST_MAP).is_empty());
+    }
+}
@@ -11,12 +11,14 @@ pub struct GeneralSection {
     pub letterbox_in_breaks: bool,
     pub epilepsy_warning: bool,
     pub widescreen_storyboard: bool,
+    pub samples_match_playback_rate: bool,
+    pub use_skin_sprites: bool,
 }
 
 impl GeneralSection {
     pub fn to_osu_text(&self) -> String {
         format!(
-            "AudioFilename:{}\nAudioLeadIn:{}\nPreviewTime:{}\nCountdown:{}\nSampleSet:{}\nStackLeniency:{}\nMode:{}\nLetterboxInBreaks:{}\nEpilepsyWarning:{}\nWidescreenStoryboard:{}\n",
+            "AudioFilename:{}\nAudioLeadIn:{}\nPreviewTime:{}\nCountdown:{}\nSampleSet:{}\nStackLeniency:{}\nMode:{}\nLetterboxInBreaks:{}\nEpilepsyWarning:{}\nWidescreenStoryboard:{}\nSamplesMatchPlaybackRate:{}\nUseSkinSprites:{}\n",
             self.audio_filename,
             self.audio_lead_in,
             self.preview_time,
@@ -26,7 +28,13 @@ impl GeneralSection {
             self.mode,
             if self.letterbox_in_breaks { 1 } else { 0 },
             if self.epilepsy_warning { 1 } else { 0 },
-            if self.widescreen_storyboard { 1 } else { 0 }
+            if self.widescreen_storyboard { 1 } else { 0 },
+            if self.samples_match_playback_rate {
+                1
+            } else {
+                0
+            },
+            if self.use_skin_sprites { 1 } else { 0 }
         )
     }
 }
@@ -173,6 +181,32 @@ pub fn parse_general_section(section: &str) -> Option<GeneralSection> {
         },
         None => false,
     };
+    let samples_match_playback_rate = match pairs.get("SamplesMatchPlaybackRate") {
+        Some(val) => match val.parse::<u8>() {
+            Ok(v) => v != 0,
+            Err(err) => {
+                println!(
+                    "General parsing error: 'SamplesMatchPlaybackRate'={} is not a valid u8: {}",
+                    val, err
+                );
+                return None;
+            }
+        },
+        None => false,
+    };
+    let use_skin_sprites = match pairs.get("UseSkinSprites") {
+        Some(val) => match val.parse::<u8>() {
+            Ok(v) => v != 0,
+            Err(err) => {
+                println!(
+                    "General parsing error: 'UseSkinSprites'={} is not a valid u8: {}",
+                    val, err
+                );
+                return None;
+            }
+        },
+        None => false,
+    };
     return Some(GeneralSection {
         audio_filename,
         audio_lead_in,
@@ -184,5 +218,7 @@ pub fn parse_general_section(section: &str) -> Option<GeneralSection> {
         letterbox_in_breaks: letterbox_in_breaks,
         epilepsy_warning: epilepsy_warning,
         widescreen_storyboard: widescreen_storyboard,
+        samples_match_playback_rate,
+        use_skin_sprites,
     });
 }
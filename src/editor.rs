@@ -1,45 +1,81 @@
+use std::borrow::Cow;
 use std::collections::HashMap;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus};
 use std::rc::Rc;
 use std::sync::{
     Arc, RwLock,
     atomic::{AtomicBool, AtomicU32, Ordering},
+    mpsc,
 };
-use std::time::Instant;
+use std::time::{Duration, Instant, SystemTime};
 
 use winit::{
     application::ApplicationHandler,
-    dpi::LogicalSize,
-    event::WindowEvent,
+    dpi::{LogicalSize, PhysicalPosition, PhysicalSize},
+    event::{Ime, MouseScrollDelta, WindowEvent},
     event_loop::{ActiveEventLoop, ControlFlow, EventLoop},
+    keyboard::ModifiersState,
     window::{Fullscreen, Icon, Window, WindowId},
 };
 
 use winit::platform::run_on_demand::EventLoopExtRunOnDemand;
 
+use crate::clipboard::{SystemClipboard, objects_from_text, objects_to_text};
+use crate::tool_mode::{ToolAction, ToolEvent, ToolMode, ToolState, placed_circle, placed_slider};
+use crate::timeline_view::{BottomTimelineRange, top_timeline_window_ms};
+use crate::time_shift::{TimeRemap, TimeShiftScope, remap_beatmap};
+use crate::onboarding::{OnboardingRegion, onboarding_regions};
+use crate::time_formats::{
+    ModdingTimestamp, format_clock_time, format_modding_timestamp, parse_modding_timestamp,
+    time_formats,
+};
 use crate::dotosu::helpers::{get_key_value_pairs, get_section};
+use crate::dotosu::osu_file::parse_osu_file;
+use crate::dotosu::sections::events::parse_events_section;
 use crate::geometry::atomic_vec2::AtomicVec2;
 use crate::geometry::vec2::Vec2;
 use crate::gpu::gpu::GpuRenderer;
-use crate::gui::{DragEvent, HoverEvent, MouseHandler, RectHitbox, SimpleButton};
+use crate::gui::{DragEvent, HoverEvent, MouseHandler, RectHitbox, SimpleButton, SimpleHitbox};
 use crate::hitbox_handlers;
-use crate::layout;
-use crate::map_format::events::BreakEvent;
+use crate::imports::{import_diff_background, read_object_list, read_replay};
+use crate::report_panel::ReportPanel;
+use crate::layout::{self, HudLayout};
+use crate::map_format::beatmap::Beatmap;
+use crate::map_format::events::{Events, rapid_flash_times};
+use crate::map_format::objects::{ComboInfo, HitObject, HitSample, Hitsound, Slider, Spinner};
 use crate::map_format::slider_boxing::BBox4;
-use crate::render::{RenderShared, RendererThread};
+use crate::map_format::statistics::EditingTotals;
+use crate::map_format::slider_timing::{
+    SliderTimingFix, find_desynced_sliders, fix_desynced_sliders,
+};
+use crate::map_format::timing::{GreenLineSettings, SampleSet, Timing, TimingPoint};
+use crate::patterns::{list_patterns, load_pattern, save_pattern};
+use crate::render::{FadeEnvelope, RenderShared, RendererThread, ViewPreset};
 use crate::skin::{Texture, load_texture};
+use crate::text_input::TextInput;
 use crate::state::{
-    EditState, HitsoundRouting, HitsoundSamplesetIndices, HitsoundThreadConfig, MapState,
+    ConvertMode, DifficultySimulation, EditState, GapSuggestion, HitsoundRouting,
+    HitsoundSamplesetIndices, HitsoundThreadConfig, MapState, SessionTracker, SnapSources,
+    object_at_cursor, object_tooltip,
 };
 use crate::dialogue_app::DialogueApp;
 use crate::{
-    audio::AudioEngine, config::Config, files::BeatmapsetFolder,
-    files::sanitize_name,
+    audio::{AudioEngine, SongTimeMs, is_blank_sample},
+    config::{Config, PlayStartSnap},
+    exports::exported_osu_path,
+    files::BeatmapsetFolder,
+    files::{
+        MapSession, RecentMap, SelectionSet, create_difficulty, failed_writes, failed_writes_hint,
+        find_imported_diff, onboarding_seen, read_editing_sessions, read_map_session,
+        read_osu_timing, read_recent_maps, record_editing_session, record_onboarding_seen,
+        record_recent_map, retry_failed_writes, sanitize_name, scan_folder, write_beatmap,
+        write_bytes_to_file, write_layout_config, write_map_session, write_tool_folder,
+    },
     skin::Skin,
 };
 
-use crate::map_format::events::Event::Break;
 
 struct AtomicOverlayRectState {
     dragging: AtomicBool,
@@ -97,6 +133,50 @@ impl AtomicOverlayRectState {
     }
 }
 
+// Where a drag in layout edit mode started and where the cursor is now.
+struct LayoutDragState {
+    dragging: AtomicBool,
+    start: AtomicVec2,
+    current: AtomicVec2,
+}
+
+impl LayoutDragState {
+    fn new() -> Self {
+        Self {
+            dragging: AtomicBool::new(false),
+            start: AtomicVec2::new(Vec2 { x: 0.0, y: 0.0 }),
+            current: AtomicVec2::new(Vec2 { x: 0.0, y: 0.0 }),
+        }
+    }
+
+    fn update_drag(&self, pos: Vec2) {
+        if !self.dragging.load(Ordering::Acquire) {
+            self.start.store(pos);
+        }
+        self.current.store(pos);
+        self.dragging.store(true, Ordering::Release);
+    }
+
+    fn end_drag(&self) {
+        self.dragging.store(false, Ordering::Release);
+    }
+
+    fn drag(&self) -> Option<(Vec2, Vec2)> {
+        if !self.dragging.load(Ordering::Acquire) {
+            return None;
+        }
+        Some((self.start.load(), self.current.load()))
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum LayoutDragTarget {
+    StatsBox,
+    TimelineHeight,
+    SecondBoxWidth,
+    ThirdBoxWidth,
+}
+
 pub fn open_editor_window(
     event_loop: &mut EventLoop<()>,
     selector: &mut DialogueApp,
@@ -105,7 +185,8 @@ pub fn open_editor_window(
     skin: Skin,
     audio: Arc<AudioEngine>,
     hitsound_indices: HashMap<String, usize>,
-) {
+    difficulty: Option<&str>,
+) -> Option<RecentMap> {
     let versions_strings: Vec<String> = beatmapset
         .beatmaps
         .iter()
@@ -113,37 +194,66 @@ pub fn open_editor_window(
         .collect();
     if versions_strings.is_empty() {
         println!("Beatmapset has no difficulties.");
-        return;
+        return None;
     }
 
-    println!("Select a difficulty to edit:");
-    let difficulty_images: Vec<Option<Vec<u8>>> = beatmapset
-        .beatmaps
-        .iter()
-        .map(|beatmap| {
-            let diff_dir = sanitize_name(&beatmap.version);
-            let bg_small_path = Path::new("saves")
-                .join(&beatmapset.map_dir_name)
-                .join("diffs")
-                .join(diff_dir)
-                .join("bg_small.png");
-            fs::read(bg_small_path).ok()
-        })
-        .collect();
-
-    let selected_diff_idx = match selector.select_with_images(
-        event_loop,
-        "Select difficulty",
-        &versions_strings,
-        &difficulty_images,
-    ) {
-        Some(idx) => idx,
+    let selected_diff_idx = match difficulty {
+        Some(difficulty) => match versions_strings
+            .iter()
+            .position(|v| v == difficulty)
+            .or_else(|| {
+                versions_strings
+                    .iter()
+                    .position(|v| v.eq_ignore_ascii_case(difficulty))
+            }) {
+            Some(idx) => idx,
+            None => {
+                println!(
+                    "Difficulty '{}' not found, expected one of: {}",
+                    difficulty,
+                    versions_strings.join(", ")
+                );
+                return None;
+            }
+        },
         None => {
-            println!("Difficulty selection cancelled.");
-            return;
+            println!("Select a difficulty to edit:");
+            let difficulty_images: Vec<Option<Vec<u8>>> = beatmapset
+                .beatmaps
+                .iter()
+                .map(|beatmap| {
+                    let diff_dir = sanitize_name(&beatmap.version);
+                    let bg_small_path = Path::new("saves")
+                        .join(&beatmapset.map_dir_name)
+                        .join("diffs")
+                        .join(diff_dir)
+                        .join("bg_small.png");
+                    fs::read(bg_small_path).ok()
+                })
+                .collect();
+
+            match selector.select_with_images(
+                event_loop,
+                "Select difficulty",
+                &versions_strings,
+                &difficulty_images,
+            ) {
+                Some(idx) => idx,
+                None => {
+                    println!("Difficulty selection cancelled.");
+                    return None;
+                }
+            }
         }
     };
     println!("Selected difficulty: {}", versions_strings[selected_diff_idx]);
+    record_recent_map(
+        &beatmapset.map_dir_name,
+        &versions_strings[selected_diff_idx],
+    );
+
+    let mut beatmapset = beatmapset;
+    offer_slider_timing_fix(event_loop, selector, &mut beatmapset, selected_diff_idx);
 
     let mut app = match EditorApp::new(
         beatmapset,
@@ -156,7 +266,7 @@ pub fn open_editor_window(
         Some(a) => a,
         None => {
             println!("Failed to initialize editor app.");
-            return;
+            return None;
         }
     };
 
@@ -166,10 +276,99 @@ pub fn open_editor_window(
             println!("Editor event loop error: {:?}", e);
         }
     }
+    app.switch_to_recent.take()
+}
+
+// Sessions recorded on one difficulty before it was opened.
+fn earlier_editing_totals(map_dir_name: &str, version: &str) -> EditingTotals {
+    let sessions = read_editing_sessions(map_dir_name);
+    EditingTotals::of(sessions.iter().filter(|session| session.difficulty == version))
+}
+
+// Sliders keep their velocity from when they were written, so a later BPM or offset edit
+// leaves their end times off the grid until they are fixed up.
+fn offer_slider_timing_fix(
+    event_loop: &mut EventLoop<()>,
+    selector: &mut DialogueApp,
+    beatmapset: &mut BeatmapsetFolder,
+    diff_idx: usize,
+) {
+    let beatmap = &mut beatmapset.beatmaps[diff_idx];
+    let desynced = find_desynced_sliders(beatmap);
+    if desynced.is_empty() {
+        return;
+    }
+    let question = format!(
+        "{} slider(s) no longer match the timing of {}",
+        desynced.len(),
+        beatmap.version
+    );
+    let options = vec![
+        "recompute slider lengths".to_string(),
+        "insert compensating green lines".to_string(),
+        "leave as is".to_string(),
+    ];
+    let fix = match selector.select(event_loop, &question, &options) {
+        Some(0) => SliderTimingFix::RecomputeLengths,
+        Some(1) => SliderTimingFix::InsertGreenLines,
+        _ => return,
+    };
+    let fixed = fix_desynced_sliders(beatmap, &desynced, fix);
+    if write_beatmap(&beatmapset.map_dir_name, beatmap) {
+        println!("Fixed {} slider(s) in {}.", fixed, beatmap.version);
+    }
+}
+
+// Flashes from the difficulty's own events and from every .osb in the set count together,
+// since osu! shows both at once.
+fn storyboard_rapid_flash_times(folder: &BeatmapsetFolder, beatmap: &Beatmap) -> Vec<f64> {
+    let mut flash_times = beatmap.events.flash_times();
+    for (name, _) in folder.assets.names_and_sizes() {
+        if !name.to_ascii_lowercase().ends_with(".osb") {
+            continue;
+        }
+        let Some(bytes) = folder.assets.get(&name) else {
+            continue;
+        };
+        let text = String::from_utf8_lossy(bytes);
+        let Some(section) = get_section(&text, "Events").and_then(parse_events_section) else {
+            println!(
+                "Could not read storyboard {}, skipping its flash check.",
+                name
+            );
+            continue;
+        };
+        flash_times.extend(Events::from_osu_format(&section).flash_times());
+    }
+    flash_times.sort_by(|a, b| a.total_cmp(b));
+    rapid_flash_times(&flash_times)
+}
+
+// The flash warning depends on the difficulty's own events, so this is redone on every switch.
+fn window_title(folder: &BeatmapsetFolder, beatmap: &Beatmap) -> String {
+    let mut title = format!(
+        "osu editor | {} - {} [{}]",
+        folder.beatmapset.title, folder.beatmapset.artist, folder.beatmapset.creator
+    );
+    let rapid_flashes = storyboard_rapid_flash_times(folder, beatmap);
+    if !rapid_flashes.is_empty() {
+        let times: Vec<String> = rapid_flashes
+            .iter()
+            .map(|t| format!("{:.0}ms", t))
+            .collect();
+        println!("Storyboard flashes rapidly at {}.", times.join(", "));
+        if !beatmap.general.epilepsy_warning {
+            println!("EpilepsyWarning is off, turn it on in the general settings (;).");
+        }
+        title.push_str(" | WARNING: rapid flashing in storyboard");
+    }
+    title
 }
 
 pub struct EditorApp {
     title: String,
+    beatmapset: BeatmapsetFolder,
+    diff_version: String,
     pub window: Option<Arc<Window>>,
     width: u32,
     height: u32,
@@ -187,6 +386,9 @@ pub struct EditorApp {
     pub desired_sound_volume: f64,
     pub desired_hitsound_volume: f64,
     pub desired_fix_pitch: bool,
+    pub modifiers: ModifiersState,
+    // Touchpad scrolling not yet turned into a seek step.
+    wheel_seek_px: f64,
 
     sound_volume_hitbox: Rc<RectHitbox>,
     hitsound_volume_hitbox: Rc<RectHitbox>,
@@ -197,11 +399,30 @@ pub struct EditorApp {
     selection_right_bbox_hitbox: Rc<RectHitbox>,
     selection_left_origin_hitbox: Rc<RectHitbox>,
     selection_right_origin_hitbox: Rc<RectHitbox>,
+    selection_left_scale_hitbox: Rc<RectHitbox>,
+    selection_right_scale_hitbox: Rc<RectHitbox>,
+    selection_left_rotation_hitbox: Rc<RectHitbox>,
+    selection_right_rotation_hitbox: Rc<RectHitbox>,
     undo_button_hitbox: Rc<RectHitbox>,
     current_state_button_hitbox: Rc<RectHitbox>,
     redo_buttons_hitbox: Rc<RectHitbox>,
     progress_bar_hitbox: Rc<RectHitbox>,
+    timeline_object_drag_hitbox: Rc<RectHitbox>,
     play_pause_button: Rc<SimpleButton>,
+    play_pause_requested: Arc<AtomicBool>,
+    stats_box_hitbox: Rc<RectHitbox>,
+    stats_box_copy_requested: Arc<AtomicU32>,
+    tool_state: ToolState,
+    // The mode of `tool_state` as its index, for the hitboxes to read.
+    tool_mode: Arc<AtomicU32>,
+    tool_click_requested: Arc<AtomicU32>,
+    tool_click_playfield_pos: Arc<AtomicVec2>,
+    // The object under the cursor and since when, so its tooltip waits for the hover delay.
+    hovered_object: Option<(usize, Instant)>,
+    // The first-run overlay, up until any key is pressed.
+    onboarding_visible: bool,
+    // View-only AR / CS the playfield is drawn at, off until nudged.
+    difficulty_simulation: DifficultySimulation,
 
     pub mouse_handler: MouseHandler,
 
@@ -221,6 +442,17 @@ pub struct EditorApp {
     pub selection_right_origin_hovered: Arc<AtomicBool>,
     pub selection_left_origin_dragging: Arc<AtomicBool>,
     pub selection_right_origin_dragging: Arc<AtomicBool>,
+    pub selection_left_scale_hovered: Arc<AtomicBool>,
+    pub selection_right_scale_hovered: Arc<AtomicBool>,
+    pub selection_left_scale_dragging: Arc<AtomicBool>,
+    pub selection_right_scale_dragging: Arc<AtomicBool>,
+    pub selection_left_rotation_hovered: Arc<AtomicBool>,
+    pub selection_right_rotation_hovered: Arc<AtomicBool>,
+    pub selection_left_rotation_dragging: Arc<AtomicBool>,
+    pub selection_right_rotation_dragging: Arc<AtomicBool>,
+    pub shift_held: Arc<AtomicBool>,
+    pub ctrl_held: Arc<AtomicBool>,
+    pub alt_held: Arc<AtomicBool>,
     undo_button_hovered: Arc<AtomicBool>,
     undo_button_clicked: Arc<AtomicBool>,
     current_state_button_hovered: Arc<AtomicBool>,
@@ -238,15 +470,83 @@ pub struct EditorApp {
     playfield_screen_top_left: Arc<AtomicVec2>,
     playfield_scale_state: Arc<AtomicU32>,
     timeline_zoom_state: Arc<AtomicU32>,
+    // x..y: map time range the bottom timeline shows, updated every frame to keep the top
+    // timeline's window in view.
+    bottom_timeline_range: Arc<AtomicVec2>,
+    // x..y: map time range the top timeline shows.
+    top_timeline_window: Arc<AtomicVec2>,
     viewport_width_state: Arc<AtomicU32>,
     viewport_height_state: Arc<AtomicU32>,
+    timeline_height_percent_state: Arc<AtomicU32>,
 
     drag_rect_left: Rc<AtomicOverlayRectState>,
     drag_rect_right: Rc<AtomicOverlayRectState>,
+    layout_edit_mode: Rc<AtomicBool>,
+    layout_drag: Rc<LayoutDragState>,
+    // The panel being dragged and the layout from before the drag.
+    layout_drag_target: Option<(LayoutDragTarget, HudLayout)>,
+    layout_edited: bool,
     is_renaming_current_state: bool,
-    current_state_name_input: String,
+    text_input_target: TextInputTarget,
+    text_input: TextInput,
+    copied_objects: Vec<HitObject>,
+    clipboard: SystemClipboard,
+    hitsound_routing: HitsoundRouting,
+    gap_suggestions: Vec<GapSuggestion>,
+    // The difficulty's exported .osu and its last seen modification time, None while it
+    // doesn't exist.
+    watched_osu: (PathBuf, Option<SystemTime>),
+    next_osu_watch_check: Instant,
+    external_edit_pending: bool,
+    // Set when a timing edit leaves sliders off their timing, asked about like an external edit.
+    slider_timing_fix_pending: bool,
+    // Set by the recent maps quick switch, opened once this editor has closed.
+    switch_to_recent: Option<RecentMap>,
+    recent_maps: Vec<RecentMap>,
+    external_tool_run: Option<ExternalToolRun>,
+    // Of every difficulty in the set, written back to the session file as they change.
+    selection_sets: Vec<SelectionSet>,
+    // Measures per combo of the last previewed combo proposal, applied with "apply".
+    combo_proposal_measures: Option<usize>,
+    session_tracker: SessionTracker,
+    // Findings of the last map check, shown until Esc or the next check.
+    report_panel: Option<ReportPanel>,
+}
+
+// A tool started from the external tools menu, the .osu it was given and when it was written.
+struct ExternalToolRun {
+    name: String,
+    osu_path: PathBuf,
+    written_at: Option<SystemTime>,
+    finished: mpsc::Receiver<std::io::Result<ExitStatus>>,
+}
+
+// The current state name box doubles as the editor's only text field.
+enum TextInputTarget {
+    CurrentStateName,
+    NewDifficultyName { copy_objects: bool },
+    PatternName,
+    PatternInsert,
+    ObjectFields,
+    ObjectListImport,
+    CopyTiming,
+    TimingTools,
+    GeneralSettings,
+    SampleFile,
+    ExternalEdit,
+    RecentMap,
+    GreenLineHere,
+    ExternalTool,
+    PlaybackMix,
+    ReplayImport,
+    SelectionSet,
+    ComboProposal,
+    SelectionTransform,
+    SliderTimingFix,
 }
 
+const OSU_WATCH_INTERVAL: Duration = Duration::from_secs(1);
+
 struct SamplesetIdx {
     hitclap: usize,
     hitfinish: usize,
@@ -314,6 +614,64 @@ fn load_sampleset(name: &str, hitsound_indices: &HashMap<String, usize>) -> Opti
     return Some(sampleset_ids);
 }
 
+// Sample files of the beatmap folder, for objects that name one. They are loaded into the
+// engine after the skin's samples, starting at `first_index`.
+fn load_map_samples(
+    beatmapset: &BeatmapsetFolder,
+    audio: &AudioEngine,
+    first_index: usize,
+) -> HashMap<String, usize> {
+    let mut map_samples = HashMap::new();
+    for (name, _) in beatmapset.assets.names_and_sizes() {
+        let hint_ext = Path::new(&name)
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| ext.to_ascii_lowercase());
+        if !matches!(hint_ext.as_deref(), Some("wav" | "ogg" | "mp3"))
+            || name.eq_ignore_ascii_case(&beatmapset.beatmapset.audio_filename)
+        {
+            continue;
+        }
+        let Some(bytes) = beatmapset.assets.get(&name) else {
+            continue;
+        };
+        let index = first_index + map_samples.len();
+        if audio
+            .set_hitsound_sample(bytes.clone(), index, name.clone(), hint_ext)
+            .is_some()
+        {
+            map_samples.insert(name.to_ascii_lowercase(), index);
+        }
+    }
+    map_samples
+}
+
+// Splits an external tool command into the program and its arguments, on whitespace outside
+// double quotes. Placeholders are filled in per argument afterwards, so a path with spaces or
+// quotes in it stays one argument and is never seen by a shell.
+fn tool_command_args(template: &str, osu_path: &Path, folder: &Path) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut current: Option<String> = None;
+    let mut quoted = false;
+    for ch in template.chars() {
+        match ch {
+            '"' => {
+                quoted = !quoted;
+                current.get_or_insert_with(String::new);
+            }
+            ch if ch.is_whitespace() && !quoted => args.extend(current.take()),
+            ch => current.get_or_insert_with(String::new).push(ch),
+        }
+    }
+    args.extend(current);
+    args.into_iter()
+        .map(|arg| {
+            arg.replace("{osu}", &osu_path.to_string_lossy())
+                .replace("{folder}", &folder.to_string_lossy())
+        })
+        .collect()
+}
+
 fn parse_bookmarks_from_editor_section(osu_text: &str) -> Vec<f64> {
     let editor_section = match get_section(osu_text, "Editor") {
         Some(section) => section,
@@ -343,55 +701,8 @@ fn parse_bookmarks_from_editor_section(osu_text: &str) -> Vec<f64> {
     out
 }
 
-fn load_bookmarks_for_diff(map_dir_name: &str, version: &str) -> Vec<f64> {
-    let imported_diffs_dir = Path::new("saves").join(map_dir_name).join("imported_diffs");
-    let entries = match fs::read_dir(&imported_diffs_dir) {
-        Ok(entries) => entries,
-        Err(_) => return Vec::new(),
-    };
-
-    for entry in entries {
-        let entry = match entry {
-            Ok(entry) => entry,
-            Err(_) => continue,
-        };
-        let path = entry.path();
-        if !path
-            .extension()
-            .and_then(|x| x.to_str())
-            .map(|x| x.eq_ignore_ascii_case("osu"))
-            .unwrap_or(false)
-        {
-            continue;
-        }
-
-        let osu_text = match fs::read_to_string(&path) {
-            Ok(text) => text,
-            Err(_) => continue,
-        };
-
-        let metadata_section = match get_section(&osu_text, "Metadata") {
-            Some(section) => section,
-            None => continue,
-        };
-        let metadata = match get_key_value_pairs(metadata_section) {
-            Some(pairs) => pairs,
-            None => continue,
-        };
-        let osu_version = match metadata.get("Version") {
-            Some(value) => value.trim(),
-            None => continue,
-        };
-        if osu_version == version {
-            return parse_bookmarks_from_editor_section(&osu_text);
-        }
-    }
-
-    Vec::new()
-}
-
 impl EditorApp {
-    fn new(
+    pub fn new(
         beatmapset: BeatmapsetFolder,
         editor_config: Config,
         skin: Skin,
@@ -445,16 +756,20 @@ impl EditorApp {
                 return None;
             }
         };
-        let bookmarks = load_bookmarks_for_diff(&beatmapset.map_dir_name, &beatmap.version);
+        let imported_diff = find_imported_diff(&beatmapset.map_dir_name, &beatmap.version);
+        let bookmarks = match &imported_diff {
+            Some((_, osu_text)) => parse_bookmarks_from_editor_section(osu_text),
+            None => Vec::new(),
+        };
+        // Exports are where other tools edit the map, whether or not it started as an import.
+        let osu_path = exported_osu_path(&beatmapset.map_dir_name, &beatmap);
+        let modified = fs::metadata(&osu_path).and_then(|m| m.modified()).ok();
+        let watched_osu = (osu_path, modified);
 
         let desired_sound_volume = editor_config.audio.sound_volume;
         let desired_hitsound_volume = editor_config.audio.hitsound_volume;
         let desired_fix_pitch = editor_config.general.fix_pitch;
-        let timeline_height_percent = editor_config.appearance.layout.timeline_height_percent;
-        let timeline_second_box_width_percent =
-            editor_config.appearance.layout.timeline_second_box_width_percent;
-        let timeline_third_box_width_percent =
-            editor_config.appearance.layout.timeline_third_box_width_percent;
+        let hud_layout = HudLayout::from_config(&editor_config.appearance.layout);
         audio.set_volume(desired_sound_volume);
         audio.set_hitsound_volume(desired_hitsound_volume);
         audio.set_fix_pitch(desired_fix_pitch);
@@ -473,6 +788,17 @@ impl EditorApp {
         let selection_right_origin_hovered = Arc::new(AtomicBool::new(false));
         let selection_left_origin_dragging = Arc::new(AtomicBool::new(false));
         let selection_right_origin_dragging = Arc::new(AtomicBool::new(false));
+        let selection_left_scale_hovered = Arc::new(AtomicBool::new(false));
+        let selection_right_scale_hovered = Arc::new(AtomicBool::new(false));
+        let selection_left_scale_dragging = Arc::new(AtomicBool::new(false));
+        let selection_right_scale_dragging = Arc::new(AtomicBool::new(false));
+        let selection_left_rotation_hovered = Arc::new(AtomicBool::new(false));
+        let selection_right_rotation_hovered = Arc::new(AtomicBool::new(false));
+        let selection_left_rotation_dragging = Arc::new(AtomicBool::new(false));
+        let selection_right_rotation_dragging = Arc::new(AtomicBool::new(false));
+        let shift_held = Arc::new(AtomicBool::new(false));
+        let ctrl_held = Arc::new(AtomicBool::new(false));
+        let alt_held = Arc::new(AtomicBool::new(false));
         let selection_left_bbox_screen: Arc<RwLock<Option<BBox4>>> = Arc::new(RwLock::new(None));
         let selection_right_bbox_screen: Arc<RwLock<Option<BBox4>>> = Arc::new(RwLock::new(None));
         let selection_left_origin_playfield = Arc::new(AtomicVec2::new(Vec2 { x: 0.0, y: 0.0 }));
@@ -484,13 +810,49 @@ impl EditorApp {
         let playfield_scale_state = Arc::new(AtomicU32::new(
             (editor_config.general.playfield_scale.clamp(0.01, 1.0) as f32).to_bits(),
         ));
-        let timeline_zoom_state = Arc::new(AtomicU32::new((1.0f32).to_bits()));
+        let session = read_map_session(&beatmapset.map_dir_name);
+        let timeline_zoom = session
+            .as_ref()
+            .map(|session| session.timeline_zoom)
+            .unwrap_or(1.0);
+        let selection_sets = session
+            .map(|session| session.selection_sets)
+            .unwrap_or_default();
+        let timeline_zoom_state = Arc::new(AtomicU32::new(
+            (timeline_zoom.clamp(0.1, 10.0) as f32).to_bits(),
+        ));
         let viewport_width_state = Arc::new(AtomicU32::new(1280));
         let viewport_height_state = Arc::new(AtomicU32::new(720));
+        let timeline_height_percent_state = Arc::new(AtomicU32::new(
+            (hud_layout.timeline_height_percent as f32).to_bits(),
+        ));
 
         let drag_rect_left = Rc::new(AtomicOverlayRectState::new());
         let drag_rect_right = Rc::new(AtomicOverlayRectState::new());
 
+        // Covers the whole window while editing the layout, so no other hitbox takes the drags.
+        let layout_edit_mode = Rc::new(AtomicBool::new(false));
+        let layout_drag = Rc::new(LayoutDragState::new());
+        let layout_edit_hitbox = {
+            let layout_edit_mode = Rc::clone(&layout_edit_mode);
+            let layout_drag = Rc::clone(&layout_drag);
+            Rc::new(SimpleHitbox::new(
+                Box::new(move |_| layout_edit_mode.load(Ordering::Acquire)),
+                Box::new(move |event: DragEvent| match event {
+                    DragEvent::Move {
+                        left,
+                        absolute_cursor_pos,
+                    } => {
+                        if left {
+                            layout_drag.update_drag(absolute_cursor_pos);
+                        }
+                    }
+                    DragEvent::Stop => layout_drag.end_drag(),
+                }),
+                Box::new(|_| {}),
+            ))
+        };
+
         let drag_left_move: Rc<dyn Fn(Vec2)> = {
             let drag_rect_left_state = Rc::clone(&drag_rect_left);
             Rc::new(move |absolute: Vec2| {
@@ -561,44 +923,32 @@ impl EditorApp {
 
         let seek_dragging = Arc::new(AtomicBool::new(false));
         let seek_resume_after_drag = Arc::new(AtomicBool::new(false));
+        let bottom_timeline_range = Arc::new(AtomicVec2::new(Vec2 { x: 0.0, y: 0.0 }));
+        let top_timeline_window = Arc::new(AtomicVec2::new(Vec2 { x: 0.0, y: 0.0 }));
         let progress_bar_hitbox = hitbox_handlers::create_progress_bar_hitbox(
             Arc::clone(&audio),
             Arc::clone(&seek_dragging),
             Arc::clone(&seek_resume_after_drag),
             Arc::clone(&progress_bar_hitbox_hovered),
+            Arc::clone(&bottom_timeline_range),
+            Arc::clone(&top_timeline_window),
+        );
+        let play_pause_requested = Arc::new(AtomicBool::new(false));
+        let play_pause_button =
+            hitbox_handlers::create_play_pause_button(Arc::clone(&play_pause_requested));
+        let stats_box_copy_requested = Arc::new(AtomicU32::new(0));
+        let stats_box_hitbox =
+            hitbox_handlers::create_stats_box_hitbox(Arc::clone(&stats_box_copy_requested));
+        let tool_mode = Arc::new(AtomicU32::new(ToolMode::Select as u32));
+        let tool_click_requested = Arc::new(AtomicU32::new(0));
+        let tool_click_playfield_pos = Arc::new(AtomicVec2::new(Vec2 { x: 0.0, y: 0.0 }));
+        let tool_playfield_hitbox = hitbox_handlers::create_tool_playfield_hitbox(
+            Arc::clone(&tool_mode),
+            Arc::clone(&tool_click_requested),
+            Arc::clone(&tool_click_playfield_pos),
+            Arc::clone(&playfield_screen_scale),
+            Arc::clone(&playfield_screen_top_left),
         );
-        let play_pause_button = hitbox_handlers::create_play_pause_button(Arc::clone(&audio));
-
-        let mut break_times: Vec<(f64, f64)> = Vec::new();
-        for event in &beatmap.events.events {
-            match event {
-                Break(BreakEvent {
-                    start_time: start,
-                    end_time: end,
-                }) => {
-                    break_times.push((*start, *end));
-                }
-                _ => {}
-            }
-        }
-        let kiai_times = {
-            let mut kiai_times: Vec<(f64, f64)> = Vec::new();
-            let mut kiai_start = None;
-
-            for timing_point in &beatmap.timing.timing_points {
-                if timing_point.effects().kiai_mode {
-                    if kiai_start.is_none() {
-                        kiai_start = Some(timing_point.time());
-                    }
-                } else {
-                    if let Some(start) = kiai_start {
-                        kiai_times.push((start, timing_point.time()));
-                        kiai_start = None;
-                    }
-                }
-            }
-            kiai_times
-        };
 
         let hitsound_thread_config = HitsoundThreadConfig {
             audio: Arc::clone(&audio),
@@ -606,22 +956,27 @@ impl EditorApp {
                 normal: normal_sampleset.to_hitsound_sampleset_indices(),
                 soft: soft_sampleset.to_hitsound_sampleset_indices(),
                 drum: drum_sampleset.to_hitsound_sampleset_indices(),
+                map_samples: load_map_samples(&beatmapset, &audio, hitsound_indices.len()),
             },
         };
 
+        let hitsound_routing = hitsound_thread_config.routing.clone();
         let edit_state = EditState::new(
-            MapState::new(
-                beatmap.objects.objects.clone(),
-                beatmap.timing.timing_points.clone(),
-                bookmarks,
-                kiai_times,
-                break_times,
-                beatmap.colors.combo_colors.clone(),
-                beatmap.diff_settings.clone(),
-                editor_config.clone(),
-            ),
+            MapState::from_beatmap(beatmap, bookmarks, editor_config.clone()),
             hitsound_thread_config,
         );
+        {
+            let mut edit_state = edit_state.write().expect("edit_state lock poisoned");
+            edit_state.fade_envelope = FadeEnvelope::from_config(&editor_config);
+            edit_state.snap_sources = SnapSources::from_config(&editor_config);
+        }
+        let session_tracker = SessionTracker::new(
+            edit_state
+                .read()
+                .expect("edit_state lock poisoned")
+                .current_map_state(),
+            earlier_editing_totals(&beatmapset.map_dir_name, &beatmap.version),
+        );
 
         let undo_button_hovered = Arc::new(AtomicBool::new(false));
         let undo_button_clicked = Arc::new(AtomicBool::new(false));
@@ -637,6 +992,7 @@ impl EditorApp {
                 let clicked = Arc::clone(&undo_button_clicked);
                 let viewport_width_state = Arc::clone(&viewport_width_state);
                 let viewport_height_state = Arc::clone(&viewport_height_state);
+                let timeline_height_percent_state = Arc::clone(&timeline_height_percent_state);
                 let mut pressed_inside = false;
                 let mut current_inside = false;
                 Box::new(move |event: DragEvent| match event {
@@ -652,6 +1008,9 @@ impl EditorApp {
                         }
                         let screen_w = viewport_width_state.load(Ordering::Acquire).max(1) as f64;
                         let screen_h = viewport_height_state.load(Ordering::Acquire).max(1) as f64;
+                        let timeline_height_percent =
+                            f32::from_bits(timeline_height_percent_state.load(Ordering::Acquire))
+                                as f64;
                         current_inside = EditorApp::undo_button_contains_cursor(
                             absolute_cursor_pos,
                             screen_w,
@@ -694,6 +1053,7 @@ impl EditorApp {
                 let activate_requested = Arc::clone(&current_state_button_activate_requested);
                 let viewport_width_state = Arc::clone(&viewport_width_state);
                 let viewport_height_state = Arc::clone(&viewport_height_state);
+                let timeline_height_percent_state = Arc::clone(&timeline_height_percent_state);
                 let mut pressed_inside = false;
                 let mut current_inside = false;
                 Box::new(move |event: DragEvent| match event {
@@ -709,6 +1069,9 @@ impl EditorApp {
                         }
                         let screen_w = viewport_width_state.load(Ordering::Acquire).max(1) as f64;
                         let screen_h = viewport_height_state.load(Ordering::Acquire).max(1) as f64;
+                        let timeline_height_percent =
+                            f32::from_bits(timeline_height_percent_state.load(Ordering::Acquire))
+                                as f64;
                         current_inside = EditorApp::current_state_button_contains_cursor(
                             absolute_cursor_pos,
                             screen_w,
@@ -751,6 +1114,7 @@ impl EditorApp {
                 let clicked_row = Arc::clone(&redo_buttons_clicked_row);
                 let viewport_width_state = Arc::clone(&viewport_width_state);
                 let viewport_height_state = Arc::clone(&viewport_height_state);
+                let timeline_height_percent_state = Arc::clone(&timeline_height_percent_state);
                 let mut pressed_row: Option<usize> = None;
                 let mut current_row: Option<usize> = None;
                 Box::new(move |event: DragEvent| match event {
@@ -766,6 +1130,9 @@ impl EditorApp {
                         }
                         let screen_w = viewport_width_state.load(Ordering::Acquire).max(1) as f64;
                         let screen_h = viewport_height_state.load(Ordering::Acquire).max(1) as f64;
+                        let timeline_height_percent =
+                            f32::from_bits(timeline_height_percent_state.load(Ordering::Acquire))
+                                as f64;
                         current_row = EditorApp::redo_button_index_from_cursor_y(
                             absolute_cursor_pos.y,
                             screen_w,
@@ -810,12 +1177,16 @@ impl EditorApp {
                 let hovered_row = Arc::clone(&redo_buttons_hovered_row);
                 let viewport_width_state = Arc::clone(&viewport_width_state);
                 let viewport_height_state = Arc::clone(&viewport_height_state);
+                let timeline_height_percent_state = Arc::clone(&timeline_height_percent_state);
                 Box::new(move |event: HoverEvent| match event {
                     HoverEvent::Move {
                         absolute_cursor_pos,
                     } => {
                         let screen_w = viewport_width_state.load(Ordering::Acquire).max(1) as f64;
                         let screen_h = viewport_height_state.load(Ordering::Acquire).max(1) as f64;
+                        let timeline_height_percent =
+                            f32::from_bits(timeline_height_percent_state.load(Ordering::Acquire))
+                                as f64;
                         let row = EditorApp::redo_button_index_from_cursor_y(
                             absolute_cursor_pos.y,
                             screen_w,
@@ -871,6 +1242,61 @@ impl EditorApp {
             Arc::clone(&playfield_screen_scale),
             Arc::clone(&playfield_screen_top_left),
         );
+        let slider_anchor_hitbox = hitbox_handlers::create_slider_anchor_drag_hitbox(
+            Arc::clone(&edit_state),
+            Arc::clone(&shift_held),
+            editor_config.appearance.layout.snap_distance_px,
+            editor_config
+                .appearance
+                .layout
+                .movable_snap_hitbox_radius_px,
+            editor_config.general.slider_anchor_grid_px,
+            Arc::clone(&playfield_screen_scale),
+            Arc::clone(&playfield_screen_top_left),
+        );
+        let timeline_object_drag_hitbox = hitbox_handlers::create_timeline_object_drag_hitbox(
+            Arc::clone(&edit_state),
+            Arc::clone(&audio),
+            Arc::clone(&top_timeline_window),
+            editor_config.general.beat_divisor,
+            Arc::clone(&alt_held),
+        );
+        let selection_left_scale_hitbox = hitbox_handlers::create_selection_scale_hitbox(
+            Arc::clone(&selection_left_scale_hovered),
+            Arc::clone(&selection_left_scale_dragging),
+            Arc::clone(&edit_state),
+            true,
+            Arc::clone(&shift_held),
+            Arc::clone(&playfield_screen_scale),
+            Arc::clone(&playfield_screen_top_left),
+        );
+        let selection_right_scale_hitbox = hitbox_handlers::create_selection_scale_hitbox(
+            Arc::clone(&selection_right_scale_hovered),
+            Arc::clone(&selection_right_scale_dragging),
+            Arc::clone(&edit_state),
+            false,
+            Arc::clone(&shift_held),
+            Arc::clone(&playfield_screen_scale),
+            Arc::clone(&playfield_screen_top_left),
+        );
+        let selection_left_rotation_hitbox = hitbox_handlers::create_selection_rotation_hitbox(
+            Arc::clone(&selection_left_rotation_hovered),
+            Arc::clone(&selection_left_rotation_dragging),
+            Arc::clone(&edit_state),
+            true,
+            Arc::clone(&ctrl_held),
+            Arc::clone(&playfield_screen_scale),
+            Arc::clone(&playfield_screen_top_left),
+        );
+        let selection_right_rotation_hitbox = hitbox_handlers::create_selection_rotation_hitbox(
+            Arc::clone(&selection_right_rotation_hovered),
+            Arc::clone(&selection_right_rotation_dragging),
+            Arc::clone(&edit_state),
+            false,
+            Arc::clone(&ctrl_held),
+            Arc::clone(&playfield_screen_scale),
+            Arc::clone(&playfield_screen_top_left),
+        );
         {
             let selection_left_bbox_screen = Arc::clone(&selection_left_bbox_screen);
             let selection_left_simple_hitbox = selection_left_bbox_hitbox.hitbox();
@@ -891,6 +1317,45 @@ impl EditorApp {
                 guard.as_ref().map(|bbox| bbox.contains(pos)).unwrap_or(false)
             });
         }
+        {
+            let selection_left_bbox_screen = Arc::clone(&selection_left_bbox_screen);
+            let selection_left_scale_simple_hitbox = selection_left_scale_hitbox.hitbox();
+            hitbox_handlers::wire_point_hit_test(&selection_left_scale_simple_hitbox, move |pos| {
+                let Ok(guard) = selection_left_bbox_screen.read() else {
+                    return false;
+                };
+                guard.as_ref().is_some_and(|bbox| {
+                    hitbox_handlers::selection_scale_handle_at(bbox, pos).is_some()
+                })
+            });
+        }
+        {
+            let selection_right_bbox_screen = Arc::clone(&selection_right_bbox_screen);
+            let selection_right_scale_simple_hitbox = selection_right_scale_hitbox.hitbox();
+            hitbox_handlers::wire_point_hit_test(&selection_right_scale_simple_hitbox, move |pos| {
+                let Ok(guard) = selection_right_bbox_screen.read() else {
+                    return false;
+                };
+                guard.as_ref().is_some_and(|bbox| {
+                    hitbox_handlers::selection_scale_handle_at(bbox, pos).is_some()
+                })
+            });
+        }
+        for (bbox_screen, rotation_hitbox) in [
+            (&selection_left_bbox_screen, &selection_left_rotation_hitbox),
+            (&selection_right_bbox_screen, &selection_right_rotation_hitbox),
+        ] {
+            let bbox_screen = Arc::clone(bbox_screen);
+            hitbox_handlers::wire_point_hit_test(&rotation_hitbox.hitbox(), move |pos| {
+                let Ok(guard) = bbox_screen.read() else {
+                    return false;
+                };
+                guard.as_ref().is_some_and(|bbox| {
+                    let handle = hitbox_handlers::selection_rotation_handle_pos(bbox);
+                    (pos - handle).len2() <= hitbox_handlers::ROTATION_HANDLE_HIT_RADIUS_PX.powi(2)
+                })
+            });
+        }
         {
             let selection_left_origin_playfield = Arc::clone(&selection_left_origin_playfield);
             let selection_left_origin_present = Arc::clone(&selection_left_origin_present);
@@ -931,14 +1396,62 @@ impl EditorApp {
                 (pos - origin_screen).len2() <= 26.0 * 26.0
             });
         }
+        {
+            // Anchors are only grabbed with ctrl held, a plain drag still moves the selection.
+            // In the edit path mode they are grabbed without it.
+            let edit_state = Arc::clone(&edit_state);
+            let ctrl_held = Arc::clone(&ctrl_held);
+            let tool_mode = Arc::clone(&tool_mode);
+            let playfield_screen_scale = Arc::clone(&playfield_screen_scale);
+            let playfield_screen_top_left = Arc::clone(&playfield_screen_top_left);
+            let grab_radius_px = editor_config
+                .appearance
+                .layout
+                .movable_snap_hitbox_radius_px;
+            let slider_anchor_simple_hitbox = slider_anchor_hitbox.hitbox();
+            hitbox_handlers::wire_point_hit_test(&slider_anchor_simple_hitbox, move |pos| {
+                if !ctrl_held.load(Ordering::Acquire)
+                    && !ToolMode::from_index(tool_mode.load(Ordering::Acquire)).grabs_anchors()
+                {
+                    return false;
+                }
+                let Ok(state) = edit_state.read() else {
+                    return false;
+                };
+                hitbox_handlers::slider_anchor_at(
+                    &state,
+                    pos,
+                    grab_radius_px,
+                    playfield_screen_scale.load(),
+                    playfield_screen_top_left.load(),
+                )
+                .is_some()
+            });
+        }
+        // Timing mode locks the objects, so their handles let clicks through to what's below.
+        for hitbox in [
+            selection_left_bbox_hitbox.hitbox(),
+            selection_right_bbox_hitbox.hitbox(),
+            selection_left_scale_hitbox.hitbox(),
+            selection_right_scale_hitbox.hitbox(),
+            selection_left_rotation_hitbox.hitbox(),
+            selection_right_rotation_hitbox.hitbox(),
+            selection_left_origin_hitbox.hitbox(),
+            selection_right_origin_hitbox.hitbox(),
+            slider_anchor_hitbox.hitbox(),
+            timeline_object_drag_hitbox.hitbox(),
+        ] {
+            let tool_mode = Arc::clone(&tool_mode);
+            hitbox.gate_hit_test(move || {
+                !ToolMode::from_index(tool_mode.load(Ordering::Acquire)).locks_objects()
+            });
+        }
         let (width, height) = (1280, 720);
         Self::update_hitbox_bounds(
             width,
             height,
             editor_config.general.playfield_scale.clamp(0.01, 1.0),
-            timeline_height_percent,
-            timeline_second_box_width_percent,
-            timeline_third_box_width_percent,
+            &hud_layout,
             &sound_volume_hitbox,
             &hitsound_volume_hitbox,
             &playfield_scale_hitbox,
@@ -948,31 +1461,56 @@ impl EditorApp {
             &current_state_button_hitbox,
             &redo_buttons_hitbox,
             &progress_bar_hitbox,
+            &timeline_object_drag_hitbox,
             &play_pause_button,
+            &stats_box_hitbox,
         );
+        sound_volume_hitbox.hitbox().set_tooltip("music volume - drag to change");
+        hitsound_volume_hitbox.hitbox().set_tooltip("hitsound volume - drag to change");
+        playfield_scale_hitbox.hitbox().set_tooltip("playfield scale - drag to change");
+        timeline_zoom_hitbox.hitbox().set_tooltip("timeline zoom - drag to change");
+        progress_bar_hitbox
+            .hitbox()
+            .set_tooltip("song position - click or drag to seek - ctrl wheel to zoom");
+        timeline_object_drag_hitbox
+            .hitbox()
+            .set_tooltip("drag the left selection in time - alt snaps to the marks");
+        play_pause_button.hitbox().set_tooltip("play or pause - space");
+        stats_box_hitbox.hitbox().set_tooltip("click a time to copy it");
+        undo_button_hitbox.hitbox().set_tooltip("undo to the previous state");
+        current_state_button_hitbox.hitbox().set_tooltip("current state - click to rename");
+        redo_buttons_hitbox.hitbox().set_tooltip("redo to this state");
+
         let mut mouse_handler = MouseHandler::new();
         mouse_handler.add_hitbox(global_interaction_hitbox.hitbox());
         mouse_handler.add_hitbox(sound_volume_hitbox.hitbox());
         mouse_handler.add_hitbox(hitsound_volume_hitbox.hitbox());
         mouse_handler.add_hitbox(playfield_scale_hitbox.hitbox());
         mouse_handler.add_hitbox(timeline_zoom_hitbox.hitbox());
+        mouse_handler.add_hitbox(stats_box_hitbox.hitbox());
         mouse_handler.add_hitbox(progress_bar_hitbox.hitbox());
+        mouse_handler.add_hitbox(timeline_object_drag_hitbox.hitbox());
         mouse_handler.add_hitbox(play_pause_button.hitbox());
         mouse_handler.add_hitbox(selection_right_bbox_hitbox.hitbox());
         mouse_handler.add_hitbox(selection_left_bbox_hitbox.hitbox());
+        mouse_handler.add_hitbox(selection_right_scale_hitbox.hitbox());
+        mouse_handler.add_hitbox(selection_left_scale_hitbox.hitbox());
+        mouse_handler.add_hitbox(selection_right_rotation_hitbox.hitbox());
+        mouse_handler.add_hitbox(selection_left_rotation_hitbox.hitbox());
         mouse_handler.add_hitbox(selection_right_origin_hitbox.hitbox());
         mouse_handler.add_hitbox(selection_left_origin_hitbox.hitbox());
+        mouse_handler.add_hitbox(slider_anchor_hitbox.hitbox());
+        mouse_handler.add_hitbox(Rc::clone(&tool_playfield_hitbox));
         mouse_handler.add_hitbox(undo_button_hitbox.hitbox());
         mouse_handler.add_hitbox(current_state_button_hitbox.hitbox());
         mouse_handler.add_hitbox(redo_buttons_hitbox.hitbox());
+        mouse_handler.add_hitbox(Rc::clone(&layout_edit_hitbox));
+
+        let title = window_title(&beatmapset, beatmap);
 
         return Some(Self {
-            title: format!(
-                "osu editor | {} - {} [{}]",
-                beatmapset.beatmapset.title,
-                beatmapset.beatmapset.artist,
-                beatmapset.beatmapset.creator
-            ),
+            title,
+            diff_version: beatmap.version.clone(),
 
             edit_state,
 
@@ -991,6 +1529,7 @@ impl EditorApp {
             desired_sound_volume,
             desired_hitsound_volume,
             desired_fix_pitch,
+            wheel_seek_px: 0.0,
 
             sound_volume_hitbox,
             hitsound_volume_hitbox,
@@ -1001,11 +1540,26 @@ impl EditorApp {
             selection_right_bbox_hitbox,
             selection_left_origin_hitbox,
             selection_right_origin_hitbox,
+            selection_left_scale_hitbox,
+            selection_right_scale_hitbox,
+            selection_left_rotation_hitbox,
+            selection_right_rotation_hitbox,
             undo_button_hitbox,
             current_state_button_hitbox,
             redo_buttons_hitbox,
             progress_bar_hitbox,
+            timeline_object_drag_hitbox,
             play_pause_button,
+            play_pause_requested,
+            stats_box_hitbox,
+            stats_box_copy_requested,
+            tool_state: ToolState::new(),
+            tool_mode,
+            tool_click_requested,
+            tool_click_playfield_pos,
+            hovered_object: None,
+            onboarding_visible: !onboarding_seen(),
+            difficulty_simulation: DifficultySimulation::default(),
 
             mouse_handler,
             progress_bar_hitbox_hovered,
@@ -1021,6 +1575,17 @@ impl EditorApp {
             selection_right_origin_hovered,
             selection_left_origin_dragging,
             selection_right_origin_dragging,
+            selection_left_scale_hovered,
+            selection_right_scale_hovered,
+            selection_left_scale_dragging,
+            selection_right_scale_dragging,
+            selection_left_rotation_hovered,
+            selection_right_rotation_hovered,
+            selection_left_rotation_dragging,
+            selection_right_rotation_dragging,
+            shift_held,
+            ctrl_held,
+            alt_held,
             undo_button_hovered,
             undo_button_clicked,
             current_state_button_hovered,
@@ -1038,13 +1603,38 @@ impl EditorApp {
             playfield_screen_top_left,
             playfield_scale_state,
             timeline_zoom_state,
+            bottom_timeline_range,
+            top_timeline_window,
             viewport_width_state,
             viewport_height_state,
+            timeline_height_percent_state,
             drag_rect_left,
             drag_rect_right,
+            layout_edit_mode,
+            layout_drag,
+            layout_drag_target: None,
+            layout_edited: false,
             is_renaming_current_state: false,
-            current_state_name_input: String::new(),
+            text_input_target: TextInputTarget::CurrentStateName,
+            text_input: TextInput::new(),
+            copied_objects: Vec::new(),
+            clipboard: SystemClipboard::new(),
+            hitsound_routing,
+            gap_suggestions: Vec::new(),
+            watched_osu,
+            next_osu_watch_check: Instant::now() + OSU_WATCH_INTERVAL,
+            external_edit_pending: false,
+            slider_timing_fix_pending: false,
+            switch_to_recent: None,
+            recent_maps: Vec::new(),
+            external_tool_run: None,
+            selection_sets,
+            combo_proposal_measures: None,
+            session_tracker,
+            report_panel: None,
+            modifiers: ModifiersState::empty(),
             global_interaction_hitbox_hovered,
+            beatmapset,
         });
     }
 
@@ -1085,30 +1675,7 @@ impl EditorApp {
         self.height = size.height.max(1);
         self.viewport_width_state.store(self.width, Ordering::Release);
         self.viewport_height_state.store(self.height, Ordering::Release);
-        Self::update_hitbox_bounds(
-            self.width,
-            self.height,
-            self.current_playfield_scale(),
-            self.editor_config.appearance.layout.timeline_height_percent,
-            self.editor_config
-                .appearance
-                .layout
-                .timeline_second_box_width_percent,
-            self.editor_config
-                .appearance
-                .layout
-                .timeline_third_box_width_percent,
-            &self.sound_volume_hitbox,
-            &self.hitsound_volume_hitbox,
-            &self.playfield_scale_hitbox,
-            &self.timeline_zoom_hitbox,
-            &self.global_interaction_hitbox,
-            &self.undo_button_hitbox,
-            &self.current_state_button_hitbox,
-            &self.redo_buttons_hitbox,
-            &self.progress_bar_hitbox,
-            &self.play_pause_button,
-        );
+        self.refresh_hitbox_bounds();
 
         // Start paused; do not advance time until the user presses play.
         let gpu = GpuRenderer::new(
@@ -1126,6 +1693,21 @@ impl EditorApp {
             self.current_playfield_scale(),
             Arc::clone(&self.edit_state),
         ));
+        shared.set_flow_overlay_visible(self.editor_config.appearance.general.show_flow_overlay);
+        shared.set_frame_graph_visible(self.editor_config.appearance.general.show_frame_graph);
+        shared.set_kiai_effects_visible(self.editor_config.appearance.general.show_kiai_effects);
+        shared.set_potato_mode(self.editor_config.performance.potato_mode);
+        if self.editor_config.performance.potato_mode {
+            shared.request_msaa_samples(1);
+        }
+        shared.set_ignore_beatmap_combo_colors(
+            self.editor_config
+                .appearance
+                .general
+                .ignore_beatmap_combo_colors,
+        );
+        shared.set_view_preset(self.current_view_preset());
+        shared.set_convert_mode(self.current_convert_mode());
         self.render_shared = Some(Arc::clone(&shared));
         self.sync_overlay_rects_to_renderer();
 
@@ -1134,6 +1716,7 @@ impl EditorApp {
             Arc::clone(&shared),
             Arc::clone(&self.audio),
             self.editor_config.clone(),
+            skin.combo_colors.clone(),
             self.ui_start,
         ));
     }
@@ -1152,6 +1735,15 @@ impl ApplicationHandler for EditorApp {
         _window_id: WindowId,
         event: WindowEvent,
     ) {
+        if matches!(
+            event,
+            WindowEvent::KeyboardInput { .. }
+                | WindowEvent::MouseInput { .. }
+                | WindowEvent::MouseWheel { .. }
+                | WindowEvent::CursorMoved { .. }
+        ) {
+            self.session_tracker.note_activity(Instant::now());
+        }
         self.handle_kb_or_mouse_event(&event);
         self.sync_overlay_rects_to_renderer();
         match event {
@@ -1171,30 +1763,7 @@ impl ApplicationHandler for EditorApp {
                 self.height = size.height.max(1);
                 self.viewport_width_state.store(self.width, Ordering::Release);
                 self.viewport_height_state.store(self.height, Ordering::Release);
-                Self::update_hitbox_bounds(
-                    self.width,
-                    self.height,
-                    self.current_playfield_scale(),
-                    self.editor_config.appearance.layout.timeline_height_percent,
-                    self.editor_config
-                        .appearance
-                        .layout
-                        .timeline_second_box_width_percent,
-                    self.editor_config
-                        .appearance
-                        .layout
-                        .timeline_third_box_width_percent,
-                    &self.sound_volume_hitbox,
-                    &self.hitsound_volume_hitbox,
-                    &self.playfield_scale_hitbox,
-                    &self.timeline_zoom_hitbox,
-                    &self.global_interaction_hitbox,
-                    &self.undo_button_hitbox,
-                    &self.current_state_button_hitbox,
-                    &self.redo_buttons_hitbox,
-                    &self.progress_bar_hitbox,
-                    &self.play_pause_button,
-                );
+                self.refresh_hitbox_bounds();
                 self.mark_resize(self.width, self.height);
             }
             WindowEvent::Moved(_) => {}
@@ -1205,33 +1774,13 @@ impl ApplicationHandler for EditorApp {
                     self.height = size.height.max(1);
                     self.viewport_width_state.store(self.width, Ordering::Release);
                     self.viewport_height_state.store(self.height, Ordering::Release);
-                    Self::update_hitbox_bounds(
-                        self.width,
-                        self.height,
-                        self.current_playfield_scale(),
-                        self.editor_config.appearance.layout.timeline_height_percent,
-                        self.editor_config
-                            .appearance
-                            .layout
-                            .timeline_second_box_width_percent,
-                        self.editor_config
-                            .appearance
-                            .layout
-                            .timeline_third_box_width_percent,
-                        &self.sound_volume_hitbox,
-                        &self.hitsound_volume_hitbox,
-                        &self.playfield_scale_hitbox,
-                        &self.timeline_zoom_hitbox,
-                        &self.global_interaction_hitbox,
-                        &self.undo_button_hitbox,
-                        &self.current_state_button_hitbox,
-                        &self.redo_buttons_hitbox,
-                        &self.progress_bar_hitbox,
-                        &self.play_pause_button,
-                    );
+                    self.refresh_hitbox_bounds();
                     self.mark_resize(self.width, self.height);
                 }
             }
+            WindowEvent::DroppedFile(path) => {
+                self.set_background_from_file(&path);
+            }
             WindowEvent::RedrawRequested => {
                 let _ = event_loop;
             }
@@ -1255,7 +1804,10 @@ impl ApplicationHandler for EditorApp {
             event_loop.set_control_flow(ControlFlow::Wait);
             return;
         }
-        event_loop.set_control_flow(ControlFlow::Wait);
+        self.check_external_osu_edit();
+        self.check_slider_timing_fix();
+        self.check_external_tool_finished();
+        event_loop.set_control_flow(ControlFlow::WaitUntil(self.next_osu_watch_check));
     }
 
     fn new_events(&mut self, event_loop: &ActiveEventLoop, cause: winit::event::StartCause) {
@@ -1441,23 +1993,135 @@ impl EditorApp {
             .store((clamped as f32).to_bits(), Ordering::Release);
     }
 
-    fn update_hitbox_bounds(
-        width: u32,
-        height: u32,
-        playfield_scale: f64,
-        timeline_height_percent: f64,
-        timeline_second_box_width_percent: f64,
-        timeline_third_box_width_percent: f64,
-        sound_volume_hitbox: &Rc<RectHitbox>,
-        hitsound_volume_hitbox: &Rc<RectHitbox>,
-        playfield_scale_hitbox: &Rc<RectHitbox>,
+    // Jumps to the next configured zoom preset past the current zoom, which need not be a preset
+    // itself after dragging or scrolling the zoom box.
+    pub fn step_timeline_zoom_preset(&mut self, zoom_in: bool) {
+        let mut presets: Vec<f64> = self
+            .editor_config
+            .general
+            .timeline_zoom_presets
+            .iter()
+            .map(|zoom| zoom.clamp(0.1, 10.0))
+            .collect();
+        presets.sort_by(|a, b| a.total_cmp(b));
+        let current = self.current_timeline_zoom();
+        let next = if zoom_in {
+            presets.iter().find(|zoom| **zoom > current + 1e-3)
+        } else {
+            presets.iter().rev().find(|zoom| **zoom < current - 1e-3)
+        };
+        match next {
+            Some(zoom) => {
+                self.set_timeline_zoom(*zoom);
+                println!("Timeline zoom: {:.2}x", zoom);
+            }
+            None => println!("No timeline zoom preset past {:.2}x.", current),
+        }
+    }
+
+    // The whole song until the bottom timeline is zoomed into.
+    fn current_bottom_timeline_range(&self) -> BottomTimelineRange {
+        let range = self.bottom_timeline_range.load();
+        if range.y > range.x {
+            BottomTimelineRange {
+                start_ms: range.x,
+                end_ms: range.y,
+            }
+        } else {
+            BottomTimelineRange::full(self.audio.song_total().0)
+        }
+    }
+
+    // One notch halves or doubles the span the bottom timeline shows, keeping the time under
+    // the cursor in place.
+    pub fn zoom_bottom_timeline(&self, zoom_in: bool) {
+        let total_ms = self.audio.song_total().0;
+        if total_ms <= 0.0 {
+            return;
+        }
+        let (origin, size) = self.progress_bar_hitbox.bounds();
+        let anchor_frac = (self.mouse_handler.position().x - origin.x) / size.x.max(1.0);
+        let factor = if zoom_in { 0.5 } else { 2.0 };
+        let range = self
+            .current_bottom_timeline_range()
+            .zoomed(anchor_frac, factor, total_ms);
+        self.bottom_timeline_range.store(Vec2 {
+            x: range.start_ms,
+            y: range.end_ms,
+        });
+    }
+
+    // Keeps the top timeline's window inside the bottom timeline's range, so scrolling either
+    // one scrolls the other.
+    fn sync_bottom_timeline_range(&self) -> BottomTimelineRange {
+        let frame_layout = layout::compute_layout(
+            self.width.max(1) as f64,
+            self.height.max(1) as f64,
+            self.current_playfield_scale(),
+            &HudLayout::from_config(&self.editor_config.appearance.layout),
+        );
+        let (window_start_ms, window_end_ms) = top_timeline_window_ms(
+            &self.editor_config,
+            &frame_layout.top_timeline_rect,
+            self.current_timeline_zoom(),
+            self.audio.current_time().0,
+        );
+        self.top_timeline_window.store(Vec2 {
+            x: window_start_ms,
+            y: window_end_ms,
+        });
+        let total_ms = self.audio.song_total().0;
+        let range = self.current_bottom_timeline_range();
+        let range = if range.is_zoomed(total_ms) {
+            range.following(window_start_ms, window_end_ms, total_ms)
+        } else {
+            BottomTimelineRange::full(total_ms)
+        };
+        self.bottom_timeline_range.store(Vec2 {
+            x: range.start_ms,
+            y: range.end_ms,
+        });
+        range
+    }
+
+    fn refresh_hitbox_bounds(&self) {
+        Self::update_hitbox_bounds(
+            self.width,
+            self.height,
+            self.current_playfield_scale(),
+            &HudLayout::from_config(&self.editor_config.appearance.layout),
+            &self.sound_volume_hitbox,
+            &self.hitsound_volume_hitbox,
+            &self.playfield_scale_hitbox,
+            &self.timeline_zoom_hitbox,
+            &self.global_interaction_hitbox,
+            &self.undo_button_hitbox,
+            &self.current_state_button_hitbox,
+            &self.redo_buttons_hitbox,
+            &self.progress_bar_hitbox,
+            &self.timeline_object_drag_hitbox,
+            &self.play_pause_button,
+            &self.stats_box_hitbox,
+        );
+    }
+
+    fn update_hitbox_bounds(
+        width: u32,
+        height: u32,
+        playfield_scale: f64,
+        hud_layout: &HudLayout,
+        sound_volume_hitbox: &Rc<RectHitbox>,
+        hitsound_volume_hitbox: &Rc<RectHitbox>,
+        playfield_scale_hitbox: &Rc<RectHitbox>,
         timeline_zoom_hitbox: &Rc<RectHitbox>,
         global_interaction_hitbox: &Rc<RectHitbox>,
         undo_button_hitbox: &Rc<RectHitbox>,
         current_state_button_hitbox: &Rc<RectHitbox>,
         redo_buttons_hitbox: &Rc<RectHitbox>,
         progress_bar_hitbox: &Rc<RectHitbox>,
+        timeline_object_drag_hitbox: &Rc<RectHitbox>,
         play_pause_button: &Rc<SimpleButton>,
+        stats_box_hitbox: &Rc<RectHitbox>,
     ) {
         let screen_w = width.max(1);
         let screen_h = height.max(1);
@@ -1479,9 +2143,7 @@ impl EditorApp {
             screen_w as f64,
             screen_h as f64,
             playfield_scale,
-            timeline_height_percent,
-            timeline_second_box_width_percent,
-            timeline_third_box_width_percent,
+            hud_layout,
         );
         let _legacy_split_hitboxes = (&layout.left_hitbox_rect, &layout.right_hitbox_rect);
 
@@ -1507,29 +2169,36 @@ impl EditorApp {
         let (undo_top_left, undo_size) = Self::undo_button_bounds(
             screen_w as f64,
             screen_h as f64,
-            timeline_height_percent,
+            hud_layout.timeline_height_percent,
         );
         undo_button_hitbox.set_bounds(undo_top_left, undo_size);
 
         let (current_state_top_left, current_state_size) = Self::current_state_button_bounds(
             screen_w as f64,
             screen_h as f64,
-            timeline_height_percent,
+            hud_layout.timeline_height_percent,
         );
         current_state_button_hitbox.set_bounds(current_state_top_left, current_state_size);
 
         let (redo_top_left, redo_size) = Self::redo_buttons_hitbox_bounds(
             screen_w as f64,
             screen_h as f64,
-            timeline_height_percent,
+            hud_layout.timeline_height_percent,
         );
         redo_buttons_hitbox.set_bounds(redo_top_left, redo_size);
 
         let (timeline_top_left, timeline_size) = rect_to_bounds(&layout.timeline_hitbox_rect);
         progress_bar_hitbox.set_bounds(timeline_top_left, timeline_size);
 
+        let (top_timeline_top_left, top_timeline_size) =
+            rect_to_bounds(&layout.top_timeline_hitbox_rect);
+        timeline_object_drag_hitbox.set_bounds(top_timeline_top_left, top_timeline_size);
+
         let (play_pause_top_left, play_pause_size) = rect_to_bounds(&layout.play_pause_button_rect);
         play_pause_button.set_bounds(play_pause_top_left, play_pause_size);
+
+        let (stats_box_top_left, stats_box_size) = rect_to_bounds(&layout.stats_box_rect);
+        stats_box_hitbox.set_bounds(stats_box_top_left, stats_box_size);
     }
 
     fn selection_bbox_to_screen_bbox4(playfield_rect: &layout::Rect, bbox: &BBox4) -> BBox4 {
@@ -1548,15 +2217,7 @@ impl EditorApp {
             self.width.max(1) as f64,
             self.height.max(1) as f64,
             self.current_playfield_scale(),
-            self.editor_config.appearance.layout.timeline_height_percent,
-            self.editor_config
-                .appearance
-                .layout
-                .timeline_second_box_width_percent,
-            self.editor_config
-                .appearance
-                .layout
-                .timeline_third_box_width_percent,
+            &HudLayout::from_config(&self.editor_config.appearance.layout),
         );
 
         self.playfield_screen_scale.store(Vec2 {
@@ -1603,6 +2264,29 @@ impl EditorApp {
                     y: (aabb.y[1] - aabb.y[0]).max(0.0),
                 },
             );
+            let rotation_handle = hitbox_handlers::selection_rotation_handle_pos(&screen_bbox);
+            let rotation_r = hitbox_handlers::ROTATION_HANDLE_HIT_RADIUS_PX;
+            self.selection_left_rotation_hitbox.set_bounds(
+                Vec2 {
+                    x: rotation_handle.x - rotation_r,
+                    y: rotation_handle.y - rotation_r,
+                },
+                Vec2 {
+                    x: 2.0 * rotation_r,
+                    y: 2.0 * rotation_r,
+                },
+            );
+            let handle_r = hitbox_handlers::SCALE_HANDLE_HIT_RADIUS_PX;
+            self.selection_left_scale_hitbox.set_bounds(
+                Vec2 {
+                    x: aabb.x[0] - handle_r,
+                    y: aabb.y[0] - handle_r,
+                },
+                Vec2 {
+                    x: (aabb.x[1] - aabb.x[0]).max(0.0) + 2.0 * handle_r,
+                    y: (aabb.y[1] - aabb.y[0]).max(0.0) + 2.0 * handle_r,
+                },
+            );
         } else {
             if let Ok(mut guard) = self.selection_left_bbox_screen.write() {
                 *guard = None;
@@ -1611,6 +2295,18 @@ impl EditorApp {
                 .set_bounds(Vec2 { x: -1.0, y: -1.0 }, Vec2 { x: 0.0, y: 0.0 });
             self.selection_left_bbox_hovered
                 .store(false, Ordering::Release);
+            self.selection_left_scale_hitbox
+                .set_bounds(Vec2 { x: -1.0, y: -1.0 }, Vec2 { x: 0.0, y: 0.0 });
+            self.selection_left_scale_hovered
+                .store(false, Ordering::Release);
+            self.selection_left_scale_dragging
+                .store(false, Ordering::Release);
+            self.selection_left_rotation_hitbox
+                .set_bounds(Vec2 { x: -1.0, y: -1.0 }, Vec2 { x: 0.0, y: 0.0 });
+            self.selection_left_rotation_hovered
+                .store(false, Ordering::Release);
+            self.selection_left_rotation_dragging
+                .store(false, Ordering::Release);
         }
 
         if let Some(left_origin) = left_origin {
@@ -1650,6 +2346,29 @@ impl EditorApp {
                     y: (aabb.y[1] - aabb.y[0]).max(0.0),
                 },
             );
+            let rotation_handle = hitbox_handlers::selection_rotation_handle_pos(&screen_bbox);
+            let rotation_r = hitbox_handlers::ROTATION_HANDLE_HIT_RADIUS_PX;
+            self.selection_right_rotation_hitbox.set_bounds(
+                Vec2 {
+                    x: rotation_handle.x - rotation_r,
+                    y: rotation_handle.y - rotation_r,
+                },
+                Vec2 {
+                    x: 2.0 * rotation_r,
+                    y: 2.0 * rotation_r,
+                },
+            );
+            let handle_r = hitbox_handlers::SCALE_HANDLE_HIT_RADIUS_PX;
+            self.selection_right_scale_hitbox.set_bounds(
+                Vec2 {
+                    x: aabb.x[0] - handle_r,
+                    y: aabb.y[0] - handle_r,
+                },
+                Vec2 {
+                    x: (aabb.x[1] - aabb.x[0]).max(0.0) + 2.0 * handle_r,
+                    y: (aabb.y[1] - aabb.y[0]).max(0.0) + 2.0 * handle_r,
+                },
+            );
         } else {
             if let Ok(mut guard) = self.selection_right_bbox_screen.write() {
                 *guard = None;
@@ -1658,6 +2377,18 @@ impl EditorApp {
                 .set_bounds(Vec2 { x: -1.0, y: -1.0 }, Vec2 { x: 0.0, y: 0.0 });
             self.selection_right_bbox_hovered
                 .store(false, Ordering::Release);
+            self.selection_right_scale_hitbox
+                .set_bounds(Vec2 { x: -1.0, y: -1.0 }, Vec2 { x: 0.0, y: 0.0 });
+            self.selection_right_scale_hovered
+                .store(false, Ordering::Release);
+            self.selection_right_scale_dragging
+                .store(false, Ordering::Release);
+            self.selection_right_rotation_hitbox
+                .set_bounds(Vec2 { x: -1.0, y: -1.0 }, Vec2 { x: 0.0, y: 0.0 });
+            self.selection_right_rotation_hovered
+                .store(false, Ordering::Release);
+            self.selection_right_rotation_dragging
+                .store(false, Ordering::Release);
         }
 
         if let Some(right_origin) = right_origin {
@@ -1686,6 +2417,22 @@ impl EditorApp {
 
     fn update_selection_bbox_cursor(&self) {
         if let Some(window) = self.window.as_ref() {
+            if self.selection_left_rotation_dragging.load(Ordering::Acquire)
+                || self.selection_right_rotation_dragging.load(Ordering::Acquire)
+            {
+                window.set_cursor(winit::window::CursorIcon::Grabbing);
+                return;
+            }
+            if self.selection_left_rotation_hovered.load(Ordering::Acquire)
+                || self.selection_right_rotation_hovered.load(Ordering::Acquire)
+            {
+                window.set_cursor(winit::window::CursorIcon::Grab);
+                return;
+            }
+            if let Some(icon) = self.selection_scale_handle_cursor() {
+                window.set_cursor(icon);
+                return;
+            }
             let selection_bbox_hovered = self.selection_left_bbox_hovered.load(Ordering::Acquire)
                 || self.selection_right_bbox_hovered.load(Ordering::Acquire)
                 || self.selection_left_origin_hovered.load(Ordering::Acquire)
@@ -1698,197 +2445,3017 @@ impl EditorApp {
         }
     }
 
+    // Resize cursor for the hovered or dragged scale handle, by the handle's screen direction.
+    fn selection_scale_handle_cursor(&self) -> Option<winit::window::CursorIcon> {
+        use winit::window::CursorIcon;
+        let cursor = self.mouse_handler.position();
+        let sides = [
+            (
+                &self.selection_left_bbox_screen,
+                &self.selection_left_scale_hovered,
+                &self.selection_left_scale_dragging,
+            ),
+            (
+                &self.selection_right_bbox_screen,
+                &self.selection_right_scale_hovered,
+                &self.selection_right_scale_dragging,
+            ),
+        ];
+        for (bbox_screen, hovered, dragging) in sides {
+            if !hovered.load(Ordering::Acquire) && !dragging.load(Ordering::Acquire) {
+                continue;
+            }
+            let Ok(guard) = bbox_screen.read() else {
+                continue;
+            };
+            let Some(bbox) = guard.as_ref() else {
+                continue;
+            };
+            let points = bbox.handle_points();
+            let handle = hitbox_handlers::selection_scale_handle_at(bbox, cursor).unwrap_or(0);
+            let dir = points[handle] - bbox.center();
+            let angle = dir.y.atan2(dir.x).to_degrees().rem_euclid(180.0);
+            return Some(match ((angle + 22.5) / 45.0) as u32 % 4 {
+                0 => CursorIcon::EwResize,
+                1 => CursorIcon::NwseResize,
+                2 => CursorIcon::NsResize,
+                _ => CursorIcon::NeswResize,
+            });
+        }
+        None
+    }
+
     fn begin_current_state_rename(&mut self) {
-        self.current_state_name_input.clear();
-        self.is_renaming_current_state = true;
+        self.begin_text_input(TextInputTarget::CurrentStateName);
     }
 
-    pub fn cancel_current_state_rename(&mut self) {
-        self.is_renaming_current_state = false;
-        self.current_state_name_input.clear();
+    pub fn begin_new_difficulty(&mut self, copy_objects: bool) {
+        self.begin_text_input(TextInputTarget::NewDifficultyName { copy_objects });
+        println!("Type the new difficulty name and press Enter (Esc to cancel).");
     }
 
-    pub fn commit_current_state_rename(&mut self) {
-        if !self.is_renaming_current_state {
-            return;
-        }
-        self.edit_state
-            .write()
-            .expect("edit_state lock poisoned")
-            .rename_current_state(self.current_state_name_input.clone());
-        self.cancel_current_state_rename();
+    pub fn begin_save_pattern(&mut self) {
+        self.begin_text_input(TextInputTarget::PatternName);
+        println!("Type a name for the selected pattern and press Enter (Esc to cancel).");
     }
 
-    pub fn append_current_state_rename_text(&mut self, text: &str) {
-        if !self.is_renaming_current_state {
+    pub fn begin_insert_pattern(&mut self) {
+        if self.objects_locked() {
             return;
         }
-        const MAX_LEN: usize = 40;
-        if self.current_state_name_input.len() >= MAX_LEN {
+        let patterns = list_patterns();
+        if patterns.is_empty() {
+            println!("No patterns saved in patterns/ yet.");
             return;
         }
-        for ch in text.chars() {
-            if ch.is_control() {
-                continue;
-            }
-            if self.current_state_name_input.len() >= MAX_LEN {
-                break;
-            }
-            self.current_state_name_input.push(ch);
-        }
+        println!("Patterns: {}", patterns.join(", "));
+        println!("Type \"<name> [scale%] [rotation°]\" and press Enter (Esc to cancel).");
+        self.begin_text_input(TextInputTarget::PatternInsert);
     }
 
-    pub fn backspace_current_state_rename(&mut self) {
-        if !self.is_renaming_current_state {
+    pub fn begin_inspect_selection(&mut self) {
+        if self.objects_locked() {
             return;
         }
-        self.current_state_name_input.pop();
-    }
-
-    pub fn is_current_state_rename_active(&self) -> bool {
-        self.is_renaming_current_state
+        let Some(panel) = self.inspector_panel() else {
+            self.show_report(ReportPanel::new("select an object to inspect"));
+            return;
+        };
+        self.show_report(panel);
+        self.begin_text_input(TextInputTarget::ObjectFields);
     }
 
-    pub fn sync_overlay_rects_to_renderer(&mut self) {
-        if self
-            .current_state_button_activate_requested
-            .swap(false, Ordering::AcqRel)
-        {
-            self.begin_current_state_rename();
+    // Exact values of the inspected object and how to type new ones.
+    fn inspector_panel(&self) -> Option<ReportPanel> {
+        let (_, hit_object) = self
+            .edit_state
+            .read()
+            .expect("edit_state lock poisoned")
+            .inspected_object()?;
+        let pos = hit_object.position();
+        let mut panel = ReportPanel::new("object inspector - enter applies");
+        panel.push(&format!("x {:.2}  y {:.2}", pos.x, pos.y));
+        panel.push(&format!("t {:.2}", hit_object.time()));
+        match &hit_object {
+            HitObject::Slider(s) => {
+                panel.push(&format!(
+                    "len {:.2}  dur {:.2}",
+                    s.length_pixels,
+                    s.end_time() - s.time
+                ));
+                panel.push(self.slider_beats_readout(s).trim_start());
+            }
+            HitObject::Spinner(sp) => panel.push(&format!("dur {:.2}", sp.end_time - sp.time)),
+            HitObject::Circle(_) => {}
         }
-        self.update_selection_bbox_hitbox_bounds();
-        self.update_selection_bbox_cursor();
-        if let Some(shared) = self.render_shared.as_ref() {
-            shared.set_playfield_scale(self.current_playfield_scale());
-            shared.set_timeline_zoom(self.current_timeline_zoom());
-            shared.set_overlay_rect_left(self.drag_rect_left.rect());
-            shared.set_overlay_rect_right(self.drag_rect_right.rect());
-            shared.set_play_pause_button_hovered(self.play_pause_button.is_hovered());
-            shared.set_play_pause_button_clicked(self.play_pause_button.is_clicked());
-            shared.set_undo_button_hovered(self.undo_button_hovered.load(Ordering::Acquire));
-            shared.set_undo_button_clicked(self.undo_button_clicked.load(Ordering::Acquire));
-            shared.set_current_state_button_hovered(
-                self.current_state_button_hovered.load(Ordering::Acquire),
-            );
-            shared.set_current_state_button_clicked(
-                self.current_state_button_clicked.load(Ordering::Acquire),
-            );
-            shared.set_current_state_rename_state(
-                self.is_renaming_current_state,
-                self.current_state_name_input.clone(),
-            );
-            let redo_hover_row = self.redo_buttons_hovered_row.load(Ordering::Acquire);
-            shared.set_redo_button_hovered_row(if redo_hover_row == u32::MAX {
-                None
-            } else {
-                Some(redo_hover_row)
-            });
-            let redo_click_row = self.redo_buttons_clicked_row.load(Ordering::Acquire);
-            shared.set_redo_button_clicked_row(if redo_click_row == u32::MAX {
-                None
-            } else {
-                Some(redo_click_row)
-            });
-            shared.set_selection_left_bbox_hovered(
-                self.selection_left_bbox_hovered.load(Ordering::Acquire),
-            );
-            shared.set_selection_right_bbox_hovered(
-                self.selection_right_bbox_hovered.load(Ordering::Acquire),
-            );
-            shared.set_selection_left_bbox_dragging(
-                self.selection_left_bbox_dragging.load(Ordering::Acquire),
-            );
-            shared.set_selection_right_bbox_dragging(
-                self.selection_right_bbox_dragging.load(Ordering::Acquire),
-            );
-            shared.set_selection_left_origin_hovered(
-                self.selection_left_origin_hovered.load(Ordering::Acquire),
-            );
-            shared.set_selection_right_origin_hovered(
-                self.selection_right_origin_hovered.load(Ordering::Acquire),
-            );
-            shared.set_selection_left_origin_dragging(
-                self.selection_left_origin_dragging.load(Ordering::Acquire),
-            );
-            shared.set_selection_right_origin_dragging(
-                self.selection_right_origin_dragging.load(Ordering::Acquire),
-            );
-            shared.set_cursor_pos(self.mouse_handler.position());
+        panel.push("type x.. y.. t.. len.. dur.. or x y t len");
+        if matches!(hit_object, HitObject::Slider(_)) {
+            panel.push("beats.. scales the path - beats.. sv sets sv");
         }
+        Some(panel)
     }
 
-    pub fn clear_selections(&self) {
-        let mut edit_state = self.edit_state.write().expect("edit_state lock poisoned");
-        edit_state.clear_selections();
+    // Duration in beats of the red line at the head, and whether the end is on the beat divisor.
+    fn slider_beats_readout(&self, slider: &Slider) -> String {
+        let timing = self.current_timing();
+        let Some(red_line) = timing.get_lines_at_time(slider.time).0 else {
+            return String::new();
+        };
+        let divisor = self.editor_config.general.beat_divisor.max(1);
+        let end_time = slider.end_time();
+        let beats = (end_time - slider.time) / red_line.beat_length;
+        let snap = match timing.nearest_tick(end_time, divisor as f64) {
+            Some(tick) if (end_time - tick).abs() < 1.0 => format!("end snapped to 1/{}", divisor),
+            Some(tick) => format!("end {:+.1}ms off 1/{}", end_time - tick, divisor),
+            None => "end unsnapped".to_string(),
+        };
+        format!(" beats={:.3} ({})", beats, snap)
     }
 
-    pub fn select_all_to_left(&self) {
-        let mut edit_state = self.edit_state.write().expect("edit_state lock poisoned");
-        edit_state.select_all_to_left();
+    fn current_timing(&self) -> Arc<Timing> {
+        let edit_state = self.edit_state.read().expect("edit_state lock poisoned");
+        Arc::clone(&edit_state.current_map_state().timing)
     }
 
-    pub fn select_visible_to_left(&self) {
-        let time_ms = self.audio.current_time_ms();
-        let mut edit_state = self.edit_state.write().expect("edit_state lock poisoned");
-        edit_state.select_visible_to_left(time_ms);
+    fn analysis_dir(&self) -> PathBuf {
+        Path::new("saves")
+            .join(&self.beatmapset.map_dir_name)
+            .join("analysis")
     }
 
-    pub fn swap_selections(&self) {
-        let mut edit_state = self.edit_state.write().expect("edit_state lock poisoned");
-        edit_state.swap_selections();
+    pub fn begin_import_object_list(&mut self) {
+        if self.objects_locked() {
+            return;
+        }
+        let lists = scan_folder(&self.analysis_dir(), Some(false), Some(&vec![".json"]));
+        if lists.is_empty() {
+            println!("No object lists found in {}.", self.analysis_dir().display());
+            return;
+        }
+        println!("Object lists: {}", lists.join(", "));
+        println!("Type \"<name> [replace]\" and press Enter (Esc to cancel).");
+        self.begin_text_input(TextInputTarget::ObjectListImport);
     }
 
-    pub fn toggle_selection_position_lock(&self, left: bool) {
-        let mut edit_state = self.edit_state.write().expect("edit_state lock poisoned");
-        edit_state.toggle_selection_origin_lock(left);
+    pub fn begin_copy_timing(&mut self) {
+        let difficulties: Vec<&str> = self
+            .beatmapset
+            .beatmaps
+            .iter()
+            .filter(|b| b.version != self.diff_version)
+            .map(|b| b.version.as_str())
+            .collect();
+        let osu_files = scan_folder(Path::new("imports"), Some(false), Some(&vec![".osu"]));
+        if difficulties.is_empty() && osu_files.is_empty() {
+            println!("No other difficulties or .osu files in imports to copy timing from.");
+            return;
+        }
+        println!("Difficulties: {}", difficulties.join(", "));
+        println!(".osu files in imports: {}", osu_files.join(", "));
+        println!("Type \"<difficulty or file> [green]\" and press Enter (Esc to cancel).");
+        println!("Only red lines are copied unless \"green\" is given.");
+        self.begin_text_input(TextInputTarget::CopyTiming);
     }
 
-    pub fn toggle_selection_scale_lock(&self, left: bool) {
-        let mut edit_state = self.edit_state.write().expect("edit_state lock poisoned");
-        edit_state.toggle_selection_scale_lock(left);
+    // Source is another difficulty of this set, else a .osu file in imports (a prefix of the
+    // file name is enough), else a path.
+    fn copy_timing(&mut self, input: String) {
+        let input = input.trim();
+        let (source, include_inherited) = match input.strip_suffix(" green") {
+            Some(source) => (source.trim(), true),
+            None => (input, false),
+        };
+        if source.is_empty() {
+            return;
+        }
+        let source_timing = match self
+            .beatmapset
+            .beatmaps
+            .iter()
+            .find(|b| b.version.eq_ignore_ascii_case(source))
+        {
+            Some(beatmap) => Some(beatmap.timing.clone()),
+            None => {
+                let imported = scan_folder(Path::new("imports"), Some(false), Some(&vec![".osu"]))
+                    .into_iter()
+                    .find(|name| name.starts_with(source));
+                match imported {
+                    Some(name) => read_osu_timing(&Path::new("imports").join(name)),
+                    None => read_osu_timing(Path::new(source)),
+                }
+            }
+        };
+        let Some(source_timing) = source_timing else {
+            return;
+        };
+        if !source_timing
+            .timing_points
+            .iter()
+            .any(|tp| matches!(tp, TimingPoint::RedLine(_)))
+        {
+            println!("{} has no red lines, timing left unchanged.", source);
+            return;
+        }
+
+        let map_state = {
+            let mut edit_state = self.edit_state.write().expect("edit_state lock poisoned");
+            let timing_points = edit_state
+                .current_map_state()
+                .timing
+                .copied_from(&source_timing, include_inherited);
+            let count = timing_points.len();
+            edit_state.replace_timing(timing_points);
+            println!("Copied timing from {} ({} timing point(s)).", source, count);
+            edit_state.current_map_state()
+        };
+        self.report_desynced_sliders(&map_state);
     }
 
-    pub fn rotate_selection_left_90(&self, left_selection: bool) {
-        let mut edit_state = self.edit_state.write().expect("edit_state lock poisoned");
-        edit_state.rotate_selection_left_90(left_selection);
+    // The same choice `offer_slider_timing_fix` gives on open, asked once the timing prompt
+    // has closed.
+    fn report_desynced_sliders(&mut self, map_state: &MapState) {
+        if let Some(template) = self
+            .beatmapset
+            .beatmaps
+            .iter()
+            .find(|b| b.version == self.diff_version)
+        {
+            let desynced = find_desynced_sliders(&map_state.to_beatmap(template));
+            if !desynced.is_empty() {
+                println!("{} slider(s) no longer match the new timing.", desynced.len());
+                self.slider_timing_fix_pending = true;
+            }
+        }
     }
 
-    pub fn rotate_selection_right_90(&self, left_selection: bool) {
-        let mut edit_state = self.edit_state.write().expect("edit_state lock poisoned");
-        edit_state.rotate_selection_right_90(left_selection);
+    fn check_slider_timing_fix(&mut self) {
+        if self.slider_timing_fix_pending && !self.is_renaming_current_state {
+            self.slider_timing_fix_pending = false;
+            println!("Type \"lengths\" to recompute slider lengths or \"green\" to insert");
+            println!("compensating green lines (Esc leaves them as is).");
+            self.begin_text_input(TextInputTarget::SliderTimingFix);
+        }
     }
 
-    pub fn flip_selection_horizontal(&self) {
+    // Applied to the difficulty as one undo step.
+    fn apply_slider_timing_fix(&mut self, input: String) {
+        let fix = match input.trim().to_ascii_lowercase().as_str() {
+            "lengths" => SliderTimingFix::RecomputeLengths,
+            "green" => SliderTimingFix::InsertGreenLines,
+            _ => {
+                println!("Left the sliders as they are.");
+                return;
+            }
+        };
+        let Some(template) = self
+            .beatmapset
+            .beatmaps
+            .iter()
+            .find(|b| b.version == self.diff_version)
+        else {
+            return;
+        };
         let mut edit_state = self.edit_state.write().expect("edit_state lock poisoned");
-        edit_state.flip_selection_horizontal();
+        let map_state = edit_state.current_map_state();
+        let mut beatmap = map_state.to_beatmap(template);
+        let desynced = find_desynced_sliders(&beatmap);
+        if desynced.is_empty() {
+            return;
+        }
+        let fixed = fix_desynced_sliders(&mut beatmap, &desynced, fix);
+        let bookmarks = map_state.bookmarks.iter().copied().collect();
+        edit_state.replace_map_state(MapState::from_beatmap(
+            &beatmap,
+            bookmarks,
+            self.editor_config.clone(),
+        ));
+        println!("Fixed {} slider(s).", fixed);
     }
 
-    pub fn flip_selection_vertical(&self) {
-        let mut edit_state = self.edit_state.write().expect("edit_state lock poisoned");
+    pub fn begin_timing_tools(&mut self) {
+        println!("Timing tools, each is one undo step:");
+        println!("  shift <ms>      move the timing and every object by <ms>");
+        println!("  scale <factor>  multiply every BPM by <factor>, objects follow their beats");
+        println!("  merge           remove green lines that change nothing");
+        println!("Type one and press Enter (Esc to cancel).");
+        self.begin_text_input(TextInputTarget::TimingTools);
+    }
+
+    fn apply_timing_tool(&mut self, input: String) {
+        let mut parts = input.split_whitespace();
+        let command = parts.next().unwrap_or("");
+        let value = parts.next().map(|value| value.parse::<f64>());
+        let timing = self.current_timing();
+        let map_state = match (command, value) {
+            ("shift", Some(Ok(offset_ms))) if offset_ms.is_finite() => {
+                println!("Moved the timing and every object by {}ms.", offset_ms);
+                self.remap_current_difficulty(&TimeRemap::shifted(offset_ms))
+            }
+            ("scale", Some(Ok(factor))) if factor.is_finite() && factor > 0.0 => {
+                let Some(remap) = TimeRemap::bpm_scaled(&timing, factor) else {
+                    println!("No red lines to scale.");
+                    return;
+                };
+                println!("Multiplied every BPM by {}, objects stay on their beats.", factor);
+                self.remap_current_difficulty(&remap)
+            }
+            ("merge", None) => {
+                let timing_points = timing.without_redundant_green_lines();
+                let removed = timing.timing_points.len() - timing_points.len();
+                if removed == 0 {
+                    println!("No redundant green lines.");
+                    return;
+                }
+                println!("Removed {} redundant green line(s).", removed);
+                let mut edit_state = self.edit_state.write().expect("edit_state lock poisoned");
+                edit_state.replace_timing(timing_points);
+                Some(edit_state.current_map_state())
+            }
+            _ => {
+                println!("Expected \"shift <ms>\", \"scale <factor>\" or \"merge\".");
+                return;
+            }
+        };
+        if let Some(map_state) = map_state {
+            self.report_desynced_sliders(&map_state);
+        }
+    }
+
+    // Timing, objects, breaks and bookmarks all go through `remap` as one undo step, the same
+    // way the time shift tools move a saved map.
+    fn remap_current_difficulty(&self, remap: &TimeRemap) -> Option<Arc<MapState>> {
+        let template = self
+            .beatmapset
+            .beatmaps
+            .iter()
+            .find(|b| b.version == self.diff_version)?;
+        let mut edit_state = self.edit_state.write().expect("edit_state lock poisoned");
+        let map_state = edit_state.current_map_state();
+        let mut beatmap = map_state.to_beatmap(template);
+        let scope = TimeShiftScope {
+            timing_points: true,
+            bookmarks: true,
+            breaks: true,
+        };
+        remap_beatmap(&mut beatmap, remap, &scope);
+        let bookmarks = map_state.bookmarks.iter().map(|&ms| remap.apply(ms)).collect();
+        edit_state.replace_map_state(MapState::from_beatmap(
+            &beatmap,
+            bookmarks,
+            self.editor_config.clone(),
+        ));
+        Some(edit_state.current_map_state())
+    }
+
+    pub fn begin_general_settings(&mut self) {
+        let Some(beatmap) = self
+            .beatmapset
+            .beatmaps
+            .iter()
+            .find(|b| b.version == self.diff_version)
+        else {
+            println!("Current difficulty not found in beatmapset.");
+            return;
+        };
+        let on_off = |value: bool| if value { "on" } else { "off" };
+        println!(
+            "countdown={} letterbox={} widescreen={} samplerate={} epilepsy={} skinsprites={}",
+            on_off(beatmap.general.countdown),
+            on_off(beatmap.general.letterbox_in_breaks),
+            on_off(beatmap.general.widescreen_storyboard),
+            on_off(beatmap.general.samples_match_playback_rate),
+            on_off(beatmap.general.epilepsy_warning),
+            on_off(beatmap.general.use_skin_sprites)
+        );
+        println!("Type \"<setting> [on|off] ...\" and press Enter (Esc to cancel).");
+        println!("A setting without on/off is toggled.");
+        self.begin_text_input(TextInputTarget::GeneralSettings);
+    }
+
+    fn apply_general_settings(&mut self, input: String) {
+        let Some(beatmap) = self
+            .beatmapset
+            .beatmaps
+            .iter_mut()
+            .find(|b| b.version == self.diff_version)
+        else {
+            println!("Current difficulty not found in beatmapset.");
+            return;
+        };
+        let mut general = beatmap.general.clone();
+        let mut words = input.split_whitespace().peekable();
+        while let Some(setting) = words.next() {
+            let flag = match setting.to_ascii_lowercase().as_str() {
+                "countdown" => &mut general.countdown,
+                "letterbox" => &mut general.letterbox_in_breaks,
+                "widescreen" => &mut general.widescreen_storyboard,
+                "samplerate" => &mut general.samples_match_playback_rate,
+                "epilepsy" => &mut general.epilepsy_warning,
+                "skinsprites" => &mut general.use_skin_sprites,
+                _ => {
+                    println!(
+                        "Unknown setting {}, general settings left unchanged.",
+                        setting
+                    );
+                    return;
+                }
+            };
+            *flag = match words.peek().map(|w| w.to_ascii_lowercase()).as_deref() {
+                Some("on") => {
+                    words.next();
+                    true
+                }
+                Some("off") => {
+                    words.next();
+                    false
+                }
+                _ => !*flag,
+            };
+        }
+        beatmap.general = general;
+        if write_beatmap(&self.beatmapset.map_dir_name, beatmap) {
+            println!(
+                "General settings saved for {}, included in the next .osu export.",
+                beatmap.version
+            );
+        }
+    }
+
+    fn save_selection_as_pattern(&self, name: String) {
+        let name = name.trim().to_string();
+        if name.is_empty() {
+            println!("Pattern name cannot be empty.");
+            return;
+        }
+        let (objects, map_state) = {
+            let edit_state = self.edit_state.read().expect("edit_state lock poisoned");
+            (edit_state.selected_hit_objects(true), edit_state.current_map_state())
+        };
+        save_pattern(&name, &objects, &map_state.timing, &map_state.diff_settings);
+    }
+
+    // Applies typed values to the inspected object. Fields are either named ("t=1500 x=100")
+    // or positional in the order x, y, time, length. Duration covers all slides, beats are
+    // those of the red line at the head. With "sv", beats are reached by a green line instead.
+    fn apply_object_fields(&self, input: String) {
+        const POSITIONAL: [&str; 4] = ["x", "y", "t", "len"];
+        let mut fields: Vec<(String, f64)> = Vec::new();
+        let mut beats_by_sv = false;
+        for (i, part) in input.split_whitespace().enumerate() {
+            if part.eq_ignore_ascii_case("sv") {
+                beats_by_sv = true;
+                continue;
+            }
+            let (key, value) = match part.split_once('=') {
+                Some((key, value)) => (key.to_ascii_lowercase(), value),
+                None => match POSITIONAL.get(i) {
+                    Some(key) => (key.to_string(), part),
+                    None => {
+                        println!("Too many values, expected at most x, y, time and length.");
+                        return;
+                    }
+                },
+            };
+            let key = match key.as_str() {
+                "time" => "t".to_string(),
+                "length" => "len".to_string(),
+                "duration" => "dur".to_string(),
+                _ => key,
+            };
+            if !["x", "y", "t", "len", "dur", "beats"].contains(&key.as_str()) {
+                println!(
+                    "Unknown field {}, expected x, y, t, len, dur or beats.",
+                    key
+                );
+                return;
+            }
+            match value.parse::<f64>() {
+                Ok(value) if value.is_finite() => fields.push((key, value)),
+                _ => {
+                    println!("Invalid value for {}: {}", key, value);
+                    return;
+                }
+            }
+        }
+        let field = |name: &str| fields.iter().rev().find(|(key, _)| key == name).map(|f| f.1);
+
+        let timing = self.current_timing();
+        let beat_length = |time: f64| {
+            timing
+                .get_lines_at_time(time)
+                .0
+                .map(|red_line| red_line.beat_length)
+        };
+
+        let mut edit_state = self.edit_state.write().expect("edit_state lock poisoned");
+        let Some((id, mut hit_object)) = edit_state.inspected_object() else {
+            return;
+        };
+        if beats_by_sv {
+            let (Some(beats), HitObject::Slider(s)) = (field("beats"), &hit_object) else {
+                println!("\"sv\" needs a slider and a beats=.. value.");
+                return;
+            };
+            if fields.iter().any(|(key, _)| key != "beats") {
+                println!("Only beats can be changed together with \"sv\".");
+                return;
+            }
+            let Some(beat_length) = beat_length(s.time) else {
+                println!("No timing at the slider to count beats with.");
+                return;
+            };
+            if beats <= 0.0 || !edit_state.fit_slider_duration_with_sv(id, beats * beat_length) {
+                println!("Could not fit the slider to {} beats.", beats);
+            }
+            return;
+        }
+        let pos = hit_object.position();
+        let new_pos = Vec2 {
+            x: field("x").unwrap_or(pos.x),
+            y: field("y").unwrap_or(pos.y),
+        };
+        hit_object = match hit_object {
+            HitObject::Spinner(sp) => HitObject::Spinner(Spinner {
+                x: new_pos.x,
+                y: new_pos.y,
+                ..sp
+            }),
+            other => other.move_by_offset(new_pos - pos),
+        };
+        if let Some(time) = field("t") {
+            hit_object.retime(time, 1.0);
+        }
+        match &mut hit_object {
+            HitObject::Slider(s) => {
+                if let Some(length) = field("len") {
+                    s.length_pixels = length;
+                }
+                if let Some(duration) = field("dur") {
+                    s.length_pixels = s.sv_pixels_per_ms * duration / s.slides.max(1) as f64;
+                }
+                if let Some(beats) = field("beats") {
+                    let Some(beat_length) = beat_length(s.time) else {
+                        println!("No timing at the slider to count beats with.");
+                        return;
+                    };
+                    let duration = beats * beat_length;
+                    s.scale_path_to_length(s.sv_pixels_per_ms * duration / s.slides.max(1) as f64);
+                }
+                if s.length_pixels <= 0.0 {
+                    println!("Slider length must be positive.");
+                    return;
+                }
+            }
+            HitObject::Spinner(sp) => {
+                if let Some(duration) = field("dur") {
+                    if duration <= 0.0 {
+                        println!("Spinner duration must be positive.");
+                        return;
+                    }
+                    sp.end_time = sp.time + duration;
+                }
+            }
+            HitObject::Circle(_) => {
+                if field("len").is_some() || field("dur").is_some() || field("beats").is_some() {
+                    println!("Circles have no length or duration.");
+                }
+            }
+        }
+        edit_state.replace_object(id, hit_object);
+    }
+
+    fn import_object_list(&self, input: String) {
+        let mut parts = input.split_whitespace();
+        let Some(name) = parts.next() else {
+            return;
+        };
+        let replace = match parts.next() {
+            Some("replace") => true,
+            Some(other) => {
+                println!("Unknown option {}, expected \"replace\".", other);
+                return;
+            }
+            None => false,
+        };
+        let file_name = if name.ends_with(".json") {
+            name.to_string()
+        } else {
+            format!("{}.json", name)
+        };
+        let mut edit_state = self.edit_state.write().expect("edit_state lock poisoned");
+        let map_state = edit_state.current_map_state();
+        let Some(objects) = read_object_list(
+            &self.analysis_dir().join(file_name),
+            &map_state.timing,
+            &map_state.diff_settings,
+        ) else {
+            return;
+        };
+        println!(
+            "{} {} object(s) from {}.",
+            if replace { "Replaced objects with" } else { "Merged" },
+            objects.len(),
+            name
+        );
+        edit_state.import_objects(objects, replace);
+    }
+
+    fn insert_pattern_at_playhead(&self, input: String) {
+        let mut parts = input.split_whitespace();
+        let Some(name) = parts.next() else {
+            return;
+        };
+        let scale_percent = match parts.next().map(|p| p.trim_end_matches('%').parse::<f64>()) {
+            Some(Ok(value)) => value,
+            Some(Err(_)) => {
+                println!("Invalid scale, expected a percentage.");
+                return;
+            }
+            None => 100.0,
+        };
+        let rotation_degrees = match parts.next().map(|p| p.trim_end_matches('°').parse::<f64>()) {
+            Some(Ok(value)) => value,
+            Some(Err(_)) => {
+                println!("Invalid rotation, expected degrees.");
+                return;
+            }
+            None => 0.0,
+        };
+
+        let time_ms = self.audio.current_time().0;
+        let mut edit_state = self.edit_state.write().expect("edit_state lock poisoned");
+        let map_state = edit_state.current_map_state();
+        let Some(objects) = load_pattern(
+            name,
+            &map_state.timing,
+            &map_state.diff_settings,
+            time_ms,
+            (scale_percent / 100.0).max(0.01),
+            rotation_degrees,
+        ) else {
+            return;
+        };
+        println!("Inserted pattern {} ({} objects) at {:.0}ms.", name, objects.len(), time_ms);
+        edit_state.insert_objects(objects);
+    }
+
+    fn create_difficulty_from_current(&mut self, version: String, copy_objects: bool) {
+        let version = version.trim().to_string();
+        if version.is_empty() {
+            println!("Difficulty name cannot be empty.");
+            return;
+        }
+        let Some(template) = self
+            .beatmapset
+            .beatmaps
+            .iter()
+            .find(|b| b.version == self.diff_version)
+            .cloned()
+        else {
+            println!("Current difficulty not found in beatmapset.");
+            return;
+        };
+        let map_state = self
+            .edit_state
+            .read()
+            .expect("edit_state lock poisoned")
+            .current_map_state();
+        let mut beatmap = map_state.to_beatmap(&template);
+        beatmap.id = 0;
+        beatmap.version = version;
+        if !copy_objects {
+            beatmap.objects.objects.clear();
+            beatmap.events = beatmap.events.without_breaks();
+        }
+        let version = beatmap.version.clone();
+        if create_difficulty(&mut self.beatmapset, beatmap, &self.diff_version) {
+            println!("Created difficulty {}.", version);
+            self.switch_difficulty(&version);
+        }
+    }
+
+    // Opens another difficulty of the set in this window. The one that was open keeps its
+    // current state in the folder, so switching back finds it as it was left.
+    fn switch_difficulty(&mut self, version: &str) {
+        let Some(beatmap) = self
+            .beatmapset
+            .beatmaps
+            .iter()
+            .find(|b| b.version == version)
+            .cloned()
+        else {
+            println!("Difficulty {} not found in beatmapset.", version);
+            return;
+        };
+        self.record_editing_session();
+        let current = self
+            .edit_state
+            .read()
+            .expect("edit_state lock poisoned")
+            .current_map_state();
+        if let Some(template) = self
+            .beatmapset
+            .beatmaps
+            .iter_mut()
+            .find(|b| b.version == self.diff_version)
+        {
+            *template = current.to_beatmap(template);
+        }
+
+        let bookmarks = current.bookmarks.iter().copied().collect();
+        let map_state = MapState::from_beatmap(&beatmap, bookmarks, self.editor_config.clone());
+        let opened = {
+            let mut edit_state = self.edit_state.write().expect("edit_state lock poisoned");
+            edit_state.load_map_state(map_state);
+            edit_state.current_map_state()
+        };
+        let map_dir_name = self.beatmapset.map_dir_name.clone();
+        let osu_path = exported_osu_path(&map_dir_name, &beatmap);
+        let modified = fs::metadata(&osu_path).and_then(|m| m.modified()).ok();
+        self.watched_osu = (osu_path, modified);
+        self.diff_version = beatmap.version.clone();
+        self.session_tracker =
+            SessionTracker::new(opened, earlier_editing_totals(&map_dir_name, version));
+        self.gap_suggestions.clear();
+        self.load_difficulty_background(&beatmap);
+        self.title = window_title(&self.beatmapset, &beatmap);
+        if let Some(window) = self.window.as_ref() {
+            window.set_title(&self.title);
+        }
+        record_recent_map(&map_dir_name, version);
+        println!("Now editing {}.", version);
+    }
+
+    // Keeps the previous background when the difficulty's own is missing or unreadable.
+    fn load_difficulty_background(&mut self, beatmap: &Beatmap) {
+        let name = beatmap.events.background_name();
+        let Some(bytes) = self.beatmapset.assets.get(&name) else {
+            println!("Background {} not found in assets, keeping the current one.", name);
+            return;
+        };
+        let Some(background) = load_texture(bytes) else {
+            println!("Failed to load background {}, keeping the current one.", name);
+            return;
+        };
+        if let Some(shared) = self.render_shared.as_ref() {
+            shared.set_pending_background(background.clone());
+        }
+        self.background = background;
+    }
+
+    // Writes the objects in the looped range, or the selected ones when nothing is looped, with
+    // the full timing as "<diff> (practice)", for drilling a hard section on its own. Later
+    // exports get "(practice 2)" and so on instead of clashing with the first.
+    pub fn export_selection_as_practice_difficulty(&mut self) {
+        let loop_range = self.audio.loop_range();
+        let (map_state, objects) = {
+            let edit_state = self.edit_state.read().expect("edit_state lock poisoned");
+            let objects = match loop_range {
+                Some((start, end)) => edit_state.hit_objects_between(start.0, end.0),
+                None => edit_state.hit_objects_in_selections(),
+            };
+            (edit_state.current_map_state(), objects)
+        };
+        if objects.is_empty() {
+            println!("Select or loop the objects to export as a practice difficulty first.");
+            return;
+        }
+        let Some(template) = self
+            .beatmapset
+            .beatmaps
+            .iter()
+            .find(|b| b.version == self.diff_version)
+            .cloned()
+        else {
+            println!("Current difficulty not found in beatmapset.");
+            return;
+        };
+        let mut beatmap = map_state.to_beatmap(&template);
+        beatmap.id = 0;
+        beatmap.version = self.unused_practice_version();
+        beatmap.objects.objects = objects;
+        beatmap.events = beatmap.events.without_breaks();
+        let count = beatmap.objects.objects.len();
+        let version = beatmap.version.clone();
+        if create_difficulty(&mut self.beatmapset, beatmap, &self.diff_version) {
+            println!(
+                "Exported {} object(s) as {}. Reopen the map to edit it.",
+                count, version
+            );
+        }
+    }
+
+    fn unused_practice_version(&self) -> String {
+        let is_taken = |version: &str| {
+            self.beatmapset
+                .beatmaps
+                .iter()
+                .any(|b| sanitize_name(&b.version) == sanitize_name(version))
+        };
+        let mut version = format!("{} (practice)", self.diff_version);
+        let mut n = 2;
+        while is_taken(&version) {
+            version = format!("{} (practice {})", self.diff_version, n);
+            n += 1;
+        }
+        version
+    }
+
+    fn begin_text_input(&mut self, target: TextInputTarget) {
+        self.text_input.clear();
+        self.is_renaming_current_state = true;
+        self.text_input_target = target;
+        // Lets input methods compose CJK text, their candidate window opened by the text field.
+        if let Some(window) = self.window.as_ref() {
+            window.set_ime_allowed(true);
+            let (origin, size) = self.current_state_button_hitbox.bounds();
+            window.set_ime_cursor_area(
+                PhysicalPosition::new(origin.x, origin.y),
+                PhysicalSize::new(size.x, size.y),
+            );
+        }
+    }
+
+    pub fn cancel_current_state_rename(&mut self) {
+        self.is_renaming_current_state = false;
+        self.text_input.clear();
+        if let Some(window) = self.window.as_ref() {
+            window.set_ime_allowed(false);
+        }
+    }
+
+    pub fn commit_current_state_rename(&mut self) {
+        if !self.is_renaming_current_state {
+            return;
+        }
+        match self.text_input_target {
+            TextInputTarget::CurrentStateName => {
+                self.edit_state
+                    .write()
+                    .expect("edit_state lock poisoned")
+                    .rename_current_state(self.text_input.text().to_string());
+            }
+            TextInputTarget::NewDifficultyName { copy_objects } => {
+                self.create_difficulty_from_current(
+                    self.text_input.text().to_string(),
+                    copy_objects,
+                );
+            }
+            TextInputTarget::PatternName => {
+                self.save_selection_as_pattern(self.text_input.text().to_string());
+            }
+            TextInputTarget::PatternInsert => {
+                self.insert_pattern_at_playhead(self.text_input.text().to_string());
+            }
+            TextInputTarget::ObjectFields => {
+                self.apply_object_fields(self.text_input.text().to_string());
+                if let Some(panel) = self.inspector_panel() {
+                    self.show_report(panel);
+                }
+            }
+            TextInputTarget::ObjectListImport => {
+                self.import_object_list(self.text_input.text().to_string());
+            }
+            TextInputTarget::CopyTiming => {
+                self.copy_timing(self.text_input.text().to_string());
+            }
+            TextInputTarget::TimingTools => {
+                self.apply_timing_tool(self.text_input.text().to_string());
+            }
+            TextInputTarget::GeneralSettings => {
+                self.apply_general_settings(self.text_input.text().to_string());
+            }
+            TextInputTarget::SampleFile => {
+                self.apply_sample_file(self.text_input.text().to_string());
+            }
+            TextInputTarget::ExternalEdit => {
+                self.apply_external_edit_choice(self.text_input.text().to_string());
+            }
+            TextInputTarget::RecentMap => {
+                self.switch_to_recent_map(self.text_input.text().to_string());
+            }
+            TextInputTarget::GreenLineHere => {
+                self.apply_green_line_here(self.text_input.text().to_string());
+            }
+            TextInputTarget::ExternalTool => {
+                self.run_external_tool(self.text_input.text().to_string());
+            }
+            TextInputTarget::PlaybackMix => {
+                self.export_playback_mix(self.text_input.text().to_string());
+            }
+            TextInputTarget::ReplayImport => {
+                self.import_replay(self.text_input.text().to_string());
+            }
+            TextInputTarget::SelectionSet => {
+                self.apply_selection_set_command(self.text_input.text().to_string());
+            }
+            TextInputTarget::ComboProposal => {
+                self.apply_combo_proposal_command(self.text_input.text().to_string());
+            }
+            TextInputTarget::SelectionTransform => {
+                self.apply_selection_transform(self.text_input.text().to_string());
+            }
+            TextInputTarget::SliderTimingFix => {
+                self.apply_slider_timing_fix(self.text_input.text().to_string());
+            }
+        }
+        self.cancel_current_state_rename();
+    }
+
+    pub fn append_current_state_rename_text(&mut self, text: &str) {
+        if !self.is_renaming_current_state {
+            return;
+        }
+        self.text_input.insert(text);
+    }
+
+    // Pastes the first line of the clipboard text into the text field.
+    pub fn paste_current_state_rename_text(&mut self) {
+        let Some(text) = self.clipboard.get_text() else {
+            return;
+        };
+        let line = text.lines().next().unwrap_or_default().to_string();
+        self.append_current_state_rename_text(&line);
+    }
+
+    pub fn backspace_current_state_rename(&mut self) {
+        if !self.is_renaming_current_state {
+            return;
+        }
+        self.text_input.backspace();
+    }
+
+    pub fn handle_ime_event(&mut self, ime: &Ime) {
+        if !self.is_renaming_current_state {
+            return;
+        }
+        match ime {
+            Ime::Preedit(preedit, _) => self.text_input.set_preedit(preedit),
+            Ime::Commit(text) => {
+                self.text_input.set_preedit("");
+                self.text_input.insert(text);
+            }
+            Ime::Enabled => {}
+            Ime::Disabled => self.text_input.set_preedit(""),
+        }
+    }
+
+    pub fn is_text_input_composing(&self) -> bool {
+        self.text_input.is_composing()
+    }
+
+    pub fn is_current_state_rename_active(&self) -> bool {
+        self.is_renaming_current_state
+    }
+
+    pub fn sync_overlay_rects_to_renderer(&mut self) {
+        self.apply_layout_drag();
+        if self
+            .current_state_button_activate_requested
+            .swap(false, Ordering::AcqRel)
+        {
+            self.begin_current_state_rename();
+        }
+        if self.play_pause_requested.swap(false, Ordering::AcqRel) {
+            self.toggle_playback();
+        }
+        match self.stats_box_copy_requested.swap(0, Ordering::AcqRel) {
+            0 => {}
+            line => self.copy_stats_box_time(line as usize - 1),
+        }
+        match self.tool_click_requested.swap(0, Ordering::AcqRel) {
+            0 => {}
+            1 => {
+                let pos = self.tool_click_playfield_pos.load();
+                self.handle_tool_event(ToolEvent::PlayfieldClick(pos));
+            }
+            _ => {
+                self.handle_tool_event(ToolEvent::Confirm);
+            }
+        }
+        self.update_selection_bbox_hitbox_bounds();
+        self.update_selection_bbox_cursor();
+        let bottom_timeline_range = self.sync_bottom_timeline_range();
+        let editing_totals = self.editing_totals();
+        let tooltip = match self.mouse_handler.hovered_tooltip() {
+            Some((hover_started, text)) => Some((hover_started, Cow::Borrowed(text))),
+            None => self.hovered_object_tooltip(),
+        };
+        if let Some(shared) = self.render_shared.as_ref() {
+            shared.set_playfield_scale(self.current_playfield_scale());
+            shared.set_timeline_zoom(self.current_timeline_zoom());
+            shared.set_bottom_timeline_range(
+                bottom_timeline_range.start_ms,
+                bottom_timeline_range.end_ms,
+            );
+            shared.set_hint_line(self.hint_line());
+            shared.set_editing_totals(editing_totals);
+            shared.set_report_lines(
+                self.report_panel
+                    .as_ref()
+                    .map(ReportPanel::hud_lines)
+                    .unwrap_or_default(),
+            );
+            shared.set_onboarding_regions(self.visible_onboarding_regions());
+            shared.set_overlay_rect_left(
+                self.layout_drag_rect()
+                    .or_else(|| self.drag_rect_left.rect()),
+            );
+            shared.set_overlay_rect_right(self.drag_rect_right.rect());
+            shared.set_play_pause_button_hovered(self.play_pause_button.is_hovered());
+            shared.set_play_pause_button_clicked(self.play_pause_button.is_clicked());
+            shared.set_undo_button_hovered(self.undo_button_hovered.load(Ordering::Acquire));
+            shared.set_undo_button_clicked(self.undo_button_clicked.load(Ordering::Acquire));
+            shared.set_current_state_button_hovered(
+                self.current_state_button_hovered.load(Ordering::Acquire),
+            );
+            shared.set_current_state_button_clicked(
+                self.current_state_button_clicked.load(Ordering::Acquire),
+            );
+            shared.set_current_state_rename_state(
+                self.is_renaming_current_state,
+                self.text_input.display_text(),
+            );
+            let redo_hover_row = self.redo_buttons_hovered_row.load(Ordering::Acquire);
+            shared.set_redo_button_hovered_row(if redo_hover_row == u32::MAX {
+                None
+            } else {
+                Some(redo_hover_row)
+            });
+            let redo_click_row = self.redo_buttons_clicked_row.load(Ordering::Acquire);
+            shared.set_redo_button_clicked_row(if redo_click_row == u32::MAX {
+                None
+            } else {
+                Some(redo_click_row)
+            });
+            shared.set_selection_left_bbox_hovered(
+                self.selection_left_bbox_hovered.load(Ordering::Acquire),
+            );
+            shared.set_selection_right_bbox_hovered(
+                self.selection_right_bbox_hovered.load(Ordering::Acquire),
+            );
+            shared.set_selection_left_bbox_dragging(
+                self.selection_left_bbox_dragging.load(Ordering::Acquire),
+            );
+            shared.set_selection_right_bbox_dragging(
+                self.selection_right_bbox_dragging.load(Ordering::Acquire),
+            );
+            shared.set_selection_left_origin_hovered(
+                self.selection_left_origin_hovered.load(Ordering::Acquire),
+            );
+            shared.set_selection_right_origin_hovered(
+                self.selection_right_origin_hovered.load(Ordering::Acquire),
+            );
+            shared.set_selection_left_origin_dragging(
+                self.selection_left_origin_dragging.load(Ordering::Acquire),
+            );
+            shared.set_selection_right_origin_dragging(
+                self.selection_right_origin_dragging.load(Ordering::Acquire),
+            );
+            shared.set_cursor_pos(self.mouse_handler.position());
+            shared.set_tooltip(tooltip);
+        }
+    }
+
+    // Time, snap, combo number and hitsounds of the object under the cursor, while the cursor
+    // rests on the playfield without dragging.
+    fn hovered_object_tooltip(&mut self) -> Option<(Instant, Cow<'static, str>)> {
+        let hovered = if self.mouse_handler.is_hovering() {
+            let scale = self.playfield_screen_scale.load();
+            let top_left = self.playfield_screen_top_left.load();
+            let cursor = self.mouse_handler.position();
+            let cursor_playfield = Vec2 {
+                x: (cursor.x - top_left.x) / scale.x.max(1e-9),
+                y: (cursor.y - top_left.y) / scale.y.max(1e-9),
+            };
+            let edit_state = self.edit_state.read().expect("edit_state lock poisoned");
+            let map_state = edit_state.current_map_state();
+            object_at_cursor(
+                &map_state,
+                &edit_state.fade_envelope,
+                cursor_playfield,
+                self.audio.current_time().0,
+            )
+            .map(|id| (id, object_tooltip(&map_state, id)))
+        } else {
+            None
+        };
+        let Some((id, text)) = hovered else {
+            self.hovered_object = None;
+            return None;
+        };
+        let hover_started = match self.hovered_object {
+            Some((hovered_id, hover_started)) if hovered_id == id => hover_started,
+            _ => Instant::now(),
+        };
+        self.hovered_object = Some((id, hover_started));
+        Some((hover_started, Cow::Owned(text)))
+    }
+
+    pub fn clear_selections(&self) {
+        let mut edit_state = self.edit_state.write().expect("edit_state lock poisoned");
+        edit_state.clear_selections();
+    }
+
+    pub fn select_all_to_left(&self) {
+        let mut edit_state = self.edit_state.write().expect("edit_state lock poisoned");
+        edit_state.select_all_to_left();
+    }
+
+    pub fn select_visible_to_left(&self) {
+        let time = self.audio.current_time();
+        let mut edit_state = self.edit_state.write().expect("edit_state lock poisoned");
+        edit_state.select_visible_to_left(time);
+    }
+
+    pub fn set_background_from_file(&mut self, path: &Path) {
+        let Some((file_name, background)) =
+            import_diff_background(&mut self.beatmapset, &self.diff_version, path)
+        else {
+            return;
+        };
+        if let Some(beatmap) = self
+            .beatmapset
+            .beatmaps
+            .iter_mut()
+            .find(|b| b.version == self.diff_version)
+        {
+            beatmap.events.set_background_name(&file_name);
+        }
+        if let Some(shared) = self.render_shared.as_ref() {
+            shared.set_pending_background(background.clone());
+        }
+        self.background = background;
+    }
+
+    // Starting goes back to the previous tick or downbeat first when play_start_snap asks for it.
+    pub fn toggle_playback(&self) {
+        if self.audio.is_playing() {
+            self.audio.pause();
+            return;
+        }
+        let ticks_per_beat = match self.editor_config.general.play_start_snap {
+            PlayStartSnap::Tick => Some(self.editor_config.general.beat_divisor.max(1) as f64),
+            PlayStartSnap::Downbeat => {
+                let time_ms = self.audio.current_time().0;
+                self.current_timing()
+                    .get_lines_at_time(time_ms)
+                    .0
+                    .map(|red_line| 1.0 / red_line.meter.max(1) as f64)
+            }
+            PlayStartSnap::Off => None,
+        };
+        if let Some(ticks_per_beat) = ticks_per_beat {
+            let time_ms = self.audio.current_time().0;
+            let timing = self.current_timing();
+            if let Some(start_ms) = timing.grid_line_at_or_before(time_ms, ticks_per_beat) {
+                self.audio.seek(SongTimeMs(start_ms.max(0.0)));
+            }
+        }
+        self.audio.play();
+    }
+
+    // Seeks one beat divisor tick (or one measure) forward or backward.
+    pub fn seek_by_grid(&self, forward: bool, by_measure: bool) {
+        let timing = self.current_timing();
+        let time_ms = self.audio.current_time().0;
+        let ticks_per_beat = if by_measure {
+            match timing.get_lines_at_time(time_ms).0 {
+                Some(red_line) => 1.0 / red_line.meter.max(1) as f64,
+                None => return,
+            }
+        } else {
+            self.editor_config.general.beat_divisor.max(1) as f64
+        };
+        let Some(target_ms) = timing.step_grid(time_ms, ticks_per_beat, forward) else {
+            return;
+        };
+        self.audio
+            .seek(SongTimeMs(target_ms.clamp(0.0, self.audio.song_total().0)));
+    }
+
+    // One step per notch, scrolling up goes back. Touchpads step once per WHEEL_SEEK_STEP_PX.
+    pub fn seek_by_wheel(&mut self, delta: &MouseScrollDelta, by_measure: bool) {
+        const WHEEL_SEEK_STEP_PX: f64 = 40.0;
+        let steps = match delta {
+            MouseScrollDelta::LineDelta(_, y) => {
+                self.wheel_seek_px = 0.0;
+                let y = *y as f64;
+                if y != 0.0 && y.abs() < 1.0 {
+                    y.signum()
+                } else {
+                    y.round()
+                }
+            }
+            MouseScrollDelta::PixelDelta(pos) => {
+                self.wheel_seek_px += pos.y;
+                let steps = (self.wheel_seek_px / WHEEL_SEEK_STEP_PX).trunc();
+                self.wheel_seek_px -= steps * WHEEL_SEEK_STEP_PX;
+                steps
+            }
+        };
+        for _ in 0..steps.abs() as u32 {
+            self.seek_by_grid(steps < 0.0, by_measure);
+        }
+    }
+
+    // First press goes to the first object, pressing again from there goes to the start.
+    pub fn seek_to_start_or_first_object(&self) {
+        let map_state = {
+            let edit_state = self.edit_state.read().expect("edit_state lock poisoned");
+            edit_state.current_map_state()
+        };
+        let time_ms = self.audio.current_time().0;
+        let first_object_ms = map_state
+            .objects
+            .iter()
+            .next()
+            .map(|object| object.hit_object.time());
+        match first_object_ms {
+            Some(first_object_ms) if time_ms > first_object_ms + 1.0 => {
+                self.audio.seek(SongTimeMs(first_object_ms));
+            }
+            _ => self.audio.seek(SongTimeMs(0.0)),
+        }
+    }
+
+    pub fn seek_to_last_object(&self) {
+        let map_state = {
+            let edit_state = self.edit_state.read().expect("edit_state lock poisoned");
+            edit_state.current_map_state()
+        };
+        if let Some(last_object_ms) = map_state
+            .objects
+            .iter()
+            .map(|object| object.hit_object.time())
+            .last()
+        {
+            self.audio.seek(SongTimeMs(last_object_ms));
+        }
+    }
+
+    pub fn seek_to_percent(&self, percent: f64) {
+        let song_total_ms = self.audio.song_total().0;
+        self.audio
+            .seek(SongTimeMs(song_total_ms * (percent / 100.0).clamp(0.0, 1.0)));
+    }
+
+    // Plays the head hitsounds of the first left-selected object, or of the next object
+    // at or after the playhead when nothing is selected.
+    pub fn audition_hitsounds(&self) {
+        let time_ms = self.audio.current_time().0;
+        let (object, forced_sample_set) = {
+            let edit_state = self.edit_state.read().expect("edit_state lock poisoned");
+            let selected = edit_state.selected_hit_objects(true);
+            let object = match selected
+                .into_iter()
+                .min_by(|a, b| a.time().total_cmp(&b.time()))
+            {
+                Some(object) => Some(object),
+                None => edit_state
+                    .current_map_state()
+                    .objects
+                    .iter()
+                    .find(|object| object.hit_object.time() >= time_ms - 1.0)
+                    .map(|object| (*object.hit_object).clone()),
+            };
+            (object, edit_state.forced_sample_set())
+        };
+        let (hitsound_info, position_x) = match &object {
+            Some(HitObject::Circle(c)) => (&c.hitsound_info, c.pos.x / 512.0),
+            Some(HitObject::Slider(s)) => match s.hitsounds.first() {
+                Some(info) => (info, s.control_points.start.x / 512.0),
+                None => return,
+            },
+            _ => {
+                println!("No object to audition.");
+                return;
+            }
+        };
+        let mut hitsound_info = hitsound_info.clone();
+        if let Some(sample_set) = forced_sample_set {
+            hitsound_info.hit_sampleset = sample_set.clone();
+            hitsound_info.additions_sampleset = sample_set;
+        }
+        for (index, volume, x) in self
+            .hitsound_routing
+            .resolve_audio_events(&hitsound_info, position_x)
+        {
+            self.audio.audition_hitsound(index, volume, x);
+        }
+    }
+
+    // Cycles the sample set every hitsound plays on: the map's own, then normal, soft and drum
+    // for all of them, to pick a base sample set by ear.
+    pub fn cycle_forced_sample_set(&self) {
+        let mut edit_state = self.edit_state.write().expect("edit_state lock poisoned");
+        let next = match edit_state.forced_sample_set() {
+            None => Some(SampleSet::Normal),
+            Some(SampleSet::Normal) => Some(SampleSet::Soft),
+            Some(SampleSet::Soft) => Some(SampleSet::Drum),
+            Some(SampleSet::Drum) => None,
+        };
+        match &next {
+            Some(sample_set) => println!("Hitsounds forced to the {:?} sample set", sample_set),
+            None => println!("Hitsounds on the map's own sample sets"),
+        }
+        edit_state.set_forced_sample_set(next);
+    }
+
+    // Loops playback from the pre-roll before the selection to just after its last object, or
+    // stops looping when a loop is already set.
+    pub fn toggle_selection_loop(&self) {
+        if self.audio.loop_range().is_some() {
+            self.audio.set_loop(None);
+            println!("Selection loop off");
+            return;
+        }
+        let range = {
+            let edit_state = self.edit_state.read().expect("edit_state lock poisoned");
+            edit_state.selection_time_range()
+        };
+        let Some((start_ms, end_ms)) = range else {
+            println!("Select objects to loop over.");
+            return;
+        };
+        const LOOP_TAIL_MS: f64 = 250.0;
+        let preroll_ms = self.editor_config.audio.selection_loop_preroll_ms.max(0.0);
+        let loop_start = SongTimeMs((start_ms - preroll_ms).max(0.0));
+        self.audio
+            .set_loop(Some((loop_start, SongTimeMs(end_ms + LOOP_TAIL_MS))));
+        self.audio.seek(loop_start);
+        if !self.audio.is_playing() {
+            self.audio.play();
+        }
+        println!(
+            "Looping {:.0}ms - {:.0}ms, Ctrl + U again to stop",
+            loop_start.0,
+            end_ms + LOOP_TAIL_MS
+        );
+    }
+
+    pub fn begin_sample_file(&mut self) {
+        if self.objects_locked() {
+            return;
+        }
+        let selected = {
+            let edit_state = self.edit_state.read().expect("edit_state lock poisoned");
+            edit_state.selected_hit_objects(true)
+        };
+        if selected.is_empty() {
+            println!("Select objects to set their sample file.");
+            return;
+        }
+        let mut current: Vec<String> = selected
+            .iter()
+            .map(|object| object.sample_filename().unwrap_or("none").to_string())
+            .collect();
+        current.sort();
+        current.dedup();
+        let mut available: Vec<&String> = self.hitsound_routing.map_samples.keys().collect();
+        available.sort();
+        println!("Current sample file: {}", current.join(", "));
+        if available.is_empty() {
+            println!("The beatmap folder has no sample files, add some with the file manager.");
+            return;
+        }
+        println!(
+            "Sample files: {}",
+            available
+                .iter()
+                .map(|name| name.as_str())
+                .collect::<Vec<&str>>()
+                .join(", ")
+        );
+        println!("Type a file name (a prefix is enough) or \"none\" and press Enter.");
+        self.begin_text_input(TextInputTarget::SampleFile);
+    }
+
+    fn apply_sample_file(&mut self, input: String) {
+        let input = input.trim().to_ascii_lowercase();
+        if input.is_empty() {
+            return;
+        }
+        let filename = if input == "none" {
+            None
+        } else {
+            let map_samples = &self.hitsound_routing.map_samples;
+            let matches: Vec<&String> = match map_samples.get_key_value(&input) {
+                Some((name, _)) => vec![name],
+                None => map_samples
+                    .keys()
+                    .filter(|name| name.starts_with(&input))
+                    .collect(),
+            };
+            if matches.len() != 1 {
+                println!("No single sample file matches \"{}\".", input);
+                return;
+            }
+            // Keep the file name as it is spelled in the beatmap folder.
+            let name = self
+                .beatmapset
+                .assets
+                .names_and_sizes()
+                .into_iter()
+                .map(|(name, _)| name)
+                .find(|name| name.eq_ignore_ascii_case(matches[0]));
+            Some(name.unwrap_or_else(|| matches[0].clone()))
+        };
+        let changed = self
+            .edit_state
+            .write()
+            .expect("edit_state lock poisoned")
+            .set_selection_sample_filename(filename.clone());
+        println!(
+            "Set the sample file of {} object(s) to {}.",
+            changed,
+            filename.as_deref().unwrap_or("none")
+        );
+        self.audition_hitsounds();
+    }
+
+    pub fn begin_selection_transform(&mut self) {
+        if self.objects_locked() {
+            return;
+        }
+        let has_selection = {
+            let edit_state = self.edit_state.read().expect("edit_state lock poisoned");
+            edit_state.left_selection.is_some() || edit_state.right_selection.is_some()
+        };
+        if !has_selection {
+            println!("Select objects to rotate or scale.");
+            return;
+        }
+        println!("Type \"[rot=<degrees>] [scale=<percent>] [right]\" and press Enter.");
+        println!("Both turn around the selection's origin, \"right\" for the right selection.");
+        self.begin_text_input(TextInputTarget::SelectionTransform);
+    }
+
+    fn apply_selection_transform(&mut self, input: String) {
+        let mut degrees = 0.0;
+        let mut scale_percent = 100.0;
+        let mut left = true;
+        for part in input.split_whitespace() {
+            if part.eq_ignore_ascii_case("right") {
+                left = false;
+                continue;
+            }
+            let Some((key, value)) = part.split_once('=') else {
+                println!("Expected key=value, got {}.", part);
+                return;
+            };
+            let value = value.trim_end_matches(['%', '°']);
+            match (key.to_ascii_lowercase().as_str(), value.parse::<f64>()) {
+                ("rot" | "rotate", Ok(value)) if value.is_finite() => degrees = value,
+                ("scale", Ok(value)) if value.is_finite() && value > 0.0 => scale_percent = value,
+                _ => {
+                    println!("Invalid {}: {}", key, value);
+                    return;
+                }
+            }
+        }
+        let mut edit_state = self.edit_state.write().expect("edit_state lock poisoned");
+        let selection = if left {
+            &edit_state.left_selection
+        } else {
+            &edit_state.right_selection
+        };
+        if selection.is_none() {
+            println!("The {} selection is empty.", if left { "left" } else { "right" });
+            return;
+        }
+        edit_state.rotate_and_scale_selection(left, degrees, scale_percent / 100.0);
+        println!("Rotated by {}° and scaled to {}%.", degrees, scale_percent);
+    }
+
+    pub fn begin_green_line_here(&mut self) {
+        let (selected, map_state) = {
+            let edit_state = self.edit_state.read().expect("edit_state lock poisoned");
+            (
+                edit_state.hit_objects_in_selections(),
+                edit_state.current_map_state(),
+            )
+        };
+        let Some(first) = selected.first() else {
+            println!("Select objects to put green lines at.");
+            return;
+        };
+        let Some(inherited) = map_state.timing.inherited_at(first.time()) else {
+            println!("No red line before the selected objects.");
+            return;
+        };
+        println!(
+            "At {:.0}ms: sv={} vol={:.0} set={:?}",
+            first.time(),
+            inherited.sv_multiplier,
+            inherited.volume * 100.0,
+            inherited.sample_set
+        );
+        println!(
+            "Type \"[sv=<multiplier>] [vol=<0-100>] [set=normal|soft|drum]\" and press Enter."
+        );
+        println!("Values left out stay as they are, a green line is put at every selected object.");
+        self.begin_text_input(TextInputTarget::GreenLineHere);
+    }
+
+    fn apply_green_line_here(&mut self, input: String) {
+        let mut settings = GreenLineSettings {
+            sv_multiplier: None,
+            volume: None,
+            sample_set: None,
+        };
+        for part in input.split_whitespace() {
+            let Some((key, value)) = part.split_once('=') else {
+                println!("Expected key=value, got {}.", part);
+                return;
+            };
+            match key.to_ascii_lowercase().as_str() {
+                "sv" => match value.parse::<f64>() {
+                    Ok(sv) if sv.is_finite() && sv > 0.0 => settings.sv_multiplier = Some(sv),
+                    _ => {
+                        println!("Invalid slider velocity: {}", value);
+                        return;
+                    }
+                },
+                "vol" | "volume" => match value.parse::<f64>() {
+                    Ok(volume) if (0.0..=100.0).contains(&volume) => {
+                        settings.volume = Some(volume / 100.0)
+                    }
+                    _ => {
+                        println!("Invalid volume: {}, expected 0 to 100.", value);
+                        return;
+                    }
+                },
+                "set" => {
+                    settings.sample_set = match value.to_ascii_lowercase().as_str() {
+                        "normal" => Some(SampleSet::Normal),
+                        "soft" => Some(SampleSet::Soft),
+                        "drum" => Some(SampleSet::Drum),
+                        _ => {
+                            println!(
+                                "Unknown sample set {}, expected normal, soft or drum.",
+                                value
+                            );
+                            return;
+                        }
+                    }
+                }
+                _ => {
+                    println!("Unknown field {}, expected sv, vol or set.", key);
+                    return;
+                }
+            }
+        }
+        let placed = self
+            .edit_state
+            .write()
+            .expect("edit_state lock poisoned")
+            .insert_green_lines_at_selection(&settings);
+        if placed == 0 {
+            println!("No green lines placed, the selection is before the first red line.");
+            return;
+        }
+        println!("Placed {} green line(s).", placed);
+        self.audition_hitsounds();
+    }
+
+    // Polls the difficulty's .osu so edits made with other tools can be pulled in. The prompt
+    // waits until no other text prompt is open.
+    fn check_external_osu_edit(&mut self) {
+        let now = Instant::now();
+        if now >= self.next_osu_watch_check {
+            self.next_osu_watch_check = now + OSU_WATCH_INTERVAL;
+            let (path, last_modified) = &mut self.watched_osu;
+            let modified = fs::metadata(path).and_then(|m| m.modified()).ok();
+            if modified.is_some() && modified != *last_modified {
+                *last_modified = modified;
+                self.external_edit_pending = true;
+            }
+        }
+        if self.external_edit_pending && !self.is_renaming_current_state {
+            self.external_edit_pending = false;
+            self.begin_external_edit();
+        }
+    }
+
+    fn begin_external_edit(&mut self) {
+        println!("{} was changed outside the editor.", self.watched_osu.0.display());
+        println!("Type \"reload\" to load it from disk or \"keep\" to keep yours (Esc keeps).");
+        self.begin_text_input(TextInputTarget::ExternalEdit);
+    }
+
+    fn apply_external_edit_choice(&mut self, input: String) {
+        match input.trim().to_ascii_lowercase().as_str() {
+            "reload" => self.reload_osu_from_disk(),
+            _ => println!("Kept the editor's version of {}.", self.diff_version),
+        }
+    }
+
+    fn reload_osu_from_disk(&mut self) {
+        self.reload_osu_file(&self.watched_osu.0.clone(), true);
+    }
+
+    // The reload is a regular undo step, so the editor's version is one undo away. Bookmarks
+    // are kept when the file's own are not used.
+    fn reload_osu_file(&mut self, path: &Path, use_file_bookmarks: bool) {
+        let data = match fs::read(path) {
+            Ok(data) => data,
+            Err(err) => {
+                println!("Failed to read {}: {}", path.display(), err);
+                return;
+            }
+        };
+        let file_name = path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string();
+        let Some(mut beatmap) = parse_osu_file(file_name, data.as_slice(), &mut |_| None)
+            .as_ref()
+            .and_then(Beatmap::from_osu_format)
+        else {
+            println!(
+                "Failed to parse {}, kept the editor's version.",
+                path.display()
+            );
+            return;
+        };
+        // Difficulties are stored under their version, a rename on disk is not followed.
+        beatmap.version = self.diff_version.clone();
+        let bookmarks = if use_file_bookmarks {
+            parse_bookmarks_from_editor_section(&String::from_utf8_lossy(&data))
+        } else {
+            self.edit_state
+                .read()
+                .expect("edit_state lock poisoned")
+                .current_map_state()
+                .bookmarks
+                .iter()
+                .copied()
+                .collect()
+        };
+        let map_state = MapState::from_beatmap(&beatmap, bookmarks, self.editor_config.clone());
+        let object_count = map_state.objects.len();
+        self.edit_state
+            .write()
+            .expect("edit_state lock poisoned")
+            .replace_map_state(map_state);
+        if let Some(template) = self
+            .beatmapset
+            .beatmaps
+            .iter_mut()
+            .find(|b| b.version == self.diff_version)
+        {
+            *template = beatmap;
+        }
+        println!(
+            "Reloaded {} from disk ({} objects), undo to get your version back.",
+            self.diff_version, object_count
+        );
+    }
+
+    pub fn begin_external_tool_selection(&mut self) {
+        if let Some(run) = &self.external_tool_run {
+            println!("{} is still running.", run.name);
+            return;
+        }
+        let tools = &self.editor_config.general.external_tools;
+        if tools.is_empty() {
+            println!(
+                "No external tools set up, add them to general.external_tools in config.json."
+            );
+            return;
+        }
+        println!("External tools:");
+        for (i, tool) in tools.iter().enumerate() {
+            println!("  {}: {}", i + 1, tool.name);
+        }
+        println!("Type a number and press Enter to run it on this difficulty (Esc to cancel).");
+        self.begin_text_input(TextInputTarget::ExternalTool);
+    }
+
+    // Writes the current state as .osu and runs the tool on a separate thread, the editor
+    // stays usable while it is open.
+    fn run_external_tool(&mut self, input: String) {
+        let Some(tool) = input
+            .trim()
+            .parse::<usize>()
+            .ok()
+            .and_then(|n| n.checked_sub(1))
+            .and_then(|i| self.editor_config.general.external_tools.get(i))
+            .cloned()
+        else {
+            println!("No external tool numbered {}.", input.trim());
+            return;
+        };
+        let Some(template) = self
+            .beatmapset
+            .beatmaps
+            .iter()
+            .find(|b| b.version == self.diff_version)
+        else {
+            println!("Current difficulty not found in beatmapset.");
+            return;
+        };
+        let beatmap = self
+            .edit_state
+            .read()
+            .expect("edit_state lock poisoned")
+            .current_map_state()
+            .to_beatmap(template);
+        let Some(osu_path) = write_tool_folder(&self.beatmapset, &beatmap) else {
+            return;
+        };
+        let osu_path = std::env::current_dir()
+            .map(|dir| dir.join(&osu_path))
+            .unwrap_or(osu_path);
+        let folder = osu_path.parent().unwrap_or(Path::new(".")).to_path_buf();
+        let args = tool_command_args(&tool.command, &osu_path, &folder);
+        let Some((program, args)) = args.split_first() else {
+            println!("{} has no command.", tool.name);
+            return;
+        };
+
+        println!("Running {}: {:?} {:?}", tool.name, program, args);
+        let mut command = Command::new(program);
+        command.args(args).current_dir(folder);
+        let (tx, rx) = mpsc::channel();
+        std::thread::spawn(move || {
+            let _ = tx.send(command.status());
+        });
+        self.external_tool_run = Some(ExternalToolRun {
+            name: tool.name,
+            written_at: fs::metadata(&osu_path).and_then(|m| m.modified()).ok(),
+            osu_path,
+            finished: rx,
+        });
+    }
+
+    fn check_external_tool_finished(&mut self) {
+        let Some(run) = &self.external_tool_run else {
+            return;
+        };
+        let status = match run.finished.try_recv() {
+            Ok(status) => status,
+            Err(mpsc::TryRecvError::Empty) => return,
+            Err(mpsc::TryRecvError::Disconnected) => {
+                Err(std::io::Error::other("the tool thread stopped"))
+            }
+        };
+        let Some(run) = self.external_tool_run.take() else {
+            return;
+        };
+        match status {
+            Ok(status) if status.success() => println!("{} finished.", run.name),
+            Ok(status) => println!("{} exited with {}.", run.name, status),
+            Err(err) => {
+                println!("Failed to run {}: {}", run.name, err);
+                return;
+            }
+        }
+        let modified = fs::metadata(&run.osu_path).and_then(|m| m.modified()).ok();
+        if modified.is_some() && modified != run.written_at {
+            self.reload_osu_file(&run.osu_path, false);
+        }
+    }
+
+    pub fn copy_selection(&mut self) {
+        let edit_state = self.edit_state.read().expect("edit_state lock poisoned");
+        self.copied_objects = edit_state.selected_hit_objects(true);
+        if !self.copied_objects.is_empty() {
+            self.clipboard.set_text(objects_to_text(&self.copied_objects));
+        }
+        println!("Copied {} object(s).", self.copied_objects.len());
+    }
+
+    // Copies the time on a clicked line of the stats box, in that line's format.
+    fn copy_stats_box_time(&mut self, line: usize) {
+        let playhead_line = layout::STATS_BOX_PLAYHEAD_TIME_LINE;
+        let object_line = layout::STATS_BOX_OBJECT_TIME_LINE;
+        let (time_ms, format) = if (playhead_line..playhead_line + 3).contains(&line) {
+            (Some(self.audio.current_time().0), line - playhead_line)
+        } else if (object_line..object_line + 3).contains(&line) {
+            let edit_state = self.edit_state.read().expect("edit_state lock poisoned");
+            let first = edit_state.hit_objects_in_selections().first().map(|o| o.time());
+            (first, line - object_line)
+        } else {
+            return;
+        };
+        let Some(time_ms) = time_ms else {
+            println!("No selected object to copy the time of.");
+            return;
+        };
+        let text = time_formats(time_ms, &self.current_timing())[format].clone();
+        if self.clipboard.set_text(text.clone()) {
+            println!("Copied {}", text);
+        }
+    }
+
+    // Puts the selection on the clipboard as a modding timestamp, for mod posts.
+    pub fn copy_selection_timestamp(&mut self) {
+        let timestamp = {
+            let edit_state = self.edit_state.read().expect("edit_state lock poisoned");
+            edit_state.selection_timestamp()
+        };
+        let Some((time_ms, combo_numbers)) = timestamp else {
+            println!("Nothing selected to copy a timestamp of.");
+            return;
+        };
+        let text = format_modding_timestamp(time_ms, &combo_numbers);
+        if self.clipboard.set_text(text.clone()) {
+            println!("Copied {}", text);
+        }
+    }
+
+    // Pastes objects copied in this or another editor instance, first object at the playhead.
+    // A modding timestamp on the clipboard jumps to it instead.
+    pub fn paste_from_clipboard(&mut self) {
+        let Some(text) = self.clipboard.get_text() else {
+            println!("Nothing to paste on the clipboard.");
+            return;
+        };
+        if let Some(objects) = objects_from_text(&text) {
+            if !self.objects_locked() {
+                self.paste_objects(objects);
+            }
+        } else if let Some(timestamp) = parse_modding_timestamp(&text) {
+            self.jump_to_timestamp(&timestamp);
+        } else {
+            println!("No copied objects or timestamp on the clipboard.");
+        }
+    }
+
+    fn jump_to_timestamp(&self, timestamp: &ModdingTimestamp) {
+        let time_ms = timestamp.time_ms.clamp(0.0, self.audio.song_total().0);
+        self.audio.seek(SongTimeMs(time_ms));
+        let mut edit_state = self.edit_state.write().expect("edit_state lock poisoned");
+        let selected =
+            edit_state.select_timestamp_objects(timestamp.time_ms, &timestamp.combo_numbers);
+        println!(
+            "Jumped to {} and selected {} object(s).",
+            format_clock_time(timestamp.time_ms),
+            selected
+        );
+    }
+
+    fn paste_objects(&self, mut objects: Vec<HitObject>) {
+        let Some(start_ms) = objects.iter().map(|o| o.time()).min_by(|a, b| a.total_cmp(b))
+        else {
+            return;
+        };
+        let time_ms = self.audio.current_time().0;
+        for object in objects.iter_mut() {
+            let time = time_ms + object.time() - start_ms;
+            object.retime(time, 1.0);
+        }
+        println!("Pasted {} object(s) at {:.0}ms.", objects.len(), time_ms);
+        let mut edit_state = self.edit_state.write().expect("edit_state lock poisoned");
+        edit_state.insert_objects(objects);
+    }
+
+    pub fn paste_rhythm_at_playhead(&mut self) {
+        if self.objects_locked() {
+            return;
+        }
+        let time = self.audio.current_time();
+        let mut edit_state = self.edit_state.write().expect("edit_state lock poisoned");
+        edit_state.paste_rhythm(&self.copied_objects, time);
+    }
+
+    pub fn resnap_unsnapped_objects(&mut self) {
+        if self.objects_locked() {
+            return;
+        }
+        let mut edit_state = self.edit_state.write().expect("edit_state lock poisoned");
+        match edit_state.resnap_unsnapped_objects() {
+            0 => println!("No unsnapped objects."),
+            count => println!("Resnapped {} object(s), they are now the left selection.", count),
+        }
+    }
+
+    pub fn split_slider_at_playhead(&mut self) {
+        if self.objects_locked() {
+            return;
+        }
+        let time = self.audio.current_time();
+        let mut edit_state = self.edit_state.write().expect("edit_state lock poisoned");
+        edit_state.split_slider_at_time(time);
+    }
+
+    pub fn cycle_msaa_samples(&mut self) {
+        let Some(shared) = self.render_shared.as_ref() else {
+            return;
+        };
+        if self.editor_config.performance.potato_mode {
+            println!("MSAA stays off in potato mode, Shift + F4 to leave it.");
+            return;
+        }
+        let supported = shared.supported_msaa_samples();
+        let current = shared.msaa_samples();
+        let next = supported
+            .iter()
+            .copied()
+            .find(|samples| *samples > current)
+            .or_else(|| supported.first().copied())
+            .unwrap_or(1);
+        println!(
+            "MSAA: {}x -> {}x (supported: {})",
+            current,
+            next,
+            supported
+                .iter()
+                .map(|samples| format!("{}x", samples))
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+        shared.request_msaa_samples(next);
+        self.editor_config.performance.msaa_samples = next;
+    }
+
+    // MSAA goes back to performance.msaa_samples when leaving potato mode.
+    pub fn toggle_potato_mode(&mut self) {
+        let enabled = !self.editor_config.performance.potato_mode;
+        self.editor_config.performance.potato_mode = enabled;
+        if let Some(shared) = self.render_shared.as_ref() {
+            shared.set_potato_mode(enabled);
+            let samples = if enabled {
+                1
+            } else {
+                self.editor_config.performance.msaa_samples
+            };
+            shared.request_msaa_samples(samples);
+        }
+        println!("Potato mode {}", if enabled { "on" } else { "off" });
+    }
+
+    pub fn toggle_flow_overlay(&mut self) {
+        let visible = !self.editor_config.appearance.general.show_flow_overlay;
+        self.editor_config.appearance.general.show_flow_overlay = visible;
+        if let Some(shared) = self.render_shared.as_ref() {
+            shared.set_flow_overlay_visible(visible);
+        }
+        println!("Flow overlay {}", if visible { "on" } else { "off" });
+    }
+
+    pub fn toggle_kiai_effects(&mut self) {
+        let visible = !self.editor_config.appearance.general.show_kiai_effects;
+        self.editor_config.appearance.general.show_kiai_effects = visible;
+        if let Some(shared) = self.render_shared.as_ref() {
+            shared.set_kiai_effects_visible(visible);
+        }
+        println!("Kiai effects {}", if visible { "on" } else { "off" });
+    }
+
+    pub fn toggle_frame_graph(&mut self) {
+        let visible = !self.editor_config.appearance.general.show_frame_graph;
+        self.editor_config.appearance.general.show_frame_graph = visible;
+        if let Some(shared) = self.render_shared.as_ref() {
+            shared.set_frame_graph_visible(visible);
+        }
+        println!("Frame-time graph {}", if visible { "on" } else { "off" });
+    }
+
+    pub fn toggle_beatmap_combo_colors(&mut self) {
+        let ignore = !self
+            .editor_config
+            .appearance
+            .general
+            .ignore_beatmap_combo_colors;
+        self.editor_config
+            .appearance
+            .general
+            .ignore_beatmap_combo_colors = ignore;
+        if let Some(shared) = self.render_shared.as_ref() {
+            shared.set_ignore_beatmap_combo_colors(ignore);
+        }
+        if ignore && self.skin.combo_colors.is_empty() {
+            println!("Combo colours: skin (the skin sets none, using the config fallback)");
+        } else {
+            println!("Combo colours: {}", if ignore { "skin" } else { "map" });
+        }
+    }
+
+    fn current_view_preset(&self) -> ViewPreset {
+        ViewPreset::from_name(&self.editor_config.appearance.general.view_preset)
+            .unwrap_or(ViewPreset::Design)
+    }
+
+    pub fn cycle_view_preset(&mut self) {
+        let view_preset = self.current_view_preset().next();
+        self.editor_config.appearance.general.view_preset = view_preset.name().to_string();
+        if let Some(shared) = self.render_shared.as_ref() {
+            shared.set_view_preset(view_preset);
+        }
+        println!("View preset: {}", view_preset.name());
+    }
+
+    // Draws the playfield at a simulated AR, or CS with `cs`, one step from the current one.
+    // The map's difficulty settings stay as they are.
+    pub fn nudge_difficulty_simulation(&mut self, cs: bool, sign: f64) {
+        let diff_settings = {
+            let edit_state = self.edit_state.read().expect("edit_state lock poisoned");
+            edit_state.current_map_state().diff_settings.clone()
+        };
+        let simulation = self.difficulty_simulation.nudged(&diff_settings, cs, 0.1 * sign);
+        self.set_difficulty_simulation(simulation);
+    }
+
+    pub fn reset_difficulty_simulation(&mut self) {
+        self.set_difficulty_simulation(DifficultySimulation::default());
+    }
+
+    fn set_difficulty_simulation(&mut self, simulation: DifficultySimulation) {
+        self.difficulty_simulation = simulation;
+        if let Some(shared) = self.render_shared.as_ref() {
+            shared.set_difficulty_simulation(simulation);
+        }
+    }
+
+    // The simulated AR / CS take over the hint line while on, it is easy to forget otherwise.
+    // A failed save stays up until it is retried successfully, over every other hint.
+    fn hint_line(&self) -> String {
+        if let Some(hint) = failed_writes_hint(&failed_writes()) {
+            return hint;
+        }
+        if !self.difficulty_simulation.is_active() {
+            return self.tool_state.hint();
+        }
+        let edit_state = self.edit_state.read().expect("edit_state lock poisoned");
+        self.difficulty_simulation
+            .hint(&edit_state.current_map_state().diff_settings)
+    }
+
+    pub fn retry_failed_saves(&self) {
+        if failed_writes().is_empty() {
+            println!("No failed saves to retry.");
+            return;
+        }
+        match retry_failed_writes() {
+            0 => println!("Every failed save went through on retry."),
+            still_failing => println!(
+                "{} save(s) still failed, free up disk space or check folder permissions.",
+                still_failing
+            ),
+        }
+    }
+
+    pub fn is_onboarding_visible(&self) -> bool {
+        self.onboarding_visible
+    }
+
+    pub fn show_onboarding(&mut self) {
+        self.onboarding_visible = true;
+    }
+
+    // Remembered next to config.json, so the overlay only comes up by itself once.
+    pub fn dismiss_onboarding(&mut self) {
+        self.onboarding_visible = false;
+        if !onboarding_seen() {
+            record_onboarding_seen();
+        }
+    }
+
+    // The playfield, both timelines and the undo panel, outlined while the first-run overlay
+    // is up and none otherwise.
+    fn visible_onboarding_regions(&self) -> Vec<OnboardingRegion> {
+        if !self.onboarding_visible {
+            return Vec::new();
+        }
+        let screen_w = self.width.max(1) as f64;
+        let screen_h = self.height.max(1) as f64;
+        let hud_layout = HudLayout::from_config(&self.editor_config.appearance.layout);
+        let frame_layout = layout::compute_layout(
+            screen_w,
+            screen_h,
+            self.current_playfield_scale(),
+            &hud_layout,
+        );
+        let (undo_top_left, _) =
+            Self::undo_button_bounds(screen_w, screen_h, hud_layout.timeline_height_percent);
+        let (redo_top_left, redo_size) = Self::redo_buttons_hitbox_bounds(
+            screen_w,
+            screen_h,
+            hud_layout.timeline_height_percent,
+        );
+        let undo_panel_rect = layout::Rect {
+            x0: undo_top_left.x,
+            y0: undo_top_left.y,
+            x1: redo_top_left.x + redo_size.x,
+            y1: redo_top_left.y + redo_size.y,
+        };
+        onboarding_regions(&frame_layout, &undo_panel_rect)
+    }
+
+    pub fn select_tool_mode(&mut self, mode: ToolMode) {
+        self.handle_tool_event(ToolEvent::SwitchTo(mode));
+    }
+
+    // True when the tool mode locks objects, so the object edit asking should do nothing.
+    fn objects_locked(&mut self) -> bool {
+        self.handle_tool_event(ToolEvent::ObjectEdit)
+    }
+
+    // Runs `event` through the tool mode and carries out what it asks for. False when the
+    // mode has no use for it, so the key keeps its usual binding.
+    pub fn handle_tool_event(&mut self, event: ToolEvent) -> bool {
+        match self.tool_state.handle(event) {
+            ToolAction::Unhandled => return false,
+            ToolAction::None => {}
+            ToolAction::ModeChanged(mode) => {
+                self.tool_mode.store(mode as u32, Ordering::Release);
+                println!("Tool: {}", mode.name());
+            }
+            ToolAction::PlaceCircle(pos) => self.place_objects_at_playhead(|time, map_state| {
+                placed_circle(pos, time, &map_state.timing)
+            }),
+            ToolAction::AddSliderAnchor(count) => {
+                println!("Slider anchor {}, Enter or right click to place the slider.", count);
+            }
+            ToolAction::PlaceSlider(anchors) => {
+                self.place_objects_at_playhead(|time, map_state| {
+                    placed_slider(&anchors, time, &map_state.timing, &map_state.diff_settings)
+                });
+            }
+            ToolAction::OpenTimingTools => self.begin_timing_tools(),
+        }
+        true
+    }
+
+    // Places the object `build` makes at the playhead, snapped to the nearest tick.
+    fn place_objects_at_playhead<F>(&self, build: F)
+    where
+        F: FnOnce(f64, &MapState) -> Option<HitObject>,
+    {
+        let mut edit_state = self.edit_state.write().expect("edit_state lock poisoned");
+        let map_state = edit_state.current_map_state();
+        let time_ms = self.audio.current_time().0;
+        let time_ms = map_state.timing.nearest_snap(time_ms).unwrap_or(time_ms);
+        let Some(object) = build(time_ms, &*map_state) else {
+            println!("Nothing placed at {:.0}ms, is there a red line before it?", time_ms);
+            return;
+        };
+        edit_state.insert_objects(vec![object]);
+    }
+
+    fn current_convert_mode(&self) -> ConvertMode {
+        ConvertMode::from_name(&self.editor_config.appearance.general.convert_preview)
+            .unwrap_or(ConvertMode::Off)
+    }
+
+    pub fn cycle_convert_preview(&mut self) {
+        let convert_mode = self.current_convert_mode().next();
+        self.editor_config.appearance.general.convert_preview = convert_mode.name().to_string();
+        if let Some(shared) = self.render_shared.as_ref() {
+            shared.set_convert_mode(convert_mode);
+        }
+        println!("Convert preview: {}", convert_mode.name());
+    }
+
+    // The arrangement is written to config.json when leaving the mode.
+    pub fn toggle_layout_edit_mode(&mut self) {
+        let active = !self.layout_edit_mode.load(Ordering::Acquire);
+        self.layout_edit_mode.store(active, Ordering::Release);
+        self.layout_drag.end_drag();
+        self.layout_drag_target = None;
+        if active {
+            println!(
+                "Editing the HUD layout: drag the stats box to move it, the bottom edge of the top \
+                 timeline to change its height and the gap left of its second or third box to \
+                 change that box's width. Press ] again to save."
+            );
+            return;
+        }
+        if !std::mem::take(&mut self.layout_edited) {
+            println!("Stopped editing the HUD layout.");
+            return;
+        }
+        if let Some(path) = write_layout_config(
+            &self.beatmapset.map_dir_name,
+            &self.editor_config.appearance.layout,
+            self.editor_config.general.config_backups,
+        ) {
+            println!("Saved the HUD layout to {}.", path.display());
+        }
+    }
+
+    fn compute_layout_for(&self, hud_layout: &HudLayout) -> layout::Layout {
+        layout::compute_layout(
+            self.width.max(1) as f64,
+            self.height.max(1) as f64,
+            self.current_playfield_scale(),
+            hud_layout,
+        )
+    }
+
+    // The stats box is grabbed together with the volume boxes on its right.
+    fn layout_drag_target_at(frame_layout: &layout::Layout, pos: Vec2) -> Option<LayoutDragTarget> {
+        let grab_px = 6.0;
+        let stats = &frame_layout.stats_box_rect;
+        let top = &frame_layout.top_timeline_rect;
+        let second = &frame_layout.top_timeline_second_rect;
+        let third = &frame_layout.top_timeline_third_rect;
+        let stats_x1 = stats.x1.max(frame_layout.audio_volume_box_rect.x1);
+        if pos.x >= stats.x0 && pos.x <= stats_x1 && pos.y >= stats.y0 && pos.y <= stats.y1 {
+            return Some(LayoutDragTarget::StatsBox);
+        }
+        if (pos.y - top.y1).abs() <= grab_px {
+            return Some(LayoutDragTarget::TimelineHeight);
+        }
+        if pos.y > top.y1 {
+            return None;
+        }
+        if pos.x >= top.x1 - grab_px && pos.x <= second.x0 + grab_px {
+            Some(LayoutDragTarget::SecondBoxWidth)
+        } else if pos.x >= second.x1 - grab_px && pos.x <= third.x0 + grab_px {
+            Some(LayoutDragTarget::ThirdBoxWidth)
+        } else {
+            None
+        }
+    }
+
+    fn apply_layout_drag(&mut self) {
+        let Some((start, current)) = self.layout_drag.drag() else {
+            self.layout_drag_target = None;
+            return;
+        };
+        let (target, origin) = match self.layout_drag_target {
+            Some(drag) => drag,
+            None => {
+                let origin = HudLayout::from_config(&self.editor_config.appearance.layout);
+                let frame_layout = self.compute_layout_for(&origin);
+                let Some(target) = Self::layout_drag_target_at(&frame_layout, start) else {
+                    return;
+                };
+                self.layout_drag_target = Some((target, origin));
+                (target, origin)
+            }
+        };
+        let screen_w = self.width.max(1) as f64;
+        let screen_h = self.height.max(1) as f64;
+        let delta = current - start;
+        let mut hud_layout = origin;
+        match target {
+            LayoutDragTarget::StatsBox => {
+                hud_layout.stats_box_offset_px = [
+                    origin.stats_box_offset_px[0] + delta.x,
+                    origin.stats_box_offset_px[1] + delta.y,
+                ];
+                // Keep the offset the box ends up at, it stops at the window edges.
+                let mut unmoved = hud_layout;
+                unmoved.stats_box_offset_px = [0.0, 0.0];
+                let home = self.compute_layout_for(&unmoved).stats_box_rect;
+                let placed = self.compute_layout_for(&hud_layout).stats_box_rect;
+                hud_layout.stats_box_offset_px = [placed.x0 - home.x0, placed.y0 - home.y0];
+            }
+            LayoutDragTarget::TimelineHeight => {
+                hud_layout.timeline_height_percent =
+                    (origin.timeline_height_percent + delta.y / screen_h).clamp(0.02, 0.5);
+            }
+            LayoutDragTarget::SecondBoxWidth => {
+                hud_layout.timeline_second_box_width_percent =
+                    (origin.timeline_second_box_width_percent - delta.x / screen_w)
+                        .clamp(0.02, 0.4);
+            }
+            LayoutDragTarget::ThirdBoxWidth => {
+                hud_layout.timeline_third_box_width_percent =
+                    (origin.timeline_third_box_width_percent - delta.x / screen_w)
+                        .clamp(0.02, 0.4);
+            }
+        }
+        if hud_layout != HudLayout::from_config(&self.editor_config.appearance.layout) {
+            self.set_hud_layout(hud_layout);
+        }
+    }
+
+    fn set_hud_layout(&mut self, hud_layout: HudLayout) {
+        hud_layout.write_to_config(&mut self.editor_config.appearance.layout);
+        self.timeline_height_percent_state.store(
+            (hud_layout.timeline_height_percent as f32).to_bits(),
+            Ordering::Release,
+        );
+        if let Some(shared) = self.render_shared.as_ref() {
+            shared.set_pending_hud_layout(hud_layout);
+        }
+        self.refresh_hitbox_bounds();
+        self.layout_edited = true;
+    }
+
+    // Outline of the panel being dragged, drawn in place of the drag selection rectangle.
+    fn layout_drag_rect(&self) -> Option<[f32; 4]> {
+        let (target, _) = self.layout_drag_target?;
+        let frame_layout = self.compute_layout_for(&HudLayout::from_config(
+            &self.editor_config.appearance.layout,
+        ));
+        let rect = match target {
+            LayoutDragTarget::StatsBox => layout::Rect {
+                x1: frame_layout.audio_volume_box_rect.x1,
+                ..frame_layout.stats_box_rect
+            },
+            LayoutDragTarget::TimelineHeight => frame_layout.top_timeline_rect,
+            LayoutDragTarget::SecondBoxWidth => frame_layout.top_timeline_second_rect,
+            LayoutDragTarget::ThirdBoxWidth => frame_layout.top_timeline_third_rect,
+        };
+        Some(rect.to_f32_array())
+    }
+
+    // Hitsounds of this difficulty without music, for lining up against the song in a DAW.
+    pub fn export_hitsound_stem(&self) {
+        let path = self
+            .analysis_dir()
+            .join(format!("{} hitsounds.wav", self.diff_version));
+        println!("Rendering hitsound stem to {}...", path.display());
+        self.audio.export_hitsound_stem(path);
+    }
+
+    pub fn begin_playback_mix_export(&mut self) {
+        println!("Type \"<start ms> <end ms>\", or nothing for the selected objects.");
+        println!("Add \"click\" for a metronome and press Enter (Esc to cancel).");
+        self.begin_text_input(TextInputTarget::PlaybackMix);
+    }
+
+    // Music at the current rate, hitsounds and optionally metronome clicks, as they play.
+    fn export_playback_mix(&self, input: String) {
+        let mut words: Vec<&str> = input.split_whitespace().collect();
+        let metronome = words.last() == Some(&"click");
+        if metronome {
+            words.pop();
+        }
+        let range = match words.as_slice() {
+            [] => {
+                let edit_state = self.edit_state.read().expect("edit_state lock poisoned");
+                edit_state.selection_time_range()
+            }
+            [start, end] => match (start.parse::<f64>(), end.parse::<f64>()) {
+                (Ok(start_ms), Ok(end_ms)) if start_ms < end_ms => Some((start_ms, end_ms)),
+                _ => None,
+            },
+            _ => None,
+        };
+        let Some((start_ms, end_ms)) = range else {
+            println!("Expected \"<start ms> <end ms>\" or a selection.");
+            return;
+        };
+        let beats = if metronome {
+            self.current_timing()
+                .beats_between(start_ms, end_ms)
+                .into_iter()
+                .map(|(time_ms, downbeat)| (SongTimeMs(time_ms), downbeat))
+                .collect()
+        } else {
+            Vec::new()
+        };
+        let path = self.analysis_dir().join(format!(
+            "{} mix {:.0}-{:.0}.wav",
+            self.diff_version, start_ms, end_ms
+        ));
+        println!("Rendering the playback mix to {}...", path.display());
+        self.audio
+            .export_playback_mix(path, SongTimeMs(start_ms), SongTimeMs(end_ms), beats);
+    }
+
+    pub fn begin_replay_import(&mut self) {
+        let replays = scan_folder(Path::new("imports"), Some(false), Some(&vec![".osr"]));
+        let loaded = self
+            .render_shared
+            .as_ref()
+            .is_some_and(|shared| shared.replay().is_some());
+        if replays.is_empty() && !loaded {
+            println!("No .osr replays found in imports/");
+            return;
+        }
+        println!("Replays in imports: {}", replays.join(", "));
+        println!("Type a replay name (a prefix is enough) and press Enter (Esc to cancel).");
+        if loaded {
+            println!("Press Enter with nothing typed to hide the loaded replay.");
+        }
+        self.begin_text_input(TextInputTarget::ReplayImport);
+    }
+
+    // Draws a testplay's cursor and clicks over the playfield and lists the objects it missed.
+    fn import_replay(&self, input: String) {
+        let Some(shared) = self.render_shared.as_ref() else {
+            return;
+        };
+        let input = input.trim();
+        if input.is_empty() {
+            shared.set_replay(None);
+            println!("Replay hidden.");
+            return;
+        }
+        let path = match scan_folder(Path::new("imports"), Some(false), Some(&vec![".osr"]))
+            .into_iter()
+            .find(|name| name.starts_with(input))
+        {
+            Some(name) => Path::new("imports").join(name),
+            None => PathBuf::from(input),
+        };
+        let Some(replay) = read_replay(&path) else {
+            return;
+        };
+        if replay.mode != 0 {
+            println!("{} is not an osu!standard replay.", path.display());
+            return;
+        }
+
+        let map_state = {
+            let edit_state = self.edit_state.read().expect("edit_state lock poisoned");
+            edit_state.current_map_state()
+        };
+        let judgements = map_state.replay_judgements(&replay);
+        let count = |score: u32| judgements.iter().filter(|j| j.score == score).count();
+        println!(
+            "Replay by {}: {} x300, {} x100, {} x50, {} misses.",
+            replay.player,
+            count(300),
+            count(100),
+            count(50),
+            count(0)
+        );
+        println!(
+            "Its result screen: {} x300, {} x100, {} x50, {} misses, {}x max combo.",
+            replay.count_300, replay.count_100, replay.count_50, replay.count_miss, replay.max_combo
+        );
+        for judgement in judgements.iter().filter(|j| j.hit_error_ms.is_none()) {
+            println!("  miss at {:.0}ms", judgement.object_time_ms);
+        }
+        shared.set_replay(Some(Arc::new(replay)));
+    }
+
+    pub fn begin_combo_proposal(&mut self) {
+        if self.objects_locked() {
+            return;
+        }
+        println!("Type the number of measures per combo (1 if empty) to preview new combos on");
+        println!("downbeats and bookmarks, or \"apply\" to apply the last preview, and press");
+        println!("Enter (Esc to cancel).");
+        self.begin_text_input(TextInputTarget::ComboProposal);
+    }
+
+    // Same precedence as the renderer: map colours unless ignored, then the skin's, then the
+    // config fallback.
+    fn combo_color_count(&self, map_state: &MapState) -> usize {
+        if !map_state.combo_colors.is_empty()
+            && !self
+                .editor_config
+                .appearance
+                .general
+                .ignore_beatmap_combo_colors
+        {
+            map_state.combo_colors.len()
+        } else if !self.skin.combo_colors.is_empty() {
+            self.skin.combo_colors.len()
+        } else {
+            self.editor_config
+                .appearance
+                .colors
+                .fallback_combo_colors_rgb
+                .len()
+        }
+    }
+
+    fn apply_combo_proposal_command(&mut self, input: String) {
+        let input = input.trim();
+        let map_state = {
+            let edit_state = self.edit_state.read().expect("edit_state lock poisoned");
+            edit_state.current_map_state()
+        };
+        let color_count = self.combo_color_count(&map_state);
+        if input == "apply" {
+            let Some(measures) = self.combo_proposal_measures.take() else {
+                println!("Preview a combo proposal first.");
+                return;
+            };
+            let proposal = map_state.combo_proposal(measures, color_count);
+            let changed = self
+                .edit_state
+                .write()
+                .expect("edit_state lock poisoned")
+                .set_new_combos(&proposal.new_combos);
+            println!("Changed the new combo flag of {} object(s).", changed);
+            return;
+        }
+
+        let measures = if input.is_empty() {
+            1
+        } else {
+            match input.parse::<usize>() {
+                Ok(measures) if measures > 0 => measures,
+                _ => {
+                    println!("Expected a number of measures or \"apply\".");
+                    return;
+                }
+            }
+        };
+        let proposal = map_state.combo_proposal(measures, color_count);
+        println!(
+            "{} combo(s) every {} measure(s) and at bookmarks: {} new combo(s) added, {} removed.",
+            proposal.combos.len(),
+            measures,
+            proposal.added,
+            proposal.removed
+        );
+        for combo in proposal.combos.iter() {
+            println!(
+                "  {:.0}ms  colour {}  {} object(s)",
+                combo.start_ms,
+                combo.color_index + 1,
+                combo.object_count
+            );
+        }
+        println!("Shift + N and \"apply\" to use it.");
+        self.combo_proposal_measures = Some(measures);
+    }
+
+    fn write_session(&self) {
+        let session = MapSession {
+            timeline_zoom: self.current_timeline_zoom(),
+            selection_sets: self.selection_sets.clone(),
+        };
+        write_map_session(&self.beatmapset.map_dir_name, &session);
+    }
+
+    fn record_editing_session(&mut self) {
+        let current = self
+            .edit_state
+            .read()
+            .expect("edit_state lock poisoned")
+            .current_map_state();
+        let Some(session) = self.session_tracker.session(&self.diff_version, &current) else {
+            return;
+        };
+        println!(
+            "Session on {}: {:.0} min active, {} placed, {} edited, {} removed.",
+            session.difficulty,
+            session.active_ms / 60_000.0,
+            session.placed,
+            session.edited,
+            session.removed
+        );
+        record_editing_session(
+            &self.beatmapset.map_dir_name,
+            &session,
+            self.editor_config.general.write_session_csv,
+        );
+    }
+
+    // Editing on this difficulty so far, including the session in progress.
+    fn editing_totals(&mut self) -> EditingTotals {
+        let current = self
+            .edit_state
+            .read()
+            .expect("edit_state lock poisoned")
+            .current_map_state();
+        self.session_tracker.totals(&self.diff_version, &current)
+    }
+
+    // Selection sets of the open difficulty, in the order they were saved.
+    fn difficulty_selection_sets(&self) -> Vec<&SelectionSet> {
+        self.selection_sets
+            .iter()
+            .filter(|set| set.difficulty == self.diff_version)
+            .collect()
+    }
+
+    pub fn begin_selection_sets(&mut self) {
+        let sets = self.difficulty_selection_sets();
+        if sets.is_empty() {
+            println!("No selection sets saved for this difficulty yet.");
+        }
+        for (i, set) in sets.iter().enumerate() {
+            println!(
+                "  {}. {} ({} left, {} right)",
+                i + 1,
+                set.name,
+                set.left_times_ms.len(),
+                set.right_times_ms.len()
+            );
+        }
+        println!("Type \"save <name>\" to store the current selections, a number or name to");
+        println!("restore a set, or \"delete <name>\", and press Enter (Esc to cancel).");
+        println!("Ctrl + 1-9 restores a set directly.");
+        self.begin_text_input(TextInputTarget::SelectionSet);
+    }
+
+    fn apply_selection_set_command(&mut self, input: String) {
+        let input = input.trim();
+        if let Some(name) = input.strip_prefix("save ") {
+            self.save_selection_set(name.trim());
+        } else if let Some(name) = input.strip_prefix("delete ") {
+            let name = name.trim();
+            let before = self.selection_sets.len();
+            self.selection_sets
+                .retain(|set| set.difficulty != self.diff_version || set.name != name);
+            if self.selection_sets.len() == before {
+                println!("No selection set named {}.", name);
+                return;
+            }
+            self.write_session();
+            println!("Deleted selection set {}.", name);
+        } else if let Ok(number) = input.parse::<usize>() {
+            self.restore_selection_set(number);
+        } else if !input.is_empty() {
+            let number = self
+                .difficulty_selection_sets()
+                .iter()
+                .position(|set| set.name == input)
+                .map(|i| i + 1);
+            match number {
+                Some(number) => self.restore_selection_set(number),
+                None => println!("No selection set named {}.", input),
+            }
+        }
+    }
+
+    // Saving under an existing name replaces that set.
+    fn save_selection_set(&mut self, name: &str) {
+        if name.is_empty() {
+            return;
+        }
+        let (left_times_ms, right_times_ms) = {
+            let edit_state = self.edit_state.read().expect("edit_state lock poisoned");
+            (
+                edit_state.selected_object_times(true),
+                edit_state.selected_object_times(false),
+            )
+        };
+        if left_times_ms.is_empty() && right_times_ms.is_empty() {
+            println!("Nothing selected to save as a set.");
+            return;
+        }
+        println!(
+            "Saved selection set {} ({} left, {} right).",
+            name,
+            left_times_ms.len(),
+            right_times_ms.len()
+        );
+        let set = SelectionSet {
+            difficulty: self.diff_version.clone(),
+            name: name.to_string(),
+            left_times_ms,
+            right_times_ms,
+        };
+        match self
+            .selection_sets
+            .iter_mut()
+            .find(|existing| existing.difficulty == set.difficulty && existing.name == set.name)
+        {
+            Some(existing) => *existing = set,
+            None => self.selection_sets.push(set),
+        }
+        self.write_session();
+    }
+
+    // `number` counts from 1, in the order begin_selection_sets lists them.
+    pub fn restore_selection_set(&mut self, number: usize) {
+        let Some(set) = number
+            .checked_sub(1)
+            .and_then(|i| self.difficulty_selection_sets().get(i).map(|set| (*set).clone()))
+        else {
+            println!("No selection set numbered {}.", number);
+            return;
+        };
+        let missing = self
+            .edit_state
+            .write()
+            .expect("edit_state lock poisoned")
+            .select_objects_at_times(&set.left_times_ms, &set.right_times_ms);
+        if missing > 0 {
+            println!(
+                "Restored selection set {}, {} object(s) no longer exist.",
+                set.name, missing
+            );
+        } else {
+            println!("Restored selection set {}.", set.name);
+        }
+    }
+
+    // Every edit made to this difficulty since it was opened, oldest first.
+    pub fn export_operation_log(&self) {
+        let edit_state = self.edit_state.read().expect("edit_state lock poisoned");
+        let log = &edit_state.operation_log;
+        let json = match serde_json::to_string_pretty(log) {
+            Ok(json) => json,
+            Err(err) => {
+                println!("Failed to serialize operation log: {}", err);
+                return;
+            }
+        };
+        let path = self.analysis_dir().join(format!(
+            "{} operations {}.json",
+            self.diff_version, log.started_at
+        ));
+        if let Err(err) = write_bytes_to_file(&path, json.as_bytes()) {
+            println!("Failed to write {}: {}", path.display(), err);
+            return;
+        }
+        println!(
+            "Exported {} operation(s) to {}.",
+            log.entries.len(),
+            path.display()
+        );
+    }
+
+    pub fn show_report(&mut self, panel: ReportPanel) {
+        self.report_panel = Some(panel);
+    }
+
+    // Returns whether there was a report to close.
+    pub fn close_report(&mut self) -> bool {
+        self.report_panel.take().is_some()
+    }
+
+    pub fn jump_to_next_overlap_warning(&mut self) {
+        let max_overlap_percent = self.editor_config.general.overlap_warning_percent;
+        let map_state = {
+            let edit_state = self.edit_state.read().expect("edit_state lock poisoned");
+            edit_state.current_map_state()
+        };
+        let warnings = map_state.overlap_warnings(max_overlap_percent);
+        if warnings.is_empty() {
+            self.show_report(ReportPanel::new(&format!(
+                "no overlaps above {:.0} pct",
+                max_overlap_percent
+            )));
+            return;
+        }
+
+        let time_ms = self.audio.current_time().0;
+        let next = warnings
+            .iter()
+            .position(|warning| warning.start_ms > time_ms + 1.0)
+            .unwrap_or(0);
+        let mut panel = ReportPanel::new(&format!(
+            "{} overlaps above {:.0} pct - f5 next",
+            warnings.len(),
+            max_overlap_percent
+        ));
+        for (i, warning) in warnings.iter().enumerate() {
+            panel.push(&format!(
+                "{} {:.0}ms - {:.0}ms  up to {:.0} pct",
+                if i == next { "|" } else { " " },
+                warning.start_ms,
+                warning.end_ms,
+                warning.max_overlap_percent
+            ));
+        }
+        self.show_report(panel);
+        self.audio.seek(SongTimeMs(warnings[next].start_ms));
+    }
+
+    // Lists sliders osu!stable and osu!lazer may draw differently, with how far apart the two
+    // readings get, and seeks to the next one.
+    pub fn jump_to_next_slider_curve_warning(&self) {
+        let general = &self.editor_config.general;
+        let map_state = {
+            let edit_state = self.edit_state.read().expect("edit_state lock poisoned");
+            edit_state.current_map_state()
+        };
+        let warnings = map_state.slider_curve_warnings(
+            general.slider_curve_max_bezier_anchors,
+            general.slider_curve_warning_px,
+        );
+        if warnings.is_empty() {
+            println!("No slider curves that differ between stable and lazer found.");
+            return;
+        }
+
+        println!("{} slider curve warning(s):", warnings.len());
+        for warning in warnings.iter() {
+            println!(
+                "  {:.0}ms: {} (up to {:.2}px apart)",
+                warning.time_ms, warning.reason, warning.diff_px
+            );
+        }
+
+        let time_ms = self.audio.current_time().0;
+        let next = warnings
+            .iter()
+            .find(|warning| warning.time_ms > time_ms + 1.0)
+            .unwrap_or(&warnings[0]);
+        self.audio.seek(SongTimeMs(next.time_ms));
+    }
+
+    // Lists hitsounds that resolve to blank map samples or play at 5% volume or lower,
+    // and seeks to the next one.
+    pub fn jump_to_next_silent_hitsound(&mut self) {
+        let map_state = {
+            let edit_state = self.edit_state.read().expect("edit_state lock poisoned");
+            edit_state.current_map_state()
+        };
+        let mut blank_cache: HashMap<String, bool> = HashMap::new();
+        let silent = map_state.silent_hitsounds(|name| {
+            let bytes = self.beatmapset.assets.get(name)?;
+            let blank = blank_cache.entry(name.to_string()).or_insert_with(|| {
+                let ext = Path::new(name).extension().and_then(|ext| ext.to_str());
+                is_blank_sample(bytes, ext)
+            });
+            Some(*blank)
+        });
+        if silent.is_empty() {
+            self.show_report(ReportPanel::new("no silent hitsounds"));
+            return;
+        }
+
+        let time_ms = self.audio.current_time().0;
+        let next = silent
+            .iter()
+            .position(|hitsound| hitsound.time_ms > time_ms + 1.0)
+            .unwrap_or(0);
+        let mut panel = ReportPanel::new(&format!("{} silent hitsounds", silent.len()));
+        for (i, hitsound) in silent.iter().enumerate() {
+            panel.push(&format!(
+                "{} {:.0}ms  {}",
+                if i == next { "|" } else { " " },
+                hitsound.time_ms,
+                hitsound.reason
+            ));
+        }
+        self.show_report(panel);
+        self.audio.seek(SongTimeMs(silent[next].time_ms));
+    }
+
+    // Break and spinner suggestions are reviewed one card at a time: the current card is
+    // shown and seeked to, then accepted or dismissed.
+    pub fn review_gap_suggestions(&mut self) {
+        let map_state = {
+            let edit_state = self.edit_state.read().expect("edit_state lock poisoned");
+            edit_state.current_map_state()
+        };
+        let onsets = self
+            .audio
+            .onsets_between(SongTimeMs(f64::NEG_INFINITY), SongTimeMs(f64::INFINITY));
+        let mut suggestions = map_state.gap_suggestions(&onsets);
+        let divisor = self.editor_config.general.beat_divisor.max(1) as f64;
+        for suggestion in suggestions.iter_mut() {
+            if let GapSuggestion::Spinner { start_ms, end_ms } = suggestion {
+                let snap = |t: f64| map_state.timing.nearest_tick(t, divisor).unwrap_or(t);
+                *start_ms = snap(*start_ms);
+                *end_ms = snap(*end_ms);
+            }
+        }
+        self.gap_suggestions = suggestions;
+        self.show_current_gap_suggestion();
+    }
+
+    // The card being reviewed is marked and listed first, the rest follow.
+    fn show_current_gap_suggestion(&mut self) {
+        let Some(suggestion) = self.gap_suggestions.first() else {
+            self.show_report(ReportPanel::new("no break or spinner suggestions - f9"));
+            return;
+        };
+        self.audio.seek(SongTimeMs(suggestion.start_ms().max(0.0)));
+        let mut panel = ReportPanel::new(&format!(
+            "{} suggestions - f10 accept - f12 dismiss",
+            self.gap_suggestions.len()
+        ));
+        for (i, suggestion) in self.gap_suggestions.iter().enumerate() {
+            let marker = if i == 0 { "|" } else { " " };
+            panel.push(&format!("{} {}", marker, suggestion.describe()));
+        }
+        self.show_report(panel);
+    }
+
+    pub fn accept_gap_suggestion(&mut self) {
+        if self.objects_locked() {
+            return;
+        }
+        if self.gap_suggestions.is_empty() {
+            self.show_current_gap_suggestion();
+            return;
+        }
+        let suggestion = self.gap_suggestions.remove(0);
+        {
+            let mut edit_state = self.edit_state.write().expect("edit_state lock poisoned");
+            match suggestion {
+                GapSuggestion::InsertBreak { start_ms, end_ms } => {
+                    edit_state.add_break(start_ms, end_ms);
+                    println!("Added break {:.0}ms - {:.0}ms.", start_ms, end_ms);
+                }
+                GapSuggestion::Spinner { start_ms, end_ms } => {
+                    edit_state.insert_objects(vec![HitObject::Spinner(Spinner {
+                        x: 256.0,
+                        y: 192.0,
+                        time: start_ms,
+                        end_time: end_ms,
+                        combo_info: ComboInfo {
+                            new_combo: true,
+                            color_skip: 0,
+                        },
+                        hitsound: Hitsound {
+                            normal: true,
+                            whistle: false,
+                            finish: false,
+                            clap: false,
+                        },
+                        hitsample: HitSample {
+                            normal_set: 0,
+                            addition_set: 0,
+                            index: 0,
+                            volume: 0,
+                            filename: String::new(),
+                        },
+                    })]);
+                    println!("Added spinner {:.0}ms - {:.0}ms.", start_ms, end_ms);
+                }
+                GapSuggestion::TooShortForBreak { .. } => {
+                    println!("Noted, nothing to insert for this gap.");
+                }
+            }
+        }
+        self.show_current_gap_suggestion();
+    }
+
+    pub fn dismiss_gap_suggestion(&mut self) {
+        if self.gap_suggestions.is_empty() {
+            return;
+        }
+        self.gap_suggestions.remove(0);
+        self.show_current_gap_suggestion();
+    }
+
+    pub fn swap_selections(&self) {
+        let mut edit_state = self.edit_state.write().expect("edit_state lock poisoned");
+        edit_state.swap_selections();
+    }
+
+    pub fn toggle_selection_position_lock(&self, left: bool) {
+        let mut edit_state = self.edit_state.write().expect("edit_state lock poisoned");
+        edit_state.toggle_selection_origin_lock(left);
+    }
+
+    pub fn toggle_selection_scale_lock(&self, left: bool) {
+        let mut edit_state = self.edit_state.write().expect("edit_state lock poisoned");
+        edit_state.toggle_selection_scale_lock(left);
+    }
+
+    pub fn rotate_selection_left_90(&mut self, left_selection: bool) {
+        if self.objects_locked() {
+            return;
+        }
+        let mut edit_state = self.edit_state.write().expect("edit_state lock poisoned");
+        edit_state.rotate_selection_left_90(left_selection);
+    }
+
+    pub fn rotate_selection_right_90(&mut self, left_selection: bool) {
+        if self.objects_locked() {
+            return;
+        }
+        let mut edit_state = self.edit_state.write().expect("edit_state lock poisoned");
+        edit_state.rotate_selection_right_90(left_selection);
+    }
+
+    pub fn flip_selection_horizontal(&mut self) {
+        if self.objects_locked() {
+            return;
+        }
+        let mut edit_state = self.edit_state.write().expect("edit_state lock poisoned");
+        edit_state.flip_selection_horizontal();
+    }
+
+    pub fn flip_selection_vertical(&mut self) {
+        if self.objects_locked() {
+            return;
+        }
+        let mut edit_state = self.edit_state.write().expect("edit_state lock poisoned");
         edit_state.flip_selection_vertical();
     }
 
-    pub fn flip_left_selection_coordinates(&self) {
+    pub fn flip_left_selection_coordinates(&mut self) {
+        if self.objects_locked() {
+            return;
+        }
         let mut edit_state = self.edit_state.write().expect("edit_state lock poisoned");
         edit_state.flip_selection_coordinates(true);
     }
 
-    pub fn swap_left_selection_xy(&self) {
+    pub fn swap_left_selection_xy(&mut self) {
+        if self.objects_locked() {
+            return;
+        }
         let mut edit_state = self.edit_state.write().expect("edit_state lock poisoned");
         edit_state.swap_selection_xy(true);
     }
 
-    pub fn swap_left_selection_xy_2(&self) {
+    pub fn swap_left_selection_xy_2(&mut self) {
+        if self.objects_locked() {
+            return;
+        }
         let mut edit_state = self.edit_state.write().expect("edit_state lock poisoned");
         edit_state.swap_selection_xy_2(true);
     }
 
-    pub fn swap_left_selection_xy_3(&self) {
+    pub fn swap_left_selection_xy_3(&mut self) {
+        if self.objects_locked() {
+            return;
+        }
         let mut edit_state = self.edit_state.write().expect("edit_state lock poisoned");
         edit_state.swap_selection_xy_3(true);
     }
 
-    pub fn swap_left_selection_xy_4(&self) {
+    pub fn swap_left_selection_xy_4(&mut self) {
+        if self.objects_locked() {
+            return;
+        }
         let mut edit_state = self.edit_state.write().expect("edit_state lock poisoned");
         edit_state.swap_selection_xy_4(true);
     }
@@ -1903,11 +5470,18 @@ impl EditorApp {
         edit_state.scale_selection_percent(left, percent_delta, checkpoint);
     }
 
-    pub fn translate_selection(&self, left: bool, delta: Vec2, checkpoint: bool) {
+    pub fn translate_selection(&mut self, left: bool, delta: Vec2, checkpoint: bool) {
+        if self.objects_locked() {
+            return;
+        }
         let mut edit_state = self.edit_state.write().expect("edit_state lock poisoned");
         edit_state.translate_selection(left, delta, checkpoint);
     }
 
+    pub fn edit_state(&self) -> &Arc<RwLock<EditState>> {
+        &self.edit_state
+    }
+
     pub fn undo(&self) {
         let mut edit_state = self.edit_state.write().expect("edit_state lock poisoned");
         edit_state.undo();
@@ -1955,8 +5529,48 @@ impl EditorApp {
         self.set_fullscreen(!self.is_fullscreen());
     }
 
+    pub fn begin_recent_map_switch(&mut self) {
+        let map_dir_name = &self.beatmapset.map_dir_name;
+        self.recent_maps = read_recent_maps()
+            .into_iter()
+            .filter(|entry| {
+                entry.map_dir_name != *map_dir_name || entry.difficulty != self.diff_version
+            })
+            .collect();
+        if self.recent_maps.is_empty() {
+            println!("No other recently opened maps.");
+            return;
+        }
+        println!("Recently opened:");
+        for (i, entry) in self.recent_maps.iter().enumerate() {
+            println!("  {}: {} [{}]", i + 1, entry.map_dir_name, entry.difficulty);
+        }
+        println!("Type a number and press Enter to switch (Esc to cancel).");
+        self.begin_text_input(TextInputTarget::RecentMap);
+    }
+
+    // Closes this editor, the map is opened from the CLI loop once the window is gone.
+    fn switch_to_recent_map(&mut self, input: String) {
+        let entry = input
+            .trim()
+            .parse::<usize>()
+            .ok()
+            .and_then(|n| n.checked_sub(1))
+            .and_then(|i| self.recent_maps.get(i));
+        match entry {
+            Some(entry) => {
+                println!("Switching to {} [{}]", entry.map_dir_name, entry.difficulty);
+                self.switch_to_recent = Some(entry.clone());
+                self.exit_editor_window();
+            }
+            None => println!("No recent map numbered {}.", input.trim()),
+        }
+    }
+
     pub fn exit_editor_window(&mut self) {
         self.exiting = true;
+        self.write_session();
+        self.record_editing_session();
 
         if let Some(window) = self.window.as_ref() {
             window.set_visible(false);
@@ -1982,3 +5596,31 @@ impl EditorApp {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_harness::{TEST_MAP, headless_editor, object_positions};
+
+    #[test]
+    fn tool_paths_stay_one_argument() {
+        let osu_path = Path::new("/maps/a b/x\"; rm -rf ~.osu");
+        let template = "\"my tool\" --in {osu} -d \"{folder}\"";
+        let args = tool_command_args(template, osu_path, Path::new("/maps/a b"));
+        let expected = ["my tool", "--in", "/maps/a b/x\"; rm -rf ~.osu", "-d", "/maps/a b"];
+        assert_eq!(args, expected);
+    }
+
+    #[test]
+    fn editor_undo_restores_state() {
+        let Some(mut editor) = headless_editor(TEST_MAP) else {
+            return;
+        };
+        let before = object_positions(&editor.edit_state().read().unwrap().current_map_state());
+        editor.edit_state().write().unwrap().select_all_to_left();
+        editor.translate_selection(true, Vec2 { x: -20.0, y: 10.0 }, true);
+        editor.undo();
+        let after = object_positions(&editor.edit_state().read().unwrap().current_map_state());
+        assert_eq!(before, after);
+    }
+}
@@ -1,11 +1,24 @@
-use std::{fs, path::Path};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+use serde::{Deserialize, Serialize};
 use winit::event_loop::EventLoop;
 
 use crate::{
-    dotosu::osu_file::OsuFile,
-    files::{create_zip, open_beatmapset_folder, sanitize_name, scan_folder, write_bytes_to_file},
+    dotosu::osu_diff::diff_osu_texts,
+    files::{
+        BeatmapsetFolder, create_zip, describe_write_error, open_beatmapset_folder, sanitize_name,
+        scan_folder, write_bytes_to_file, write_file_atomically,
+    },
     dialogue_app::DialogueApp,
-    map_format::convert_to_osu_format::convert_internal_to_osu_format,
+    map_format::{
+        beatmap::Beatmap,
+        convert_to_osu_format::convert_internal_to_osu_format,
+        objects::{HitObject, HitsoundInfo},
+        slider_curve::ControlPoints,
+        timing::{SampleSet, TimingPoint},
+    },
 };
 
 pub fn select_and_export_map(event_loop: &mut EventLoop<()>, selector: &mut DialogueApp) {
@@ -34,7 +47,18 @@ pub fn select_and_export_map(event_loop: &mut EventLoop<()>, selector: &mut Dial
         }
     };
     let selected_map = &entries[selection];
-    export_map(event_loop, selector, selected_map);
+
+    let formats = vec![
+        ".osz for osu!".to_string(),
+        "objects and timing as JSON".to_string(),
+        "objects and timing as CSV".to_string(),
+    ];
+    match selector.select(event_loop, "Export format", &formats) {
+        Some(0) => export_map(event_loop, selector, selected_map),
+        Some(1) => export_analysis(event_loop, selector, selected_map, AnalysisFormat::Json),
+        Some(2) => export_analysis(event_loop, selector, selected_map, AnalysisFormat::Csv),
+        _ => println!("Export cancelled."),
+    }
 }
 
 pub fn export_map(event_loop: &mut EventLoop<()>, selector: &mut DialogueApp, map_name: &String) {
@@ -48,72 +72,495 @@ pub fn export_map(event_loop: &mut EventLoop<()>, selector: &mut DialogueApp, ma
         }
     };
 
-    let osu_files: Vec<OsuFile> = beatmapset_folder
-        .beatmaps
-        .into_iter()
-        .map(|b| convert_internal_to_osu_format(beatmapset_folder.beatmapset.clone(), b))
-        .collect();
+    let osu_texts = export_osu_texts(&beatmapset_folder);
 
     let export_path = format!("saves/{}/exports", map_name);
     let export_path = Path::new(&export_path);
     if export_path.exists() {
-        match selector.confirm(
+        summarize_changes_since_last_export(export_path, &osu_texts);
+        if !selector.confirm(
             event_loop,
             &format!("Export path {} already exists. Overwrite?", export_path.display()),
         ) {
-            true => {
-                if let Err(err) = fs::remove_dir_all(&export_path) {
-                    println!(
-                        "Failed to remove existing export directory {}: {}",
-                        export_path.display(),
-                        err
-                    );
-                    return;
-                }
-            }
-            false => {
-                println!("Export cancelled.");
-                return;
-            }
+            println!("Export cancelled.");
+            return;
         }
     }
 
+    if let Some(zip_path) = write_export(map_name, beatmapset_folder, osu_texts) {
+        println!("Exported map to {}", zip_path.display());
+    }
+}
+
+fn exported_osu_name(beatmap: &Beatmap) -> String {
+    sanitize_name(&format!("{} ({}).osu", beatmap.version, beatmap.id))
+}
+
+// Where the last export put the difficulty's .osu, for tools that edit it there.
+pub fn exported_osu_path(map_name: &str, beatmap: &Beatmap) -> PathBuf {
+    Path::new("saves")
+        .join(map_name)
+        .join("exports")
+        .join(exported_osu_name(beatmap))
+}
+
+fn export_osu_texts(beatmapset_folder: &BeatmapsetFolder) -> Vec<(String, String)> {
+    beatmapset_folder
+        .beatmaps
+        .iter()
+        .map(|b| {
+            let beatmapset = beatmapset_folder.beatmapset.clone();
+            let osu_file = convert_internal_to_osu_format(beatmapset, b.clone());
+            (exported_osu_name(b), osu_file.to_osu_text())
+        })
+        .collect()
+}
+
+// Replaces saves/<map>/exports with the song folder and its .osz, returns the .osz path.
+// Everything is written to exports_new first, so a failed export leaves the last one intact.
+// Those writes aren't kept for a retry: the staging dir is gone by then, so export again.
+fn write_export(
+    map_name: &str,
+    beatmapset_folder: BeatmapsetFolder,
+    osu_texts: Vec<(String, String)>,
+) -> Option<PathBuf> {
+    let export_path = format!("saves/{}/exports", map_name);
+    let export_path = Path::new(&export_path);
+    let staging_path = export_path.with_file_name("exports_new");
+    if !remove_dir_if_exists(&staging_path) {
+        return None;
+    }
+
     let mut all_files = beatmapset_folder.assets.clone();
 
-    for osu_file in osu_files {
-        let file_name = format!(
-            "{} ({}).osu",
-            &osu_file.metadata.version, osu_file.metadata.beatmap_id,
-        );
-        let file_name = sanitize_name(&file_name);
-        let osu_file_content = osu_file.to_osu_text();
-        all_files.insert(file_name, osu_file_content.into_bytes());
+    for (file_name, osu_text) in osu_texts {
+        all_files.insert(file_name, osu_text.into_bytes());
     }
 
     for (asset_name, asset_bytes) in all_files.clone_map() {
-        let asset_path = export_path.join(&asset_name);
-        if let Err(err) = write_bytes_to_file(&asset_path, &asset_bytes) {
+        let asset_path = staging_path.join(&asset_name);
+        if let Err(err) = write_file_atomically(&asset_path, &asset_bytes) {
             println!(
                 "Failed to write asset file {}: {}",
                 asset_path.display(),
-                err
+                describe_write_error(&err)
             );
-            return;
+            let _ = fs::remove_dir_all(&staging_path);
+            return None;
         }
     }
 
+    let zip_name = format!("{}.osz", map_name);
     match create_zip(all_files.clone_map()) {
         Some(zip_bytes) => {
-            let zip_path = format!("saves/{}/exports/{}.osz", map_name, map_name);
-            let zip_path = Path::new(&zip_path);
-            if let Err(err) = write_bytes_to_file(&zip_path, &zip_bytes) {
-                println!("Failed to write zip file {}: {}", zip_path.display(), err);
-                return;
+            let zip_path = staging_path.join(&zip_name);
+            if let Err(err) = write_file_atomically(&zip_path, &zip_bytes) {
+                println!(
+                    "Failed to write zip file {}: {}",
+                    zip_path.display(),
+                    describe_write_error(&err)
+                );
+                let _ = fs::remove_dir_all(&staging_path);
+                return None;
             }
-            println!("Exported map to {}", zip_path.display());
         }
         None => {
             println!("Failed to create zip file for {}", map_name);
+            let _ = fs::remove_dir_all(&staging_path);
+            return None;
+        }
+    }
+
+    if !remove_dir_if_exists(export_path) {
+        return None;
+    }
+    if let Err(err) = fs::rename(&staging_path, export_path) {
+        println!(
+            "Failed to move {} to {}: {}",
+            staging_path.display(),
+            export_path.display(),
+            err
+        );
+        return None;
+    }
+    Some(export_path.join(zip_name))
+}
+
+fn remove_dir_if_exists(path: &Path) -> bool {
+    match fs::remove_dir_all(path) {
+        Ok(()) => true,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => true,
+        Err(err) => {
+            println!("Failed to remove directory {}: {}", path.display(), err);
+            false
+        }
+    }
+}
+
+pub fn confirm_and_export_all_maps(event_loop: &mut EventLoop<()>, selector: &mut DialogueApp) {
+    let question = "Export every map in saves/ to .osz? Previous exports are replaced.";
+    if selector.confirm(event_loop, question) {
+        export_all_maps();
+    } else {
+        println!("Export cancelled.");
+    }
+}
+
+// Exports every map in saves/ without asking, for backups and `--export-all`. Returns false if
+// any of them failed.
+pub fn export_all_maps() -> bool {
+    let saves_path = Path::new("saves");
+    if !saves_path.exists() {
+        println!("No saves/ directory found.");
+        return false;
+    }
+
+    let entries = scan_folder(saves_path, Some(true), None);
+    let mut exported: Vec<PathBuf> = Vec::new();
+    let mut failed: Vec<&String> = Vec::new();
+    for map_name in entries.iter() {
+        println!("Exporting map: {}", map_name);
+        let zip_path = open_beatmapset_folder(map_name).and_then(|beatmapset_folder| {
+            let osu_texts = export_osu_texts(&beatmapset_folder);
+            write_export(map_name, beatmapset_folder, osu_texts)
+        });
+        match zip_path {
+            Some(zip_path) => exported.push(zip_path),
+            None => failed.push(map_name),
+        }
+    }
+
+    println!("Exported {} of {} maps:", exported.len(), entries.len());
+    for zip_path in exported.iter() {
+        println!("  {}", zip_path.display());
+    }
+    if !failed.is_empty() {
+        println!("Failed to export:");
+        for map_name in failed.iter() {
+            println!("  {}", map_name);
+        }
+    }
+    failed.is_empty()
+}
+
+// Compares each difficulty with the .osu left by the previous export, so exporting the wrong
+// difficulty or an experimental state shows up before the old export is overwritten.
+fn summarize_changes_since_last_export(export_path: &Path, osu_texts: &[(String, String)]) {
+    let previous = scan_folder(export_path, Some(false), Some(&vec![".osu"]));
+    if previous.is_empty() {
+        return;
+    }
+    println!("Changes since the last export:");
+    for (file_name, osu_text) in osu_texts {
+        let old_text = previous
+            .iter()
+            .find(|name| *name == file_name)
+            .and_then(|name| fs::read(export_path.join(name)).ok());
+        match old_text {
+            Some(old_text) => {
+                let changes = diff_osu_texts(&String::from_utf8_lossy(&old_text), osu_text);
+                println!("  {}: {}", file_name, changes.describe());
+            }
+            None => println!("  {}: new difficulty", file_name),
+        }
+    }
+    for name in previous.iter() {
+        if !osu_texts.iter().any(|(file_name, _)| file_name == name) {
+            println!("  {}: no longer exported", name);
         }
+    }
+}
+
+pub enum AnalysisFormat {
+    Json,
+    Csv,
+}
+
+// One row per object. Sliders report their head hitsound, and their path in JSON only.
+#[derive(Serialize, Deserialize)]
+pub struct ObjectRow {
+    #[serde(default)]
+    pub index: usize,
+    pub kind: String,
+    pub time: f64,
+    pub end_time: f64,
+    pub x: f64,
+    pub y: f64,
+    pub new_combo: bool,
+    pub color_skip: i64,
+    pub slides: u64,
+    pub length: f64,
+    pub sampleset: String,
+    pub additions: String,
+    pub volume: f64,
+    pub custom_index: i32,
+    pub whistle: bool,
+    pub finish: bool,
+    pub clap: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub curve: Option<ControlPoints>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct TimingRow {
+    kind: String,
+    time: f64,
+    beat_length: Option<f64>,
+    bpm: Option<f64>,
+    meter: Option<i32>,
+    sv_multiplier: Option<f64>,
+    sampleset: String,
+    custom_index: i32,
+    volume: f64,
+    kiai: bool,
+}
+
+// Imports only need `objects`, so generators can leave out the rest.
+#[derive(Serialize, Deserialize)]
+pub struct AnalysisExport {
+    #[serde(default)]
+    version: String,
+    pub objects: Vec<ObjectRow>,
+    #[serde(default)]
+    timing_points: Vec<TimingRow>,
+}
+
+fn sampleset_name(sample_set: &SampleSet) -> String {
+    format!("{:?}", sample_set)
+}
+
+// Spinners keep the raw osu! sampleset number, where 0 follows the timing point.
+fn osu_sampleset_name(sample_set: i32) -> String {
+    match sample_set {
+        1 => "Normal".to_string(),
+        2 => "Soft".to_string(),
+        3 => "Drum".to_string(),
+        _ => "Auto".to_string(),
+    }
+}
+
+fn object_row(index: usize, object: &HitObject) -> ObjectRow {
+    let pos = object.position();
+    let head = |hitsound: &HitsoundInfo| {
+        (
+            sampleset_name(&hitsound.hit_sampleset),
+            sampleset_name(&hitsound.additions_sampleset),
+            hitsound.volume,
+            hitsound.index,
+            hitsound.play_whistle,
+            hitsound.play_finish,
+            hitsound.play_clap,
+        )
+    };
+    let (kind, end_time, slides, length, hitsound, curve) = match object {
+        HitObject::Circle(c) => ("circle", c.time, 0, 0.0, Some(head(&c.hitsound_info)), None),
+        HitObject::Slider(s) => (
+            "slider",
+            s.end_time(),
+            s.slides,
+            s.length_pixels,
+            s.hitsounds.first().map(head),
+            Some(s.control_points.clone()),
+        ),
+        HitObject::Spinner(sp) => (
+            "spinner",
+            sp.end_time,
+            0,
+            0.0,
+            Some((
+                osu_sampleset_name(sp.hitsample.normal_set),
+                osu_sampleset_name(sp.hitsample.addition_set),
+                sp.hitsample.volume as f64 / 100.0,
+                sp.hitsample.index,
+                sp.hitsound.whistle,
+                sp.hitsound.finish,
+                sp.hitsound.clap,
+            )),
+            None,
+        ),
+    };
+    let (sampleset, additions, volume, custom_index, whistle, finish, clap) = hitsound
+        .unwrap_or(("Auto".to_string(), "Auto".to_string(), 0.0, 0, false, false, false));
+    ObjectRow {
+        index,
+        kind: kind.to_string(),
+        time: object.time(),
+        end_time,
+        x: pos.x,
+        y: pos.y,
+        new_combo: object.combo_info().new_combo,
+        color_skip: object.combo_info().color_skip,
+        slides,
+        length,
+        sampleset,
+        additions,
+        volume,
+        custom_index,
+        whistle,
+        finish,
+        clap,
+        curve,
+    }
+}
+
+fn timing_row(timing_point: &TimingPoint) -> TimingRow {
+    match timing_point {
+        TimingPoint::RedLine(rl) => TimingRow {
+            kind: "red".to_string(),
+            time: rl.time,
+            beat_length: Some(rl.beat_length),
+            bpm: Some(60_000.0 / rl.beat_length),
+            meter: Some(rl.meter),
+            sv_multiplier: None,
+            sampleset: sampleset_name(&rl.sample_set),
+            custom_index: rl.sample_index,
+            volume: rl.volume,
+            kiai: rl.effects.kiai_mode,
+        },
+        TimingPoint::GreenLine(gl) => TimingRow {
+            kind: "green".to_string(),
+            time: gl.time,
+            beat_length: None,
+            bpm: None,
+            meter: None,
+            sv_multiplier: Some(gl.sv_multiplier),
+            sampleset: sampleset_name(&gl.sample_set),
+            custom_index: gl.sample_index,
+            volume: gl.volume,
+            kiai: gl.effects.kiai_mode,
+        },
+    }
+}
+
+fn csv_field<T: ToString>(value: Option<T>) -> String {
+    value.map(|v| v.to_string()).unwrap_or_default()
+}
+
+fn objects_csv(rows: &[ObjectRow]) -> String {
+    let mut text = String::from(
+        "index,kind,time,end_time,x,y,new_combo,color_skip,slides,length,\
+         sampleset,additions,volume,custom_index,whistle,finish,clap\n",
+    );
+    for r in rows {
+        text.push_str(&format!(
+            "{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{}\n",
+            r.index,
+            r.kind,
+            r.time,
+            r.end_time,
+            r.x,
+            r.y,
+            r.new_combo,
+            r.color_skip,
+            r.slides,
+            r.length,
+            r.sampleset,
+            r.additions,
+            r.volume,
+            r.custom_index,
+            r.whistle,
+            r.finish,
+            r.clap
+        ));
+    }
+    text
+}
+
+fn timing_csv(rows: &[TimingRow]) -> String {
+    let mut text = String::from(
+        "kind,time,beat_length,bpm,meter,sv_multiplier,sampleset,custom_index,volume,kiai\n",
+    );
+    for r in rows {
+        text.push_str(&format!(
+            "{},{},{},{},{},{},{},{},{},{}\n",
+            r.kind,
+            r.time,
+            csv_field(r.beat_length),
+            csv_field(r.bpm),
+            csv_field(r.meter),
+            csv_field(r.sv_multiplier),
+            r.sampleset,
+            r.custom_index,
+            r.volume,
+            r.kiai
+        ));
+    }
+    text
+}
+
+fn write_analysis(analysis_path: &Path, beatmap: &Beatmap, format: &AnalysisFormat) -> bool {
+    let export = AnalysisExport {
+        version: beatmap.version.clone(),
+        objects: beatmap
+            .objects
+            .objects
+            .iter()
+            .enumerate()
+            .map(|(idx, object)| object_row(idx, object))
+            .collect(),
+        timing_points: beatmap.timing.timing_points.iter().map(timing_row).collect(),
+    };
+    let name = sanitize_name(&beatmap.version);
+    let files: Vec<(String, String)> = match format {
+        AnalysisFormat::Json => match serde_json::to_string_pretty(&export) {
+            Ok(json) => vec![(format!("{}.json", name), json)],
+            Err(err) => {
+                println!("Failed to serialize {}: {}", beatmap.version, err);
+                return false;
+            }
+        },
+        AnalysisFormat::Csv => vec![
+            (format!("{}_objects.csv", name), objects_csv(&export.objects)),
+            (format!("{}_timing.csv", name), timing_csv(&export.timing_points)),
+        ],
     };
+    for (file_name, text) in files {
+        let path = analysis_path.join(file_name);
+        if let Err(err) = write_bytes_to_file(&path, text.as_bytes()) {
+            println!("Failed to write {}: {}", path.display(), err);
+            return false;
+        }
+        println!("Wrote {}", path.display());
+    }
+    true
+}
+
+// Dumps objects and timing points of one or all difficulties to saves/<map>/analysis
+// for spreadsheets and external tools.
+pub fn export_analysis(
+    event_loop: &mut EventLoop<()>,
+    selector: &mut DialogueApp,
+    map_name: &String,
+    format: AnalysisFormat,
+) {
+    let beatmapset_folder = match open_beatmapset_folder(map_name) {
+        Some(beatmapset_folder) => beatmapset_folder,
+        None => {
+            println!("Failed to open beatmapset folder for {}", map_name);
+            return;
+        }
+    };
+    let mut options: Vec<String> = beatmapset_folder
+        .beatmaps
+        .iter()
+        .map(|b| b.version.clone())
+        .collect();
+    options.push("all difficulties".to_string());
+    let selection = match selector.select(event_loop, "Export difficulty", &options) {
+        Some(idx) => idx,
+        None => {
+            println!("Export cancelled.");
+            return;
+        }
+    };
+
+    let analysis_path = Path::new("saves").join(map_name).join("analysis");
+    for (idx, beatmap) in beatmapset_folder.beatmaps.iter().enumerate() {
+        if selection < beatmapset_folder.beatmaps.len() && idx != selection {
+            continue;
+        }
+        if !write_analysis(&analysis_path, beatmap, &format) {
+            return;
+        }
+    }
 }
@@ -0,0 +1,762 @@
+use std::{
+    collections::HashSet,
+    fs,
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+use winit::event_loop::EventLoop;
+
+use crate::{
+    dialogue_app::DialogueApp,
+    map_format::{
+        beatmap::Beatmap,
+        beatmapset::Beatmapset,
+        events::Event,
+        objects::{HitObject, HitsoundInfo},
+        timing::SampleSet,
+    },
+    files::{
+        BeatmapsetFolder, is_enclosed_path, open_beatmapset_folder, read_beatmap_json,
+        sanitize_name, scan_folder, write_beatmapset_folder, write_bytes_to_file,
+    },
+    imports::make_bg_small_png,
+};
+
+#[derive(PartialEq)]
+enum AssetKind {
+    Audio,
+    Hitsound,
+    Image,
+    Video,
+    Storyboard,
+    Other,
+}
+
+impl AssetKind {
+    fn from_name(name: &str) -> Self {
+        let path = Path::new(name);
+        let ext = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or("")
+            .to_ascii_lowercase();
+        let stem = path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or("")
+            .to_ascii_lowercase();
+        match ext.as_str() {
+            "wav" | "ogg" | "mp3" => {
+                if ["normal-", "soft-", "drum-"].iter().any(|p| stem.starts_with(p)) {
+                    AssetKind::Hitsound
+                } else {
+                    AssetKind::Audio
+                }
+            }
+            "png" | "jpg" | "jpeg" => AssetKind::Image,
+            "mp4" | "avi" | "flv" | "mkv" => AssetKind::Video,
+            "osb" => AssetKind::Storyboard,
+            _ => AssetKind::Other,
+        }
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            AssetKind::Audio => "audio",
+            AssetKind::Hitsound => "hitsound",
+            AssetKind::Image => "image",
+            AssetKind::Video => "video",
+            AssetKind::Storyboard => "storyboard",
+            AssetKind::Other => "other",
+        }
+    }
+}
+
+fn normalize_reference(name: &str) -> String {
+    let name = name.trim();
+    let name = if name.starts_with('"') && name.ends_with('"') && name.len() >= 2 {
+        &name[1..name.len() - 1]
+    } else {
+        name
+    };
+    let name = name.trim().replace('\\', "/");
+    name.strip_prefix("./")
+        .unwrap_or(&name)
+        .to_ascii_lowercase()
+}
+
+fn format_size(bytes: usize) -> String {
+    let bytes = bytes as f64;
+    if bytes >= 1024.0 * 1024.0 {
+        format!("{:.1} MB", bytes / (1024.0 * 1024.0))
+    } else if bytes >= 1024.0 {
+        format!("{:.1} KB", bytes / 1024.0)
+    } else {
+        format!("{} B", bytes)
+    }
+}
+
+// Animation events reference "name.png" while the frames on disk are "name0.png", "name1.png", ...
+fn is_animation_frame(asset: &str, animation_path: &str) -> bool {
+    let (base, ext) = match animation_path.rsplit_once('.') {
+        Some((base, ext)) => (base, format!(".{}", ext)),
+        None => (animation_path, String::new()),
+    };
+    match asset
+        .strip_prefix(base)
+        .and_then(|rest| rest.strip_suffix(ext.as_str()))
+    {
+        Some(frame) => !frame.is_empty() && frame.chars().all(|c| c.is_ascii_digit()),
+        None => false,
+    }
+}
+
+// Name osu! loads the set's storyboard script from, other .osb files are never read.
+fn storyboard_file_name(beatmapset: &Beatmapset) -> String {
+    let name = format!(
+        "{} - {} ({}).osb",
+        beatmapset.artist, beatmapset.title, beatmapset.creator
+    );
+    name.chars()
+        .filter(|c| !matches!(c, '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|'))
+        .collect::<String>()
+        .to_ascii_lowercase()
+}
+
+// Sample index 0 leaves the sound to the skin, 1 plays the map's file without a number.
+fn add_sample_stem(stems: &mut HashSet<String>, sample_set: &SampleSet, sound: &str, index: i32) {
+    if index <= 0 {
+        return;
+    }
+    let prefix = sample_set.file_prefix();
+    stems.insert(format!("{}-{}{}", prefix, sound, index));
+    if index == 1 {
+        stems.insert(format!("{}-{}", prefix, sound));
+    }
+}
+
+fn add_hitsound_stems(stems: &mut HashSet<String>, hitsound: &HitsoundInfo, index: i32) {
+    add_sample_stem(stems, &hitsound.hit_sampleset, "hitnormal", index);
+    for (enabled, sound) in [
+        (hitsound.play_whistle, "hitwhistle"),
+        (hitsound.play_finish, "hitfinish"),
+        (hitsound.play_clap, "hitclap"),
+    ] {
+        if enabled {
+            add_sample_stem(stems, &hitsound.additions_sampleset, sound, index);
+        }
+    }
+}
+
+// Stems like "soft-hitclap2" of the custom samples a difficulty can play, from the objects'
+// sample sets and indices and the timing line's index where an object leaves it at 0.
+fn reachable_sample_stems(beatmap: &Beatmap, stems: &mut HashSet<String>) {
+    let line_at = |time: f64| {
+        let (red_line, green_line) = beatmap.timing.get_lines_at_time(time + 0.5);
+        match (red_line, green_line) {
+            (_, Some(gl)) => Some((gl.sample_set, gl.sample_index)),
+            (Some(rl), None) => Some((rl.sample_set, rl.sample_index)),
+            (None, None) => None,
+        }
+    };
+    let index_at = |index: i32, time: f64| {
+        if index > 0 {
+            index
+        } else {
+            line_at(time).map(|(_, line_index)| line_index).unwrap_or(0)
+        }
+    };
+    for object in beatmap.objects.objects.iter() {
+        match object {
+            HitObject::Circle(c) => {
+                let index = index_at(c.hitsound_info.index, c.time);
+                add_hitsound_stems(stems, &c.hitsound_info, index);
+            }
+            HitObject::Slider(s) => {
+                let body = &s.sliderbody_hitsound;
+                for (i, hitsound) in s.hitsounds.iter().enumerate() {
+                    let time = s.time + i as f64 * s.slide_duration();
+                    add_hitsound_stems(stems, hitsound, index_at(hitsound.index, time));
+                    let body_index = index_at(body.index, time);
+                    add_sample_stem(stems, &body.hit_sampleset, "sliderslide", body_index);
+                    add_sample_stem(stems, &body.hit_sampleset, "slidertick", body_index);
+                    if body.play_whistle {
+                        let additions = &body.additions_sampleset;
+                        add_sample_stem(stems, additions, "sliderwhistle", body_index);
+                    }
+                }
+            }
+            // Spinners keep the raw .osu hitsample and sound at their end.
+            HitObject::Spinner(sp) => {
+                let Some((line_set, line_index)) = line_at(sp.end_time) else {
+                    continue;
+                };
+                let raw_set = |set: i32, default: &SampleSet| match set {
+                    1 => SampleSet::Normal,
+                    2 => SampleSet::Soft,
+                    3 => SampleSet::Drum,
+                    _ => default.clone(),
+                };
+                let hit_sampleset = raw_set(sp.hitsample.normal_set, &line_set);
+                let hitsound = HitsoundInfo {
+                    additions_sampleset: raw_set(sp.hitsample.addition_set, &hit_sampleset),
+                    hit_sampleset,
+                    volume: 1.0,
+                    index: sp.hitsample.index,
+                    play_whistle: sp.hitsound.whistle,
+                    play_finish: sp.hitsound.finish,
+                    play_clap: sp.hitsound.clap,
+                    filename: None,
+                };
+                let index = if hitsound.index > 0 {
+                    hitsound.index
+                } else {
+                    line_index
+                };
+                add_hitsound_stems(stems, &hitsound, index);
+            }
+        }
+    }
+}
+
+// What the set's difficulties and its storyboard script point at, to tell used assets apart.
+struct AssetReferences {
+    names: HashSet<String>,
+    // Animation paths stand for their numbered frames.
+    animation_paths: Vec<String>,
+    sample_stems: HashSet<String>,
+    storyboard_name: String,
+    storyboard_text: Option<String>,
+}
+
+impl AssetReferences {
+    fn collect(folder: &BeatmapsetFolder) -> Self {
+        let mut names = HashSet::new();
+        let mut animation_paths = Vec::new();
+        let mut sample_stems = HashSet::new();
+        names.insert(normalize_reference(&folder.beatmapset.audio_filename));
+        for beatmap in folder.beatmaps.iter() {
+            for event in beatmap.events.events.iter() {
+                if let Event::Animation(an) = event {
+                    animation_paths.push(normalize_reference(&an.file_path));
+                }
+            }
+            for path in beatmap.events.file_paths() {
+                names.insert(normalize_reference(path));
+            }
+            for object in beatmap.objects.objects.iter() {
+                if let Some(filename) = object.sample_filename() {
+                    names.insert(normalize_reference(filename));
+                }
+            }
+            reachable_sample_stems(beatmap, &mut sample_stems);
+        }
+        let storyboard_name = storyboard_file_name(&folder.beatmapset);
+        let storyboard_text = folder
+            .assets
+            .names_and_sizes()
+            .into_iter()
+            .find(|(name, _)| normalize_reference(name) == storyboard_name)
+            .and_then(|(name, _)| folder.assets.get(&name))
+            .map(|bytes| String::from_utf8_lossy(bytes).replace('\\', "/").to_ascii_lowercase());
+        AssetReferences {
+            names,
+            animation_paths,
+            sample_stems,
+            storyboard_name,
+            storyboard_text,
+        }
+    }
+
+    fn is_used(&self, asset: &str, kind: &AssetKind) -> bool {
+        let asset = normalize_reference(asset);
+        match kind {
+            AssetKind::Storyboard => return asset == self.storyboard_name,
+            AssetKind::Hitsound => {
+                let stem = asset.rsplit_once('.').map(|(stem, _)| stem).unwrap_or(&asset);
+                if self.sample_stems.contains(stem) {
+                    return true;
+                }
+            }
+            _ => {}
+        }
+        self.names.contains(&asset)
+            || self
+                .animation_paths
+                .iter()
+                .any(|path| is_animation_frame(&asset, path))
+            || self
+                .storyboard_text
+                .as_ref()
+                .is_some_and(|text| text.contains(&asset))
+    }
+}
+
+// Replaces references to `old_name` with `new_name`, keeping the original quoting.
+fn replace_reference(reference: &mut String, old_name: &str, new_name: &str) -> bool {
+    if normalize_reference(reference) != normalize_reference(old_name) {
+        return false;
+    }
+    let quoted = reference.trim().starts_with('"');
+    *reference = if quoted {
+        format!("\"{}\"", new_name)
+    } else {
+        new_name.to_string()
+    };
+    true
+}
+
+// Renames the file only, the asset stays in the folder it's in.
+fn rename_asset(folder: &mut BeatmapsetFolder, old_name: &str, new_file_name: &str) {
+    if !is_enclosed_path(new_file_name) || new_file_name.contains(['/', '\\']) {
+        println!("{} isn't a plain file name.", new_file_name);
+        return;
+    }
+    let new_name = match old_name.rsplit_once('/') {
+        Some((dir, _)) => format!("{}/{}", dir, new_file_name),
+        None => new_file_name.to_string(),
+    };
+    let new_name = new_name.as_str();
+    if new_name == old_name {
+        return;
+    }
+    let assets_path = Path::new("saves").join(&folder.map_dir_name).join("assets");
+    let new_path = assets_path.join(new_name);
+    if new_path.exists() {
+        println!("An asset named {} already exists.", new_name);
+        return;
+    }
+    if let Some(parent) = new_path.parent() {
+        if let Err(err) = fs::create_dir_all(parent) {
+            println!("Failed to create {}: {}", parent.display(), err);
+            return;
+        }
+    }
+    if let Err(err) = fs::rename(assets_path.join(old_name), &new_path) {
+        println!("Failed to rename {} to {}: {}", old_name, new_name, err);
+        return;
+    }
+
+    let mut updated = 0usize;
+    if replace_reference(&mut folder.beatmapset.audio_filename, old_name, new_name) {
+        updated += 1;
+    }
+    for beatmap in folder.beatmaps.iter_mut() {
+        for path in beatmap.events.file_paths_mut() {
+            if replace_reference(path, old_name, new_name) {
+                updated += 1;
+            }
+        }
+        for object in beatmap.objects.objects.iter_mut() {
+            for filename in object.sample_filenames_mut() {
+                if replace_reference(filename, old_name, new_name) {
+                    updated += 1;
+                }
+            }
+        }
+    }
+    if updated > 0 && !write_beatmapset_folder(folder) {
+        println!("Renamed {} but failed to update references to it.", old_name);
+        return;
+    }
+
+    // Storyboard scripts reference assets by path too.
+    for (name, _) in folder.assets.names_and_sizes() {
+        if AssetKind::from_name(&name) != AssetKind::Storyboard || name == old_name {
+            continue;
+        }
+        let osb_path = assets_path.join(&name);
+        let Ok(text) = fs::read_to_string(&osb_path) else {
+            continue;
+        };
+        let old_reference = format!("\"{}\"", old_name);
+        if !text.contains(&old_reference) {
+            continue;
+        }
+        let text = text.replace(&old_reference, &format!("\"{}\"", new_name));
+        if let Err(err) = write_bytes_to_file(&osb_path, text.as_bytes()) {
+            println!("Failed to update storyboard {}: {}", name, err);
+        } else {
+            updated += 1;
+        }
+    }
+
+    println!(
+        "Renamed {} to {} ({} reference(s) updated).",
+        old_name, new_name, updated
+    );
+}
+
+fn delete_asset(folder: &BeatmapsetFolder, name: &str) {
+    let asset_path = Path::new("saves")
+        .join(&folder.map_dir_name)
+        .join("assets")
+        .join(name);
+    match fs::remove_file(&asset_path) {
+        Ok(()) => println!("Deleted {}.", name),
+        Err(err) => println!("Failed to delete {}: {}", name, err),
+    }
+}
+
+pub fn select_and_manage_files(event_loop: &mut EventLoop<()>, selector: &mut DialogueApp) {
+    let saves_path = Path::new("saves");
+    if !saves_path.exists() {
+        println!("No saves/ directory found.");
+        return;
+    }
+    let entries = scan_folder(saves_path, Some(true), None);
+    if entries.is_empty() {
+        println!("No maps found in saves/");
+        return;
+    }
+    let selection = match selector.select(event_loop, "Manage files of map", &entries) {
+        Some(idx) => idx,
+        None => return,
+    };
+    let map_dir_name = &entries[selection];
+
+    loop {
+        // Reload every time so the list reflects the previous operation.
+        let mut folder = match open_beatmapset_folder(map_dir_name) {
+            Some(folder) => folder,
+            None => {
+                println!("Failed to open beatmapset folder for {}", map_dir_name);
+                return;
+            }
+        };
+        let references = AssetReferences::collect(&folder);
+
+        let assets = folder.assets.names_and_sizes();
+        let mut total_size = 0usize;
+        let mut unused_count = 0usize;
+        let mut rows: Vec<String> = Vec::with_capacity(assets.len() + 1);
+        for (name, size) in assets.iter() {
+            let kind = AssetKind::from_name(name);
+            let used = references.is_used(name, &kind);
+            total_size += size;
+            if !used {
+                unused_count += 1;
+            }
+            rows.push(format!(
+                "{}  ({}, {}){}",
+                name,
+                kind.label(),
+                format_size(*size),
+                if used { "" } else { "  [unused]" }
+            ));
+        }
+        rows.push("back".to_string());
+
+        let title = format!(
+            "{} files, {} total, {} unused",
+            assets.len(),
+            format_size(total_size),
+            unused_count
+        );
+        let selection = match selector.select(event_loop, &title, &rows) {
+            Some(idx) if idx < assets.len() => idx,
+            _ => return,
+        };
+        let asset_name = assets[selection].0.clone();
+
+        let actions = vec!["rename".to_string(), "delete".to_string(), "back".to_string()];
+        match selector.select(event_loop, &asset_name, &actions) {
+            Some(0) => {
+                let Some(new_name) = selector.prompt_text(
+                    event_loop,
+                    "Rename asset",
+                    &format!("New file name for {}", asset_name),
+                ) else {
+                    continue;
+                };
+                let new_name = new_name.trim();
+                if new_name.is_empty() {
+                    continue;
+                }
+                rename_asset(&mut folder, &asset_name, new_name);
+            }
+            Some(1) => {
+                let kind = AssetKind::from_name(&asset_name);
+                let used = references.is_used(&asset_name, &kind);
+                let question = if used {
+                    format!("{} is still referenced by the map. Delete anyway?", asset_name)
+                } else {
+                    format!("Delete {}?", asset_name)
+                };
+                if selector.confirm(event_loop, &question) {
+                    delete_asset(&folder, &asset_name);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+// Where difficulty folders whose beatmap.json doesn't parse are moved, so the map opens again
+// without losing whatever is left of them.
+const UNREADABLE_DIFFS_PATH: &str = "backups/unreadable_diffs";
+
+// Difficulty folders without a readable beatmap.json, split into those with no beatmap.json at
+// all and those whose beatmap.json doesn't parse. The map does not open while either exist.
+fn find_orphaned_diffs(map_dir_name: &str) -> (Vec<String>, Vec<String>) {
+    let diffs_path = Path::new("saves").join(map_dir_name).join("diffs");
+    scan_folder(&diffs_path, Some(true), None)
+        .into_iter()
+        .filter(|diff| read_beatmap_json(map_dir_name, diff).is_none())
+        .partition(|diff| !diffs_path.join(diff).join("beatmap.json").exists())
+}
+
+fn move_unreadable_diff(map_dir_name: &str, diff: &str) {
+    let now_ms = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or(0);
+    let from = Path::new("saves").join(map_dir_name).join("diffs").join(diff);
+    let to_dir = Path::new(UNREADABLE_DIFFS_PATH).join(map_dir_name);
+    let to = to_dir.join(format!("{}_{}", diff, now_ms));
+    match fs::create_dir_all(&to_dir).and_then(|_| fs::rename(&from, &to)) {
+        Ok(()) => println!("Moved diffs/{} to {}.", diff, to.display()),
+        Err(err) => println!("Failed to move diffs/{}: {}", diff, err),
+    }
+}
+
+// Difficulties whose bg_small.png is missing or older than their background asset.
+fn find_stale_thumbnails(folder: &BeatmapsetFolder) -> Vec<String> {
+    let save_path = Path::new("saves").join(&folder.map_dir_name);
+    let modified = |path: &Path| fs::metadata(path).and_then(|m| m.modified()).ok();
+    folder
+        .beatmaps
+        .iter()
+        .filter(|beatmap| {
+            let thumbnail_path = save_path
+                .join("diffs")
+                .join(sanitize_name(&beatmap.version))
+                .join("bg_small.png");
+            let Some(thumbnail_modified) = modified(&thumbnail_path) else {
+                return true;
+            };
+            let background_name = beatmap.events.background_name().replace('\\', "/");
+            if background_name.is_empty() {
+                return false;
+            }
+            modified(&save_path.join("assets").join(background_name))
+                .is_some_and(|background_modified| background_modified > thumbnail_modified)
+        })
+        .map(|beatmap| beatmap.version.clone())
+        .collect()
+}
+
+fn regenerate_thumbnail(folder: &BeatmapsetFolder, version: &str) {
+    let Some(beatmap) = folder.beatmaps.iter().find(|b| b.version == version) else {
+        return;
+    };
+    let background_name = beatmap.events.background_name();
+    let background_bytes = if background_name.is_empty() {
+        None
+    } else {
+        folder
+            .assets
+            .get(&background_name)
+            .map(|bytes| bytes.as_slice())
+    };
+    let thumbnail_path = Path::new("saves")
+        .join(&folder.map_dir_name)
+        .join("diffs")
+        .join(sanitize_name(version))
+        .join("bg_small.png");
+    let thumbnail = make_bg_small_png(background_bytes);
+    match write_bytes_to_file(&thumbnail_path, &thumbnail) {
+        Ok(()) => println!("Regenerated the thumbnail of {}.", version),
+        Err(err) => println!("Failed to write {}: {}", thumbnail_path.display(), err),
+    }
+}
+
+pub fn select_and_clean_map(event_loop: &mut EventLoop<()>, selector: &mut DialogueApp) {
+    let saves_path = Path::new("saves");
+    if !saves_path.exists() {
+        println!("No saves/ directory found.");
+        return;
+    }
+    let entries = scan_folder(saves_path, Some(true), None);
+    if entries.is_empty() {
+        println!("No maps found in saves/");
+        return;
+    }
+    let selection = match selector.select(event_loop, "Clean up map", &entries) {
+        Some(idx) => idx,
+        None => return,
+    };
+    let map_dir_name = &entries[selection];
+    clean_map(event_loop, selector, map_dir_name);
+}
+
+// Offers to remove orphaned difficulty folders, set aside unreadable ones, regenerate out of
+// date thumbnails and delete assets nothing references, asking once for each.
+fn clean_map(event_loop: &mut EventLoop<()>, selector: &mut DialogueApp, map_dir_name: &str) {
+    let mut cleaned = false;
+
+    let (orphaned_diffs, unreadable_diffs) = find_orphaned_diffs(map_dir_name);
+    if !orphaned_diffs.is_empty() {
+        println!("Difficulty folders without a beatmap.json:");
+        for diff in orphaned_diffs.iter() {
+            println!("  diffs/{}", diff);
+        }
+        let question = format!(
+            "Delete {} orphaned difficulty folder(s)?",
+            orphaned_diffs.len()
+        );
+        if !selector.confirm(event_loop, &question) {
+            println!("The map does not open with them, skipped the rest of the clean up.");
+            return;
+        }
+        let diffs_path = Path::new("saves").join(map_dir_name).join("diffs");
+        for diff in orphaned_diffs.iter() {
+            match fs::remove_dir_all(diffs_path.join(diff)) {
+                Ok(()) => println!("Deleted diffs/{}.", diff),
+                Err(err) => println!("Failed to delete diffs/{}: {}", diff, err),
+            }
+        }
+        cleaned = true;
+    }
+
+    if !unreadable_diffs.is_empty() {
+        println!("Difficulty folders whose beatmap.json doesn't parse:");
+        for diff in unreadable_diffs.iter() {
+            println!("  diffs/{}", diff);
+        }
+        let question = format!(
+            "Move {} unreadable difficulty folder(s) to {}/?",
+            unreadable_diffs.len(),
+            UNREADABLE_DIFFS_PATH
+        );
+        if !selector.confirm(event_loop, &question) {
+            println!("The map does not open with them, skipped the rest of the clean up.");
+            return;
+        }
+        for diff in unreadable_diffs.iter() {
+            move_unreadable_diff(map_dir_name, diff);
+        }
+        cleaned = true;
+    }
+
+    let folder = match open_beatmapset_folder(&map_dir_name.to_string()) {
+        Some(folder) => folder,
+        None => {
+            println!("Failed to open beatmapset folder for {}", map_dir_name);
+            return;
+        }
+    };
+
+    let stale_thumbnails = find_stale_thumbnails(&folder);
+    if !stale_thumbnails.is_empty() {
+        println!("Missing or out of date thumbnails:");
+        for version in stale_thumbnails.iter() {
+            println!("  {}", version);
+        }
+        let question = format!("Regenerate {} thumbnail(s)?", stale_thumbnails.len());
+        if selector.confirm(event_loop, &question) {
+            for version in stale_thumbnails.iter() {
+                regenerate_thumbnail(&folder, version);
+            }
+            cleaned = true;
+        }
+    }
+
+    let references = AssetReferences::collect(&folder);
+    let unused_assets: Vec<(String, usize)> = folder
+        .assets
+        .names_and_sizes()
+        .into_iter()
+        .filter(|(name, _)| !references.is_used(name, &AssetKind::from_name(name)))
+        .collect();
+    if !unused_assets.is_empty() {
+        println!("Assets nothing references:");
+        for (name, size) in unused_assets.iter() {
+            println!("  {} ({})", name, format_size(*size));
+        }
+        let total_size: usize = unused_assets.iter().map(|(_, size)| size).sum();
+        let question = format!(
+            "Delete {} unused asset(s), {}?",
+            unused_assets.len(),
+            format_size(total_size)
+        );
+        if selector.confirm(event_loop, &question) {
+            for (name, _) in unused_assets.iter() {
+                delete_asset(&folder, name);
+            }
+            cleaned = true;
+        }
+    }
+
+    if !cleaned {
+        println!("Nothing to clean up in {}.", map_dir_name);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        files::AssetsFolder,
+        test_harness::{TEST_MAP, parse_test_map},
+    };
+
+    #[test]
+    fn samples_are_reached_through_object_and_timing_indices() {
+        let osu_text = TEST_MAP
+            .replace("0,500,4,2,0,60,1,0", "0,500,4,2,1,60,1,0")
+            .replace("2000,-100,4,2,0,60,0,1", "2000,-100,4,2,2,60,0,1");
+        let (beatmapset, beatmap) = parse_test_map(&osu_text);
+        let mut stems = HashSet::new();
+        reachable_sample_stems(&beatmap, &mut stems);
+        for used in [
+            "soft-hitnormal",
+            "soft-hitwhistle",
+            "soft-hitnormal2",
+            "soft-hitwhistle2",
+            "soft-sliderslide2",
+        ] {
+            assert!(stems.contains(used), "{used}");
+        }
+        for unused in ["soft-hitclap", "soft-hitnormal3", "normal-hitnormal"] {
+            assert!(!stems.contains(unused), "{unused}");
+        }
+        assert_eq!(storyboard_file_name(&beatmapset), "test - harness (test).osb");
+    }
+
+    #[test]
+    fn assets_are_used_when_a_difficulty_or_the_storyboard_points_at_them() {
+        let (beatmapset, beatmap) = parse_test_map(TEST_MAP);
+        let mut assets = AssetsFolder::default();
+        let script = "[Events]\nSprite,Foreground,Centre,\"SB\\star.png\",320,240\n";
+        assets.insert("Test - Harness (Test).osb".to_string(), script.as_bytes().to_vec());
+        let folder = BeatmapsetFolder {
+            map_dir_name: "harness".to_string(),
+            beatmapset,
+            beatmaps: vec![beatmap],
+            assets,
+        };
+        let references = AssetReferences::collect(&folder);
+        assert!(references.is_used("audio.mp3", &AssetKind::Audio));
+        assert!(references.is_used("BG.png", &AssetKind::Image));
+        assert!(references.is_used("sb/star.png", &AssetKind::Image));
+        assert!(!references.is_used("cover.png", &AssetKind::Image));
+        // Index 0 everywhere leaves every hitsound to the skin.
+        assert!(!references.is_used("soft-hitwhistle.wav", &AssetKind::Hitsound));
+        assert!(references.is_used("Test - Harness (Test).osb", &AssetKind::Storyboard));
+        assert!(!references.is_used("old.osb", &AssetKind::Storyboard));
+    }
+
+    #[test]
+    fn renamed_references_keep_their_quotes() {
+        let mut quoted = "\"BG.png\"".to_string();
+        assert!(replace_reference(&mut quoted, "bg.png", "cover.png"));
+        assert_eq!(quoted, "\"cover.png\"");
+        let mut plain = "./sb\\star.png".to_string();
+        assert!(replace_reference(&mut plain, "sb/star.png", "sb/glow.png"));
+        assert_eq!(plain, "sb/glow.png");
+        let mut other = "video.mp4".to_string();
+        assert!(!replace_reference(&mut other, "bg.png", "cover.png"));
+        assert_eq!(other, "video.mp4");
+    }
+}
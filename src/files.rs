@@ -2,12 +2,31 @@ use std::{
     collections::HashMap,
     fs,
     io::{Read, Write},
-    path::Path,
+    path::{Component, Path, PathBuf},
+    sync::{
+        Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::{SystemTime, UNIX_EPOCH},
 };
 
+use serde::{Deserialize, Serialize};
+
 use crate::{
-    config::Config,
-    map_format::{beatmap::Beatmap, beatmapset::Beatmapset},
+    color_presets::{COLOR_PRESET_NAMES, ColorPreset},
+    config::{AppearanceLayoutConfig, Config},
+    dotosu::{
+        helpers::{get_key_value_pairs, get_section},
+        osu_file::parse_osu_file,
+    },
+    map_format::{
+        beatmap::Beatmap, beatmapset::Beatmapset,
+        convert_to_osu_format::convert_internal_to_osu_format,
+        statistics::{EditingSession, MapStatistics},
+        timing::Timing,
+    },
+    render::{VIEW_PRESET_NAMES, ViewPreset},
+    state::{CONVERT_MODE_NAMES, ConvertMode},
 };
 
 pub fn scan_folder(path: &Path, dir: Option<bool>, suffix: Option<&Vec<&str>>) -> Vec<String> {
@@ -56,6 +75,14 @@ pub fn sanitize_name(input: &str) -> String {
     return out;
 }
 
+// Whether `path` is relative and stays inside the folder it's joined to: no root, drive,
+// `.` or `..` parts, with either slash counted as a separator.
+pub fn is_enclosed_path(path: &str) -> bool {
+    path.split(['/', '\\'])
+        .all(|part| !matches!(part, "" | "." | "..") && !part.contains(':'))
+        && Path::new(path).components().all(|part| matches!(part, Component::Normal(_)))
+}
+
 pub fn extract_zip(bytes: Vec<u8>) -> Option<Vec<(String, Vec<u8>)>> {
     let reader = std::io::Cursor::new(bytes);
     let mut archive = match zip::ZipArchive::new(reader) {
@@ -81,6 +108,10 @@ pub fn extract_zip(bytes: Vec<u8>) -> Option<Vec<(String, Vec<u8>)>> {
         }
 
         let name = file.name().to_string();
+        if !is_enclosed_path(&name) {
+            println!("Skipped {} in the archive, it points outside the folder.", name);
+            continue;
+        }
         let mut bytes = Vec::with_capacity(file.size() as usize);
         if let Err(err) = file.read_to_end(&mut bytes) {
             println!("Failed to extract {} from archive: {}", name, err);
@@ -92,21 +123,188 @@ pub fn extract_zip(bytes: Vec<u8>) -> Option<Vec<(String, Vec<u8>)>> {
     return Some(extracted);
 }
 
-pub fn write_bytes_to_file(path: &Path, bytes: &[u8]) -> std::io::Result<()> {
-    match path.parent() {
-        Some(parent) => {
-            let err = fs::create_dir_all(parent).err();
-            if let Some(err) = err {
-                return Err(err);
+// Bytes kept of all failed writes together. Failures past it are still shown, but retrying
+// them means doing what wrote them again.
+const MAX_RETRY_BYTES: usize = 32 * 1024 * 1024;
+
+// A write that failed, kept with its bytes so the editor can offer to try it again.
+struct FailedWrite {
+    path: PathBuf,
+    reason: String,
+    bytes: Option<Vec<u8>>,
+}
+
+// Writes that failed and haven't succeeded since, oldest first. The editor uses the one
+// behind `write_bytes_to_file`.
+#[derive(Default)]
+pub struct FailedWrites(Mutex<Vec<FailedWrite>>);
+
+static FAILED_WRITES: FailedWrites = FailedWrites::new();
+static NEXT_TMP_ID: AtomicU64 = AtomicU64::new(0);
+
+impl FailedWrites {
+    pub const fn new() -> Self {
+        FailedWrites(Mutex::new(Vec::new()))
+    }
+
+    // Like `write_file_atomically`, remembering a failure until the same path is written again.
+    pub fn write(&self, path: &Path, bytes: &[u8]) -> std::io::Result<()> {
+        let result = write_file_atomically(path, bytes);
+        if let Ok(mut failed_writes) = self.0.lock() {
+            failed_writes.retain(|failed| failed.path != path);
+            if let Err(err) = &result {
+                let kept: usize = failed_writes
+                    .iter()
+                    .filter_map(|failed| failed.bytes.as_ref().map(|bytes| bytes.len()))
+                    .sum();
+                failed_writes.push(FailedWrite {
+                    path: path.to_path_buf(),
+                    reason: describe_write_error(err),
+                    bytes: (kept + bytes.len() <= MAX_RETRY_BYTES).then(|| bytes.to_vec()),
+                });
+            }
+        }
+        result
+    }
+
+    // Path and reason of every failed write, oldest first.
+    pub fn list(&self) -> Vec<(PathBuf, String)> {
+        match self.0.lock() {
+            Ok(failed_writes) => failed_writes
+                .iter()
+                .map(|failed| (failed.path.clone(), failed.reason.clone()))
+                .collect(),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    // Tries every failed write again with the bytes it had then. Ones too large to have been
+    // kept are dropped with a note. Returns how many still fail.
+    pub fn retry(&self) -> usize {
+        let retries = match self.0.lock() {
+            Ok(mut failed_writes) => std::mem::take(&mut *failed_writes),
+            Err(_) => return 0,
+        };
+        let mut still_failing = 0;
+        for failed in retries {
+            let Some(bytes) = failed.bytes else {
+                println!(
+                    "{} was too large to keep for a retry, save or export it again.",
+                    failed.path.display()
+                );
+                continue;
+            };
+            match self.write(&failed.path, &bytes) {
+                Ok(()) => println!("Saved {} on retry.", failed.path.display()),
+                Err(err) => {
+                    println!("Still failed to write {}: {}", failed.path.display(), err);
+                    still_failing += 1;
+                }
             }
         }
-        None => {}
+        still_failing
+    }
+}
+
+// Writes `bytes` safely, see `write_file_atomically`. A failure is remembered for
+// `retry_failed_writes` until the same path is written again.
+pub fn write_bytes_to_file(path: &Path, bytes: &[u8]) -> std::io::Result<()> {
+    FAILED_WRITES.write(path, bytes)
+}
+
+// Writes to a temporary file next to `path`, flushes it to disk and only then renames it over
+// `path`, so a full disk or a crash halfway through leaves the previous file as it was. Nothing
+// is remembered for a retry, for files that are only useful as part of a larger write.
+pub fn write_file_atomically(path: &Path, bytes: &[u8]) -> std::io::Result<()> {
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    fs::create_dir_all(parent)?;
+    // Renaming over a read-only file works on some systems, so check rather than find out.
+    // Windows ignores the read-only attribute of folders, which many of them have set anyway.
+    let checked_paths = if cfg!(windows) {
+        vec![path]
+    } else {
+        vec![parent, path]
+    };
+    for checked in checked_paths {
+        if fs::metadata(checked).is_ok_and(|m| m.permissions().readonly()) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::PermissionDenied,
+                format!("{} is read-only", checked.display()),
+            ));
+        }
+    }
+
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+    let tmp_id = NEXT_TMP_ID.fetch_add(1, Ordering::Relaxed);
+    let tmp_path = parent.join(format!(".{}.{}.{}.tmp", file_name, std::process::id(), tmp_id));
+    let written = fs::File::create(&tmp_path)
+        .and_then(|mut file| {
+            file.write_all(bytes)?;
+            file.sync_all()
+        })
+        .and_then(|_| fs::rename(&tmp_path, path));
+    if let Err(err) = written {
+        let _ = fs::remove_file(&tmp_path);
+        return Err(err);
+    }
+    // Makes the rename itself survive a power loss. Directories can't be opened for this on
+    // Windows, where the rename is already durable.
+    #[cfg(unix)]
+    if let Ok(dir) = fs::File::open(parent) {
+        let _ = dir.sync_all();
+    }
+    Ok(())
+}
+
+// Short enough for the editor's hint line.
+pub fn describe_write_error(err: &std::io::Error) -> String {
+    match err.kind() {
+        std::io::ErrorKind::StorageFull => "not enough disk space".to_string(),
+        std::io::ErrorKind::ReadOnlyFilesystem => "the drive is read-only".to_string(),
+        std::io::ErrorKind::PermissionDenied if err.get_ref().is_none() => {
+            "no permission to write there".to_string()
+        }
+        _ => err.to_string(),
     }
-    fs::create_dir_all(path.parent().unwrap())?;
-    let mut file = fs::File::create(path)?;
-    use std::io::Write;
-    file.write_all(bytes)?;
-    return Ok(());
+}
+
+// Path and reason of every write that failed and hasn't succeeded since, oldest first.
+pub fn failed_writes() -> Vec<(PathBuf, String)> {
+    FAILED_WRITES.list()
+}
+
+// The oldest failed write for the hint line, in the HUD font's letters, digits, spaces and
+// `-./:_|`, with how many more there are.
+pub fn failed_writes_hint(failed_writes: &[(PathBuf, String)]) -> Option<String> {
+    const MAX_HINT_CHARS: usize = 96;
+    let (path, reason) = failed_writes.first()?;
+    let more = match failed_writes.len() - 1 {
+        0 => String::new(),
+        count => format!(" and {} more", count),
+    };
+    let suffix = format!("{} | ctrl s retries", more);
+    let file_name = path.file_name().unwrap_or(path.as_os_str()).to_string_lossy();
+    let hint: String = format!("save failed: {} - {}", file_name, reason)
+        .to_lowercase()
+        .chars()
+        .map(|ch| {
+            if ch.is_ascii_alphanumeric() || " -./:_|".contains(ch) {
+                ch
+            } else {
+                ' '
+            }
+        })
+        .take(MAX_HINT_CHARS - suffix.len())
+        .collect();
+    Some(hint + &suffix)
+}
+
+// Tries every failed write again, see `FailedWrites::retry`. Returns how many still fail.
+pub fn retry_failed_writes() -> usize {
+    FAILED_WRITES.retry()
 }
 
 fn scan_folder_recursive_files(root: &Path) -> Vec<String> {
@@ -145,6 +343,18 @@ fn scan_folder_recursive_files(root: &Path) -> Vec<String> {
     out
 }
 
+// Every file below `root` with its path relative to it, the same shape `extract_zip` returns.
+pub fn read_folder_files(root: &Path) -> Vec<(String, Vec<u8>)> {
+    let mut files = Vec::new();
+    for rel in scan_folder_recursive_files(root) {
+        match fs::read(root.join(&rel)) {
+            Ok(bytes) => files.push((rel, bytes)),
+            Err(err) => println!("Failed to read {}: {}", rel, err),
+        }
+    }
+    files
+}
+
 pub struct BeatmapsetFolder {
     pub map_dir_name: String,
     pub beatmapset: Beatmapset,
@@ -152,7 +362,7 @@ pub struct BeatmapsetFolder {
     pub assets: AssetsFolder,
 }
 
-#[derive(Clone)]
+#[derive(Clone, Default)]
 pub struct AssetsFolder {
     assets: HashMap<String, Vec<u8>>,
 }
@@ -206,6 +416,34 @@ impl AssetsFolder {
         self.assets.insert(name, data);
     }
 
+    // `name`, or `<stem>_2.<ext>`, `<stem>_3.<ext>`, ... when an asset with other contents
+    // already goes by it. An asset with the same bytes keeps its name and is shared.
+    pub fn free_name_for(&self, name: &str, bytes: &[u8]) -> String {
+        let path = Path::new(name);
+        let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or(name);
+        let ext = path.extension().and_then(|s| s.to_str());
+        let mut candidate = name.to_string();
+        let mut n = 2;
+        while self.get(&candidate).is_some_and(|existing| existing.as_slice() != bytes) {
+            candidate = match ext {
+                Some(ext) => format!("{}_{}.{}", stem, n, ext),
+                None => format!("{}_{}", stem, n),
+            };
+            n += 1;
+        }
+        candidate
+    }
+
+    pub fn names_and_sizes(&self) -> Vec<(String, usize)> {
+        let mut entries: Vec<(String, usize)> = self
+            .assets
+            .iter()
+            .map(|(name, bytes)| (name.clone(), bytes.len()))
+            .collect();
+        entries.sort_by(|a, b| a.0.to_ascii_lowercase().cmp(&b.0.to_ascii_lowercase()));
+        entries
+    }
+
     pub fn clone_map(&self) -> HashMap<String, Vec<u8>> {
         self.assets.clone()
     }
@@ -234,28 +472,49 @@ pub fn open_beatmapset_folder(map_dir_name: &String) -> Option<BeatmapsetFolder>
         println!("No diffs found in diffs/");
         return None;
     }
-    let mut beatmaps = Vec::new();
-    for diff in diffs_folders {
-        let diff =
-            Path::new(&format!("saves/{}/diffs/{}", map_dir_name, diff)).join("beatmap.json");
-        let beatmap_json = match fs::read_to_string(diff) {
-            Ok(content) => content,
-            Err(err) => {
-                println!("Failed to read beatmap JSON: {}", err);
-                return None;
-            }
-        };
-        let beatmap =
-            match serde_json::from_str::<crate::map_format::beatmap::Beatmap>(&beatmap_json) {
-                Ok(b) => b,
-                Err(err) => {
-                    println!("Failed to parse beatmap JSON: {}", err);
-                    return None;
-                }
-            };
-        beatmaps.push(beatmap);
+    // Difficulties are parsed on their own threads while the assets are read, large sets
+    // spend most of their open time in serde_json.
+    let (beatmaps, assets) = std::thread::scope(|scope| {
+        let parsers: Vec<_> = diffs_folders
+            .iter()
+            .map(|diff| scope.spawn(move || read_beatmap_json(map_dir_name, diff)))
+            .collect();
+        let assets = read_assets_folder(map_dir_name);
+        let beatmaps: Option<Vec<Beatmap>> = parsers
+            .into_iter()
+            .map(|parser| parser.join().ok().flatten())
+            .collect();
+        (beatmaps, assets)
+    });
+    let beatmaps = beatmaps?;
+
+    return Some(BeatmapsetFolder {
+        map_dir_name: map_dir_name.clone(),
+        beatmapset,
+        beatmaps,
+        assets: AssetsFolder { assets },
+    });
+}
+
+pub fn read_beatmap_json(map_dir_name: &str, diff: &str) -> Option<Beatmap> {
+    let diff = Path::new(&format!("saves/{}/diffs/{}", map_dir_name, diff)).join("beatmap.json");
+    let beatmap_json = match fs::read_to_string(diff) {
+        Ok(content) => content,
+        Err(err) => {
+            println!("Failed to read beatmap JSON: {}", err);
+            return None;
+        }
+    };
+    match serde_json::from_str::<Beatmap>(&beatmap_json) {
+        Ok(b) => Some(b),
+        Err(err) => {
+            println!("Failed to parse beatmap JSON: {}", err);
+            None
+        }
     }
+}
 
+fn read_assets_folder(map_dir_name: &str) -> HashMap<String, Vec<u8>> {
     let assets_folder = format!("saves/{}/assets", map_dir_name);
     let assets_folder = Path::new(assets_folder.as_str());
     let assets_folder = scan_folder_recursive_files(assets_folder);
@@ -274,13 +533,336 @@ pub fn open_beatmapset_folder(map_dir_name: &String) -> Option<BeatmapsetFolder>
         };
         assets.insert(asset, asset_bytes);
     }
+    assets
+}
 
-    return Some(BeatmapsetFolder {
-        map_dir_name: map_dir_name.clone(),
-        beatmapset,
-        beatmaps,
-        assets: AssetsFolder { assets },
+pub fn write_beatmap(map_dir_name: &str, beatmap: &Beatmap) -> bool {
+    let beatmap_path = Path::new("saves")
+        .join(map_dir_name)
+        .join("diffs")
+        .join(sanitize_name(&beatmap.version))
+        .join("beatmap.json");
+    let beatmap_json = match serde_json::to_string_pretty(beatmap) {
+        Ok(json) => json,
+        Err(err) => {
+            println!("Failed to serialize beatmap to JSON: {}", err);
+            return false;
+        }
+    };
+    if let Err(err) = write_bytes_to_file(&beatmap_path, beatmap_json.as_bytes()) {
+        println!(
+            "Failed to write beatmap file {}: {}",
+            beatmap_path.display(),
+            describe_write_error(&err)
+        );
+        return false;
+    }
+    record_map_statistics(map_dir_name, beatmap);
+    true
+}
+
+fn map_statistics_path(map_dir_name: &str) -> PathBuf {
+    Path::new("saves")
+        .join(map_dir_name)
+        .join("statistics.json")
+}
+
+pub fn read_map_statistics(map_dir_name: &str) -> Vec<MapStatistics> {
+    let path = map_statistics_path(map_dir_name);
+    if !path.exists() {
+        return Vec::new();
+    }
+    let Some(value) = read_json_value(&path) else {
+        return Vec::new();
+    };
+    match serde_json::from_value::<Vec<MapStatistics>>(value) {
+        Ok(history) => history,
+        Err(err) => {
+            println!("Failed to parse {}: {}", path.display(), err);
+            Vec::new()
+        }
+    }
+}
+
+// One snapshot per difficulty and day, a later save on the same day replaces it.
+fn record_map_statistics(map_dir_name: &str, beatmap: &Beatmap) {
+    const SECONDS_PER_DAY: u64 = 86400;
+    let saved_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let snapshot = MapStatistics::of(beatmap, saved_at);
+    let mut history = read_map_statistics(map_dir_name);
+    history.retain(|entry| {
+        entry.difficulty != snapshot.difficulty
+            || entry.saved_at / SECONDS_PER_DAY != saved_at / SECONDS_PER_DAY
+    });
+    history.push(snapshot);
+    let path = map_statistics_path(map_dir_name);
+    let json = match serde_json::to_string_pretty(&history) {
+        Ok(json) => json,
+        Err(err) => {
+            println!("Failed to serialize map statistics: {}", err);
+            return;
+        }
+    };
+    if let Err(err) = write_bytes_to_file(&path, json.as_bytes()) {
+        println!("Failed to write {}: {}", path.display(), err);
+    }
+}
+
+fn editing_sessions_path(map_dir_name: &str) -> PathBuf {
+    Path::new("saves").join(map_dir_name).join("sessions.json")
+}
+
+pub fn read_editing_sessions(map_dir_name: &str) -> Vec<EditingSession> {
+    let path = editing_sessions_path(map_dir_name);
+    if !path.exists() {
+        return Vec::new();
+    }
+    let Some(value) = read_json_value(&path) else {
+        return Vec::new();
+    };
+    match serde_json::from_value::<Vec<EditingSession>>(value) {
+        Ok(sessions) => sessions,
+        Err(err) => {
+            println!("Failed to parse {}: {}", path.display(), err);
+            Vec::new()
+        }
+    }
+}
+
+// Replaces the entry of the same sitting, so it can be recorded more than once as it goes on.
+// With `write_csv` every session of the map is also written to sessions.csv next to it.
+pub fn record_editing_session(map_dir_name: &str, session: &EditingSession, write_csv: bool) {
+    let mut sessions = read_editing_sessions(map_dir_name);
+    sessions.retain(|entry| {
+        entry.difficulty != session.difficulty || entry.started_at != session.started_at
     });
+    sessions.push(session.clone());
+    let path = editing_sessions_path(map_dir_name);
+    let json = match serde_json::to_string_pretty(&sessions) {
+        Ok(json) => json,
+        Err(err) => {
+            println!("Failed to serialize editing sessions: {}", err);
+            return;
+        }
+    };
+    if let Err(err) = write_bytes_to_file(&path, json.as_bytes()) {
+        println!("Failed to write {}: {}", path.display(), err);
+    }
+    if write_csv {
+        let csv_path = path.with_extension("csv");
+        let csv = editing_sessions_csv(&sessions);
+        if let Err(err) = write_bytes_to_file(&csv_path, csv.as_bytes()) {
+            println!("Failed to write {}: {}", csv_path.display(), err);
+        }
+    }
+}
+
+pub fn editing_sessions_csv(sessions: &[EditingSession]) -> String {
+    let mut csv = String::from("difficulty,started_at,active_minutes,placed,edited,removed\n");
+    for session in sessions {
+        csv.push_str(&format!(
+            "\"{}\",{},{:.1},{},{},{}\n",
+            session.difficulty.replace('"', "\"\""),
+            session.started_at,
+            session.active_ms / 60_000.0,
+            session.placed,
+            session.edited,
+            session.removed
+        ));
+    }
+    csv
+}
+
+// Writes every difficulty's beatmap.json of `folder`, then beatmapset.json once they all
+// made it, so the set never points at difficulties that were not saved.
+pub fn write_beatmapset_folder(folder: &BeatmapsetFolder) -> bool {
+    folder
+        .beatmaps
+        .iter()
+        .all(|beatmap| write_beatmap(&folder.map_dir_name, beatmap))
+        && write_beatmapset_json(folder)
+}
+
+pub fn write_beatmapset_json(folder: &BeatmapsetFolder) -> bool {
+    let save_path = Path::new("saves").join(&folder.map_dir_name);
+    let beatmapset_json = match serde_json::to_string_pretty(&folder.beatmapset) {
+        Ok(json) => json,
+        Err(err) => {
+            println!("Failed to serialize beatmapset to JSON: {}", err);
+            return false;
+        }
+    };
+    if let Err(err) = write_bytes_to_file(
+        &save_path.join("beatmapset.json"),
+        beatmapset_json.as_bytes(),
+    ) {
+        println!("Failed to write beatmapset.json: {}", err);
+        return false;
+    }
+    true
+}
+
+// A song folder under saves/<map>/tools for external tools, holding the assets and `beatmap`
+// as .osu. Assets already written are kept, the .osu is rewritten every time.
+pub fn write_tool_folder(folder: &BeatmapsetFolder, beatmap: &Beatmap) -> Option<PathBuf> {
+    let tools_path = Path::new("saves").join(&folder.map_dir_name).join("tools");
+    for (asset_name, size) in folder.assets.names_and_sizes() {
+        let asset_path = tools_path.join(&asset_name);
+        if fs::metadata(&asset_path).is_ok_and(|m| m.len() == size as u64) {
+            continue;
+        }
+        let Some(asset_bytes) = folder.assets.get(&asset_name) else {
+            continue;
+        };
+        if let Err(err) = write_bytes_to_file(&asset_path, asset_bytes) {
+            println!("Failed to write {}: {}", asset_path.display(), err);
+            return None;
+        }
+    }
+
+    let osu_file = convert_internal_to_osu_format(folder.beatmapset.clone(), beatmap.clone());
+    let file_name = format!(
+        "{} ({}).osu",
+        &osu_file.metadata.version, osu_file.metadata.beatmap_id,
+    );
+    let osu_path = tools_path.join(sanitize_name(&file_name));
+    if let Err(err) = write_bytes_to_file(&osu_path, osu_file.to_osu_text().as_bytes()) {
+        println!("Failed to write {}: {}", osu_path.display(), err);
+        return None;
+    }
+    Some(osu_path)
+}
+
+// The .osu file a difficulty was imported from and its text. Bookmarks are only kept there.
+pub fn find_imported_diff(map_dir_name: &str, version: &str) -> Option<(PathBuf, String)> {
+    let imported_diffs_dir = Path::new("saves").join(map_dir_name).join("imported_diffs");
+    let entries = fs::read_dir(&imported_diffs_dir).ok()?;
+    for entry in entries {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(_) => continue,
+        };
+        let path = entry.path();
+        if !path
+            .extension()
+            .and_then(|x| x.to_str())
+            .map(|x| x.eq_ignore_ascii_case("osu"))
+            .unwrap_or(false)
+        {
+            continue;
+        }
+
+        let osu_text = match fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(_) => continue,
+        };
+
+        let metadata_section = match get_section(&osu_text, "Metadata") {
+            Some(section) => section,
+            None => continue,
+        };
+        let metadata = match get_key_value_pairs(metadata_section) {
+            Some(pairs) => pairs,
+            None => continue,
+        };
+        let osu_version = match metadata.get("Version") {
+            Some(value) => value.trim(),
+            None => continue,
+        };
+        if osu_version == version {
+            return Some((path, osu_text));
+        }
+    }
+    None
+}
+
+// Timing points of any .osu file, the rest of it is not converted.
+pub fn read_osu_timing(path: &Path) -> Option<Timing> {
+    let data = match fs::read(path) {
+        Ok(data) => data,
+        Err(err) => {
+            println!("Failed to read {}: {}", path.display(), err);
+            return None;
+        }
+    };
+    let file_name = path.file_name()?.to_string_lossy().to_string();
+    let osu_file = parse_osu_file(file_name, data.as_slice(), &mut |_| None)?;
+    Some(Timing::from_osu_format(&osu_file.timing, &osu_file.general))
+}
+
+// Writes a new difficulty next to the existing ones and registers it in `folder`.
+// The preview image is copied from `source_version`.
+pub fn create_difficulty(
+    folder: &mut BeatmapsetFolder,
+    beatmap: Beatmap,
+    source_version: &str,
+) -> bool {
+    if folder
+        .beatmaps
+        .iter()
+        .any(|b| sanitize_name(&b.version) == sanitize_name(&beatmap.version))
+    {
+        println!("Difficulty {} already exists.", beatmap.version);
+        return false;
+    }
+
+    let save_path = Path::new("saves").join(&folder.map_dir_name);
+    let diffs_path = save_path.join("diffs");
+    let diff_path = diffs_path.join(sanitize_name(&beatmap.version));
+    let beatmap_json = match serde_json::to_string_pretty(&beatmap) {
+        Ok(json) => json,
+        Err(err) => {
+            println!("Failed to serialize beatmap to JSON: {}", err);
+            return false;
+        }
+    };
+    let beatmap_path = diff_path.join("beatmap.json");
+    if let Err(err) = write_bytes_to_file(&beatmap_path, beatmap_json.as_bytes()) {
+        println!(
+            "Failed to write beatmap file {}: {}",
+            beatmap_path.display(),
+            err
+        );
+        return false;
+    }
+
+    let source_bg_small_path = diffs_path
+        .join(sanitize_name(source_version))
+        .join("bg_small.png");
+    if let Ok(bg_small) = fs::read(&source_bg_small_path) {
+        let bg_small_path = diff_path.join("bg_small.png");
+        if let Err(err) = write_bytes_to_file(&bg_small_path, bg_small.as_slice()) {
+            println!(
+                "Failed to write diff background preview {}: {}",
+                bg_small_path.display(),
+                err
+            );
+        }
+    }
+
+    let osu_file = convert_internal_to_osu_format(folder.beatmapset.clone(), beatmap.clone());
+    let osu_file_name = sanitize_name(&format!(
+        "{} - {} ({}) [{}].osu",
+        folder.beatmapset.artist,
+        folder.beatmapset.title,
+        folder.beatmapset.creator,
+        beatmap.version
+    ));
+    let osu_file_path = save_path.join("imported_diffs").join(osu_file_name);
+    if let Err(err) = write_bytes_to_file(&osu_file_path, osu_file.to_osu_text().as_bytes()) {
+        println!(
+            "Failed to write .osu file {}: {}",
+            osu_file_path.display(),
+            err
+        );
+    }
+
+    log!("Created difficulty {} in {}", beatmap.version, diff_path.display());
+    folder.beatmaps.push(beatmap);
+    return true;
 }
 
 pub fn create_zip(files: HashMap<String, Vec<u8>>) -> Option<Vec<u8>> {
@@ -308,21 +890,365 @@ pub fn create_zip(files: HashMap<String, Vec<u8>>) -> Option<Vec<u8>> {
     return Some(buffer.into_inner());
 }
 
-pub fn get_config() -> Option<Config> {
-    let config_path = Path::new("config.json");
-
-    let config_json = match fs::read_to_string(config_path) {
+fn read_json_value(path: &Path) -> Option<serde_json::Value> {
+    let json = match fs::read_to_string(path) {
         Ok(content) => content,
         Err(err) => {
-            println!("Failed to read config.json: {}", err);
+            println!("Failed to read {}: {}", path.display(), err);
             return None;
         }
     };
-    return match serde_json::from_str::<Config>(&config_json) {
-        Ok(config) => Some(config),
+    match serde_json::from_str::<serde_json::Value>(&json) {
+        Ok(value) => Some(value),
         Err(err) => {
-            println!("Failed to parse config.json: {}", err);
+            println!("Failed to parse {}: {}", path.display(), err);
+            None
+        }
+    }
+}
+
+// Editor state that is remembered per map between sessions, in saves/<map>/session.json.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct MapSession {
+    pub timeline_zoom: f64,
+    // Missing from sessions written before selection sets existed.
+    #[serde(default)]
+    pub selection_sets: Vec<SelectionSet>,
+}
+
+// Named left / right selections of one difficulty, stored as object start times so they
+// survive edits that shift indices.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct SelectionSet {
+    pub difficulty: String,
+    pub name: String,
+    pub left_times_ms: Vec<f64>,
+    pub right_times_ms: Vec<f64>,
+}
+
+fn map_session_path(map_dir_name: &str) -> PathBuf {
+    Path::new("saves").join(map_dir_name).join("session.json")
+}
+
+pub fn read_map_session(map_dir_name: &str) -> Option<MapSession> {
+    let path = map_session_path(map_dir_name);
+    if !path.exists() {
+        return None;
+    }
+    let value = read_json_value(&path)?;
+    match serde_json::from_value::<MapSession>(value) {
+        Ok(session) => Some(session),
+        Err(err) => {
+            println!("Failed to parse {}: {}", path.display(), err);
             None
         }
+    }
+}
+
+pub fn write_map_session(map_dir_name: &str, session: &MapSession) -> bool {
+    let path = map_session_path(map_dir_name);
+    let json = match serde_json::to_string_pretty(session) {
+        Ok(json) => json,
+        Err(err) => {
+            println!("Failed to serialize session: {}", err);
+            return false;
+        }
+    };
+    match write_bytes_to_file(&path, json.as_bytes()) {
+        Ok(()) => true,
+        Err(err) => {
+            println!("Failed to write {}: {}", path.display(), err);
+            false
+        }
+    }
+}
+
+// A map and difficulty that was opened, kept newest first in recent.json next to config.json.
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+pub struct RecentMap {
+    pub map_dir_name: String,
+    pub difficulty: String,
+}
+
+const RECENT_MAPS_PATH: &str = "recent.json";
+const MAX_RECENT_MAPS: usize = 10;
+
+// Maps deleted from saves/ since they were opened are left out.
+pub fn read_recent_maps() -> Vec<RecentMap> {
+    let path = Path::new(RECENT_MAPS_PATH);
+    if !path.exists() {
+        return Vec::new();
+    }
+    let Some(value) = read_json_value(path) else {
+        return Vec::new();
+    };
+    match serde_json::from_value::<Vec<RecentMap>>(value) {
+        Ok(recent) => recent
+            .into_iter()
+            .filter(|entry| Path::new("saves").join(&entry.map_dir_name).is_dir())
+            .collect(),
+        Err(err) => {
+            println!("Failed to parse {}: {}", RECENT_MAPS_PATH, err);
+            Vec::new()
+        }
+    }
+}
+
+pub fn record_recent_map(map_dir_name: &str, difficulty: &str) {
+    let entry = RecentMap {
+        map_dir_name: map_dir_name.to_string(),
+        difficulty: difficulty.to_string(),
+    };
+    let mut recent = read_recent_maps();
+    recent.retain(|existing| *existing != entry);
+    recent.insert(0, entry);
+    recent.truncate(MAX_RECENT_MAPS);
+    let json = match serde_json::to_string_pretty(&recent) {
+        Ok(json) => json,
+        Err(err) => {
+            println!("Failed to serialize recent maps: {}", err);
+            return;
+        }
+    };
+    if let Err(err) = write_bytes_to_file(Path::new(RECENT_MAPS_PATH), json.as_bytes()) {
+        println!("Failed to write {}: {}", RECENT_MAPS_PATH, err);
+    }
+}
+
+// Created next to config.json once the first-run overlay has been dismissed.
+const ONBOARDING_SEEN_PATH: &str = "onboarding_seen";
+
+pub fn onboarding_seen() -> bool {
+    Path::new(ONBOARDING_SEEN_PATH).exists()
+}
+
+pub fn record_onboarding_seen() {
+    if let Err(err) = write_bytes_to_file(Path::new(ONBOARDING_SEEN_PATH), b"") {
+        println!("Failed to write {}: {}", ONBOARDING_SEEN_PATH, err);
+    }
+}
+
+// Objects are merged key by key, anything else in `overrides` replaces the base value.
+fn merge_json(base: &mut serde_json::Value, overrides: serde_json::Value) {
+    match (base, overrides) {
+        (serde_json::Value::Object(base), serde_json::Value::Object(overrides)) => {
+            for (key, value) in overrides {
+                match base.get_mut(&key) {
+                    Some(existing) => merge_json(existing, value),
+                    None => println!("Ignoring unknown config override {}.", key),
+                }
+            }
+        }
+        (base, overrides) => *base = overrides,
+    }
+}
+
+// Global config with the map's saves/<map>/config_overrides.json merged over it, if present.
+// The overrides file holds any subset of config.json, e.g. {"general": {"playfield_scale": 0.7}}.
+pub fn get_config_for_map(map_dir_name: &str) -> Option<Config> {
+    let overrides_path = config_overrides_path(map_dir_name);
+    let mut config_json = read_json_value(Path::new("config.json"))?;
+    if overrides_path.exists() {
+        println!("Applying config overrides from {}", overrides_path.display());
+        if let Some(overrides) = read_json_value(&overrides_path) {
+            merge_json(&mut config_json, overrides);
+        }
+    }
+    let mut config = match serde_json::from_value::<Config>(config_json) {
+        Ok(config) => config,
+        Err(err) => {
+            println!("Failed to parse config.json: {}", err);
+            return None;
+        }
+    };
+    let preset_name = &config.appearance.general.color_preset;
+    match ColorPreset::from_name(preset_name) {
+        Some(preset) => preset.apply(&mut config.appearance.colors),
+        None => println!(
+            "Unknown color preset {}, expected one of {}.",
+            preset_name,
+            COLOR_PRESET_NAMES.join(", ")
+        ),
+    }
+    let view_preset_names = std::iter::once(&config.appearance.general.view_preset)
+        .chain(config.appearance.general.realtime_approach_view_presets.iter());
+    for view_preset_name in view_preset_names {
+        if ViewPreset::from_name(view_preset_name).is_none() {
+            println!(
+                "Unknown view preset {}, expected one of {}.",
+                view_preset_name,
+                VIEW_PRESET_NAMES.join(", ")
+            );
+        }
+    }
+    let convert_preview_name = &config.appearance.general.convert_preview;
+    if ConvertMode::from_name(convert_preview_name).is_none() {
+        println!(
+            "Unknown convert preview {}, expected one of {}.",
+            convert_preview_name,
+            CONVERT_MODE_NAMES.join(", ")
+        );
+    }
+    Some(config)
+}
+
+const CONFIG_BACKUPS_PATH: &str = "backups/config";
+
+// Copies config.json to backups/config/config_<unix ms>.json and removes all but the newest
+// `backups_to_keep` copies.
+pub fn backup_config_json(backups_to_keep: usize) {
+    if backups_to_keep == 0 {
+        return;
+    }
+    let bytes = match fs::read("config.json") {
+        Ok(bytes) => bytes,
+        Err(err) => {
+            println!("Failed to read config.json for a backup: {}", err);
+            return;
+        }
+    };
+    let now_ms = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or(0);
+    let backups_path = Path::new(CONFIG_BACKUPS_PATH);
+    let backup_path = backups_path.join(format!("config_{}.json", now_ms));
+    if let Err(err) = write_bytes_to_file(&backup_path, &bytes) {
+        println!("Failed to write {}: {}", backup_path.display(), err);
+        return;
+    }
+
+    let backup_time = |name: &String| -> u128 {
+        name.trim_start_matches("config_")
+            .trim_end_matches(".json")
+            .parse()
+            .unwrap_or(0)
+    };
+    let mut backups = scan_folder(backups_path, Some(false), Some(&vec![".json"]));
+    backups.sort_by_key(backup_time);
+    let excess = backups.len().saturating_sub(backups_to_keep);
+    for name in backups.iter().take(excess) {
+        if let Err(err) = fs::remove_file(backups_path.join(name)) {
+            println!("Failed to remove old config backup {}: {}", name, err);
+        }
+    }
+}
+
+fn config_overrides_path(map_dir_name: &str) -> PathBuf {
+    Path::new("saves")
+        .join(map_dir_name)
+        .join("config_overrides.json")
+}
+
+// Only appearance.layout is written, the rest keeps its values (serde_json sorts the keys). It
+// goes to the map's config_overrides.json if it has one, since the layout being edited is the
+// map's, else to config.json. Returns the file written.
+pub fn write_layout_config(
+    map_dir_name: &str,
+    layout: &AppearanceLayoutConfig,
+    backups_to_keep: usize,
+) -> Option<PathBuf> {
+    let overrides_path = config_overrides_path(map_dir_name);
+    let path = if overrides_path.exists() {
+        overrides_path
+    } else {
+        PathBuf::from("config.json")
+    };
+    let mut config_json = read_json_value(&path)?;
+    let layout_json = match serde_json::to_value(layout) {
+        Ok(value) => value,
+        Err(err) => {
+            println!("Failed to serialize the layout: {}", err);
+            return None;
+        }
+    };
+    let Some(config_object) = config_json.as_object_mut() else {
+        println!("{} is not a JSON object.", path.display());
+        return None;
+    };
+    let is_config_json = path == Path::new("config.json");
+    // Overrides may leave out the appearance section, config.json may not.
+    if !config_object.contains_key("appearance") {
+        if is_config_json {
+            println!("config.json has no appearance section.");
+            return None;
+        }
+        config_object.insert("appearance".to_string(), serde_json::json!({}));
+    }
+    let Some(appearance) = config_object
+        .get_mut("appearance")
+        .and_then(|appearance| appearance.as_object_mut())
+    else {
+        println!("The appearance section of {} is not a JSON object.", path.display());
+        return None;
+    };
+    appearance.insert("layout".to_string(), layout_json);
+    let json = match serde_json::to_string_pretty(&config_json) {
+        Ok(json) => json,
+        Err(err) => {
+            println!("Failed to serialize {}: {}", path.display(), err);
+            return None;
+        }
     };
+    if is_config_json {
+        backup_config_json(backups_to_keep);
+    }
+    match write_bytes_to_file(&path, json.as_bytes()) {
+        Ok(()) => Some(path),
+        Err(err) => {
+            println!("Failed to write {}: {}", path.display(), err);
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn failed_writes_keep_the_old_file_and_can_be_retried() {
+        let failed_writes = FailedWrites::new();
+        let dir = std::env::temp_dir().join(format!("osu-editor-write-{}", std::process::id()));
+        let path = dir.join("beatmap.json");
+        failed_writes.write(&path, b"old").unwrap();
+
+        let mut permissions = std::fs::metadata(&path).unwrap().permissions();
+        permissions.set_readonly(true);
+        std::fs::set_permissions(&path, permissions.clone()).unwrap();
+        assert!(failed_writes.write(&path, b"new").is_err());
+        assert_eq!(std::fs::read(&path).unwrap(), b"old");
+        assert_eq!(failed_writes.list().len(), 1);
+        let hint = failed_writes_hint(&failed_writes.list()).unwrap();
+        assert!(hint.len() <= 96 && hint.ends_with("| ctrl s retries"), "{hint}");
+
+        #[allow(clippy::permissions_set_readonly_false)]
+        permissions.set_readonly(false);
+        std::fs::set_permissions(&path, permissions).unwrap();
+        assert_eq!(failed_writes.retry(), 0);
+        assert_eq!(std::fs::read(&path).unwrap(), b"new");
+        assert!(failed_writes.list().is_empty());
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn taken_asset_names_get_a_number_unless_the_bytes_match() {
+        let mut assets = AssetsFolder::default();
+        assets.insert("bg.jpg".to_string(), vec![1]);
+        assets.insert("bg_2.jpg".to_string(), vec![2]);
+        assert_eq!(assets.free_name_for("bg.jpg", &[1]), "bg.jpg");
+        assert_eq!(assets.free_name_for("BG.jpg", &[3]), "BG_3.jpg");
+        assert_eq!(assets.free_name_for("cover.png", &[3]), "cover.png");
+    }
+
+    #[test]
+    fn only_paths_inside_the_folder_are_enclosed() {
+        assert!(is_enclosed_path("skin/hitcircle.png"));
+        assert!(is_enclosed_path("sb/bg 1.jpg"));
+        let outside = ["", "../x", "skin/../../x", "/etc/passwd", "C:\\x", "a\\..\\x", "./a"];
+        for path in outside {
+            assert!(!is_enclosed_path(path), "{path}");
+        }
+    }
 }
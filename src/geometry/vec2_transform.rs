@@ -42,6 +42,27 @@ impl Vec2Transform {
         }
     }
 
+    // Scales by `along` in the direction of `axis` and by `across` perpendicular to it.
+    pub fn scale_along_axis(axis: Vec2, along: f64, across: f64) -> Self {
+        let u = axis * (1.0 / axis.len());
+        let v = Vec2 { x: -u.y, y: u.x };
+        Vec2Transform {
+            matrix: [
+                [
+                    along * u.x * u.x + across * v.x * v.x,
+                    along * u.x * u.y + across * v.x * v.y,
+                    0.0,
+                ],
+                [
+                    along * u.x * u.y + across * v.x * v.y,
+                    along * u.y * u.y + across * v.y * v.y,
+                    0.0,
+                ],
+                [0.0, 0.0, 1.0],
+            ],
+        }
+    }
+
     fn flip_around_axis(dir: Vec2) -> Self {
         let r2 = dir.len2();
         Vec2Transform {
@@ -7,27 +7,77 @@ use winit::dpi::PhysicalSize;
 use winit::window::Window;
 
 use crate::config::Config;
+use crate::dotosu::osr_file::OsrFile;
 use crate::geometry::vec2::Vec2;
-use crate::layout;
+use crate::layout::{self, HudLayout};
+use crate::render::{FadeEnvelope, ViewPreset, ghost_window_ms, past_window_ms};
 use crate::map_format::colors::Color;
+use crate::map_format::statistics::{EditingTotals, format_active_time};
+use crate::map_format::timing::{SNAP_DIVISORS, Timing};
+use crate::onboarding::{MAX_ONBOARDING_REGIONS, ONBOARDING_DISMISS_HINT, OnboardingRegion};
+use crate::report_panel::{MAX_REPORT_LINE_CHARS, MAX_REPORT_LINES};
 use crate::skin::{Skin, Texture, load_texture};
-use crate::state::Object;
+use crate::state::{
+    ConvertPreview, Object, ReplayJudgement, TimelineDragMarks, auto_cursor_trail,
+    find_flow_transitions, replay_cursor_trail,
+};
+use crate::time_formats::time_formats;
+use crate::timeline_view::top_timeline_window_ms;
 use crate::treap::Treap;
 
 use super::msaa;
 use super::textures;
-use super::timeline::calculate_timeline_points_and_boxes;
+use super::timeline::{TimelinePointsCache, calculate_timeline_points_and_boxes};
 pub use super::types::ObjectInstance;
 use super::types::{
-    CircleGpu, DigitsMeta, Globals, INITIAL_SLIDER_BOXES_CAPACITY, INITIAL_SLIDER_SEGS_CAPACITY,
-    MAX_BOOKMARKS, MAX_BREAK_INTERVALS, MAX_CIRCLES, MAX_KIAI_INTERVALS, MAX_RED_LINES,
-    MAX_SNAP_MARKERS, MAX_TIMELINE_MARKS, MAX_TIMELINE_SNAKES, MAX_TIMELINE_X_BOXES, SkinMeta,
-    SliderBoxGpu, SliderSegGpu, TimelinePointGpu, TimelineXBoxGpu,
+    AUTO_CURSOR_TRAIL_SAMPLES, CircleGpu, ConvertNoteGpu, DigitsMeta, FLOW_SEGMENT_ANTI_FLOW,
+    FLOW_SEGMENT_HAS_ANGLE, FLOW_SEGMENT_REPLAY_HIT, FLOW_SEGMENT_REPLAY_MISS, FLOW_SEGMENT_SHARP,
+    FLOW_SEGMENT_UNSNAPPED,
+    FRAME_GRAPH_SAMPLES, FlowSegmentGpu, Globals,
+    INITIAL_CIRCLES_CAPACITY, INITIAL_SLIDER_BOXES_CAPACITY, INITIAL_SLIDER_SEGS_CAPACITY,
+    MAX_BOOKMARKS, MAX_BREAK_INTERVALS, MAX_CIRCLES, MAX_CONVERT_NOTES, MAX_FLOW_SEGMENTS,
+    MAX_KIAI_INTERVALS, MAX_RED_LINES, MAX_SNAP_MARKERS, MAX_TIMELINE_DRAG_MARKS,
+    MAX_TIMELINE_MARKS, MAX_TIMELINE_SNAKES,
+    MAX_TIMELINE_X_BOXES, POTATO_MAX_TIMELINE_POINTS, REPLAY_JUDGEMENT_VISIBLE_MS, SkinMeta, SliderBoxGpu, SliderSegGpu,
+    TimelinePointGpu, TimelineXBoxGpu,
 };
 
+// Everything that has to be rebuilt when the MSAA sample count changes.
+struct ScenePipelines {
+    background_pipeline: wgpu::RenderPipeline,
+    overlay_pipeline: wgpu::RenderPipeline,
+    hud_pipeline: wgpu::RenderPipeline,
+    timeline_kiai_pipeline: wgpu::RenderPipeline,
+    timeline_break_pipeline: wgpu::RenderPipeline,
+    timeline_bookmark_pipeline: wgpu::RenderPipeline,
+    timeline_slider_pipeline: wgpu::RenderPipeline,
+    sliders_pipeline: wgpu::RenderPipeline,
+    slider_caps_pipeline: wgpu::RenderPipeline,
+    circles_pipeline: wgpu::RenderPipeline,
+}
+
+struct ScenePipelineLayouts {
+    background: wgpu::PipelineLayout,
+    overlay: wgpu::PipelineLayout,
+    circles: wgpu::PipelineLayout,
+    sliders: wgpu::PipelineLayout,
+    timeline: wgpu::PipelineLayout,
+    timeline_slider: wgpu::PipelineLayout,
+}
+
+// Headless renderers (tests) draw into an offscreen texture instead of a window surface.
+enum RenderTarget {
+    Window {
+        _window: Arc<Window>,
+        surface: wgpu::Surface<'static>,
+    },
+    Offscreen {
+        texture: wgpu::Texture,
+    },
+}
+
 pub struct GpuRenderer {
-    _window: Arc<Window>,
-    surface: wgpu::Surface<'static>,
+    target: RenderTarget,
     device: wgpu::Device,
     queue: wgpu::Queue,
     config: wgpu::SurfaceConfiguration,
@@ -77,10 +127,14 @@ pub struct GpuRenderer {
     _digits_texture_view: wgpu::TextureView,
     _digits_meta_buffer: wgpu::Buffer,
     _skin_meta_buffer: wgpu::Buffer,
+    texture_bind_group_layout: wgpu::BindGroupLayout,
     texture_bind_group: wgpu::BindGroup,
     msaa_samples: u32,
+    supported_msaa_samples: Vec<u32>,
     msaa_color: Option<wgpu::Texture>,
     msaa_color_view: Option<wgpu::TextureView>,
+    shader: wgpu::ShaderModule,
+    pipeline_layouts: ScenePipelineLayouts,
 
     timeline_kiai_buffer: wgpu::Buffer,
     timeline_kiai_bind_group: wgpu::BindGroup,
@@ -94,8 +148,11 @@ pub struct GpuRenderer {
     timeline_x_boxes_buffer: wgpu::Buffer,
     timeline_x_boxes_capacity: usize,
     timeline_x_boxes_bind_group: wgpu::BindGroup,
+    timeline_points_cache: TimelinePointsCache,
     snap_markers_buffer: wgpu::Buffer,
     snap_markers_capacity: usize,
+    flow_segments_buffer: wgpu::Buffer,
+    convert_notes_buffer: wgpu::Buffer,
     snap_markers_bind_group: wgpu::BindGroup,
 
     slider_segs_buffer: wgpu::Buffer,
@@ -105,7 +162,10 @@ pub struct GpuRenderer {
     slider_draw_indices_buffer: wgpu::Buffer,
     slider_draw_indices_capacity: usize,
     slider_bind_group: wgpu::BindGroup,
+    // 0 = full quality, see `SliderQualityController`.
+    slider_quality_reduction: usize,
     objects_buffer: wgpu::Buffer,
+    objects_capacity: usize,
     objects_bind_group: wgpu::BindGroup,
     objects_upload: Vec<CircleGpu>,
     size: PhysicalSize<u32>,
@@ -113,6 +173,8 @@ pub struct GpuRenderer {
     gpu_pass_x10: u32,
     cpu_pass_history: VecDeque<(Instant, u32)>,
     gpu_pass_history: VecDeque<(Instant, u32)>,
+    // Unsmoothed (cpu_pass_x10, gpu_pass_x10) of the last frames, for the frame-time graph.
+    frame_graph: VecDeque<(u32, u32)>,
 }
 
 impl GpuRenderer {
@@ -133,6 +195,28 @@ impl GpuRenderer {
         history.iter().map(|(_, value)| *value).max().unwrap_or(0)
     }
 
+    // What the HUD shaders draw for `ch`. The font is ASCII only, anything else (CJK typed
+    // through the IME, accented letters) is drawn as an empty box so it still takes a place.
+    fn hud_char_code(ch: char) -> u32 {
+        // Code 127 (DEL) never shows up in text, `glyph5x7_row_bits` draws it as the box.
+        const MISSING_GLYPH: u32 = 127;
+        if ch.is_ascii() {
+            ch as u32
+        } else {
+            MISSING_GLYPH
+        }
+    }
+
+    // Up to 16 chars, 4 per u32, zero after the end. Non-ASCII chars show as a box.
+    fn pack_ascii16(text: &str) -> [u32; 4] {
+        let mut packed = [0u32; 4];
+        for (i, ch) in text.chars().take(16).enumerate() {
+            let code = Self::hud_char_code(ch);
+            packed[i / 4] |= code << ((i % 4) * 8);
+        }
+        packed
+    }
+
     fn upload_texture_2d_srgb(
         device: &wgpu::Device,
         queue: &wgpu::Queue,
@@ -153,16 +237,182 @@ impl GpuRenderer {
         textures::upload_texture_2d_array_srgb(device, queue, label, frames, pad_to_nominal)
     }
 
-    fn normalize_msaa_samples(samples: u32) -> u32 {
-        msaa::normalize_msaa_samples(samples)
+    fn create_render_pipeline(
+        device: &wgpu::Device,
+        shader: &wgpu::ShaderModule,
+        layout: &wgpu::PipelineLayout,
+        label: &'static str,
+        vs_entry_point: &'static str,
+        fs_entry_point: &'static str,
+        format: wgpu::TextureFormat,
+        msaa_samples: u32,
+    ) -> wgpu::RenderPipeline {
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some(label),
+            layout: Some(layout),
+            vertex: wgpu::VertexState {
+                module: shader,
+                entry_point: Some(vs_entry_point),
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: shader,
+                entry_point: Some(fs_entry_point),
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                ..Default::default()
+            },
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState {
+                count: msaa_samples,
+                ..Default::default()
+            },
+            multiview_mask: None,
+            cache: None,
+        })
     }
 
-    fn select_supported_msaa_samples(
-        adapter: &wgpu::Adapter,
+    fn create_scene_pipelines(
+        device: &wgpu::Device,
+        shader: &wgpu::ShaderModule,
+        layouts: &ScenePipelineLayouts,
         format: wgpu::TextureFormat,
-        requested: u32,
-    ) -> u32 {
-        msaa::select_supported_msaa_samples(adapter, format, requested)
+        msaa_samples: u32,
+    ) -> ScenePipelines {
+        ScenePipelines {
+            background_pipeline: Self::create_render_pipeline(
+                device,
+                shader,
+                &layouts.background,
+                "background pipeline",
+                "vs_bg",
+                "fs_bg",
+                format,
+                msaa_samples,
+            ),
+            overlay_pipeline: Self::create_render_pipeline(
+                device,
+                shader,
+                &layouts.overlay,
+                "overlay pipeline",
+                "vs_overlay",
+                "fs_overlay",
+                format,
+                msaa_samples,
+            ),
+            hud_pipeline: Self::create_render_pipeline(
+                device,
+                shader,
+                &layouts.overlay,
+                "hud pipeline",
+                "vs_hud",
+                "fs_hud",
+                format,
+                msaa_samples,
+            ),
+            timeline_kiai_pipeline: Self::create_render_pipeline(
+                device,
+                shader,
+                &layouts.timeline,
+                "timeline kiai pipeline",
+                "vs_hud",
+                "fs_timeline_kiai",
+                format,
+                msaa_samples,
+            ),
+            timeline_break_pipeline: Self::create_render_pipeline(
+                device,
+                shader,
+                &layouts.timeline,
+                "timeline break pipeline",
+                "vs_hud",
+                "fs_timeline_break",
+                format,
+                msaa_samples,
+            ),
+            timeline_bookmark_pipeline: Self::create_render_pipeline(
+                device,
+                shader,
+                &layouts.timeline,
+                "timeline bookmark pipeline",
+                "vs_hud",
+                "fs_timeline_bookmarks",
+                format,
+                msaa_samples,
+            ),
+            timeline_slider_pipeline: Self::create_render_pipeline(
+                device,
+                shader,
+                &layouts.timeline_slider,
+                "timeline slider pipeline",
+                "vs_timeline_slider_boxes",
+                "fs_timeline_slider_boxes",
+                format,
+                msaa_samples,
+            ),
+            sliders_pipeline: Self::create_render_pipeline(
+                device,
+                shader,
+                &layouts.sliders,
+                "sliders body pipeline",
+                "vs_slider_box",
+                "fs_slider_box",
+                format,
+                msaa_samples,
+            ),
+            slider_caps_pipeline: Self::create_render_pipeline(
+                device,
+                shader,
+                &layouts.sliders,
+                "slider caps pipeline",
+                "vs_slider_caps",
+                "fs_slider_caps",
+                format,
+                msaa_samples,
+            ),
+            circles_pipeline: Self::create_render_pipeline(
+                device,
+                shader,
+                &layouts.circles,
+                "circles pipeline",
+                "vs_main",
+                "fs_main",
+                format,
+                msaa_samples,
+            ),
+        }
+    }
+
+    fn select_supported_msaa_samples(supported: &[u32], requested: u32) -> u32 {
+        msaa::select_supported_msaa_samples(supported, requested)
+    }
+
+    fn create_offscreen_texture(
+        device: &wgpu::Device,
+        config: &wgpu::SurfaceConfiguration,
+    ) -> wgpu::Texture {
+        device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("offscreen target"),
+            size: wgpu::Extent3d {
+                width: config.width,
+                height: config.height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: config.format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        })
     }
 
     fn create_msaa_target(
@@ -173,6 +423,112 @@ impl GpuRenderer {
         msaa::create_msaa_target(device, surface_config, samples)
     }
 
+    fn create_texture_bind_group(
+        device: &wgpu::Device,
+        texture_bind_group_layout: &wgpu::BindGroupLayout,
+        demo_sampler: &wgpu::Sampler,
+        demo_texture_view: &wgpu::TextureView,
+        demo_overlay_texture_view: &wgpu::TextureView,
+        slidercircle_texture_view: &wgpu::TextureView,
+        slidercircleoverlay_texture_view: &wgpu::TextureView,
+        approachcircle_texture_view: &wgpu::TextureView,
+        digits_texture_view: &wgpu::TextureView,
+        digits_meta_buffer: &wgpu::Buffer,
+        background_texture_view: &wgpu::TextureView,
+        skin_meta_buffer: &wgpu::Buffer,
+        sliderendcircle_texture_view: &wgpu::TextureView,
+        sliderendcircleoverlay_texture_view: &wgpu::TextureView,
+        reverse_arrow_texture_view: &wgpu::TextureView,
+        slider_ball_texture_view: &wgpu::TextureView,
+        slider_follow_circle_texture_view: &wgpu::TextureView,
+        loading_texture_view: &wgpu::TextureView,
+        break_texture_view: &wgpu::TextureView,
+        spinner_texture_view: &wgpu::TextureView,
+    ) -> wgpu::BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("texture bind group"),
+            layout: texture_bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::Sampler(demo_sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(demo_texture_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::TextureView(demo_overlay_texture_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: wgpu::BindingResource::TextureView(slidercircle_texture_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 4,
+                    resource: wgpu::BindingResource::TextureView(slidercircleoverlay_texture_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 5,
+                    resource: wgpu::BindingResource::TextureView(approachcircle_texture_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 6,
+                    resource: wgpu::BindingResource::TextureView(digits_texture_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 7,
+                    resource: digits_meta_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 8,
+                    resource: wgpu::BindingResource::TextureView(background_texture_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 9,
+                    resource: skin_meta_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 10,
+                    resource: wgpu::BindingResource::TextureView(sliderendcircle_texture_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 11,
+                    resource: wgpu::BindingResource::TextureView(
+                        sliderendcircleoverlay_texture_view,
+                    ),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 12,
+                    resource: wgpu::BindingResource::TextureView(reverse_arrow_texture_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 13,
+                    resource: wgpu::BindingResource::TextureView(slider_ball_texture_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 14,
+                    resource: wgpu::BindingResource::TextureView(
+                        slider_follow_circle_texture_view,
+                    ),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 15,
+                    resource: wgpu::BindingResource::TextureView(loading_texture_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 16,
+                    resource: wgpu::BindingResource::TextureView(break_texture_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 17,
+                    resource: wgpu::BindingResource::TextureView(spinner_texture_view),
+                },
+            ],
+        })
+    }
+
     pub fn new(
         window: Arc<Window>,
         editor_config: Config,
@@ -185,23 +541,56 @@ impl GpuRenderer {
         // SAFETY: wgpu requires the window handle outlive the surface.
         // We keep an `Arc<Window>` inside `GpuRenderer` to guarantee that.
         let surface = instance.create_surface(window.clone())?;
+        Self::with_target(
+            instance,
+            Some((window, surface)),
+            size,
+            editor_config,
+            skin,
+            background,
+        )
+    }
+
+    // Renders into an offscreen texture of the given size, no window or surface needed.
+    #[cfg(test)]
+    pub fn new_headless(
+        size: PhysicalSize<u32>,
+        editor_config: Config,
+        skin: Skin,
+        background: Texture,
+    ) -> anyhow::Result<Self> {
+        Self::with_target(
+            wgpu::Instance::default(),
+            None,
+            size,
+            editor_config,
+            skin,
+            background,
+        )
+    }
 
+    fn with_target(
+        instance: wgpu::Instance,
+        window_surface: Option<(Arc<Window>, wgpu::Surface<'static>)>,
+        size: PhysicalSize<u32>,
+        editor_config: Config,
+        skin: Skin,
+        background: Texture,
+    ) -> anyhow::Result<Self> {
         let adapter = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
             power_preference: wgpu::PowerPreference::HighPerformance,
-            compatible_surface: Some(&surface),
+            compatible_surface: window_surface.as_ref().map(|(_, surface)| surface),
             force_fallback_adapter: false,
         }))
         .map_err(|e| anyhow::anyhow!("request_adapter failed: {e}"))?;
 
-        let requested_msaa = Self::normalize_msaa_samples(editor_config.performance.msaa_samples);
-        let wants_adapter_specific_msaa = requested_msaa != 1 && requested_msaa != 4;
+        // Always enable adapter-specific formats when available so 2x/8x can be picked at runtime.
+        let adapter_specific_msaa = adapter
+            .features()
+            .contains(wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES);
 
         let mut required_features = wgpu::Features::empty();
-        if wants_adapter_specific_msaa
-            && adapter
-                .features()
-                .contains(wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES)
-        {
+        if adapter_specific_msaa {
             required_features |= wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES;
         }
 
@@ -215,41 +604,51 @@ impl GpuRenderer {
                 experimental_features: wgpu::ExperimentalFeatures::default(),
             }))?;
 
-        let surface_caps = surface.get_capabilities(&adapter);
-        let format = surface_caps
-            .formats
-            .iter()
-            .copied()
-            .find(|f| f.is_srgb())
-            .unwrap_or(surface_caps.formats[0]);
-
-        let present_mode = if editor_config.performance.prefer_vrr {
-            if surface_caps
-                .present_modes
-                .contains(&wgpu::PresentMode::Mailbox)
-            {
-                wgpu::PresentMode::Mailbox
-            } else {
-                // FIFO is always supported and VRR-friendly when available through compositor/driver.
-                wgpu::PresentMode::Fifo
+        let (format, present_mode, alpha_mode) = match &window_surface {
+            Some((_, surface)) => {
+                let surface_caps = surface.get_capabilities(&adapter);
+                let format = surface_caps
+                    .formats
+                    .iter()
+                    .copied()
+                    .find(|f| f.is_srgb())
+                    .unwrap_or(surface_caps.formats[0]);
+
+                let present_mode = if editor_config.performance.prefer_vrr {
+                    if surface_caps
+                        .present_modes
+                        .contains(&wgpu::PresentMode::Mailbox)
+                    {
+                        wgpu::PresentMode::Mailbox
+                    } else {
+                        // FIFO is always supported and VRR-friendly when available through
+                        // compositor/driver.
+                        wgpu::PresentMode::Fifo
+                    }
+                } else if surface_caps
+                    .present_modes
+                    .contains(&wgpu::PresentMode::Immediate)
+                {
+                    wgpu::PresentMode::Immediate
+                } else if surface_caps
+                    .present_modes
+                    .contains(&wgpu::PresentMode::Mailbox)
+                {
+                    wgpu::PresentMode::Mailbox
+                } else {
+                    // FIFO is always supported; slightly higher latency but consistent.
+                    wgpu::PresentMode::Fifo
+                };
+
+                (format, present_mode, surface_caps.alpha_modes[0])
             }
-        } else if surface_caps
-            .present_modes
-            .contains(&wgpu::PresentMode::Immediate)
-        {
-            wgpu::PresentMode::Immediate
-        } else if surface_caps
-            .present_modes
-            .contains(&wgpu::PresentMode::Mailbox)
-        {
-            wgpu::PresentMode::Mailbox
-        } else {
-            // FIFO is always supported; slightly higher latency but consistent.
-            wgpu::PresentMode::Fifo
+            None => (
+                wgpu::TextureFormat::Rgba8UnormSrgb,
+                wgpu::PresentMode::Fifo,
+                wgpu::CompositeAlphaMode::Opaque,
+            ),
         };
 
-        let alpha_mode = surface_caps.alpha_modes[0];
-
         let config = wgpu::SurfaceConfiguration {
             usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
             format,
@@ -261,11 +660,23 @@ impl GpuRenderer {
             desired_maximum_frame_latency: 2,
         };
 
-        surface.configure(&device, &config);
+        let target = match window_surface {
+            Some((window, surface)) => {
+                surface.configure(&device, &config);
+                RenderTarget::Window {
+                    _window: window,
+                    surface,
+                }
+            }
+            None => RenderTarget::Offscreen {
+                texture: Self::create_offscreen_texture(&device, &config),
+            },
+        };
 
+        let supported_msaa_samples =
+            msaa::supported_msaa_samples(&adapter, config.format, adapter_specific_msaa);
         let msaa_samples = Self::select_supported_msaa_samples(
-            &adapter,
-            config.format,
+            &supported_msaa_samples,
             editor_config.performance.msaa_samples,
         );
         let (msaa_color, msaa_color_view) =
@@ -296,15 +707,7 @@ impl GpuRenderer {
             config.width as f64,
             config.height as f64,
             playfield_scale,
-            editor_config.appearance.layout.timeline_height_percent,
-            editor_config
-                .appearance
-                .layout
-                .timeline_second_box_width_percent,
-            editor_config
-                .appearance
-                .layout
-                .timeline_third_box_width_percent,
+            &HudLayout::from_config(&editor_config.appearance.layout),
         );
 
         let timeline_rect = initial_layout.timeline_rect.to_f32_array();
@@ -1140,6 +1543,47 @@ impl GpuRenderer {
             _timeline_past_pad: [0.0, 0.0, 0.0],
             timeline_past_tint_rgba: [0.0, 0.0, 0.0, 0.0],
             timeline_past_object_tint_rgba: [0.0, 0.0, 0.0, 0.0],
+            object_fade_ms: [0.0, 250.0, 0.0, 0.0],
+            tooltip_meta: [0, 0, 0, 0],
+            tooltip_anchor: [0.0, 0.0, 0.0, 0.0],
+            tooltip_text_packed: [[0, 0, 0, 0]; 4],
+            auto_cursor_rgba: [0.0, 0.0, 0.0, 0.0],
+            auto_cursor_style: [0.0, 0.0, 0.0, 0.0],
+            flow_meta: [0, 0, 0, 0],
+            flow_style: [0.0, 0.0, 0.0, 0.0],
+            flow_line_rgba: [0.0, 0.0, 0.0, 0.0],
+            flow_sharp_rgba: [0.0, 0.0, 0.0, 0.0],
+            flow_anti_flow_rgba: [0.0, 0.0, 0.0, 0.0],
+            view_meta: [0, 0, 0, 0],
+            frame_graph_meta: [0, 0, 0, 0],
+            frame_graph_packed: [[0, 0, 0, 0]; 16],
+            convert_meta: [0, 0, 0, 0],
+            convert_rect: [0.0, 0.0, 0.0, 0.0],
+            hit_windows_ms: [0.0, 0.0, 0.0, 0.0],
+            hit_window_300_rgba: [0.0, 0.0, 0.0, 0.0],
+            hit_window_100_rgba: [0.0, 0.0, 0.0, 0.0],
+            hit_window_50_rgba: [0.0, 0.0, 0.0, 0.0],
+            kiai_effect_meta: [-1.0, 0.0, 0.0, 0.0],
+            kiai_effect_rgba: [0.0, 0.0, 0.0, 0.0],
+            visible_area_rect: [0.0, 0.0, 0.0, 0.0],
+            ghost_meta: [0.0, 0.0, 0.0, 0.0],
+            selection_loop_ms: [0.0, 0.0, 0.0, 0.0],
+            selection_loop_rgba: [0.0, 0.0, 0.0, 0.0],
+            replay_hit_rgba: [0.0, 0.0, 0.0, 0.0],
+            replay_miss_rgba: [0.0, 0.0, 0.0, 0.0],
+            unsnapped_object_rgba: [0.0, 0.0, 0.0, 0.0],
+            stats_time_text_packed: [[0, 0, 0, 0]; 18],
+            hint_text_packed: [[0, 0, 0, 0]; 6],
+            onboarding_meta: [0, 0, 0, 0],
+            onboarding_rects: [[0.0, 0.0, 0.0, 0.0]; 4],
+            onboarding_text_packed: [[0, 0, 0, 0]; 8],
+            bottom_timeline_ms: [0.0, 0.0, 0.0, 0.0],
+            past_object_meta: [0.0, 0.0, 0.0, 0.0],
+            past_object_tint_rgba: [0.0, 0.0, 0.0, 0.0],
+            report_meta: [0, 0, 0, 0],
+            report_text_packed: [[0, 0, 0, 0]; 24],
+            timeline_drag_meta: [0, 0, 0, 0],
+            timeline_drag_marks_ms: [[0.0, 0.0, 0.0, 0.0]; 4],
             _pad_end: [0.0, 0.0, 0.0, 0.0],
         };
 
@@ -1191,16 +1635,38 @@ impl GpuRenderer {
         let snap_markers_bind_group_layout =
             device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
                 label: Some("snap markers layout"),
-                entries: &[wgpu::BindGroupLayoutEntry {
-                    binding: 4,
-                    visibility: wgpu::ShaderStages::FRAGMENT,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Storage { read_only: true },
-                        has_dynamic_offset: false,
-                        min_binding_size: None,
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 4,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Storage { read_only: true },
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
                     },
-                    count: None,
-                }],
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 5,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Storage { read_only: true },
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 6,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Storage { read_only: true },
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                ],
             });
 
         let timeline_kiai_buffer = device.create_buffer(&wgpu::BufferDescriptor {
@@ -1228,6 +1694,18 @@ impl GpuRenderer {
             contents: bytemuck::cast_slice(snap_markers_init.as_slice()),
             usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
         });
+        let flow_segments_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("flow segments buffer"),
+            size: (MAX_FLOW_SEGMENTS * std::mem::size_of::<FlowSegmentGpu>()) as u64,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let convert_notes_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("convert notes buffer"),
+            size: (MAX_CONVERT_NOTES * std::mem::size_of::<ConvertNoteGpu>()) as u64,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
 
         let timeline_kiai_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
             label: Some("timeline kiai bind group"),
@@ -1324,10 +1802,20 @@ impl GpuRenderer {
         let snap_markers_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
             label: Some("snap markers bind group"),
             layout: &snap_markers_bind_group_layout,
-            entries: &[wgpu::BindGroupEntry {
-                binding: 4,
-                resource: snap_markers_buffer.as_entire_binding(),
-            }],
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 4,
+                    resource: snap_markers_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 5,
+                    resource: flow_segments_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 6,
+                    resource: convert_notes_buffer.as_entire_binding(),
+                },
+            ],
         });
 
         let hitcircle = skin.hit_circle;
@@ -1885,88 +2373,28 @@ impl GpuRenderer {
                 ],
             });
 
-        let texture_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("texture bind group"),
-            layout: &texture_bind_group_layout,
-            entries: &[
-                wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: wgpu::BindingResource::Sampler(&demo_sampler),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 1,
-                    resource: wgpu::BindingResource::TextureView(&demo_texture_view),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 2,
-                    resource: wgpu::BindingResource::TextureView(&demo_overlay_texture_view),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 3,
-                    resource: wgpu::BindingResource::TextureView(&slidercircle_texture_view),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 4,
-                    resource: wgpu::BindingResource::TextureView(&slidercircleoverlay_texture_view),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 5,
-                    resource: wgpu::BindingResource::TextureView(&approachcircle_texture_view),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 6,
-                    resource: wgpu::BindingResource::TextureView(&digits_texture_view),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 7,
-                    resource: digits_meta_buffer.as_entire_binding(),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 8,
-                    resource: wgpu::BindingResource::TextureView(&background_texture_view),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 9,
-                    resource: skin_meta_buffer.as_entire_binding(),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 10,
-                    resource: wgpu::BindingResource::TextureView(&sliderendcircle_texture_view),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 11,
-                    resource: wgpu::BindingResource::TextureView(
-                        &sliderendcircleoverlay_texture_view,
-                    ),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 12,
-                    resource: wgpu::BindingResource::TextureView(&reverse_arrow_texture_view),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 13,
-                    resource: wgpu::BindingResource::TextureView(&slider_ball_texture_view),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 14,
-                    resource: wgpu::BindingResource::TextureView(
-                        &slider_follow_circle_texture_view,
-                    ),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 15,
-                    resource: wgpu::BindingResource::TextureView(&loading_texture_view),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 16,
-                    resource: wgpu::BindingResource::TextureView(&break_texture_view),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 17,
-                    resource: wgpu::BindingResource::TextureView(&spinner_texture_view),
-                },
-            ],
-        });
+        let texture_bind_group = Self::create_texture_bind_group(
+            &device,
+            &texture_bind_group_layout,
+            &demo_sampler,
+            &demo_texture_view,
+            &demo_overlay_texture_view,
+            &slidercircle_texture_view,
+            &slidercircleoverlay_texture_view,
+            &approachcircle_texture_view,
+            &digits_texture_view,
+            &digits_meta_buffer,
+            &background_texture_view,
+            &skin_meta_buffer,
+            &sliderendcircle_texture_view,
+            &sliderendcircleoverlay_texture_view,
+            &reverse_arrow_texture_view,
+            &slider_ball_texture_view,
+            &slider_follow_circle_texture_view,
+            &loading_texture_view,
+            &break_texture_view,
+            &spinner_texture_view,
+        );
 
         // --- Circles instance data (from Rust -> shader) ---
         let circles_bind_group_layout =
@@ -1984,7 +2412,8 @@ impl GpuRenderer {
                 }],
             });
 
-        let circles_init: Vec<CircleGpu> = vec![CircleGpu::zeroed(); MAX_CIRCLES];
+        let circles_capacity = INITIAL_CIRCLES_CAPACITY.max(1);
+        let circles_init: Vec<CircleGpu> = vec![CircleGpu::zeroed(); circles_capacity];
         let circles_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
             label: Some("circles buffer"),
             contents: bytemuck::cast_slice(circles_init.as_slice()),
@@ -2085,7 +2514,7 @@ impl GpuRenderer {
             usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
         });
 
-        let slider_draw_indices_capacity = MAX_CIRCLES.max(1);
+        let slider_draw_indices_capacity = INITIAL_CIRCLES_CAPACITY.max(1);
         let slider_draw_indices_init: Vec<u32> = vec![0u32; slider_draw_indices_capacity];
         let slider_draw_indices_buffer =
             device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
@@ -2126,381 +2555,72 @@ impl GpuRenderer {
             });
 
         let empty_bind_group_layout =
-            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-                label: Some("empty layout"),
-                entries: &[],
-            });
-
-        let timeline_empty_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("timeline empty bind group"),
-            layout: &empty_bind_group_layout,
-            entries: &[],
-        });
-
-        let timeline_pipeline_layout =
-            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-                label: Some("timeline pipeline layout"),
-                bind_group_layouts: &[
-                    &globals_bind_group_layout,
-                    &empty_bind_group_layout,
-                    &empty_bind_group_layout,
-                    &timeline_marks_bind_group_layout,
-                ],
-                immediate_size: 0,
-            });
-
-        let timeline_slider_pipeline_layout =
-            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-                label: Some("timeline slider pipeline layout"),
-                bind_group_layouts: &[
-                    &globals_bind_group_layout,
-                    &empty_bind_group_layout,
-                    &timeline_points_bind_group_layout,
-                    &timeline_x_boxes_bind_group_layout,
-                ],
-                immediate_size: 0,
-            });
-
-        let background_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some("background pipeline"),
-            layout: Some(&background_pipeline_layout),
-            vertex: wgpu::VertexState {
-                module: &shader,
-                entry_point: Some("vs_bg"),
-                compilation_options: wgpu::PipelineCompilationOptions::default(),
-                buffers: &[],
-            },
-            fragment: Some(wgpu::FragmentState {
-                module: &shader,
-                entry_point: Some("fs_bg"),
-                compilation_options: wgpu::PipelineCompilationOptions::default(),
-                targets: &[Some(wgpu::ColorTargetState {
-                    format: config.format,
-                    blend: Some(wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING),
-                    write_mask: wgpu::ColorWrites::ALL,
-                })],
-            }),
-            primitive: wgpu::PrimitiveState {
-                topology: wgpu::PrimitiveTopology::TriangleList,
-                ..Default::default()
-            },
-            depth_stencil: None,
-            multisample: wgpu::MultisampleState {
-                count: msaa_samples,
-                ..Default::default()
-            },
-            multiview_mask: None,
-            cache: None,
-        });
-
-        let overlay_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some("overlay pipeline"),
-            layout: Some(&overlay_pipeline_layout),
-            vertex: wgpu::VertexState {
-                module: &shader,
-                entry_point: Some("vs_overlay"),
-                compilation_options: wgpu::PipelineCompilationOptions::default(),
-                buffers: &[],
-            },
-            fragment: Some(wgpu::FragmentState {
-                module: &shader,
-                entry_point: Some("fs_overlay"),
-                compilation_options: wgpu::PipelineCompilationOptions::default(),
-                targets: &[Some(wgpu::ColorTargetState {
-                    format: config.format,
-                    blend: Some(wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING),
-                    write_mask: wgpu::ColorWrites::ALL,
-                })],
-            }),
-            primitive: wgpu::PrimitiveState {
-                topology: wgpu::PrimitiveTopology::TriangleList,
-                ..Default::default()
-            },
-            depth_stencil: None,
-            multisample: wgpu::MultisampleState {
-                count: msaa_samples,
-                ..Default::default()
-            },
-            multiview_mask: None,
-            cache: None,
-        });
-
-        let hud_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some("hud pipeline"),
-            layout: Some(&overlay_pipeline_layout),
-            vertex: wgpu::VertexState {
-                module: &shader,
-                entry_point: Some("vs_hud"),
-                compilation_options: wgpu::PipelineCompilationOptions::default(),
-                buffers: &[],
-            },
-            fragment: Some(wgpu::FragmentState {
-                module: &shader,
-                entry_point: Some("fs_hud"),
-                compilation_options: wgpu::PipelineCompilationOptions::default(),
-                targets: &[Some(wgpu::ColorTargetState {
-                    format: config.format,
-                    blend: Some(wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING),
-                    write_mask: wgpu::ColorWrites::ALL,
-                })],
-            }),
-            primitive: wgpu::PrimitiveState {
-                topology: wgpu::PrimitiveTopology::TriangleList,
-                ..Default::default()
-            },
-            depth_stencil: None,
-            multisample: wgpu::MultisampleState {
-                count: msaa_samples,
-                ..Default::default()
-            },
-            multiview_mask: None,
-            cache: None,
-        });
-
-        let timeline_kiai_pipeline =
-            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-                label: Some("timeline kiai pipeline"),
-                layout: Some(&timeline_pipeline_layout),
-                vertex: wgpu::VertexState {
-                    module: &shader,
-                    entry_point: Some("vs_hud"),
-                    compilation_options: wgpu::PipelineCompilationOptions::default(),
-                    buffers: &[],
-                },
-                fragment: Some(wgpu::FragmentState {
-                    module: &shader,
-                    entry_point: Some("fs_timeline_kiai"),
-                    compilation_options: wgpu::PipelineCompilationOptions::default(),
-                    targets: &[Some(wgpu::ColorTargetState {
-                        format: config.format,
-                        blend: Some(wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING),
-                        write_mask: wgpu::ColorWrites::ALL,
-                    })],
-                }),
-                primitive: wgpu::PrimitiveState {
-                    topology: wgpu::PrimitiveTopology::TriangleList,
-                    ..Default::default()
-                },
-                depth_stencil: None,
-                multisample: wgpu::MultisampleState {
-                    count: msaa_samples,
-                    ..Default::default()
-                },
-                multiview_mask: None,
-                cache: None,
-            });
-
-        let timeline_break_pipeline =
-            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-                label: Some("timeline break pipeline"),
-                layout: Some(&timeline_pipeline_layout),
-                vertex: wgpu::VertexState {
-                    module: &shader,
-                    entry_point: Some("vs_hud"),
-                    compilation_options: wgpu::PipelineCompilationOptions::default(),
-                    buffers: &[],
-                },
-                fragment: Some(wgpu::FragmentState {
-                    module: &shader,
-                    entry_point: Some("fs_timeline_break"),
-                    compilation_options: wgpu::PipelineCompilationOptions::default(),
-                    targets: &[Some(wgpu::ColorTargetState {
-                        format: config.format,
-                        blend: Some(wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING),
-                        write_mask: wgpu::ColorWrites::ALL,
-                    })],
-                }),
-                primitive: wgpu::PrimitiveState {
-                    topology: wgpu::PrimitiveTopology::TriangleList,
-                    ..Default::default()
-                },
-                depth_stencil: None,
-                multisample: wgpu::MultisampleState {
-                    count: msaa_samples,
-                    ..Default::default()
-                },
-                multiview_mask: None,
-                cache: None,
-            });
-
-        let timeline_bookmark_pipeline =
-            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-                label: Some("timeline bookmark pipeline"),
-                layout: Some(&timeline_pipeline_layout),
-                vertex: wgpu::VertexState {
-                    module: &shader,
-                    entry_point: Some("vs_hud"),
-                    compilation_options: wgpu::PipelineCompilationOptions::default(),
-                    buffers: &[],
-                },
-                fragment: Some(wgpu::FragmentState {
-                    module: &shader,
-                    entry_point: Some("fs_timeline_bookmarks"),
-                    compilation_options: wgpu::PipelineCompilationOptions::default(),
-                    targets: &[Some(wgpu::ColorTargetState {
-                        format: config.format,
-                        blend: Some(wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING),
-                        write_mask: wgpu::ColorWrites::ALL,
-                    })],
-                }),
-                primitive: wgpu::PrimitiveState {
-                    topology: wgpu::PrimitiveTopology::TriangleList,
-                    ..Default::default()
-                },
-                depth_stencil: None,
-                multisample: wgpu::MultisampleState {
-                    count: msaa_samples,
-                    ..Default::default()
-                },
-                multiview_mask: None,
-                cache: None,
-            });
-
-        let timeline_slider_pipeline =
-            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-                label: Some("timeline slider pipeline"),
-                layout: Some(&timeline_slider_pipeline_layout),
-                vertex: wgpu::VertexState {
-                    module: &shader,
-                    entry_point: Some("vs_timeline_slider_boxes"),
-                    compilation_options: wgpu::PipelineCompilationOptions::default(),
-                    buffers: &[],
-                },
-                fragment: Some(wgpu::FragmentState {
-                    module: &shader,
-                    entry_point: Some("fs_timeline_slider_boxes"),
-                    compilation_options: wgpu::PipelineCompilationOptions::default(),
-                    targets: &[Some(wgpu::ColorTargetState {
-                        format: config.format,
-                        blend: Some(wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING),
-                        write_mask: wgpu::ColorWrites::ALL,
-                    })],
-                }),
-                primitive: wgpu::PrimitiveState {
-                    topology: wgpu::PrimitiveTopology::TriangleList,
-                    ..Default::default()
-                },
-                depth_stencil: None,
-                multisample: wgpu::MultisampleState {
-                    count: msaa_samples,
-                    ..Default::default()
-                },
-                multiview_mask: None,
-                cache: None,
-            });
-
-        let sliders_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some("sliders body pipeline"),
-            layout: Some(&sliders_pipeline_layout),
-            vertex: wgpu::VertexState {
-                module: &shader,
-                entry_point: Some("vs_slider_box"),
-                compilation_options: wgpu::PipelineCompilationOptions::default(),
-                buffers: &[],
-            },
-            fragment: Some(wgpu::FragmentState {
-                module: &shader,
-                entry_point: Some("fs_slider_box"),
-                compilation_options: wgpu::PipelineCompilationOptions::default(),
-                targets: &[Some(wgpu::ColorTargetState {
-                    format: config.format,
-                    blend: Some(wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING),
-                    write_mask: wgpu::ColorWrites::ALL,
-                })],
-            }),
-            primitive: wgpu::PrimitiveState {
-                topology: wgpu::PrimitiveTopology::TriangleList,
-                ..Default::default()
-            },
-            depth_stencil: None,
-            multisample: wgpu::MultisampleState {
-                count: msaa_samples,
-                ..Default::default()
-            },
-            multiview_mask: None,
-            cache: None,
-        });
-
-        let slider_caps_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some("slider caps pipeline"),
-            layout: Some(&sliders_pipeline_layout),
-            vertex: wgpu::VertexState {
-                module: &shader,
-                entry_point: Some("vs_slider_caps"),
-                compilation_options: wgpu::PipelineCompilationOptions::default(),
-                buffers: &[],
-            },
-            fragment: Some(wgpu::FragmentState {
-                module: &shader,
-                entry_point: Some("fs_slider_caps"),
-                compilation_options: wgpu::PipelineCompilationOptions::default(),
-                targets: &[Some(wgpu::ColorTargetState {
-                    format: config.format,
-                    blend: Some(wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING),
-                    write_mask: wgpu::ColorWrites::ALL,
-                })],
-            }),
-            primitive: wgpu::PrimitiveState {
-                topology: wgpu::PrimitiveTopology::TriangleList,
-                ..Default::default()
-            },
-            depth_stencil: None,
-            multisample: wgpu::MultisampleState {
-                count: msaa_samples,
-                ..Default::default()
-            },
-            multiview_mask: None,
-            cache: None,
-        });
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("empty layout"),
+                entries: &[],
+            });
 
-        let circles_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some("circles pipeline"),
-            layout: Some(&circles_pipeline_layout),
-            vertex: wgpu::VertexState {
-                module: &shader,
-                entry_point: Some("vs_main"),
-                compilation_options: wgpu::PipelineCompilationOptions::default(),
-                buffers: &[],
-            },
-            fragment: Some(wgpu::FragmentState {
-                module: &shader,
-                entry_point: Some("fs_main"),
-                compilation_options: wgpu::PipelineCompilationOptions::default(),
-                targets: &[Some(wgpu::ColorTargetState {
-                    format: config.format,
-                    blend: Some(wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING),
-                    write_mask: wgpu::ColorWrites::ALL,
-                })],
-            }),
-            primitive: wgpu::PrimitiveState {
-                topology: wgpu::PrimitiveTopology::TriangleList,
-                ..Default::default()
-            },
-            depth_stencil: None,
-            multisample: wgpu::MultisampleState {
-                count: msaa_samples,
-                ..Default::default()
-            },
-            multiview_mask: None,
-            cache: None,
+        let timeline_empty_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("timeline empty bind group"),
+            layout: &empty_bind_group_layout,
+            entries: &[],
         });
 
+        let timeline_pipeline_layout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("timeline pipeline layout"),
+                bind_group_layouts: &[
+                    &globals_bind_group_layout,
+                    &empty_bind_group_layout,
+                    &empty_bind_group_layout,
+                    &timeline_marks_bind_group_layout,
+                ],
+                immediate_size: 0,
+            });
+
+        let timeline_slider_pipeline_layout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("timeline slider pipeline layout"),
+                bind_group_layouts: &[
+                    &globals_bind_group_layout,
+                    &empty_bind_group_layout,
+                    &timeline_points_bind_group_layout,
+                    &timeline_x_boxes_bind_group_layout,
+                ],
+                immediate_size: 0,
+            });
+
+        let pipeline_layouts = ScenePipelineLayouts {
+            background: background_pipeline_layout,
+            overlay: overlay_pipeline_layout,
+            circles: circles_pipeline_layout,
+            sliders: sliders_pipeline_layout,
+            timeline: timeline_pipeline_layout,
+            timeline_slider: timeline_slider_pipeline_layout,
+        };
+        let pipelines = Self::create_scene_pipelines(
+            &device,
+            &shader,
+            &pipeline_layouts,
+            config.format,
+            msaa_samples,
+        );
+
         Ok(Self {
-            _window: window,
-            surface,
+            target,
             device,
             queue,
             config,
-            circles_pipeline,
-            sliders_pipeline,
-            slider_caps_pipeline,
-            background_pipeline,
-            overlay_pipeline,
-            hud_pipeline,
-            timeline_kiai_pipeline,
-            timeline_break_pipeline,
-            timeline_bookmark_pipeline,
-            timeline_slider_pipeline,
+            circles_pipeline: pipelines.circles_pipeline,
+            sliders_pipeline: pipelines.sliders_pipeline,
+            slider_caps_pipeline: pipelines.slider_caps_pipeline,
+            background_pipeline: pipelines.background_pipeline,
+            overlay_pipeline: pipelines.overlay_pipeline,
+            hud_pipeline: pipelines.hud_pipeline,
+            timeline_kiai_pipeline: pipelines.timeline_kiai_pipeline,
+            timeline_break_pipeline: pipelines.timeline_break_pipeline,
+            timeline_bookmark_pipeline: pipelines.timeline_bookmark_pipeline,
+            timeline_slider_pipeline: pipelines.timeline_slider_pipeline,
             globals_buffer,
             globals_bind_group,
             timeline_empty_bind_group,
@@ -2537,10 +2657,14 @@ impl GpuRenderer {
             _digits_texture_view: digits_texture_view,
             _digits_meta_buffer: digits_meta_buffer,
             _skin_meta_buffer: skin_meta_buffer,
+            texture_bind_group_layout,
             texture_bind_group,
             msaa_samples,
+            supported_msaa_samples,
             msaa_color,
             msaa_color_view,
+            shader,
+            pipeline_layouts,
 
             timeline_kiai_buffer,
             timeline_kiai_bind_group,
@@ -2554,8 +2678,11 @@ impl GpuRenderer {
             timeline_x_boxes_buffer,
             timeline_x_boxes_capacity,
             timeline_x_boxes_bind_group,
+            timeline_points_cache: TimelinePointsCache::new(),
             snap_markers_buffer,
             snap_markers_capacity,
+            flow_segments_buffer,
+            convert_notes_buffer,
             snap_markers_bind_group,
 
             slider_segs_buffer,
@@ -2565,7 +2692,9 @@ impl GpuRenderer {
             slider_draw_indices_buffer,
             slider_draw_indices_capacity,
             slider_bind_group,
+            slider_quality_reduction: 0,
             objects_buffer: circles_buffer,
+            objects_capacity: circles_capacity,
             objects_bind_group: circles_bind_group,
             objects_upload: circles_init,
             size,
@@ -2573,6 +2702,7 @@ impl GpuRenderer {
             gpu_pass_x10: 0,
             cpu_pass_history: VecDeque::new(),
             gpu_pass_history: VecDeque::new(),
+            frame_graph: VecDeque::with_capacity(FRAME_GRAPH_SAMPLES),
         })
     }
 
@@ -2585,12 +2715,96 @@ impl GpuRenderer {
         self.size = new_size;
         self.config.width = new_size.width;
         self.config.height = new_size.height;
-        self.surface.configure(&self.device, &self.config);
+        match &mut self.target {
+            RenderTarget::Window { surface, .. } => surface.configure(&self.device, &self.config),
+            RenderTarget::Offscreen { texture } => {
+                *texture = Self::create_offscreen_texture(&self.device, &self.config);
+            }
+        }
+
+        let (msaa_color, msaa_color_view) =
+            Self::create_msaa_target(&self.device, &self.config, self.msaa_samples);
+        self.msaa_color = msaa_color;
+        self.msaa_color_view = msaa_color_view;
+    }
+
+    pub fn msaa_samples(&self) -> u32 {
+        self.msaa_samples
+    }
+
+    pub fn set_slider_quality_reduction(&mut self, reduction: usize) {
+        self.slider_quality_reduction = reduction;
+    }
 
+    pub fn supported_msaa_samples(&self) -> &[u32] {
+        &self.supported_msaa_samples
+    }
+
+    // Falls back to the closest supported count below the requested one.
+    pub fn set_msaa_samples(&mut self, requested: u32) -> u32 {
+        let msaa_samples = Self::select_supported_msaa_samples(&self.supported_msaa_samples, requested);
+        if msaa_samples == self.msaa_samples {
+            return msaa_samples;
+        }
+
+        let pipelines = Self::create_scene_pipelines(
+            &self.device,
+            &self.shader,
+            &self.pipeline_layouts,
+            self.config.format,
+            msaa_samples,
+        );
+        self.circles_pipeline = pipelines.circles_pipeline;
+        self.sliders_pipeline = pipelines.sliders_pipeline;
+        self.slider_caps_pipeline = pipelines.slider_caps_pipeline;
+        self.background_pipeline = pipelines.background_pipeline;
+        self.overlay_pipeline = pipelines.overlay_pipeline;
+        self.hud_pipeline = pipelines.hud_pipeline;
+        self.timeline_kiai_pipeline = pipelines.timeline_kiai_pipeline;
+        self.timeline_break_pipeline = pipelines.timeline_break_pipeline;
+        self.timeline_bookmark_pipeline = pipelines.timeline_bookmark_pipeline;
+        self.timeline_slider_pipeline = pipelines.timeline_slider_pipeline;
+
+        self.msaa_samples = msaa_samples;
         let (msaa_color, msaa_color_view) =
             Self::create_msaa_target(&self.device, &self.config, self.msaa_samples);
         self.msaa_color = msaa_color;
         self.msaa_color_view = msaa_color_view;
+        msaa_samples
+    }
+
+    pub fn set_background(&mut self, background: &Texture) {
+        let (background_texture, background_texture_view) = Self::upload_texture_2d_srgb(
+            &self.device,
+            &self.queue,
+            "background texture",
+            background,
+            false,
+        );
+        self.texture_bind_group = Self::create_texture_bind_group(
+            &self.device,
+            &self.texture_bind_group_layout,
+            &self._demo_sampler,
+            &self._demo_texture_view,
+            &self._demo_overlay_texture_view,
+            &self._slider_texture_view,
+            &self._slider_overlay_texture_view,
+            &self._approach_circle_texture_view,
+            &self._digits_texture_view,
+            &self._digits_meta_buffer,
+            &background_texture_view,
+            &self._skin_meta_buffer,
+            &self._slider_end_texture_view,
+            &self._slider_end_overlay_texture_view,
+            &self._reverse_arrow_texture_view,
+            &self._slider_ball_texture_view,
+            &self._slider_follow_circle_texture_view,
+            &self._loading_texture_view,
+            &self._break_texture_view,
+            &self._spinner_texture_view,
+        );
+        self._background_texture = background_texture;
+        self._background_texture_view = background_texture_view;
     }
 
     pub fn render<'a>(
@@ -2602,6 +2816,8 @@ impl GpuRenderer {
         kiai_times: &Treap<(f64, f64)>,
         bookmarks: &Treap<f64>,
         red_lines: &Treap<f64>,
+        timing: &Arc<Timing>,
+        base_sv: f64,
         left_selected_objects: &[usize],
         right_selected_objects: &[usize],
         time_ms: f64,
@@ -2645,6 +2861,7 @@ impl GpuRenderer {
         selection_left_origin_dragging: bool,
         selection_right_origin_dragging: bool,
         cursor_pos: [f32; 2],
+        tooltip_text: &str,
         play_pause_button_hovered: bool,
         play_pause_button_clicked: bool,
         undo_button_hovered: bool,
@@ -2667,12 +2884,34 @@ impl GpuRenderer {
         movable_snap_positions: &[Vec2],
         drag_happening: bool,
         timeline_zoom: f64,
+        convert_preview: Option<&ConvertPreview>,
+        hit_windows_ms: [f64; 3],
+        slider_tick_rate: f64,
+        kiai_levels: [f32; 4],
+        selection_loop_ms: Option<(f64, f64)>,
+        bottom_timeline_ms: (f64, f64),
+        replay: Option<(&OsrFile, &[ReplayJudgement])>,
+        unsnapped_objects: &[(usize, f64)],
+        hint_line: &str,
+        onboarding_regions: &[OnboardingRegion],
+        editing_totals: &EditingTotals,
+        report_lines: &[String],
+        timeline_drag_marks: &TimelineDragMarks,
     ) -> Result<(), wgpu::SurfaceError> {
         let frame_start = Instant::now();
-        let output = self.surface.get_current_texture()?;
-        let swapchain_view = output
-            .texture
-            .create_view(&wgpu::TextureViewDescriptor::default());
+        let (output, swapchain_view) = match &self.target {
+            RenderTarget::Window { surface, .. } => {
+                let output = surface.get_current_texture()?;
+                let view = output
+                    .texture
+                    .create_view(&wgpu::TextureViewDescriptor::default());
+                (Some(output), view)
+            }
+            RenderTarget::Offscreen { texture } => (
+                None,
+                texture.create_view(&wgpu::TextureViewDescriptor::default()),
+            ),
+        };
 
         let playfield_rect = layout.playfield_rect.to_f32_array();
         let gameplay_rect = layout.gameplay_rect.to_f32_array();
@@ -2682,10 +2921,40 @@ impl GpuRenderer {
         // Uploading/drawing circles that are not currently visible wastes fill-rate and texture
         // bandwidth. We cull by time window and a conservative on-screen bounds check.
         const IGNORE_CIRCLES_DELTA: f64 = 200.0;
-        const FADE_OUT_MS: f64 = 250.0;
+        let fade_envelope = FadeEnvelope::from_config(config);
+        let view_preset = ViewPreset::from_name(&config.appearance.general.view_preset)
+            .unwrap_or(ViewPreset::Design);
+        let realtime_approach = config
+            .appearance
+            .general
+            .realtime_approach_view_presets
+            .iter()
+            .any(|name| ViewPreset::from_name(name) == Some(view_preset));
+        let ghost_window = if is_playing {
+            None
+        } else {
+            ghost_window_ms(
+                objects.iter(),
+                time_ms,
+                config.appearance.general.ghost_object_count as usize,
+            )
+        };
+        let ghost_opacity = config.appearance.general.ghost_object_opacity as f32;
+        let past_window = if is_playing {
+            None
+        } else {
+            past_window_ms(
+                objects.iter(),
+                time_ms,
+                config.appearance.general.past_object_count as usize,
+            )
+        };
+        let past_opacity = config.appearance.general.past_object_opacity as f32;
 
         let circles_to_upload = &mut self.objects_upload;
+        circles_to_upload.clear();
         let mut count: usize = 0;
+        let mut circles_truncated = false;
 
         let mut slider_segs: Vec<SliderSegGpu> = Vec::new();
         let mut slider_boxes: Vec<SliderBoxGpu> = Vec::new();
@@ -2713,22 +2982,15 @@ impl GpuRenderer {
                 .max(0.0)
                 .clamp(0.0, 1.0))
         .max(1.0);
-        let timeline_ms_per_radius = config
-            .appearance
-            .timeline
-            .milliseconds_per_object_radius
-            .max(1.0)
-            / timeline_zoom;
-        let timeline_window_span_ms =
-            ((top_timeline_width_px / timeline_radius_px) * timeline_ms_per_radius).max(1.0);
-        let timeline_ms_per_pixel = timeline_window_span_ms / top_timeline_width_px;
+        let (timeline_window_start_ms, timeline_window_end_ms) =
+            top_timeline_window_ms(config, &layout.top_timeline_rect, timeline_zoom, time_ms);
+        let timeline_ms_per_pixel =
+            (timeline_window_end_ms - timeline_window_start_ms) / top_timeline_width_px;
         let timeline_current_pos = config
             .appearance
             .timeline
             .current_timestamp_position_percent
             .clamp(0.0, 1.0);
-        let timeline_window_start_ms = time_ms - timeline_window_span_ms * timeline_current_pos;
-        let timeline_window_end_ms = timeline_window_start_ms + timeline_window_span_ms;
         let timeline_window_ms = [
             timeline_window_start_ms as f32,
             timeline_window_end_ms as f32,
@@ -2743,7 +3005,10 @@ impl GpuRenderer {
             timeline_radius_px * config.appearance.timeline.slider_outline_thickness_percent;
 
         let (timeline_points_cpu, timeline_x_boxes_cpu) = calculate_timeline_points_and_boxes(
+            &mut self.timeline_points_cache,
             objects,
+            timing,
+            base_sv,
             layout.top_timeline_rect.x0,
             timeline_current_x,
             layout.top_timeline_rect.x1,
@@ -2754,9 +3019,29 @@ impl GpuRenderer {
             timeline_radius_px,
             outline_thickness_px,
             combo_colors,
+            if config.performance.potato_mode {
+                POTATO_MAX_TIMELINE_POINTS
+            } else {
+                usize::MAX
+            },
         );
         let timeline_center_y =
             (layout.top_timeline_rect.y0 + layout.top_timeline_rect.y1) as f32 * 0.5;
+        let colors = &config.appearance.colors;
+        // Like stable: 1/1 heads white, 1/2 red, 1/4 blue and so on, unsnapped ones stand out.
+        let head_rgba = |snap_divisor: u32| -> [f32; 4] {
+            let rgb = SNAP_DIVISORS
+                .iter()
+                .position(|divisor| *divisor == snap_divisor)
+                .and_then(|i| colors.timeline_snap_divisor_rgb.get(i))
+                .unwrap_or(&colors.timeline_unsnapped_head_rgb);
+            [
+                (rgb[0] / 255.0) as f32,
+                (rgb[1] / 255.0) as f32,
+                (rgb[2] / 255.0) as f32,
+                colors.timeline_slider_head_overlay_rgba[3] as f32,
+            ]
+        };
         let timeline_points_gpu: Vec<TimelinePointGpu> = timeline_points_cpu
             .iter()
             .map(|p| TimelinePointGpu {
@@ -2769,8 +3054,10 @@ impl GpuRenderer {
                 is_selected: p.is_selected,
                 is_selected_by_left: if p.selection_side == 1 { 1 } else { 0 },
                 is_slider_or_spinner: p.is_slider_or_spinner,
-                _pad: [0, 0, 0],
+                sv_label: p.sv_label,
+                _pad: 0,
                 color: p.combo_color_and_opacity,
+                head_rgba: head_rgba(p.snap_divisor),
             })
             .collect();
         let timeline_x_boxes_gpu: Vec<TimelineXBoxGpu> = timeline_x_boxes_cpu
@@ -2982,15 +3269,19 @@ impl GpuRenderer {
                 combo_color
             };
 
-            let appear_ms = circle.time - circle.preempt - IGNORE_CIRCLES_DELTA;
+            let appear_ms = fade_envelope.appear_ms(&circle) - IGNORE_CIRCLES_DELTA;
             let end_ms = if circle.is_slider {
                 circle.slider_end_time_ms
             } else {
                 circle.time
             };
-            let disappear_ms = end_ms + FADE_OUT_MS + IGNORE_CIRCLES_DELTA;
+            let disappear_ms = fade_envelope.disappear_ms(&circle) + IGNORE_CIRCLES_DELTA;
 
-            if selected_side == 0 && (time_ms < appear_ms || time_ms > disappear_ms) {
+            let ghosted = [ghost_window, past_window].iter().any(|window| match window {
+                Some((from_ms, to_ms)) => *from_ms <= circle.time && circle.time <= *to_ms,
+                None => false,
+            });
+            if selected_side == 0 && !ghosted && (time_ms < appear_ms || time_ms > disappear_ms) {
                 continue;
             }
 
@@ -3073,7 +3364,7 @@ impl GpuRenderer {
 
                 let box_start = slider_boxes.len() as u32;
                 let mut box_count: u32 = 0;
-                for b in curve.boxes.iter() {
+                for b in curve.boxes_for_reduction(self.slider_quality_reduction) {
                     let seg_start = slider_segs.len() as u32;
                     for seg in b.segments.iter() {
                         let a = seg[0];
@@ -3114,6 +3405,18 @@ impl GpuRenderer {
                     [start_rotation.x as f32, start_rotation.y as f32];
                 circle_gpu.slider_end_rotation = [end_rotation.x as f32, end_rotation.y as f32];
                 circle_gpu.slider_end_center_xy = [end_pt.x as f32, end_pt.y as f32];
+                let lazy_end = if config.appearance.general.show_slider_lazy_end {
+                    let beat_length = match timing.get_lines_at_time(circle.time).0 {
+                        Some(red_line) if red_line.beat_length > 0.0 => red_line.beat_length,
+                        _ => 500.0,
+                    };
+                    circle.lazy_end_pos(beat_length / slider_tick_rate.max(0.1))
+                } else if circle.slides % 2 == 0 {
+                    circle.pos
+                } else {
+                    end_pt
+                };
+                circle_gpu.lazy_end_xy = [lazy_end.x as f32, lazy_end.y as f32];
 
                 circle_gpu.slider_box_start = box_start;
                 circle_gpu.slider_box_count = box_count;
@@ -3121,9 +3424,10 @@ impl GpuRenderer {
                 slider_draw_lookup.push(-1);
             }
 
-            circles_to_upload[count] = circle_gpu;
+            circles_to_upload.push(circle_gpu);
             count += 1;
             if count >= MAX_CIRCLES {
+                circles_truncated = true;
                 break;
             }
         }
@@ -3217,6 +3521,154 @@ impl GpuRenderer {
         for pos in movable_snap_positions.iter().take(movable_snap_count) {
             snap_markers_upload.push([pos.x as f32, pos.y as f32]);
         }
+        // The auto or replay cursor trail rides along after the snap markers, oldest point
+        // first. A loaded replay's cursor wins and also shows while paused.
+        let trail_ms = config.appearance.general.auto_cursor_trail_ms;
+        let cursor_trail = match replay {
+            Some((osr_file, _)) => {
+                replay_cursor_trail(osr_file, time_ms, trail_ms, AUTO_CURSOR_TRAIL_SAMPLES)
+            }
+            None if is_playing
+                && config
+                    .appearance
+                    .general
+                    .cursor_trace_mode
+                    .eq_ignore_ascii_case("auto") =>
+            {
+                auto_cursor_trail(objects, time_ms, trail_ms, AUTO_CURSOR_TRAIL_SAMPLES)
+            }
+            None => Vec::new(),
+        };
+        for pos in &cursor_trail {
+            snap_markers_upload.push([pos.x as f32, pos.y as f32]);
+        }
+        let auto_cursor_count = cursor_trail.len();
+
+        // Flow lines between objects that are on screen.
+        let mut flow_segments: Vec<FlowSegmentGpu> = Vec::new();
+        if config.appearance.general.show_flow_overlay {
+            let transitions = find_flow_transitions(
+                objects,
+                config.appearance.general.flow_sharp_angle_deg,
+                config.appearance.general.flow_anti_flow_turn_deg,
+            );
+            for transition in transitions {
+                let appear_ms = transition.to_time_ms - transition.to_preempt_ms;
+                let disappear_ms = transition.from_end_ms + fade_envelope.fade_out_ms;
+                if time_ms < appear_ms || time_ms > disappear_ms {
+                    continue;
+                }
+                if flow_segments.len() >= MAX_FLOW_SEGMENTS {
+                    break;
+                }
+                let mut flags = 0;
+                if transition.is_sharp {
+                    flags |= FLOW_SEGMENT_SHARP;
+                }
+                if transition.is_anti_flow {
+                    flags |= FLOW_SEGMENT_ANTI_FLOW;
+                }
+                if transition.angle_deg.is_some() {
+                    flags |= FLOW_SEGMENT_HAS_ANGLE;
+                }
+                flow_segments.push(FlowSegmentGpu {
+                    start_pos: [transition.from.x as f32, transition.from.y as f32],
+                    end_pos: [transition.to.x as f32, transition.to.y as f32],
+                    angle_deg: transition.angle_deg.unwrap_or(0.0) as f32,
+                    flags,
+                    ring_radius: 0.0,
+                    _pad: 0.0,
+                });
+            }
+        }
+        if let Some((_, judgements)) = replay {
+            let first = judgements.partition_point(|judgement| {
+                judgement.object_time_ms < time_ms - REPLAY_JUDGEMENT_VISIBLE_MS
+            });
+            for judgement in judgements[first..].iter() {
+                if judgement.object_time_ms > time_ms
+                    || flow_segments.len() >= MAX_FLOW_SEGMENTS
+                {
+                    break;
+                }
+                let (flags, hit_error_ms, ring_radius) = match judgement.hit_error_ms {
+                    Some(error_ms) => (
+                        FLOW_SEGMENT_REPLAY_HIT | FLOW_SEGMENT_HAS_ANGLE,
+                        error_ms,
+                        0.0,
+                    ),
+                    None => (FLOW_SEGMENT_REPLAY_MISS, 0.0, judgement.radius),
+                };
+                flow_segments.push(FlowSegmentGpu {
+                    start_pos: [judgement.object_pos.x as f32, judgement.object_pos.y as f32],
+                    end_pos: [judgement.cursor_pos.x as f32, judgement.cursor_pos.y as f32],
+                    angle_deg: hit_error_ms.abs() as f32,
+                    flags,
+                    ring_radius: ring_radius as f32,
+                    _pad: 0.0,
+                });
+            }
+        }
+        for (id, _) in unsnapped_objects.iter() {
+            if flow_segments.len() >= MAX_FLOW_SEGMENTS {
+                break;
+            }
+            let Some(instance) = objects.get(*id).instance() else {
+                continue;
+            };
+            let end_ms = if instance.is_slider {
+                instance.slider_end_time_ms
+            } else {
+                instance.time
+            };
+            if time_ms < instance.time - instance.preempt
+                || time_ms > end_ms + fade_envelope.fade_out_ms
+            {
+                continue;
+            }
+            let pos = [instance.pos.x as f32, instance.pos.y as f32];
+            flow_segments.push(FlowSegmentGpu {
+                start_pos: pos,
+                end_pos: pos,
+                angle_deg: 0.0,
+                flags: FLOW_SEGMENT_UNSNAPPED,
+                ring_radius: (instance.radius * 1.15) as f32,
+                _pad: 0.0,
+            });
+        }
+
+        // Converted notes inside the timeline window, in a strip under the timeline right of
+        // the stats box.
+        let mut convert_notes: Vec<ConvertNoteGpu> = Vec::new();
+        let convert_rect = [
+            layout.stats_box_rect.x1 as f32 + 8.0,
+            layout.top_timeline_rect.y1 as f32 + 8.0,
+            layout.top_timeline_rect.x1 as f32,
+            layout.top_timeline_rect.y1 as f32 + 48.0,
+        ];
+        let (convert_mode, convert_key_count) = match convert_preview {
+            Some(preview) => (preview.mode as u32, preview.key_count),
+            None => (0, 0),
+        };
+        if let Some(preview) = convert_preview {
+            let to_x = |t: f64| (timeline_current_x + (t - time_ms) / timeline_ms_per_pixel) as f32;
+            for note in preview.notes.iter() {
+                if note.end_time_ms < timeline_window_start_ms
+                    || note.time_ms > timeline_window_end_ms
+                {
+                    continue;
+                }
+                if convert_notes.len() >= MAX_CONVERT_NOTES {
+                    break;
+                }
+                convert_notes.push(ConvertNoteGpu {
+                    x0: to_x(note.time_ms),
+                    x1: to_x(note.end_time_ms),
+                    lane: note.lane as f32,
+                    kind: note.kind as u32,
+                });
+            }
+        }
 
         let fps_clamped = fps.clamp(0.0, u32::MAX as f64 / 10.0);
         let fps_low_clamped = fps_low.clamp(0.0, u32::MAX as f64 / 10.0);
@@ -3253,7 +3705,7 @@ impl GpuRenderer {
                 if ch.is_control() {
                     continue;
                 }
-                let code = if ch.is_ascii() { ch as u32 } else { '?' as u32 };
+                let code = Self::hud_char_code(ch);
                 undo_prev_state_name_chars[undo_prev_state_name_len] = code;
                 undo_prev_state_name_len += 1;
             }
@@ -3282,7 +3734,7 @@ impl GpuRenderer {
                     if ch.is_control() {
                         continue;
                     }
-                    let code = if ch.is_ascii() { ch as u32 } else { '?' as u32 };
+                    let code = Self::hud_char_code(ch);
                     undo_next_states_name_chars[row * 16 + char_count] = code;
                     char_count += 1;
                 }
@@ -3304,6 +3756,122 @@ impl GpuRenderer {
             }
         }
 
+        // A hovered control's tooltip still wins, the warning is back once the cursor moves on.
+        let overflow_warning;
+        let overflow_warning_pinned = circles_truncated && tooltip_text.is_empty();
+        let tooltip_text = if overflow_warning_pinned {
+            overflow_warning = format!("too many objects - only the first {MAX_CIRCLES} are drawn");
+            overflow_warning.as_str()
+        } else {
+            tooltip_text
+        };
+        let tooltip_anchor = if overflow_warning_pinned {
+            [playfield_rect[0], playfield_rect[1]]
+        } else {
+            cursor_pos
+        };
+        let mut tooltip_len = 0usize;
+        let mut tooltip_text_packed = [[0u32; 4]; 4];
+        for ch in tooltip_text.chars() {
+            if tooltip_len >= 64 {
+                break;
+            }
+            if ch.is_control() {
+                continue;
+            }
+            let code = Self::hud_char_code(ch);
+            tooltip_text_packed[tooltip_len / 16][(tooltip_len % 16) / 4] |=
+                (code & 0xFF) << ((tooltip_len % 4) * 8);
+            tooltip_len += 1;
+        }
+
+        // The playhead's clock time has its own line, the selected object shows all three.
+        let [_, playhead_ms, playhead_beat] = time_formats(time_ms, timing);
+        let selected_times = left_selected_objects
+            .iter()
+            .chain(right_selected_objects)
+            .min()
+            .map(|idx| time_formats(objects.get(*idx).hit_object.time(), timing))
+            .unwrap_or_else(|| ["-".to_string(), "-".to_string(), "-".to_string()]);
+        let [object_clock, object_ms, object_beat] = selected_times;
+        let stats_time_lines = [
+            ("MS", playhead_ms),
+            ("BEAT", playhead_beat),
+            ("OBJ", object_clock),
+            ("OBJ_MS", object_ms),
+            ("OBJ_BEAT", object_beat),
+            ("EDIT_TIME", format_active_time(editing_totals.active_ms)),
+            ("PLACED", editing_totals.placed.to_string()),
+            ("EDITED", editing_totals.edited.to_string()),
+            ("REMOVED", editing_totals.removed.to_string()),
+        ];
+        let mut stats_time_text_packed = [[0u32; 4]; 18];
+        for (i, (label, value)) in stats_time_lines.iter().enumerate() {
+            stats_time_text_packed[i * 2] = Self::pack_ascii16(label);
+            stats_time_text_packed[i * 2 + 1] = Self::pack_ascii16(value);
+        }
+
+        // The hint line gives way to how to dismiss the first-run overlay while it is up.
+        let hint_line = if !onboarding_regions.is_empty() {
+            ONBOARDING_DISMISS_HINT
+        } else if config.appearance.general.show_hint_line {
+            hint_line
+        } else {
+            ""
+        };
+        let mut hint_text_packed = [[0u32; 4]; 6];
+        for (i, chunk) in hint_line.as_bytes().chunks(16).take(6).enumerate() {
+            hint_text_packed[i] = Self::pack_ascii16(&String::from_utf8_lossy(chunk));
+        }
+        let onboarding_region_count = onboarding_regions.len().min(MAX_ONBOARDING_REGIONS);
+        let mut onboarding_rects = [[0.0f32; 4]; MAX_ONBOARDING_REGIONS];
+        let mut onboarding_text_packed = [[0u32; 4]; MAX_ONBOARDING_REGIONS * 2];
+        for (i, region) in onboarding_regions.iter().take(MAX_ONBOARDING_REGIONS).enumerate() {
+            onboarding_rects[i] = region.rect;
+            let (first, second) = region.label.split_at(region.label.len().min(16));
+            onboarding_text_packed[i * 2] = Self::pack_ascii16(first);
+            onboarding_text_packed[i * 2 + 1] = Self::pack_ascii16(second);
+        }
+
+        let report_line_count = report_lines.len().min(MAX_REPORT_LINES);
+        let mut report_text_packed = [[0u32; 4]; MAX_REPORT_LINES * 3];
+        let mut report_width = 0;
+        for (i, line) in report_lines.iter().take(MAX_REPORT_LINES).enumerate() {
+            let chars: Vec<char> = line.chars().take(MAX_REPORT_LINE_CHARS).collect();
+            report_width = report_width.max(chars.len());
+            for (j, chunk) in chars.chunks(16).enumerate() {
+                let text: String = chunk.iter().collect();
+                report_text_packed[i * 3 + j] = Self::pack_ascii16(&text);
+            }
+        }
+        let report_meta = [report_line_count as u32, report_width as u32, 0, 0];
+
+        let mut timeline_drag_marks_ms = [[0.0f32; 4]; 4];
+        let mut timeline_drag_meta = [0u32; 4];
+        for (kind, times) in [&timeline_drag_marks.onsets_ms, &timeline_drag_marks.triplet_ticks_ms]
+            .into_iter()
+            .enumerate()
+        {
+            let count = times.len().min(MAX_TIMELINE_DRAG_MARKS);
+            for (i, time) in times.iter().take(count).enumerate() {
+                let slot = kind * MAX_TIMELINE_DRAG_MARKS + i;
+                timeline_drag_marks_ms[slot / 4][slot % 4] = *time as f32;
+            }
+            timeline_drag_meta[kind] = count as u32;
+        }
+
+        // The graph is scaled to the slowest recent frame, but never below the frame budget so a
+        // smooth graph stays low.
+        let frame_budget_ms = 1000.0 / config.performance.fps_limiter.max(1.0);
+        let frame_budget_x10 = (frame_budget_ms * 10.0).round() as u32;
+        let mut frame_graph_packed = [[0u32; 4]; 16];
+        let mut frame_graph_scale_x10 = frame_budget_x10;
+        for (i, (cpu_x10, gpu_x10)) in self.frame_graph.iter().enumerate() {
+            let (cpu_x10, gpu_x10) = ((*cpu_x10).min(0xFFFF), (*gpu_x10).min(0xFFFF));
+            frame_graph_packed[i / 4][i % 4] = cpu_x10 | (gpu_x10 << 16);
+            frame_graph_scale_x10 = frame_graph_scale_x10.max(cpu_x10 + gpu_x10);
+        }
+
         let current_state_name_source = if current_state_rename_active {
             current_state_rename_text
         } else {
@@ -3319,7 +3887,7 @@ impl GpuRenderer {
             if ch.is_control() {
                 continue;
             }
-            let code = if ch.is_ascii() { ch as u32 } else { '?' as u32 };
+            let code = Self::hud_char_code(ch);
             current_state_name_chars[current_state_name_len] = code;
             current_state_name_len += 1;
         }
@@ -4287,7 +4855,7 @@ impl GpuRenderer {
                 static_snap_count.min(u32::MAX as usize) as u32,
                 if drag_happening { 1 } else { 0 },
                 movable_snap_count.min(u32::MAX as usize) as u32,
-                0,
+                auto_cursor_count as u32,
             ],
             drag_state_marker_rgba: [
                 (config.appearance.colors.drag_state_marker_rgba[0] / 255.0) as f32,
@@ -4410,6 +4978,189 @@ impl GpuRenderer {
                 (config.appearance.colors.timeline_past_object_tint_rgba[2] / 255.0) as f32,
                 config.appearance.colors.timeline_past_object_tint_rgba[3] as f32,
             ],
+            object_fade_ms: [
+                fade_envelope.fade_in_ms_override as f32,
+                fade_envelope.fade_out_ms as f32,
+                0.0,
+                0.0,
+            ],
+            tooltip_meta: [tooltip_len as u32, overflow_warning_pinned as u32, 0, 0],
+            tooltip_anchor: [tooltip_anchor[0], tooltip_anchor[1], 0.0, 0.0],
+            tooltip_text_packed,
+            auto_cursor_rgba: [
+                (config.appearance.colors.auto_cursor_rgba[0] / 255.0) as f32,
+                (config.appearance.colors.auto_cursor_rgba[1] / 255.0) as f32,
+                (config.appearance.colors.auto_cursor_rgba[2] / 255.0) as f32,
+                config.appearance.colors.auto_cursor_rgba[3] as f32,
+            ],
+            auto_cursor_style: [
+                config.appearance.layout.auto_cursor_radius_px.max(0.0) as f32,
+                config.appearance.layout.auto_cursor_trail_width_px.max(0.0) as f32,
+                0.0,
+                0.0,
+            ],
+            flow_meta: [flow_segments.len() as u32, 0, 0, 0],
+            flow_style: [
+                config.appearance.layout.flow_line_width_px.max(0.0) as f32,
+                config.appearance.layout.flow_angle_text_px.max(0.0) as f32,
+                0.0,
+                0.0,
+            ],
+            flow_line_rgba: [
+                (config.appearance.colors.flow_line_rgba[0] / 255.0) as f32,
+                (config.appearance.colors.flow_line_rgba[1] / 255.0) as f32,
+                (config.appearance.colors.flow_line_rgba[2] / 255.0) as f32,
+                config.appearance.colors.flow_line_rgba[3] as f32,
+            ],
+            flow_sharp_rgba: [
+                (config.appearance.colors.flow_sharp_rgba[0] / 255.0) as f32,
+                (config.appearance.colors.flow_sharp_rgba[1] / 255.0) as f32,
+                (config.appearance.colors.flow_sharp_rgba[2] / 255.0) as f32,
+                config.appearance.colors.flow_sharp_rgba[3] as f32,
+            ],
+            flow_anti_flow_rgba: [
+                (config.appearance.colors.flow_anti_flow_rgba[0] / 255.0) as f32,
+                (config.appearance.colors.flow_anti_flow_rgba[1] / 255.0) as f32,
+                (config.appearance.colors.flow_anti_flow_rgba[2] / 255.0) as f32,
+                config.appearance.colors.flow_anti_flow_rgba[3] as f32,
+            ],
+            view_meta: [
+                view_preset as u32,
+                config.performance.potato_mode as u32,
+                realtime_approach as u32,
+                0,
+            ],
+            frame_graph_meta: [
+                self.frame_graph.len() as u32,
+                config.appearance.general.show_frame_graph as u32,
+                frame_graph_scale_x10,
+                frame_budget_x10,
+            ],
+            frame_graph_packed,
+            convert_meta: [
+                convert_mode,
+                convert_notes.len() as u32,
+                convert_key_count,
+                0,
+            ],
+            convert_rect,
+            hit_windows_ms: [
+                hit_windows_ms[0].max(0.0) as f32,
+                hit_windows_ms[1].max(0.0) as f32,
+                hit_windows_ms[2].max(0.0) as f32,
+                config.appearance.timeline.show_hit_windows as u32 as f32,
+            ],
+            hit_window_300_rgba: [
+                (config.appearance.colors.timeline_hit_window_300_rgba[0] / 255.0) as f32,
+                (config.appearance.colors.timeline_hit_window_300_rgba[1] / 255.0) as f32,
+                (config.appearance.colors.timeline_hit_window_300_rgba[2] / 255.0) as f32,
+                config.appearance.colors.timeline_hit_window_300_rgba[3] as f32,
+            ],
+            hit_window_100_rgba: [
+                (config.appearance.colors.timeline_hit_window_100_rgba[0] / 255.0) as f32,
+                (config.appearance.colors.timeline_hit_window_100_rgba[1] / 255.0) as f32,
+                (config.appearance.colors.timeline_hit_window_100_rgba[2] / 255.0) as f32,
+                config.appearance.colors.timeline_hit_window_100_rgba[3] as f32,
+            ],
+            hit_window_50_rgba: [
+                (config.appearance.colors.timeline_hit_window_50_rgba[0] / 255.0) as f32,
+                (config.appearance.colors.timeline_hit_window_50_rgba[1] / 255.0) as f32,
+                (config.appearance.colors.timeline_hit_window_50_rgba[2] / 255.0) as f32,
+                config.appearance.colors.timeline_hit_window_50_rgba[3] as f32,
+            ],
+            kiai_effect_meta: [
+                match kiai_time {
+                    Some((start, _)) if config.appearance.general.show_kiai_effects => {
+                        (time_ms - start) as f32
+                    }
+                    _ => -1.0,
+                },
+                kiai_levels[0],
+                kiai_levels.iter().sum::<f32>() / 4.0,
+                0.0,
+            ],
+            kiai_effect_rgba: [
+                (config.appearance.colors.kiai_effect_rgba[0] / 255.0) as f32,
+                (config.appearance.colors.kiai_effect_rgba[1] / 255.0) as f32,
+                (config.appearance.colors.kiai_effect_rgba[2] / 255.0) as f32,
+                config.appearance.colors.kiai_effect_rgba[3] as f32,
+            ],
+            visible_area_rect: layout::visible_gameplay_rect(
+                &layout.gameplay_rect,
+                config.appearance.general.offscreen_warning_aspect_ratio,
+            )
+            .to_f32_array(),
+            ghost_meta: match ghost_window {
+                Some((from_ms, to_ms)) => [
+                    from_ms as f32,
+                    to_ms as f32,
+                    ghost_opacity.clamp(0.0, 1.0),
+                    0.0,
+                ],
+                None => [0.0, 0.0, 0.0, 0.0],
+            },
+            selection_loop_ms: match selection_loop_ms {
+                Some((start_ms, end_ms)) => [start_ms as f32, end_ms as f32, 1.0, 0.0],
+                None => [0.0, 0.0, 0.0, 0.0],
+            },
+            selection_loop_rgba: [
+                (config.appearance.colors.timeline_selection_loop_rgba[0] / 255.0) as f32,
+                (config.appearance.colors.timeline_selection_loop_rgba[1] / 255.0) as f32,
+                (config.appearance.colors.timeline_selection_loop_rgba[2] / 255.0) as f32,
+                config.appearance.colors.timeline_selection_loop_rgba[3] as f32,
+            ],
+            replay_hit_rgba: [
+                (config.appearance.colors.replay_hit_rgba[0] / 255.0) as f32,
+                (config.appearance.colors.replay_hit_rgba[1] / 255.0) as f32,
+                (config.appearance.colors.replay_hit_rgba[2] / 255.0) as f32,
+                config.appearance.colors.replay_hit_rgba[3] as f32,
+            ],
+            replay_miss_rgba: [
+                (config.appearance.colors.replay_miss_rgba[0] / 255.0) as f32,
+                (config.appearance.colors.replay_miss_rgba[1] / 255.0) as f32,
+                (config.appearance.colors.replay_miss_rgba[2] / 255.0) as f32,
+                config.appearance.colors.replay_miss_rgba[3] as f32,
+            ],
+            unsnapped_object_rgba: [
+                (config.appearance.colors.unsnapped_object_rgba[0] / 255.0) as f32,
+                (config.appearance.colors.unsnapped_object_rgba[1] / 255.0) as f32,
+                (config.appearance.colors.unsnapped_object_rgba[2] / 255.0) as f32,
+                config.appearance.colors.unsnapped_object_rgba[3] as f32,
+            ],
+            stats_time_text_packed,
+            hint_text_packed,
+            onboarding_meta: [onboarding_region_count as u32, 0, 0, 0],
+            onboarding_rects,
+            onboarding_text_packed,
+            bottom_timeline_ms: [
+                bottom_timeline_ms.0 as f32,
+                bottom_timeline_ms.1 as f32,
+                if bottom_timeline_ms.0 > 0.0 || bottom_timeline_ms.1 < song_total_ms - 1.0 {
+                    1.0
+                } else {
+                    0.0
+                },
+                0.0,
+            ],
+            past_object_meta: match past_window {
+                Some((from_ms, to_ms)) => [
+                    from_ms as f32,
+                    to_ms as f32,
+                    past_opacity.clamp(0.0, 1.0),
+                    0.0,
+                ],
+                None => [0.0, 0.0, 0.0, 0.0],
+            },
+            past_object_tint_rgba: [
+                (config.appearance.colors.past_object_tint_rgba[0] / 255.0) as f32,
+                (config.appearance.colors.past_object_tint_rgba[1] / 255.0) as f32,
+                (config.appearance.colors.past_object_tint_rgba[2] / 255.0) as f32,
+                config.appearance.colors.past_object_tint_rgba[3] as f32,
+            ],
+            report_meta,
+            report_text_packed,
+            timeline_drag_meta,
+            timeline_drag_marks_ms,
             _pad_end: [0.0, 0.0, 0.0, 0.0],
         };
         self.queue
@@ -4428,10 +5179,20 @@ impl GpuRenderer {
                 self.device.create_bind_group(&wgpu::BindGroupDescriptor {
                     label: Some("snap markers bind group"),
                     layout: &snap_markers_bind_group_layout,
-                    entries: &[wgpu::BindGroupEntry {
-                        binding: 4,
-                        resource: self.snap_markers_buffer.as_entire_binding(),
-                    }],
+                    entries: &[
+                        wgpu::BindGroupEntry {
+                            binding: 4,
+                            resource: self.snap_markers_buffer.as_entire_binding(),
+                        },
+                        wgpu::BindGroupEntry {
+                            binding: 5,
+                            resource: self.flow_segments_buffer.as_entire_binding(),
+                        },
+                        wgpu::BindGroupEntry {
+                            binding: 6,
+                            resource: self.convert_notes_buffer.as_entire_binding(),
+                        },
+                    ],
                 });
         }
 
@@ -4442,6 +5203,20 @@ impl GpuRenderer {
                 bytemuck::cast_slice(snap_markers_upload.as_slice()),
             );
         }
+        if !flow_segments.is_empty() {
+            self.queue.write_buffer(
+                &self.flow_segments_buffer,
+                0,
+                bytemuck::cast_slice(flow_segments.as_slice()),
+            );
+        }
+        if !convert_notes.is_empty() {
+            self.queue.write_buffer(
+                &self.convert_notes_buffer,
+                0,
+                bytemuck::cast_slice(convert_notes.as_slice()),
+            );
+        }
 
         if timeline_points_gpu.len() > self.timeline_points_capacity {
             self.timeline_points_capacity = timeline_points_gpu.len().next_power_of_two().max(1);
@@ -4579,6 +5354,25 @@ impl GpuRenderer {
             );
         }
 
+        if count > self.objects_capacity {
+            self.objects_capacity = count.next_power_of_two().max(1);
+            self.objects_buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("circles buffer (resized)"),
+                size: (self.objects_capacity * std::mem::size_of::<CircleGpu>()) as u64,
+                usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            });
+            let circles_bind_group_layout = self.circles_pipeline.get_bind_group_layout(2);
+            self.objects_bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("circles bind group"),
+                layout: &circles_bind_group_layout,
+                entries: &[wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: self.objects_buffer.as_entire_binding(),
+                }],
+            });
+        }
+
         // Upload only the active instances (after slider buffers so indices are valid).
         self.queue.write_buffer(
             &self.objects_buffer,
@@ -4714,11 +5508,17 @@ impl GpuRenderer {
         let cpu_perf_ms = frame_start.elapsed().as_secs_f64() * 1000.0;
         let gpu_start = Instant::now();
         self.queue.submit(Some(encoder.finish()));
-        output.present();
+        if let Some(output) = output {
+            output.present();
+        }
         let gpu_perf_ms = gpu_start.elapsed().as_secs_f64() * 1000.0;
         let cpu_pass_x10 = (cpu_perf_ms.clamp(0.0, u32::MAX as f64 / 10.0) * 10.0).round() as u32;
         let gpu_pass_x10 = (gpu_perf_ms.clamp(0.0, u32::MAX as f64 / 10.0) * 10.0).round() as u32;
         const PERF_WINDOW: Duration = Duration::from_millis(100);
+        if self.frame_graph.len() >= FRAME_GRAPH_SAMPLES {
+            self.frame_graph.pop_front();
+        }
+        self.frame_graph.push_back((cpu_pass_x10, gpu_pass_x10));
         let now = Instant::now();
         self.cpu_pass_x10 =
             Self::update_recent_peak(&mut self.cpu_pass_history, now, cpu_pass_x10, PERF_WINDOW);
@@ -4727,3 +5527,29 @@ impl GpuRenderer {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_harness::{EditHarness, TEST_MAP, headless_renderer, render_map_state};
+
+    #[test]
+    fn renders_a_frame_headless() {
+        let Some(mut gpu) = headless_renderer(640, 480) else {
+            return;
+        };
+        let harness = EditHarness::new(TEST_MAP);
+        let map_state = harness.map_state();
+        map_state.export();
+        for time_ms in [0.0, 1000.0, 2200.0, 3500.0, 6000.0] {
+            assert!(render_map_state(&mut gpu, &map_state, 640, 480, time_ms).is_ok());
+        }
+    }
+
+    #[test]
+    fn text_outside_the_font_packs_as_the_missing_glyph() {
+        let packed = GpuRenderer::pack_ascii16("a譜b");
+        assert_eq!(packed[0], 0x61 | (127 << 8) | (0x62 << 16));
+        assert_eq!(packed[1..], [0, 0, 0]);
+    }
+}
@@ -7,15 +7,17 @@ pub fn normalize_msaa_samples(samples: u32) -> u32 {
     }
 }
 
-pub fn select_supported_msaa_samples(
+pub fn supported_msaa_samples(
     adapter: &wgpu::Adapter,
     format: wgpu::TextureFormat,
-    requested: u32,
-) -> u32 {
+    adapter_specific_formats: bool,
+) -> Vec<u32> {
     // WebGPU guarantees 1x and 4x for common swapchain formats.
     // 2x/8x (and others) are adapter-specific and require the
     // TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES feature to be enabled.
-    let requested = normalize_msaa_samples(requested);
+    if !adapter_specific_formats {
+        return vec![1, 4];
+    }
 
     let features = adapter.get_texture_format_features(format);
     let flags = features.flags;
@@ -33,7 +35,13 @@ pub fn select_supported_msaa_samples(
 
     supported.sort_unstable();
     supported
-        .into_iter()
+}
+
+pub fn select_supported_msaa_samples(supported: &[u32], requested: u32) -> u32 {
+    let requested = normalize_msaa_samples(requested);
+    supported
+        .iter()
+        .copied()
         .filter(|s| *s <= requested)
         .max()
         .unwrap_or(1)
@@ -157,6 +157,47 @@ struct Globals {
     timeline_past_grayscale_strength: f32,
     timeline_past_tint_rgba: vec4<f32>,
     timeline_past_object_tint_rgba: vec4<f32>,
+    object_fade_ms: vec4<f32>,
+    tooltip_meta: vec4<u32>,
+    tooltip_anchor: vec4<f32>,
+    tooltip_text_packed: array<vec4<u32>, 4>,
+    auto_cursor_rgba: vec4<f32>,
+    auto_cursor_style: vec4<f32>,
+    flow_meta: vec4<u32>,
+    flow_style: vec4<f32>,
+    flow_line_rgba: vec4<f32>,
+    flow_sharp_rgba: vec4<f32>,
+    flow_anti_flow_rgba: vec4<f32>,
+    view_meta: vec4<u32>,
+    frame_graph_meta: vec4<u32>,
+    frame_graph_packed: array<vec4<u32>, 16>,
+    convert_meta: vec4<u32>,
+    convert_rect: vec4<f32>,
+    hit_windows_ms: vec4<f32>,
+    hit_window_300_rgba: vec4<f32>,
+    hit_window_100_rgba: vec4<f32>,
+    hit_window_50_rgba: vec4<f32>,
+    kiai_effect_meta: vec4<f32>,
+    kiai_effect_rgba: vec4<f32>,
+    visible_area_rect: vec4<f32>,
+    ghost_meta: vec4<f32>,
+    selection_loop_ms: vec4<f32>,
+    selection_loop_rgba: vec4<f32>,
+    replay_hit_rgba: vec4<f32>,
+    replay_miss_rgba: vec4<f32>,
+    unsnapped_object_rgba: vec4<f32>,
+    stats_time_text_packed: array<vec4<u32>, 18>,
+    hint_text_packed: array<vec4<u32>, 6>,
+    onboarding_meta: vec4<u32>,
+    onboarding_rects: array<vec4<f32>, 4>,
+    onboarding_text_packed: array<vec4<u32>, 8>,
+    bottom_timeline_ms: vec4<f32>,
+    past_object_meta: vec4<f32>,
+    past_object_tint_rgba: vec4<f32>,
+    report_meta: vec4<u32>,
+    report_text_packed: array<vec4<u32>, 24>,
+    timeline_drag_meta: vec4<u32>,
+    timeline_drag_marks_ms: array<vec4<f32>, 4>,
     _pad_end: vec4<f32>,
 };
 
@@ -223,6 +264,31 @@ var<storage, read> timeline_marks: array<vec2<f32>>;
 @group(2) @binding(4)
 var<storage, read> snap_positions: array<vec2<f32>>;
 
+// flags: 1 sharp angle, 2 anti-flow, 4 angle_deg is set.
+struct FlowSegmentGPU {
+    start_pos: vec2<f32>,
+    end_pos: vec2<f32>,
+    angle_deg: f32,
+    flags: u32,
+    ring_radius: f32,
+    _pad: f32,
+};
+
+@group(2) @binding(5)
+var<storage, read> flow_segments: array<FlowSegmentGPU>;
+
+// kind: 0 don, 1 kat, 2 big don, 3 big kat, 4 drumroll, 5 swell, 6 fruit, 7 droplet,
+// 8 banana shower, 9 mania note, 10 mania hold.
+struct ConvertNoteGPU {
+    x0: f32,
+    x1: f32,
+    lane: f32,
+    kind: u32,
+};
+
+@group(2) @binding(6)
+var<storage, read> convert_notes: array<ConvertNoteGPU>;
+
 struct DigitsMeta {
     // uv' = uv * scale + offset; stored as vec4(scale.xy, offset.zw)
     uv_xform: array<vec4<f32>, 10>,
@@ -273,7 +339,7 @@ struct CircleGPU {
 
     slides: u32,
     selected_side: u32,
-    _pad1: array<u32, 2>,
+    lazy_end_xy: vec2<f32>,
 
     slider_head_rotation: vec2<f32>,
     slider_end_rotation: vec2<f32>,
@@ -316,8 +382,10 @@ struct TimelinePointGPU {
     is_selected: u32,
     is_selected_by_left: u32,
     is_slider_or_spinner: u32,
-    _pad: array<u32, 3>,
+    sv_label: array<u32, 2>,
+    _pad: u32,
     color: vec4<f32>,
+    head_rgba: vec4<f32>,
 };
 
 struct TimelineXBoxGPU {
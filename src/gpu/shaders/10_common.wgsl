@@ -76,6 +76,7 @@ fn glyph5x7_row_bits(ch: u32, row: u32) -> u32 {
     // Punctuation
     if (c == 45u) { let r = array<u32, 7>(0u, 0u, 0u, 14u, 0u, 0u, 0u); return r[row]; } // -
     if (c == 46u) { let r = array<u32, 7>(0u, 0u, 0u, 0u, 0u, 4u, 4u); return r[row]; } // .
+    if (c == 47u) { let r = array<u32, 7>(1u, 1u, 2u, 4u, 8u, 16u, 16u); return r[row]; } // /
     if (c == 58u) { let r = array<u32, 7>(0u, 4u, 4u, 0u, 4u, 4u, 0u); return r[row]; } // :
     if (c == 95u) { let r = array<u32, 7>(0u, 0u, 0u, 0u, 0u, 0u, 31u); return r[row]; } // _
     if (c == 124u) { let r = array<u32, 7>(4u, 4u, 4u, 4u, 4u, 4u, 4u); return r[row]; } // |
@@ -108,6 +109,9 @@ fn glyph5x7_row_bits(ch: u32, row: u32) -> u32 {
     if (c == 89u) { let r = array<u32, 7>(17u, 10u, 4u, 4u, 4u, 4u, 4u); return r[row]; } // Y
     if (c == 90u) { let r = array<u32, 7>(31u, 1u, 2u, 4u, 8u, 16u, 31u); return r[row]; } // Z
 
+    // Box for chars the font doesn't have, the CPU side maps all non-ASCII text to 127.
+    if (c == 127u) { let r = array<u32, 7>(31u, 17u, 17u, 17u, 17u, 17u, 31u); return r[row]; }
+
     return 0u;
 }
 
@@ -331,7 +335,100 @@ fn saturate(x: f32) -> f32 {
     return clamp(x, 0.0, 1.0);
 }
 
+// Editor fade envelope. Full opacity is always reached at the gameplay moment
+// (preempt / 3 before the hit); a fade-in override only changes how long the ramp is.
+fn object_fade_in_alpha(now_ms: f32, time_ms: f32, preempt_ms: f32) -> f32 {
+    let override_ms = globals.object_fade_ms.x;
+    let fade_in_ms = max(select(preempt_ms * 0.6666666666666666666666666667, override_ms, override_ms > 0.0), 1e-6);
+    let opaque_ms = time_ms - preempt_ms * 0.3333333333333333333333333333;
+    return saturate((now_ms - (opaque_ms - fade_in_ms)) / fade_in_ms);
+}
+
+fn object_fade_out_ms() -> f32 {
+    return max(globals.object_fade_ms.y, 1e-6);
+}
+
+// View presets, see `ViewPreset` in render.rs.
+const VIEW_DESIGN: u32 = 0u;
+const VIEW_COMPOSE: u32 = 1u;
+const VIEW_TIMING: u32 = 2u;
+
+// Compose keeps every visible object fully opaque, Timing dims them behind the timing marks.
+fn view_object_alpha(alpha: f32) -> f32 {
+    let view = globals.view_meta.x;
+    if (view == VIEW_COMPOSE) {
+        return select(0.0, 1.0, alpha > 1e-4);
+    }
+    if (view == VIEW_TIMING) {
+        return alpha * 0.35;
+    }
+    return alpha;
+}
+
+// Potato mode draws flat white shapes in place of skin sprites, at `uv` of the sprite they
+// stand in for. Combo numbers are still sampled.
+fn is_potato_mode() -> bool {
+    return globals.view_meta.y != 0u;
+}
+
+fn potato_disc(uv: vec2<f32>, radius: f32) -> vec4<f32> {
+    let d = length(uv - vec2<f32>(0.5));
+    return vec4<f32>(1.0, 1.0, 1.0, 1.0 - smoothstep(radius - 0.01, radius + 0.01, d));
+}
+
+fn potato_ring(uv: vec2<f32>, radius: f32, width: f32) -> vec4<f32> {
+    let d = length(uv - vec2<f32>(0.5));
+    let outer = 1.0 - smoothstep(radius - 0.01, radius + 0.01, d);
+    let inner = smoothstep(radius - width - 0.01, radius - width + 0.01, d);
+    return vec4<f32>(1.0, 1.0, 1.0, outer * inner);
+}
+
+// Map milliseconds the approach circle takes to close. With real-time approach on for the
+// view preset (view_meta.z) that is the preempt scaled by a playback rate below 1x, so the
+// circle closes in the same wall-clock time as in game.
+fn approach_preempt_ms(preempt_ms: f32) -> f32 {
+    if (globals.view_meta.z == 0u || globals.playback_rate <= 0.0) {
+        return preempt_ms;
+    }
+    return preempt_ms * min(globals.playback_rate, 1.0);
+}
+
+// Paused objects near the playhead stay visible at ghost_meta.z, see `ghost_window_ms`.
+fn is_ghost_window_object(t_ms: f32) -> bool {
+    let g = globals.ghost_meta;
+    return g.z > 0.0 && t_ms >= g.x && t_ms <= g.y;
+}
+
+// Paused objects up to the playhead from past_object_meta.x stay visible at
+// past_object_meta.z, see `past_object_count`.
+fn is_past_object(t_ms: f32) -> bool {
+    let p = globals.past_object_meta;
+    return p.z > 0.0 && t_ms >= p.x && t_ms <= p.y;
+}
+
+fn is_ghost_object(t_ms: f32) -> bool {
+    return is_ghost_window_object(t_ms) || is_past_object(t_ms);
+}
+
+fn ghost_object_alpha(alpha: f32, t_ms: f32) -> f32 {
+    let ghost_alpha = select(alpha, max(alpha, globals.ghost_meta.z), is_ghost_window_object(t_ms));
+    return select(ghost_alpha, max(ghost_alpha, globals.past_object_meta.z), is_past_object(t_ms));
+}
+
+// Premultiplied `pm` of an object starting at `t_ms` and over at `end_ms`, mixed towards the
+// past object tint once it is over.
+fn past_object_tinted(pm: vec3<f32>, a: f32, t_ms: f32, end_ms: f32) -> vec3<f32> {
+    if (!is_past_object(t_ms) || globals.time_ms <= end_ms) {
+        return pm;
+    }
+    let tint = globals.past_object_tint_rgba;
+    return mix(pm, tint.rgb * a, clamp(tint.a, 0.0, 1.0));
+}
+
 fn fade_out_grow(now_ms: f32, t_ms: f32, fade_out_ms: f32) -> f32 {
+    if (globals.view_meta.x == VIEW_COMPOSE) {
+        return 1.0;
+    }
     // Grow from 1.0 -> 1.25 while fading out.
     let t = saturate((now_ms - t_ms) / max(fade_out_ms, 1e-6));
     // Ease-out so the growth is noticeable before alpha gets too low.
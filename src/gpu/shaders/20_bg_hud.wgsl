@@ -41,9 +41,26 @@ fn vs_hud(@builtin(vertex_index) vid: u32) -> HudOut {
     return out;
 }
 
+// The map time range the bottom timeline shows, the whole song until it is zoomed into.
+fn bottom_timeline_range(total: f32) -> vec2<f32> {
+    let range = globals.bottom_timeline_ms.xy;
+    return select(vec2<f32>(0.0, total), range, range.y > range.x);
+}
+
+// Where `time_ms` falls across the bottom timeline, 0 at the start of its range and 1 at the end.
+fn bottom_timeline_frac(time_ms: f32, total: f32) -> f32 {
+    let range = bottom_timeline_range(total);
+    return clamp((time_ms - range.x) / max(range.y - range.x, 1.0), 0.0, 1.0);
+}
+
+fn bottom_timeline_shows(time_ms: f32, total: f32) -> bool {
+    let range = bottom_timeline_range(total);
+    return time_ms >= range.x && time_ms <= range.y;
+}
+
 fn timeline_fill_x(total: f32, bar_x0: f32, bar_x1: f32) -> f32 {
     let t = clamp(globals.time_ms, 0.0, total);
-    let frac = select(0.0, clamp(t / max(total, 1.0), 0.0, 1.0), total > 0.0);
+    let frac = select(0.0, bottom_timeline_frac(t, total), total > 0.0);
     return mix(bar_x0, bar_x1, frac);
 }
 
@@ -51,15 +68,16 @@ fn px_in_timeline_interval(px_x: f32, total: f32, bar_x0: f32, bar_x1: f32, coun
     if (total <= 0.0) {
         return false;
     }
+    let range = bottom_timeline_range(total);
     for (var i: u32 = 0u; i < count; i = i + 1u) {
         let interval = timeline_marks[i];
         let start_ms = interval.x;
         let end_ms = interval.y;
-        if (end_ms <= start_ms) {
+        if (end_ms <= start_ms || end_ms < range.x || start_ms > range.y) {
             continue;
         }
-        let start_frac = clamp(start_ms / max(total, 1.0), 0.0, 1.0);
-        let end_frac = clamp(end_ms / max(total, 1.0), 0.0, 1.0);
+        let start_frac = bottom_timeline_frac(start_ms, total);
+        let end_frac = bottom_timeline_frac(end_ms, total);
         let x0 = mix(bar_x0, bar_x1, min(start_frac, end_frac));
         let x1 = mix(bar_x0, bar_x1, max(start_frac, end_frac));
         if (px_x >= x0 && px_x <= x1) {
@@ -76,6 +94,10 @@ struct TimelineSliderBoxOut {
     @location(2) @interpolate(flat) segment_count: u32,
 };
 
+fn sv_label_char(label: array<u32, 2>, index: u32) -> u32 {
+    return (label[index / 4u] >> (8u * (index % 4u))) & 255u;
+}
+
 @vertex
 fn vs_timeline_slider_boxes(
     @builtin(vertex_index) vid: u32,
@@ -282,7 +304,7 @@ fn fs_timeline_slider_boxes(
                             * smoothstep(body_r - aa, body_r + aa, pd);
 
                         let start_body_a = start_body_cov * globals.timeline_slider_head_body_rgba.a * opacity;
-                        let start_outline_a = start_outline_cov * globals.timeline_slider_head_overlay_rgba.a * opacity;
+                        let start_outline_a = start_outline_cov * pt.head_rgba.a * opacity;
 
                         var ptmp = over_pm(
                             group_pm,
@@ -295,7 +317,7 @@ fn fs_timeline_slider_boxes(
                         ptmp = over_pm(
                             group_pm,
                             group_a,
-                            vec4<f32>(globals.timeline_slider_head_overlay_rgba.rgb, start_outline_a),
+                            vec4<f32>(pt.head_rgba.rgb, start_outline_a),
                         );
                         group_pm = ptmp.rgb;
                         group_a = ptmp.a;
@@ -307,6 +329,32 @@ fn fs_timeline_slider_boxes(
                 marker_layer = marker_layer + 1u;
             }
 
+            // SV multiplier label in the body right after the head, once zoomed in far enough
+            // for it to fit before the next point.
+            if (p0.is_slide_start != 0u && p0.sv_label[0] != 0u) {
+                let text_h = max(7.0, body_r);
+                let adv = text_h * 6.0 / 7.0;
+                let label_x0 = p0.x + outer_r + 0.5 * adv;
+                var char_count: u32 = 0u;
+                loop {
+                    if (char_count >= 8u || sv_label_char(p0.sv_label, char_count) == 0u) {
+                        break;
+                    }
+                    char_count = char_count + 1u;
+                }
+                let next_x = timeline_points[i + 1u].x;
+                if (label_x0 + f32(char_count) * adv + body_r <= next_x) {
+                    var label_a: f32 = 0.0;
+                    for (var c: u32 = 0u; c < char_count; c = c + 1u) {
+                        let tl = vec2<f32>(label_x0 + f32(c) * adv, p0.center_y - 0.5 * text_h);
+                        label_a = max(label_a, glyph5x7_alpha(px, tl, text_h, sv_label_char(p0.sv_label, c)));
+                    }
+                    let ltmp = over_pm(group_pm, group_a, vec4<f32>(vec3<f32>(1.0), label_a * 0.9 * opacity));
+                    group_pm = ltmp.rgb;
+                    group_a = ltmp.a;
+                }
+            }
+
             // Composite this group UNDER previously accumulated groups so smaller X
             // (earlier loop iterations) stay visually on top.
             out_pm = out_pm + group_pm * (1.0 - out_a);
@@ -357,6 +405,22 @@ fn timeline_time_to_top_box_x(time_ms: f32, x0: f32, x1: f32) -> f32 {
     return mix(x0, x1, t);
 }
 
+// Innermost hit window the x falls in, measured from the current time on the top timeline.
+fn hit_window_rgba(x: f32, x0: f32, x1: f32) -> vec4<f32> {
+    let span = max(globals.timeline_window_ms.y - globals.timeline_window_ms.x, 1.0);
+    let ms_per_px = span / max(x1 - x0, 1.0);
+    let offset_ms = abs(x - globals.timeline_current_x) * ms_per_px;
+    var rgba = vec4<f32>(0.0);
+    if (offset_ms <= globals.hit_windows_ms.x) {
+        rgba = globals.hit_window_300_rgba;
+    } else if (offset_ms <= globals.hit_windows_ms.y) {
+        rgba = globals.hit_window_100_rgba;
+    } else if (offset_ms <= globals.hit_windows_ms.z) {
+        rgba = globals.hit_window_50_rgba;
+    }
+    return vec4<f32>(rgba.rgb, clamp(rgba.a, 0.0, 1.0));
+}
+
 fn selection_quad_left() -> array<vec2<f32>, 4> {
     return array<vec2<f32>, 4>(
         vec2<f32>(globals.selection_quad_left_01.x, globals.selection_quad_left_01.y),
@@ -474,6 +538,37 @@ const SC_SELECTION_ORIGIN_CLICKED: u32 = 9u;
 const SC_SELECTION_ORIGIN_LOCKED: u32 = 10u;
 const SC_SELECTION_COMBO_COLOR: u32 = 11u;
 
+// Square scale handles on the corners and edge midpoints of a selection quad.
+fn selection_scale_handles_alpha(point: vec2<f32>, quad: array<vec2<f32>, 4>, half_size: f32) -> f32 {
+    var a = 0.0;
+    for (var i: u32 = 0u; i < 4u; i = i + 1u) {
+        let corner = quad[i];
+        let mid = (quad[i] + quad[(i + 1u) % 4u]) * 0.5;
+        let dc = abs(point - corner);
+        let dm = abs(point - mid);
+        a = max(a, select(0.0, 1.0, max(dc.x, dc.y) <= half_size));
+        a = max(a, select(0.0, 1.0, max(dm.x, dm.y) <= half_size));
+    }
+    return a;
+}
+
+// Knob outside the middle of edge 0 with a stem back to the edge, matches
+// selection_rotation_handle_pos in hitbox_handlers.rs.
+fn selection_rotation_handle_alpha(point: vec2<f32>, quad: array<vec2<f32>, 4>, offset: f32, radius: f32) -> f32 {
+    let mid = (quad[0] + quad[1]) * 0.5;
+    let center = (quad[0] + quad[1] + quad[2] + quad[3]) * 0.25;
+    var outward = vec2<f32>(0.0, -1.0);
+    if (dot(mid - center, mid - center) > 1e-12) {
+        outward = normalize(mid - center);
+    }
+    let knob = mid + outward * offset;
+    let knob_d = length(point - knob);
+    let ring_a = 1.0 - smoothstep(1.5, 2.5, abs(knob_d - radius));
+    let stem_a = segment_line_alpha(point, mid, knob - outward * radius, 1.0, 1.0);
+    let fill_a = select(0.0, 0.35, knob_d < radius);
+    return max(max(ring_a, stem_a), fill_a);
+}
+
 fn side_selection_color(side: u32, index: u32) -> vec4<f32> {
     return select(globals.left_selection_colors[index], globals.right_selection_colors[index], side == 1u);
 }
@@ -923,6 +1018,48 @@ fn packed_name_alpha(
     return line_a;
 }
 
+// Up to 16 chars packed 4 per u32, ending at the first zero.
+fn packed_ascii16_alpha(px: vec2<f32>, tl: vec2<f32>, text_h: f32, adv: f32, packed: vec4<u32>) -> f32 {
+    var line_a: f32 = 0.0;
+    var x = tl.x;
+    for (var i: u32 = 0u; i < 16u; i = i + 1u) {
+        let ch = unpack_ascii_char(packed[i / 4u], i % 4u);
+        if (ch == 0u) {
+            break;
+        }
+        line_a = max(line_a, glyph5x7_alpha(px, vec2<f32>(x, tl.y), text_h, ch));
+        x = x + adv;
+    }
+    return line_a;
+}
+
+fn packed_ascii16_len(packed: vec4<u32>) -> u32 {
+    for (var i: u32 = 0u; i < 16u; i = i + 1u) {
+        if (unpack_ascii_char(packed[i / 4u], i % 4u) == 0u) {
+            return i;
+        }
+    }
+    return 16u;
+}
+
+// Up to 96 chars in rows of 16, a row only continues the text when the one before it is full.
+fn hint_text_len() -> u32 {
+    var len: u32 = 0u;
+    for (var row: u32 = 0u; row < 6u; row = row + 1u) {
+        let row_len = packed_ascii16_len(globals.hint_text_packed[row]);
+        len = len + row_len;
+        if (row_len < 16u) {
+            break;
+        }
+    }
+    return len;
+}
+
+fn tooltip_char_at(index: u32) -> u32 {
+    let packed_row = globals.tooltip_text_packed[index / 16u];
+    return unpack_ascii_char(packed_row[(index % 16u) / 4u], index % 4u);
+}
+
 fn current_state_name_alpha(
     px: vec2<f32>,
     start_x: f32,
@@ -949,6 +1086,100 @@ fn current_state_name_alpha(
     return line_a;
 }
 
+fn convert_note_rgba(kind: u32) -> vec4<f32> {
+    var col = vec4<f32>(1.0);
+    switch kind {
+        case 0u, 2u: { col = vec4<f32>(0.92, 0.27, 0.2, 1.0); }
+        case 1u, 3u: { col = vec4<f32>(0.27, 0.6, 0.92, 1.0); }
+        case 4u: { col = vec4<f32>(0.99, 0.75, 0.1, 0.9); }
+        case 5u: { col = vec4<f32>(1.0, 0.5, 0.1, 0.9); }
+        case 6u: { col = vec4<f32>(0.45, 0.9, 0.3, 1.0); }
+        case 7u: { col = vec4<f32>(0.7, 0.95, 0.6, 1.0); }
+        case 8u: { col = vec4<f32>(1.0, 0.9, 0.3, 0.35); }
+        case 10u: { col = vec4<f32>(0.85, 0.85, 0.85, 0.6); }
+        default: {}
+    }
+    return col;
+}
+
+// Radius of a converted note in pixels for a strip of height `h` (or one mania column).
+fn convert_note_radius(kind: u32, h: f32) -> f32 {
+    var scale = 0.4;
+    switch kind {
+        case 0u, 1u, 4u: { scale = 0.2; }
+        case 2u, 3u, 5u: { scale = 0.3; }
+        case 6u: { scale = 0.12; }
+        case 7u: { scale = 0.06; }
+        default: {}
+    }
+    return h * scale;
+}
+
+// Convert preview strip: taiko notes on one line, catch objects by their x from top to bottom,
+// mania columns as rows. Straight alpha.
+fn convert_preview_rgba(px: vec2<f32>) -> vec4<f32> {
+    let rect = globals.convert_rect;
+    if (globals.convert_meta.x == 0u || px.x < rect.x || px.x > rect.z || px.y < rect.y || px.y > rect.w) {
+        return vec4<f32>(0.0);
+    }
+    let pad = 3.0;
+    let inner_h = max(rect.w - rect.y - pad * 2.0, 1.0);
+    let is_mania = globals.convert_meta.x == 3u;
+    let keys = max(f32(globals.convert_meta.z), 1.0);
+    let note_h = select(inner_h, inner_h / keys, is_mania);
+
+    var out_pm = vec3<f32>(0.0);
+    var out_a: f32 = 0.0;
+    let on_border = px.x <= rect.x + 1.0 || px.x >= rect.z - 1.0 || px.y <= rect.y + 1.0 || px.y >= rect.w - 1.0;
+    let panel = select(vec4<f32>(vec3<f32>(0.0), 0.55), vec4<f32>(vec3<f32>(1.0), 0.5), on_border);
+    var tmp = over_pm(out_pm, out_a, panel);
+    out_pm = tmp.rgb;
+    out_a = tmp.a;
+    if (is_mania) {
+        let column_y = (px.y - rect.y - pad) / note_h;
+        if (abs(column_y - round(column_y)) * note_h < 0.5 && column_y > 0.5 && column_y < keys - 0.5) {
+            tmp = over_pm(out_pm, out_a, vec4<f32>(vec3<f32>(1.0), 0.12));
+            out_pm = tmp.rgb;
+            out_a = tmp.a;
+        }
+    }
+
+    let count = min(globals.convert_meta.y, arrayLength(&convert_notes));
+    for (var i = 0u; i < count; i = i + 1u) {
+        let note = convert_notes[i];
+        let r = convert_note_radius(note.kind, note_h);
+        if (px.x < note.x0 - r - 1.0 || px.x > note.x1 + r + 1.0) {
+            continue;
+        }
+        let y = rect.y + pad + note.lane * inner_h;
+        var a: f32;
+        if (is_mania) {
+            // Notes are short bars, holds run to their end.
+            let inside_x = px.x >= note.x0 - 3.0 && px.x <= note.x1 + 3.0;
+            a = select(0.0, 1.0, inside_x && abs(px.y - y) <= note_h * 0.4);
+        } else {
+            let d = distance_point_to_segment(px, vec2<f32>(note.x0, y), vec2<f32>(note.x1, y));
+            a = 1.0 - smoothstep(r - 1.0, r + 1.0, d);
+        }
+        if (a > 0.0) {
+            let col = convert_note_rgba(note.kind);
+            tmp = over_pm(out_pm, out_a, vec4<f32>(col.rgb, col.a * a));
+            out_pm = tmp.rgb;
+            out_a = tmp.a;
+        }
+    }
+
+    if (abs(px.x - globals.timeline_current_x) < 1.0) {
+        tmp = over_pm(out_pm, out_a, vec4<f32>(vec3<f32>(1.0), 0.8));
+        out_pm = tmp.rgb;
+        out_a = tmp.a;
+    }
+    if (out_a <= 1e-6) {
+        return vec4<f32>(0.0);
+    }
+    return vec4<f32>(out_pm / out_a, out_a);
+}
+
 @fragment
 fn fs_hud(@location(0) uv: vec2<f32>) -> @location(0) vec4<f32> {
     let res = globals.screen_size;
@@ -1007,7 +1238,8 @@ fn fs_hud(@location(0) uv: vec2<f32>) -> @location(0) vec4<f32> {
     let fill_x = timeline_fill_x(total, bar_x0, bar_x1);
     let timeline_rgb = vec3<f32>(1.0);
     let perf_margin = 12.0;
-    let perf_box_h = 85.0;
+    let perf_graph_h = select(0.0, 52.0, globals.frame_graph_meta.y != 0u);
+    let perf_box_h = 85.0 + perf_graph_h;
     let perf_text_h = 14.0;
     let perf_adv = (perf_text_h / 7.0) * 6.0;
     let perf_side_padding = 8.0;
@@ -1021,6 +1253,16 @@ fn fs_hud(@location(0) uv: vec2<f32>) -> @location(0) vec4<f32> {
     let perf_box_y0 = perf_box_y1 - perf_box_h;
     let px_in_perf_box = px.x >= perf_box_x0 && px.x <= perf_box_x1 && px.y >= perf_box_y0 && px.y <= perf_box_y1;
 
+    // --- Convert preview strip, under the stats and status boxes ---
+    {
+        let convert = convert_preview_rgba(px);
+        if (convert.a > 0.0) {
+            let tmp = over_pm(out_pm, out_a, convert);
+            out_pm = tmp.rgb;
+            out_a = tmp.a;
+        }
+    }
+
     // --- Top-left stats box (skin-independent glyphs) ---
     {
         let box = globals.stats_box_rect;
@@ -1065,9 +1307,9 @@ fn fs_hud(@location(0) uv: vec2<f32>) -> @location(0) vec4<f32> {
             let cursor_game_x = (globals.cursor_pos.x - globals.playfield_rect.x) * (512.0 / pf_w);
             let cursor_game_y = (globals.cursor_pos.y - globals.playfield_rect.y) * (384.0 / pf_h);
 
-            // Line 5: VOL_AUDIO
+            // Line 6: VOL_AUDIO
             {
-                let y = box_y0 + 8.0 + line_step * 3.0;
+                let y = box_y0 + 8.0 + line_step * 5.0;
                 var line_a: f32 = 0.0;
                 var x = text_x;
 
@@ -1115,9 +1357,9 @@ fn fs_hud(@location(0) uv: vec2<f32>) -> @location(0) vec4<f32> {
                 }
             }
 
-            // Line 6: VOL_HS
+            // Line 7: VOL_HS
             {
-                let y = box_y0 + 8.0 + line_step * 4.0;
+                let y = box_y0 + 8.0 + line_step * 6.0;
                 var line_a: f32 = 0.0;
                 var x = text_x;
                 line_a = max(line_a, glyph5x7_alpha(px, vec2<f32>(x, y), text_h, 86u)); x = x + adv; // V
@@ -1161,9 +1403,9 @@ fn fs_hud(@location(0) uv: vec2<f32>) -> @location(0) vec4<f32> {
                 }
             }
 
-            // Line 2: RATE
+            // Line 4: RATE
             {
-                let y = box_y0 + 8.0 + line_step * 1.0;
+                let y = box_y0 + 8.0 + line_step * 3.0;
                 var line_a: f32 = 0.0;
                 var x = text_x;
                 line_a = max(line_a, glyph5x7_alpha(px, vec2<f32>(x, y), text_h, 82u)); x = x + adv; // R
@@ -1190,9 +1432,9 @@ fn fs_hud(@location(0) uv: vec2<f32>) -> @location(0) vec4<f32> {
                 }
             }
 
-            // Line 7: X
+            // Line 9: X
             {
-                let y = box_y0 + 8.0 + line_step * 6.0;
+                let y = box_y0 + 8.0 + line_step * 8.0;
                 var line_a: f32 = 0.0;
                 var x = text_x;
                 line_a = max(line_a, glyph5x7_alpha(px, vec2<f32>(x, y), text_h, 88u)); // X
@@ -1230,9 +1472,9 @@ fn fs_hud(@location(0) uv: vec2<f32>) -> @location(0) vec4<f32> {
                 }
             }
 
-            // Line 8: Y
+            // Line 10: Y
             {
-                let y = box_y0 + 8.0 + line_step * 7.0;
+                let y = box_y0 + 8.0 + line_step * 9.0;
                 var line_a: f32 = 0.0;
                 var x = text_x;
                 line_a = max(line_a, glyph5x7_alpha(px, vec2<f32>(x, y), text_h, 89u)); // Y
@@ -1270,6 +1512,23 @@ fn fs_hud(@location(0) uv: vec2<f32>) -> @location(0) vec4<f32> {
                 }
             }
 
+            // Lines 2-3, 12-14 and 15-18: playhead and selected object times and editing totals,
+            // formatted on the CPU
+            for (var row: u32 = 0u; row < 9u; row = row + 1u) {
+                let line = select(row + 1u, row + 9u, row >= 2u);
+                let y = box_y0 + 8.0 + line_step * f32(line);
+                let label = globals.stats_time_text_packed[row * 2u];
+                let value = globals.stats_time_text_packed[row * 2u + 1u];
+                var line_a = packed_ascii16_alpha(px, vec2<f32>(text_x, y), text_h, adv, label);
+                line_a = max(line_a, packed_ascii16_alpha(px, vec2<f32>(value_x, y), text_h, adv, value));
+
+                if (line_a > 0.0) {
+                    let tmp = over_pm(out_pm, out_a, vec4<f32>(text_color.rgb, text_color.a * line_a));
+                    out_pm = tmp.rgb;
+                    out_a = tmp.a;
+                }
+            }
+
             // Line 1: TIME
             {
                 let y = box_y0 + 8.0;
@@ -1712,6 +1971,44 @@ fn fs_hud(@location(0) uv: vec2<f32>) -> @location(0) vec4<f32> {
                 }
             }
 
+            // Frame-time graph: CPU pass stacked under GPU pass, newest frame on the right.
+            if (perf_graph_h > 0.0) {
+                let graph_x0 = text_x;
+                let graph_x1 = value_right_x;
+                let graph_y0 = box_y0 + 8.0 + line_step * 4.0;
+                let graph_y1 = box_y1 - 6.0;
+                if (px.x >= graph_x0 && px.x < graph_x1 && px.y >= graph_y0 && px.y <= graph_y1) {
+                    let graph_h = graph_y1 - graph_y0;
+                    let scale_ms = max(f32(globals.frame_graph_meta.z) * 0.1, 1e-3);
+                    let budget_ms = f32(globals.frame_graph_meta.w) * 0.1;
+                    let count = min(globals.frame_graph_meta.x, 64u);
+                    let col_w = (graph_x1 - graph_x0) / 64.0;
+                    let col = u32(floor((px.x - graph_x0) / col_w));
+                    var graph_col = vec4<f32>(vec3<f32>(1.0), 0.08);
+                    if (col + count >= 64u) {
+                        let i = col + count - 64u;
+                        let packed = globals.frame_graph_packed[i / 4u][i % 4u];
+                        let cpu_ms = f32(packed & 0xFFFFu) * 0.1;
+                        let gpu_ms = f32(packed >> 16u) * 0.1;
+                        let cpu_y = graph_y1 - graph_h * clamp(cpu_ms / scale_ms, 0.0, 1.0);
+                        let total_y = graph_y1 - graph_h * clamp((cpu_ms + gpu_ms) / scale_ms, 0.0, 1.0);
+                        if (px.y >= cpu_y) {
+                            graph_col = vec4<f32>(0.35, 0.75, 1.0, 0.85);
+                        } else if (px.y >= total_y) {
+                            graph_col = vec4<f32>(1.0, 0.6, 0.2, 0.85);
+                        }
+                    }
+                    // Frame budget of the fps limiter.
+                    let budget_y = graph_y1 - graph_h * clamp(budget_ms / scale_ms, 0.0, 1.0);
+                    if (abs(px.y - budget_y) < 0.5) {
+                        graph_col = vec4<f32>(1.0, 0.25, 0.25, 0.9);
+                    }
+                    let tmp = over_pm(out_pm, out_a, graph_col);
+                    out_pm = tmp.rgb;
+                    out_a = tmp.a;
+                }
+            }
+
         }
     }
 
@@ -2390,6 +2687,46 @@ fn fs_hud(@location(0) uv: vec2<f32>) -> @location(0) vec4<f32> {
                 }
             }
 
+            if (!on_border && globals.hit_windows_ms.w != 0.0 && timeline_window_valid()) {
+                let band = hit_window_rgba(px.x, top_bar0_x0, top_bar0_x1);
+                if (band.a > 0.0) {
+                    let t = over_pm(out_pm, out_a, band);
+                    out_pm = t.rgb;
+                    out_a = t.a;
+                }
+            }
+
+            // Onsets (yellow) and triplet ticks (purple) near a selection dragged in time.
+            if (!on_border && timeline_window_valid()) {
+                for (var kind: u32 = 0u; kind < 2u; kind = kind + 1u) {
+                    let count = min(globals.timeline_drag_meta[kind], 8u);
+                    for (var i: u32 = 0u; i < count; i = i + 1u) {
+                        let slot = kind * 8u + i;
+                        let mark_ms = globals.timeline_drag_marks_ms[slot / 4u][slot % 4u];
+                        let mark_x = timeline_time_to_top_box_x(mark_ms, top_bar0_x0, top_bar0_x1);
+                        if (abs(px.x - mark_x) <= 1.0) {
+                            let rgb = select(vec3<f32>(1.0, 0.85, 0.2), vec3<f32>(0.75, 0.35, 1.0), kind == 1u);
+                            let t = over_pm(out_pm, out_a, vec4<f32>(rgb, 0.9));
+                            out_pm = t.rgb;
+                            out_a = t.a;
+                        }
+                    }
+                }
+            }
+
+            // Selection loop range, with solid edges at its bounds.
+            if (!on_border && globals.selection_loop_ms.z != 0.0 && timeline_window_valid()) {
+                let loop_x0 = timeline_time_to_top_box_x(globals.selection_loop_ms.x, top_bar0_x0, top_bar0_x1);
+                let loop_x1 = timeline_time_to_top_box_x(globals.selection_loop_ms.y, top_bar0_x0, top_bar0_x1);
+                if (px.x >= loop_x0 - 1.0 && px.x <= loop_x1 + 1.0) {
+                    let on_edge = px.x <= loop_x0 + 1.0 || px.x >= loop_x1 - 1.0;
+                    let rgba = globals.selection_loop_rgba;
+                    let alpha = select(clamp(rgba.a, 0.0, 1.0), 0.9, on_edge);
+                    let t = over_pm(out_pm, out_a, vec4<f32>(rgba.rgb, alpha));
+                    out_pm = t.rgb;
+                    out_a = t.a;
+                }
+            }
         }
 
         // Box 2
@@ -2458,11 +2795,42 @@ fn fs_hud(@location(0) uv: vec2<f32>) -> @location(0) vec4<f32> {
         }
     }
 
+    // --- Top timeline's window on the bottom timeline, dragged to scroll ---
+    if (total > 0.0 && px.y >= hitbox_y0 && px.y <= hitbox_y1 && px.x >= bar_x0 && px.x <= bar_x1 && !px_in_perf_box) {
+        let window_x0 = mix(bar_x0, bar_x1, bottom_timeline_frac(globals.timeline_window_ms.x, total));
+        let window_x1 = max(mix(bar_x0, bar_x1, bottom_timeline_frac(globals.timeline_window_ms.y, total)), window_x0 + 2.0);
+        if (px.x >= window_x0 && px.x <= window_x1) {
+            let on_border =
+                px.x <= window_x0 + 1.0 ||
+                px.x >= window_x1 - 1.0 ||
+                px.y <= hitbox_y0 + 1.0 ||
+                px.y >= hitbox_y1 - 1.0;
+            let window_rgba = select(vec4<f32>(timeline_rgb, 0.08), vec4<f32>(timeline_rgb, 0.8), on_border);
+            let tmp = over_pm(out_pm, out_a, window_rgba);
+            out_pm = tmp.rgb;
+            out_a = tmp.a;
+        }
+    }
+
+    // --- Zoomed range within the whole song, along the bottom edge of the timeline hitbox ---
+    if (globals.bottom_timeline_ms.z != 0.0 && total > 0.0 && px.y >= hitbox_y1 - 3.0 && px.y <= hitbox_y1 && px.x >= bar_x0 && px.x <= bar_x1 && !px_in_perf_box) {
+        let range_x0 = mix(bar_x0, bar_x1, clamp(globals.bottom_timeline_ms.x / total, 0.0, 1.0));
+        let range_x1 = mix(bar_x0, bar_x1, clamp(globals.bottom_timeline_ms.y / total, 0.0, 1.0));
+        let in_range = px.x >= range_x0 && px.x <= max(range_x1, range_x0 + 2.0);
+        let strip = vec4<f32>(vec3<f32>(0.35, 0.75, 1.0), select(0.3, 1.0, in_range));
+        let tmp = over_pm(out_pm, out_a, strip);
+        out_pm = tmp.rgb;
+        out_a = tmp.a;
+    }
+
     // --- Editor drag rectangles + selection rectangles ---
     {
         let selection_border_px = 10.0;
         let selection_border_aa = 1.0;
         let drag_border_px = 2.0;
+        let selection_handle_px = 5.0;
+        let rotation_handle_offset_px = 28.0;
+        let rotation_handle_radius_px = 7.0;
 
         if (globals.selection_meta.y != 0u) {
             let q = selection_quad_right();
@@ -2510,6 +2878,16 @@ fn fs_hud(@location(0) uv: vec2<f32>) -> @location(0) vec4<f32> {
                 }
             }
 
+            let rotation_a = selection_rotation_handle_alpha(px, q, rotation_handle_offset_px, rotation_handle_radius_px);
+            let handle_a = max(selection_scale_handles_alpha(px, q, selection_handle_px), rotation_a);
+            if (handle_a > 1e-4) {
+                let c = side_selection_color(1u, SC_SELECTION_BORDER_HOVERED);
+                let handle = vec4<f32>(mix(c.rgb, vec3<f32>(1.0), 0.5), max(c.a, 0.9) * handle_a);
+                let tmp = over_pm(out_pm, out_a, handle);
+                out_pm = tmp.rgb;
+                out_a = tmp.a;
+            }
+
             let origin = globals.selection_origin_right.xy;
             let guide_width = 1.0;
             let guide_aa = 1.0;
@@ -2583,6 +2961,16 @@ fn fs_hud(@location(0) uv: vec2<f32>) -> @location(0) vec4<f32> {
                 }
             }
 
+            let rotation_a = selection_rotation_handle_alpha(px, q, rotation_handle_offset_px, rotation_handle_radius_px);
+            let handle_a = max(selection_scale_handles_alpha(px, q, selection_handle_px), rotation_a);
+            if (handle_a > 1e-4) {
+                let c = side_selection_color(0u, SC_SELECTION_BORDER_HOVERED);
+                let handle = vec4<f32>(mix(c.rgb, vec3<f32>(1.0), 0.5), max(c.a, 0.9) * handle_a);
+                let tmp = over_pm(out_pm, out_a, handle);
+                out_pm = tmp.rgb;
+                out_a = tmp.a;
+            }
+
             let origin = globals.selection_origin_left.xy;
             let guide_width = 1.0;
             let guide_aa = 1.0;
@@ -2895,6 +3283,175 @@ fn fs_hud(@location(0) uv: vec2<f32>) -> @location(0) vec4<f32> {
         }
     }
 
+    // --- First-run overlay: everything outside the outlined regions dimmed, each labelled ---
+    let onboarding_count = min(globals.onboarding_meta.x, 4u);
+    if (onboarding_count > 0u) {
+        let accent = vec3<f32>(0.35, 0.75, 1.0);
+        let label_text_h = 14.0;
+        let label_adv = (label_text_h / 7.0) * 6.0;
+        let label_padding = 5.0;
+        var in_region = false;
+        var on_outline = false;
+        for (var i: u32 = 0u; i < onboarding_count; i = i + 1u) {
+            let r = globals.onboarding_rects[i];
+            if (px.x >= r.x && px.x <= r.z && px.y >= r.y && px.y <= r.w) {
+                in_region = true;
+                on_outline = on_outline || px.x <= r.x + 2.0 || px.x >= r.z - 2.0 || px.y <= r.y + 2.0 || px.y >= r.w - 2.0;
+            }
+        }
+        if (!in_region) {
+            let dim = over_pm(out_pm, out_a, vec4<f32>(vec3<f32>(0.0), 0.6));
+            out_pm = dim.rgb;
+            out_a = dim.a;
+        }
+        if (on_outline) {
+            let outline = over_pm(out_pm, out_a, vec4<f32>(accent, 0.95));
+            out_pm = outline.rgb;
+            out_a = outline.a;
+        }
+
+        for (var i: u32 = 0u; i < onboarding_count; i = i + 1u) {
+            let r = globals.onboarding_rects[i];
+            let first = globals.onboarding_text_packed[i * 2u];
+            let second = globals.onboarding_text_packed[i * 2u + 1u];
+            let label_len = packed_ascii16_len(first) + select(0u, packed_ascii16_len(second), packed_ascii16_len(first) == 16u);
+            let label_x0 = r.x + 6.0;
+            let label_y0 = r.y + 6.0;
+            let label_x1 = label_x0 + label_adv * f32(label_len) + label_padding * 2.0;
+            let label_y1 = label_y0 + label_text_h + label_padding * 2.0;
+            if (px.x >= label_x0 && px.x <= label_x1 && px.y >= label_y0 && px.y <= label_y1) {
+                let panel_blend = over_pm(out_pm, out_a, vec4<f32>(accent * 0.25, 0.9));
+                out_pm = panel_blend.rgb;
+                out_a = panel_blend.a;
+
+                let tl = vec2<f32>(label_x0 + label_padding, label_y0 + label_padding);
+                var text_a = packed_ascii16_alpha(px, tl, label_text_h, label_adv, first);
+                text_a = max(text_a, packed_ascii16_alpha(px, tl + vec2<f32>(label_adv * 16.0, 0.0), label_text_h, label_adv, second));
+                if (text_a > 1e-4) {
+                    let text_blend = over_pm(out_pm, out_a, vec4<f32>(vec3<f32>(1.0), text_a));
+                    out_pm = text_blend.rgb;
+                    out_a = text_blend.a;
+                }
+            }
+        }
+    }
+
+    // --- Hint line with the shortcuts of the active tool mode, above the bottom timeline ---
+    let hint_len = hint_text_len();
+    if (hint_len > 0u) {
+        let hint_text_h = 12.0;
+        let hint_adv = (hint_text_h / 7.0) * 6.0;
+        let hint_padding = 4.0;
+        let hint_x0 = play_pause_x1 + 8.0;
+        let hint_x1 = hint_x0 + hint_adv * f32(hint_len) + hint_padding * 2.0;
+        let hint_y1 = hitbox_y0 - 4.0;
+        let hint_y0 = hint_y1 - hint_text_h - hint_padding * 2.0;
+        if (px.x >= hint_x0 && px.x <= hint_x1 && px.y >= hint_y0 && px.y <= hint_y1) {
+            let panel_blend = over_pm(out_pm, out_a, vec4<f32>(vec3<f32>(0.0), 0.6));
+            out_pm = panel_blend.rgb;
+            out_a = panel_blend.a;
+
+            var text_a: f32 = 0.0;
+            for (var row: u32 = 0u; row < 6u; row = row + 1u) {
+                let tl = vec2<f32>(hint_x0 + hint_padding + hint_adv * 16.0 * f32(row), hint_y0 + hint_padding);
+                text_a = max(text_a, packed_ascii16_alpha(px, tl, hint_text_h, hint_adv, globals.hint_text_packed[row]));
+            }
+            if (text_a > 1e-4) {
+                let text_blend = over_pm(out_pm, out_a, vec4<f32>(vec3<f32>(1.0), 0.9 * text_a));
+                out_pm = text_blend.rgb;
+                out_a = text_blend.a;
+            }
+        }
+    }
+
+    // --- Report panel of the last map check, top right below the top timeline ---
+    let report_line_count = min(globals.report_meta.x, 8u);
+    if (report_line_count > 0u) {
+        let report_text_h = 12.0;
+        let report_adv = (report_text_h / 7.0) * 6.0;
+        let report_padding = 6.0;
+        let report_line_step = report_text_h + 6.0;
+        let report_x1 = res.x - perf_margin;
+        let report_x0 = report_x1 - report_adv * f32(globals.report_meta.y) - report_padding * 2.0;
+        let report_y0 = top_bar0_y1 + perf_margin;
+        let report_y1 = report_y0 + report_line_step * f32(report_line_count) - 6.0 + report_padding * 2.0;
+        if (px.x >= report_x0 && px.x <= report_x1 && px.y >= report_y0 && px.y <= report_y1) {
+            let panel_blend = over_pm(out_pm, out_a, vec4<f32>(vec3<f32>(0.0), 0.7));
+            out_pm = panel_blend.rgb;
+            out_a = panel_blend.a;
+
+            // The first line is the heading, the findings below it are dimmer.
+            let line = min(u32(max(px.y - report_y0 - report_padding, 0.0) / report_line_step), report_line_count - 1u);
+            let line_y = report_y0 + report_padding + report_line_step * f32(line);
+            var text_a: f32 = 0.0;
+            for (var chunk: u32 = 0u; chunk < 3u; chunk = chunk + 1u) {
+                let tl = vec2<f32>(report_x0 + report_padding + report_adv * 16.0 * f32(chunk), line_y);
+                text_a = max(text_a, packed_ascii16_alpha(px, tl, report_text_h, report_adv, globals.report_text_packed[line * 3u + chunk]));
+            }
+            if (text_a > 1e-4) {
+                let text_rgba = select(vec4<f32>(vec3<f32>(1.0), 0.8 * text_a), vec4<f32>(0.35, 0.75, 1.0, text_a), line == 0u);
+                let text_blend = over_pm(out_pm, out_a, text_rgba);
+                out_pm = text_blend.rgb;
+                out_a = text_blend.a;
+            }
+        }
+    }
+
+    // --- Tooltip of the hovered control, below-right of the cursor ---
+    // A pinned warning uses the same panel, with its corner at the anchor.
+    let tooltip_len = min(globals.tooltip_meta.x, 64u);
+    if (tooltip_len > 0u) {
+        let pinned = globals.tooltip_meta.y != 0u;
+        let tip_text_h = 14.0;
+        let tip_adv = (tip_text_h / 7.0) * 6.0;
+        let tip_padding = 6.0;
+        let tip_w = tip_adv * f32(tooltip_len) + tip_padding * 2.0;
+        let tip_h = tip_text_h + tip_padding * 2.0;
+        let anchor = globals.tooltip_anchor.xy;
+        var tip_x0 = select(anchor.x + 16.0, anchor.x, pinned);
+        var tip_y0 = select(anchor.y + 20.0, anchor.y, pinned);
+        // Flip to the other side of the cursor near the window edges.
+        if (tip_x0 + tip_w > res.x) {
+            tip_x0 = max(0.0, anchor.x - tip_w - 4.0);
+        }
+        if (tip_y0 + tip_h > res.y) {
+            tip_y0 = max(0.0, anchor.y - tip_h - 4.0);
+        }
+        let tip_x1 = tip_x0 + tip_w;
+        let tip_y1 = tip_y0 + tip_h;
+
+        if (px.x >= tip_x0 && px.x <= tip_x1 && px.y >= tip_y0 && px.y <= tip_y1) {
+            let border = 1.0;
+            let on_border =
+                px.x <= tip_x0 + border ||
+                px.x >= tip_x1 - border ||
+                px.y <= tip_y0 + border ||
+                px.y >= tip_y1 - border;
+            let border_col = select(
+                vec4<f32>(vec3<f32>(1.0), 0.9),
+                vec4<f32>(1.0, 0.3, 0.2, 0.9),
+                pinned,
+            );
+            let panel = select(vec4<f32>(vec3<f32>(0.0), 0.8), border_col, on_border);
+            let panel_blend = over_pm(out_pm, out_a, panel);
+            out_pm = panel_blend.rgb;
+            out_a = panel_blend.a;
+
+            var text_a: f32 = 0.0;
+            var x = tip_x0 + tip_padding;
+            for (var i: u32 = 0u; i < tooltip_len; i = i + 1u) {
+                let ch = tooltip_char_at(i);
+                text_a = max(text_a, glyph5x7_alpha(px, vec2<f32>(x, tip_y0 + tip_padding), tip_text_h, ch));
+                x = x + tip_adv;
+            }
+            if (text_a > 1e-4) {
+                let text_blend = over_pm(out_pm, out_a, vec4<f32>(vec3<f32>(1.0), 0.95 * text_a));
+                out_pm = text_blend.rgb;
+                out_a = text_blend.a;
+            }
+        }
+    }
+
     out_pm = out_pm * opacity;
     out_a = out_a * opacity;
 
@@ -3063,37 +3620,110 @@ fn fs_timeline_bookmarks(@location(0) uv: vec2<f32>) -> @location(0) vec4<f32> {
 
     let hitbox_mid_y = hitbox_y0 + (hitbox_y1 - hitbox_y0) * 0.5;
 
+    // The timing view emphasizes red lines: wider and over the full height, above bookmarks.
+    let timing_view = globals.view_meta.x == VIEW_TIMING;
+    let red_line_w = select(1.0, 3.0, timing_view);
+    if (timing_view || px.y > hitbox_mid_y) {
+        for (var i: u32 = 0u; i < red_line_count; i = i + 1u) {
+            let idx = bookmark_count + i;
+            let red_line_ms = timeline_marks[idx].x;
+            if (!bottom_timeline_shows(red_line_ms, total)) {
+                continue;
+            }
+            let red_line_frac = bottom_timeline_frac(red_line_ms, total);
+            let bx = mix(bar_x0, bar_x1, red_line_frac) - floor(red_line_w * 0.5);
+            if (px.x >= bx && px.x < bx + red_line_w) {
+                let marker = vec4<f32>(vec3<f32>(1.0, 0.2, 0.2), opacity);
+                return marker;
+            }
+        }
+    }
+
     if (px.y <= hitbox_mid_y) {
         for (var i: u32 = 0u; i < bookmark_count; i = i + 1u) {
             let bookmark_ms = timeline_marks[i].x;
-            let bookmark_frac = clamp(bookmark_ms / max(total, 1.0), 0.0, 1.0);
+            if (!bottom_timeline_shows(bookmark_ms, total)) {
+                continue;
+            }
+            let bookmark_frac = bottom_timeline_frac(bookmark_ms, total);
             let bx = mix(bar_x0, bar_x1, bookmark_frac);
             if (px.x >= bx && px.x < bx + 1.0) {
                 let marker = vec4<f32>(vec3<f32>(0.2, 0.45, 1.0), opacity);
                 return marker;
             }
         }
-        discard;
     }
 
-    for (var i: u32 = 0u; i < red_line_count; i = i + 1u) {
-        let idx = bookmark_count + i;
-        let red_line_ms = timeline_marks[idx].x;
-        let red_line_frac = clamp(red_line_ms / max(total, 1.0), 0.0, 1.0);
-        let bx = mix(bar_x0, bar_x1, red_line_frac);
-        if (px.x >= bx && px.x < bx + 1.0) {
-            let marker = vec4<f32>(vec3<f32>(1.0, 0.2, 0.2), opacity);
-            return marker;
+    discard;
+}
+
+// Stars thrown up from both bottom corners, each one relaunched every lifetime. Stars whose
+// launch would be before the kiai section started are left out, so the section opens with a
+// burst.
+fn kiai_fountain_alpha(px: vec2<f32>, res: vec2<f32>, since_ms: f32) -> f32 {
+    let lifetime_ms = 1400.0;
+    let gravity = 1.2 * res.y;
+    var alpha = 0.0;
+    for (var i: u32 = 0u; i < 24u; i = i + 1u) {
+        let seed0 = fract(sin(f32(i) * 12.9898) * 43758.5453);
+        let seed1 = fract(sin(f32(i) * 78.233) * 12543.123);
+        let age_ms = (since_ms + seed0 * lifetime_ms) % lifetime_ms;
+        if (since_ms - age_ms < 0.0) {
+            continue;
         }
-    }
+        let t = age_ms / 1000.0;
+        let from_right = (i & 1u) == 1u;
+        let side = select(1.0, -1.0, from_right);
+        let origin = vec2<f32>(select(0.0, res.x, from_right), res.y);
+        let velocity = vec2<f32>(side * (0.1 + 0.2 * seed1) * res.x, -(0.9 + 0.4 * seed0) * res.y);
+        let pos = origin + velocity * t + vec2<f32>(0.0, 0.5 * gravity * t * t);
+        let fade = 1.0 - age_ms / lifetime_ms;
+        let radius = 2.0 + 3.0 * fade;
+        let d = length(px - pos);
+        alpha = max(alpha, fade * (1.0 - smoothstep(radius - 1.0, radius + 1.0, d)));
+    }
+    return alpha;
+}
 
-    discard;
+// Kiai preview over the background: a pulse and a flash along the window edges on the bass,
+// fountains that follow the overall loudness.
+fn apply_kiai_effects(px: vec2<f32>, res: vec2<f32>, dst: vec4<f32>) -> vec4<f32> {
+    let kiai = globals.kiai_effect_meta;
+    let color = globals.kiai_effect_rgba;
+    var out_pm = dst.rgb;
+    var out_a = dst.a;
+    let bass = kiai.y * kiai.y * kiai.y;
+
+    out_pm = min(out_pm + color.rgb * color.a * 0.25 * bass * out_a, vec3<f32>(out_a));
+
+    let edge = min(min(px.x, res.x - px.x), min(px.y, res.y - px.y));
+    let flash = 0.5 * bass * (1.0 - smoothstep(0.0, 0.12 * res.y, edge));
+    if (flash > 1e-4) {
+        let t = over_pm(out_pm, out_a, vec4<f32>(color.rgb, clamp(color.a * flash, 0.0, 1.0)));
+        out_pm = t.rgb;
+        out_a = t.a;
+    }
+
+    let burst = exp(-kiai.x / 800.0);
+    let strength = clamp(burst + kiai.z * kiai.z, 0.0, 1.0);
+    if (strength > 0.01) {
+        let star = kiai_fountain_alpha(px, res, kiai.x) * strength;
+        if (star > 1e-4) {
+            let t = over_pm(out_pm, out_a, vec4<f32>(color.rgb, clamp(color.a * star, 0.0, 1.0)));
+            out_pm = t.rgb;
+            out_a = t.a;
+        }
+    }
+    return vec4<f32>(out_pm, out_a);
 }
 
 @fragment
 fn fs_bg(@location(0) uv: vec2<f32>) -> @location(0) vec4<f32> {
     let uvc = cover_uv(uv);
-    let bg = textureSample(background_tex, skin_samp, uvc);
+    var bg = vec4<f32>(0.0, 0.0, 0.0, 1.0);
+    if (!is_potato_mode()) {
+        bg = textureSample(background_tex, skin_samp, uvc);
+    }
 
     // Output premultiplied RGB.
     var out_pm = bg.rgb * bg.a;
@@ -3159,6 +3789,19 @@ fn fs_bg(@location(0) uv: vec2<f32>) -> @location(0) vec4<f32> {
         out_a = out_a + o_a * (1.0 - out_a);
     }
 
+    // Screen edges at the off-screen warning's aspect ratio, when it isn't 4:3.
+    let va = globals.visible_area_rect;
+    if (abs(va.x - os_x0) > 0.5) {
+        let va_outline_alpha = rect_outline_alpha(px, va.x, va.y, va.z, va.w, 1.0, border_aa);
+        if (va_outline_alpha > 1e-4) {
+            let o = globals.offscreen_osu_tint_rgba;
+            let o_a = clamp(0.5 * break_mul * va_outline_alpha, 0.0, 1.0);
+            let o_pm = o.rgb * o_a;
+            out_pm = out_pm * (1.0 - o_a) + o_pm;
+            out_a = out_a + o_a * (1.0 - out_a);
+        }
+    }
+
     let pf_outline_alpha = rect_outline_alpha(px, pf_x0, pf_y0, pf_x1, pf_y1, 2.0, border_aa);
     if (pf_outline_alpha > 1e-4) {
         let o = globals.playfield_border_rgba;
@@ -3168,5 +3811,8 @@ fn fs_bg(@location(0) uv: vec2<f32>) -> @location(0) vec4<f32> {
         out_a = out_a + o_a * (1.0 - out_a);
     }
 
+    if (globals.kiai_effect_meta.x >= 0.0) {
+        return apply_kiai_effects(px, res, vec4<f32>(out_pm, out_a));
+    }
     return vec4<f32>(out_pm, out_a);
 }
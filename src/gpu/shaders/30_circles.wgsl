@@ -31,14 +31,14 @@ fn vs_main(
     // While fading out, slightly grow the sprite (osu!-like hit animation).
     // Must match fs_main's fade-out timing.
     let now_ms: f32 = globals.time_ms;
-    let fade_out_ms: f32 = 250.0;
+    let fade_out_ms: f32 = object_fade_out_ms();
     let is_selected: bool = p.selected_side != 0u;
     let grow_boost: f32 = 1.2;
     let grow_raw: f32 = fade_out_grow(now_ms, time_ms, fade_out_ms);
     let grow: f32 = select(
         1.0 + (grow_raw - 1.0) * grow_boost,
         1.0,
-        is_selected,
+        is_selected || is_ghost_object(time_ms),
     );
 
     let approach_start = p.approach_circle_start_scale;
@@ -99,13 +99,11 @@ fn fs_main(
     // --- Time-based opacity (shared by hitcircle + approach circle) ---
     // `globals.time_ms` is editor time in milliseconds.
     let now_ms: f32 = globals.time_ms;
-    let appear_ms: f32 = time_ms - preempt_ms;
 
-    let fade_in_ms: f32 = max(preempt_ms * 0.6666666666666666666666666667, 1e-6);
-    let fade_in_alpha: f32 = saturate((now_ms - appear_ms) / fade_in_ms);
+    let fade_in_alpha: f32 = object_fade_in_alpha(now_ms, time_ms, preempt_ms);
 
     // Fade out shortly after hit time.
-    let fade_out_ms: f32 = 250.0;
+    let fade_out_ms: f32 = object_fade_out_ms();
     let fade_out_t: f32 = saturate((now_ms - time_ms) / fade_out_ms);
     let fade_out_alpha: f32 = pow(1.0 - fade_out_t, 2.0);
 
@@ -116,13 +114,13 @@ fn fs_main(
     let grow: f32 = select(
         1.0 + (grow_raw - 1.0) * grow_boost,
         1.0,
-        is_selected,
+        is_selected || is_ghost_object(time_ms),
     );
 
-    let alpha: f32 = fade_in_alpha * fade_out_alpha;
+    let alpha: f32 = view_object_alpha(fade_in_alpha * fade_out_alpha);
     if (alpha <= 1e-4) {
         // Early-out to avoid texture sampling for invisible instances.
-        if (!is_selected) {
+        if (!is_selected && !is_ghost_object(time_ms)) {
             discard;
         }
     }
@@ -158,7 +156,10 @@ fn fs_main(
 
     var base = vec4<f32>(0.0);
     var over = vec4<f32>(0.0);
-    if (hit_in_base || hit_in_over) {
+    if (is_potato_mode()) {
+        base = potato_disc(hit_uv_base, 0.46);
+        over = potato_ring(hit_uv_over, 0.46, 0.06);
+    } else if (hit_in_base || hit_in_over) {
         if (is_slider != 0u) {
             if (hit_in_base) {
                 base = textureSample(slidercircle_tex, skin_samp, hit_uv_base);
@@ -178,11 +179,12 @@ fn fs_main(
 
     // --- Approach circle (behind hitcircle) ---
     var approach_texel = vec4<f32>(0.0);
-    let denom: f32 = max(preempt_ms, 1e-6);
+    let approach_ms: f32 = approach_preempt_ms(preempt_ms);
+    let denom: f32 = max(approach_ms, 1e-6);
     // Clamp the approach-circle scaling animation at hit time so it stays at the end size
     // during fade-out.
     let anim_ms: f32 = min(now_ms, time_ms);
-    let t01: f32 = saturate((anim_ms - appear_ms) / denom);
+    let t01: f32 = saturate((anim_ms - (time_ms - approach_ms)) / denom);
 
     // Scale from start -> end over the preempt window.
     let approach_scale: f32 = mix(approach_start, approach_end, t01);
@@ -190,8 +192,12 @@ fn fs_main(
     let rel: f32 = max(1e-6, approach_scale / (max_scale * grow));
     let approach_uv = (uv - vec2<f32>(0.5)) / rel + vec2<f32>(0.5);
     let approach_in = all(approach_uv >= vec2<f32>(0.0)) && all(approach_uv <= vec2<f32>(1.0));
-    if (approach_in) {
-        approach_texel = textureSample(approach_circle_tex, skin_samp, approach_uv);
+    if (approach_in && globals.view_meta.x != VIEW_COMPOSE) {
+        if (is_potato_mode()) {
+            approach_texel = potato_ring(approach_uv, 0.47, 0.03);
+        } else {
+            approach_texel = textureSample(approach_circle_tex, skin_samp, approach_uv);
+        }
     }
 
     // Tint by combo color; premultiply.
@@ -296,10 +302,10 @@ fn fs_main(
         pm = globals.offscreen_playfield_tint_rgba.rgb * a;
     }
 
-    // If this pixel is outside the 640x480 outer playfield, make the hitcircle
-    // very visible. This is intentionally applied before approach compositing so
-    // approach circles keep their normal appearance.
-    let os = globals.osu_rect;
+    // If this pixel is off screen at the configured aspect ratio (640x480 at 4:3),
+    // make the hitcircle very visible. This is intentionally applied before approach
+    // compositing so approach circles keep their normal appearance.
+    let os = globals.visible_area_rect;
     let outside_osu =
         screen_px.x < os.x || screen_px.x > os.z || screen_px.y < os.y || screen_px.y > os.w;
     if (outside_osu) {
@@ -311,9 +317,11 @@ fn fs_main(
     let selected_fade_in_cap = clamp(globals.selected_fade_in_opacity_cap, 0.0, 1.0);
     let selected_fade_out_cap = clamp(globals.selected_fade_out_opacity_cap, 0.0, 1.0);
     let selected_cap = select(selected_fade_in_cap, selected_fade_out_cap, now_ms > time_ms);
-    let body_alpha = select(alpha, max(alpha, selected_cap), is_selected);
+    let selected_alpha = select(alpha, max(alpha, selected_cap), is_selected);
+    let body_alpha = ghost_object_alpha(selected_alpha, time_ms);
     pm = pm * body_alpha;
     a = a * body_alpha;
+    pm = past_object_tinted(pm, a, time_ms, time_ms);
 
     if (offscreen_tinted && a > 1e-4) {
         let offscreen_alpha_mult: f32 = select(0.6, 0.4, now_ms > time_ms);
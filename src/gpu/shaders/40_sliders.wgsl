@@ -68,22 +68,23 @@ fn fs_slider_box(
     @location(10) @interpolate(flat) bbox_max: vec2<f32>,
 ) -> @location(0) vec4<f32> {
     let now_ms: f32 = globals.time_ms;
-    let appear_ms: f32 = time_ms - preempt_ms;
     let p = circles[obj_iid];
     let is_selected: bool = p.selected_side != 0u;
 
-    let fade_in_ms: f32 = max(preempt_ms * 0.6666666666666666666666666667, 1e-6);
-    let fade_in_alpha: f32 = saturate((now_ms - appear_ms) / fade_in_ms);
+    let fade_in_alpha: f32 = object_fade_in_alpha(now_ms, time_ms, preempt_ms);
 
     // Start fading out when the slider ends (not when it starts).
-    let fade_out_ms: f32 = 250.0;
+    let fade_out_ms: f32 = object_fade_out_ms();
     let fade_out_alpha: f32 = 1.0 - saturate((now_ms - slider_end_time_ms) / fade_out_ms);
 
-    let alpha_raw: f32 = fade_in_alpha * fade_out_alpha;
+    let alpha_raw: f32 = view_object_alpha(fade_in_alpha * fade_out_alpha);
     let selected_fade_in_cap = clamp(globals.selected_fade_in_opacity_cap, 0.0, 1.0);
     let selected_fade_out_cap = clamp(globals.selected_fade_out_opacity_cap, 0.0, 1.0);
     let selected_cap = select(selected_fade_in_cap, selected_fade_out_cap, now_ms > slider_end_time_ms);
-    let alpha: f32 = select(alpha_raw, max(alpha_raw, selected_cap), is_selected);
+    let alpha: f32 = ghost_object_alpha(
+        select(alpha_raw, max(alpha_raw, selected_cap), is_selected),
+        time_ms,
+    );
     if (alpha <= 1e-4) {
         if (!is_selected) {
             discard;
@@ -146,10 +147,10 @@ fn fs_slider_box(
         let outer_t = base_r * outer_t_ratio;
         let outer_r = base_r + outer_t;
 
-        // If this pixel is outside the 640x480 outer playfield, recolor while
+        // If this pixel is off screen at the configured aspect ratio, recolor while
         // preserving the exact AA coverage/alpha from normal slider shading.
         let px = pf.xy + pf_pos * pf_scale;
-        let os = globals.osu_rect;
+        let os = globals.visible_area_rect;
         let outside_osu = px.x < os.x || px.x > os.z || px.y < os.y || px.y > os.w;
 
         let outer_edge_a = 1.0 - smoothstep(outer_r - aa, outer_r + aa, min_d);
@@ -197,6 +198,7 @@ fn fs_slider_box(
     if (out_a <= 1e-6) {
         discard;
     }
+    out_pm = past_object_tinted(out_pm, out_a, time_ms, slider_end_time_ms);
     return vec4<f32>(out_pm, out_a);
 }
 
@@ -226,17 +228,18 @@ fn vs_slider_caps(
     let pf_scale = pf_size / vec2<f32>(512.0, 384.0);
 
     let now_ms: f32 = globals.time_ms;
-    let fade_out_grow_ms: f32 = 250.0;
+    let fade_out_grow_ms: f32 = object_fade_out_ms();
     let grow: f32 = fade_out_grow(now_ms, p.slider_end_time_ms, fade_out_grow_ms);
 
     let scaled_radius = p.radius;
     let endcap_scale = max(skin_meta.sliderendcircle_scale, skin_meta.sliderendcircleoverlay_scale);
     let endcap_extent_pf = max(1e-6, (2.0 * scaled_radius) * endcap_scale) * grow;
-    let arrow_extent_pf = max(1e-6, (2.0 * scaled_radius) * skin_meta.reversearrow_scale);
+    // Includes the largest repeat arrow pulse, see `repeat_arrow_pulse`.
+    let arrow_extent_pf = max(1e-6, (2.0 * scaled_radius) * skin_meta.reversearrow_scale) * 1.3;
     let extent_pf = max(endcap_extent_pf, arrow_extent_pf);
 
-    let bb_min_pf = min(start_pf, end_pf) - vec2<f32>(extent_pf);
-    let bb_max_pf = max(start_pf, end_pf) + vec2<f32>(extent_pf);
+    let bb_min_pf = min(min(start_pf, end_pf), p.lazy_end_xy) - vec2<f32>(extent_pf);
+    let bb_max_pf = max(max(start_pf, end_pf), p.lazy_end_xy) + vec2<f32>(extent_pf);
 
     let corner = corners[vid];
     let uv = corner * 0.5 + vec2<f32>(0.5);
@@ -256,6 +259,37 @@ fn vs_slider_caps(
     return out;
 }
 
+// Repeat `i` (1-based) is the reverse arrow hit at time_ms + i * span. Like gameplay, the first
+// arrow fades in with the slider and each later one fades in when the arrow before it at the
+// same end is hit, two spans earlier. Hit arrows fade out quickly.
+fn repeat_arrow_alpha(p: CircleGPU, i: u32, now_ms: f32) -> f32 {
+    let span_ms = max(p.slider_length_duration_ms, 1e-3);
+    let anim_ms = min(300.0, span_ms);
+    let hit_ms = p.time_ms + f32(i) * span_ms;
+    var fade_in = object_fade_in_alpha(now_ms, p.time_ms, p.preempt_ms);
+    if (i >= 2u) {
+        fade_in = saturate((now_ms - (hit_ms - 2.0 * span_ms)) / (anim_ms * 0.5));
+    }
+    let fade_out = 1.0 - saturate((now_ms - hit_ms) / (anim_ms * 0.5));
+    let a = fade_in * fade_out;
+    if (globals.view_meta.x == VIEW_COMPOSE) {
+        return select(0.0, 1.0, a > 1e-4);
+    }
+    return a;
+}
+
+// Arrows pulse from 1.3x down to their size once per min(300ms, span) while waiting to be hit.
+fn repeat_arrow_pulse(p: CircleGPU, i: u32, now_ms: f32) -> f32 {
+    let span_ms = max(p.slider_length_duration_ms, 1e-3);
+    let anim_ms = min(300.0, span_ms);
+    let hit_ms = p.time_ms + f32(i) * span_ms;
+    if (now_ms >= hit_ms || globals.view_meta.x == VIEW_COMPOSE) {
+        return 1.0;
+    }
+    let t = fract(max(now_ms - p.time_ms, 0.0) / anim_ms);
+    return 1.0 + 0.3 * (1.0 - t) * (1.0 - t);
+}
+
 @fragment
 fn fs_slider_caps(
     @location(0) pf_pos: vec2<f32>,
@@ -265,20 +299,21 @@ fn fs_slider_caps(
     let is_selected: bool = p.selected_side != 0u;
 
     let now_ms: f32 = globals.time_ms;
-    let appear_ms: f32 = p.time_ms - p.preempt_ms;
 
-    let fade_in_ms: f32 = max(p.preempt_ms * 0.6666666666666666666666666667, 1e-6);
-    let fade_in_alpha: f32 = saturate((now_ms - appear_ms) / fade_in_ms);
+    let fade_in_alpha: f32 = object_fade_in_alpha(now_ms, p.time_ms, p.preempt_ms);
 
     // Start fading out when the slider ends (not when it starts).
-    let fade_out_ms: f32 = 250.0;
+    let fade_out_ms: f32 = object_fade_out_ms();
     let fade_out_alpha: f32 = 1.0 - saturate((now_ms - p.slider_end_time_ms) / fade_out_ms);
 
-    let alpha_raw: f32 = fade_in_alpha * fade_out_alpha;
+    let alpha_raw: f32 = view_object_alpha(fade_in_alpha * fade_out_alpha);
     let selected_fade_in_cap = clamp(globals.selected_fade_in_opacity_cap, 0.0, 1.0);
     let selected_fade_out_cap = clamp(globals.selected_fade_out_opacity_cap, 0.0, 1.0);
     let selected_cap = select(selected_fade_in_cap, selected_fade_out_cap, now_ms > p.slider_end_time_ms);
-    let alpha: f32 = select(alpha_raw, max(alpha_raw, selected_cap), is_selected);
+    let alpha: f32 = ghost_object_alpha(
+        select(alpha_raw, max(alpha_raw, selected_cap), is_selected),
+        p.time_ms,
+    );
     if (alpha <= 1e-4) {
         discard;
     }
@@ -315,8 +350,12 @@ fn fs_slider_caps(
     let pf_scale = pf_size / vec2<f32>(512.0, 384.0);
     let px_per_pf = max(1e-6, min(pf_scale.x, pf_scale.y));
 
-    let fade_out_grow_ms: f32 = 250.0;
-    let grow: f32 = select(fade_out_grow(now_ms, p.slider_end_time_ms, fade_out_grow_ms), 1.0, is_selected);
+    let fade_out_grow_ms: f32 = object_fade_out_ms();
+    let grow: f32 = select(
+        fade_out_grow(now_ms, p.slider_end_time_ms, fade_out_grow_ms),
+        1.0,
+        is_selected || is_ghost_object(p.time_ms),
+    );
 
     let scaled_radius = p.radius;
     let endcap_scale = max(skin_meta.sliderendcircle_scale, skin_meta.sliderendcircleoverlay_scale);
@@ -327,8 +366,12 @@ fn fs_slider_caps(
         let uv_end = ((pf_pos - final_end_pf) * pf_scale) / endcap_extent_px + vec2<f32>(0.5);
         let in_end = all(uv_end >= vec2<f32>(0.0)) && all(uv_end <= vec2<f32>(1.0));
         if (in_end) {
-            let base = textureSample(sliderendcircle_tex, skin_samp, uv_end);
-            let over = textureSample(sliderendcircle_overlay_tex, skin_samp, uv_end);
+            var base = potato_disc(uv_end, 0.46);
+            var over = potato_ring(uv_end, 0.46, 0.06);
+            if (!is_potato_mode()) {
+                base = textureSample(sliderendcircle_tex, skin_samp, uv_end);
+                over = textureSample(sliderendcircle_overlay_tex, skin_samp, uv_end);
+            }
 
             // Premultiply and composite base + overlay first.
             let base_a = base.a;
@@ -345,37 +388,65 @@ fn fs_slider_caps(
         }
     }
 
-    // Reverse arrows: show up to 2 repeat markers (end then start), unrotated for now.
-    // slides >= 2 => reverse at end after first slide
-    // slides >= 3 => reverse at start after second slide
-    if (slides >= 2u) {
-        let local_r1 = ((pf_pos - end_pf) * pf_scale) / arrow_extent_px;
-        let uv_r1 = rotate_inv(local_r1, p.slider_end_rotation) + vec2<f32>(0.5);
-        let in_r1 = all(uv_r1 >= vec2<f32>(0.0)) && all(uv_r1 <= vec2<f32>(1.0));
-        if (in_r1) {
-            let t = textureSample(reverse_arrow_tex, skin_samp, uv_r1);
-            let src_a = t.a * alpha;
-            let src_pm = t.rgb * src_a;
-            out_pm = src_pm + out_pm * (1.0 - src_a);
-            out_a = src_a + out_a * (1.0 - src_a);
+    // Reverse arrows: odd repeats sit at the end, even ones at the start. Per end only the next
+    // arrow to be hit and the one before it (still fading out) can be visible. Selected sliders
+    // outside their active time show their first two arrows, as before they are hit.
+    let span_ms = max(p.slider_length_duration_ms, 1e-3);
+    let selected_static = is_selected && (now_ms < p.time_ms || now_ms > p.slider_end_time_ms);
+    let spans_done = select(i32(floor((now_ms - p.time_ms) / span_ms)), -1, selected_static);
+    for (var side: u32 = 0u; side < 2u; side = side + 1u) {
+        let at_end = side == 0u;
+        let arrow_pf = select(start_pf, end_pf, at_end);
+        let rotation = select(p.slider_head_rotation, p.slider_end_rotation, at_end);
+        var next_i = max(spans_done + 1, 1);
+        if ((next_i % 2) != select(0, 1, at_end)) {
+            next_i = next_i + 1;
+        }
+        for (var k: i32 = 0; k < 2; k = k + 1) {
+            let i = next_i - 2 * k;
+            if (i < 1 || u32(i) + 1u > slides) {
+                continue;
+            }
+            let arrow_alpha = select(repeat_arrow_alpha(p, u32(i), now_ms), 1.0, selected_static);
+            if (arrow_alpha <= 1e-4) {
+                continue;
+            }
+            let pulse = repeat_arrow_pulse(p, u32(i), now_ms);
+            let local_r = ((pf_pos - arrow_pf) * pf_scale) / (arrow_extent_px * pulse);
+            let uv_r = rotate_inv(local_r, rotation) + vec2<f32>(0.5);
+            let in_r = all(uv_r >= vec2<f32>(0.0)) && all(uv_r <= vec2<f32>(1.0));
+            if (in_r) {
+                var t = potato_disc(uv_r, 0.2);
+                if (!is_potato_mode()) {
+                    t = textureSample(reverse_arrow_tex, skin_samp, uv_r);
+                }
+                let src_a = t.a * alpha * arrow_alpha;
+                let src_pm = t.rgb * src_a;
+                out_pm = src_pm + out_pm * (1.0 - src_a);
+                out_a = src_a + out_a * (1.0 - src_a);
+            }
         }
     }
 
-    if (slides >= 3u) {
-        let local_r2 = ((pf_pos - start_pf) * pf_scale) / arrow_extent_px;
-        let uv_r2 = rotate_inv(local_r2, p.slider_head_rotation) + vec2<f32>(0.5);
-        let in_r2 = all(uv_r2 >= vec2<f32>(0.0)) && all(uv_r2 <= vec2<f32>(1.0));
-        if (in_r2) {
-            let t = textureSample(reverse_arrow_tex, skin_samp, uv_r2);
-            let src_a = t.a * alpha;
-            let src_pm = t.rgb * src_a;
-            out_pm = src_pm + out_pm * (1.0 - src_a);
+    // Lazy end: where the cursor has to be at the tail judgement. Only drawn when it is
+    // away from the visual tail, lazy_end_xy equals the tail when the marker is disabled.
+    let lazy_offset_px = length((p.lazy_end_xy - final_end_pf) * pf_scale);
+    if (lazy_offset_px > 1.0) {
+        let lazy_dist_px = length((pf_pos - p.lazy_end_xy) * pf_scale);
+        let ring_radius_px = max(3.0, 0.35 * scaled_radius * px_per_pf);
+        let ring = 1.0 - saturate(abs(lazy_dist_px - ring_radius_px) - 1.0);
+        let center = 1.0 - saturate(lazy_dist_px - 2.0);
+        let mark = max(ring, center);
+        if (mark > 1e-4) {
+            let lazy_rgb = mix(endcap_tint_rgb, vec3<f32>(1.0), 0.5);
+            let src_a = mark * alpha;
+            out_pm = lazy_rgb * src_a + out_pm * (1.0 - src_a);
             out_a = src_a + out_a * (1.0 - src_a);
         }
     }
 
     let px = pf.xy + pf_pos * pf_scale;
-    let os = globals.osu_rect;
+    let os = globals.visible_area_rect;
     let outside_osu = px.x < os.x || px.x > os.z || px.y < os.y || px.y > os.w;
     if (outside_osu && out_a > 1e-4) {
         let offscreen_alpha_mult: f32 = select(0.6, 0.4, now_ms > p.slider_end_time_ms);
@@ -386,5 +457,6 @@ fn fs_slider_caps(
     if (out_a <= 1e-6) {
         discard;
     }
+    out_pm = past_object_tinted(out_pm, out_a, p.time_ms, p.slider_end_time_ms);
     return vec4<f32>(out_pm, out_a);
 }
@@ -56,7 +56,10 @@ fn fs_overlay(
             let layers = max(1u, textureNumLayers(sliderfollowcircle_tex));
             let t = max(globals.slider_ball_rotation_index, 0);
             let li = i32(u32(t) % layers);
-            let fc = textureSample(sliderfollowcircle_tex, skin_samp, follow_uv, li);
+            var fc = potato_ring(follow_uv, 0.47, 0.04);
+            if (!is_potato_mode()) {
+                fc = textureSample(sliderfollowcircle_tex, skin_samp, follow_uv, li);
+            }
             let src_a = fc.a;
             let src_pm = fc.rgb * src_a;
             out_pm = src_pm + out_pm * (1.0 - src_a);
@@ -69,7 +72,10 @@ fn fs_overlay(
             if (all(ball_uv_rot >= vec2<f32>(0.0)) && all(ball_uv_rot <= vec2<f32>(1.0))) {
                 let layers = max(1u, textureNumLayers(sliderball_tex));
                 let li = i32(u32(globals.slider_ball_rotation_index) % layers);
-                let sb = textureSample(sliderball_tex, skin_samp, ball_uv_rot, li);
+                var sb = potato_disc(ball_uv_rot, 0.45);
+                if (!is_potato_mode()) {
+                    sb = textureSample(sliderball_tex, skin_samp, ball_uv_rot, li);
+                }
                 let src_a = sb.a;
                 let tinted_rgb = sb.rgb * globals.slider_color;
                 let src_pm = tinted_rgb * src_a;
@@ -120,6 +126,120 @@ fn fs_overlay(
         }
     }
 
+    // Flow overlay: jumps between visible objects with the angle at each landing object.
+    // Replay judgements share the buffer: a line from the object to the click with the hit
+    // error next to it, and a ring around missed objects. So do the pulsing rings around
+    // unsnapped objects.
+    if (globals.flow_meta.x > 0u) {
+        let pf = globals.playfield_rect;
+        let pf_scale = (pf.zw - pf.xy) / vec2<f32>(512.0, 384.0);
+        let screen_px = uv * globals.screen_size;
+        let half_width = 0.5 * globals.flow_style.x;
+        let text_h = globals.flow_style.y;
+        let text_scale = max(1.0, text_h / 7.0);
+        let text_adv = 6.0 * text_scale;
+        for (var i: u32 = 0u; i < globals.flow_meta.x; i = i + 1u) {
+            let seg = flow_segments[i];
+            var c = globals.flow_line_rgba;
+            if ((seg.flags & 1u) != 0u) {
+                c = globals.flow_sharp_rgba;
+            } else if ((seg.flags & 2u) != 0u) {
+                c = globals.flow_anti_flow_rgba;
+            } else if ((seg.flags & 8u) != 0u) {
+                c = globals.replay_hit_rgba;
+            } else if ((seg.flags & 16u) != 0u) {
+                c = globals.replay_miss_rgba;
+            } else if ((seg.flags & 32u) != 0u) {
+                let pulse = 0.5 + 0.5 * sin(globals.time_elapsed_ms * 0.006);
+                c = vec4<f32>(
+                    globals.unsnapped_object_rgba.rgb,
+                    globals.unsnapped_object_rgba.a * (0.35 + 0.65 * pulse),
+                );
+            }
+
+            let a = pf.xy + seg.start_pos * pf_scale;
+            let b = pf.xy + seg.end_pos * pf_scale;
+            if (half_width > 0.0 && (seg.flags & 32u) == 0u) {
+                let d = dist_point_segment(screen_px, a, b);
+                let line_a = 1.0 - smoothstep(half_width - 1.0, half_width + 1.0, d);
+                if (line_a > 1e-3) {
+                    let tmp = over_pm(out_pm, out_a, vec4<f32>(c.rgb, c.a * line_a));
+                    out_pm = tmp.rgb;
+                    out_a = tmp.a;
+                }
+            }
+
+            // Unsnapped rings still show with the flow lines turned thin or off.
+            let ring_half_width = select(half_width, max(half_width, 1.5), (seg.flags & 32u) != 0u);
+            if (seg.ring_radius > 0.0 && ring_half_width > 0.0) {
+                let ring_px = seg.ring_radius * pf_scale.x;
+                let d = abs(length(screen_px - a) - ring_px);
+                let ring_a = 1.0 - smoothstep(ring_half_width - 1.0, ring_half_width + 1.0, d);
+                if (ring_a > 1e-3) {
+                    let tmp = over_pm(out_pm, out_a, vec4<f32>(c.rgb, c.a * ring_a));
+                    out_pm = tmp.rgb;
+                    out_a = tmp.a;
+                }
+            }
+
+            if ((seg.flags & 4u) != 0u && text_h > 0.0) {
+                // Hit errors can go past 180 at low OD.
+                let max_value = select(180.0, 999.0, (seg.flags & 8u) != 0u);
+                let angle = u32(round(clamp(seg.angle_deg, 0.0, max_value)));
+                let text_tl = b + vec2<f32>(text_h * 0.75, -text_h * 1.75);
+                let text_w = f32(u32_char_count(angle)) * text_adv;
+                if (screen_px.x >= text_tl.x && screen_px.x < text_tl.x + text_w
+                    && screen_px.y >= text_tl.y && screen_px.y < text_tl.y + 7.0 * text_scale) {
+                    let text_a = uint_u32_alpha(screen_px, text_tl.x, text_tl.y, text_h, text_adv, angle);
+                    if (text_a > 1e-3) {
+                        let tmp = over_pm(out_pm, out_a, vec4<f32>(c.rgb, max(c.a, 0.9) * text_a));
+                        out_pm = tmp.rgb;
+                        out_a = tmp.a;
+                    }
+                }
+            }
+        }
+    }
+
+    // Auto cursor: trail points follow the snap markers in snap_positions, oldest first.
+    if (globals.snap_meta.w > 0u) {
+        let pf = globals.playfield_rect;
+        let pf_scale = (pf.zw - pf.xy) / vec2<f32>(512.0, 384.0);
+        let screen_px = uv * globals.screen_size;
+        let base = globals.snap_meta.x + globals.snap_meta.z;
+        let count = globals.snap_meta.w;
+        let half_width = 0.5 * globals.auto_cursor_style.y;
+        let c = globals.auto_cursor_rgba;
+
+        // Max over segments so overlapping pieces of the trail don't stack up.
+        var trail_a = 0.0;
+        if (half_width > 0.0) {
+            for (var i: u32 = 1u; i < count; i = i + 1u) {
+                let a = pf.xy + snap_positions[base + i - 1u] * pf_scale;
+                let b = pf.xy + snap_positions[base + i] * pf_scale;
+                let d = dist_point_segment(screen_px, a, b);
+                let seg_a = 1.0 - smoothstep(half_width - 1.0, half_width + 1.0, d);
+                let age_fade = f32(i) / f32(count);
+                trail_a = max(trail_a, seg_a * age_fade);
+            }
+        }
+        if (trail_a > 1e-3) {
+            let tmp = over_pm(out_pm, out_a, vec4<f32>(c.rgb, c.a * trail_a * 0.6));
+            out_pm = tmp.rgb;
+            out_a = tmp.a;
+        }
+
+        let cursor_px = pf.xy + snap_positions[base + count - 1u] * pf_scale;
+        let cursor_radius = globals.auto_cursor_style.x;
+        let cursor_d = length(screen_px - cursor_px);
+        let cursor_a = 1.0 - smoothstep(cursor_radius - 1.0, cursor_radius + 1.0, cursor_d);
+        if (cursor_radius > 0.0 && cursor_a > 1e-3) {
+            let tmp = over_pm(out_pm, out_a, vec4<f32>(c.rgb, c.a * cursor_a));
+            out_pm = tmp.rgb;
+            out_a = tmp.a;
+        }
+    }
+
     if (globals.selection_drag_pos_right.z > 0.5 || globals.selection_drag_pos_left.z > 0.5) {
         let screen_px = uv * globals.screen_size;
         let marker_radius = globals.drag_state_marker_style.x;
@@ -1,9 +1,14 @@
 use std::{
     cmp::{ min},
     collections::HashSet,
+    sync::Arc,
 };
 
-use crate::{map_format::colors::Color, state::Object, treap::Treap};
+use crate::{
+    map_format::{colors::Color, objects::HitObject, timing::Timing},
+    state::Object,
+    treap::Treap,
+};
 
 pub struct TimelinePoint {
     pub x: f32,
@@ -17,6 +22,9 @@ pub struct TimelinePoint {
     pub combo_color_and_opacity: [f32; 4],
 
     pub is_slider_or_spinner: u32,
+    pub sv_label: [u32; 2],
+    // Object starts only: the beat divisor the object is snapped to, 0 if it is unsnapped.
+    pub snap_divisor: u32,
 }
 
 pub struct TimelineBox {
@@ -26,8 +34,209 @@ pub struct TimelineBox {
     pub point_count: u32,
 }
 
+// Per-object timeline points only depend on the objects, timing, selections and combo colors,
+// so they are kept in map time and only converted to x positions for the visible window.
+struct CachedTimelinePoint {
+    time_ms: f64,
+    is_selected: u32,
+    is_object_start: u32,
+    is_slide_repeat: u32,
+    is_object_end: u32,
+    selection_side: u32,
+    combo_color_and_opacity: [f32; 4],
+    is_slider_or_spinner: u32,
+    sv_label: [u32; 2],
+    snap_divisor: u32,
+}
+
+// Slider heads get their effective SV multiplier as e.g. "1.5x", packed four ASCII chars per
+// word for the shader.
+fn sv_label(sv_multiplier: f64) -> [u32; 2] {
+    let text = format!("{:.2}", sv_multiplier);
+    let text = text.trim_end_matches('0').trim_end_matches('.');
+    let mut label = [0u32; 2];
+    for (i, byte) in format!("{}x", text).bytes().take(8).enumerate() {
+        label[i / 4] |= (byte as u32) << (8 * (i % 4));
+    }
+    label
+}
+
+pub struct TimelinePointsCache {
+    objects: Treap<Object>,
+    timing: Arc<Timing>,
+    base_sv: f64,
+    left_selection: HashSet<usize>,
+    right_selection: HashSet<usize>,
+    combo_colors: Vec<[f32; 4]>,
+    points: Vec<CachedTimelinePoint>,
+    // prefix_max_ms[i] = max time of points[..=i], suffix_min_ms[i] = min time of points[i..]
+    prefix_max_ms: Vec<f64>,
+    suffix_min_ms: Vec<f64>,
+}
+
+impl TimelinePointsCache {
+    pub fn new() -> Self {
+        TimelinePointsCache {
+            objects: Treap::new_empty(),
+            timing: Arc::new(Timing {
+                timing_points: Vec::new(),
+            }),
+            base_sv: 0.0,
+            left_selection: HashSet::new(),
+            right_selection: HashSet::new(),
+            combo_colors: Vec::new(),
+            points: Vec::new(),
+            prefix_max_ms: Vec::new(),
+            suffix_min_ms: Vec::new(),
+        }
+    }
+
+    fn is_valid_for(
+        &self,
+        objects: &Treap<Object>,
+        timing: &Arc<Timing>,
+        base_sv: f64,
+        left_selection: &HashSet<usize>,
+        right_selection: &HashSet<usize>,
+        combo_colors: &[[f32; 4]],
+    ) -> bool {
+        self.objects.ptr_eq(objects)
+            && Arc::ptr_eq(&self.timing, timing)
+            && self.base_sv == base_sv
+            && self.combo_colors.as_slice() == combo_colors
+            && &self.left_selection == left_selection
+            && &self.right_selection == right_selection
+    }
+
+    fn rebuild(
+        &mut self,
+        objects: &Treap<Object>,
+        timing: &Arc<Timing>,
+        base_sv: f64,
+        left_selection: &HashSet<usize>,
+        right_selection: &HashSet<usize>,
+        combo_colors: &[[f32; 4]],
+    ) {
+        let mut points = Vec::with_capacity(objects.len() * 2);
+        let mut combo_color_index: i64 = 0;
+
+        for (i, object) in objects.iter().enumerate() {
+            let combo_info = object.hit_object.combo_info();
+            let sv_multiplier = match object.hit_object.as_ref() {
+                HitObject::Slider(slider) if base_sv > 0.0 => {
+                    timing.get_lines_at_time(slider.time).0.map(|red_line| {
+                        slider.sv_pixels_per_ms * red_line.beat_length / (base_sv * 100.0)
+                    })
+                }
+                _ => None,
+            };
+            let snap_divisor = timing
+                .snap_divisor_at(object.hit_object.time())
+                .unwrap_or(0);
+            let object = object.instance().unwrap();
+            if combo_info.new_combo && !object.is_spinner {
+                combo_color_index =
+                    (combo_color_index + 1 + combo_info.color_skip) % (combo_colors.len() as i64);
+            }
+            let color = if object.is_spinner {
+                [1.0, 1.0, 1.0, 0.5]
+            } else {
+                combo_colors[combo_color_index as usize]
+            };
+            let (selected, is_selection_left) = if left_selection.contains(&i) {
+                (true, true)
+            } else if right_selection.contains(&i) {
+                (true, false)
+            } else {
+                (false, false)
+            };
+            let is_slider_or_spinner = if object.is_slider || object.is_spinner {
+                1
+            } else {
+                0
+            };
+            points.push(CachedTimelinePoint {
+                time_ms: object.timeline_start_ms,
+                is_selected: if selected { 1 } else { 0 },
+                is_object_start: 1,
+                is_slide_repeat: 0,
+                is_object_end: 0,
+                selection_side: if selected {
+                    if is_selection_left { 1 } else { 2 }
+                } else {
+                    0
+                },
+                combo_color_and_opacity: color,
+                is_slider_or_spinner,
+                sv_label: sv_multiplier.map(sv_label).unwrap_or([0, 0]),
+                snap_divisor,
+            });
+            for repeat_time in &object.timeline_repeat_ms {
+                points.push(CachedTimelinePoint {
+                    time_ms: *repeat_time,
+                    is_selected: if selected { 1 } else { 0 },
+                    is_object_start: 0,
+                    is_slide_repeat: 1,
+                    is_object_end: 0,
+                    selection_side: if selected {
+                        if is_selection_left { 1 } else { 2 }
+                    } else {
+                        0
+                    },
+                    combo_color_and_opacity: color,
+                    is_slider_or_spinner,
+                    sv_label: [0, 0],
+                    snap_divisor: 0,
+                });
+            }
+            points.push(CachedTimelinePoint {
+                time_ms: object.timeline_end_ms,
+                is_selected: if selected { 1 } else { 0 },
+                is_object_start: 0,
+                is_slide_repeat: 0,
+                is_object_end: 1,
+                selection_side: if selected {
+                    if is_selection_left { 1 } else { 2 }
+                } else {
+                    0
+                },
+                combo_color_and_opacity: color,
+                is_slider_or_spinner,
+                sv_label: [0, 0],
+                snap_divisor: 0,
+            });
+        }
+
+        let mut prefix_max_ms = Vec::with_capacity(points.len());
+        let mut running_max = f64::NEG_INFINITY;
+        for point in points.iter() {
+            running_max = running_max.max(point.time_ms);
+            prefix_max_ms.push(running_max);
+        }
+        let mut suffix_min_ms = vec![0.0; points.len()];
+        let mut running_min = f64::INFINITY;
+        for (i, point) in points.iter().enumerate().rev() {
+            running_min = running_min.min(point.time_ms);
+            suffix_min_ms[i] = running_min;
+        }
+
+        self.objects = objects.clone();
+        self.timing = Arc::clone(timing);
+        self.base_sv = base_sv;
+        self.left_selection = left_selection.clone();
+        self.right_selection = right_selection.clone();
+        self.combo_colors = combo_colors.to_vec();
+        self.points = points;
+        self.prefix_max_ms = prefix_max_ms;
+        self.suffix_min_ms = suffix_min_ms;
+    }
+}
+
 pub fn calculate_timeline_points_and_boxes<'a>(
+    cache: &mut TimelinePointsCache,
     objects: &Treap<Object>,
+    timing: &Arc<Timing>,
+    base_sv: f64,
     timeline_x0: f64,
     timeline_x_current: f64,
     timeline_x1: f64,
@@ -38,12 +247,12 @@ pub fn calculate_timeline_points_and_boxes<'a>(
     circle_radius_px: f64,
     outline_thickness_px: f64,
     combo_colors: &[Color],
+    max_points: usize,
 ) -> (Vec<TimelinePoint>, Vec<TimelineBox>) {
     let radius = circle_radius_px + outline_thickness_px + 1.0;
     let ms_to_x = |time_ms: f64| -> f32 {
         (timeline_x_current + (time_ms - current_time_ms) / timeline_ms_per_pixel) as f32
     };
-    let mut points = Vec::new();
 
     let combo_colors = combo_colors
         .iter()
@@ -57,76 +266,67 @@ pub fn calculate_timeline_points_and_boxes<'a>(
         })
         .collect::<Vec<_>>();
 
-    let mut combo_color_index: i64 = 0;
+    if !cache.is_valid_for(
+        objects,
+        timing,
+        base_sv,
+        left_selection,
+        right_selection,
+        &combo_colors,
+    ) {
+        cache.rebuild(
+            objects,
+            timing,
+            base_sv,
+            left_selection,
+            right_selection,
+            &combo_colors,
+        );
+    }
 
-    for (i, object) in objects.iter().enumerate() {
-        let combo_info = object.hit_object.combo_info();
-        let object = object.instance().unwrap();
-        if combo_info.new_combo && !object.is_spinner {
-            combo_color_index =
-                (combo_color_index + 1 + combo_info.color_skip) % (combo_colors.len() as i64);
-        }
-        let color = if object.is_spinner {
-            [1.0, 1.0, 1.0, 0.5]
-        } else {
-            combo_colors[combo_color_index as usize]
-        };
-        let (selected, is_selection_left) = if left_selection.contains(&i) {
-            (true, true)
-        } else if right_selection.contains(&i) {
-            (true, false)
-        } else {
-            (false, false)
-        };
-        let is_slider_or_spinner = if object.is_slider || object.is_spinner {
-            1
-        } else {
-            0
-        };
-        points.push(TimelinePoint {
-            x: ms_to_x(object.timeline_start_ms),
-            is_selected: if selected { 1 } else { 0 },
-            is_object_start: 1,
-            is_slide_repeat: 0,
-            is_object_end: 0,
-            selection_side: if selected {
-                if is_selection_left { 1 } else { 2 }
-            } else {
-                0
-            },
-            combo_color_and_opacity: color,
-            is_slider_or_spinner,
-        });
-        for repeat_time in &object.timeline_repeat_ms {
-            points.push(TimelinePoint {
-                x: ms_to_x(*repeat_time),
-                is_selected: if selected { 1 } else { 0 },
-                is_object_start: 0,
-                is_slide_repeat: 1,
-                is_object_end: 0,
-                selection_side: if selected {
-                    if is_selection_left { 1 } else { 2 }
-                } else {
-                    0
-                },
-                combo_color_and_opacity: color,
-                is_slider_or_spinner,
-            });
-        }
-        points.push(TimelinePoint {
-            x: ms_to_x(object.timeline_end_ms),
-            is_selected: if selected { 1 } else { 0 },
-            is_object_start: 0,
-            is_slide_repeat: 0,
-            is_object_end: 1,
-            selection_side: if selected {
-                if is_selection_left { 1 } else { 2 }
-            } else {
-                0
-            },
-            combo_color_and_opacity: color,
-            is_slider_or_spinner,
-        });
+    // Only points that can touch the visible window (plus one on each side) are emitted.
+    let visible_start_ms =
+        current_time_ms + (timeline_x0 - radius - timeline_x_current) * timeline_ms_per_pixel;
+    let visible_end_ms =
+        current_time_ms + (timeline_x1 + radius - timeline_x_current) * timeline_ms_per_pixel;
+    let first = cache
+        .prefix_max_ms
+        .partition_point(|t| *t < visible_start_ms)
+        .saturating_sub(1);
+    let last = min(
+        cache
+            .suffix_min_ms
+            .partition_point(|t| *t <= visible_end_ms)
+            + 1,
+        cache.points.len(),
+    );
+    let mut points: Vec<TimelinePoint> = if first < last {
+        cache.points[first..last]
+            .iter()
+            .map(|p| TimelinePoint {
+                x: ms_to_x(p.time_ms),
+                is_selected: p.is_selected,
+                is_object_start: p.is_object_start,
+                is_slide_repeat: p.is_slide_repeat,
+                is_object_end: p.is_object_end,
+                selection_side: p.selection_side,
+                combo_color_and_opacity: p.combo_color_and_opacity,
+                is_slider_or_spinner: p.is_slider_or_spinner,
+                sv_label: p.sv_label,
+                snap_divisor: p.snap_divisor,
+            })
+            .collect()
+    } else {
+        Vec::new()
+    };
+    // Past `max_points`, only the ones closest to the playhead are kept.
+    if points.len() > max_points {
+        let current = points.partition_point(|p| p.x < timeline_x_current as f32);
+        let start = current
+            .saturating_sub(max_points / 2)
+            .min(points.len() - max_points);
+        points.drain(start + max_points..);
+        points.drain(..start);
     }
 
     let x_splits = {
@@ -138,6 +338,9 @@ pub fn calculate_timeline_points_and_boxes<'a>(
         x_splits.push(timeline_x1);
         x_splits
     };
+    // SV labels sit in the slider body right after the head, so a box also needs the heads
+    // whose label reaches into it.
+    let label_reach = radius + 6.0 * circle_radius_px.max(7.0);
     let mut boxes = Vec::with_capacity(x_splits.len() - 1);
     let mut min_point = 0;
     let mut max_point = 0;
@@ -147,7 +350,7 @@ pub fn calculate_timeline_points_and_boxes<'a>(
         while max_point < points.len() && points[max_point].x < (x1 + radius) as f32 {
             max_point += 1;
         }
-        while min_point < points.len() && points[min_point].x < (x0 - radius) as f32 {
+        while min_point < points.len() && points[min_point].x < (x0 - label_reach) as f32 {
             min_point += 1;
         }
         // expand by 1
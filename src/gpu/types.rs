@@ -10,8 +10,19 @@ pub const MAX_BOOKMARKS: usize = 1024;
 pub const MAX_RED_LINES: usize = 1024;
 pub const MAX_TIMELINE_MARKS: usize = MAX_BOOKMARKS + MAX_RED_LINES;
 pub const MAX_SNAP_MARKERS: usize = 8192;
+pub const AUTO_CURSOR_TRAIL_SAMPLES: usize = 24;
+pub const MAX_FLOW_SEGMENTS: usize = 512;
+// How long a replay hit or miss stays on the playfield after the object's time.
+pub const REPLAY_JUDGEMENT_VISIBLE_MS: f64 = 800.0;
+pub const MAX_CONVERT_NOTES: usize = 512;
+// Must match the size of `Globals::frame_graph_packed`.
+pub const FRAME_GRAPH_SAMPLES: usize = 64;
 pub const MAX_TIMELINE_SNAKES: usize = 4096;
 pub const MAX_TIMELINE_X_BOXES: usize = 16384;
+// Timeline objects drawn around the playhead in potato mode.
+pub const POTATO_MAX_TIMELINE_POINTS: usize = 256;
+// Onsets and triplet ticks each, must match half of `Globals::timeline_drag_marks_ms`.
+pub const MAX_TIMELINE_DRAG_MARKS: usize = 8;
 
 #[repr(C)]
 #[derive(Clone, Copy, Pod, Zeroable)]
@@ -185,6 +196,86 @@ pub struct Globals {
     pub _timeline_past_pad: [f32; 3],
     pub timeline_past_tint_rgba: [f32; 4],
     pub timeline_past_object_tint_rgba: [f32; 4],
+    // x: fade-in override (0 = AR-accurate), y: fade-out duration, zw unused.
+    pub object_fade_ms: [f32; 4],
+    // x: tooltip length in chars (0 = hidden), y: 1 = warning pinned at the anchor, zw unused.
+    pub tooltip_meta: [u32; 4],
+    // xy: cursor position in screen pixels the tooltip is placed next to (top-left corner of
+    // a pinned warning), zw unused.
+    pub tooltip_anchor: [f32; 4],
+    // Up to 64 ASCII chars, 4 per u32.
+    pub tooltip_text_packed: [[u32; 4]; 4],
+    pub auto_cursor_rgba: [f32; 4],
+    // x: cursor radius in pixels, y: trail width in pixels, zw unused.
+    pub auto_cursor_style: [f32; 4],
+    // x: number of flow segments, yzw unused.
+    pub flow_meta: [u32; 4],
+    // x: line width in pixels, y: angle text height in pixels, zw unused.
+    pub flow_style: [f32; 4],
+    pub flow_line_rgba: [f32; 4],
+    pub flow_sharp_rgba: [f32; 4],
+    pub flow_anti_flow_rgba: [f32; 4],
+    // x: view preset (0 design, 1 compose, 2 timing), y: 1 = potato mode, z: 1 = approach
+    // circles close in real time at slowed playback, w unused.
+    pub view_meta: [u32; 4],
+    // x: frame graph samples, y: 1 = graph shown, z: graph scale in ms x10, w: frame budget in
+    // ms x10.
+    pub frame_graph_meta: [u32; 4],
+    // Oldest sample first, each u32 is CPU pass ms x10 in the low and GPU pass ms x10 in the
+    // high 16 bits.
+    pub frame_graph_packed: [[u32; 4]; 16],
+    // x: convert mode (0 off, 1 taiko, 2 catch, 3 mania), y: number of notes, z: mania key
+    // count, w unused.
+    pub convert_meta: [u32; 4],
+    // (x0, y0, x1, y1) of the convert preview strip in screen pixels.
+    pub convert_rect: [f32; 4],
+    // xyz: 300, 100 and 50 hit windows in ms either side of the current time, w: 1 = shown.
+    pub hit_windows_ms: [f32; 4],
+    pub hit_window_300_rgba: [f32; 4],
+    pub hit_window_100_rgba: [f32; 4],
+    pub hit_window_50_rgba: [f32; 4],
+    // x: ms since the current kiai section started, negative outside kiai or with the effects
+    // off, y: bass level, z: mean level of all bands, w unused.
+    pub kiai_effect_meta: [f32; 4],
+    pub kiai_effect_rgba: [f32; 4],
+    // osu_rect resized to appearance.general.offscreen_warning_aspect_ratio.
+    pub visible_area_rect: [f32; 4],
+    // x..y: start times of objects drawn as ghosts, z: their opacity, 0 without ghosts.
+    pub ghost_meta: [f32; 4],
+    // x..y: map time range playback loops over, z: 1 while a loop is set.
+    pub selection_loop_ms: [f32; 4],
+    pub selection_loop_rgba: [f32; 4],
+    pub replay_hit_rgba: [f32; 4],
+    pub replay_miss_rgba: [f32; 4],
+    pub unsnapped_object_rgba: [f32; 4],
+    // Label then value of the stats box's playhead ms and measure:beat:tick lines, its selected
+    // object lines and its editing totals, up to 16 ASCII chars each, 4 per u32.
+    pub stats_time_text_packed: [[u32; 4]; 18],
+    // Shortcuts of the active tool mode, up to 96 ASCII chars, 4 per u32.
+    pub hint_text_packed: [[u32; 4]; 6],
+    // x: number of regions the first-run overlay outlines, 0 while it is hidden, yzw unused.
+    pub onboarding_meta: [u32; 4],
+    // (x0, y0, x1, y1) of each outlined region in screen pixels.
+    pub onboarding_rects: [[f32; 4]; 4],
+    // Label of each region, up to 32 ASCII chars in two rows of 4 u32.
+    pub onboarding_text_packed: [[u32; 4]; 8],
+    // x..y: map time range the bottom timeline shows, z: 1 while zoomed into part of the song,
+    // w unused.
+    pub bottom_timeline_ms: [f32; 4],
+    // x..y: start time of the first past object kept visible up to the playhead, z: their
+    // opacity, 0 while playing or turned off.
+    pub past_object_meta: [f32; 4],
+    pub past_object_tint_rgba: [f32; 4],
+    // x: lines in the report panel, 0 while it is hidden, y: chars in its longest line, zw
+    // unused.
+    pub report_meta: [u32; 4],
+    // Up to 8 lines of 48 ASCII chars, each in three rows of 4 u32.
+    pub report_text_packed: [[u32; 4]; 24],
+    // x: onsets, y: triplet ticks marked on the top timeline while dragging in time, zw
+    // unused.
+    pub timeline_drag_meta: [u32; 4],
+    // Times of the onsets in the first two rows, of the triplet ticks in the last two.
+    pub timeline_drag_marks_ms: [[f32; 4]; 4],
     pub _pad_end: [f32; 4],
 }
 
@@ -200,8 +291,11 @@ pub struct TimelinePointGpu {
     pub is_selected: u32,
     pub is_selected_by_left: u32,
     pub is_slider_or_spinner: u32,
-    pub _pad: [u32; 3],
+    pub sv_label: [u32; 2],
+    pub _pad: u32,
     pub color: [f32; 4],
+    // Outline of the object's head, colored by its snap divisor.
+    pub head_rgba: [f32; 4],
 }
 
 #[repr(C)]
@@ -213,6 +307,40 @@ pub struct TimelineXBoxGpu {
     pub segment_count: u32,
 }
 
+pub const FLOW_SEGMENT_SHARP: u32 = 1;
+pub const FLOW_SEGMENT_ANTI_FLOW: u32 = 2;
+pub const FLOW_SEGMENT_HAS_ANGLE: u32 = 4;
+// Replay judgements ride along in the flow segments: start is the object, end the click, the
+// angle slot holds the hit error in ms.
+pub const FLOW_SEGMENT_REPLAY_HIT: u32 = 8;
+pub const FLOW_SEGMENT_REPLAY_MISS: u32 = 16;
+// Pulsing ring around an object that is off every snap tick, start and end both the object.
+pub const FLOW_SEGMENT_UNSNAPPED: u32 = 32;
+
+// Jump between two consecutive objects in playfield coordinates, see flow_analysis.rs.
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+pub struct FlowSegmentGpu {
+    pub start_pos: [f32; 2],
+    pub end_pos: [f32; 2],
+    pub angle_deg: f32,
+    pub flags: u32,
+    // Playfield radius of a ring drawn around start_pos, 0 for none.
+    pub ring_radius: f32,
+    pub _pad: f32,
+}
+
+// Converted note in the preview strip, see convert_preview.rs. A note without duration has
+// x0 == x1.
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+pub struct ConvertNoteGpu {
+    pub x0: f32,
+    pub x1: f32,
+    pub lane: f32,
+    pub kind: u32,
+}
+
 #[repr(C)]
 #[derive(Clone, Copy, Pod, Zeroable)]
 pub struct DigitsMeta {
@@ -243,7 +371,10 @@ pub struct SkinMeta {
     pub _pad: [f32; 2],
 }
 
-pub const MAX_CIRCLES: usize = 8192;
+pub const INITIAL_CIRCLES_CAPACITY: usize = 8192;
+// Objects drawn in one frame. The circles buffer grows up to this, which stays well below the
+// default 128 MiB storage binding limit; anything beyond is dropped with a HUD warning.
+pub const MAX_CIRCLES: usize = 262144;
 // Slider body rendering loops over path segments per pixel within the slider bbox.
 // Keep a reasonable per-object cap to avoid pathological shader workloads.
 pub const INITIAL_SLIDER_SEGS_CAPACITY: usize = 32768;
@@ -297,7 +428,7 @@ pub struct CircleGpu {
 
     pub slides: u32,
     pub selected_side: u32,
-    pub _pad1: [u32; 2],
+    pub lazy_end_xy: [f32; 2],
 
     pub slider_head_rotation: [f32; 2],
     pub slider_end_rotation: [f32; 2],
@@ -334,12 +465,17 @@ impl CircleGpu {
             slides: instance.slides as u32,
             selected_side: 0,
             slider_head_rotation: [1.0, 0.0],
-            _pad1: [0, 0],
+            lazy_end_xy: [0.0, 0.0],
             slider_end_rotation: [1.0, 0.0],
         }
     }
 }
 
+const FOLLOW_CIRCLE_SCALE: f64 = 2.4;
+const LEGACY_LAST_TICK_OFFSET_MS: f64 = 36.0;
+// Ticks closer than this to a repeat or the tail are not placed.
+const MIN_TICK_GAP_MS: f64 = 10.0;
+
 #[derive(Clone, Debug)]
 pub struct ObjectInstance {
     pub pos: Vec2,
@@ -409,13 +545,69 @@ impl ObjectInstance {
             (pos, progress, direction)
         }
     }
+
+    // Where a cursor that only moves when the ball is about to leave the follow circle ends
+    // up, following the ball through every tick and repeat. osu! judges the tail 36ms before
+    // the end, so fast sliders can be left early and the real tail doesn't have to be reached.
+    pub fn lazy_end_pos(&self, tick_spacing_ms: f64) -> Vec2 {
+        if !self.is_slider || self.slider_path.is_none() {
+            return self.end_pos();
+        }
+        let follow_radius = self.radius * FOLLOW_CIRCLE_SCALE;
+        let duration = self.slider_end_time_ms - self.time;
+        let last_tick_ms = (self.slider_end_time_ms - LEGACY_LAST_TICK_OFFSET_MS)
+            .max(self.time + duration / 2.0);
+        let mut cursor = self.pos;
+        let mut checkpoints: Vec<f64> = self
+            .slider_tick_times_ms(tick_spacing_ms)
+            .into_iter()
+            .chain(
+                (1..self.slides)
+                    .map(|repeat| self.time + self.slider_slide_duration_ms * repeat as f64),
+            )
+            .filter(|t| *t < last_tick_ms)
+            .collect();
+        checkpoints.sort_by(f64::total_cmp);
+        checkpoints.push(last_tick_ms);
+        for time_ms in checkpoints {
+            let (ball, _, _) = self.sample_position_and_progress_and_direction(time_ms);
+            let distance = ball.distance2(cursor).sqrt();
+            if distance > follow_radius {
+                cursor = cursor + (ball - cursor) * ((distance - follow_radius) / distance);
+            }
+        }
+        cursor
+    }
+
+    // Ticks sit at the same places along the path on every slide, so on reverse slides they
+    // come in the opposite order, counted back from the slide's end.
+    fn slider_tick_times_ms(&self, tick_spacing_ms: f64) -> Vec<f64> {
+        let slide_ms = self.slider_slide_duration_ms;
+        if tick_spacing_ms <= 0.0 || slide_ms <= 0.0 {
+            return Vec::new();
+        }
+        let mut ticks = Vec::new();
+        for slide in 0..self.slides {
+            let slide_start = self.time + slide_ms * slide as f64;
+            let mut offset = tick_spacing_ms;
+            while offset < slide_ms - MIN_TICK_GAP_MS {
+                ticks.push(if slide % 2 == 0 {
+                    slide_start + offset
+                } else {
+                    slide_start + slide_ms - offset
+                });
+                offset += tick_spacing_ms;
+            }
+        }
+        ticks
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::{
-        CircleGpu, DigitsMeta, Globals, SkinMeta, SliderBoxGpu, SliderSegGpu,
-        TimelinePointGpu, TimelineXBoxGpu,
+        CircleGpu, ConvertNoteGpu, DigitsMeta, FlowSegmentGpu, Globals, SkinMeta, SliderBoxGpu,
+        SliderSegGpu, TimelinePointGpu, TimelineXBoxGpu,
     };
     use wgpu::naga::{
         front::wgsl,
@@ -1165,6 +1357,122 @@ mod tests {
                 "timeline_past_object_tint_rgba",
                 std::mem::offset_of!(Globals, timeline_past_object_tint_rgba),
             ),
+            ("object_fade_ms", std::mem::offset_of!(Globals, object_fade_ms)),
+            ("tooltip_meta", std::mem::offset_of!(Globals, tooltip_meta)),
+            ("tooltip_anchor", std::mem::offset_of!(Globals, tooltip_anchor)),
+            (
+                "tooltip_text_packed",
+                std::mem::offset_of!(Globals, tooltip_text_packed),
+            ),
+            ("auto_cursor_rgba", std::mem::offset_of!(Globals, auto_cursor_rgba)),
+            ("auto_cursor_style", std::mem::offset_of!(Globals, auto_cursor_style)),
+            ("flow_meta", std::mem::offset_of!(Globals, flow_meta)),
+            ("flow_style", std::mem::offset_of!(Globals, flow_style)),
+            ("flow_line_rgba", std::mem::offset_of!(Globals, flow_line_rgba)),
+            ("flow_sharp_rgba", std::mem::offset_of!(Globals, flow_sharp_rgba)),
+            (
+                "flow_anti_flow_rgba",
+                std::mem::offset_of!(Globals, flow_anti_flow_rgba),
+            ),
+            ("view_meta", std::mem::offset_of!(Globals, view_meta)),
+            (
+                "frame_graph_meta",
+                std::mem::offset_of!(Globals, frame_graph_meta),
+            ),
+            (
+                "frame_graph_packed",
+                std::mem::offset_of!(Globals, frame_graph_packed),
+            ),
+            ("convert_meta", std::mem::offset_of!(Globals, convert_meta)),
+            ("convert_rect", std::mem::offset_of!(Globals, convert_rect)),
+            (
+                "hit_windows_ms",
+                std::mem::offset_of!(Globals, hit_windows_ms),
+            ),
+            (
+                "hit_window_300_rgba",
+                std::mem::offset_of!(Globals, hit_window_300_rgba),
+            ),
+            (
+                "hit_window_100_rgba",
+                std::mem::offset_of!(Globals, hit_window_100_rgba),
+            ),
+            (
+                "hit_window_50_rgba",
+                std::mem::offset_of!(Globals, hit_window_50_rgba),
+            ),
+            (
+                "kiai_effect_meta",
+                std::mem::offset_of!(Globals, kiai_effect_meta),
+            ),
+            (
+                "kiai_effect_rgba",
+                std::mem::offset_of!(Globals, kiai_effect_rgba),
+            ),
+            (
+                "visible_area_rect",
+                std::mem::offset_of!(Globals, visible_area_rect),
+            ),
+            ("ghost_meta", std::mem::offset_of!(Globals, ghost_meta)),
+            (
+                "selection_loop_ms",
+                std::mem::offset_of!(Globals, selection_loop_ms),
+            ),
+            (
+                "selection_loop_rgba",
+                std::mem::offset_of!(Globals, selection_loop_rgba),
+            ),
+            ("replay_hit_rgba", std::mem::offset_of!(Globals, replay_hit_rgba)),
+            ("replay_miss_rgba", std::mem::offset_of!(Globals, replay_miss_rgba)),
+            (
+                "unsnapped_object_rgba",
+                std::mem::offset_of!(Globals, unsnapped_object_rgba),
+            ),
+            (
+                "stats_time_text_packed",
+                std::mem::offset_of!(Globals, stats_time_text_packed),
+            ),
+            (
+                "hint_text_packed",
+                std::mem::offset_of!(Globals, hint_text_packed),
+            ),
+            (
+                "onboarding_meta",
+                std::mem::offset_of!(Globals, onboarding_meta),
+            ),
+            (
+                "onboarding_rects",
+                std::mem::offset_of!(Globals, onboarding_rects),
+            ),
+            (
+                "onboarding_text_packed",
+                std::mem::offset_of!(Globals, onboarding_text_packed),
+            ),
+            (
+                "bottom_timeline_ms",
+                std::mem::offset_of!(Globals, bottom_timeline_ms),
+            ),
+            (
+                "past_object_meta",
+                std::mem::offset_of!(Globals, past_object_meta),
+            ),
+            (
+                "past_object_tint_rgba",
+                std::mem::offset_of!(Globals, past_object_tint_rgba),
+            ),
+            ("report_meta", std::mem::offset_of!(Globals, report_meta)),
+            (
+                "report_text_packed",
+                std::mem::offset_of!(Globals, report_text_packed),
+            ),
+            (
+                "timeline_drag_meta",
+                std::mem::offset_of!(Globals, timeline_drag_meta),
+            ),
+            (
+                "timeline_drag_marks_ms",
+                std::mem::offset_of!(Globals, timeline_drag_marks_ms),
+            ),
             ("_pad_end", std::mem::offset_of!(Globals, _pad_end)),
         ];
         (fields, std::mem::size_of::<Globals>())
@@ -1278,7 +1586,7 @@ mod tests {
                     "selected_side",
                     std::mem::offset_of!(CircleGpu, selected_side),
                 ),
-                ("_pad1", std::mem::offset_of!(CircleGpu, _pad1)),
+                ("lazy_end_xy", std::mem::offset_of!(CircleGpu, lazy_end_xy)),
                 (
                     "slider_head_rotation",
                     std::mem::offset_of!(CircleGpu, slider_head_rotation),
@@ -1348,6 +1656,7 @@ mod tests {
                 ),
                 ("_pad", std::mem::offset_of!(TimelinePointGpu, _pad)),
                 ("color", std::mem::offset_of!(TimelinePointGpu, color)),
+                ("head_rgba", std::mem::offset_of!(TimelinePointGpu, head_rgba)),
             ],
             std::mem::size_of::<TimelinePointGpu>(),
         )
@@ -1371,6 +1680,32 @@ mod tests {
         )
     }
 
+    fn rust_flow_segment_layout() -> (Vec<(&'static str, usize)>, usize) {
+        (
+            vec![
+                ("start_pos", std::mem::offset_of!(FlowSegmentGpu, start_pos)),
+                ("end_pos", std::mem::offset_of!(FlowSegmentGpu, end_pos)),
+                ("angle_deg", std::mem::offset_of!(FlowSegmentGpu, angle_deg)),
+                ("flags", std::mem::offset_of!(FlowSegmentGpu, flags)),
+                ("ring_radius", std::mem::offset_of!(FlowSegmentGpu, ring_radius)),
+                ("_pad", std::mem::offset_of!(FlowSegmentGpu, _pad)),
+            ],
+            std::mem::size_of::<FlowSegmentGpu>(),
+        )
+    }
+
+    fn rust_convert_note_layout() -> (Vec<(&'static str, usize)>, usize) {
+        (
+            vec![
+                ("x0", std::mem::offset_of!(ConvertNoteGpu, x0)),
+                ("x1", std::mem::offset_of!(ConvertNoteGpu, x1)),
+                ("lane", std::mem::offset_of!(ConvertNoteGpu, lane)),
+                ("kind", std::mem::offset_of!(ConvertNoteGpu, kind)),
+            ],
+            std::mem::size_of::<ConvertNoteGpu>(),
+        )
+    }
+
     fn assert_wgsl_rust_layout_match(
         wgsl_struct_name: &str,
         addr_space: AddrSpace,
@@ -1507,6 +1842,28 @@ mod tests {
         );
     }
 
+    #[test]
+    fn flow_segment_matches_wgsl_layout() {
+        let (rust_offsets, rust_size) = rust_flow_segment_layout();
+        assert_wgsl_rust_layout_match(
+            "FlowSegmentGPU",
+            AddrSpace::Storage,
+            rust_offsets,
+            rust_size,
+        );
+    }
+
+    #[test]
+    fn convert_note_matches_wgsl_layout() {
+        let (rust_offsets, rust_size) = rust_convert_note_layout();
+        assert_wgsl_rust_layout_match(
+            "ConvertNoteGPU",
+            AddrSpace::Storage,
+            rust_offsets,
+            rust_size,
+        );
+    }
+
     #[test]
     fn scene_wgsl_parses_and_validates() {
         let scene_wgsl = concat!(
@@ -1,13 +1,18 @@
 use std::cell::RefCell;
 use std::rc::Rc;
 use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
 
 use crate::geometry::{atomic_vec2::AtomicVec2, vec2::Vec2};
 
+// How long the cursor has to rest on a hitbox before its tooltip shows up.
+pub const TOOLTIP_DELAY: Duration = Duration::from_millis(500);
+
 pub struct SimpleHitbox {
     hit_test: RefCell<Box<dyn Fn(Vec2) -> bool>>,
     drag_handler: RefCell<Box<dyn FnMut(DragEvent)>>,
     hover_handler: RefCell<Box<dyn FnMut(HoverEvent)>>,
+    tooltip: RefCell<Option<&'static str>>,
 }
 
 pub enum DragEvent {
@@ -36,9 +41,19 @@ impl SimpleHitbox {
             hit_test: RefCell::new(hit_test),
             drag_handler: RefCell::new(drag_handler),
             hover_handler: RefCell::new(hover_handler),
+            tooltip: RefCell::new(None),
         }
     }
 
+    // The HUD font only has letters, digits, spaces and `-./:_|`.
+    pub fn set_tooltip(&self, text: &'static str) {
+        *self.tooltip.borrow_mut() = Some(text);
+    }
+
+    pub fn tooltip(&self) -> Option<&'static str> {
+        *self.tooltip.borrow()
+    }
+
     pub fn set_hit_test<F>(&self, hit_test: F)
     where
         F: 'static + Fn(Vec2) -> bool,
@@ -46,6 +61,15 @@ impl SimpleHitbox {
         *self.hit_test.borrow_mut() = Box::new(hit_test);
     }
 
+    // Keeps the hit test set so far, but only while `enabled` returns true.
+    pub fn gate_hit_test<F>(&self, enabled: F)
+    where
+        F: 'static + Fn() -> bool,
+    {
+        let hit_test = self.hit_test.replace(Box::new(|_| false));
+        *self.hit_test.borrow_mut() = Box::new(move |point| enabled() && hit_test(point));
+    }
+
     fn drag(&self, left: bool, pos: Vec2) {
         (self.drag_handler.borrow_mut())(DragEvent::Move {
             left,
@@ -199,6 +223,7 @@ pub struct MouseHandler {
     current_action: Action,
     focused: bool,
     position: Vec2,
+    hover_started: Instant,
 }
 
 pub enum Action {
@@ -219,6 +244,7 @@ impl MouseHandler {
             current_action: Action::None,
             focused: false,
             position: Vec2 { x: 0.0, y: 0.0 },
+            hover_started: Instant::now(),
         }
     }
 
@@ -264,6 +290,19 @@ impl MouseHandler {
         self.position
     }
 
+    // Tooltip of the hovered hitbox and when the hover started. Nothing while dragging.
+    pub fn hovered_tooltip(&self) -> Option<(Instant, &'static str)> {
+        match &self.current_action {
+            Action::Hovering { hitbox } => hitbox.tooltip().map(|text| (self.hover_started, text)),
+            _ => None,
+        }
+    }
+
+    // Whether the cursor rests on a hitbox without dragging it.
+    pub fn is_hovering(&self) -> bool {
+        matches!(self.current_action, Action::Hovering { .. })
+    }
+
     fn get_hovering_hitbox(&self) -> Option<Rc<SimpleHitbox>> {
         // Iterate in reverse order to prioritize hitboxes added later (which are on top)
         for hitbox in self.hitboxes.iter().rev() {
@@ -278,6 +317,7 @@ impl MouseHandler {
         match self.get_hovering_hitbox() {
             Some(hitbox) => {
                 hitbox.hover(self.position);
+                self.hover_started = Instant::now();
                 self.current_action = Action::Hovering { hitbox };
             }
             None => {
@@ -296,6 +336,7 @@ impl MouseHandler {
                     if !Rc::ptr_eq(&current_hitbox, hitbox) {
                         hitbox.stop_hover();
                         current_hitbox.hover(self.position);
+                        self.hover_started = Instant::now();
                         self.current_action = Action::Hovering {
                             hitbox: current_hitbox,
                         };
@@ -2,17 +2,213 @@ use std::{
     rc::Rc,
     sync::{
         Arc, RwLock,
-        atomic::{AtomicBool, Ordering},
+        atomic::{AtomicBool, AtomicU32, Ordering},
     },
 };
 
 use crate::{
-    audio::AudioEngine,
+    audio::{AudioEngine, SongTimeMs},
     geometry::{atomic_vec2::AtomicVec2, vec2::Vec2, vec2_transform::Vec2Transform},
     gui::{DragEvent, HoverEvent, RectHitbox, SimpleButton, SimpleHitbox},
-    state::{DragState, EditState},
+    layout,
+    map_format::{objects::HitObject, slider_boxing::BBox4, timing::Timing},
+    state::{DragState, EditState, TimelineDragMarks, snap_slider_anchor},
+    timeline_view::BottomTimelineRange,
+    tool_mode::ToolMode,
 };
 
+pub const SCALE_HANDLE_HIT_RADIUS_PX: f64 = 12.0;
+pub const ROTATION_HANDLE_OFFSET_PX: f64 = 28.0;
+pub const ROTATION_HANDLE_HIT_RADIUS_PX: f64 = 10.0;
+const ROTATION_SNAP_DEGREES: f64 = 15.0;
+
+// Index into `BBox4::handle_points` of the handle under `cursor`, both in screen space.
+pub fn selection_scale_handle_at(bbox_screen: &BBox4, cursor: Vec2) -> Option<usize> {
+    bbox_screen
+        .handle_points()
+        .iter()
+        .enumerate()
+        .map(|(i, point)| (i, (*point - cursor).len2()))
+        .filter(|(_, d2)| *d2 <= SCALE_HANDLE_HIT_RADIUS_PX * SCALE_HANDLE_HIT_RADIUS_PX)
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(i, _)| i)
+}
+
+// Sits outside the middle of edge 0 of the selection box, in screen space.
+pub fn selection_rotation_handle_pos(bbox_screen: &BBox4) -> Vec2 {
+    let mid = (bbox_screen.corners[0] + bbox_screen.corners[1]) * 0.5;
+    let out = mid - bbox_screen.center();
+    let out = if out.len2() > 1e-12 {
+        out * (1.0 / out.len())
+    } else {
+        Vec2 { x: 0.0, y: -1.0 }
+    };
+    mid + out * ROTATION_HANDLE_OFFSET_PX
+}
+
+struct ScaleDrag {
+    anchor: Vec2,
+    axis: Vec2,
+    handle_start: Vec2,
+    cursor_offset: Vec2,
+    scales_along: bool,
+    scales_across: bool,
+    applied: (f64, f64),
+}
+
+impl ScaleDrag {
+    // Corners scale both axes around the opposite corner, edges scale one axis around the
+    // opposite edge. `axis` runs along edge 0 of the selection box.
+    fn new(bbox_inner: &BBox4, handle: usize, cursor_playfield: Vec2) -> Self {
+        let points = bbox_inner.handle_points();
+        let anchor = if handle < 4 {
+            points[(handle + 2) % 4]
+        } else {
+            points[4 + (handle - 4 + 2) % 4]
+        };
+        let edge = bbox_inner.corners[1] - bbox_inner.corners[0];
+        let axis = if edge.len2() > 1e-12 {
+            edge
+        } else {
+            Vec2 { x: 1.0, y: 0.0 }
+        };
+        ScaleDrag {
+            anchor,
+            axis,
+            handle_start: points[handle],
+            cursor_offset: cursor_playfield - points[handle],
+            scales_along: handle < 4 || handle % 2 == 1,
+            scales_across: handle < 4 || handle % 2 == 0,
+            applied: (1.0, 1.0),
+        }
+    }
+
+    // Total (along, across) factors for the handle following `cursor_playfield`.
+    fn factors(&self, cursor_playfield: Vec2, lock_aspect: bool) -> (f64, f64) {
+        let start = self.handle_start - self.anchor;
+        let target = cursor_playfield - self.cursor_offset - self.anchor;
+        let u = self.axis * (1.0 / self.axis.len());
+        let v = Vec2 { x: -u.y, y: u.x };
+        let factor = |dir: Vec2| {
+            let start_proj = start.x * dir.x + start.y * dir.y;
+            if start_proj.abs() > 1e-6 {
+                (target.x * dir.x + target.y * dir.y) / start_proj
+            } else {
+                1.0
+            }
+        };
+        let along = if self.scales_along { factor(u) } else { 1.0 };
+        let across = if self.scales_across { factor(v) } else { 1.0 };
+        if !lock_aspect {
+            return (along.max(0.01), across.max(0.01));
+        }
+        let uniform = if self.scales_along && self.scales_across {
+            if start.len2() > 1e-12 {
+                (target.x * start.x + target.y * start.y) / start.len2()
+            } else {
+                1.0
+            }
+        } else if self.scales_along {
+            along
+        } else {
+            across
+        };
+        (uniform.max(0.01), uniform.max(0.01))
+    }
+}
+
+pub fn create_selection_scale_hitbox(
+    hover_state: Arc<AtomicBool>,
+    dragging_state: Arc<AtomicBool>,
+    edit_state: Arc<RwLock<EditState>>,
+    target_left_selection: bool,
+    shift_held: Arc<AtomicBool>,
+    playfield_screen_scale: Arc<AtomicVec2>,
+    playfield_screen_top_left: Arc<AtomicVec2>,
+) -> Rc<RectHitbox> {
+    let mut drag = None::<ScaleDrag>;
+    let mut changed = false;
+    Rc::new(RectHitbox::new(
+        Vec2 { x: 0.0, y: 0.0 },
+        Vec2 { x: 1.0, y: 1.0 },
+        Box::new(move |event: DragEvent| match event {
+            DragEvent::Move {
+                absolute_cursor_pos,
+                left,
+            } => {
+                if !left {
+                    return;
+                }
+                let scale = playfield_screen_scale.load();
+                let top_left = playfield_screen_top_left.load();
+                let cursor_playfield = Vec2 {
+                    x: (absolute_cursor_pos.x - top_left.x) / scale.x.max(1e-9),
+                    y: (absolute_cursor_pos.y - top_left.y) / scale.y.max(1e-9),
+                };
+                let mut state = edit_state.write().expect("edit_state lock poisoned");
+                if drag.is_none() {
+                    let selection = if target_left_selection {
+                        &state.left_selection
+                    } else {
+                        &state.right_selection
+                    };
+                    let Some(selection) = selection else {
+                        return;
+                    };
+                    let outer_screen = BBox4 {
+                        corners: selection.bbox_outer.corners.map(|corner| Vec2 {
+                            x: top_left.x + corner.x * scale.x,
+                            y: top_left.y + corner.y * scale.y,
+                        }),
+                    };
+                    let Some(handle) =
+                        selection_scale_handle_at(&outer_screen, absolute_cursor_pos)
+                    else {
+                        return;
+                    };
+                    drag = Some(ScaleDrag::new(
+                        &selection.bbox_inner,
+                        handle,
+                        cursor_playfield,
+                    ));
+                    dragging_state.store(true, Ordering::Release);
+                }
+                let Some(drag) = drag.as_mut() else {
+                    return;
+                };
+                let (along, across) =
+                    drag.factors(cursor_playfield, shift_held.load(Ordering::Acquire));
+                let step = (along / drag.applied.0, across / drag.applied.1);
+                if (step.0 - 1.0).abs() > 1e-9 || (step.1 - 1.0).abs() > 1e-9 {
+                    state.scale_selection_along_axis(
+                        target_left_selection,
+                        drag.anchor,
+                        drag.axis,
+                        step.0,
+                        step.1,
+                        false,
+                    );
+                    drag.applied = (along, across);
+                    changed = true;
+                }
+            }
+            DragEvent::Stop => {
+                dragging_state.store(false, Ordering::Release);
+                if changed {
+                    let mut state = edit_state.write().expect("edit_state lock poisoned");
+                    state.checkpoint_current_state();
+                }
+                changed = false;
+                drag = None;
+            }
+        }),
+        Box::new(move |event: HoverEvent| match event {
+            HoverEvent::Move { .. } => hover_state.store(true, Ordering::Release),
+            HoverEvent::Exit => hover_state.store(false, Ordering::Release),
+        }),
+    ))
+}
+
 pub fn wire_point_hit_test<F>(hitbox: &Rc<SimpleHitbox>, contains: F)
 where
     F: 'static + Fn(Vec2) -> bool,
@@ -173,91 +369,37 @@ pub fn create_selection_drag_hitbox(
                     } else {
                         let offset = cursor_offset.unwrap_or(Vec2 { x: 0.0, y: 0.0 });
                         let part_of_object = dragged_part_of_object.unwrap_or(false);
-                        let unsnapped = cursor_playfield - offset;
-                        let unsnapped_screen = Vec2 {
-                            x: playfield_top_left.x + unsnapped.x * scale.x,
-                            y: playfield_top_left.y + unsnapped.y * scale.y,
-                        };
-                        let snapped = {
-                            let state = edit_state.read().expect("edit_state lock poisoned");
-                            let origin_locked = if target_left_selection {
-                                state
-                                    .left_selection
-                                    .as_ref()
-                                    .map(|s| s.origin_locked)
-                                    .unwrap_or(false)
-                            } else {
-                                state
-                                    .right_selection
-                                    .as_ref()
-                                    .map(|s| s.origin_locked)
-                                    .unwrap_or(false)
-                            };
-                            let mut best: Option<(f64, Vec2)> = None;
-                            for snap in state.snap_positions.positions.iter() {
-                                if snap.virtual_stack && !part_of_object {
-                                    continue;
-                                }
-                                let (from_same_side_selection, from_same_side_origin) =
-                                    if target_left_selection {
-                                        (snap.from_left_sel_and_movable, snap.is_left_origin)
-                                    } else {
-                                        (snap.from_right_sel_and_movable, snap.is_right_origin)
-                                    };
-                                if from_same_side_selection
-                                    || (from_same_side_origin && !origin_locked)
-                                {
-                                    continue;
-                                }
-                                let snap_screen = Vec2 {
-                                    x: playfield_top_left.x + snap.pos.x * scale.x,
-                                    y: playfield_top_left.y + snap.pos.y * scale.y,
-                                };
-                                let d2 = (snap_screen - unsnapped_screen).len2();
-                                if d2 > snap_distance2 {
-                                    continue;
-                                }
-                                match best {
-                                    Some((best_d2, _)) if d2 >= best_d2 => {}
-                                    _ => best = Some((d2, snap.pos)),
-                                }
-                            }
-                            best.map(|(_, pos)| pos).unwrap_or(unsnapped)
-                        };
+                        let snapped = edit_state
+                            .read()
+                            .expect("edit_state lock poisoned")
+                            .snap_dragged_selection(
+                                target_left_selection,
+                                cursor_playfield - offset,
+                                part_of_object,
+                                scale,
+                                snap_distance_px,
+                            );
                         (snapped, offset, part_of_object)
                     };
 
-                    if let Some(prev) = last_pos {
-                        let delta_playfield = current_pos - prev;
-                        if delta_playfield.x.abs() > 0.0 || delta_playfield.y.abs() > 0.0 {
-                            let mut state = edit_state.write().expect("edit_state lock poisoned");
-                            state.translate_selection(target_left_selection, delta_playfield, false);
-                            state.set_selection_drag_state(
-                                target_left_selection,
-                                Some(DragState {
-                                    pos: current_pos,
-                                    part_of_object: current_part_of_object,
-                                    is_rotation: false,
-                                }),
-                            );
-                            changed = true;
-                        } else {
-                            let mut state = edit_state.write().expect("edit_state lock poisoned");
-                            state.set_selection_drag_state(
-                                target_left_selection,
-                                Some(DragState {
-                                    pos: current_pos,
-                                    part_of_object: current_part_of_object,
-                                    is_rotation: false,
-                                }),
-                            );
-                        }
-                    } else {
+                    {
                         let mut state = edit_state.write().expect("edit_state lock poisoned");
+                        if last_pos.is_some() {
+                            // The renderer may already have moved the selection ahead of us,
+                            // so measure the delta from where the selection is now.
+                            let prev = state
+                                .selection_drag_pos(target_left_selection)
+                                .unwrap_or(current_pos);
+                            let delta_playfield = current_pos - prev;
+                            if delta_playfield.x.abs() > 0.0 || delta_playfield.y.abs() > 0.0 {
+                                state.drag_selection(target_left_selection, delta_playfield);
+                            }
+                        }
                         state.set_selection_drag_state(
                             target_left_selection,
                             Some(DragState {
                                 pos: current_pos,
+                                grab_offset: current_offset,
                                 part_of_object: current_part_of_object,
                                 is_rotation: false,
                             }),
@@ -367,6 +509,7 @@ pub fn create_selection_drag_hitbox(
                         target_left_selection,
                         Some(DragState {
                             pos: current_pos,
+                            grab_offset: current_offset,
                             part_of_object: current_part_of_object,
                             is_rotation: true,
                         }),
@@ -418,6 +561,7 @@ pub fn create_selection_drag_hitbox(
                                         target_left_selection,
                                         Some(DragState {
                                             pos: current_pos,
+                                            grab_offset: current_offset,
                                             part_of_object: current_part_of_object,
                                             is_rotation: true,
                                         }),
@@ -440,6 +584,7 @@ pub fn create_selection_drag_hitbox(
                 {
                     let mut state = edit_state.write().expect("edit_state lock poisoned");
                     state.set_selection_drag_state(target_left_selection, None);
+                    state.commit_drag();
                 }
                 if changed {
                     let mut state = edit_state.write().expect("edit_state lock poisoned");
@@ -459,6 +604,197 @@ pub fn create_selection_drag_hitbox(
     ))
 }
 
+// The anchor of a slider in the left selection closest to `cursor` (screen space) within
+// `radius_px`, as the slider's index, the anchor's index and its playfield position.
+pub fn slider_anchor_at(
+    state: &EditState,
+    cursor: Vec2,
+    radius_px: f64,
+    playfield_screen_scale: Vec2,
+    playfield_screen_top_left: Vec2,
+) -> Option<(usize, usize, Vec2)> {
+    let selection = state.left_selection.as_ref()?;
+    let map_state = state.current_map_state();
+    let mut best: Option<(f64, (usize, usize, Vec2))> = None;
+    for id in selection.objects.iter().copied() {
+        let object = map_state.objects.get(id);
+        let HitObject::Slider(slider) = &*object.hit_object else {
+            continue;
+        };
+        for (anchor, pos) in slider.control_points.anchors().into_iter().enumerate() {
+            let pos_screen = Vec2 {
+                x: playfield_screen_top_left.x + pos.x * playfield_screen_scale.x,
+                y: playfield_screen_top_left.y + pos.y * playfield_screen_scale.y,
+            };
+            let d2 = (pos_screen - cursor).len2();
+            if d2 > radius_px * radius_px {
+                continue;
+            }
+            match best {
+                Some((best_d2, _)) if d2 >= best_d2 => {}
+                _ => best = Some((d2, (id, anchor, pos))),
+            }
+        }
+    }
+    best.map(|(_, found)| found)
+}
+
+// Drags one anchor of a selected slider. Snapping is done by `snap_slider_anchor`, shift
+// aligns the anchor with its neighbour.
+pub fn create_slider_anchor_drag_hitbox(
+    edit_state: Arc<RwLock<EditState>>,
+    shift_held: Arc<AtomicBool>,
+    snap_distance_px: f64,
+    grab_radius_px: f64,
+    grid_px: f64,
+    playfield_screen_scale: Arc<AtomicVec2>,
+    playfield_screen_top_left: Arc<AtomicVec2>,
+) -> Rc<RectHitbox> {
+    // The slider's index, the anchor's index and the cursor's offset from the anchor.
+    let mut dragged = None::<(usize, usize, Vec2)>;
+    let mut changed = false;
+    Rc::new(RectHitbox::new(
+        Vec2 { x: 0.0, y: 0.0 },
+        Vec2 { x: 1.0, y: 1.0 },
+        Box::new(move |event: DragEvent| match event {
+            DragEvent::Move {
+                absolute_cursor_pos,
+                left,
+            } => {
+                if !left {
+                    return;
+                }
+                let scale = playfield_screen_scale.load();
+                let top_left = playfield_screen_top_left.load();
+                let cursor_playfield = Vec2 {
+                    x: (absolute_cursor_pos.x - top_left.x) / scale.x.max(1e-9),
+                    y: (absolute_cursor_pos.y - top_left.y) / scale.y.max(1e-9),
+                };
+                let Some((id, anchor, offset)) = dragged else {
+                    let state = edit_state.read().expect("edit_state lock poisoned");
+                    dragged = slider_anchor_at(
+                        &state,
+                        absolute_cursor_pos,
+                        grab_radius_px,
+                        scale,
+                        top_left,
+                    )
+                    .map(|(id, anchor, pos)| (id, anchor, cursor_playfield - pos));
+                    return;
+                };
+                let mut state = edit_state.write().expect("edit_state lock poisoned");
+                let object = state.current_map_state().objects.get(id);
+                let HitObject::Slider(slider) = &*object.hit_object else {
+                    return;
+                };
+                let anchors = slider.control_points.anchors();
+                let target = snap_slider_anchor(
+                    &anchors,
+                    anchor,
+                    cursor_playfield - offset,
+                    snap_distance_px / scale.x.max(1e-9),
+                    grid_px,
+                    shift_held.load(Ordering::Acquire),
+                );
+                if anchors
+                    .get(anchor)
+                    .is_some_and(|pos| pos.distance2(target) < 1e-12)
+                {
+                    return;
+                }
+                if let Some(new_id) = state.move_slider_anchor(id, anchor, target, false) {
+                    dragged = Some((new_id, anchor, offset));
+                    changed = true;
+                }
+            }
+            DragEvent::Stop => {
+                if changed {
+                    let mut state = edit_state.write().expect("edit_state lock poisoned");
+                    state.checkpoint_current_state();
+                }
+                dragged = None;
+                changed = false;
+            }
+        }),
+        Box::new(move |_event: HoverEvent| {}),
+    ))
+}
+
+pub fn create_selection_rotation_hitbox(
+    hover_state: Arc<AtomicBool>,
+    dragging_state: Arc<AtomicBool>,
+    edit_state: Arc<RwLock<EditState>>,
+    target_left_selection: bool,
+    ctrl_held: Arc<AtomicBool>,
+    playfield_screen_scale: Arc<AtomicVec2>,
+    playfield_screen_top_left: Arc<AtomicVec2>,
+) -> Rc<RectHitbox> {
+    let mut start_angle = None::<f64>;
+    let mut applied_degrees = 0.0;
+    Rc::new(RectHitbox::new(
+        Vec2 { x: 0.0, y: 0.0 },
+        Vec2 { x: 1.0, y: 1.0 },
+        Box::new(move |event: DragEvent| match event {
+            DragEvent::Move {
+                absolute_cursor_pos,
+                left,
+            } => {
+                if !left {
+                    return;
+                }
+                let scale = playfield_screen_scale.load();
+                let top_left = playfield_screen_top_left.load();
+                let cursor_playfield = Vec2 {
+                    x: (absolute_cursor_pos.x - top_left.x) / scale.x.max(1e-9),
+                    y: (absolute_cursor_pos.y - top_left.y) / scale.y.max(1e-9),
+                };
+                let mut state = edit_state.write().expect("edit_state lock poisoned");
+                let selection = if target_left_selection {
+                    &state.left_selection
+                } else {
+                    &state.right_selection
+                };
+                let Some(selection) = selection else {
+                    return;
+                };
+                let arm = cursor_playfield - selection.origin;
+                if arm.len2() < 1e-6 {
+                    return;
+                }
+                let angle = arm.y.atan2(arm.x).to_degrees();
+                let Some(start) = start_angle else {
+                    start_angle = Some(angle);
+                    applied_degrees = 0.0;
+                    dragging_state.store(true, Ordering::Release);
+                    return;
+                };
+                let mut total = (angle - start + 180.0).rem_euclid(360.0) - 180.0;
+                if ctrl_held.load(Ordering::Acquire) {
+                    total = (total / ROTATION_SNAP_DEGREES).round() * ROTATION_SNAP_DEGREES;
+                }
+                let delta = total - applied_degrees;
+                if delta.abs() > 1e-9 {
+                    state.rotate_selection_degrees(target_left_selection, delta, false);
+                    applied_degrees = total;
+                }
+            }
+            DragEvent::Stop => {
+                dragging_state.store(false, Ordering::Release);
+                if start_angle.is_some() && applied_degrees.abs() > 1e-9 {
+                    let mut state = edit_state.write().expect("edit_state lock poisoned");
+                    state.checkpoint_current_state();
+                }
+                start_angle = None;
+                applied_degrees = 0.0;
+            }
+        }),
+        Box::new(move |event: HoverEvent| match event {
+            HoverEvent::Move { .. } => hover_state.store(true, Ordering::Release),
+            HoverEvent::Exit => hover_state.store(false, Ordering::Release),
+        }),
+    ))
+}
+
 pub fn create_selection_origin_drag_hitbox(
     hover_state: Arc<AtomicBool>,
     dragging_state: Arc<AtomicBool>,
@@ -587,17 +923,22 @@ pub fn create_selection_origin_drag_hitbox(
     ))
 }
 
+// Seeks across the range the bottom timeline shows. A drag that starts inside the top
+// timeline's window moves that window along instead of jumping the playhead to the cursor.
 pub fn create_progress_bar_hitbox(
     audio: Arc<AudioEngine>,
     seek_dragging: Arc<AtomicBool>,
     seek_resume_after_drag: Arc<AtomicBool>,
     progress_bar_hitbox_hovered: Arc<AtomicBool>,
+    bottom_timeline_range: Arc<AtomicVec2>,
+    top_timeline_window: Arc<AtomicVec2>,
 ) -> Rc<RectHitbox> {
     let drag_audio = Arc::clone(&audio);
     let drag_seek_dragging = Arc::clone(&seek_dragging);
     let drag_seek_resume_after_drag: Arc<AtomicBool> = Arc::clone(&seek_resume_after_drag);
     Rc::new_cyclic(|weak_hitbox: &std::rc::Weak<RectHitbox>| {
         let weak_for_drag = weak_hitbox.clone();
+        let mut grab_offset_ms = 0.0;
         RectHitbox::new(
             Vec2 { x: 0.0, y: 0.0 },
             Vec2 { x: 1.0, y: 1.0 },
@@ -611,15 +952,7 @@ pub fn create_progress_bar_hitbox(
                         return;
                     }
 
-                    if !drag_seek_dragging.swap(true, Ordering::AcqRel) {
-                        let was_playing = drag_audio.is_playing();
-                        drag_seek_resume_after_drag.store(was_playing, Ordering::Release);
-                        if was_playing {
-                            drag_audio.pause();
-                        }
-                    }
-
-                    let total_ms = drag_audio.song_total_ms();
+                    let total_ms = drag_audio.song_total().0;
                     if total_ms <= 0.0 {
                         return;
                     }
@@ -629,7 +962,33 @@ pub fn create_progress_bar_hitbox(
                     let (hitbox_origin, hitbox_size) = hitbox.bounds();
                     let pos = absolute_cursor_pos - hitbox_origin;
                     let frac = (pos.x / hitbox_size.x.max(1.0)).clamp(0.0, 1.0);
-                    drag_audio.seek_map_time_ms(frac * total_ms);
+                    let range = bottom_timeline_range.load();
+                    let range = if range.y > range.x {
+                        BottomTimelineRange {
+                            start_ms: range.x,
+                            end_ms: range.y,
+                        }
+                    } else {
+                        BottomTimelineRange::full(total_ms)
+                    };
+                    let cursor_ms = range.time_at(frac);
+
+                    if !drag_seek_dragging.swap(true, Ordering::AcqRel) {
+                        let was_playing = drag_audio.is_playing();
+                        drag_seek_resume_after_drag.store(was_playing, Ordering::Release);
+                        if was_playing {
+                            drag_audio.pause();
+                        }
+                        let window = top_timeline_window.load();
+                        grab_offset_ms = if cursor_ms >= window.x && cursor_ms <= window.y {
+                            drag_audio.current_time().0 - cursor_ms
+                        } else {
+                            0.0
+                        };
+                    }
+
+                    let target_ms = (cursor_ms + grab_offset_ms).clamp(0.0, total_ms);
+                    drag_audio.seek(SongTimeMs(target_ms));
                 }
                 DragEvent::Stop => {
                     if drag_seek_dragging.swap(false, Ordering::AcqRel)
@@ -649,17 +1008,246 @@ pub fn create_progress_bar_hitbox(
     })
 }
 
-pub fn create_play_pause_button(audio: Arc<AudioEngine>) -> Rc<SimpleButton> {
-    let play_pause_button_audio = Arc::clone(&audio);
+// Time under `x` on the top timeline, which spans `window` (x = start, y = end in ms) over
+// the screen rect at `origin` with `size`.
+fn top_timeline_time_at(window: Vec2, origin: Vec2, size: Vec2, x: f64) -> f64 {
+    window.x + (x - origin.x) / size.x.max(1.0) * (window.y - window.x)
+}
+
+// Music onsets and the 1/6 ticks off the grid of `divisor` within half a beat of `time`,
+// the alternatives to the grid offered while dragging.
+fn timeline_drag_marks(
+    timing: &Timing,
+    audio: &AudioEngine,
+    divisor: u32,
+    time: SongTimeMs,
+) -> TimelineDragMarks {
+    let Some(red_line) = timing.get_lines_at_time(time.0).0 else {
+        return TimelineDragMarks::default();
+    };
+    let window_ms = red_line.beat_length * 0.5;
+    let onsets_ms = audio
+        .onsets_between(SongTimeMs(time.0 - window_ms), SongTimeMs(time.0 + window_ms))
+        .into_iter()
+        .map(|onset| onset.0)
+        .collect();
+    let mut triplet_ticks_ms = Vec::new();
+    let triplet_tick = red_line.beat_length / 6.0;
+    let grid_tick = red_line.beat_length / divisor.max(1) as f64;
+    if triplet_tick > 0.0 {
+        let first = ((time.0 - window_ms - red_line.time) / triplet_tick).ceil() as i64;
+        let last = ((time.0 + window_ms - red_line.time) / triplet_tick).floor() as i64;
+        for i in first..=last {
+            let tick_ms = red_line.time + i as f64 * triplet_tick;
+            let grid_steps = (tick_ms - red_line.time) / grid_tick;
+            if (grid_steps - grid_steps.round()).abs() * grid_tick > 1.0 {
+                triplet_ticks_ms.push(tick_ms);
+            }
+        }
+    }
+    TimelineDragMarks {
+        onsets_ms,
+        triplet_ticks_ms,
+    }
+}
+
+// Drags the left selection along the top timeline. It is only grabbed within a few pixels of
+// the selection's time span, elsewhere the timeline is left to the hitboxes below. The
+// selection snaps to the ticks of the beat divisor, nearby onsets and triplet ticks are
+// marked on the timeline and taken instead while Alt is held.
+pub fn create_timeline_object_drag_hitbox(
+    edit_state: Arc<RwLock<EditState>>,
+    audio: Arc<AudioEngine>,
+    top_timeline_window: Arc<AtomicVec2>,
+    beat_divisor: u32,
+    alt_held: Arc<AtomicBool>,
+) -> Rc<RectHitbox> {
+    const GRAB_MARGIN_PX: f64 = 6.0;
+    let hitbox = Rc::new_cyclic(|weak_hitbox: &std::rc::Weak<RectHitbox>| {
+        let weak_for_drag = weak_hitbox.clone();
+        let edit_state = Arc::clone(&edit_state);
+        let top_timeline_window = Arc::clone(&top_timeline_window);
+        // The selection's start minus the cursor's time when the drag started.
+        let mut grab_offset_ms = None::<f64>;
+        RectHitbox::new(
+            Vec2 { x: 0.0, y: 0.0 },
+            Vec2 { x: 1.0, y: 1.0 },
+            Box::new(move |event| match event {
+                DragEvent::Move {
+                    left,
+                    absolute_cursor_pos,
+                } => {
+                    if !left {
+                        return;
+                    }
+                    let Some(hitbox) = weak_for_drag.upgrade() else {
+                        return;
+                    };
+                    let (origin, size) = hitbox.bounds();
+                    let window = top_timeline_window.load();
+                    let cursor_ms =
+                        top_timeline_time_at(window, origin, size, absolute_cursor_pos.x);
+                    let Some(offset_ms) = grab_offset_ms else {
+                        let state = edit_state.read().expect("edit_state lock poisoned");
+                        grab_offset_ms = state
+                            .left_selection_time_range()
+                            .map(|(start_ms, _)| start_ms - cursor_ms);
+                        return;
+                    };
+                    let mut state = edit_state.write().expect("edit_state lock poisoned");
+                    let timing = Arc::clone(&state.current_map_state().timing);
+                    let unsnapped_ms = cursor_ms + offset_ms;
+                    let marks = timeline_drag_marks(
+                        &timing,
+                        &audio,
+                        beat_divisor,
+                        SongTimeMs(unsnapped_ms),
+                    );
+                    let distance = |t: &f64| (t - unsnapped_ms).abs();
+                    let chosen_mark = if alt_held.load(Ordering::Acquire) {
+                        marks
+                            .onsets_ms
+                            .iter()
+                            .chain(marks.triplet_ticks_ms.iter())
+                            .copied()
+                            .min_by(|a, b| distance(a).total_cmp(&distance(b)))
+                    } else {
+                        None
+                    };
+                    let target_ms = chosen_mark
+                        .or_else(|| timing.nearest_tick(unsnapped_ms, beat_divisor.max(1) as f64))
+                        .unwrap_or(unsnapped_ms);
+                    state.timeline_drag_marks = marks;
+                    state.drag_selection_to_time(SongTimeMs(target_ms));
+                }
+                DragEvent::Stop => {
+                    if grab_offset_ms.take().is_some() {
+                        let mut state = edit_state.write().expect("edit_state lock poisoned");
+                        state.commit_drag();
+                        state.timeline_drag_marks = TimelineDragMarks::default();
+                    }
+                }
+            }),
+            Box::new(move |_event: HoverEvent| {}),
+        )
+    });
+    let weak_for_hit_test = Rc::downgrade(&hitbox);
+    hitbox.hitbox().set_hit_test(move |pos| {
+        let Some(hitbox) = weak_for_hit_test.upgrade() else {
+            return false;
+        };
+        let (origin, size) = hitbox.bounds();
+        if pos.x < origin.x
+            || pos.x > origin.x + size.x
+            || pos.y < origin.y
+            || pos.y > origin.y + size.y
+        {
+            return false;
+        }
+        let Ok(state) = edit_state.read() else {
+            return false;
+        };
+        let Some((start_ms, end_ms)) = state.left_selection_time_range() else {
+            return false;
+        };
+        let window = top_timeline_window.load();
+        let margin_ms = GRAB_MARGIN_PX * (window.y - window.x) / size.x.max(1.0);
+        let cursor_ms = top_timeline_time_at(window, origin, size, pos.x);
+        cursor_ms >= start_ms - margin_ms && cursor_ms <= end_ms + margin_ms
+    });
+    hitbox
+}
+
+// A left click on a line of the stats box asks the editor to copy it on its next frame, stored
+// as the line + 1 so 0 means no request.
+pub fn create_stats_box_hitbox(copy_line_requested: Arc<AtomicU32>) -> Rc<RectHitbox> {
+    Rc::new_cyclic(|weak_hitbox: &std::rc::Weak<RectHitbox>| {
+        let weak_for_drag = weak_hitbox.clone();
+        let mut pressed = false;
+        RectHitbox::new(
+            Vec2 { x: 0.0, y: 0.0 },
+            Vec2 { x: 1.0, y: 1.0 },
+            Box::new(move |event| match event {
+                DragEvent::Move {
+                    left,
+                    absolute_cursor_pos,
+                } => {
+                    if !left || pressed {
+                        return;
+                    }
+                    pressed = true;
+                    let Some(hitbox) = weak_for_drag.upgrade() else {
+                        return;
+                    };
+                    let (hitbox_origin, _) = hitbox.bounds();
+                    if let Some(line) =
+                        layout::stats_box_line_at(absolute_cursor_pos.y - hitbox_origin.y)
+                    {
+                        copy_line_requested.store(line as u32 + 1, Ordering::Release);
+                    }
+                }
+                DragEvent::Stop => pressed = false,
+            }),
+            Box::new(|_| {}),
+        )
+    })
+}
+
+// The editor toggles playback on its next frame, since starting can snap to the timing.
+pub fn create_play_pause_button(toggle_requested: Arc<AtomicBool>) -> Rc<SimpleButton> {
     Rc::new(SimpleButton::new(
         Vec2 { x: 0.0, y: 0.0 },
         Vec2 { x: 1.0, y: 1.0 },
-        Box::new(move || {
-            if play_pause_button_audio.is_playing() {
-                play_pause_button_audio.pause();
-            } else {
-                play_pause_button_audio.play();
+        Box::new(move || toggle_requested.store(true, Ordering::Release)),
+    ))
+}
+
+// Takes clicks on the playfield while the tool mode wants them, so they don't reach the
+// selection hitboxes below. A press is stored as 1 (left) or 2 (right) with its playfield
+// position and handed to the tool mode on the editor's next frame.
+pub fn create_tool_playfield_hitbox(
+    tool_mode: Arc<AtomicU32>,
+    click_requested: Arc<AtomicU32>,
+    click_playfield_pos: Arc<AtomicVec2>,
+    playfield_screen_scale: Arc<AtomicVec2>,
+    playfield_screen_top_left: Arc<AtomicVec2>,
+) -> Rc<SimpleHitbox> {
+    let to_playfield = {
+        let playfield_screen_scale = Arc::clone(&playfield_screen_scale);
+        let playfield_screen_top_left = Arc::clone(&playfield_screen_top_left);
+        move |pos: Vec2| {
+            let scale = playfield_screen_scale.load();
+            let top_left = playfield_screen_top_left.load();
+            Vec2 {
+                x: (pos.x - top_left.x) / scale.x.max(1e-9),
+                y: (pos.y - top_left.y) / scale.y.max(1e-9),
+            }
+        }
+    };
+    let to_playfield_for_drag = to_playfield.clone();
+    let mut pressed = false;
+    Rc::new(SimpleHitbox::new(
+        Box::new(move |pos| {
+            if !ToolMode::from_index(tool_mode.load(Ordering::Acquire)).takes_playfield_clicks() {
+                return false;
+            }
+            let pos = to_playfield(pos);
+            (0.0..=512.0).contains(&pos.x) && (0.0..=384.0).contains(&pos.y)
+        }),
+        Box::new(move |event: DragEvent| match event {
+            DragEvent::Move {
+                left,
+                absolute_cursor_pos,
+            } => {
+                if pressed {
+                    return;
+                }
+                pressed = true;
+                click_playfield_pos.store(to_playfield_for_drag(absolute_cursor_pos));
+                click_requested.store(if left { 1 } else { 2 }, Ordering::Release);
             }
+            DragEvent::Stop => pressed = false,
         }),
+        Box::new(|_| {}),
     ))
 }
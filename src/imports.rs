@@ -2,19 +2,37 @@ use std::{
     fs,
     path::{Path, PathBuf},
 };
-use image::{DynamicImage, GenericImageView, ImageFormat, RgbaImage, imageops::FilterType};
+use image::{
+    DynamicImage, GenericImageView, ImageFormat, RgbaImage, codecs::jpeg::JpegEncoder,
+    imageops::FilterType,
+};
 use winit::event_loop::EventLoop;
 
 use crate::{
     EDITOR_VERSION,
-    dotosu::osu_file::{OsuFile, parse_osu_file},
-    files::{extract_zip, sanitize_name, write_bytes_to_file},
+    dotosu::{
+        osr_file::{OsrFile, parse_osr_file},
+        osu_file::{OsuFile, parse_osu_file},
+    },
+    files::{
+        BeatmapsetFolder, extract_zip, read_folder_files, sanitize_name, write_bytes_to_file,
+    },
     dialogue_app::DialogueApp,
+    exports::{AnalysisExport, ObjectRow},
+    geometry::vec2::Vec2,
     map_format::{
-        beatmap::Beatmap, beatmapset::Beatmapset,
+        beatmap::Beatmap,
+        beatmapset::Beatmapset,
         convert_from_osu_format::convert_osu_beatmapset_to_internal,
+        diff_settings::DiffSettings,
+        objects::{
+            Circle, ComboInfo, HitObject, HitSample, Hitsound, HitsoundInfo, Slider, Spinner,
+        },
+        slider_curve::{ControlPointSegment, ControlPoints},
+        timing::{SampleSet, Timing},
     },
     scan_folder,
+    skin::Texture,
 };
 
 pub fn select_and_import_map(event_loop: &mut EventLoop<()>, selector: &mut DialogueApp) {
@@ -25,7 +43,11 @@ pub fn select_and_import_map(event_loop: &mut EventLoop<()>, selector: &mut Dial
         return;
     }
 
-    let entries = scan_folder(imports_path, Some(false), Some(&vec![".osz"]));
+    // Archives, and already extracted beatmap folders listed with a trailing slash.
+    let mut entries = scan_folder(imports_path, Some(false), Some(&vec![".osz", ".zip"]));
+    for dir in scan_folder(imports_path, Some(true), None) {
+        entries.push(format!("{}/", dir));
+    }
 
     if entries.is_empty() {
         println!("No maps found in imports/");
@@ -34,7 +56,8 @@ pub fn select_and_import_map(event_loop: &mut EventLoop<()>, selector: &mut Dial
 
     // --- Step 2: console selection ---
     println!("Available maps:");
-    let selection = match selector.select(event_loop, "Import map (.osz)", &entries) {
+    let selection = match selector.select(event_loop, "Import map (.osz, .zip or folder)", &entries)
+    {
         Some(idx) => idx,
         None => {
             println!("Import cancelled.");
@@ -43,7 +66,10 @@ pub fn select_and_import_map(event_loop: &mut EventLoop<()>, selector: &mut Dial
     };
     let selected_map = &entries[selection];
     println!("Importing: {}", selected_map);
-    import_osz(selected_map, event_loop, selector);
+    match selected_map.strip_suffix('/') {
+        Some(dir) => import_map_folder(dir, event_loop, selector),
+        None => import_osz(selected_map, event_loop, selector),
+    }
 }
 
 pub fn select_and_import_skin(event_loop: &mut EventLoop<()>, selector: &mut DialogueApp) {
@@ -109,16 +135,55 @@ fn import_osz(selected_map: &str, event_loop: &mut EventLoop<()>, selector: &mut
     let extracted = match extract_zip(osz_bytes) {
         Some(files) => files,
         None => {
-            println!("Failed to extract .osz file: {}", selected_map);
+            println!("Failed to extract archive: {}", selected_map);
             return;
         }
     };
+    import_map_files(selected_map, extracted, event_loop, selector);
+}
+
+fn import_map_folder(
+    selected_map: &str,
+    event_loop: &mut EventLoop<()>,
+    selector: &mut DialogueApp,
+) {
+    let files = read_folder_files(&Path::new("imports/").join(selected_map));
+    import_map_files(selected_map, files, event_loop, selector);
+}
+
+// Plain .zip archives are often made by zipping the beatmap folder itself, so a single folder
+// wrapping every file is dropped to get the flat layout of an .osz.
+fn strip_wrapper_folder(files: Vec<(String, Vec<u8>)>) -> Vec<(String, Vec<u8>)> {
+    let wrapper = match files.first().and_then(|(name, _)| name.split_once('/')) {
+        Some((dir, _)) => format!("{}/", dir),
+        None => return files,
+    };
+    if !files.iter().all(|(name, _)| name.starts_with(&wrapper)) {
+        return files;
+    }
+    log!("Dropping wrapper folder {} from import", wrapper);
+    files
+        .into_iter()
+        .map(|(name, bytes)| (name[wrapper.len()..].to_string(), bytes))
+        .collect()
+}
 
+fn import_map_files(
+    selected_map: &str,
+    extracted: Vec<(String, Vec<u8>)>,
+    event_loop: &mut EventLoop<()>,
+    selector: &mut DialogueApp,
+) {
+    let extracted = strip_wrapper_folder(extracted);
     let osu_files: Vec<(String, Vec<u8>)> = extracted
         .iter()
         .filter(|(name, _)| name.to_ascii_lowercase().ends_with(".osu"))
         .cloned()
         .collect();
+    if osu_files.is_empty() {
+        println!("No .osu files found in {}", selected_map);
+        return;
+    }
 
     let assets: Vec<(String, Vec<u8>)> = extracted
         .iter()
@@ -269,7 +334,7 @@ fn find_asset_bytes_by_name<'a>(assets: &'a [(String, Vec<u8>)], file_name: &str
     None
 }
 
-fn make_bg_small_png(background_bytes: Option<&[u8]>) -> Vec<u8> {
+pub fn make_bg_small_png(background_bytes: Option<&[u8]>) -> Vec<u8> {
     const OUT_SIZE: u32 = 128;
 
     let mut canvas = RgbaImage::from_pixel(OUT_SIZE, OUT_SIZE, image::Rgba([0, 0, 0, 255]));
@@ -291,6 +356,111 @@ fn make_bg_small_png(background_bytes: Option<&[u8]>) -> Vec<u8> {
     out.into_inner()
 }
 
+// The image as it is stored in the map's assets: oversized ones are downscaled and
+// re-encoded as "<stem>.jpg", others keep their file name and bytes.
+fn background_asset(image_path: &Path, bytes: Vec<u8>) -> Option<(String, Vec<u8>, DynamicImage)> {
+    const MAX_WIDTH: u32 = 1920;
+    const MAX_HEIGHT: u32 = 1080;
+    const JPEG_QUALITY: u8 = 90;
+
+    let decoded = match image::load_from_memory(&bytes) {
+        Ok(decoded) => decoded,
+        Err(err) => {
+            println!("Failed to decode background {}: {}", image_path.display(), err);
+            return None;
+        }
+    };
+    let file_stem = image_path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .map(sanitize_name)
+        .unwrap_or_else(|| "background".to_string());
+
+    let (src_w, src_h) = decoded.dimensions();
+    if src_w > MAX_WIDTH || src_h > MAX_HEIGHT {
+        let resized = decoded.resize(MAX_WIDTH, MAX_HEIGHT, FilterType::Triangle);
+        let mut out = std::io::Cursor::new(Vec::new());
+        let encoder = JpegEncoder::new_with_quality(&mut out, JPEG_QUALITY);
+        if let Err(err) = DynamicImage::ImageRgb8(resized.to_rgb8()).write_with_encoder(encoder) {
+            println!("Failed to compress background: {}", err);
+            return None;
+        }
+        log!(
+            "Resized background from {}x{} to {}x{}.",
+            src_w,
+            src_h,
+            resized.width(),
+            resized.height()
+        );
+        Some((format!("{}.jpg", file_stem), out.into_inner(), resized))
+    } else {
+        let file_name = image_path
+            .file_name()
+            .and_then(|s| s.to_str())
+            .map(sanitize_name)
+            .unwrap_or_else(|| format!("{}.png", file_stem));
+        Some((file_name, bytes, decoded))
+    }
+}
+
+// Copies an image into the map's assets, registering it with the open folder, and makes it the
+// background of one difficulty.
+pub fn import_diff_background(
+    folder: &mut BeatmapsetFolder,
+    version: &str,
+    image_path: &Path,
+) -> Option<(String, Texture)> {
+    let bytes = match fs::read(image_path) {
+        Ok(bytes) => bytes,
+        Err(err) => {
+            println!("Failed to read background {}: {}", image_path.display(), err);
+            return None;
+        }
+    };
+    let (file_name, bytes, decoded) = background_asset(image_path, bytes)?;
+    let file_name = folder.assets.free_name_for(&file_name, &bytes);
+
+    let save_path = Path::new("saves").join(&folder.map_dir_name);
+    let asset_path = save_path.join("assets").join(&file_name);
+    if let Err(err) = write_bytes_to_file(&asset_path, bytes.as_slice()) {
+        println!("Failed to write asset file {}: {}", asset_path.display(), err);
+        return None;
+    }
+
+    let diff_path = save_path.join("diffs").join(sanitize_name(version));
+    let beatmap_path = diff_path.join("beatmap.json");
+    let mut beatmap = match fs::read_to_string(&beatmap_path)
+        .ok()
+        .and_then(|json| serde_json::from_str::<Beatmap>(&json).ok())
+    {
+        Some(beatmap) => beatmap,
+        None => {
+            println!("Failed to read beatmap file {}", beatmap_path.display());
+            return None;
+        }
+    };
+    beatmap.events.set_background_name(&file_name);
+    let bg_small_png = make_bg_small_png(Some(bytes.as_slice()));
+    if let Err(err) = import_beatmap(diff_path, beatmap, bg_small_png.as_slice()) {
+        println!("{}", err);
+        return None;
+    }
+    folder.assets.insert(file_name.clone(), bytes);
+
+    let rgba = decoded.to_rgba8();
+    let (width, height) = rgba.dimensions();
+    println!("Background of {} set to {}.", version, file_name);
+    Some((
+        file_name,
+        Texture {
+            rgba: rgba.into_raw(),
+            width,
+            height,
+            is_2x: false,
+        },
+    ))
+}
+
 fn import_beatmaps(save_path: PathBuf, beatmaps: Vec<Beatmap>, assets: &[(String, Vec<u8>)]) {
     let diffs_path = save_path.join("diffs");
     for beatmap in beatmaps {
@@ -302,42 +472,43 @@ fn import_beatmaps(save_path: PathBuf, beatmaps: Vec<Beatmap>, assets: &[(String
             find_asset_bytes_by_name(assets, bg_name.as_str())
         };
         let bg_small_png = make_bg_small_png(bg_bytes);
-        import_beatmap(diff_path, beatmap, bg_small_png.as_slice());
+        if let Err(err) = import_beatmap(diff_path, beatmap, bg_small_png.as_slice()) {
+            println!("{}", err);
+        }
     }
 }
 
-fn import_beatmap(diff_path: PathBuf, beatmap: Beatmap, bg_small_png: &[u8]) {
+// Writes beatmap.json and the small background preview of one difficulty.
+fn import_beatmap(
+    diff_path: PathBuf,
+    beatmap: Beatmap,
+    bg_small_png: &[u8],
+) -> Result<(), String> {
     let beatmap_path = diff_path.join("beatmap.json");
-    let beatmap_json = match serde_json::to_string_pretty(&beatmap) {
-        Ok(json) => json,
-        Err(err) => {
-            println!("Failed to serialize beatmap to JSON: {}", err);
-            return;
-        }
-    };
-    if let Err(err) = write_bytes_to_file(&beatmap_path, beatmap_json.as_bytes()) {
-        println!(
+    let beatmap_json = serde_json::to_string_pretty(&beatmap)
+        .map_err(|err| format!("Failed to serialize beatmap to JSON: {}", err))?;
+    write_bytes_to_file(&beatmap_path, beatmap_json.as_bytes()).map_err(|err| {
+        format!(
             "Failed to write beatmap file {}: {}",
             beatmap_path.display(),
             err
-        );
-        return;
-    }
+        )
+    })?;
 
     let bg_small_path = diff_path.join("bg_small.png");
-    if let Err(err) = write_bytes_to_file(&bg_small_path, bg_small_png) {
-        println!(
+    write_bytes_to_file(&bg_small_path, bg_small_png).map_err(|err| {
+        format!(
             "Failed to write diff background preview {}: {}",
             bg_small_path.display(),
             err
-        );
-        return;
-    }
+        )
+    })?;
 
     log!(
         "Successfully imported beatmap to {}",
         beatmap_path.display()
     );
+    Ok(())
 }
 
 fn import_osz_files(
@@ -435,3 +606,193 @@ fn import_osk_files(
 
     println!("Successfully imported a skin to {}", skin_path.display());
 }
+
+fn parse_sampleset(name: &str) -> Option<SampleSet> {
+    match name.to_ascii_lowercase().as_str() {
+        "normal" => Some(SampleSet::Normal),
+        "soft" => Some(SampleSet::Soft),
+        "drum" => Some(SampleSet::Drum),
+        _ => None,
+    }
+}
+
+fn osu_sampleset_number(sample_set: Option<SampleSet>) -> i32 {
+    match sample_set {
+        Some(SampleSet::Normal) => 1,
+        Some(SampleSet::Soft) => 2,
+        Some(SampleSet::Drum) => 3,
+        None => 0,
+    }
+}
+
+// Converts a row of the analysis export back into an object. "Auto" samplesets and a zero
+// volume follow the timing point at the object, sliders without a path become straight.
+fn object_from_row(
+    row: &ObjectRow,
+    timing: &Timing,
+    diff_settings: &DiffSettings,
+) -> Option<HitObject> {
+    let (red_line, green_line) = timing.get_lines_at_time(row.time);
+    let red_line = red_line?;
+    let (default_sampleset, default_volume, sv_multiplier) = match &green_line {
+        Some(gl) => (gl.sample_set.clone(), gl.volume, gl.sv_multiplier),
+        None => (red_line.sample_set.clone(), red_line.volume, 1.0),
+    };
+    let hit_sampleset = parse_sampleset(&row.sampleset).unwrap_or(default_sampleset);
+    let hitsound_info = HitsoundInfo {
+        additions_sampleset: parse_sampleset(&row.additions).unwrap_or(hit_sampleset.clone()),
+        hit_sampleset,
+        volume: if row.volume > 0.0 { row.volume } else { default_volume },
+        index: row.custom_index,
+        play_whistle: row.whistle,
+        play_finish: row.finish,
+        play_clap: row.clap,
+        filename: None,
+    };
+    let combo_info = ComboInfo {
+        new_combo: row.new_combo,
+        color_skip: row.color_skip,
+    };
+    let pos = Vec2 { x: row.x, y: row.y };
+
+    match row.kind.to_ascii_lowercase().as_str() {
+        "circle" => Some(HitObject::Circle(Circle {
+            pos,
+            time: row.time,
+            combo_info,
+            hitsound_info,
+        })),
+        "slider" => {
+            let slides = row.slides.max(1);
+            let sv_pixels_per_ms =
+                (diff_settings.sv_multiplier * 100.0 * sv_multiplier) / red_line.beat_length;
+            let length_pixels = if row.length > 0.0 {
+                row.length
+            } else {
+                sv_pixels_per_ms * (row.end_time - row.time) / slides as f64
+            };
+            if length_pixels <= 0.0 {
+                return None;
+            }
+            let control_points = match &row.curve {
+                Some(curve) => {
+                    let offset = pos - curve.start;
+                    curve.move_by_offset(offset)
+                }
+                None => ControlPoints {
+                    start: pos,
+                    slider_segments: vec![ControlPointSegment::Linear(
+                        vec![],
+                        Vec2 {
+                            x: pos.x + length_pixels,
+                            y: pos.y,
+                        },
+                    )],
+                },
+            };
+            Some(HitObject::Slider(Slider {
+                time: row.time,
+                slides,
+                length_pixels,
+                sv_pixels_per_ms,
+                combo_info,
+                hitsounds: vec![hitsound_info.clone(); slides as usize + 1],
+                sliderbody_hitsound: hitsound_info,
+                control_points,
+            }))
+        }
+        "spinner" => Some(HitObject::Spinner(Spinner {
+            x: row.x,
+            y: row.y,
+            time: row.time,
+            end_time: row.end_time.max(row.time),
+            combo_info,
+            hitsound: Hitsound {
+                normal: true,
+                whistle: row.whistle,
+                finish: row.finish,
+                clap: row.clap,
+            },
+            hitsample: HitSample {
+                normal_set: osu_sampleset_number(parse_sampleset(&row.sampleset)),
+                addition_set: osu_sampleset_number(parse_sampleset(&row.additions)),
+                index: row.custom_index,
+                volume: (row.volume * 100.0).round() as i32,
+                filename: String::new(),
+            },
+        })),
+        _ => None,
+    }
+}
+
+// Reads an object list in the analysis export's JSON schema.
+pub fn read_object_list(
+    path: &Path,
+    timing: &Timing,
+    diff_settings: &DiffSettings,
+) -> Option<Vec<HitObject>> {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(err) => {
+            println!("Failed to read {}: {}", path.display(), err);
+            return None;
+        }
+    };
+    let list: AnalysisExport = match serde_json::from_str(&text) {
+        Ok(list) => list,
+        Err(err) => {
+            println!("Failed to parse {}: {}", path.display(), err);
+            return None;
+        }
+    };
+    let mut objects = Vec::with_capacity(list.objects.len());
+    for row in list.objects.iter() {
+        match object_from_row(row, timing, diff_settings) {
+            Some(object) => objects.push(object),
+            None => println!("Skipping {} at {:.0}ms.", row.kind, row.time),
+        }
+    }
+    Some(objects)
+}
+
+// Reads a .osr replay, see dotosu/osr_file.rs.
+pub fn read_replay(path: &Path) -> Option<OsrFile> {
+    let data = match fs::read(path) {
+        Ok(data) => data,
+        Err(err) => {
+            println!("Failed to read {}: {}", path.display(), err);
+            return None;
+        }
+    };
+    parse_osr_file(&data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn png_bytes(width: u32, height: u32) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        DynamicImage::ImageRgba8(RgbaImage::new(width, height))
+            .write_to(&mut std::io::Cursor::new(&mut bytes), ImageFormat::Png)
+            .expect("png does not encode");
+        bytes
+    }
+
+    #[test]
+    fn oversized_backgrounds_are_stored_as_smaller_jpegs() {
+        let small = png_bytes(64, 48);
+        let (name, bytes, decoded) = background_asset(Path::new("bg.png"), small.clone()).unwrap();
+        assert_eq!(name, "bg.png");
+        assert_eq!(bytes, small);
+        assert_eq!(decoded.dimensions(), (64, 48));
+
+        let (name, bytes, decoded) =
+            background_asset(Path::new("wide.png"), png_bytes(2400, 1200)).unwrap();
+        assert_eq!(name, "wide.jpg");
+        assert_eq!(decoded.dimensions(), (1920, 960));
+        assert!(image::load_from_memory_with_format(&bytes, ImageFormat::Jpeg).is_ok());
+
+        assert!(background_asset(Path::new("notes.png"), b"not an image".to_vec()).is_none());
+    }
+}
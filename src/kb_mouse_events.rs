@@ -5,12 +5,36 @@ use winit::{
     keyboard::{KeyCode, PhysicalKey},
 };
 
-use crate::{editor::EditorApp, geometry::vec2::Vec2};
+use crate::{
+    editor::EditorApp,
+    geometry::vec2::Vec2,
+    tool_mode::{ToolEvent, ToolMode},
+};
+
+fn digit_key_value(key: PhysicalKey) -> Option<u32> {
+    match key {
+        PhysicalKey::Code(KeyCode::Digit0) => Some(0),
+        PhysicalKey::Code(KeyCode::Digit1) => Some(1),
+        PhysicalKey::Code(KeyCode::Digit2) => Some(2),
+        PhysicalKey::Code(KeyCode::Digit3) => Some(3),
+        PhysicalKey::Code(KeyCode::Digit4) => Some(4),
+        PhysicalKey::Code(KeyCode::Digit5) => Some(5),
+        PhysicalKey::Code(KeyCode::Digit6) => Some(6),
+        PhysicalKey::Code(KeyCode::Digit7) => Some(7),
+        PhysicalKey::Code(KeyCode::Digit8) => Some(8),
+        PhysicalKey::Code(KeyCode::Digit9) => Some(9),
+        _ => None,
+    }
+}
 
 impl EditorApp {
     pub fn handle_keyboard_input(&mut self, event: &KeyEvent) {
         if event.state == ElementState::Pressed {
             if self.is_current_state_rename_active() {
+                // Keys pressed while an input method composes belong to the composition.
+                if self.is_text_input_composing() {
+                    return;
+                }
                 match event.physical_key {
                     PhysicalKey::Code(KeyCode::Enter) | PhysicalKey::Code(KeyCode::NumpadEnter) => {
                         self.commit_current_state_rename();
@@ -24,6 +48,11 @@ impl EditorApp {
                         self.backspace_current_state_rename();
                         return;
                     }
+                    PhysicalKey::Code(KeyCode::KeyV) if self.modifiers.control_key() => {
+                        // CTRL + V: PASTE CLIPBOARD TEXT
+                        self.paste_current_state_rename_text();
+                        return;
+                    }
                     _ => {}
                 }
 
@@ -34,18 +63,266 @@ impl EditorApp {
             }
         }
 
+        if event.state == ElementState::Pressed && self.is_onboarding_visible() {
+            // ANY KEY: DISMISS THE FIRST-RUN OVERLAY
+            self.dismiss_onboarding();
+            return;
+        }
+
+        if event.state == ElementState::Pressed && self.modifiers.control_key() {
+            if event.physical_key == PhysicalKey::Code(KeyCode::Tab) {
+                // CTRL + TAB: QUICK SWITCH TO A RECENTLY OPENED MAP
+                self.begin_recent_map_switch();
+                return;
+            }
+            if event.physical_key == PhysicalKey::Code(KeyCode::KeyT) {
+                // CTRL + T: RUN AN EXTERNAL TOOL ON THIS DIFFICULTY
+                self.begin_external_tool_selection();
+                return;
+            }
+            if event.physical_key == PhysicalKey::Code(KeyCode::KeyU) {
+                // CTRL + U: LOOP PLAYBACK OVER THE SELECTION, AGAIN TO STOP
+                self.toggle_selection_loop();
+                return;
+            }
+            if event.physical_key == PhysicalKey::Code(KeyCode::KeyL) {
+                // CTRL + L: EXPORT THE LOG OF EDITS MADE THIS SESSION
+                self.export_operation_log();
+                return;
+            }
+            if event.physical_key == PhysicalKey::Code(KeyCode::KeyR) {
+                // CTRL + R: LOAD A .OSR REPLAY FROM IMPORTS AND OVERLAY ITS CURSOR AND HITS
+                self.begin_replay_import();
+                return;
+            }
+            if event.physical_key == PhysicalKey::Code(KeyCode::KeyS) {
+                // CTRL + S: RETRY SAVES THAT FAILED, LIKE ON A FULL DISK
+                self.retry_failed_saves();
+                return;
+            }
+            if event.physical_key == PhysicalKey::Code(KeyCode::KeyG) {
+                // CTRL + G: SAVE, RESTORE OR DELETE NAMED SELECTION SETS
+                self.begin_selection_sets();
+                return;
+            }
+            if event.physical_key == PhysicalKey::Code(KeyCode::KeyV) {
+                // CTRL + V: PASTE OBJECTS COPIED WITH C, ALSO FROM ANOTHER EDITOR INSTANCE, OR
+                // JUMP TO A MODDING TIMESTAMP LIKE 01:23:456 (1,2,3) AND SELECT ITS OBJECTS
+                self.paste_from_clipboard();
+                return;
+            }
+            if event.physical_key == PhysicalKey::Code(KeyCode::KeyC) && self.modifiers.shift_key()
+            {
+                // CTRL + SHIFT + C: COPY THE SELECTION AS A MODDING TIMESTAMP
+                self.copy_selection_timestamp();
+                return;
+            }
+            // CTRL + 1-9: RESTORE SELECTION SET 1-9
+            if let Some(digit @ 1..=9) = digit_key_value(event.physical_key) {
+                self.restore_selection_set(digit as usize);
+                return;
+            }
+        }
+
+        if event.state == ElementState::Pressed && self.modifiers.shift_key() {
+            // SHIFT + 0-9: JUMP TO 0% - 90% OF THE SONG
+            if let Some(digit) = digit_key_value(event.physical_key) {
+                self.seek_to_percent(digit as f64 * 10.0);
+                return;
+            }
+            if event.physical_key == PhysicalKey::Code(KeyCode::F1) {
+                // SHIFT + F1: EXPORT MUSIC, HITSOUNDS AND METRONOME OF A TIME RANGE AS A WAV
+                self.begin_playback_mix_export();
+                return;
+            }
+            if event.physical_key == PhysicalKey::Code(KeyCode::F3) {
+                // SHIFT + F3: EXPORT LOOPED OR SELECTED OBJECTS AS A PRACTICE DIFFICULTY
+                self.export_selection_as_practice_difficulty();
+                return;
+            }
+            if event.physical_key == PhysicalKey::Code(KeyCode::F4) {
+                // SHIFT + F4: TOGGLE POTATO MODE (NO MSAA, FLAT OBJECTS, FEWER TIMELINE OBJECTS)
+                self.toggle_potato_mode();
+                return;
+            }
+            if event.physical_key == PhysicalKey::Code(KeyCode::F5) {
+                // SHIFT + F5: JUMP TO NEXT SLIDER CURVE THAT DIFFERS BETWEEN STABLE AND LAZER
+                self.jump_to_next_slider_curve_warning();
+                return;
+            }
+            if event.physical_key == PhysicalKey::Code(KeyCode::KeyH) {
+                // SHIFT + H: SHOW THE FIRST-RUN OVERLAY OF THE EDITOR'S REGIONS AGAIN
+                self.show_onboarding();
+                return;
+            }
+            if event.physical_key == PhysicalKey::Code(KeyCode::KeyN) {
+                // SHIFT + N: PREVIEW AND APPLY NEW COMBOS ON DOWNBEATS AND BOOKMARKS
+                self.begin_combo_proposal();
+                return;
+            }
+            if event.physical_key == PhysicalKey::Code(KeyCode::KeyS) {
+                // SHIFT + S: PLAY ALL HITSOUNDS ON NORMAL, SOFT OR DRUM, OR THE MAP'S OWN
+                self.cycle_forced_sample_set();
+                return;
+            }
+            if event.physical_key == PhysicalKey::Code(KeyCode::KeyR) {
+                // SHIFT + R: RESNAP OBJECTS THAT ARE OFF EVERY SNAP TICK
+                self.resnap_unsnapped_objects();
+                return;
+            }
+            if event.physical_key == PhysicalKey::Code(KeyCode::KeyT) {
+                // SHIFT + T: ROTATE / SCALE THE SELECTION BY TYPED DEGREES AND PERCENT
+                self.begin_selection_transform();
+                return;
+            }
+            if event.physical_key == PhysicalKey::Code(KeyCode::KeyY) {
+                // SHIFT + Y: SHIFT RED LINES, SCALE BPMS OR MERGE REDUNDANT GREEN LINES
+                self.begin_timing_tools();
+                return;
+            }
+        }
+
+        if event.state == ElementState::Pressed && self.modifiers.alt_key() {
+            // ALT + 0: DRAW THE PLAYFIELD AT THE MAP'S OWN AR / CS AGAIN
+            if digit_key_value(event.physical_key) == Some(0) {
+                self.reset_difficulty_simulation();
+                return;
+            }
+            // ALT + 1-5: TOOL MODE (SELECT / PLACE CIRCLE / PLACE SLIDER / EDIT PATH / TIMING)
+            if let Some(digit @ 1..=5) = digit_key_value(event.physical_key) {
+                self.select_tool_mode(ToolMode::from_index(digit - 1));
+                return;
+            }
+        }
+
         if event.state == ElementState::Pressed && !event.repeat {
             match event.physical_key {
                 PhysicalKey::Code(KeyCode::Space) => {
                     // SPACE: PLAY / PAUSE TOGGLE
-                    if self.audio.is_playing() {
-                        self.audio.pause();
-                    } else {
-                        self.audio.play();
-                    }
+                    self.toggle_playback();
                 }
                 PhysicalKey::Code(KeyCode::Escape) => {
-                    self.clear_selections();
+                    // ESCAPE: CLOSE THE REPORT, DROP AN UNFINISHED SLIDER, LEAVE THE TOOL MODE,
+                    // THEN DESELECT
+                    if !self.close_report() && !self.handle_tool_event(ToolEvent::Cancel) {
+                        self.clear_selections();
+                    }
+                }
+                PhysicalKey::Code(KeyCode::Enter) | PhysicalKey::Code(KeyCode::NumpadEnter) => {
+                    // ENTER: PLACE THE SLIDER BEING DRAWN, OR OPEN THE TIMING TOOLS IN TIMING MODE
+                    self.handle_tool_event(ToolEvent::Confirm);
+                }
+                PhysicalKey::Code(KeyCode::F1) => {
+                    // F1: EXPORT HITSOUNDS WITHOUT MUSIC AS A WAV STEM
+                    self.export_hitsound_stem();
+                }
+                PhysicalKey::Code(KeyCode::F2) => {
+                    // F2: CREATE NEW EMPTY DIFFICULTY
+                    self.begin_new_difficulty(false);
+                }
+                PhysicalKey::Code(KeyCode::F3) => {
+                    // F3: DUPLICATE CURRENT DIFFICULTY
+                    self.begin_new_difficulty(true);
+                }
+                PhysicalKey::Code(KeyCode::F4) => {
+                    // F4: CYCLE MSAA SAMPLES
+                    self.cycle_msaa_samples();
+                }
+                PhysicalKey::Code(KeyCode::F5) => {
+                    // F5: JUMP TO NEXT OVERLAP WARNING
+                    self.jump_to_next_overlap_warning();
+                }
+                PhysicalKey::Code(KeyCode::KeyM) => {
+                    // M: JUMP TO NEXT SILENT HITSOUND
+                    self.jump_to_next_silent_hitsound();
+                }
+                PhysicalKey::Code(KeyCode::KeyY) => {
+                    // Y: COPY TIMING FROM ANOTHER DIFFICULTY OR .OSU FILE
+                    self.begin_copy_timing();
+                }
+                PhysicalKey::Code(KeyCode::Semicolon) => {
+                    // ;: EDIT GENERAL SETTINGS (COUNTDOWN, LETTERBOX, STORYBOARD FLAGS)
+                    self.begin_general_settings();
+                }
+                PhysicalKey::Code(KeyCode::Slash) => {
+                    // /: SET CUSTOM SAMPLE FILE OF LEFT SELECTION
+                    self.begin_sample_file();
+                }
+                PhysicalKey::Code(KeyCode::Backslash) => {
+                    // \: GREEN LINE AT THE SELECTED OBJECTS WITH TYPED SV / VOLUME / SAMPLE SET
+                    self.begin_green_line_here();
+                }
+                PhysicalKey::Code(KeyCode::KeyF) => {
+                    // F: TOGGLE FLOW / ANGLE OVERLAY
+                    self.toggle_flow_overlay();
+                }
+                PhysicalKey::Code(KeyCode::Insert) => {
+                    // INSERT: TOGGLE KIAI FOUNTAINS / FLASHES / BACKGROUND PULSE
+                    self.toggle_kiai_effects();
+                }
+                PhysicalKey::Code(KeyCode::Backquote) => {
+                    // `: TOGGLE FRAME-TIME GRAPH
+                    self.toggle_frame_graph();
+                }
+                PhysicalKey::Code(KeyCode::Quote) => {
+                    // ': PREVIEW SKIN OR MAP COMBO COLOURS
+                    self.toggle_beatmap_combo_colors();
+                }
+                PhysicalKey::Code(KeyCode::Tab) => {
+                    // TAB: CYCLE VIEW PRESET (DESIGN / COMPOSE / TIMING)
+                    self.cycle_view_preset();
+                }
+                PhysicalKey::Code(KeyCode::BracketLeft) => {
+                    // [: CYCLE CONVERT PREVIEW (OFF / TAIKO / CATCH / MANIA)
+                    self.cycle_convert_preview();
+                }
+                PhysicalKey::Code(KeyCode::BracketRight) => {
+                    // ]: TOGGLE HUD LAYOUT EDITING (SAVED TO CONFIG.JSON WHEN TOGGLED OFF)
+                    self.toggle_layout_edit_mode();
+                }
+                PhysicalKey::Code(KeyCode::Minus) => {
+                    // -: ZOOM THE TIMELINE OUT TO THE PREVIOUS PRESET
+                    self.step_timeline_zoom_preset(false);
+                }
+                PhysicalKey::Code(KeyCode::Equal) => {
+                    // =: ZOOM THE TIMELINE IN TO THE NEXT PRESET
+                    self.step_timeline_zoom_preset(true);
+                }
+                PhysicalKey::Code(KeyCode::F6) => {
+                    // F6: SAVE LEFT SELECTION AS PATTERN
+                    self.begin_save_pattern();
+                }
+                PhysicalKey::Code(KeyCode::F7) => {
+                    // F7: INSERT PATTERN AT PLAYHEAD
+                    self.begin_insert_pattern();
+                }
+                PhysicalKey::Code(KeyCode::KeyN) => {
+                    // N: INSPECT AND EDIT X/Y/TIME/LENGTH OF SELECTED OBJECT
+                    self.begin_inspect_selection();
+                }
+                PhysicalKey::Code(KeyCode::F8) => {
+                    // F8: IMPORT OBJECT LIST (JSON) INTO CURRENT DIFFICULTY
+                    self.begin_import_object_list();
+                }
+                PhysicalKey::Code(KeyCode::F9) => {
+                    // F9: LOOK FOR BREAK / SPINNER SUGGESTIONS
+                    self.review_gap_suggestions();
+                }
+                PhysicalKey::Code(KeyCode::F10) => {
+                    // F10: ACCEPT CURRENT SUGGESTION
+                    self.accept_gap_suggestion();
+                }
+                PhysicalKey::Code(KeyCode::F12) => {
+                    // F12: DISMISS CURRENT SUGGESTION
+                    self.dismiss_gap_suggestion();
+                }
+                PhysicalKey::Code(KeyCode::Home) => {
+                    // HOME: FIRST OBJECT, AGAIN FOR SONG START
+                    self.seek_to_start_or_first_object();
+                }
+                PhysicalKey::Code(KeyCode::End) => {
+                    // END: LAST OBJECT
+                    self.seek_to_last_object();
                 }
                 PhysicalKey::Code(KeyCode::F11) => {
                     // F11: TOGGLE FULLSCREEN
@@ -113,6 +390,22 @@ impl EditorApp {
                 PhysicalKey::Code(KeyCode::KeyX) => {
                     self.redo(None);
                 }
+                PhysicalKey::Code(KeyCode::KeyB) => {
+                    // B: SPLIT SLIDER AT PLAYHEAD
+                    self.split_slider_at_playhead();
+                }
+                PhysicalKey::Code(KeyCode::KeyC) => {
+                    // C: COPY LEFT SELECTION
+                    self.copy_selection();
+                }
+                PhysicalKey::Code(KeyCode::KeyG) => {
+                    // G: PASTE COPIED RHYTHM ONLY (ONTO LEFT SELECTION OR AS PLACEHOLDERS)
+                    self.paste_rhythm_at_playhead();
+                }
+                PhysicalKey::Code(KeyCode::KeyU) => {
+                    // U: AUDITION HITSOUNDS OF SELECTION / NEXT OBJECT
+                    self.audition_hitsounds();
+                }
                 PhysicalKey::Code(KeyCode::KeyH) => {
                     self.flip_selection_horizontal();
                 }
@@ -156,6 +449,15 @@ impl EditorApp {
             WindowEvent::KeyboardInput { event, .. } => {
                 self.handle_keyboard_input(event);
             }
+            WindowEvent::Ime(ime) => {
+                self.handle_ime_event(ime);
+            }
+            WindowEvent::ModifiersChanged(modifiers) => {
+                self.modifiers = modifiers.state();
+                self.shift_held.store(self.modifiers.shift_key(), Ordering::Release);
+                self.ctrl_held.store(self.modifiers.control_key(), Ordering::Release);
+                self.alt_held.store(self.modifiers.alt_key(), Ordering::Release);
+            }
             WindowEvent::Focused(focused) => {
                 self.mouse_handler.handle_focused_change(*focused);
             }
@@ -195,6 +497,11 @@ impl EditorApp {
                     -1.0
                 };
 
+                // ALT + WHEEL: SIMULATE A HIGHER / LOWER AR, CS WITH SHIFT, FOR THE VIEW ONLY
+                if self.modifiers.alt_key() {
+                    self.nudge_difficulty_simulation(self.modifiers.shift_key(), sign);
+                    return;
+                }
                 if self.selection_left_origin_hovered.load(Ordering::Acquire) {
                     self.rotate_selection_degrees(true, sign, false);
                     return;
@@ -230,13 +537,18 @@ impl EditorApp {
                     let next = (self.current_timeline_zoom() + 0.1 * sign).clamp(0.1, 10.0);
                     self.set_timeline_zoom(next);
                 }
+                // CTRL + WHEEL OVER THE BOTTOM TIMELINE: ZOOM IT AROUND THE CURSOR
+                if self.modifiers.control_key()
+                    && self.progress_bar_hitbox_hovered.load(Ordering::Acquire)
+                {
+                    self.zoom_bottom_timeline(sign > 0.0);
+                    return;
+                }
                 if self.global_interaction_hitbox_hovered.load(Ordering::Acquire)
                     || self.progress_bar_hitbox_hovered.load(Ordering::Acquire)
                 {
-                    let current_ms = self.audio.current_time_ms();
-                    let song_total_ms = self.audio.song_total_ms();
-                    let target_ms = (current_ms - sign * 1000.0).clamp(0.0, song_total_ms);
-                    self.audio.seek_map_time_ms(target_ms);
+                    // One divisor tick per notch, a full measure with shift held.
+                    self.seek_by_wheel(delta, self.modifiers.shift_key());
                 }
             }
             _ => {}
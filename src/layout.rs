@@ -1,3 +1,5 @@
+use crate::config::AppearanceLayoutConfig;
+
 pub struct Rect {
     pub x0: f64,
     pub y0: f64,
@@ -16,6 +18,33 @@ impl Rect{
     }
 }
 
+// The part of the layout that can be rearranged at runtime, saved in config.appearance.layout.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct HudLayout {
+    pub timeline_height_percent: f64,
+    pub timeline_second_box_width_percent: f64,
+    pub timeline_third_box_width_percent: f64,
+    pub stats_box_offset_px: [f64; 2],
+}
+
+impl HudLayout {
+    pub fn from_config(config: &AppearanceLayoutConfig) -> Self {
+        HudLayout {
+            timeline_height_percent: config.timeline_height_percent,
+            timeline_second_box_width_percent: config.timeline_second_box_width_percent,
+            timeline_third_box_width_percent: config.timeline_third_box_width_percent,
+            stats_box_offset_px: config.stats_box_offset_px,
+        }
+    }
+
+    pub fn write_to_config(&self, config: &mut AppearanceLayoutConfig) {
+        config.timeline_height_percent = self.timeline_height_percent;
+        config.timeline_second_box_width_percent = self.timeline_second_box_width_percent;
+        config.timeline_third_box_width_percent = self.timeline_third_box_width_percent;
+        config.stats_box_offset_px = self.stats_box_offset_px;
+    }
+}
+
 pub struct Layout {
     pub top_timeline_rect: Rect,
     pub top_timeline_hitbox_rect: Rect,
@@ -41,12 +70,10 @@ pub fn compute_layout(
     screen_w: f64,
     screen_h: f64,
     playfield_scale: f64,
-    timeline_height_percent: f64,
-    timeline_second_box_width_percent: f64,
-    timeline_third_box_width_percent: f64,
+    hud: &HudLayout,
 ) -> Layout {
     let top_timeline_height_px =
-        (screen_h * timeline_height_percent.clamp(0.0, 1.0)).max(0.0);
+        (screen_h * hud.timeline_height_percent.clamp(0.0, 1.0)).max(0.0);
     let (
         top_timeline_rect,
         top_timeline_hitbox_rect,
@@ -57,13 +84,18 @@ pub fn compute_layout(
     ) = compute_top_timeline_rects(
         screen_w,
         top_timeline_height_px,
-        timeline_second_box_width_percent,
-        timeline_third_box_width_percent,
+        hud.timeline_second_box_width_percent,
+        hud.timeline_third_box_width_percent,
     );
     let timeline_rect = compute_timeline_rect(screen_w, screen_h);
     let timeline_hitbox_rect = compute_timeline_hitbox_rect(screen_w, screen_h);
     let play_pause_button_rect = compute_play_pause_button_rect(screen_h);
-    let stats_box_rect = compute_stats_box_rect(top_timeline_height_px);
+    let stats_box_rect = compute_stats_box_rect(
+        screen_w,
+        screen_h,
+        top_timeline_height_px,
+        hud.stats_box_offset_px,
+    );
     let (audio_volume_box_rect, hitsound_volume_box_rect, playfield_scale_box_rect, timeline_zoom_box_rect) =
         compute_volume_box_rects(&stats_box_rect);
     let (playfield_rect, gameplay_rect) = compute_playfield_and_gameplay_rects(screen_w, screen_h, playfield_scale);
@@ -188,19 +220,44 @@ fn compute_play_pause_button_rect(screen_h: f64) -> Rect {
     Rect { x0, y0, x1: x0 + button_size, y1: y0 + button_size }
 }
 
-fn compute_stats_box_rect(timeline_height_px: f64) -> Rect {
+// First lines of the stats box's playhead and selected object times. Each is mm:ss.mmm,
+// followed by the same time in ms and in measure:beat:tick.
+pub const STATS_BOX_PLAYHEAD_TIME_LINE: usize = 0;
+pub const STATS_BOX_OBJECT_TIME_LINE: usize = 11;
+
+// Line of the stats box at `y_in_box` pixels below its top, spaced like the shader draws them.
+pub fn stats_box_line_at(y_in_box: f64) -> Option<usize> {
+    let padding = 8.0;
+    let text_h = 14.0;
+    let line_step = 18.0;
+    let y = y_in_box - padding + (line_step - text_h) * 0.5;
+    (y >= 0.0).then(|| (y / line_step) as usize)
+}
+
+// The offset moves the box from its spot under the top timeline, the box and the volume boxes
+// next to it are kept on screen.
+fn compute_stats_box_rect(
+    screen_w: f64,
+    screen_h: f64,
+    timeline_height_px: f64,
+    offset_px: [f64; 2],
+) -> Rect {
     let margin = 8.0;
     let text_h = 14.0;
     let adv = (text_h / 7.0) * 6.0;
     let side_padding = 8.0;
     let label_chars = 9.0;
-    let value_chars = 8.0;
+    let value_chars = 9.0;
     let column_gap_chars = 1.0;
     let width = side_padding * 2.0 + adv * (label_chars + column_gap_chars + value_chars) - 2.0;
-    let height = 156.0;
+    let height = 336.0;
 
-    let x0 = margin;
-    let y0 = timeline_height_px.max(0.0) + margin;
+    // Gap and width of the volume boxes on its right.
+    let attached_w = 8.0 + 236.0;
+    let max_x0 = (screen_w - margin - width - attached_w).max(margin);
+    let max_y0 = (screen_h - margin - height).max(margin);
+    let x0 = (margin + offset_px[0]).clamp(margin, max_x0);
+    let y0 = (timeline_height_px.max(0.0) + margin + offset_px[1]).clamp(margin, max_y0);
     let x1 = x0 + width;
     let y1 = y0 + height;
     Rect { x0, y0, x1, y1 }
@@ -236,6 +293,20 @@ fn compute_volume_box_rects(stats_box_rect: &Rect) -> (Rect, Rect, Rect, Rect) {
     (audio, hitsounds, playfield, timeline_zoom)
 }
 
+// The part of the screen gameplay is visible on at the given width:height, the 640x480 area
+// stretched or cropped horizontally around its center.
+pub fn visible_gameplay_rect(gameplay_rect: &Rect, aspect_ratio: [f64; 2]) -> Rect {
+    let center_x = (gameplay_rect.x0 + gameplay_rect.x1) * 0.5;
+    let height = gameplay_rect.y1 - gameplay_rect.y0;
+    let half_width = height * aspect_ratio[0] / aspect_ratio[1].max(1e-6) * 0.5;
+    Rect {
+        x0: center_x - half_width,
+        y0: gameplay_rect.y0,
+        x1: center_x + half_width,
+        y1: gameplay_rect.y1,
+    }
+}
+
 fn compute_left_right_hitbox_rects(screen_w: f64, screen_h: f64) -> (Rect, Rect) {
     let width = screen_w;
     let height = screen_h;
@@ -273,4 +344,40 @@ fn compute_playfield_and_gameplay_rects(
     };
 
     return (playfield_rect, osu_rect);
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_harness::test_config;
+
+    #[test]
+    fn stats_box_offset_moves_its_panels_and_stays_on_screen() {
+        let mut hud = HudLayout::from_config(&test_config().appearance.layout);
+        let home = compute_layout(1280.0, 720.0, 1.0, &hud).stats_box_rect;
+
+        hud.stats_box_offset_px = [100.0, 50.0];
+        let moved = compute_layout(1280.0, 720.0, 1.0, &hud);
+        assert_eq!(moved.stats_box_rect.x0, home.x0 + 100.0);
+        assert_eq!(moved.stats_box_rect.y0, home.y0 + 50.0);
+        assert_eq!(moved.audio_volume_box_rect.y0, moved.stats_box_rect.y0);
+
+        hud.stats_box_offset_px = [5000.0, 5000.0];
+        let clamped = compute_layout(1280.0, 720.0, 1.0, &hud);
+        assert!(clamped.audio_volume_box_rect.x1 <= 1280.0);
+        assert!(clamped.stats_box_rect.y1 <= 720.0);
+    }
+
+    #[test]
+    fn offscreen_warning_area_follows_the_aspect_ratio() {
+        let hud = HudLayout::from_config(&test_config().appearance.layout);
+        let gameplay = compute_layout(1280.0, 720.0, 1.0, &hud).gameplay_rect;
+
+        let four_three = visible_gameplay_rect(&gameplay, [4.0, 3.0]);
+        assert_eq!(four_three.to_f32_array(), gameplay.to_f32_array());
+        let wide = visible_gameplay_rect(&gameplay, [16.0, 9.0]);
+        assert_eq!(wide.to_f32_array(), [0.0, 0.0, 1280.0, 720.0]);
+        let narrow = visible_gameplay_rect(&gameplay, [5.0, 4.0]);
+        assert_eq!(narrow.to_f32_array(), [190.0, 0.0, 1090.0, 720.0]);
+    }
+}
@@ -1,24 +1,41 @@
 #[macro_use]
 mod logging;
 
+mod asset_cache;
 mod audio;
+mod clipboard;
+mod color_presets;
 mod config;
 mod dotosu;
 mod editor;
 mod exports;
+mod file_manager;
 mod files;
 mod geometry;
 mod gpu;
 mod imports;
 mod layout;
 mod map_format;
+mod onboarding;
+mod patterns;
+mod profile;
 mod dialogue_app;
 mod render;
+mod report_panel;
 mod skin;
+mod skin_inspector;
+mod statistics_history;
 mod gui;
 mod hitbox_handlers;
 mod kb_mouse_events;
 mod state;
+#[cfg(test)]
+mod test_harness;
+mod text_input;
+mod time_formats;
+mod time_shift;
+mod timeline_view;
+mod tool_mode;
 mod treap;
 
 use std::collections::HashMap;
@@ -29,20 +46,35 @@ use std::sync::{
 
 use winit::event_loop::EventLoop;
 
-use crate::audio::{AudioEngine, AudioEngineConfig};
+use crate::audio::{AudioEngine, AudioEngineConfig, DuckingConfig, SongTimeMs, WallClockMs};
 use crate::config::Config;
 use crate::editor::open_editor_window;
 use crate::dialogue_app::DialogueApp;
 
-use crate::exports::select_and_export_map;
-use crate::files::{BeatmapsetFolder, get_config, open_beatmapset_folder};
+use crate::exports::{confirm_and_export_all_maps, export_all_maps, select_and_export_map};
+use crate::file_manager::{select_and_clean_map, select_and_manage_files};
+use crate::time_shift::{select_and_replace_audio, select_and_shift_map};
+use crate::files::{
+    BeatmapsetFolder, RecentMap, get_config_for_map, open_beatmapset_folder, read_recent_maps,
+};
 use crate::imports::{select_and_import_map, select_and_import_skin};
+use crate::profile::{prompt_and_export_profile, select_and_import_profile};
 use crate::skin::Skin;
+use crate::skin_inspector::select_and_inspect_skin;
+use crate::statistics_history::select_and_show_statistics;
 use crate::files::scan_folder;
 
 const EDITOR_VERSION: &str = "0.0.1";
 
 fn main() {
+    // `osu-editor --export-all` exports every map in saves/ and exits, non-zero if any failed.
+    if std::env::args().nth(1).as_deref() == Some("--export-all") {
+        if !export_all_maps() {
+            std::process::exit(1);
+        }
+        return;
+    }
+
     let audio = match AudioEngine::new(AudioEngineConfig {
         queue_ms: 60,
         preferred_buffer_frames: 128,
@@ -60,26 +92,78 @@ fn main() {
     let mut event_loop = EventLoop::new().expect("Failed to create winit EventLoop");
     let mut selector = DialogueApp::new();
 
+    // `osu-editor saves/<map> [difficulty]` opens straight into the editor, for file
+    // associations and external tools.
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(map_arg) = args.first() {
+        match map_dir_name_from_arg(map_arg) {
+            Some(map_dir_name) => open_map(
+                &mut event_loop,
+                &mut selector,
+                &audio,
+                &map_dir_name,
+                args.get(1).map(|s| s.as_str()),
+            ),
+            None => println!("No map found at {}, expected saves/<map>.", map_arg),
+        }
+        return;
+    }
+
     loop {
-        let option_strings: Vec<String> = vec![
+        // Recently opened difficulties come first, for reopening them in one step.
+        let recent_maps = read_recent_maps();
+        let mut option_strings: Vec<String> = recent_maps
+            .iter()
+            .map(|entry| format!("open recent: {} [{}]", entry.map_dir_name, entry.difficulty))
+            .collect();
+        option_strings.extend([
             "import .osz map from imports/".to_string(),
             "import .osk skin from imports/".to_string(),
             "open a map from saves/".to_string(),
             "export a map from saves/".to_string(),
+            "export every map in saves/ to .osz".to_string(),
+            "manage files of a map in saves/".to_string(),
+            "clean up leftover files of a map in saves/".to_string(),
+            "shift objects of a map in saves/".to_string(),
+            "replace the audio of a map in saves/".to_string(),
+            "inspect a skin from skins/".to_string(),
+            "statistics history of a map in saves/".to_string(),
+            "export the editor profile (config and skin) to exports/".to_string(),
+            "import an editor profile from imports/".to_string(),
             "exit".to_string(),
-        ];
+        ]);
 
         let selection = match selector.select(&mut event_loop, "Main menu", &option_strings) {
             Some(idx) => idx,
             None => break,
         };
 
-        match selection {
+        if let Some(entry) = recent_maps.get(selection) {
+            open_map(
+                &mut event_loop,
+                &mut selector,
+                &audio,
+                &entry.map_dir_name,
+                Some(&entry.difficulty),
+            );
+            continue;
+        }
+
+        match selection - recent_maps.len() {
             0 => select_and_import_map(&mut event_loop, &mut selector),
             1 => select_and_import_skin(&mut event_loop, &mut selector),
             2 => select_and_open_map(&mut event_loop, &mut selector, &audio),
             3 => select_and_export_map(&mut event_loop, &mut selector),
-            4 => break,
+            4 => confirm_and_export_all_maps(&mut event_loop, &mut selector),
+            5 => select_and_manage_files(&mut event_loop, &mut selector),
+            6 => select_and_clean_map(&mut event_loop, &mut selector),
+            7 => select_and_shift_map(&mut event_loop, &mut selector),
+            8 => select_and_replace_audio(&mut event_loop, &mut selector),
+            9 => select_and_inspect_skin(&mut event_loop, &mut selector, &audio),
+            10 => select_and_show_statistics(&mut event_loop, &mut selector),
+            11 => prompt_and_export_profile(&mut event_loop, &mut selector),
+            12 => select_and_import_profile(&mut event_loop, &mut selector),
+            13 => break,
             _ => unreachable!(),
         }
     }
@@ -92,22 +176,6 @@ fn select_and_open_map(
 ) {
     println!("Opening map...");
 
-    let config = match get_config() {
-        Some(cfg) => cfg,
-        None => {
-            println!("Failed to load config.json, using default config.");
-            return;
-        }
-    };
-
-    let skin = match Skin::load_from_path(&Path::new("skins/").join(&config.appearance.general.skin), &Path::new("skins/default")) {
-        Some(skin) => skin,
-        None => {
-            println!("Failed to load skin.");
-            return;
-        }
-    };
-
     // --- Step 1: scan saves/ ---
     let saves_path = Path::new("saves");
     if !saves_path.exists() {
@@ -131,21 +199,79 @@ fn select_and_open_map(
             return;
         }
     };
-    let map_dir_name = &entries[selection];
+    open_map(event_loop, selector, audio, &entries[selection], None);
+}
+
+// Accepts `saves/<map>`, a full path into saves/ or just the folder name.
+fn map_dir_name_from_arg(arg: &str) -> Option<String> {
+    let name = Path::new(arg.trim_end_matches(['/', '\\']))
+        .file_name()?
+        .to_string_lossy()
+        .to_string();
+    if Path::new("saves").join(&name).is_dir() {
+        Some(name)
+    } else {
+        None
+    }
+}
+
+// Keeps opening maps while the editor is closed with a quick switch to another recent map.
+fn open_map(
+    event_loop: &mut EventLoop<()>,
+    selector: &mut DialogueApp,
+    audio: &Arc<AudioEngine>,
+    map_dir_name: &String,
+    difficulty: Option<&str>,
+) {
+    let mut switch_to = open_map_once(event_loop, selector, audio, map_dir_name, difficulty);
+    while let Some(recent) = switch_to {
+        switch_to = open_map_once(
+            event_loop,
+            selector,
+            audio,
+            &recent.map_dir_name,
+            Some(&recent.difficulty),
+        );
+    }
+}
+
+fn open_map_once(
+    event_loop: &mut EventLoop<()>,
+    selector: &mut DialogueApp,
+    audio: &Arc<AudioEngine>,
+    map_dir_name: &String,
+    difficulty: Option<&str>,
+) -> Option<RecentMap> {
     let beatmapset = match open_beatmapset_folder(map_dir_name) {
         Some(beatmapset) => beatmapset,
         None => {
             println!("Failed to open beatmapset folder for {}", map_dir_name);
-            return;
+            return None;
         }
     };
     println!("Launching: {}", map_dir_name);
 
+    let config = match get_config_for_map(map_dir_name) {
+        Some(cfg) => cfg,
+        None => {
+            println!("Failed to load config.json, using default config.");
+            return None;
+        }
+    };
+
+    let skin = match Skin::load_from_path(&Path::new("skins/").join(&config.appearance.general.skin), &Path::new("skins/default")) {
+        Some(skin) => skin,
+        None => {
+            println!("Failed to load skin.");
+            return None;
+        }
+    };
+
     match load_beatmapset_audio(&beatmapset, &config, map_dir_name, audio) {
         Some(()) => {}
         None => {
             println!("Failed to load beatmap audio.");
-            return;
+            return None;
         }
     }
 
@@ -165,7 +291,7 @@ fn select_and_open_map(
         audio.set_hitsound_sample(bytes.clone(), *index, name.clone(), hint_ext);
     }
 
-    open_editor_window(
+    let switch_to = open_editor_window(
         event_loop,
         selector,
         beatmapset,
@@ -173,9 +299,11 @@ fn select_and_open_map(
         skin,
         Arc::clone(audio),
         hitsound_indices,
+        difficulty,
     );
 
     audio.stop();
+    switch_to
 }
 
 fn load_beatmapset_audio(
@@ -194,8 +322,15 @@ fn load_beatmapset_audio(
         audio.set_volume(config.audio.sound_volume);
         audio.set_hitsound_volume(config.audio.hitsound_volume);
         audio.set_spacial_audio(config.audio.spacial_audio);
-        audio.set_map_time_offset_ms(config.audio.audio_offset_ms);
-        audio.set_hitsounds_offset_ms(config.audio.hitsounds_offset_ms);
+        audio.set_ducking(DuckingConfig {
+            enabled: config.audio.duck_music_on_audition,
+            level: config.audio.duck_level,
+            attack_ms: config.audio.duck_attack_ms,
+            release_ms: config.audio.duck_release_ms,
+        });
+        audio.set_music_start(SongTimeMs(config.audio.audio_offset_ms));
+        audio.set_hitsounds_offset(WallClockMs(config.audio.hitsounds_offset_ms));
+        audio.set_stream_music(config.audio.stream_music);
         audio.load_music(
             bytes.clone(),
             map_dir_name,
@@ -22,6 +22,8 @@ pub fn convert_internal_to_osu_format(beatmapset: Beatmapset, beatmap: Beatmap)
             letterbox_in_breaks: beatmap.general.letterbox_in_breaks,
             epilepsy_warning: beatmap.general.epilepsy_warning,
             widescreen_storyboard: beatmap.general.widescreen_storyboard,
+            samples_match_playback_rate: beatmap.general.samples_match_playback_rate,
+            use_skin_sprites: beatmap.general.use_skin_sprites,
         },
         metadata: MetadataSection {
             beatmapset_id: beatmapset.id,
@@ -46,3 +48,20 @@ pub fn convert_internal_to_osu_format(beatmapset: Beatmapset, beatmap: Beatmap)
 fn get_stack_leniency(preempt_period: f64, stacking_period: f64) -> f64 {
     (stacking_period / preempt_period).clamp(0.0, 1.0)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_harness::{EditHarness, TEST_MAP, object_positions};
+
+    #[test]
+    fn export_round_trips_objects() {
+        let harness = EditHarness::new(TEST_MAP);
+        let exported = EditHarness::new(&harness.export_osu_text());
+        assert_eq!(harness.object_times(), exported.object_times());
+        assert_eq!(
+            object_positions(&harness.map_state()),
+            object_positions(&exported.map_state())
+        );
+    }
+}
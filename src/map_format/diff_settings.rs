@@ -2,7 +2,7 @@ use serde::{Deserialize, Serialize};
 
 use crate::dotosu::sections::{difficulty::DifficultySection, general::GeneralSection};
 
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
 pub struct DiffSettings {
     pub circle_radius: f64,
     pub preempt_period: f64,
@@ -36,6 +36,12 @@ impl DiffSettings {
             slider_tick_rate: self.tick_rate,
         }
     }
+
+    // How far off a hit can be for a 300, 100 and 50, in ms either side of the object.
+    pub fn hit_windows_ms(&self) -> [f64; 3] {
+        let od = self.overall_difficulty;
+        [80.0 - 6.0 * od, 140.0 - 8.0 * od, 200.0 - 10.0 * od]
+    }
 }
 
 pub fn circle_radius_from_cs(cs: f64) -> f64 {
@@ -65,3 +71,19 @@ pub fn preempt_period_from_ar(ar: f64) -> f64 {
         1200.0 - (ar - 5.0) * 150.0
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_harness::{TEST_MAP, parse_test_map};
+
+    #[test]
+    fn hit_windows_narrow_with_overall_difficulty() {
+        let (_, beatmap) = parse_test_map(TEST_MAP);
+        let mut diff_settings = beatmap.diff_settings;
+        diff_settings.overall_difficulty = 8.0;
+        assert_eq!(diff_settings.hit_windows_ms(), [32.0, 76.0, 120.0]);
+        diff_settings.overall_difficulty = 0.0;
+        assert_eq!(diff_settings.hit_windows_ms(), [80.0, 140.0, 200.0]);
+    }
+}
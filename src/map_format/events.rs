@@ -2,6 +2,13 @@ use serde::{Deserialize, Serialize};
 
 use crate::dotosu::sections::events;
 
+// A fade or colour change this large and this fast reads as a flash.
+const FLASH_MAX_DURATION_MS: f64 = 50.0;
+const FLASH_MIN_CHANGE: f64 = 0.8;
+const FLASH_WINDOW_MS: f64 = 1000.0;
+const MAX_FLASHES_PER_WINDOW: usize = 3;
+const MAX_LOOP_ITERATIONS: i32 = 1000;
+
 #[derive(Serialize, Deserialize, Clone)]
 pub struct Events {
     pub events: Vec<Event>,
@@ -35,6 +42,75 @@ impl Events {
         }
         String::new()
     }
+    pub fn without_breaks(&self) -> Self {
+        Events {
+            events: self
+                .events
+                .iter()
+                .filter(|event| !matches!(event, Event::Break(_)))
+                .cloned()
+                .collect(),
+        }
+    }
+    // Every asset path referenced from the events section, still quoted as in the .osu file.
+    pub fn file_paths(&self) -> Vec<&String> {
+        let mut paths = Vec::new();
+        for event in self.events.iter() {
+            match event {
+                Event::Background(bg) => paths.push(&bg.file_path),
+                Event::Video(vd) => paths.push(&vd.file_path),
+                Event::Sprite(sp) => paths.push(&sp.file_path),
+                Event::Animation(an) => paths.push(&an.file_path),
+                Event::Break(_) => {}
+            }
+        }
+        paths
+    }
+    pub fn file_paths_mut(&mut self) -> Vec<&mut String> {
+        let mut paths = Vec::new();
+        for event in self.events.iter_mut() {
+            match event {
+                Event::Background(bg) => paths.push(&mut bg.file_path),
+                Event::Video(vd) => paths.push(&mut vd.file_path),
+                Event::Sprite(sp) => paths.push(&mut sp.file_path),
+                Event::Animation(an) => paths.push(&mut an.file_path),
+                Event::Break(_) => {}
+            }
+        }
+        paths
+    }
+    // Start times of the storyboard flashes, in order.
+    pub fn flash_times(&self) -> Vec<f64> {
+        let mut times = Vec::new();
+        for event in &self.events {
+            let commands = match event {
+                Event::Sprite(sp) => &sp.commands,
+                Event::Animation(an) => &an.commands,
+                _ => continue,
+            };
+            collect_flash_times(commands, 0.0, &mut times);
+        }
+        times.sort_by(|a, b| a.total_cmp(b));
+        times
+    }
+    pub fn set_background_name(&mut self, file_name: &str) {
+        let file_path = format!("\"{}\"", file_name);
+        for event in self.events.iter_mut() {
+            if let Event::Background(bg) = event {
+                bg.file_path = file_path;
+                return;
+            }
+        }
+        self.events.insert(
+            0,
+            Event::Background(BackgroundEvent {
+                file_path,
+                start_time: 0.0,
+                x: 0.0,
+                y: 0.0,
+            }),
+        );
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -147,6 +223,63 @@ impl Event {
     }
 }
 
+fn collect_flash_times(commands: &[Command], offset: f64, times: &mut Vec<f64>) {
+    for command in commands {
+        match command {
+            Command::FadeCommand(fc) => {
+                if fc.end_time - fc.start_time <= FLASH_MAX_DURATION_MS
+                    && (fc.end_opacity - fc.start_opacity).abs() >= FLASH_MIN_CHANGE
+                {
+                    times.push(offset + fc.start_time);
+                }
+            }
+            Command::ColourCommand(cc) => {
+                let brightness = |r: u8, g: u8, b: u8| {
+                    (0.2126 * r as f64 + 0.7152 * g as f64 + 0.0722 * b as f64) / 255.0
+                };
+                let change = brightness(cc.end_r, cc.end_g, cc.end_b)
+                    - brightness(cc.start_r, cc.start_g, cc.start_b);
+                if cc.end_time - cc.start_time <= FLASH_MAX_DURATION_MS
+                    && change.abs() >= FLASH_MIN_CHANGE
+                {
+                    times.push(offset + cc.start_time);
+                }
+            }
+            Command::LoopCommand(lc) => {
+                let loop_duration = lc.iteration_duration();
+                if loop_duration <= 0.0 {
+                    continue;
+                }
+                for i in 0..lc.loop_count.clamp(1, MAX_LOOP_ITERATIONS) {
+                    let iteration_start = offset + lc.start_time + i as f64 * loop_duration;
+                    collect_flash_times(&lc.inner_commands, iteration_start, times);
+                }
+            }
+            // Triggers depend on gameplay, so there is no fixed time to check them at.
+            _ => {}
+        }
+    }
+}
+
+// Start of every stretch where more than three flashes land within a second.
+pub fn rapid_flash_times(flash_times: &[f64]) -> Vec<f64> {
+    let mut result: Vec<f64> = Vec::new();
+    let mut window_end = f64::NEG_INFINITY;
+    for (i, &start) in flash_times.iter().enumerate() {
+        let Some(&last) = flash_times.get(i + MAX_FLASHES_PER_WINDOW) else {
+            break;
+        };
+        if last - start >= FLASH_WINDOW_MS {
+            continue;
+        }
+        if start > window_end {
+            result.push(start);
+        }
+        window_end = last;
+    }
+    result
+}
+
 #[derive(Serialize, Deserialize, Clone)]
 pub struct BackgroundEvent {
     pub file_path: String,
@@ -208,6 +341,23 @@ pub enum Command {
 }
 
 impl Command {
+    pub fn end_time(&self) -> f64 {
+        match self {
+            Command::FadeCommand(c) => c.end_time,
+            Command::MoveCommand(c) => c.end_time,
+            Command::MoveXCommand(c) => c.end_time,
+            Command::MoveYCommand(c) => c.end_time,
+            Command::ScaleCommand(c) => c.end_time,
+            Command::VectorScaleCommand(c) => c.end_time,
+            Command::RotateCommand(c) => c.end_time,
+            Command::ColourCommand(c) => c.end_time,
+            Command::ParameterCommand(c) => c.end_time,
+            Command::LoopCommand(c) => {
+                c.start_time + c.loop_count.max(1) as f64 * c.iteration_duration()
+            }
+            Command::TriggerCommand(c) => c.end_time,
+        }
+    }
     pub fn from_osu_format(command: events::Command) -> Self {
         match command {
             events::Command::FadeCommand(fc) => Command::FadeCommand(FadeCommand {
@@ -516,6 +666,16 @@ pub struct LoopCommand {
     pub inner_commands: Vec<Command>,
 }
 
+impl LoopCommand {
+    // Inner command times are relative to the start of each iteration.
+    pub fn iteration_duration(&self) -> f64 {
+        self.inner_commands
+            .iter()
+            .map(Command::end_time)
+            .fold(0.0, f64::max)
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone)]
 pub struct TriggerCommand {
     pub trigger_type: String,
@@ -523,3 +683,49 @@ pub struct TriggerCommand {
     pub end_time: f64,
     pub inner_commands: Vec<Command>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn loops_end_after_their_last_iteration() {
+        let section = events::parse_events_section(
+            "Sprite,Foreground,Centre,\"white.png\",320,240
+ L,1000,8
+  F,0,0,40,1,0
+  F,0,100,200,0,0",
+        )
+        .unwrap();
+        let events = Events::from_osu_format(&section);
+        let end_times: Vec<f64> = events
+            .events
+            .iter()
+            .filter_map(|event| match event {
+                Event::Sprite(sprite) => sprite.commands.first().map(Command::end_time),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(end_times, vec![1000.0 + 8.0 * 200.0]);
+    }
+
+    #[test]
+    fn looped_storyboard_fades_count_as_rapid_flashing() {
+        let section = events::parse_events_section(
+            "Sprite,Foreground,Centre,\"white.png\",320,240
+ L,1000,8
+  F,0,0,40,1,0
+  F,0,100,200,0,0
+ F,0,5000,5030,0,1",
+        )
+        .unwrap();
+        let events = Events::from_osu_format(&section);
+        let flash_times = events.flash_times();
+        assert_eq!(flash_times.len(), 9);
+        assert_eq!(flash_times[1], 1200.0);
+        assert_eq!(
+            rapid_flash_times(&flash_times),
+            vec![1000.0]
+        );
+    }
+}
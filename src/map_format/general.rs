@@ -10,6 +10,10 @@ pub struct General {
     pub letterbox_in_breaks: bool,
     pub epilepsy_warning: bool,
     pub widescreen_storyboard: bool,
+    #[serde(default)]
+    pub samples_match_playback_rate: bool,
+    #[serde(default)]
+    pub use_skin_sprites: bool,
 }
 
 impl General {
@@ -21,6 +25,8 @@ impl General {
             letterbox_in_breaks: general.letterbox_in_breaks,
             epilepsy_warning: general.epilepsy_warning,
             widescreen_storyboard: general.widescreen_storyboard,
+            samples_match_playback_rate: general.samples_match_playback_rate,
+            use_skin_sprites: general.use_skin_sprites,
         }
     }
 }
@@ -9,5 +9,7 @@ pub mod general;
 pub mod objects;
 pub mod slider_curve;
 pub mod slider_boxing;
+pub mod slider_timing;
+pub mod statistics;
 pub mod timing;
 pub mod stacking;
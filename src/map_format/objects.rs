@@ -2,7 +2,11 @@ use serde::{Deserialize, Serialize};
 
 use crate::{
     geometry::{vec2::Vec2, vec2_transform::Vec2Transform},
-    map_format::{slider_curve::ControlPoints, stacking::apply_stacking, timing::SampleSet},
+    map_format::{
+        slider_curve::{ControlPointSegment, ControlPoints},
+        stacking::apply_stacking,
+        timing::{GreenLine, SampleSet},
+    },
 };
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -50,6 +54,112 @@ pub enum HitObject {
 }
 
 impl HitObject {
+    pub fn sample_filenames_mut(&mut self) -> Vec<&mut String> {
+        let mut filenames = Vec::new();
+        match self {
+            HitObject::Circle(c) => {
+                if let Some(filename) = c.hitsound_info.filename.as_mut() {
+                    filenames.push(filename);
+                }
+            }
+            HitObject::Slider(s) => {
+                for hitsound in s.hitsounds.iter_mut() {
+                    if let Some(filename) = hitsound.filename.as_mut() {
+                        filenames.push(filename);
+                    }
+                }
+                if let Some(filename) = s.sliderbody_hitsound.filename.as_mut() {
+                    filenames.push(filename);
+                }
+            }
+            HitObject::Spinner(sp) => {
+                if !sp.hitsample.filename.is_empty() {
+                    filenames.push(&mut sp.hitsample.filename);
+                }
+            }
+        }
+        filenames
+    }
+
+    pub fn sample_filename(&self) -> Option<&str> {
+        match self {
+            HitObject::Circle(c) => c.hitsound_info.filename.as_deref(),
+            HitObject::Slider(s) => s.sliderbody_hitsound.filename.as_deref(),
+            HitObject::Spinner(sp) => {
+                Some(sp.hitsample.filename.as_str()).filter(|f| !f.is_empty())
+            }
+        }
+    }
+
+    // `None` goes back to the samplesets.
+    pub fn set_sample_filename(&mut self, filename: Option<String>) {
+        match self {
+            HitObject::Circle(c) => c.hitsound_info.filename = filename,
+            HitObject::Slider(s) => {
+                for hitsound in s.hitsounds.iter_mut() {
+                    hitsound.filename = filename.clone();
+                }
+                s.sliderbody_hitsound.filename = filename;
+            }
+            HitObject::Spinner(sp) => sp.hitsample.filename = filename.unwrap_or_default(),
+        }
+    }
+
+    // Spinners always start a new combo and keep theirs.
+    pub fn set_new_combo(&mut self, new_combo: bool) {
+        match self {
+            HitObject::Circle(c) => c.combo_info.new_combo = new_combo,
+            HitObject::Slider(s) => s.combo_info.new_combo = new_combo,
+            HitObject::Spinner(_) => {}
+        }
+    }
+
+    // Objects store the samplesets, volume and velocity they inherited when loaded, so a new
+    // green line over [from, until) is passed on to whatever still matches `previous`.
+    pub fn inherit_green_line(
+        &mut self,
+        from: f64,
+        until: f64,
+        previous: &GreenLine,
+        green_line: &GreenLine,
+    ) {
+        let covered = |time: f64| time >= from && time < until;
+        let inherit = |hitsound_info: &mut HitsoundInfo| {
+            if (hitsound_info.volume - previous.volume).abs() < 1e-9 {
+                hitsound_info.volume = green_line.volume;
+            }
+            if hitsound_info.hit_sampleset == previous.sample_set {
+                hitsound_info.hit_sampleset = green_line.sample_set.clone();
+            }
+            if hitsound_info.additions_sampleset == previous.sample_set {
+                hitsound_info.additions_sampleset = green_line.sample_set.clone();
+            }
+        };
+        match self {
+            HitObject::Circle(c) => {
+                if covered(c.time) {
+                    inherit(&mut c.hitsound_info);
+                }
+            }
+            HitObject::Slider(s) => {
+                let slide_duration = s.slide_duration();
+                for (i, hitsound) in s.hitsounds.iter_mut().enumerate() {
+                    if covered(s.time + slide_duration * i as f64) {
+                        inherit(hitsound);
+                    }
+                }
+                if covered(s.time) {
+                    inherit(&mut s.sliderbody_hitsound);
+                    // Same length at the new velocity, like osu! does.
+                    if previous.sv_multiplier > 0.0 {
+                        s.sv_pixels_per_ms *= green_line.sv_multiplier / previous.sv_multiplier;
+                    }
+                }
+            }
+            HitObject::Spinner(_) => {}
+        }
+    }
+
     pub fn apply_transform(&mut self, transform: Vec2Transform) {
         match self {
             HitObject::Circle(c) => {
@@ -101,6 +211,53 @@ impl HitObject {
         }
     }
 
+    pub fn time(&self) -> f64 {
+        match self {
+            HitObject::Circle(c) => c.time,
+            HitObject::Slider(s) => s.time,
+            HitObject::Spinner(sp) => sp.time,
+        }
+    }
+
+    pub fn position(&self) -> Vec2 {
+        match self {
+            HitObject::Circle(c) => c.pos,
+            HitObject::Slider(s) => s.control_points.start,
+            HitObject::Spinner(sp) => Vec2 { x: sp.x, y: sp.y },
+        }
+    }
+
+    // Moves the object to `time`, stretching its duration by `duration_scale`. A stretched
+    // slider is off the timing's velocity until `fit_slider_velocities` runs over it.
+    pub fn retime(&mut self, time: f64, duration_scale: f64) {
+        match self {
+            HitObject::Circle(c) => c.time = time,
+            HitObject::Slider(s) => {
+                s.time = time;
+                s.sv_pixels_per_ms /= duration_scale;
+            }
+            HitObject::Spinner(sp) => {
+                sp.end_time = time + (sp.end_time - sp.time) * duration_scale;
+                sp.time = time;
+            }
+        }
+    }
+
+    // A circle at `pos` carrying this object's timing, combo and head hitsound.
+    // Spinners stay spinners since their position doesn't matter.
+    pub fn placeholder_at(&self, pos: Vec2) -> Option<HitObject> {
+        match self {
+            HitObject::Circle(c) => Some(HitObject::Circle(Circle { pos, ..c.clone() })),
+            HitObject::Slider(s) => Some(HitObject::Circle(Circle {
+                pos,
+                time: s.time,
+                combo_info: s.combo_info.clone(),
+                hitsound_info: s.hitsounds.first()?.clone(),
+            })),
+            HitObject::Spinner(sp) => Some(HitObject::Spinner(sp.clone())),
+        }
+    }
+
     pub fn combo_info(&self) -> &ComboInfo {
         match self {
             HitObject::Circle(c) => &c.combo_info,
@@ -268,6 +425,15 @@ impl Slider {
         self.length_pixels / self.sv_pixels_per_ms
     }
 
+    // Takes on `sv_pixels_per_ms` with the length changed to keep the slide duration. The .osu
+    // only has the length, so this is what makes a retimed slider export as long as it plays.
+    pub fn set_velocity_keeping_duration(&mut self, sv_pixels_per_ms: f64) {
+        if sv_pixels_per_ms > 0.0 && self.sv_pixels_per_ms > 0.0 {
+            self.length_pixels *= sv_pixels_per_ms / self.sv_pixels_per_ms;
+            self.sv_pixels_per_ms = sv_pixels_per_ms;
+        }
+    }
+
     pub fn from_osu_format(
         osu_slider: &crate::dotosu::sections::objects::Slider,
         timing: &crate::map_format::timing::Timing,
@@ -403,7 +569,8 @@ impl Slider {
                     Some(es) => es.clap,
                     None => false,
                 },
-                filename: None,
+                // A custom sample file replaces the sounds of every edge.
+                filename: sliderbody_hitsound_info.filename.clone(),
             };
             hitsounds.push(hitsound_info);
         }
@@ -493,6 +660,106 @@ impl Slider {
     pub fn end_time(&self) -> f64 {
         return self.time + self.slide_duration() * (self.slides as f64);
     }
+
+    // Scales the path around the head so it is `length_pixels` long at the same velocity.
+    pub fn scale_path_to_length(&mut self, length_pixels: f64) {
+        if self.length_pixels > 1e-9 {
+            let ratio = length_pixels / self.length_pixels;
+            let scale = Vec2Transform::transform_at_origin(
+                Vec2Transform::multiply_by_complex(Vec2 { x: ratio, y: 0.0 }),
+                self.control_points.start,
+            );
+            self.control_points = self.control_points.apply_transform(scale);
+        }
+        self.length_pixels = length_pixels;
+    }
+
+    // Splits a single-slide slider at `time` into (head, tail).
+    // A part shorter than `min_slider_length_px` becomes a circle instead.
+    pub fn split_at_time(
+        &self,
+        time: f64,
+        min_slider_length_px: f64,
+    ) -> Option<(HitObject, HitObject)> {
+        if self.slides != 1 {
+            println!("Splitting sliders with repeats is not supported.");
+            return None;
+        }
+        if time <= self.time || time >= self.end_time() {
+            return None;
+        }
+        let head_length = (time - self.time) * self.sv_pixels_per_ms;
+        let tail_length = self.length_pixels - head_length;
+        if head_length < min_slider_length_px && tail_length < min_slider_length_px {
+            return None;
+        }
+
+        let (ridge, _) = self
+            .control_points
+            .construct_curve_and_snap_points(self.length_pixels);
+        let (split_pos, _, _) = ridge.get_position_and_direction_at_length(head_length);
+        // Past the last control point the path runs straight on to the end of the body.
+        let (head_points, tail_points) = match self.control_points.split_at_length(head_length) {
+            Some((head_points, tail_points)) => (head_points, tail_points),
+            None => (
+                self.control_points.clone(),
+                ControlPoints::new(
+                    split_pos,
+                    vec![ControlPointSegment::Linear(vec![], ridge.end_point())],
+                ),
+            ),
+        };
+
+        // The new edge in the middle only keeps the samplesets and the custom sample file;
+        // additions stay on the original edges.
+        let split_hitsound = HitsoundInfo {
+            play_whistle: false,
+            play_finish: false,
+            play_clap: false,
+            ..self.hitsounds[0].clone()
+        };
+
+        let head = if head_length < min_slider_length_px {
+            HitObject::Circle(Circle {
+                pos: self.control_points.start,
+                time: self.time,
+                combo_info: self.combo_info.clone(),
+                hitsound_info: self.hitsounds[0].clone(),
+            })
+        } else {
+            let mut head = self.clone();
+            head.control_points = head_points;
+            head.length_pixels = head_length;
+            head.hitsounds = vec![self.hitsounds[0].clone(), split_hitsound.clone()];
+            HitObject::Slider(head)
+        };
+
+        let tail_combo_info = ComboInfo {
+            new_combo: false,
+            color_skip: 0,
+        };
+        let tail = if tail_length < min_slider_length_px {
+            HitObject::Circle(Circle {
+                pos: tail_points.start,
+                time,
+                combo_info: tail_combo_info,
+                hitsound_info: self.hitsounds[1].clone(),
+            })
+        } else {
+            HitObject::Slider(Slider {
+                time,
+                slides: 1,
+                length_pixels: tail_length,
+                sv_pixels_per_ms: self.sv_pixels_per_ms,
+                combo_info: tail_combo_info,
+                hitsounds: vec![split_hitsound, self.hitsounds[1].clone()],
+                sliderbody_hitsound: self.sliderbody_hitsound.clone(),
+                control_points: tail_points,
+            })
+        };
+
+        Some((head, tail))
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
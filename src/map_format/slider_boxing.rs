@@ -68,6 +68,21 @@ impl BBox4 {
         }
     }
 
+    // Corners followed by edge midpoints, edge `i` runs from corner `i` to corner `i + 1`.
+    pub fn handle_points(&self) -> [Vec2; 8] {
+        let c = &self.corners;
+        [
+            c[0],
+            c[1],
+            c[2],
+            c[3],
+            (c[0] + c[1]) * 0.5,
+            (c[1] + c[2]) * 0.5,
+            (c[2] + c[3]) * 0.5,
+            (c[3] + c[0]) * 0.5,
+        ]
+    }
+
     pub fn center(&self) -> Vec2 {
         return Vec2 {
             x: (self.corners[0].x + self.corners[1].x + self.corners[2].x + self.corners[3].x)
@@ -198,6 +213,7 @@ pub fn convert_to_boxes(
     }
     let mut result = vec![];
     convert_to_boxes_rec(
+        effective_radius * effective_radius,
         effective_radius * effective_radius,
         SliderBox {
             bbox: bbox.clone(),
@@ -208,8 +224,130 @@ pub fn convert_to_boxes(
     return (result, bbox);
 }
 
+// Number of reduced slider quality levels below full quality.
+pub const SLIDER_QUALITY_REDUCTIONS: usize = 3;
+
+// Drops ridge points that are within `tolerance` of the simplified path (Ramer-Douglas-Peucker).
+pub fn simplify_path(path: &[PointWithProgress], tolerance: f64) -> Vec<PointWithProgress> {
+    if path.len() <= 2 {
+        return path.to_vec();
+    }
+    let mut keep = vec![false; path.len()];
+    keep[0] = true;
+    keep[path.len() - 1] = true;
+    let tolerance_2 = tolerance * tolerance;
+    let mut stack = vec![(0usize, path.len() - 1)];
+    while let Some((first, last)) = stack.pop() {
+        let segment = [path[first].point, path[last].point];
+        let mut farthest = (0.0, first);
+        for i in first + 1..last {
+            let d2 = d2_segment_to_point(segment, path[i].point);
+            if d2 > farthest.0 {
+                farthest = (d2, i);
+            }
+        }
+        if farthest.0 > tolerance_2 {
+            keep[farthest.1] = true;
+            stack.push((first, farthest.1));
+            stack.push((farthest.1, last));
+        }
+    }
+    path.iter()
+        .zip(keep.iter())
+        .filter(|(_, keep)| **keep)
+        .map(|(point, _)| *point)
+        .collect()
+}
+
+// Boxes for a cheaper to render slider: each reduction level doubles the ridge simplification
+// tolerance and quadruples the smallest box area, trading accuracy for fewer segments and boxes.
+pub fn convert_to_boxes_reduced(
+    path: &[PointWithProgress],
+    effective_radius: f64,
+    reduction: usize,
+) -> Vec<SliderBox> {
+    let factor = (1usize << reduction) as f64;
+    let path = simplify_path(path, 0.5 * factor);
+    let empty = BBox {
+        x: [f64::INFINITY, f64::NEG_INFINITY],
+        y: [f64::INFINITY, f64::NEG_INFINITY],
+    };
+    let bbox = path.iter().fold(empty, |bbox, p| BBox {
+        x: [
+            bbox.x[0].min(p.point.x - effective_radius),
+            bbox.x[1].max(p.point.x + effective_radius),
+        ],
+        y: [
+            bbox.y[0].min(p.point.y - effective_radius),
+            bbox.y[1].max(p.point.y + effective_radius),
+        ],
+    });
+    let segments: Vec<[PointWithProgress; 2]> = path.windows(2).map(|w| [w[0], w[1]]).collect();
+    let mut result = vec![];
+    convert_to_boxes_rec(
+        effective_radius * effective_radius,
+        effective_radius * effective_radius * factor * factor,
+        SliderBox { bbox, segments },
+        &mut result,
+    );
+    result
+}
+
+// Picks the slider quality reduction from recent frame times. Quality drops one level after a
+// run of slow frames and comes back one level after a longer run of fast ones, or all at once
+// when the editor is idle.
+pub struct SliderQualityController {
+    reduction: usize,
+    slow_frames: u32,
+    fast_frames: u32,
+}
+
+impl SliderQualityController {
+    const SLOW_FRAMES_TO_REDUCE: u32 = 10;
+    const FAST_FRAMES_TO_RESTORE: u32 = 120;
+
+    pub fn new() -> Self {
+        SliderQualityController {
+            reduction: 0,
+            slow_frames: 0,
+            fast_frames: 0,
+        }
+    }
+
+    pub fn update(&mut self, frame_time_ms: f64, target_frame_time_ms: f64, idle: bool) -> usize {
+        if idle {
+            self.reduction = 0;
+            self.slow_frames = 0;
+            self.fast_frames = 0;
+            return self.reduction;
+        }
+        if frame_time_ms > target_frame_time_ms {
+            self.slow_frames += 1;
+            self.fast_frames = 0;
+            if self.slow_frames >= Self::SLOW_FRAMES_TO_REDUCE {
+                self.reduction = (self.reduction + 1).min(SLIDER_QUALITY_REDUCTIONS);
+                self.slow_frames = 0;
+            }
+        } else if frame_time_ms < target_frame_time_ms * 0.6 {
+            self.fast_frames += 1;
+            self.slow_frames = 0;
+            if self.fast_frames >= Self::FAST_FRAMES_TO_RESTORE {
+                self.reduction = self.reduction.saturating_sub(1);
+                self.fast_frames = 0;
+            }
+        } else {
+            self.slow_frames = 0;
+            self.fast_frames = 0;
+        }
+        self.reduction
+    }
+}
+
+// Boxes are split until they are no larger than `min_box_area` or only touch a couple of
+// segments. `min_box_area` is `effective_radius_2` at full quality.
 pub fn convert_to_boxes_rec(
     effective_radius_2: f64,
+    min_box_area: f64,
     mut bbox: SliderBox,
     result: &mut Vec<SliderBox>,
 ) {
@@ -244,7 +382,7 @@ pub fn convert_to_boxes_rec(
     let dy = bbox.bbox.y[1] - bbox.bbox.y[0];
     let area = dx * dy;
 
-    if area <= effective_radius_2
+    if area <= min_box_area
         || (best_distance2 <= effective_radius_2 && bbox.segments.len() <= 2)
     {
         result.push(bbox);
@@ -278,6 +416,7 @@ pub fn convert_to_boxes_rec(
     for b in halves.iter() {
         convert_to_boxes_rec(
             effective_radius_2,
+            min_box_area,
             SliderBox {
                 bbox: b.clone(),
                 segments: bbox.segments.clone(),
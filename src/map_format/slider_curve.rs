@@ -1,3 +1,5 @@
+use std::sync::OnceLock;
+
 use serde::{Deserialize, Serialize};
 
 use crate::{
@@ -5,7 +7,9 @@ use crate::{
         vec2::{Vec2, circle_center},
         vec2_transform::Vec2Transform,
     },
-    map_format::slider_boxing::{BBox, SliderBox, convert_to_boxes},
+    map_format::slider_boxing::{
+        BBox, SLIDER_QUALITY_REDUCTIONS, SliderBox, convert_to_boxes, convert_to_boxes_reduced,
+    },
 };
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -106,6 +110,57 @@ impl ControlPointSegment {
         }
     }
 
+    pub fn end(&self) -> Vec2 {
+        match self {
+            ControlPointSegment::Bezier(_, end)
+            | ControlPointSegment::Linear(_, end)
+            | ControlPointSegment::Catmull(_, end) => *end,
+            ControlPointSegment::PerfectCircle(points) => points[1],
+        }
+    }
+
+    // Cuts the segment starting at `start` `length` along its drawn `path` into the segments
+    // before the cut, the cut point and the segments after it. Both parts follow the original
+    // curve: beziers are subdivided and arcs keep their circle. Catmull curves cannot be cut
+    // that way, so they become the straight pieces they are drawn with.
+    fn split_at_length(
+        &self,
+        start: Vec2,
+        path: &[Vec2],
+        length: f64,
+    ) -> (Vec<ControlPointSegment>, Vec2, Vec<ControlPointSegment>) {
+        let (edge, cut) = polyline_point_at(path, length);
+        match self {
+            ControlPointSegment::Linear(points, end) => (
+                vec![ControlPointSegment::Linear(points[..edge].to_vec(), cut)],
+                cut,
+                vec![ControlPointSegment::Linear(points[edge..].to_vec(), *end)],
+            ),
+            ControlPointSegment::Bezier(points, end) => {
+                let control = curve_control_points(start, points, *end);
+                let t = bezier_t_at_length(&control, length);
+                let (head, tail) = split_bezier(&control, t);
+                let cut = tail[0];
+                (
+                    vec![ControlPointSegment::Bezier(head[1..head.len() - 1].to_vec(), cut)],
+                    cut,
+                    vec![ControlPointSegment::Bezier(tail[1..tail.len() - 1].to_vec(), *end)],
+                )
+            }
+            ControlPointSegment::PerfectCircle(points) => {
+                match split_arc([start, points[0], points[1]], length) {
+                    Some((head_middle, cut, tail_middle)) => (
+                        vec![ControlPointSegment::PerfectCircle([head_middle, cut])],
+                        cut,
+                        vec![ControlPointSegment::PerfectCircle([tail_middle, points[1]])],
+                    ),
+                    None => split_polyline(path, edge, cut),
+                }
+            }
+            ControlPointSegment::Catmull(_, _) => split_polyline(path, edge, cut),
+        }
+    }
+
     pub fn move_by_offset(&self, offset: Vec2) -> Self {
         match self {
             ControlPointSegment::Bezier(points, end) => ControlPointSegment::Bezier(
@@ -212,6 +267,8 @@ impl SliderCurveRidge {
             ridge: self.clone(),
             boxes,
             bbox,
+            effective_radius,
+            reduced_boxes: Default::default(),
         };
     }
 }
@@ -221,6 +278,23 @@ pub struct SliderCurveWithBoxes {
     pub ridge: SliderCurveRidge,
     pub boxes: Vec<SliderBox>,
     pub bbox: BBox,
+    #[serde(skip)]
+    effective_radius: f64,
+    // Lower quality boxes, built the first time a reduction level is rendered.
+    #[serde(skip)]
+    reduced_boxes: [OnceLock<Vec<SliderBox>>; SLIDER_QUALITY_REDUCTIONS],
+}
+
+impl SliderCurveWithBoxes {
+    pub fn boxes_for_reduction(&self, reduction: usize) -> &[SliderBox] {
+        if reduction == 0 || self.effective_radius <= 0.0 {
+            return &self.boxes;
+        }
+        let reduction = reduction.min(SLIDER_QUALITY_REDUCTIONS);
+        self.reduced_boxes[reduction - 1].get_or_init(|| {
+            convert_to_boxes_reduced(&self.ridge.ridge, self.effective_radius, reduction)
+        })
+    }
 }
 
 impl ControlPoints {
@@ -231,6 +305,64 @@ impl ControlPoints {
         }
     }
 
+    // The start and every control point after it, in path order.
+    pub fn anchors(&self) -> Vec<Vec2> {
+        let mut anchors = vec![self.start];
+        for segment in &self.slider_segments {
+            match segment {
+                ControlPointSegment::Bezier(points, end)
+                | ControlPointSegment::Linear(points, end)
+                | ControlPointSegment::Catmull(points, end) => {
+                    anchors.extend(points.iter().copied());
+                    anchors.push(*end);
+                }
+                ControlPointSegment::PerfectCircle(points) => {
+                    anchors.extend(points.iter().copied())
+                }
+            }
+        }
+        anchors
+    }
+
+    // Moves the anchor at `index` of `anchors()`. None if there is no such anchor.
+    pub fn with_anchor_moved(&self, index: usize, pos: Vec2) -> Option<Self> {
+        let mut moved = self.clone();
+        if index == 0 {
+            moved.start = pos;
+            return Some(moved);
+        }
+        let mut remaining = index - 1;
+        for segment in moved.slider_segments.iter_mut() {
+            let anchor = match segment {
+                ControlPointSegment::Bezier(points, end)
+                | ControlPointSegment::Linear(points, end)
+                | ControlPointSegment::Catmull(points, end) => {
+                    if remaining < points.len() {
+                        Some(&mut points[remaining])
+                    } else if remaining == points.len() {
+                        Some(end)
+                    } else {
+                        remaining -= points.len() + 1;
+                        None
+                    }
+                }
+                ControlPointSegment::PerfectCircle(points) => {
+                    if remaining < points.len() {
+                        Some(&mut points[remaining])
+                    } else {
+                        remaining -= points.len();
+                        None
+                    }
+                }
+            };
+            if let Some(anchor) = anchor {
+                *anchor = pos;
+                return Some(moved);
+            }
+        }
+        None
+    }
+
     pub fn from_osu_format(osu_slider: &crate::dotosu::sections::objects::Slider) -> Option<Self> {
         match (
             osu_slider.curve_type.as_str(),
@@ -444,6 +576,239 @@ impl ControlPoints {
             snap_points,
         );
     }
+
+    // Splits the path `length_px` along it into the path before and the path after, cutting
+    // the segment the point falls in and keeping the others as they are. None when the cut is
+    // past the last control point, on the straight extension of the path.
+    pub fn split_at_length(&self, length_px: f64) -> Option<(ControlPoints, ControlPoints)> {
+        let mut segment_start = self.start;
+        let mut travelled = 0.0;
+        for (index, segment) in self.slider_segments.iter().enumerate() {
+            let path = segment_path(segment_start, segment);
+            let segment_length: f64 = path.windows(2).map(|pair| (pair[1] - pair[0]).len()).sum();
+            if travelled + segment_length > length_px {
+                let mut head = self.slider_segments[..index].to_vec();
+                let mut tail = Vec::new();
+                let cut = if length_px - travelled < 1e-6 && index > 0 {
+                    tail.push(segment.clone());
+                    segment_start
+                } else {
+                    let (head_part, cut, tail_part) =
+                        segment.split_at_length(segment_start, &path, length_px - travelled);
+                    head.extend(head_part);
+                    tail.extend(tail_part);
+                    cut
+                };
+                tail.extend_from_slice(&self.slider_segments[index + 1..]);
+                return Some((ControlPoints::new(self.start, head), ControlPoints::new(cut, tail)));
+            }
+            travelled += segment_length;
+            segment_start = segment.end();
+        }
+        None
+    }
+
+    // Segments osu!stable and osu!lazer may draw differently, each with how far apart the two
+    // readings get in osu! pixels. Bezier segments with more than `max_bezier_anchors` anchors
+    // are always listed, near-straight perfect circles only past `max_diff_px`.
+    pub fn client_divergences(
+        &self,
+        max_bezier_anchors: usize,
+        max_diff_px: f64,
+    ) -> Vec<CurveDivergence> {
+        let mut divergences = Vec::new();
+        let mut segment_start = self.start;
+        for segment in &self.slider_segments {
+            match segment {
+                ControlPointSegment::Bezier(points, end) => {
+                    let normalized = normalize_segment_inner_points(segment_start, points, *end);
+                    let anchors = normalized.len() + 2;
+                    if anchors > max_bezier_anchors {
+                        let mut control = Vec::with_capacity(anchors);
+                        control.push(segment_start);
+                        control.extend(normalized);
+                        control.push(*end);
+                        divergences.push(CurveDivergence {
+                            reason: format!("{} anchors in one bezier segment", anchors),
+                            diff_px: bezier_single_precision_drift(&control),
+                        });
+                    }
+                    segment_start = *end;
+                }
+                ControlPointSegment::PerfectCircle(points) => {
+                    let arc = [segment_start, points[0], points[1]];
+                    let a = arc[1] - arc[0];
+                    let b = arc[2] - arc[0];
+                    let sine = a.cross(b).abs() / (a.len() * b.len()).max(1e-9);
+                    if sine < NEAR_STRAIGHT_ARC_SINE {
+                        let (arc_points, _) = create_circular_arc_curve(arc);
+                        let diff_px =
+                            max_distance_to_polyline(&arc_points, &create_bezier_curve(&arc));
+                        if diff_px > max_diff_px {
+                            divergences.push(CurveDivergence {
+                                reason: "perfect circle close to a straight line".to_string(),
+                                diff_px,
+                            });
+                        }
+                    }
+                    segment_start = points[1];
+                }
+                ControlPointSegment::Linear(_, end) | ControlPointSegment::Catmull(_, end) => {
+                    segment_start = *end;
+                }
+            }
+        }
+        divergences
+    }
+}
+
+// The points a segment from `start` is drawn through, sampled as for the slider body.
+fn segment_path(start: Vec2, segment: &ControlPointSegment) -> Vec<Vec2> {
+    match segment {
+        ControlPointSegment::Bezier(points, end) => {
+            create_bezier_curve(&curve_control_points(start, points, *end))
+        }
+        ControlPointSegment::Linear(points, end) => {
+            let mut path = vec![start];
+            path.extend_from_slice(points);
+            path.push(*end);
+            path
+        }
+        ControlPointSegment::PerfectCircle(points) => {
+            create_circular_arc_curve([start, points[0], points[1]]).0
+        }
+        ControlPointSegment::Catmull(points, end) => {
+            create_catmull_curve(&curve_control_points(start, points, *end))
+        }
+    }
+}
+
+fn curve_control_points(start: Vec2, points: &[Vec2], end: Vec2) -> Vec<Vec2> {
+    let normalized = normalize_segment_inner_points(start, points, end);
+    let mut control = Vec::with_capacity(normalized.len() + 2);
+    control.push(start);
+    control.extend_from_slice(normalized);
+    control.push(end);
+    control
+}
+
+// The point `length` along `path` and the index of the edge it is on.
+fn polyline_point_at(path: &[Vec2], length: f64) -> (usize, Vec2) {
+    let mut travelled = 0.0;
+    for edge in 0..path.len().saturating_sub(1) {
+        let step = path[edge + 1] - path[edge];
+        let step_length = step.len();
+        if step_length > 0.0 && travelled + step_length >= length {
+            return (edge, path[edge] + step * ((length - travelled) / step_length));
+        }
+        travelled += step_length;
+    }
+    (path.len().saturating_sub(2), *path.last().unwrap())
+}
+
+// `path` cut at `cut` on `edge` as straight segments, one per drawn edge.
+fn split_polyline(
+    path: &[Vec2],
+    edge: usize,
+    cut: Vec2,
+) -> (Vec<ControlPointSegment>, Vec2, Vec<ControlPointSegment>) {
+    let line = |point: &Vec2| ControlPointSegment::Linear(vec![], *point);
+    let mut head: Vec<ControlPointSegment> = path[1..=edge].iter().map(line).collect();
+    head.push(line(&cut));
+    let tail = path[edge + 1..].iter().map(line).collect();
+    (head, cut, tail)
+}
+
+// The curve parameter at `length` along the bezier through `control`.
+fn bezier_t_at_length(control: &[Vec2], length: f64) -> f64 {
+    const SAMPLES: usize = 1024;
+    let mut travelled = 0.0;
+    let mut prev = control[0];
+    for sample in 1..=SAMPLES {
+        let point = sample_bezier(control, sample as f64 / SAMPLES as f64);
+        let step_length = (point - prev).len();
+        if step_length > 0.0 && travelled + step_length >= length {
+            let fraction = (length - travelled) / step_length;
+            return (sample as f64 - 1.0 + fraction) / SAMPLES as f64;
+        }
+        travelled += step_length;
+        prev = point;
+    }
+    1.0
+}
+
+// De Casteljau's subdivision of the bezier through `control` at `t`, as the control points of
+// the part before and the part after.
+fn split_bezier(control: &[Vec2], t: f64) -> (Vec<Vec2>, Vec<Vec2>) {
+    let mut head = Vec::with_capacity(control.len());
+    let mut tail = Vec::with_capacity(control.len());
+    let mut level = control.to_vec();
+    while !level.is_empty() {
+        head.push(level[0]);
+        tail.push(level[level.len() - 1]);
+        level = level
+            .windows(2)
+            .map(|pair| pair[0] + (pair[1] - pair[0]) * t)
+            .collect();
+    }
+    tail.reverse();
+    (head, tail)
+}
+
+// The arc through `arc` cut `length` along it, as the middle point of the part before, the cut
+// and the middle point of the part after. None when the points are too close to a line.
+fn split_arc(arc: [Vec2; 3], length: f64) -> Option<(Vec2, Vec2, Vec2)> {
+    let center = circle_center(arc);
+    let radius = (arc[0] - center).len();
+    if !radius.is_finite() || radius < 1e-6 {
+        return None;
+    }
+    let (start_angle, sweep) = arc_start_and_sweep(arc, center);
+    let cut_angle = start_angle + sweep.signum() * length / radius;
+    let end_angle = start_angle + sweep;
+    let at = |angle: f64| Vec2 {
+        x: center.x + radius * angle.cos(),
+        y: center.y + radius * angle.sin(),
+    };
+    Some((
+        at((start_angle + cut_angle) / 2.0),
+        at(cut_angle),
+        at((cut_angle + end_angle) / 2.0),
+    ))
+}
+
+// The angle of the arc's first point around `center` and the signed angle it turns through.
+fn arc_start_and_sweep(points: [Vec2; 3], center: Vec2) -> (f64, f64) {
+    let v0 = points[0] - center;
+    let v1 = points[1] - center;
+    let v2 = points[2] - center;
+
+    let v0v2 = v2.div_complex(v0);
+    let v0v1 = v1.div_complex(v0);
+
+    let mut v0v1_angle = v0v1.y.atan2(v0v1.x);
+    if v0v1_angle < 0.0 {
+        v0v1_angle += 2.0 * std::f64::consts::PI;
+    }
+    let mut v0v2_angle = v0v2.y.atan2(v0v2.x);
+    if v0v2_angle < 0.0 {
+        v0v2_angle += 2.0 * std::f64::consts::PI;
+    }
+
+    if v0v2_angle < v0v1_angle {
+        v0v2_angle = -(2.0 * std::f64::consts::PI - v0v2_angle);
+    }
+    (v0.y.atan2(v0.x), v0v2_angle)
+}
+
+// The clients give up on a perfect circle through three almost collinear points at slightly
+// different thresholds and draw a bezier instead, which for a middle point outside the other
+// two is a very different shape.
+const NEAR_STRAIGHT_ARC_SINE: f64 = 0.01;
+
+pub struct CurveDivergence {
+    pub reason: String,
+    pub diff_px: f64,
 }
 
 #[derive(Serialize, Deserialize, Clone, Copy, Debug)]
@@ -576,27 +941,7 @@ fn normalize_segment_inner_points<'a>(segment_start: Vec2, mut inner: &'a [Vec2]
 fn create_circular_arc_curve(points: [Vec2; 3]) -> (Vec<Vec2>, Vec2) {
     let center = circle_center(points);
     let radius = (points[0] - center).len();
-
-    let v0 = points[0] - center;
-    let v1 = points[1] - center;
-    let v2 = points[2] - center;
-
-    let v0v2 = v2.div_complex(v0);
-    let v0v1 = v1.div_complex(v0);
-
-    let mut v0v1_angle = v0v1.y.atan2(v0v1.x);
-    if v0v1_angle < 0.0 {
-        v0v1_angle += 2.0 * std::f64::consts::PI;
-    }
-    let mut v0v2_angle = v0v2.y.atan2(v0v2.x);
-    if v0v2_angle < 0.0 {
-        v0v2_angle += 2.0 * std::f64::consts::PI;
-    }
-
-    if v0v2_angle < v0v1_angle {
-        v0v2_angle = -(2.0 * std::f64::consts::PI - v0v2_angle);
-    }
-    let start_angle = v0.y.atan2(v0.x);
+    let (start_angle, v0v2_angle) = arc_start_and_sweep(points, center);
 
     let mut path_points = vec![points[0]];
 
@@ -728,6 +1073,49 @@ fn sample_bezier(points: &[Vec2], t: f64) -> Vec2 {
     return result;
 }
 
+// How far the curve drifts when evaluated in single precision, as both clients do in their own
+// order of operations. High degrees are where that starts to show.
+fn bezier_single_precision_drift(points: &[Vec2]) -> f64 {
+    const SAMPLES: usize = 64;
+    let n = points.len();
+    (0..=SAMPLES)
+        .map(|sample| {
+            let t = sample as f64 / SAMPLES as f64;
+            let exact = sample_bezier(points, t);
+            let (mut x, mut y) = (0.0f32, 0.0f32);
+            for (k, point) in points.iter().enumerate() {
+                let coeff = BINOMIALS[n - 1][k] as f32
+                    * (t as f32).powi(k as i32)
+                    * (1.0 - t as f32).powi((n - 1 - k) as i32);
+                x += point.x as f32 * coeff;
+                y += point.y as f32 * coeff;
+            }
+            let single = Vec2 {
+                x: x as f64,
+                y: y as f64,
+            };
+            exact.distance2(single).sqrt()
+        })
+        .fold(0.0, f64::max)
+}
+
+// The largest distance from a point of `points` to the nearest segment of `polyline`.
+fn max_distance_to_polyline(points: &[Vec2], polyline: &[Vec2]) -> f64 {
+    points
+        .iter()
+        .map(|point| {
+            polyline
+                .windows(2)
+                .map(|pair| {
+                    let edge = pair[1] - pair[0];
+                    let t = ((*point - pair[0]).dot(edge) / edge.len2().max(1e-9)).clamp(0.0, 1.0);
+                    point.distance2(pair[0] + edge * t).sqrt()
+                })
+                .fold(f64::INFINITY, f64::min)
+        })
+        .fold(0.0, f64::max)
+}
+
 // Catmull-Rom spline sampling
 fn sample_catmull(points: &[Vec2], t: f64) -> Vec2 {
     let n = points.len();
@@ -779,3 +1167,98 @@ const fn precompute_binomials<const SIZE: usize>() -> [[f64; SIZE]; SIZE] {
     }
     binomials
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slider_curves_that_differ_between_clients_are_flagged() {
+        let start = Vec2 { x: 0.0, y: 0.0 };
+        let arc = ControlPoints::new(
+            start,
+            vec![ControlPointSegment::PerfectCircle([
+                Vec2 { x: 50.0, y: 50.0 },
+                Vec2 { x: 100.0, y: 0.0 },
+            ])],
+        );
+        assert!(arc.client_divergences(24, 2.0).is_empty());
+
+        // The middle point past the end, nearly in line: a huge arc or a bezier doubling back.
+        let near_straight = ControlPoints::new(
+            start,
+            vec![ControlPointSegment::PerfectCircle([
+                Vec2 { x: 200.0, y: 0.5 },
+                Vec2 { x: 100.0, y: 0.0 },
+            ])],
+        );
+        let divergences = near_straight.client_divergences(24, 2.0);
+        assert_eq!(divergences.len(), 1);
+        assert!(divergences[0].diff_px > 2.0);
+
+        let inner: Vec<Vec2> = (1..30)
+            .map(|i| Vec2 {
+                x: i as f64 * 10.0,
+                y: if i % 2 == 0 { 0.0 } else { 100.0 },
+            })
+            .collect();
+        let high_degree = ControlPoints::new(
+            start,
+            vec![ControlPointSegment::Bezier(inner, Vec2 { x: 300.0, y: 0.0 })],
+        );
+        let divergences = high_degree.client_divergences(24, 2.0);
+        assert_eq!(divergences.len(), 1);
+        assert_eq!(divergences[0].reason, "31 anchors in one bezier segment");
+        assert!(high_degree.client_divergences(31, 2.0).is_empty());
+    }
+
+    #[test]
+    fn split_paths_follow_the_original_curve() {
+        let start = Vec2 { x: 0.0, y: 0.0 };
+        let paths = [
+            ControlPoints::new(
+                start,
+                vec![ControlPointSegment::Bezier(
+                    vec![Vec2 { x: 100.0, y: 100.0 }],
+                    Vec2 { x: 200.0, y: 0.0 },
+                )],
+            ),
+            ControlPoints::new(
+                start,
+                vec![ControlPointSegment::PerfectCircle([
+                    Vec2 { x: 50.0, y: 50.0 },
+                    Vec2 { x: 100.0, y: 0.0 },
+                ])],
+            ),
+            ControlPoints::new(
+                start,
+                vec![
+                    ControlPointSegment::Linear(vec![], Vec2 { x: 50.0, y: 0.0 }),
+                    ControlPointSegment::Bezier(
+                        vec![Vec2 { x: 100.0, y: 50.0 }],
+                        Vec2 { x: 150.0, y: 0.0 },
+                    ),
+                ],
+            ),
+        ];
+        for path in paths {
+            let (ridge, _) = path.construct_curve_and_snap_points(120.0);
+            let (head, tail) = path.split_at_length(60.0).unwrap();
+            assert_eq!(
+                std::mem::discriminant(&tail.slider_segments[0]),
+                std::mem::discriminant(path.slider_segments.last().unwrap())
+            );
+            let (head_ridge, _) = head.construct_curve_and_snap_points(60.0);
+            let (tail_ridge, _) = tail.construct_curve_and_snap_points(60.0);
+            for step in 0..=6 {
+                let along = step as f64 * 10.0;
+                let (expected, _, _) = ridge.get_position_and_direction_at_length(along);
+                let (actual, _, _) = head_ridge.get_position_and_direction_at_length(along);
+                assert!(expected.distance2(actual) < 1.0, "{} vs {}", expected, actual);
+                let (expected, _, _) = ridge.get_position_and_direction_at_length(60.0 + along);
+                let (actual, _, _) = tail_ridge.get_position_and_direction_at_length(along);
+                assert!(expected.distance2(actual) < 1.0, "{} vs {}", expected, actual);
+            }
+        }
+    }
+}
@@ -0,0 +1,270 @@
+use crate::map_format::{
+    beatmap::Beatmap,
+    objects::{HitObject, Slider},
+    timing::{GreenLine, SNAP_DIVISORS, Timing, TimingPoint},
+};
+
+pub enum SliderTimingFix {
+    RecomputeLengths,
+    InsertGreenLines,
+}
+
+// Slider velocity in pixels per ms the way osu! derives it from the timing at `time`.
+fn timing_sv_pixels_per_ms(beatmap: &Beatmap, time: f64) -> Option<f64> {
+    beatmap
+        .timing
+        .slider_velocity_at(time, beatmap.diff_settings.sv_multiplier)
+}
+
+// Gives every slider among `objects` the velocity `timing` has at its head, with the length
+// changed so it lasts as long as before. For objects retimed or stretched into timing they
+// weren't made in, which would otherwise export with a different duration.
+pub fn fit_slider_velocities(objects: &mut [HitObject], timing: &Timing, slider_multiplier: f64) {
+    for object in objects.iter_mut() {
+        let HitObject::Slider(s) = object else {
+            continue;
+        };
+        if let Some(sv) = timing.slider_velocity_at(s.time, slider_multiplier) {
+            s.set_velocity_keeping_duration(sv);
+        }
+    }
+}
+
+// End time the slider was authored with, snapped to the closest tick of the current timing.
+fn snapped_end_time(beatmap: &Beatmap, slider: &Slider) -> Option<f64> {
+    let (red_line, _) = beatmap.timing.get_lines_at_time(slider.time);
+    let red_line = red_line?;
+    let end_time = slider.time + slider.slide_duration() * slider.slides as f64;
+
+    let mut best: Option<(f64, f64)> = None;
+    for divisor in SNAP_DIVISORS {
+        let tick = red_line.beat_length / divisor as f64;
+        let mut snapped = red_line.time + ((end_time - red_line.time) / tick).round() * tick;
+        // Never collapse a slider onto its own head.
+        while snapped <= slider.time + 1.0 {
+            snapped += tick;
+        }
+        let error = (snapped - end_time).abs();
+        if best.is_none_or(|(_, best_error)| error < best_error - 1e-6) {
+            best = Some((snapped, error));
+        }
+    }
+    best.map(|(snapped, _)| snapped)
+}
+
+// Sliders whose stored velocity no longer matches the timing, e.g. after a red line's BPM
+// or offset was changed. Their end times in osu! differ from what the editor shows.
+pub fn find_desynced_sliders(beatmap: &Beatmap) -> Vec<usize> {
+    beatmap
+        .objects
+        .objects
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, object)| match object {
+            HitObject::Slider(s) => {
+                let sv = timing_sv_pixels_per_ms(beatmap, s.time)?;
+                if (s.sv_pixels_per_ms - sv).abs() > sv * 1e-6 {
+                    Some(idx)
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+fn insert_timing_point(beatmap: &mut Beatmap, point: TimingPoint) {
+    let time = point.time();
+    let idx = beatmap
+        .timing
+        .timing_points
+        .partition_point(|tp| tp.time() <= time);
+    beatmap.timing.timing_points.insert(idx, point);
+}
+
+// Green line at `time` that changes nothing but the velocity.
+fn green_line_at(beatmap: &Beatmap, time: f64, sv_multiplier: f64) -> Option<GreenLine> {
+    let (red_line, green_line) = beatmap.timing.get_lines_at_time(time);
+    let red_line = red_line?;
+    Some(match green_line {
+        Some(gl) => GreenLine {
+            time,
+            sv_multiplier,
+            ..gl
+        },
+        None => GreenLine {
+            time,
+            sv_multiplier,
+            sample_set: red_line.sample_set,
+            sample_index: red_line.sample_index,
+            volume: red_line.volume,
+            effects: red_line.effects,
+        },
+    })
+}
+
+fn set_slider_sv_multiplier(beatmap: &mut Beatmap, start: f64, end: f64, sv_multiplier: f64) {
+    let (_, green_line) = beatmap.timing.get_lines_at_time(end);
+    let restore_multiplier = match green_line {
+        Some(gl) => gl.sv_multiplier,
+        None => 1.0,
+    };
+    let has_point_until_end = beatmap
+        .timing
+        .timing_points
+        .iter()
+        .any(|tp| tp.time() > start && tp.time() <= end);
+
+    let existing = beatmap
+        .timing
+        .timing_points
+        .iter_mut()
+        .find(|tp| matches!(tp, TimingPoint::GreenLine(gl) if gl.time == start));
+    match existing {
+        Some(TimingPoint::GreenLine(gl)) => gl.sv_multiplier = sv_multiplier,
+        _ => {
+            if let Some(gl) = green_line_at(beatmap, start, sv_multiplier) {
+                insert_timing_point(beatmap, TimingPoint::GreenLine(gl));
+            }
+        }
+    }
+
+    // Keep the velocity of whatever comes after the slider unchanged.
+    if !has_point_until_end && (restore_multiplier - sv_multiplier).abs() > 1e-9 {
+        if let Some(gl) = green_line_at(beatmap, end, restore_multiplier) {
+            insert_timing_point(beatmap, TimingPoint::GreenLine(gl));
+        }
+    }
+}
+
+// Brings the given sliders back in sync with the timing so that each ends on the tick
+// closest to where it ended before. Returns the number of sliders changed.
+pub fn fix_desynced_sliders(
+    beatmap: &mut Beatmap,
+    indices: &[usize],
+    fix: SliderTimingFix,
+) -> usize {
+    let mut fixed = 0usize;
+    for &idx in indices {
+        let slider = match beatmap.objects.objects.get(idx) {
+            Some(HitObject::Slider(s)) => s.clone(),
+            _ => continue,
+        };
+        let Some(end_time) = snapped_end_time(beatmap, &slider) else {
+            continue;
+        };
+        let slide_duration = (end_time - slider.time) / slider.slides.max(1) as f64;
+
+        match fix {
+            SliderTimingFix::RecomputeLengths => {
+                let Some(sv) = timing_sv_pixels_per_ms(beatmap, slider.time) else {
+                    continue;
+                };
+                if let Some(HitObject::Slider(s)) = beatmap.objects.objects.get_mut(idx) {
+                    s.sv_pixels_per_ms = sv;
+                    s.length_pixels = sv * slide_duration;
+                }
+            }
+            SliderTimingFix::InsertGreenLines => {
+                let (red_line, _) = beatmap.timing.get_lines_at_time(slider.time);
+                let Some(red_line) = red_line else {
+                    continue;
+                };
+                let base_sv =
+                    (beatmap.diff_settings.sv_multiplier * 100.0) / red_line.beat_length;
+                let sv = slider.length_pixels / slide_duration;
+                set_slider_sv_multiplier(beatmap, slider.time, end_time, sv / base_sv);
+                if let Some(HitObject::Slider(s)) = beatmap.objects.objects.get_mut(idx) {
+                    s.sv_pixels_per_ms = sv;
+                }
+            }
+        }
+        fixed += 1;
+    }
+    fixed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_harness::{TEST_MAP, parse_test_map};
+
+    fn slide_durations(beatmap: &Beatmap) -> Vec<f64> {
+        beatmap
+            .objects
+            .objects
+            .iter()
+            .filter_map(|object| match object {
+                HitObject::Slider(s) => Some(s.slide_duration()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn stretched_sliders_keep_their_duration_at_the_timing_velocity() {
+        let (_, mut beatmap) = parse_test_map(TEST_MAP);
+        for object in beatmap.objects.objects.iter_mut() {
+            let time = object.time();
+            object.retime(time, 2.0);
+        }
+        assert!(!find_desynced_sliders(&beatmap).is_empty());
+        let stretched = slide_durations(&beatmap);
+
+        fit_slider_velocities(
+            &mut beatmap.objects.objects,
+            &beatmap.timing,
+            beatmap.diff_settings.sv_multiplier,
+        );
+        assert!(find_desynced_sliders(&beatmap).is_empty());
+        for (fitted, stretched) in slide_durations(&beatmap).iter().zip(stretched.iter()) {
+            assert!((fitted - stretched).abs() < 1e-6);
+        }
+    }
+
+    // The red line sped up to 150 BPM after the slider was made at 120.
+    fn sped_up_test_map() -> Beatmap {
+        let (_, mut beatmap) = parse_test_map(TEST_MAP);
+        for timing_point in beatmap.timing.timing_points.iter_mut() {
+            if let TimingPoint::RedLine(red_line) = timing_point {
+                red_line.beat_length = 400.0;
+            }
+        }
+        beatmap
+    }
+
+    #[test]
+    fn desynced_sliders_are_fixed_by_length_or_by_green_lines() {
+        let mut recomputed = sped_up_test_map();
+        assert_eq!(find_desynced_sliders(&recomputed), vec![2]);
+        let fix = SliderTimingFix::RecomputeLengths;
+        assert_eq!(fix_desynced_sliders(&mut recomputed, &[2], fix), 1);
+        assert!(find_desynced_sliders(&recomputed).is_empty());
+        let HitObject::Slider(slider) = &recomputed.objects.objects[2] else {
+            panic!("object 2 is not a slider");
+        };
+        assert!((slider.length_pixels - 175.0).abs() < 1e-6);
+
+        // The slider keeps its 140px, a 0.8x green line slows it and 1x is restored at its end.
+        let mut green_lined = sped_up_test_map();
+        let fix = SliderTimingFix::InsertGreenLines;
+        assert_eq!(fix_desynced_sliders(&mut green_lined, &[2], fix), 1);
+        assert!(find_desynced_sliders(&green_lined).is_empty());
+        let green_lines: Vec<(f64, f64)> = green_lined
+            .timing
+            .timing_points
+            .iter()
+            .filter_map(|tp| match tp {
+                TimingPoint::GreenLine(gl) => Some((gl.time, gl.sv_multiplier)),
+                _ => None,
+            })
+            .collect();
+        let expected = [(2000.0, 0.8), (2500.0, 1.0), (3000.0, 1.0)];
+        assert_eq!(green_lines.len(), expected.len());
+        for ((time, sv), (expected_time, expected_sv)) in green_lines.iter().zip(expected) {
+            assert_eq!(*time, expected_time);
+            assert!((sv - expected_sv).abs() < 1e-9);
+        }
+    }
+}
@@ -0,0 +1,183 @@
+use serde::{Deserialize, Serialize};
+
+use crate::map_format::{beatmap::Beatmap, events::Event, objects::HitObject};
+
+// Strain model loosely after osu!'s 2019 difficulty calculator. Slider paths and stacking are
+// ignored, so it only tracks how a difficulty changes over time, not its real star rating.
+const STRAIN_SECTION_MS: f64 = 400.0;
+const MIN_STRAIN_TIME_MS: f64 = 37.5;
+const AIM_MULTIPLIER: f64 = 26.25;
+const AIM_DECAY: f64 = 0.15;
+const SPEED_MULTIPLIER: f64 = 1400.0;
+const SPEED_DECAY: f64 = 0.3;
+const NORMALIZED_RADIUS: f64 = 52.0;
+const STAR_SCALING: f64 = 0.0675;
+
+// One difficulty's numbers when it was saved, kept in saves/<map>/statistics.json.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct MapStatistics {
+    pub difficulty: String,
+    pub saved_at: u64,
+    pub circles: usize,
+    pub sliders: usize,
+    pub spinners: usize,
+    pub drain_ms: f64,
+    pub star_estimate: f64,
+}
+
+impl MapStatistics {
+    pub fn of(beatmap: &Beatmap, saved_at: u64) -> Self {
+        let objects = &beatmap.objects.objects;
+        let count = |f: fn(&HitObject) -> bool| objects.iter().filter(|o| f(o)).count();
+        MapStatistics {
+            difficulty: beatmap.version.clone(),
+            saved_at,
+            circles: count(|o| matches!(o, HitObject::Circle(_))),
+            sliders: count(|o| matches!(o, HitObject::Slider(_))),
+            spinners: count(|o| matches!(o, HitObject::Spinner(_))),
+            drain_ms: drain_time_ms(beatmap),
+            star_estimate: estimate_star_rating(beatmap),
+        }
+    }
+
+    pub fn object_count(&self) -> usize {
+        self.circles + self.sliders + self.spinners
+    }
+}
+
+// One sitting with a difficulty open in the editor, kept in saves/<map>/sessions.json.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct EditingSession {
+    pub difficulty: String,
+    pub started_at: u64,
+    // Time with input at most a minute apart, so walking away from the editor doesn't count.
+    pub active_ms: f64,
+    pub placed: usize,
+    pub edited: usize,
+    pub removed: usize,
+}
+
+#[derive(Clone, Default, PartialEq, Debug)]
+pub struct EditingTotals {
+    pub sessions: usize,
+    pub active_ms: f64,
+    pub placed: usize,
+    pub edited: usize,
+    pub removed: usize,
+}
+
+impl EditingTotals {
+    pub fn of<'a>(sessions: impl Iterator<Item = &'a EditingSession>) -> Self {
+        let mut totals = EditingTotals::default();
+        for session in sessions {
+            totals.add(session);
+        }
+        totals
+    }
+
+    pub fn add(&mut self, session: &EditingSession) {
+        self.sessions += 1;
+        self.active_ms += session.active_ms;
+        self.placed += session.placed;
+        self.edited += session.edited;
+        self.removed += session.removed;
+    }
+}
+
+pub fn format_active_time(active_ms: f64) -> String {
+    let minutes = (active_ms / 60_000.0).round() as u64;
+    format!("{}h {:02}m", minutes / 60, minutes % 60)
+}
+
+fn end_time(hit_object: &HitObject) -> f64 {
+    match hit_object {
+        HitObject::Circle(c) => c.time,
+        HitObject::Slider(s) => s.end_time(),
+        HitObject::Spinner(sp) => sp.end_time,
+    }
+}
+
+// First object to the end of the last one, without breaks.
+pub fn drain_time_ms(beatmap: &Beatmap) -> f64 {
+    let objects = &beatmap.objects.objects;
+    let (Some(first), Some(last)) = (objects.first(), objects.last()) else {
+        return 0.0;
+    };
+    let breaks: f64 = beatmap
+        .events
+        .events
+        .iter()
+        .filter_map(|event| match event {
+            Event::Break(b) => Some(b.end_time - b.start_time),
+            _ => None,
+        })
+        .sum();
+    (end_time(last) - first.time() - breaks).max(0.0)
+}
+
+fn strain_peaks(strains: &[(f64, f64)], decay: f64) -> f64 {
+    let mut peaks: Vec<f64> = Vec::new();
+    let mut strain = 0.0;
+    let mut previous_time = None;
+    for (time, value) in strains {
+        if let Some(previous_time) = previous_time {
+            strain *= f64::powf(decay, (time - previous_time) / 1000.0);
+        }
+        strain += value;
+        previous_time = Some(*time);
+        let section = (time / STRAIN_SECTION_MS).floor() as usize;
+        if peaks.len() <= section {
+            peaks.resize(section + 1, 0.0);
+        }
+        peaks[section] = peaks[section].max(strain);
+    }
+    peaks.sort_by(|a, b| b.total_cmp(a));
+    let mut weight = 1.0;
+    let mut difficulty = 0.0;
+    for peak in peaks {
+        difficulty += peak * weight;
+        weight *= 0.9;
+    }
+    difficulty
+}
+
+pub fn estimate_star_rating(beatmap: &Beatmap) -> f64 {
+    let scale = NORMALIZED_RADIUS / beatmap.diff_settings.circle_radius.max(1.0);
+    let objects: Vec<&HitObject> = beatmap
+        .objects
+        .objects
+        .iter()
+        .filter(|o| !matches!(o, HitObject::Spinner(_)))
+        .collect();
+    let mut aim_strains = Vec::new();
+    let mut speed_strains = Vec::new();
+    for pair in objects.windows(2) {
+        let time = pair[1].time().max(0.0);
+        let strain_time = (time - pair[0].time()).max(MIN_STRAIN_TIME_MS);
+        let distance = (pair[1].position() - pair[0].position()).len() * scale;
+        aim_strains.push((time, AIM_MULTIPLIER * distance.powf(0.99) / strain_time));
+        speed_strains.push((time, SPEED_MULTIPLIER / strain_time));
+    }
+    let aim = strain_peaks(&aim_strains, AIM_DECAY).sqrt() * STAR_SCALING;
+    let speed = strain_peaks(&speed_strains, SPEED_DECAY).sqrt() * STAR_SCALING;
+    aim + speed + (aim - speed).abs() / 2.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_harness::{TEST_MAP, parse_test_map};
+
+    #[test]
+    fn statistics_count_objects_and_leave_out_breaks() {
+        let (_, beatmap) = parse_test_map(TEST_MAP);
+        let statistics = MapStatistics::of(&beatmap, 0);
+        assert_eq!(
+            (statistics.circles, statistics.sliders, statistics.spinners),
+            (3, 1, 1)
+        );
+        // 1000ms to 9500ms with the 5000-9000 break taken out.
+        assert!((statistics.drain_ms - 4500.0).abs() < 1e-9);
+        assert!(statistics.star_estimate > 0.0);
+    }
+}
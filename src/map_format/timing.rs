@@ -1,5 +1,9 @@
 use serde::{Deserialize, Serialize};
 
+pub const SNAP_DIVISORS: [u32; 8] = [1, 2, 3, 4, 6, 8, 12, 16];
+// Every divisor of SNAP_DIVISORS lands on a whole tick of this many per beat.
+pub const TICKS_PER_BEAT: u32 = 48;
+
 #[derive(Serialize, Deserialize, Clone)]
 pub struct Timing {
     pub timing_points: Vec<TimingPoint>,
@@ -34,6 +38,54 @@ impl Timing {
         }
     }
 
+    // Red lines of `source`, plus its green lines when `include_inherited`. Otherwise our own
+    // green lines are kept so slider velocities and hitsound volumes stay as they are.
+    pub fn copied_from(&self, source: &Timing, include_inherited: bool) -> Vec<TimingPoint> {
+        let is_green = |tp: &TimingPoint| matches!(tp, TimingPoint::GreenLine(_));
+        let mut timing_points: Vec<TimingPoint> = source
+            .timing_points
+            .iter()
+            .filter(|tp| include_inherited || !is_green(*tp))
+            .cloned()
+            .collect();
+        if !include_inherited {
+            timing_points.extend(self.timing_points.iter().filter(|tp| is_green(*tp)).cloned());
+        }
+        sort_timing_points(&mut timing_points);
+        timing_points
+    }
+
+    // Timing points without the green lines that change nothing about what is in effect.
+    pub fn without_redundant_green_lines(&self) -> Vec<TimingPoint> {
+        let mut in_effect: Option<GreenLine> = None;
+        let mut timing_points = Vec::with_capacity(self.timing_points.len());
+        for timing_point in self.timing_points.iter() {
+            match timing_point {
+                TimingPoint::RedLine(rl) => {
+                    in_effect = Some(GreenLine {
+                        time: rl.time,
+                        sv_multiplier: 1.0,
+                        sample_set: rl.sample_set.clone(),
+                        sample_index: rl.sample_index,
+                        volume: rl.volume,
+                        effects: rl.effects.clone(),
+                    });
+                }
+                TimingPoint::GreenLine(gl) => {
+                    if in_effect
+                        .as_ref()
+                        .is_some_and(|prev| prev.has_same_settings(gl))
+                    {
+                        continue;
+                    }
+                    in_effect = Some(gl.clone());
+                }
+            }
+            timing_points.push(timing_point.clone());
+        }
+        timing_points
+    }
+
     pub fn get_lines_at_time(&self, time: f64) -> (Option<RedLine>, Option<GreenLine>) {
         let mut red_line: Option<RedLine> = None;
         let mut green_line: Option<GreenLine> = None;
@@ -56,6 +108,228 @@ impl Timing {
 
         (red_line, green_line)
     }
+
+    // Slider velocity in pixels per ms the way osu! derives it at `time`, from the red line's
+    // beat length, the green line's multiplier and the difficulty's slider multiplier.
+    pub fn slider_velocity_at(&self, time: f64, slider_multiplier: f64) -> Option<f64> {
+        let (red_line, green_line) = self.get_lines_at_time(time);
+        let sv_multiplier = green_line.map_or(1.0, |gl| gl.sv_multiplier);
+        Some((slider_multiplier * 100.0 * sv_multiplier) / red_line?.beat_length)
+    }
+
+    // Green line in effect at `time`, with a red line standing in as an inherited point at 1x.
+    pub fn inherited_at(&self, time: f64) -> Option<GreenLine> {
+        let (red_line, green_line) = self.get_lines_at_time(time);
+        let red_line = red_line?;
+        Some(match green_line {
+            Some(gl) => gl,
+            None => GreenLine {
+                time: red_line.time,
+                sv_multiplier: 1.0,
+                sample_set: red_line.sample_set,
+                sample_index: red_line.sample_index,
+                volume: red_line.volume,
+                effects: red_line.effects,
+            },
+        })
+    }
+
+    // Timing points with a green line at `time` that takes `settings` and otherwise keeps what
+    // is in effect there. A green line already at `time` is changed instead of doubled.
+    // Returns the points and the new line, or None before the first red line.
+    pub fn with_green_line_at(
+        &self,
+        time: f64,
+        settings: &GreenLineSettings,
+    ) -> Option<(Vec<TimingPoint>, GreenLine)> {
+        let inherited = self.inherited_at(time)?;
+        let green_line = GreenLine {
+            time,
+            sv_multiplier: settings.sv_multiplier.unwrap_or(inherited.sv_multiplier),
+            sample_set: settings.sample_set.clone().unwrap_or(inherited.sample_set),
+            sample_index: inherited.sample_index,
+            volume: settings.volume.unwrap_or(inherited.volume),
+            effects: inherited.effects,
+        };
+        let mut timing_points = self.timing_points.clone();
+        let existing = timing_points
+            .iter()
+            .position(|tp| matches!(tp, TimingPoint::GreenLine(gl) if gl.time == time));
+        match existing {
+            Some(idx) => timing_points[idx] = TimingPoint::GreenLine(green_line.clone()),
+            None => {
+                let idx = timing_points.partition_point(|tp| tp.time() <= time);
+                timing_points.insert(idx, TimingPoint::GreenLine(green_line.clone()));
+            }
+        }
+        Some((timing_points, green_line))
+    }
+
+    // Closest 1/`ticks_per_beat` tick of the red line active at `time`.
+    pub fn nearest_tick(&self, time: f64, ticks_per_beat: f64) -> Option<f64> {
+        let red_line = self.get_lines_at_time(time).0?;
+        let tick = red_line.beat_length / ticks_per_beat;
+        if tick <= 0.0 {
+            return None;
+        }
+        Some(red_line.time + ((time - red_line.time) / tick).round() * tick)
+    }
+
+    // Smallest of SNAP_DIVISORS with a tick of the red line at `time` within a millisecond of
+    // it, None for unsnapped times and times before the first red line.
+    pub fn snap_divisor_at(&self, time: f64) -> Option<u32> {
+        const EPSILON_MS: f64 = 1.0;
+        let red_line = self.get_lines_at_time(time + EPSILON_MS).0?;
+        if red_line.beat_length <= 0.0 {
+            return None;
+        }
+        SNAP_DIVISORS.into_iter().find(|divisor| {
+            let tick = red_line.beat_length / *divisor as f64;
+            let snapped = red_line.time + ((time - red_line.time) / tick).round() * tick;
+            (snapped - time).abs() <= EPSILON_MS
+        })
+    }
+
+    // Closest tick of any of SNAP_DIVISORS of the red line active at `time`.
+    pub fn nearest_snap(&self, time: f64) -> Option<f64> {
+        SNAP_DIVISORS
+            .into_iter()
+            .filter_map(|divisor| self.nearest_tick(time, divisor as f64))
+            .min_by(|a, b| (a - time).abs().total_cmp(&(b - time).abs()))
+    }
+
+    // 1-based measure and beat at `time` and the 1/TICKS_PER_BEAT tick within that beat.
+    // Measures count up from the first red line and every red line starts a new one. None
+    // before the first red line.
+    pub fn measure_beat_tick(&self, time: f64) -> Option<(u32, u32, u32)> {
+        const EPSILON_MS: f64 = 1.0;
+        let red_lines: Vec<&RedLine> = self
+            .timing_points
+            .iter()
+            .filter_map(|tp| match tp {
+                TimingPoint::RedLine(rl) if rl.beat_length > 0.0 => Some(rl),
+                _ => None,
+            })
+            .collect();
+        let current_idx = red_lines.iter().rposition(|rl| rl.time <= time + EPSILON_MS)?;
+
+        let mut measures = 0u32;
+        for pair in red_lines[..=current_idx].windows(2) {
+            let measure_ms = pair[0].beat_length * pair[0].meter.max(1) as f64;
+            let section_ms = pair[1].time - pair[0].time - EPSILON_MS;
+            measures += (section_ms / measure_ms).ceil().max(0.0) as u32;
+        }
+
+        let red_line = red_lines[current_idx];
+        let tick_ms = red_line.beat_length / TICKS_PER_BEAT as f64;
+        let ticks = ((time - red_line.time) / tick_ms).round().max(0.0) as u32;
+        let beats = ticks / TICKS_PER_BEAT;
+        let meter = red_line.meter.max(1) as u32;
+        Some((measures + beats / meter + 1, beats % meter + 1, ticks % TICKS_PER_BEAT))
+    }
+
+    // Latest 1/`ticks_per_beat` tick at or just before `time` of the red line active there,
+    // or of the first red line before it.
+    pub fn grid_line_at_or_before(&self, time: f64, ticks_per_beat: f64) -> Option<f64> {
+        const EPSILON_MS: f64 = 1.0;
+        let red_line = match self.get_lines_at_time(time).0 {
+            Some(red_line) => red_line,
+            None => self.timing_points.iter().find_map(|tp| match tp {
+                TimingPoint::RedLine(rl) => Some(rl.clone()),
+                _ => None,
+            })?,
+        };
+        let tick = red_line.beat_length / ticks_per_beat;
+        if tick <= 0.0 {
+            return None;
+        }
+        Some(red_line.time + ((time - red_line.time + EPSILON_MS) / tick).floor() * tick)
+    }
+
+    // Beats from `start` to `end`, and whether each one starts a measure. The first red line's
+    // beats reach back before it.
+    pub fn beats_between(&self, start: f64, end: f64) -> Vec<(f64, bool)> {
+        let red_lines: Vec<&RedLine> = self
+            .timing_points
+            .iter()
+            .filter_map(|tp| match tp {
+                TimingPoint::RedLine(rl) => Some(rl),
+                _ => None,
+            })
+            .collect();
+        let mut beats = Vec::new();
+        for (i, red_line) in red_lines.iter().enumerate() {
+            if red_line.beat_length <= 0.0 {
+                continue;
+            }
+            let next_time = red_lines.get(i + 1).map(|next| next.time);
+            let from = if i == 0 {
+                start
+            } else {
+                red_line.time.max(start)
+            };
+            let mut k = ((from - red_line.time) / red_line.beat_length).ceil() as i64;
+            loop {
+                let time = red_line.time + k as f64 * red_line.beat_length;
+                if time > end || next_time.is_some_and(|next| time >= next) {
+                    break;
+                }
+                beats.push((time, k.rem_euclid(red_line.meter.max(1) as i64) == 0));
+                k += 1;
+            }
+        }
+        beats
+    }
+
+    // Closest grid line strictly after (or before) `time`, with `ticks_per_beat` lines per
+    // beat of the red line active there. Red line starts are always grid lines.
+    pub fn step_grid(&self, time: f64, ticks_per_beat: f64, forward: bool) -> Option<f64> {
+        const EPSILON_MS: f64 = 1.0;
+        let red_lines: Vec<&RedLine> = self
+            .timing_points
+            .iter()
+            .filter_map(|tp| match tp {
+                TimingPoint::RedLine(rl) => Some(rl),
+                _ => None,
+            })
+            .collect();
+        let first = red_lines.first()?;
+        let current_idx = red_lines
+            .iter()
+            .rposition(|rl| rl.time <= time + if forward { 0.0 } else { -EPSILON_MS })
+            .unwrap_or(0);
+        let red_line = red_lines[current_idx];
+        let tick = red_line.beat_length / ticks_per_beat;
+        if tick <= 0.0 {
+            return None;
+        }
+
+        let ticks = (time - red_line.time) / tick;
+        if forward {
+            let next = red_line.time + ((ticks + EPSILON_MS / tick).floor() + 1.0) * tick;
+            Some(match red_lines.get(current_idx + 1) {
+                Some(next_red_line) if next_red_line.time < next => next_red_line.time,
+                _ => next,
+            })
+        } else {
+            let prev = red_line.time + ((ticks - EPSILON_MS / tick).ceil() - 1.0) * tick;
+            if time <= first.time + EPSILON_MS {
+                Some(prev)
+            } else {
+                Some(prev.max(red_line.time))
+            }
+        }
+    }
+}
+
+// A red line goes before a green line at the same time.
+fn sort_timing_points(timing_points: &mut [TimingPoint]) {
+    let is_green = |tp: &TimingPoint| matches!(tp, TimingPoint::GreenLine(_));
+    timing_points.sort_by(|a, b| {
+        a.time()
+            .total_cmp(&b.time())
+            .then(is_green(a).cmp(&is_green(b)))
+    });
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -160,13 +434,34 @@ pub struct GreenLine {
     pub effects: TimingPointEffect,
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+impl GreenLine {
+    pub fn has_same_settings(&self, other: &GreenLine) -> bool {
+        self.sv_multiplier == other.sv_multiplier
+            && self.sample_set == other.sample_set
+            && self.sample_index == other.sample_index
+            && self.volume == other.volume
+            && self.effects == other.effects
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub enum SampleSet {
     Normal,
     Soft,
     Drum,
 }
 
+impl SampleSet {
+    // Start of the sample file names of the set, as in "soft-hitclap2.wav".
+    pub fn file_prefix(&self) -> &'static str {
+        match self {
+            SampleSet::Normal => "normal",
+            SampleSet::Soft => "soft",
+            SampleSet::Drum => "drum",
+        }
+    }
+}
+
 impl GreenLine {
     pub fn from_osu_format(
         gl: &crate::dotosu::sections::timing::GreenLine,
@@ -204,7 +499,14 @@ impl GreenLine {
     }
 }
 
-#[derive(Serialize, Deserialize, Clone)]
+// What "green line here" sets, `None` keeps the value in effect at the object.
+pub struct GreenLineSettings {
+    pub sv_multiplier: Option<f64>,
+    pub volume: Option<f64>,
+    pub sample_set: Option<SampleSet>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
 pub struct TimingPointEffect {
     pub kiai_mode: bool,
     pub omit_first_barline: bool,
@@ -224,3 +526,103 @@ impl TimingPointEffect {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_harness::{EditHarness, TEST_MAP, parse_test_map};
+
+    #[test]
+    fn play_start_snaps_back_to_the_previous_grid_line() {
+        let (_, beatmap) = parse_test_map(TEST_MAP);
+        let timing = beatmap.timing;
+        assert_eq!(timing.grid_line_at_or_before(1900.0, 4.0), Some(1875.0));
+        assert_eq!(timing.grid_line_at_or_before(1875.5, 4.0), Some(1875.0));
+        assert_eq!(timing.grid_line_at_or_before(2100.0, 0.25), Some(2000.0));
+    }
+
+    #[test]
+    fn snap_divisor_is_the_smallest_matching_tick() {
+        let (_, beatmap) = parse_test_map(TEST_MAP);
+        let timing = beatmap.timing;
+        assert_eq!(timing.snap_divisor_at(1000.0), Some(1));
+        assert_eq!(timing.snap_divisor_at(1250.0), Some(2));
+        assert_eq!(timing.snap_divisor_at(1125.0), Some(4));
+        assert_eq!(timing.snap_divisor_at(1167.0), Some(3));
+        assert_eq!(timing.snap_divisor_at(1031.0), Some(16));
+        assert_eq!(timing.snap_divisor_at(1010.0), None);
+    }
+
+    #[test]
+    fn metronome_beats_mark_the_measure_starts() {
+        let (_, beatmap) = parse_test_map(TEST_MAP);
+        let beats = beatmap.timing.beats_between(-500.0, 2000.0);
+        let downbeats: Vec<f64> = beats
+            .iter()
+            .filter(|(_, downbeat)| *downbeat)
+            .map(|(time, _)| *time)
+            .collect();
+        assert_eq!(beats.len(), 6);
+        assert_eq!(downbeats, vec![0.0, 2000.0]);
+    }
+
+    #[test]
+    fn copied_timing_undoes_in_one_step() {
+        let harness = EditHarness::new(TEST_MAP);
+        let (_, source_map) = parse_test_map(TEST_MAP);
+        let mut source = source_map.timing;
+        for timing_point in source.timing_points.iter_mut() {
+            if let TimingPoint::RedLine(red_line) = timing_point {
+                red_line.time = 20.0;
+                red_line.beat_length = 400.0;
+            }
+        }
+
+        let copied = harness.map_state().timing.copied_from(&source, false);
+        harness.edit(|edit_state| edit_state.replace_timing(copied));
+        let map_state = harness.map_state();
+        let red_lines: Vec<f64> = map_state.red_lines.iter().cloned().collect();
+        assert_eq!(red_lines, vec![20.0]);
+        // Green lines of the current difficulty are kept, so kiai still spans 2000-3000.
+        assert_eq!(map_state.timing.timing_points.len(), 3);
+        let kiai_times: Vec<(f64, f64)> = map_state.kiai_times.iter().cloned().collect();
+        assert_eq!(kiai_times, vec![(2000.0, 3000.0)]);
+        assert!(harness.export_osu_text().contains("20,400,4,"));
+
+        harness.edit(|edit_state| edit_state.undo());
+        let red_lines: Vec<f64> = harness.map_state().red_lines.iter().cloned().collect();
+        assert_eq!(red_lines, vec![0.0]);
+    }
+
+    #[test]
+    fn redundant_green_lines_are_merged() {
+        let (_, mut beatmap) = parse_test_map(TEST_MAP);
+        let mut repeat = beatmap.timing.timing_points[1].clone();
+        if let TimingPoint::GreenLine(green_line) = &mut repeat {
+            green_line.time = 2500.0;
+        }
+        beatmap.timing.timing_points.insert(2, repeat);
+        let merged = beatmap.timing.without_redundant_green_lines();
+        let times: Vec<f64> = merged.iter().map(|tp| tp.time()).collect();
+        assert_eq!(times, vec![0.0, 2000.0, 3000.0]);
+    }
+
+    #[test]
+    fn grid_steps_stop_at_red_lines() {
+        let osu_text = TEST_MAP.replace(
+            "3000,-100,4,2,0,60,0,0",
+            "2100,400,4,2,0,60,1,0\n3000,-100,4,2,0,60,0,0",
+        );
+        let (_, beatmap) = parse_test_map(&osu_text);
+        let step = |time, ticks_per_beat, forward| {
+            beatmap.timing.step_grid(time, ticks_per_beat, forward).unwrap()
+        };
+        assert_eq!(step(1000.0, 4.0, true), 1125.0);
+        assert_eq!(step(1999.5, 1.0, true), 2100.0);
+        assert_eq!(step(2100.0, 2.0, true), 2300.0);
+        assert_eq!(step(2100.0, 1.0, false), 2000.0);
+        assert_eq!(step(2300.0, 1.0, false), 2100.0);
+        // Before the first red line its grid carries on backwards.
+        assert_eq!(step(0.0, 1.0, false), -500.0);
+    }
+}
@@ -0,0 +1,37 @@
+use crate::layout::{Layout, Rect};
+
+pub const MAX_ONBOARDING_REGIONS: usize = 4;
+
+// Shown on the hint line while the first-run overlay is up.
+pub const ONBOARDING_DISMISS_HINT: &str = "press any key to start - shift h shows this again";
+
+// A part of the editor the first-run overlay outlines, labelled with what it is for in up to
+// 32 chars of the HUD font.
+#[derive(Clone)]
+pub struct OnboardingRegion {
+    pub rect: [f32; 4],
+    pub label: &'static str,
+}
+
+// The undo panel is laid out by the editor rather than by `compute_layout`, so its rect is
+// passed in.
+pub fn onboarding_regions(layout: &Layout, undo_panel_rect: &Rect) -> Vec<OnboardingRegion> {
+    vec![
+        OnboardingRegion {
+            rect: layout.playfield_rect.to_f32_array(),
+            label: "playfield - drag to select",
+        },
+        OnboardingRegion {
+            rect: layout.top_timeline_rect.to_f32_array(),
+            label: "timeline - objects near playhead",
+        },
+        OnboardingRegion {
+            rect: layout.timeline_hitbox_rect.to_f32_array(),
+            label: "song - drag to seek",
+        },
+        OnboardingRegion {
+            rect: undo_panel_rect.to_f32_array(),
+            label: "undo history - click a state",
+        },
+    ]
+}
@@ -0,0 +1,202 @@
+use std::{fs, path::Path};
+
+use crate::{
+    dotosu::{
+        helpers::get_section,
+        sections::{
+            difficulty::parse_difficulty_section,
+            objects::{HitObjectsSection, parse_objects_section},
+            timing::{self as osu_timing, TimingSection, parse_timing_section},
+        },
+    },
+    files::{sanitize_name, scan_folder, write_bytes_to_file},
+    geometry::{vec2::Vec2, vec2_transform::Vec2Transform},
+    map_format::{
+        diff_settings::DiffSettings,
+        objects::HitObject,
+        slider_timing::fit_slider_velocities,
+        timing::{SampleSet, Timing, TimingPoint},
+    },
+};
+
+const PATTERNS_DIR: &str = "patterns";
+
+pub fn list_patterns() -> Vec<String> {
+    let entries = scan_folder(Path::new(PATTERNS_DIR), Some(false), Some(&vec![".osu"]));
+    let mut names: Vec<String> = entries
+        .into_iter()
+        .map(|name| name.trim_end_matches(".osu").to_string())
+        .collect();
+    names.sort();
+    names
+}
+
+fn sampleset_to_osu(sample_set: &SampleSet) -> i32 {
+    match sample_set {
+        SampleSet::Normal => 1,
+        SampleSet::Soft => 2,
+        SampleSet::Drum => 3,
+    }
+}
+
+// Stores `objects` as a small .osu fragment with times relative to the first object.
+// A red line carries the beat length so the rhythm can be rescaled to another BPM,
+// and a green line per slider keeps its velocity.
+pub fn save_pattern(
+    name: &str,
+    objects: &[HitObject],
+    timing: &Timing,
+    diff_settings: &DiffSettings,
+) -> bool {
+    let mut objects = objects.to_vec();
+    objects.sort_by(|a, b| a.time().total_cmp(&b.time()));
+    let Some(start_ms) = objects.first().map(|object| object.time()) else {
+        println!("Select some objects to save as a pattern.");
+        return false;
+    };
+    let Some(red_line) = timing.get_lines_at_time(start_ms).0 else {
+        println!("No red line found at {:.0}ms.", start_ms);
+        return false;
+    };
+
+    let mut timing_points = vec![osu_timing::TimingPoint::RedLine(osu_timing::RedLine {
+        time: 0.0,
+        beat_length: red_line.beat_length,
+        meter: red_line.meter,
+        sample_set: 0,
+        sample_index: 0,
+        volume: 100,
+        effects: osu_timing::TimingPointEffect::from_int(0),
+    })];
+    for object in objects.iter_mut() {
+        let time = object.time() - start_ms;
+        object.retime(time, 1.0);
+        if let HitObject::Slider(s) = object {
+            let Some(hitsound) = s.hitsounds.first() else {
+                continue;
+            };
+            timing_points.push(osu_timing::TimingPoint::GreenLine(osu_timing::GreenLine {
+                time,
+                sv_multiplier: s.sv_pixels_per_ms * red_line.beat_length
+                    / (diff_settings.sv_multiplier * 100.0),
+                sample_set: sampleset_to_osu(&hitsound.hit_sampleset),
+                sample_index: hitsound.index,
+                volume: (hitsound.volume * 100.0).round() as i8,
+                effects: osu_timing::TimingPointEffect::from_int(0),
+            }));
+        }
+    }
+
+    let text = format!(
+        "osu file format v14\n\n[Difficulty]\n{}\n[TimingPoints]\n{}\n[HitObjects]\n{}\n",
+        diff_settings.to_osu_format().to_osu_text(),
+        TimingSection { timing_points }.to_osu_text(),
+        HitObjectsSection {
+            objects: objects.iter().map(HitObject::to_osu_format).collect(),
+        }
+        .to_osu_text()
+    );
+    let path = Path::new(PATTERNS_DIR).join(format!("{}.osu", sanitize_name(name)));
+    if let Err(err) = write_bytes_to_file(&path, text.as_bytes()) {
+        println!("Failed to write pattern {}: {}", path.display(), err);
+        return false;
+    }
+    println!("Saved {} object(s) as pattern {}.", objects.len(), name);
+    true
+}
+
+fn object_position(object: &HitObject) -> Option<Vec2> {
+    match object {
+        HitObject::Circle(c) => Some(c.pos),
+        HitObject::Slider(s) => Some(s.control_points.start),
+        HitObject::Spinner(_) => None,
+    }
+}
+
+// Loads a pattern and places its first object at `time_ms`. The rhythm follows the
+// beat length at `time_ms`, positions are scaled and rotated around the pattern's center.
+pub fn load_pattern(
+    name: &str,
+    timing: &Timing,
+    diff_settings: &DiffSettings,
+    time_ms: f64,
+    scale: f64,
+    rotation_degrees: f64,
+) -> Option<Vec<HitObject>> {
+    let path = Path::new(PATTERNS_DIR).join(format!("{}.osu", sanitize_name(name)));
+    let text = match fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(err) => {
+            println!("Failed to read pattern {}: {}", path.display(), err);
+            return None;
+        }
+    };
+    let (Some(difficulty), Some(pattern_timing), Some(objects)) = (
+        get_section(&text, "Difficulty").and_then(parse_difficulty_section),
+        get_section(&text, "TimingPoints").and_then(parse_timing_section),
+        get_section(&text, "HitObjects").and_then(parse_objects_section),
+    ) else {
+        println!("Pattern {} is not a valid .osu fragment.", name);
+        return None;
+    };
+
+    let pattern_timing = Timing {
+        timing_points: pattern_timing
+            .timing_points
+            .iter()
+            .map(|tp| TimingPoint::from_osu_format(tp, SampleSet::Normal))
+            .collect(),
+    };
+    let pattern_diff_settings = DiffSettings {
+        sv_multiplier: difficulty.slider_multiplier,
+        ..diff_settings.clone()
+    };
+    let (Some(pattern_red_line), Some(red_line)) = (
+        pattern_timing.get_lines_at_time(0.0).0,
+        timing.get_lines_at_time(time_ms).0,
+    ) else {
+        println!("No red line found for pattern {}.", name);
+        return None;
+    };
+    let duration_scale = red_line.beat_length / pattern_red_line.beat_length;
+
+    let mut objects = objects
+        .objects
+        .iter()
+        .map(|object| {
+            HitObject::from_osu_format(object, &pattern_timing, &pattern_diff_settings)
+        })
+        .collect::<Option<Vec<_>>>()?;
+    for object in objects.iter_mut() {
+        let time = time_ms + object.time() * duration_scale;
+        object.retime(time, duration_scale);
+    }
+
+    if (scale - 1.0).abs() > 1e-9 || rotation_degrees.abs() > 1e-9 {
+        let positions: Vec<Vec2> = objects.iter().filter_map(object_position).collect();
+        if !positions.is_empty() {
+            let min = positions.iter().fold(positions[0], |acc, p| Vec2 {
+                x: acc.x.min(p.x),
+                y: acc.y.min(p.y),
+            });
+            let max = positions.iter().fold(positions[0], |acc, p| Vec2 {
+                x: acc.x.max(p.x),
+                y: acc.y.max(p.y),
+            });
+            let radians = rotation_degrees.to_radians();
+            let transform = Vec2Transform::transform_at_origin(
+                Vec2Transform::multiply_by_complex(Vec2 {
+                    x: scale * radians.cos(),
+                    y: scale * radians.sin(),
+                }),
+                (min + max) * 0.5,
+            );
+            for object in objects.iter_mut() {
+                object.apply_transform(transform);
+            }
+        }
+    }
+    // The stretched sliders take on the map's velocity where they land, at the same duration.
+    fit_slider_velocities(&mut objects, timing, diff_settings.sv_multiplier);
+    Some(objects)
+}
@@ -0,0 +1,168 @@
+use std::{collections::HashMap, fs, path::Path};
+use winit::event_loop::EventLoop;
+
+use crate::{
+    config::Config,
+    dialogue_app::DialogueApp,
+    files::{
+        backup_config_json, create_zip, extract_zip, is_enclosed_path, read_folder_files,
+        sanitize_name, scan_folder, write_bytes_to_file,
+    },
+};
+
+// A profile is config.json plus the skin it points at. Key bindings are fixed and not part of
+// the config, so these two are everything that makes up an editor setup.
+const PROFILE_SUFFIX: &str = ".oeprofile";
+const PROFILE_CONFIG_NAME: &str = "config.json";
+const PROFILE_SKIN_PREFIX: &str = "skin/";
+
+fn parse_config(bytes: &[u8]) -> Option<Config> {
+    match serde_json::from_slice::<Config>(bytes) {
+        Ok(config) => Some(config),
+        Err(err) => {
+            println!("Failed to parse config.json: {}", err);
+            None
+        }
+    }
+}
+
+pub fn prompt_and_export_profile(event_loop: &mut EventLoop<()>, selector: &mut DialogueApp) {
+    let Some(name) = selector.prompt_text(event_loop, "Export profile", "Profile name") else {
+        println!("Export cancelled.");
+        return;
+    };
+    if name.trim().is_empty() {
+        println!("Export cancelled.");
+        return;
+    }
+    match export_profile(&sanitize_name(&name)) {
+        Some(path) => println!("Exported the profile to {}", path),
+        None => println!("Failed to export the profile."),
+    }
+}
+
+fn export_profile(name: &str) -> Option<String> {
+    let config_bytes = match fs::read("config.json") {
+        Ok(bytes) => bytes,
+        Err(err) => {
+            println!("Failed to read config.json: {}", err);
+            return None;
+        }
+    };
+    let config = parse_config(&config_bytes)?;
+
+    let mut files: HashMap<String, Vec<u8>> = HashMap::new();
+    files.insert(PROFILE_CONFIG_NAME.to_string(), config_bytes);
+    let skin_path = Path::new("skins/").join(&config.appearance.general.skin);
+    for (rel, bytes) in read_folder_files(&skin_path) {
+        files.insert(format!("{}{}", PROFILE_SKIN_PREFIX, rel), bytes);
+    }
+
+    let zip_bytes = create_zip(files)?;
+    let profile_path = Path::new("exports/").join(format!("{}{}", name, PROFILE_SUFFIX));
+    if let Err(err) = write_bytes_to_file(&profile_path, &zip_bytes) {
+        println!("Failed to write {}: {}", profile_path.display(), err);
+        return None;
+    }
+    Some(profile_path.display().to_string())
+}
+
+pub fn select_and_import_profile(event_loop: &mut EventLoop<()>, selector: &mut DialogueApp) {
+    println!("Importing profile...");
+    let imports_path = Path::new("imports");
+    let entries = scan_folder(imports_path, Some(false), Some(&vec![PROFILE_SUFFIX]));
+    if entries.is_empty() {
+        println!("No {} profiles found in imports/", PROFILE_SUFFIX);
+        return;
+    }
+
+    let selection = match selector.select(event_loop, "Import profile", &entries) {
+        Some(idx) => idx,
+        None => {
+            println!("Import cancelled.");
+            return;
+        }
+    };
+    let selected_profile = &entries[selection];
+    let profile_bytes = match fs::read(imports_path.join(selected_profile)) {
+        Ok(bytes) => bytes,
+        Err(err) => {
+            println!("Failed to read file {}: {}", selected_profile, err);
+            return;
+        }
+    };
+    let Some(extracted) = extract_zip(profile_bytes) else {
+        println!("Failed to extract profile: {}", selected_profile);
+        return;
+    };
+    import_profile_files(extracted, event_loop, selector);
+}
+
+fn import_profile_files(
+    files: Vec<(String, Vec<u8>)>,
+    event_loop: &mut EventLoop<()>,
+    selector: &mut DialogueApp,
+) {
+    let Some(config_bytes) = files
+        .iter()
+        .find(|(name, _)| name == PROFILE_CONFIG_NAME)
+        .map(|(_, bytes)| bytes.clone())
+    else {
+        println!("The profile has no {}.", PROFILE_CONFIG_NAME);
+        return;
+    };
+    let Some(config) = parse_config(&config_bytes) else {
+        return;
+    };
+    let skin_name = config.appearance.general.skin.clone();
+    // Both come from the file being imported, so neither may lead out of skins/<skin>.
+    if !is_enclosed_path(&skin_name) || skin_name.contains(['/', '\\']) {
+        println!("The profile's skin name {:?} isn't a plain folder name.", skin_name);
+        return;
+    }
+    let skin_files: Vec<(&str, &Vec<u8>)> = files
+        .iter()
+        .filter_map(|(name, bytes)| Some((name.strip_prefix(PROFILE_SKIN_PREFIX)?, bytes)))
+        .collect();
+    if let Some((name, _)) = skin_files.iter().find(|(name, _)| !is_enclosed_path(name)) {
+        println!("The profile's skin file {:?} points outside the skin folder.", name);
+        return;
+    }
+
+    let question = "Replace config.json with the profile's? The current one is backed up.";
+    if !selector.confirm(event_loop, question) {
+        println!("Import cancelled.");
+        return;
+    }
+
+    let skin_path = Path::new("skins/").join(&skin_name);
+    let write_skin = !skin_files.is_empty()
+        && (!skin_path.exists()
+            || selector.confirm(
+                event_loop,
+                &format!("Skin directory {} already exists. Overwrite?", skin_name),
+            ));
+    if write_skin {
+        for (name, bytes) in skin_files {
+            let asset_file_path = skin_path.join(name);
+            if let Err(err) = write_bytes_to_file(&asset_file_path, bytes) {
+                println!("Failed to write {}: {}", asset_file_path.display(), err);
+                return;
+            }
+        }
+        println!("Imported the skin to {}", skin_path.display());
+    }
+
+    // The current config decides how many backups to keep, but one is always made since the
+    // whole file is replaced.
+    let backups_to_keep = fs::read("config.json")
+        .ok()
+        .and_then(|bytes| serde_json::from_slice::<Config>(&bytes).ok())
+        .map_or(config.general.config_backups, |current| current.general.config_backups);
+    backup_config_json(backups_to_keep.max(1));
+    if let Err(err) = write_bytes_to_file(Path::new("config.json"), &config_bytes) {
+        println!("Failed to write config.json: {}", err);
+        return;
+    }
+    println!("Imported the profile, using skin {}.", skin_name);
+}
@@ -1,4 +1,5 @@
 use std::{
+    borrow::Cow,
     collections::{HashSet, VecDeque},
     sync::{
         Arc, RwLock,
@@ -11,29 +12,148 @@ use std::{
 use crate::{
     audio::AudioEngine,
     config::Config,
+    dotosu::osr_file::OsrFile,
     geometry::vec2::Vec2,
     gpu::gpu::{GpuRenderer, ObjectInstance},
-    layout::{self, Rect},
-    map_format::slider_boxing::{BBox, BBox4},
-    state::{EditState, Object},
+    gui::TOOLTIP_DELAY,
+    layout::{self, HudLayout, Rect},
+    map_format::{
+        colors::Color,
+        slider_boxing::{BBox, BBox4, SliderQualityController},
+        statistics::EditingTotals,
+    },
+    onboarding::OnboardingRegion,
+    skin::Texture,
+    state::{
+        ConvertMode, ConvertPreviewCache, DifficultySimulation, EditState, Object,
+        ReplayJudgementCache, SimulatedMapStateCache, UnsnappedObjectsCache,
+    },
 };
 
-pub fn is_object_currently_visible(object: &ObjectInstance, time_ms: f64) -> bool {
-    const FADE_OUT_MS: f64 = 250.0;
+// Editor-only visibility window. Objects always reach full opacity at the same moment
+// as in gameplay; the overrides only stretch how long they take to fade in and out.
+#[derive(Clone, Copy)]
+pub struct FadeEnvelope {
+    pub fade_in_ms_override: f64,
+    pub fade_out_ms: f64,
+}
 
-    let appear_ms = if object.is_spinner {
-        object.time
-    } else {
-        object.time - object.preempt
-    };
-    let end_ms = if object.is_slider || object.is_spinner {
-        object.slider_end_time_ms
-    } else {
-        object.time
-    };
-    let disappear_ms = end_ms + FADE_OUT_MS;
+impl FadeEnvelope {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            fade_in_ms_override: config.appearance.general.object_fade_in_ms_override.max(0.0),
+            fade_out_ms: config.appearance.general.object_fade_out_ms.max(0.0),
+        }
+    }
+
+    pub fn fade_in_ms(&self, preempt: f64) -> f64 {
+        if self.fade_in_ms_override > 0.0 {
+            self.fade_in_ms_override
+        } else {
+            preempt * 2.0 / 3.0
+        }
+    }
+
+    pub fn appear_ms(&self, object: &ObjectInstance) -> f64 {
+        if object.is_spinner {
+            object.time
+        } else {
+            object.time - object.preempt / 3.0 - self.fade_in_ms(object.preempt)
+        }
+    }
 
-    time_ms >= appear_ms && time_ms <= disappear_ms
+    pub fn disappear_ms(&self, object: &ObjectInstance) -> f64 {
+        let end_ms = if object.is_slider || object.is_spinner {
+            object.slider_end_time_ms
+        } else {
+            object.time
+        };
+        end_ms + self.fade_out_ms
+    }
+}
+
+impl Default for FadeEnvelope {
+    fn default() -> Self {
+        Self {
+            fade_in_ms_override: 0.0,
+            fade_out_ms: 250.0,
+        }
+    }
+}
+
+pub const VIEW_PRESET_NAMES: [&str; 3] = ["design", "compose", "timing"];
+
+// How playfield objects are drawn. Design is gameplay-accurate, Compose keeps objects fully
+// opaque without approach circles, Timing dims them so the timing marks stand out. The
+// discriminant is what the shaders see in `view_meta.x`.
+#[derive(Clone, Copy, PartialEq)]
+pub enum ViewPreset {
+    Design = 0,
+    Compose = 1,
+    Timing = 2,
+}
+
+impl ViewPreset {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "design" | "" => Some(ViewPreset::Design),
+            "compose" => Some(ViewPreset::Compose),
+            "timing" => Some(ViewPreset::Timing),
+            _ => None,
+        }
+    }
+
+    fn from_index(index: u32) -> Self {
+        match index {
+            1 => ViewPreset::Compose,
+            2 => ViewPreset::Timing,
+            _ => ViewPreset::Design,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        VIEW_PRESET_NAMES[*self as usize]
+    }
+
+    pub fn next(&self) -> Self {
+        Self::from_index((*self as u32 + 1) % VIEW_PRESET_NAMES.len() as u32)
+    }
+}
+
+pub fn is_object_currently_visible(
+    object: &ObjectInstance,
+    time_ms: f64,
+    fade: &FadeEnvelope,
+) -> bool {
+    time_ms >= fade.appear_ms(object) && time_ms <= fade.disappear_ms(object)
+}
+
+// Start times of the first and last of the `count` objects on each side of `time_ms`. Objects
+// starting in between are drawn as ghosts while paused, whatever their fade window.
+pub fn ghost_window_ms<'a>(
+    objects: impl Iterator<Item = &'a Object>,
+    time_ms: f64,
+    count: usize,
+) -> Option<(f64, f64)> {
+    if count == 0 {
+        return None;
+    }
+    let times: Vec<f64> = objects.map(|object| object.hit_object.time()).collect();
+    let next = times.partition_point(|time| *time <= time_ms);
+    let first = times.get(next.saturating_sub(count))?;
+    let last = times.get((next + count).min(times.len()) - 1)?;
+    Some((*first, *last))
+}
+
+// From the start of the `count`th object back from `time_ms` up to `time_ms`. The ones that
+// are already over are kept visible while paused, see `past_object_count`.
+pub fn past_window_ms<'a>(
+    objects: impl Iterator<Item = &'a Object>,
+    time_ms: f64,
+    count: usize,
+) -> Option<(f64, f64)> {
+    let (first, _) = ghost_window_ms(objects, time_ms, count)?;
+    (first <= time_ms).then_some((first, time_ms))
 }
 
 fn playfield_to_screen(pos: Vec2, playfield_rect: &layout::Rect) -> Vec2 {
@@ -68,6 +188,7 @@ pub fn select_visible_objects_in_rect<'a>(
     object_instances: impl Iterator<Item = &'a Object>,
     playfield_rect: &layout::Rect,
     time_ms: f64,
+    fade: &FadeEnvelope,
     blocked_objects: &[usize],
     must_take: &[usize],
 ) -> (Vec<usize>, Option<BBox>) {
@@ -85,7 +206,7 @@ pub fn select_visible_objects_in_rect<'a>(
                 Some(instance) => instance,
                 None => panic!("object instance should be available while rendering"),
             };
-            if !must_take_this && !is_object_currently_visible(object_instance, time_ms) {
+            if !must_take_this && !is_object_currently_visible(object_instance, time_ms, fade) {
                 return None;
             }
             let object_screen_pos = playfield_to_screen(object_instance.pos, playfield_rect);
@@ -191,6 +312,8 @@ pub struct RenderShared {
     height: AtomicU32,
     playfield_scale_bits: AtomicU32,
     timeline_zoom_bits: AtomicU32,
+    bottom_timeline_start_bits: AtomicU32,
+    bottom_timeline_end_bits: AtomicU32,
     is_playing: AtomicBool,
     is_loading: AtomicBool,
     overlay_rect_left: AtomicOverlayRect,
@@ -205,6 +328,11 @@ pub struct RenderShared {
     selection_right_origin_dragging: AtomicBool,
     cursor_x: AtomicU32,
     cursor_y: AtomicU32,
+    tooltip: RwLock<Option<(Instant, Cow<'static, str>)>>,
+    hint_line: RwLock<String>,
+    editing_totals: RwLock<EditingTotals>,
+    report_lines: RwLock<Vec<String>>,
+    onboarding_regions: RwLock<Vec<OnboardingRegion>>,
     play_pause_button_hovered: AtomicBool,
     play_pause_button_clicked: AtomicBool,
     undo_button_hovered: AtomicBool,
@@ -215,6 +343,20 @@ pub struct RenderShared {
     current_state_rename_text: RwLock<String>,
     redo_button_hovered_row: AtomicU32,
     redo_button_clicked_row: AtomicU32,
+    pending_background: RwLock<Option<Texture>>,
+    pending_hud_layout: RwLock<Option<HudLayout>>,
+    msaa_samples: AtomicU32,
+    pending_msaa_samples: AtomicU32,
+    supported_msaa_samples: RwLock<Vec<u32>>,
+    flow_overlay_visible: AtomicBool,
+    kiai_effects_visible: AtomicBool,
+    potato_mode: AtomicBool,
+    frame_graph_visible: AtomicBool,
+    ignore_beatmap_combo_colors: AtomicBool,
+    view_preset: AtomicU32,
+    difficulty_simulation: RwLock<DifficultySimulation>,
+    convert_mode: AtomicU32,
+    replay: RwLock<Option<Arc<OsrFile>>>,
     edit_state: Arc<RwLock<EditState>>,
 }
 
@@ -232,6 +374,8 @@ impl RenderShared {
             height: AtomicU32::new(height),
             playfield_scale_bits: AtomicU32::new((playfield_scale.clamp(0.01, 1.0) as f32).to_bits()),
             timeline_zoom_bits: AtomicU32::new((1.0f32).to_bits()),
+            bottom_timeline_start_bits: AtomicU32::new(0.0f32.to_bits()),
+            bottom_timeline_end_bits: AtomicU32::new(0.0f32.to_bits()),
             is_playing: AtomicBool::new(false),
             is_loading: AtomicBool::new(true),
             overlay_rect_left: AtomicOverlayRect::new(),
@@ -246,6 +390,11 @@ impl RenderShared {
             selection_right_origin_dragging: AtomicBool::new(false),
             cursor_x: AtomicU32::new(0.0f32.to_bits()),
             cursor_y: AtomicU32::new(0.0f32.to_bits()),
+            tooltip: RwLock::new(None),
+            hint_line: RwLock::new(String::new()),
+            editing_totals: RwLock::new(EditingTotals::default()),
+            report_lines: RwLock::new(Vec::new()),
+            onboarding_regions: RwLock::new(Vec::new()),
             play_pause_button_hovered: AtomicBool::new(false),
             play_pause_button_clicked: AtomicBool::new(false),
             undo_button_hovered: AtomicBool::new(false),
@@ -256,6 +405,20 @@ impl RenderShared {
             current_state_rename_text: RwLock::new(String::new()),
             redo_button_hovered_row: AtomicU32::new(u32::MAX),
             redo_button_clicked_row: AtomicU32::new(u32::MAX),
+            pending_background: RwLock::new(None),
+            pending_hud_layout: RwLock::new(None),
+            msaa_samples: AtomicU32::new(1),
+            pending_msaa_samples: AtomicU32::new(0),
+            supported_msaa_samples: RwLock::new(vec![1]),
+            flow_overlay_visible: AtomicBool::new(false),
+            kiai_effects_visible: AtomicBool::new(false),
+            potato_mode: AtomicBool::new(false),
+            frame_graph_visible: AtomicBool::new(false),
+            ignore_beatmap_combo_colors: AtomicBool::new(false),
+            view_preset: AtomicU32::new(ViewPreset::Design as u32),
+            difficulty_simulation: RwLock::new(DifficultySimulation::default()),
+            convert_mode: AtomicU32::new(ConvertMode::Off as u32),
+            replay: RwLock::new(None),
             edit_state,
         }
     }
@@ -278,6 +441,20 @@ impl RenderShared {
         f32::from_bits(self.timeline_zoom_bits.load(Ordering::Acquire)) as f64
     }
 
+    pub fn set_bottom_timeline_range(&self, start_ms: f64, end_ms: f64) {
+        self.bottom_timeline_start_bits
+            .store((start_ms as f32).to_bits(), Ordering::Release);
+        self.bottom_timeline_end_bits
+            .store((end_ms as f32).to_bits(), Ordering::Release);
+    }
+
+    pub fn bottom_timeline_range(&self) -> (f64, f64) {
+        (
+            f32::from_bits(self.bottom_timeline_start_bits.load(Ordering::Acquire)) as f64,
+            f32::from_bits(self.bottom_timeline_end_bits.load(Ordering::Acquire)) as f64,
+        )
+    }
+
     pub fn set_overlay_rect_left(&self, rect: Option<[f32; 4]>) {
         self.overlay_rect_left.set(rect);
     }
@@ -385,6 +562,80 @@ impl RenderShared {
         ]
     }
 
+    pub fn set_tooltip(&self, tooltip: Option<(Instant, Cow<'static, str>)>) {
+        if let Ok(mut guard) = self.tooltip.write() {
+            *guard = tooltip;
+        }
+    }
+
+    // Empty until the hitbox has been hovered for the tooltip delay.
+    pub fn visible_tooltip(&self) -> Cow<'static, str> {
+        let Ok(guard) = self.tooltip.read() else {
+            return Cow::Borrowed("");
+        };
+        match guard.as_ref() {
+            Some((hover_started, text)) if hover_started.elapsed() >= TOOLTIP_DELAY => {
+                text.clone()
+            }
+            _ => Cow::Borrowed(""),
+        }
+    }
+
+    // Empty hides the hint line.
+    pub fn set_hint_line(&self, hint_line: String) {
+        if let Ok(mut guard) = self.hint_line.write() {
+            *guard = hint_line;
+        }
+    }
+
+    pub fn hint_line(&self) -> String {
+        self.hint_line
+            .read()
+            .map(|guard| guard.clone())
+            .unwrap_or_default()
+    }
+
+    pub fn set_editing_totals(&self, totals: EditingTotals) {
+        if let Ok(mut guard) = self.editing_totals.write() {
+            *guard = totals;
+        }
+    }
+
+    pub fn editing_totals(&self) -> EditingTotals {
+        self.editing_totals
+            .read()
+            .map(|guard| guard.clone())
+            .unwrap_or_default()
+    }
+
+    // Empty hides the report panel.
+    pub fn set_report_lines(&self, lines: Vec<String>) {
+        if let Ok(mut guard) = self.report_lines.write() {
+            *guard = lines;
+        }
+    }
+
+    pub fn report_lines(&self) -> Vec<String> {
+        self.report_lines
+            .read()
+            .map(|guard| guard.clone())
+            .unwrap_or_default()
+    }
+
+    // Empty hides the first-run overlay.
+    pub fn set_onboarding_regions(&self, regions: Vec<OnboardingRegion>) {
+        if let Ok(mut guard) = self.onboarding_regions.write() {
+            *guard = regions;
+        }
+    }
+
+    pub fn onboarding_regions(&self) -> Vec<OnboardingRegion> {
+        self.onboarding_regions
+            .read()
+            .map(|guard| guard.clone())
+            .unwrap_or_default()
+    }
+
     pub fn play_pause_button_hovered(&self) -> bool {
         self.play_pause_button_hovered.load(Ordering::Acquire)
     }
@@ -481,6 +732,144 @@ impl RenderShared {
             Some(row)
         }
     }
+
+    pub fn set_pending_background(&self, background: Texture) {
+        if let Ok(mut guard) = self.pending_background.write() {
+            *guard = Some(background);
+        }
+    }
+
+    pub fn take_pending_background(&self) -> Option<Texture> {
+        self.pending_background
+            .write()
+            .ok()
+            .and_then(|mut guard| guard.take())
+    }
+
+    pub fn set_pending_hud_layout(&self, hud_layout: HudLayout) {
+        if let Ok(mut guard) = self.pending_hud_layout.write() {
+            *guard = Some(hud_layout);
+        }
+    }
+
+    fn take_pending_hud_layout(&self) -> Option<HudLayout> {
+        self.pending_hud_layout
+            .write()
+            .ok()
+            .and_then(|mut guard| guard.take())
+    }
+
+    pub fn msaa_samples(&self) -> u32 {
+        self.msaa_samples.load(Ordering::Acquire)
+    }
+
+    pub fn supported_msaa_samples(&self) -> Vec<u32> {
+        self.supported_msaa_samples
+            .read()
+            .map(|guard| guard.clone())
+            .unwrap_or_else(|_| vec![1])
+    }
+
+    fn set_msaa_state(&self, samples: u32, supported: &[u32]) {
+        self.msaa_samples.store(samples, Ordering::Release);
+        if let Ok(mut guard) = self.supported_msaa_samples.write() {
+            *guard = supported.to_vec();
+        }
+    }
+
+    pub fn request_msaa_samples(&self, samples: u32) {
+        self.pending_msaa_samples
+            .store(samples.max(1), Ordering::Release);
+    }
+
+    fn take_pending_msaa_samples(&self) -> Option<u32> {
+        match self.pending_msaa_samples.swap(0, Ordering::AcqRel) {
+            0 => None,
+            samples => Some(samples),
+        }
+    }
+
+    pub fn set_flow_overlay_visible(&self, visible: bool) {
+        self.flow_overlay_visible.store(visible, Ordering::Release);
+    }
+
+    pub fn flow_overlay_visible(&self) -> bool {
+        self.flow_overlay_visible.load(Ordering::Acquire)
+    }
+
+    pub fn set_kiai_effects_visible(&self, visible: bool) {
+        self.kiai_effects_visible.store(visible, Ordering::Release);
+    }
+
+    pub fn kiai_effects_visible(&self) -> bool {
+        self.kiai_effects_visible.load(Ordering::Acquire)
+    }
+
+    pub fn set_potato_mode(&self, enabled: bool) {
+        self.potato_mode.store(enabled, Ordering::Release);
+    }
+
+    pub fn potato_mode(&self) -> bool {
+        self.potato_mode.load(Ordering::Acquire)
+    }
+
+    pub fn set_frame_graph_visible(&self, visible: bool) {
+        self.frame_graph_visible.store(visible, Ordering::Release);
+    }
+
+    pub fn frame_graph_visible(&self) -> bool {
+        self.frame_graph_visible.load(Ordering::Acquire)
+    }
+
+    pub fn set_ignore_beatmap_combo_colors(&self, ignore: bool) {
+        self.ignore_beatmap_combo_colors
+            .store(ignore, Ordering::Release);
+    }
+
+    pub fn ignore_beatmap_combo_colors(&self) -> bool {
+        self.ignore_beatmap_combo_colors.load(Ordering::Acquire)
+    }
+
+    pub fn set_view_preset(&self, view_preset: ViewPreset) {
+        self.view_preset
+            .store(view_preset as u32, Ordering::Release);
+    }
+
+    pub fn view_preset(&self) -> ViewPreset {
+        ViewPreset::from_index(self.view_preset.load(Ordering::Acquire))
+    }
+
+    pub fn set_difficulty_simulation(&self, simulation: DifficultySimulation) {
+        if let Ok(mut guard) = self.difficulty_simulation.write() {
+            *guard = simulation;
+        }
+    }
+
+    pub fn difficulty_simulation(&self) -> DifficultySimulation {
+        self.difficulty_simulation
+            .read()
+            .map(|guard| *guard)
+            .unwrap_or_default()
+    }
+
+    pub fn set_convert_mode(&self, convert_mode: ConvertMode) {
+        self.convert_mode
+            .store(convert_mode as u32, Ordering::Release);
+    }
+
+    pub fn convert_mode(&self) -> ConvertMode {
+        ConvertMode::from_index(self.convert_mode.load(Ordering::Acquire))
+    }
+
+    pub fn set_replay(&self, replay: Option<Arc<OsrFile>>) {
+        if let Ok(mut guard) = self.replay.write() {
+            *guard = replay;
+        }
+    }
+
+    pub fn replay(&self) -> Option<Arc<OsrFile>> {
+        self.replay.read().ok().and_then(|guard| guard.clone())
+    }
 }
 
 pub struct RendererThread {
@@ -493,32 +882,45 @@ impl RendererThread {
         mut gpu: GpuRenderer,
         shared: Arc<RenderShared>,
         audio: Arc<AudioEngine>,
-        editor_config: Config,
+        mut editor_config: Config,
+        skin_combo_colors: Vec<Color>,
         ui_start: Instant,
     ) -> Self {
         let fps = editor_config.performance.fps_limiter;
-        let timeline_height_percent = editor_config.appearance.layout.timeline_height_percent;
-        let timeline_second_box_width_percent =
-            editor_config.appearance.layout.timeline_second_box_width_percent;
-        let timeline_third_box_width_percent =
-            editor_config.appearance.layout.timeline_third_box_width_percent;
+        let initial_hud_layout = HudLayout::from_config(&editor_config.appearance.layout);
         let frame_duration = Duration::from_secs_f64(1.0 / fps);
+        let adaptive_slider_quality = editor_config.performance.adaptive_slider_quality;
+        let slider_quality_target_frame_ms =
+            editor_config.performance.slider_quality_target_frame_ms;
+        let fallback_combo_colors: Vec<Color> = editor_config
+            .appearance
+            .colors
+            .fallback_combo_colors_rgb
+            .iter()
+            .map(|[r, g, b]| Color { r: *r, g: *g, b: *b })
+            .collect();
         let shared_for_thread = Arc::clone(&shared);
         let handle = std::thread::Builder::new()
             .name("renderer".to_string())
             .spawn(move || {
+                shared_for_thread.set_msaa_state(gpu.msaa_samples(), gpu.supported_msaa_samples());
                 let mut width = shared_for_thread.width.load(Ordering::Acquire);
                 let mut height = shared_for_thread.height.load(Ordering::Acquire);
                 let mut last_frame = Instant::now();
                 let mut fps_history: VecDeque<(Instant, f64)> = VecDeque::new();
+                let mut slider_quality = SliderQualityController::new();
+                let mut convert_preview_cache = ConvertPreviewCache::new();
+                let mut replay_judgement_cache = ReplayJudgementCache::new();
+                let mut unsnapped_objects_cache = UnsnappedObjectsCache::new();
+                let mut simulated_map_state_cache = SimulatedMapStateCache::new();
+                let mut kiai_levels = [0.0f32; 4];
                 let mut playfield_scale = shared_for_thread.playfield_scale().clamp(0.01, 1.0);
+                let mut hud_layout = initial_hud_layout;
                 let mut frame_layout = layout::compute_layout(
                     width as f64,
                     height as f64,
                     playfield_scale,
-                    timeline_height_percent,
-                    timeline_second_box_width_percent,
-                    timeline_third_box_width_percent,
+                    &hud_layout,
                 );
 
                 loop {
@@ -538,22 +940,33 @@ impl RendererThread {
                             width as f64,
                             height as f64,
                             playfield_scale,
-                            timeline_height_percent,
-                            timeline_second_box_width_percent,
-                            timeline_third_box_width_percent,
+                            &hud_layout,
                         );
                     }
 
+                    if let Some(background) = shared_for_thread.take_pending_background() {
+                        gpu.set_background(&background);
+                    }
+
+                    if let Some(samples) = shared_for_thread.take_pending_msaa_samples() {
+                        let applied = gpu.set_msaa_samples(samples);
+                        shared_for_thread.set_msaa_state(applied, gpu.supported_msaa_samples());
+                    }
+
+                    let pending_hud_layout = shared_for_thread.take_pending_hud_layout();
+                    if let Some(latest_hud_layout) = pending_hud_layout {
+                        hud_layout = latest_hud_layout;
+                    }
                     let latest_playfield_scale = shared_for_thread.playfield_scale().clamp(0.01, 1.0);
-                    if (latest_playfield_scale - playfield_scale).abs() > 1e-6 {
+                    if (latest_playfield_scale - playfield_scale).abs() > 1e-6
+                        || pending_hud_layout.is_some()
+                    {
                         playfield_scale = latest_playfield_scale;
                         frame_layout = layout::compute_layout(
                             width as f64,
                             height as f64,
                             playfield_scale,
-                            timeline_height_percent,
-                            timeline_second_box_width_percent,
-                            timeline_third_box_width_percent,
+                            &hud_layout,
                         );
                     }
 
@@ -579,12 +992,36 @@ impl RendererThread {
                         .map(|(_, value)| *value)
                         .fold(fps_clamped, f64::min);
 
-                    let song_total_ms = audio.song_total_ms();
-                    let time_ms = audio.current_time_ms();
+                    let song_total_ms = audio.song_total().0;
+                    let time_ms = audio.current_time().0;
                     let timeline_zoom = shared_for_thread.timeline_zoom().clamp(0.1, 10.0);
                     let time_elapsed_ms = ui_start.elapsed().as_secs_f64() * 1000.0;
                     let is_loading = song_total_ms <= 0.0 || audio.is_loading();
                     let is_playing = audio.is_playing();
+                    editor_config.appearance.general.show_flow_overlay =
+                        shared_for_thread.flow_overlay_visible();
+                    editor_config.appearance.general.show_frame_graph =
+                        shared_for_thread.frame_graph_visible();
+                    editor_config.appearance.general.show_kiai_effects =
+                        shared_for_thread.kiai_effects_visible();
+                    editor_config.performance.potato_mode = shared_for_thread.potato_mode();
+                    let band_levels = if editor_config.appearance.general.show_kiai_effects {
+                        audio.spectrum_band_levels()
+                    } else {
+                        [0.0; 4]
+                    };
+                    // Levels jump up with the music and fall off over about 150 ms.
+                    let level_decay = (-1000.0 / fps_clamped.max(1.0) / 150.0).exp() as f32;
+                    for (level, band) in kiai_levels.iter_mut().zip(band_levels) {
+                        *level = band.max(*level * level_decay);
+                    }
+                    let view_preset = shared_for_thread.view_preset();
+                    if ViewPreset::from_name(&editor_config.appearance.general.view_preset)
+                        != Some(view_preset)
+                    {
+                        editor_config.appearance.general.view_preset =
+                            view_preset.name().to_string();
+                    }
                     let audio_volume = audio.get_volume();
                     let hitsound_volume = audio.get_hitsound_volume();
 
@@ -614,6 +1051,11 @@ impl RendererThread {
                     let selection_right_origin_dragging =
                         shared_for_thread.selection_right_origin_dragging();
                     let cursor_pos = shared_for_thread.cursor_pos();
+                    let tooltip_text = shared_for_thread.visible_tooltip();
+                    let hint_line = shared_for_thread.hint_line();
+                    let editing_totals = shared_for_thread.editing_totals();
+                    let report_lines = shared_for_thread.report_lines();
+                    let onboarding_regions = shared_for_thread.onboarding_regions();
                     let play_pause_button_hovered = shared_for_thread.play_pause_button_hovered();
                     let play_pause_button_clicked = shared_for_thread.play_pause_button_clicked();
                     let undo_button_hovered = shared_for_thread.undo_button_hovered();
@@ -658,6 +1100,7 @@ impl RendererThread {
                             overlay_rect_left,
                             overlay_rect_right,
                             cursor_pos,
+                            editor_config.appearance.layout.snap_distance_px,
                             selection_left_bbox_dragging,
                             selection_right_bbox_dragging,
                             selection_left_origin_dragging,
@@ -757,7 +1200,7 @@ impl RendererThread {
                     let origin_dragging =
                         selection_left_origin_dragging || selection_right_origin_dragging;
 
-                    let (state, snap_positions, movable_snap_positions) = {
+                    let (state, snap_positions, movable_snap_positions, timeline_drag_marks) = {
                         let edit_state_guard = shared_for_thread.edit_state.read().unwrap();
                         let left_origin_locked = edit_state_guard
                             .left_selection
@@ -858,17 +1301,59 @@ impl RendererThread {
                             edit_state_guard.get_latest_export(),
                             static_positions,
                             movable_positions,
+                            edit_state_guard.timeline_drag_marks.clone(),
                         )
                     };
                     let drag_happening = selection_dragging || origin_dragging;
+                    if adaptive_slider_quality {
+                        let reduction = slider_quality.update(
+                            1000.0 / fps_current.max(1e-6),
+                            slider_quality_target_frame_ms,
+                            !is_playing && !drag_happening,
+                        );
+                        gpu.set_slider_quality_reduction(reduction);
+                    }
+                    // Map colours win unless ignored, then the skin's, then the config fallback.
+                    let combo_colors = if !state.combo_colors.is_empty()
+                        && !shared_for_thread.ignore_beatmap_combo_colors()
+                    {
+                        state.combo_colors.as_slice()
+                    } else if !skin_combo_colors.is_empty() {
+                        skin_combo_colors.as_slice()
+                    } else {
+                        fallback_combo_colors.as_slice()
+                    };
+                    let convert_mode = shared_for_thread.convert_mode();
+                    let convert_preview = if convert_mode != ConvertMode::Off {
+                        Some(convert_preview_cache.get(
+                            &state.objects,
+                            &state.diff_settings,
+                            &state.timing,
+                            convert_mode,
+                        ))
+                    } else {
+                        None
+                    };
+                    let replay = shared_for_thread.replay();
+                    let replay_judgements = replay.as_ref().map(|replay| {
+                        replay_judgement_cache.get(&state.objects, &state.diff_settings, replay)
+                    });
+                    let unsnapped_objects =
+                        unsnapped_objects_cache.get(&state.objects, &state.timing);
+                    // Only the drawn objects take the simulated AR / CS, judgements and
+                    // previews stay on the map's own.
+                    let drawn_state = simulated_map_state_cache
+                        .get(&state, &shared_for_thread.difficulty_simulation());
                     let render_result = gpu.render(
                         &frame_layout,
-                        &state.objects,
-                        state.combo_colors.as_slice(),
+                        &drawn_state.objects,
+                        combo_colors,
                         &state.break_times,
                         &state.kiai_times,
                         &state.bookmarks,
                         &state.red_lines,
+                        &state.timing,
+                        state.diff_settings.sv_multiplier,
                         &left_selected_objects,
                         &right_selected_objects,
                         time_ms,
@@ -912,6 +1397,7 @@ impl RendererThread {
                         selection_left_origin_dragging,
                         selection_right_origin_dragging,
                         cursor_pos,
+                        &tooltip_text,
                         play_pause_button_hovered,
                         play_pause_button_clicked,
                         undo_button_hovered,
@@ -934,6 +1420,19 @@ impl RendererThread {
                         movable_snap_positions.as_slice(),
                         drag_happening,
                         timeline_zoom,
+                        convert_preview,
+                        state.diff_settings.hit_windows_ms(),
+                        state.diff_settings.tick_rate,
+                        kiai_levels,
+                        audio.loop_range().map(|(start, end)| (start.0, end.0)),
+                        shared_for_thread.bottom_timeline_range(),
+                        replay.as_deref().zip(replay_judgements),
+                        unsnapped_objects,
+                        &hint_line,
+                        &onboarding_regions,
+                        &editing_totals,
+                        &report_lines,
+                        &timeline_drag_marks,
                     );
 
                     match render_result {
@@ -970,3 +1469,34 @@ impl RendererThread {
         self.shared.resize_pending.store(true, Ordering::Release);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_harness::{EditHarness, TEST_MAP};
+
+    #[test]
+    fn ghosts_cover_the_objects_around_the_playhead() {
+        let harness = EditHarness::new(TEST_MAP);
+        let map_state = harness.map_state();
+        let window = |time_ms, count| ghost_window_ms(map_state.objects.iter(), time_ms, count);
+
+        assert_eq!(window(1700.0, 1), Some((1500.0, 2000.0)));
+        assert_eq!(window(1700.0, 2), Some((1000.0, 3000.0)));
+        assert_eq!(window(0.0, 2), Some((1000.0, 1500.0)));
+        assert_eq!(window(20000.0, 1), Some((9500.0, 9500.0)));
+        assert_eq!(window(1700.0, 0), None);
+    }
+
+    #[test]
+    fn past_objects_reach_back_from_the_playhead_only() {
+        let harness = EditHarness::new(TEST_MAP);
+        let map_state = harness.map_state();
+        let window = |time_ms, count| past_window_ms(map_state.objects.iter(), time_ms, count);
+
+        assert_eq!(window(1700.0, 1), Some((1500.0, 1700.0)));
+        assert_eq!(window(1700.0, 2), Some((1000.0, 1700.0)));
+        assert_eq!(window(0.0, 2), None);
+        assert_eq!(window(1700.0, 0), None);
+    }
+}
@@ -0,0 +1,63 @@
+pub const MAX_REPORT_LINES: usize = 8;
+pub const MAX_REPORT_LINE_CHARS: usize = 48;
+
+// What the last map check found, drawn in the top right of the HUD until Esc or the next
+// check. The first line is the heading, the rest are findings in the HUD font's letters,
+// digits, spaces and `-./:_|`.
+#[derive(Clone, Default)]
+pub struct ReportPanel {
+    lines: Vec<String>,
+}
+
+impl ReportPanel {
+    pub fn new(heading: &str) -> Self {
+        ReportPanel {
+            lines: vec![hud_text(heading)],
+        }
+    }
+
+    pub fn push(&mut self, line: &str) {
+        self.lines.push(hud_text(line));
+    }
+
+    // Findings past what fits give way to how many more there are.
+    pub fn hud_lines(&self) -> Vec<String> {
+        if self.lines.len() <= MAX_REPORT_LINES {
+            return self.lines.clone();
+        }
+        let shown = MAX_REPORT_LINES - 1;
+        let mut lines = self.lines[..shown].to_vec();
+        lines.push(format!("and {} more", self.lines.len() - shown));
+        lines
+    }
+}
+
+fn hud_text(text: &str) -> String {
+    text.chars()
+        .map(|ch| {
+            if ch.is_ascii_alphanumeric() || " -./:_|".contains(ch) {
+                ch
+            } else {
+                ' '
+            }
+        })
+        .take(MAX_REPORT_LINE_CHARS)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn long_reports_end_with_how_many_more_there_are() {
+        let mut panel = ReportPanel::new("overlaps above 60%");
+        for i in 0..10 {
+            panel.push(&format!("{}ms", i * 1000));
+        }
+        let lines = panel.hud_lines();
+        assert_eq!(lines.len(), MAX_REPORT_LINES);
+        assert_eq!(lines[0], "overlaps above 60 ");
+        assert_eq!(lines[MAX_REPORT_LINES - 1], "and 4 more");
+    }
+}
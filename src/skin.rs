@@ -1,7 +1,11 @@
 use serde::{Deserialize, Serialize};
 use std::{collections::HashMap, path::Path};
 
+use crate::asset_cache::{self, CacheKind};
+use crate::dotosu::helpers::get_section;
+use crate::dotosu::sections::colours::parse_colour;
 use crate::files::scan_folder;
+use crate::map_format::colors::Color;
 
 #[derive(Serialize, Deserialize, Clone)]
 pub struct Skin {
@@ -27,6 +31,8 @@ pub struct Skin {
     pub numbers: Vec<Texture>,
 
     pub hitsounds: HashMap<String, Vec<u8>>,
+    // Combo1..ComboN from the skin.ini [Colours] section, empty when the skin sets none.
+    pub combo_colors: Vec<Color>,
 }
 
 impl Skin {
@@ -137,6 +143,7 @@ impl Skin {
                 is_2x: false,
             }),
             hitsounds: hitsound_files,
+            combo_colors: load_skin_combo_colors(path),
         })
     }
 }
@@ -149,6 +156,48 @@ pub struct Texture {
     pub is_2x: bool,
 }
 
+// skin.ini files are hand-edited, so unlike .osu parsing anything unexpected is skipped
+// instead of failing the whole section.
+fn load_skin_combo_colors(skin_path: &Path) -> Vec<Color> {
+    let Ok(bytes) = std::fs::read(skin_path.join("skin.ini")) else {
+        return Vec::new();
+    };
+    let text = String::from_utf8_lossy(&bytes);
+    let Some(section) = get_section(&text, "Colours") else {
+        return Vec::new();
+    };
+    let mut combos: HashMap<u32, Color> = HashMap::new();
+    for line in section.lines() {
+        let line = line.split("//").next().unwrap_or("");
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let Some(index) = key.trim().strip_prefix("Combo") else {
+            continue;
+        };
+        let (Ok(index), Some(colour)) = (index.parse::<u32>(), parse_colour(value)) else {
+            continue;
+        };
+        combos.insert(
+            index,
+            Color {
+                r: colour.r,
+                g: colour.g,
+                b: colour.b,
+            },
+        );
+    }
+    // Like the game, combo colours are used in order up to the first missing one.
+    let mut combo_colors = Vec::new();
+    for index in 1.. {
+        match combos.remove(&index) {
+            Some(color) => combo_colors.push(color),
+            None => break,
+        }
+    }
+    combo_colors
+}
+
 fn load_skin_animation_texture(
     skin_path: &Path,
     fallback_path: &Path,
@@ -247,6 +296,10 @@ fn load_texture_from_path(path: &Path) -> Option<Texture> {
 }
 
 pub fn load_texture(bytes: &[u8]) -> Option<Texture> {
+    let cached = asset_cache::load(CacheKind::Texture, bytes).and_then(texture_from_cache);
+    if cached.is_some() {
+        return cached;
+    }
     let img = match image::load_from_memory(bytes) {
         Ok(i) => i,
         Err(e) => {
@@ -256,10 +309,36 @@ pub fn load_texture(bytes: &[u8]) -> Option<Texture> {
     };
     let rgba = img.to_rgba8();
     let (width, height) = rgba.dimensions();
-    return Some(Texture {
+    let texture = Texture {
         rgba: rgba.into_raw(),
         width,
         height,
         is_2x: false,
-    });
+    };
+    asset_cache::store(CacheKind::Texture, bytes, texture_to_cache(&texture));
+    return Some(texture);
+}
+
+// Cached as width and height (u32 LE) followed by the raw RGBA bytes.
+fn texture_to_cache(texture: &Texture) -> Vec<u8> {
+    let mut data = Vec::with_capacity(8 + texture.rgba.len());
+    data.extend_from_slice(&texture.width.to_le_bytes());
+    data.extend_from_slice(&texture.height.to_le_bytes());
+    data.extend_from_slice(&texture.rgba);
+    data
+}
+
+fn texture_from_cache(mut data: Vec<u8>) -> Option<Texture> {
+    let width = u32::from_le_bytes(data.get(0..4)?.try_into().ok()?);
+    let height = u32::from_le_bytes(data.get(4..8)?.try_into().ok()?);
+    if data.len() != 8 + width as usize * height as usize * 4 {
+        return None;
+    }
+    data.drain(..8);
+    Some(Texture {
+        rgba: data,
+        width,
+        height,
+        is_2x: false,
+    })
 }
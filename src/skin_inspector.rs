@@ -0,0 +1,174 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+use winit::event_loop::EventLoop;
+
+use crate::{audio::AudioEngine, dialogue_app::DialogueApp, files::scan_folder, skin::Skin};
+
+// Sprites the editor loads, with what happens when neither the skin nor the default skin has
+// one. Animated sprites are looked up by their first frame. Combo numbers are added after these.
+const SKIN_ELEMENTS: [(&str, bool, &str); 13] = [
+    ("hitcircle", false, "required"),
+    ("hitcircleoverlay", false, "required"),
+    ("approachcircle", false, "required"),
+    ("sliderstartcircle", false, "uses hitcircle"),
+    ("sliderstartcircleoverlay", false, "uses hitcircleoverlay"),
+    ("sliderendcircle", false, "not drawn"),
+    ("sliderendcircleoverlay", false, "uses hitcircleoverlay"),
+    ("reversearrow", false, "required"),
+    ("sliderb", true, "required"),
+    ("sliderfollowcircle", false, "required"),
+    ("followpoint", true, "required"),
+    ("spinner-circle", false, "not drawn"),
+    ("cursor", false, "required"),
+];
+
+enum InspectorRow {
+    Sprite,
+    Hitsound(usize),
+}
+
+pub fn select_and_inspect_skin(
+    event_loop: &mut EventLoop<()>,
+    selector: &mut DialogueApp,
+    audio: &Arc<AudioEngine>,
+) {
+    println!("Inspecting skin...");
+    let entries = scan_folder(Path::new("skins"), Some(true), None);
+    if entries.is_empty() {
+        println!("No skins found in skins/");
+        return;
+    }
+
+    println!("Available skins:");
+    let selection = match selector.select(event_loop, "Inspect skin", &entries) {
+        Some(idx) => idx,
+        None => {
+            println!("Skin selection cancelled.");
+            return;
+        }
+    };
+    inspect_skin(&entries[selection], event_loop, selector, audio);
+}
+
+// The file a sprite is loaded from, same lookup order as the skin loader: @2x before 1x and
+// animation frames before the single image.
+fn find_sprite_file(dir: &Path, name: &str, animated: bool) -> Option<PathBuf> {
+    let mut stems = Vec::new();
+    if animated {
+        stems.push(format!("{}-0", name));
+        stems.push(format!("{}0", name));
+    }
+    stems.push(name.to_string());
+    stems
+        .iter()
+        .flat_map(|stem| [format!("{}@2x.png", stem), format!("{}.png", stem)])
+        .map(|file_name| dir.join(file_name))
+        .find(|path| path.is_file())
+}
+
+fn inspect_skin(
+    skin_name: &str,
+    event_loop: &mut EventLoop<()>,
+    selector: &mut DialogueApp,
+    audio: &Arc<AudioEngine>,
+) {
+    let skin_path = Path::new("skins").join(skin_name);
+    let default_path = Path::new("skins/default");
+    let skin = match Skin::load_from_path(&skin_path, default_path) {
+        Some(skin) => skin,
+        None => {
+            println!("Failed to load skin {}.", skin_name);
+            return;
+        }
+    };
+
+    let mut labels: Vec<String> = Vec::new();
+    let mut images: Vec<Option<Vec<u8>>> = Vec::new();
+    let mut rows: Vec<InspectorRow> = Vec::new();
+    let mut substituted = 0;
+
+    let numbers = (0..10).map(|digit| (format!("default-{}", digit), false, "required"));
+    let sprites: Vec<(String, bool, &str)> = SKIN_ELEMENTS
+        .iter()
+        .map(|(name, animated, when_missing)| (name.to_string(), *animated, *when_missing))
+        .chain(numbers)
+        .collect();
+    for (name, animated, when_missing) in &sprites {
+        let from_skin = find_sprite_file(&skin_path, name, *animated);
+        let from_default = find_sprite_file(default_path, name, *animated);
+        let (label, file) = match (from_skin, from_default) {
+            (Some(path), _) => (format!("{}: skin {}", name, file_label(&path)), Some(path)),
+            (None, Some(path)) => {
+                substituted += 1;
+                (
+                    format!("{}: default {}", name, file_label(&path)),
+                    Some(path),
+                )
+            }
+            (None, None) => (format!("{}: missing, {}", name, when_missing), None),
+        };
+        labels.push(label);
+        images.push(file.and_then(|path| fs::read(path).ok()));
+        rows.push(InspectorRow::Sprite);
+    }
+
+    // Loaded into the audio engine like when opening a map, indexed by sorted name.
+    let mut hitsound_names: Vec<&String> = skin.hitsounds.keys().collect();
+    hitsound_names.sort();
+    audio.remove_all_hitsound_samples();
+    for (index, name) in hitsound_names.iter().enumerate() {
+        let hint_ext = Path::new(name.as_str())
+            .extension()
+            .and_then(|s| s.to_str())
+            .map(|s| s.to_ascii_lowercase());
+        audio.set_hitsound_sample(
+            skin.hitsounds[*name].clone(),
+            index,
+            (*name).clone(),
+            hint_ext,
+        );
+        let source = if skin_path.join(name.as_str()).is_file() {
+            "skin"
+        } else {
+            substituted += 1;
+            "default"
+        };
+        labels.push(format!("{}: {} - play", name, source));
+        images.push(None);
+        rows.push(InspectorRow::Hitsound(index));
+    }
+
+    println!(
+        "Skin {}: {} sprites and {} hitsounds, {} taken from the default skin.",
+        skin_name,
+        sprites.len(),
+        hitsound_names.len(),
+        substituted
+    );
+    if !skin.combo_colors.is_empty() {
+        println!("Skin sets {} combo colours.", skin.combo_colors.len());
+    }
+
+    // Stays open until cancelled so several hitsounds can be played in a row.
+    let title = format!("Skin {} - select a hitsound to play it", skin_name);
+    loop {
+        let selection = match selector.select_with_images(event_loop, &title, &labels, &images) {
+            Some(idx) => idx,
+            None => break,
+        };
+        match rows[selection] {
+            InspectorRow::Hitsound(index) => audio.audition_hitsound(index, 1.0, 0.5),
+            InspectorRow::Sprite => println!("{}", labels[selection]),
+        }
+    }
+    audio.remove_all_hitsound_samples();
+}
+
+fn file_label(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
@@ -0,0 +1,93 @@
+use std::f64::consts::FRAC_PI_4;
+
+use crate::geometry::vec2::Vec2;
+
+// Where a dragged slider anchor lands. With `align` it slides along the closest horizontal,
+// vertical or diagonal line through the anchor before it (the one after it for the head).
+// Otherwise it snaps onto another anchor of the same slider within `snap_distance`, and onto
+// the grid when `grid_px` is above 0.
+pub fn snap_slider_anchor(
+    anchors: &[Vec2],
+    index: usize,
+    target: Vec2,
+    snap_distance: f64,
+    grid_px: f64,
+    align: bool,
+) -> Vec2 {
+    if align {
+        let neighbour = if index > 0 {
+            anchors.get(index - 1)
+        } else {
+            anchors.get(1)
+        };
+        if let Some(neighbour) = neighbour {
+            let offset = target - *neighbour;
+            if offset.len2() < 1e-12 {
+                return *neighbour;
+            }
+            let angle = (offset.y.atan2(offset.x) / FRAC_PI_4).round() * FRAC_PI_4;
+            let direction = Vec2 {
+                x: angle.cos(),
+                y: angle.sin(),
+            };
+            return *neighbour + direction * (offset.x * direction.x + offset.y * direction.y);
+        }
+    }
+
+    let closest = anchors
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| *i != index)
+        .map(|(_, anchor)| (anchor.distance2(target), *anchor))
+        .filter(|(d2, _)| *d2 <= snap_distance * snap_distance)
+        .min_by(|a, b| a.0.total_cmp(&b.0));
+    if let Some((_, anchor)) = closest {
+        return anchor;
+    }
+    if grid_px > 0.0 {
+        return Vec2 {
+            x: (target.x / grid_px).round() * grid_px,
+            y: (target.y / grid_px).round() * grid_px,
+        };
+    }
+    target
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        map_format::objects::HitObject,
+        test_harness::{EditHarness, TEST_MAP},
+    };
+
+    #[test]
+    fn slider_anchors_snap_to_neighbours_anchors_and_grid() {
+        let anchors = [
+            Vec2 { x: 100.0, y: 100.0 },
+            Vec2 { x: 300.0, y: 100.0 },
+            Vec2 { x: 300.0, y: 300.0 },
+        ];
+        let snap =
+            |target: Vec2, align: bool| snap_slider_anchor(&anchors, 2, target, 10.0, 16.0, align);
+        let aligned = snap(Vec2 { x: 405.0, y: 195.0 }, true);
+        assert!(aligned.distance2(Vec2 { x: 400.0, y: 200.0 }) < 1e-9);
+        assert_eq!(snap(Vec2 { x: 104.0, y: 103.0 }, false), anchors[0]);
+        assert_eq!(
+            snap(Vec2 { x: 203.0, y: 197.0 }, false),
+            Vec2 { x: 208.0, y: 192.0 }
+        );
+
+        let harness = EditHarness::new(TEST_MAP);
+        let moved = harness.edit(|s| s.move_slider_anchor(2, 1, Vec2 { x: 300.0, y: 200.0 }, true));
+        assert_eq!(moved, Some(2));
+        let slider = harness.map_state().objects.get(2);
+        let HitObject::Slider(slider) = &*slider.hit_object else {
+            panic!("object 2 is not a slider");
+        };
+        assert_eq!(
+            slider.control_points.anchors(),
+            vec![Vec2 { x: 100.0, y: 100.0 }, Vec2 { x: 300.0, y: 200.0 }]
+        );
+    }
+}
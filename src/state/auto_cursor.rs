@@ -0,0 +1,113 @@
+use crate::{geometry::vec2::Vec2, gpu::gpu::ObjectInstance, treap::Treap};
+
+use super::object::Object;
+
+const SPINNER_CENTER: Vec2 = Vec2 { x: 256.0, y: 192.0 };
+const SPINNER_RADIUS: f64 = 50.0;
+// Auto spins at roughly 477 rpm.
+const SPINNER_RADIANS_PER_MS: f64 = 0.05;
+
+fn spinner_position(instance: &ObjectInstance, time_ms: f64) -> Vec2 {
+    let angle = (time_ms - instance.time) * SPINNER_RADIANS_PER_MS;
+    SPINNER_CENTER
+        + Vec2 {
+            x: angle.cos(),
+            y: angle.sin(),
+        } * SPINNER_RADIUS
+}
+
+// Where the cursor is while `instance` is active. `time_ms` is clamped to the object.
+fn position_on_object(instance: &ObjectInstance, time_ms: f64) -> Vec2 {
+    let time_ms = time_ms.min(instance.slider_end_time_ms).max(instance.time);
+    if instance.is_slider && instance.slider_path.is_some() {
+        instance
+            .sample_position_and_progress_and_direction(time_ms)
+            .0
+    } else if instance.is_spinner {
+        spinner_position(instance, time_ms)
+    } else {
+        instance.pos
+    }
+}
+
+// Index of the first object starting after `time_ms`.
+fn next_object_index(objects: &Treap<Object>, time_ms: f64) -> usize {
+    let (mut lo, mut hi) = (0, objects.len());
+    while lo < hi {
+        let mid = (lo + hi) / 2;
+        if objects.get(mid).hit_object.time() <= time_ms {
+            lo = mid + 1;
+        } else {
+            hi = mid;
+        }
+    }
+    lo
+}
+
+// Cursor of the Auto mod: it sits on circles, follows slider balls, spins spinners and
+// moves between objects with an eased straight line. Objects must already be exported.
+pub fn auto_cursor_position(objects: &Treap<Object>, time_ms: f64) -> Option<Vec2> {
+    let next = next_object_index(objects, time_ms);
+    if next == 0 {
+        let first = objects.iter().next()?;
+        return Some(position_on_object(first.instance()?, f64::NEG_INFINITY));
+    }
+    let current = objects.get(next - 1);
+    let current = current.instance()?;
+    if time_ms <= current.slider_end_time_ms || next == objects.len() {
+        return Some(position_on_object(current, time_ms));
+    }
+
+    let next = objects.get(next);
+    let next = next.instance()?;
+    let from = position_on_object(current, current.slider_end_time_ms);
+    let to = position_on_object(next, next.time);
+    let gap_ms = next.time - current.slider_end_time_ms;
+    if gap_ms <= 0.0 {
+        return Some(to);
+    }
+    let t = ((time_ms - current.slider_end_time_ms) / gap_ms).clamp(0.0, 1.0);
+    let eased = t * t * (3.0 - 2.0 * t);
+    Some(from + (to - from) * eased)
+}
+
+// `samples` cursor positions over the last `trail_ms`, oldest first. The last one is the
+// cursor itself.
+pub fn auto_cursor_trail(
+    objects: &Treap<Object>,
+    time_ms: f64,
+    trail_ms: f64,
+    samples: usize,
+) -> Vec<Vec2> {
+    let samples = samples.max(1);
+    let step_ms = if samples > 1 {
+        trail_ms.max(0.0) / (samples - 1) as f64
+    } else {
+        0.0
+    };
+    (0..samples)
+        .filter_map(|i| auto_cursor_position(objects, time_ms - (samples - 1 - i) as f64 * step_ms))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_harness::{EditHarness, TEST_MAP};
+
+    #[test]
+    fn auto_cursor_follows_objects() {
+        let harness = EditHarness::new(TEST_MAP);
+        let map_state = harness.map_state();
+        map_state.export();
+        let at = |time_ms| *auto_cursor_trail(&map_state.objects, time_ms, 0.0, 1).last().unwrap();
+
+        assert_eq!(at(0.0), Vec2 { x: 256.0, y: 192.0 });
+        assert_eq!(at(1500.0), Vec2 { x: 356.0, y: 192.0 });
+        let between = at(1250.0);
+        assert!(between.x > 256.0 && between.x < 356.0);
+        let trail = auto_cursor_trail(&map_state.objects, 2100.0, 100.0, 8);
+        assert_eq!(trail.len(), 8);
+        assert!(trail.windows(2).all(|pair| pair[0].x <= pair[1].x));
+    }
+}
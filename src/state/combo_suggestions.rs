@@ -0,0 +1,125 @@
+use crate::{
+    map_format::{objects::HitObject, timing::Timing},
+    treap::Treap,
+};
+
+use super::object::Object;
+
+// One combo of a proposal: where it starts, how many objects it has and which of the
+// colours it gets, counting from 0.
+pub struct ProposedCombo {
+    pub start_ms: f64,
+    pub object_count: usize,
+    pub color_index: usize,
+}
+
+pub struct ComboProposal {
+    // One flag per object, in map order.
+    pub new_combos: Vec<bool>,
+    pub combos: Vec<ProposedCombo>,
+    pub added: usize,
+    pub removed: usize,
+}
+
+// Phrase starts: every `measures_per_combo`-th downbeat of each red line, and every bookmark.
+fn phrase_starts(
+    timing: &Timing,
+    bookmarks: &Treap<f64>,
+    start_ms: f64,
+    end_ms: f64,
+    measures_per_combo: usize,
+) -> Vec<f64> {
+    let mut starts: Vec<f64> = timing
+        .beats_between(start_ms, end_ms)
+        .into_iter()
+        .filter(|(_, downbeat)| *downbeat)
+        .map(|(time, _)| time)
+        .enumerate()
+        .filter(|(i, _)| i % measures_per_combo.max(1) == 0)
+        .map(|(_, time)| time)
+        .collect();
+    starts.extend(bookmarks.iter().copied());
+    starts.sort_by(|a, b| a.total_cmp(b));
+    starts
+}
+
+// Puts a new combo on the first object at or after each phrase start. Spinners and the object
+// after a spinner always start one, like osu! does. Colours cycle through `color_count`
+// colours the way the playfield draws them: spinners don't take a colour and existing colour
+// skips are kept.
+pub fn propose_new_combos(
+    objects: &Treap<Object>,
+    timing: &Timing,
+    bookmarks: &Treap<f64>,
+    measures_per_combo: usize,
+    color_count: usize,
+) -> ComboProposal {
+    let hit_objects: Vec<&HitObject> = objects.iter().map(|o| o.hit_object.as_ref()).collect();
+    let (Some(first), Some(last)) = (hit_objects.first(), hit_objects.last()) else {
+        return ComboProposal {
+            new_combos: Vec::new(),
+            combos: Vec::new(),
+            added: 0,
+            removed: 0,
+        };
+    };
+    // Half a millisecond of slack for objects snapped a rounding error before the line.
+    let starts = phrase_starts(
+        timing,
+        bookmarks,
+        first.time() - 0.5,
+        last.time() + 0.5,
+        measures_per_combo,
+    );
+
+    let mut new_combos = Vec::with_capacity(hit_objects.len());
+    let mut next_start = 0;
+    let mut after_spinner = true;
+    for hit_object in hit_objects.iter() {
+        let mut crossed = false;
+        while next_start < starts.len() && starts[next_start] <= hit_object.time() + 0.5 {
+            crossed = true;
+            next_start += 1;
+        }
+        let is_spinner = matches!(hit_object, HitObject::Spinner(_));
+        new_combos.push(crossed || after_spinner || is_spinner);
+        after_spinner = is_spinner;
+    }
+
+    let mut combos: Vec<ProposedCombo> = Vec::new();
+    // Starts where the playfield's colour counter starts.
+    let mut color_index: i64 = 0;
+    for (hit_object, new_combo) in hit_objects.iter().zip(new_combos.iter()) {
+        if *new_combo {
+            if !matches!(hit_object, HitObject::Spinner(_)) && color_count > 0 {
+                color_index += 1 + hit_object.combo_info().color_skip;
+                color_index = color_index.rem_euclid(color_count as i64);
+            }
+            combos.push(ProposedCombo {
+                start_ms: hit_object.time(),
+                object_count: 0,
+                color_index: color_index as usize,
+            });
+        }
+        if let Some(combo) = combos.last_mut() {
+            combo.object_count += 1;
+        }
+    }
+
+    let added = hit_objects
+        .iter()
+        .zip(new_combos.iter())
+        .filter(|(o, nc)| **nc && !o.combo_info().new_combo)
+        .count();
+    let removed = hit_objects
+        .iter()
+        .zip(new_combos.iter())
+        .filter(|(o, nc)| !**nc && o.combo_info().new_combo)
+        .count();
+    ComboProposal {
+        new_combos,
+        combos,
+        added,
+        removed,
+    }
+}
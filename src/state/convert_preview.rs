@@ -0,0 +1,383 @@
+use std::sync::Arc;
+
+use crate::{
+    gpu::gpu::ObjectInstance,
+    map_format::{
+        diff_settings::{DiffSettings, circle_radius_to_cs},
+        objects::{HitObject, HitsoundInfo},
+        timing::Timing,
+    },
+    treap::Treap,
+};
+
+use super::object::Object;
+
+pub const CONVERT_MODE_NAMES: [&str; 4] = ["off", "taiko", "catch", "mania"];
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ConvertMode {
+    Off = 0,
+    Taiko = 1,
+    Catch = 2,
+    Mania = 3,
+}
+
+impl ConvertMode {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "off" | "" => Some(ConvertMode::Off),
+            "taiko" => Some(ConvertMode::Taiko),
+            "catch" | "ctb" => Some(ConvertMode::Catch),
+            "mania" => Some(ConvertMode::Mania),
+            _ => None,
+        }
+    }
+
+    pub fn from_index(index: u32) -> Self {
+        match index {
+            1 => ConvertMode::Taiko,
+            2 => ConvertMode::Catch,
+            3 => ConvertMode::Mania,
+            _ => ConvertMode::Off,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        CONVERT_MODE_NAMES[*self as usize]
+    }
+
+    pub fn next(&self) -> Self {
+        Self::from_index((*self as u32 + 1) % CONVERT_MODE_NAMES.len() as u32)
+    }
+}
+
+// Values are shared with the HUD shader.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ConvertedKind {
+    Don = 0,
+    Kat = 1,
+    BigDon = 2,
+    BigKat = 3,
+    Drumroll = 4,
+    Swell = 5,
+    Fruit = 6,
+    Droplet = 7,
+    Banana = 8,
+    Note = 9,
+    Hold = 10,
+}
+
+pub struct ConvertedNote {
+    pub time_ms: f64,
+    pub end_time_ms: f64,
+    // 0 is the top of the strip, 1 the bottom.
+    pub lane: f64,
+    pub kind: ConvertedKind,
+}
+
+pub struct ConvertPreview {
+    pub mode: ConvertMode,
+    // Mania only, 0 otherwise.
+    pub key_count: u32,
+    pub notes: Vec<ConvertedNote>,
+}
+
+fn beat_length_at(timing: &Timing, time_ms: f64) -> f64 {
+    match timing.get_lines_at_time(time_ms).0 {
+        Some(red_line) if red_line.beat_length > 0.0 => red_line.beat_length,
+        _ => 500.0,
+    }
+}
+
+fn taiko_kind(whistle: bool, clap: bool, finish: bool) -> ConvertedKind {
+    match (whistle || clap, finish) {
+        (false, false) => ConvertedKind::Don,
+        (true, false) => ConvertedKind::Kat,
+        (false, true) => ConvertedKind::BigDon,
+        (true, true) => ConvertedKind::BigKat,
+    }
+}
+
+fn taiko_kind_of(hitsound: &HitsoundInfo) -> ConvertedKind {
+    taiko_kind(
+        hitsound.play_whistle,
+        hitsound.play_clap,
+        hitsound.play_finish,
+    )
+}
+
+// Distance between slider ticks, never longer than one slide.
+fn tick_spacing_ms(instance: &ObjectInstance, timing: &Timing, tick_rate: f64) -> f64 {
+    let beat_length = beat_length_at(timing, instance.time);
+    (beat_length / tick_rate.max(0.1)).min(instance.slider_slide_duration_ms.max(1.0))
+}
+
+// A slider shorter than two beats becomes a hit on every tick, cycling through its edge
+// hitsounds, longer ones a drumroll. osu! also speeds drumrolls up by 1.4x, which moves
+// their end but not their start, so that is left out.
+fn convert_taiko(
+    object: &HitObject,
+    instance: &ObjectInstance,
+    timing: &Timing,
+    tick_rate: f64,
+) -> Vec<ConvertedNote> {
+    let note = |time_ms: f64, end_time_ms: f64, kind: ConvertedKind| ConvertedNote {
+        time_ms,
+        end_time_ms,
+        lane: 0.5,
+        kind,
+    };
+    match object {
+        HitObject::Circle(circle) => vec![note(
+            circle.time,
+            circle.time,
+            taiko_kind_of(&circle.hitsound_info),
+        )],
+        HitObject::Spinner(spinner) => {
+            vec![note(spinner.time, spinner.end_time, ConvertedKind::Swell)]
+        }
+        HitObject::Slider(slider) => {
+            let end_ms = instance.slider_end_time_ms;
+            let beat_length = beat_length_at(timing, slider.time);
+            if end_ms - slider.time >= beat_length * 2.0 || slider.hitsounds.is_empty() {
+                return vec![note(slider.time, end_ms, ConvertedKind::Drumroll)];
+            }
+            let spacing = tick_spacing_ms(instance, timing, tick_rate);
+            let mut notes = Vec::new();
+            let mut time_ms = slider.time;
+            let mut i = 0;
+            while time_ms <= end_ms + spacing / 8.0 {
+                let hitsound = &slider.hitsounds[i % slider.hitsounds.len()];
+                notes.push(note(time_ms, time_ms, taiko_kind_of(hitsound)));
+                time_ms += spacing;
+                i += 1;
+            }
+            notes
+        }
+    }
+}
+
+// Fruits on the head and every edge, droplets on the ticks in between.
+fn convert_catch(
+    object: &HitObject,
+    instance: &ObjectInstance,
+    timing: &Timing,
+    tick_rate: f64,
+) -> Vec<ConvertedNote> {
+    let lane_of = |x: f64| (x / 512.0).clamp(0.0, 1.0);
+    match object {
+        HitObject::Circle(circle) => vec![ConvertedNote {
+            time_ms: circle.time,
+            end_time_ms: circle.time,
+            lane: lane_of(circle.pos.x),
+            kind: ConvertedKind::Fruit,
+        }],
+        HitObject::Spinner(spinner) => vec![ConvertedNote {
+            time_ms: spinner.time,
+            end_time_ms: spinner.end_time,
+            lane: 0.5,
+            kind: ConvertedKind::Banana,
+        }],
+        HitObject::Slider(slider) => {
+            if instance.slider_path.is_none() {
+                return Vec::new();
+            }
+            let slide_ms = instance.slider_slide_duration_ms.max(1.0);
+            let spacing = tick_spacing_ms(instance, timing, tick_rate);
+            let x_at = |time_ms: f64| {
+                let (pos, _, _) = instance.sample_position_and_progress_and_direction(time_ms);
+                lane_of(pos.x)
+            };
+            let mut notes = Vec::new();
+            for slide in 0..slider.slides {
+                let start_ms = slider.time + slide_ms * slide as f64;
+                if slide == 0 {
+                    notes.push(ConvertedNote {
+                        time_ms: start_ms,
+                        end_time_ms: start_ms,
+                        lane: lane_of(slider.control_points.start.x),
+                        kind: ConvertedKind::Fruit,
+                    });
+                }
+                let mut tick_ms = start_ms + spacing;
+                while tick_ms < start_ms + slide_ms - 10.0 {
+                    notes.push(ConvertedNote {
+                        time_ms: tick_ms,
+                        end_time_ms: tick_ms,
+                        lane: x_at(tick_ms),
+                        kind: ConvertedKind::Droplet,
+                    });
+                    tick_ms += spacing;
+                }
+                let edge_ms = start_ms + slide_ms;
+                let edge_pos = if slide % 2 == 0 {
+                    instance.end_pos()
+                } else {
+                    slider.control_points.start
+                };
+                notes.push(ConvertedNote {
+                    time_ms: edge_ms,
+                    end_time_ms: edge_ms,
+                    lane: lane_of(edge_pos.x),
+                    kind: ConvertedKind::Fruit,
+                });
+            }
+            notes
+        }
+    }
+}
+
+// Key count osu! picks for a converted standard map.
+pub fn mania_key_count(objects: &Treap<Object>, diff_settings: &DiffSettings) -> u32 {
+    let total = objects.len().max(1);
+    let long_objects = objects
+        .iter()
+        .filter(|object| !matches!(*object.hit_object, HitObject::Circle(_)))
+        .count();
+    let long_percent = long_objects as f64 / total as f64;
+    let cs = circle_radius_to_cs(diff_settings.circle_radius).round();
+    let od = diff_settings.overall_difficulty.round();
+    if long_percent < 0.2 {
+        7
+    } else if long_percent < 0.3 || cs >= 5.0 {
+        if od > 5.0 { 7 } else { 6 }
+    } else if long_percent > 0.6 {
+        if od > 4.0 { 5 } else { 4 }
+    } else {
+        (od as u32 + 1).clamp(4, 7)
+    }
+}
+
+// osu! shuffles columns with a seeded pattern generator, here every object keeps the column
+// under its x so the preview stays stable while editing.
+fn convert_mania(object: &HitObject, instance: &ObjectInstance, key_count: u32) -> ConvertedNote {
+    let column =
+        ((object.position().x / 512.0 * key_count as f64).floor() as u32).min(key_count - 1);
+    let (end_time_ms, kind) = match object {
+        HitObject::Circle(circle) => (circle.time, ConvertedKind::Note),
+        HitObject::Slider(_) => (instance.slider_end_time_ms, ConvertedKind::Hold),
+        HitObject::Spinner(spinner) => (spinner.end_time, ConvertedKind::Hold),
+    };
+    ConvertedNote {
+        time_ms: object.time(),
+        end_time_ms,
+        lane: (column as f64 + 0.5) / key_count as f64,
+        kind,
+    }
+}
+
+// Objects must already be exported.
+pub fn convert_preview(
+    objects: &Treap<Object>,
+    diff_settings: &DiffSettings,
+    timing: &Timing,
+    mode: ConvertMode,
+) -> ConvertPreview {
+    let key_count = if mode == ConvertMode::Mania {
+        mania_key_count(objects, diff_settings)
+    } else {
+        0
+    };
+    let mut notes = Vec::new();
+    for object in objects.iter() {
+        let Some(instance) = object.instance() else {
+            continue;
+        };
+        let hit_object = &*object.hit_object;
+        match mode {
+            ConvertMode::Off => {}
+            ConvertMode::Taiko => notes.extend(convert_taiko(
+                hit_object,
+                instance,
+                timing,
+                diff_settings.tick_rate,
+            )),
+            ConvertMode::Catch => notes.extend(convert_catch(
+                hit_object,
+                instance,
+                timing,
+                diff_settings.tick_rate,
+            )),
+            ConvertMode::Mania => notes.push(convert_mania(hit_object, instance, key_count)),
+        }
+    }
+    ConvertPreview {
+        mode,
+        key_count,
+        notes,
+    }
+}
+
+pub struct ConvertPreviewCache {
+    objects: Treap<Object>,
+    timing: Option<Arc<Timing>>,
+    diff_settings: Option<DiffSettings>,
+    preview: ConvertPreview,
+}
+
+impl ConvertPreviewCache {
+    pub fn new() -> Self {
+        ConvertPreviewCache {
+            objects: Treap::new_empty(),
+            timing: None,
+            diff_settings: None,
+            preview: ConvertPreview {
+                mode: ConvertMode::Off,
+                key_count: 0,
+                notes: Vec::new(),
+            },
+        }
+    }
+
+    pub fn get(
+        &mut self,
+        objects: &Treap<Object>,
+        diff_settings: &DiffSettings,
+        timing: &Arc<Timing>,
+        mode: ConvertMode,
+    ) -> &ConvertPreview {
+        let is_valid = self.preview.mode == mode
+            && self.objects.ptr_eq(objects)
+            && self.timing.as_ref().is_some_and(|t| Arc::ptr_eq(t, timing))
+            && self.diff_settings.as_ref() == Some(diff_settings);
+        if !is_valid {
+            self.preview = convert_preview(objects, diff_settings, timing, mode);
+            self.objects = objects.clone();
+            self.timing = Some(timing.clone());
+            self.diff_settings = Some(diff_settings.clone());
+        }
+        &self.preview
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_harness::{EditHarness, TEST_MAP};
+
+    #[test]
+    fn converts_follow_taiko_and_mania_rules() {
+        let harness = EditHarness::new(TEST_MAP);
+        let map_state = harness.map_state();
+        map_state.export();
+        let mut cache = ConvertPreviewCache::new();
+        let mut convert = |mode| {
+            let preview = cache.get(
+                &map_state.objects,
+                &map_state.diff_settings,
+                &map_state.timing,
+                mode,
+            );
+            let kinds: Vec<u32> = preview.notes.iter().map(|note| note.kind as u32).collect();
+            (preview.key_count, kinds)
+        };
+
+        // The one beat slider becomes a kat and a don from its edge hitsounds.
+        let (_, taiko) = convert(ConvertMode::Taiko);
+        assert_eq!(taiko, vec![0, 1, 1, 0, 5, 0]);
+        // Two of five objects have a duration and OD is 5.
+        let (keys, mania) = convert(ConvertMode::Mania);
+        assert_eq!(keys, 6);
+        assert_eq!(mania, vec![9, 9, 10, 10, 9]);
+    }
+}
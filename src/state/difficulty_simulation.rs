@@ -0,0 +1,153 @@
+use std::sync::{Arc, OnceLock};
+
+use crate::{
+    map_format::diff_settings::{
+        DiffSettings, circle_radius_from_cs, circle_radius_to_cs, preempt_period_from_ar,
+        preempt_period_to_ar,
+    },
+    treap::Treap,
+};
+
+use super::{map_state::MapState, object::Object};
+
+// AR and CS the playfield is drawn at instead of the map's own, to see how it would look at
+// e.g. AR9.3 without touching the difficulty settings. Never saved or exported.
+#[derive(Clone, Copy, PartialEq, Default, Debug)]
+pub struct DifficultySimulation {
+    pub ar: Option<f64>,
+    pub cs: Option<f64>,
+}
+
+impl DifficultySimulation {
+    pub fn is_active(&self) -> bool {
+        self.ar.is_some() || self.cs.is_some()
+    }
+
+    // Moves AR, or CS with `cs`, by `step` from the simulated value or else the map's own,
+    // rounded to one decimal like osu! shows them.
+    pub fn nudged(&self, diff_settings: &DiffSettings, cs: bool, step: f64) -> Self {
+        let mut simulation = *self;
+        if cs {
+            let current = self
+                .cs
+                .unwrap_or_else(|| circle_radius_to_cs(diff_settings.circle_radius));
+            simulation.cs = Some(((current + step) * 10.0).round().clamp(0.0, 100.0) / 10.0);
+        } else {
+            let current = self
+                .ar
+                .unwrap_or_else(|| preempt_period_to_ar(diff_settings.preempt_period));
+            simulation.ar = Some(((current + step) * 10.0).round().clamp(0.0, 110.0) / 10.0);
+        }
+        simulation
+    }
+
+    // Stacking keeps the map's own leniency, the stack offsets are already in the objects.
+    pub fn apply(&self, diff_settings: &DiffSettings) -> DiffSettings {
+        let mut simulated = diff_settings.clone();
+        if let Some(ar) = self.ar {
+            simulated.preempt_period = preempt_period_from_ar(ar);
+        }
+        if let Some(cs) = self.cs {
+            simulated.circle_radius = circle_radius_from_cs(cs);
+        }
+        simulated
+    }
+
+    // For the hint line, only characters the HUD font has.
+    pub fn hint(&self, diff_settings: &DiffSettings) -> String {
+        let simulated = self.apply(diff_settings);
+        format!(
+            "view only ar {:.1} cs {:.1} | alt wheel ar - alt shift wheel cs - alt 0 resets",
+            preempt_period_to_ar(simulated.preempt_period),
+            circle_radius_to_cs(simulated.circle_radius)
+        )
+    }
+}
+
+// `map_state` drawn at the simulated AR and CS: the same hit objects with their instances,
+// which hold the radius, preempt and slider bodies, calculated again.
+pub fn simulated_map_state(map_state: &MapState, simulation: &DifficultySimulation) -> MapState {
+    let mut simulated = map_state.clone();
+    simulated.diff_settings = simulation.apply(&map_state.diff_settings);
+    let objects: Vec<Object> = map_state
+        .objects
+        .iter()
+        .map(|object| Object {
+            hit_object: Arc::clone(&object.hit_object),
+            instance: Arc::new(OnceLock::new()),
+        })
+        .collect();
+    simulated.objects = Treap::from_slice(objects.as_slice());
+    simulated.export();
+    simulated
+}
+
+pub struct SimulatedMapStateCache {
+    source: Option<Arc<MapState>>,
+    simulation: DifficultySimulation,
+    simulated: Option<Arc<MapState>>,
+}
+
+impl SimulatedMapStateCache {
+    pub fn new() -> Self {
+        SimulatedMapStateCache {
+            source: None,
+            simulation: DifficultySimulation::default(),
+            simulated: None,
+        }
+    }
+
+    pub fn get(
+        &mut self,
+        map_state: &Arc<MapState>,
+        simulation: &DifficultySimulation,
+    ) -> Arc<MapState> {
+        if !simulation.is_active() {
+            return Arc::clone(map_state);
+        }
+        let is_valid = self.simulation == *simulation
+            && self
+                .source
+                .as_ref()
+                .is_some_and(|source| Arc::ptr_eq(source, map_state));
+        if !is_valid {
+            self.simulated = Some(Arc::new(simulated_map_state(map_state, simulation)));
+            self.source = Some(Arc::clone(map_state));
+            self.simulation = *simulation;
+        }
+        Arc::clone(self.simulated.as_ref().unwrap())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_harness::{EditHarness, TEST_MAP};
+
+    #[test]
+    fn difficulty_simulation_redraws_objects_without_touching_the_map() {
+        let harness = EditHarness::new(TEST_MAP);
+        let map_state = harness.map_state();
+        map_state.export();
+        let own_diff_settings = map_state.diff_settings.clone();
+        let simulation = DifficultySimulation {
+            ar: Some(9.3),
+            cs: None,
+        }
+        .nudged(&own_diff_settings, true, 1.0);
+        let mut cache = SimulatedMapStateCache::new();
+        let simulated = cache.get(&map_state, &simulation);
+        assert!(Arc::ptr_eq(&simulated, &cache.get(&map_state, &simulation)));
+        let simulated_object = simulated.objects.get(0);
+        let instance = simulated_object.instance().unwrap();
+        assert!((instance.preempt - 555.0).abs() < 1e-6);
+        assert!((instance.radius - circle_radius_from_cs(5.0)).abs() < 1e-6);
+        assert!(own_diff_settings == harness.map_state().diff_settings);
+        let own_object = map_state.objects.get(0);
+        assert_eq!(own_object.instance().unwrap().preempt, own_diff_settings.preempt_period);
+        assert!(Arc::ptr_eq(
+            &cache.get(&map_state, &DifficultySimulation::default()),
+            &map_state
+        ));
+    }
+}
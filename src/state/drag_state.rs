@@ -2,6 +2,16 @@ use crate::geometry::vec2::Vec2;
 
 pub struct DragState {
     pub pos: Vec2,
+    // The cursor's offset from the grabbed point, which follows it at this distance.
+    pub grab_offset: Vec2,
     pub part_of_object: bool,
     pub is_rotation: bool,
 }
+
+// Onsets and triplet ticks near a selection dragged along the top timeline, drawn there as
+// markers. Empty while nothing is dragged.
+#[derive(Clone, Default)]
+pub struct TimelineDragMarks {
+    pub onsets_ms: Vec<f64>,
+    pub triplet_ticks_ms: Vec<f64>,
+}
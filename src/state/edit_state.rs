@@ -10,17 +10,29 @@ use std::{
 };
 
 use crate::{
+    audio::SongTimeMs,
     geometry::{vec2::Vec2, vec2_transform::Vec2Transform},
     layout::Layout,
-    map_format::slider_boxing::{BBox, BBox4},
-    render::{is_object_currently_visible, select_visible_objects_in_rect},
+    map_format::{
+        objects::HitObject,
+        slider_boxing::{BBox, BBox4},
+        timing::{GreenLineSettings, SampleSet, TimingPoint},
+    },
+    render::{FadeEnvelope, is_object_currently_visible, select_visible_objects_in_rect},
     state::history::{CheckPointInfo, History, UndoRedoInfo},
+    treap::Treap,
 };
 
 use super::{
-    drag_state::DragState, export_thread_state::ExportThreadState, hitsound_export::HitsoundExport,
-    hitsound_thread_config::HitsoundThreadConfig, map_state::MapState, selection::Selection,
-    snap_position::SnapPosition, snap_positions::SnapPositions,
+    drag_state::{DragState, TimelineDragMarks},
+    export_thread_state::ExportThreadState,
+    hitsound_export::HitsoundExport,
+    hitsound_thread_config::HitsoundThreadConfig,
+    map_state::MapState,
+    operation_log::OperationLog,
+    selection::Selection,
+    snap_position::SnapPosition,
+    snap_positions::{SnapPositions, SnapSources},
 };
 
 pub struct EditState {
@@ -33,9 +45,15 @@ pub struct EditState {
     hitsound_needs_recalc: bool,
     hitsound_request_tx: SyncSender<()>,
     hitsound_thread_stop: Arc<AtomicBool>,
+    // Sample set every hitsound plays on while auditioning, None for the map's own.
+    forced_sample_set: Option<SampleSet>,
     pub left_selection: Option<Selection>,
     pub right_selection: Option<Selection>,
     pub snap_positions: Arc<SnapPositions>,
+    pub timeline_drag_marks: TimelineDragMarks,
+    pub fade_envelope: FadeEnvelope,
+    pub snap_sources: SnapSources,
+    pub operation_log: OperationLog,
 }
 
 impl EditState {
@@ -48,9 +66,26 @@ impl EditState {
     }
 
     pub fn rename_current_state(&mut self, display_name: String) {
+        self.operation_log
+            .record(format!("rename state to \"{}\"", display_name), false);
         self.history.name_current_state(display_name);
     }
 
+    pub fn forced_sample_set(&self) -> Option<SampleSet> {
+        self.forced_sample_set.clone()
+    }
+
+    // Reschedules every hitsound on the forced sample set, or back on the map's own.
+    pub fn set_forced_sample_set(&mut self, sample_set: Option<SampleSet>) {
+        self.forced_sample_set = sample_set;
+        self.hitsound_needs_recalc = true;
+        let _ = self.hitsound_request_tx.try_send(());
+    }
+
+    pub fn current_map_state(&self) -> Arc<MapState> {
+        self.history.get_current_state()
+    }
+
     pub fn get_latest_export(&self) -> Arc<MapState> {
         return Arc::clone(&self.export_thread_state.latest_export.read().unwrap());
     }
@@ -58,6 +93,20 @@ impl EditState {
     pub fn new(
         map_state: MapState,
         hitsound_thread_config: HitsoundThreadConfig,
+    ) -> Arc<RwLock<EditState>> {
+        Self::with_hitsound_thread(map_state, Some(hitsound_thread_config))
+    }
+
+    // Without an audio engine there is nothing to schedule hitsounds on, so only the export
+    // thread runs. Hitsound requests go to a closed channel and are dropped.
+    #[cfg(test)]
+    pub fn new_headless(map_state: MapState) -> Arc<RwLock<EditState>> {
+        Self::with_hitsound_thread(map_state, None)
+    }
+
+    fn with_hitsound_thread(
+        map_state: MapState,
+        hitsound_thread_config: Option<HitsoundThreadConfig>,
     ) -> Arc<RwLock<EditState>> {
         let (tx, rx) = mpsc::sync_channel::<()>(1);
         let (hitsound_tx, hitsound_rx) = mpsc::sync_channel::<()>(1);
@@ -75,14 +124,25 @@ impl EditState {
             hitsound_needs_recalc: true,
             hitsound_request_tx: hitsound_tx,
             hitsound_thread_stop: Arc::new(AtomicBool::new(false)),
+            forced_sample_set: None,
             left_selection: None,
             right_selection: None,
             snap_positions: Arc::new(SnapPositions::new()),
+            timeline_drag_marks: TimelineDragMarks::default(),
+            fade_envelope: FadeEnvelope::default(),
+            snap_sources: SnapSources::default(),
+            operation_log: OperationLog::new(),
         };
 
         let state = Arc::new(RwLock::new(state));
         EditState::spawn_export_thread(Arc::clone(&state), rx);
-        EditState::spawn_hitsound_thread(Arc::clone(&state), hitsound_rx, hitsound_thread_config);
+        if let Some(hitsound_thread_config) = hitsound_thread_config {
+            EditState::spawn_hitsound_thread(
+                Arc::clone(&state),
+                hitsound_rx,
+                hitsound_thread_config,
+            );
+        }
         {
             let state_guard = state.read().unwrap();
             let _ = state_guard.hitsound_request_tx.try_send(());
@@ -173,12 +233,18 @@ impl EditState {
                         edit_state_guard.hitsound_needs_recalc = false;
                     }
 
-                    let current_state = {
+                    let (current_state, forced_sample_set) = {
                         let edit_state_guard = edit_state.read().unwrap();
-                        edit_state_guard.history.get_current_state()
+                        (
+                            edit_state_guard.history.get_current_state(),
+                            edit_state_guard.forced_sample_set.clone(),
+                        )
                     };
 
-                    let hitsound_export = HitsoundExport::from_map_state(&current_state);
+                    let mut hitsound_export = HitsoundExport::from_map_state(&current_state);
+                    if let Some(sample_set) = forced_sample_set.as_ref() {
+                        hitsound_export.force_sample_set(sample_set);
+                    }
 
                     let mut prev_counts: HashMap<(u64, usize, u64, u64), usize> = HashMap::new();
                     for (map_time_ms, position_x, hitsound_info) in prev_hitsounds.hitsounds.iter()
@@ -287,7 +353,7 @@ impl EditState {
                         match action {
                             HitsoundActionKind::Remove => {
                                 hitsound_thread_config.audio.remove_hitsound(
-                                    map_time_ms,
+                                    SongTimeMs(map_time_ms),
                                     index,
                                     volume,
                                     x,
@@ -295,7 +361,7 @@ impl EditState {
                             }
                             HitsoundActionKind::Add => {
                                 hitsound_thread_config.audio.add_hitsound(
-                                    map_time_ms,
+                                    SongTimeMs(map_time_ms),
                                     index,
                                     volume,
                                     x,
@@ -317,6 +383,7 @@ impl EditState {
         overlay_rect_left: Option<[f32; 4]>,
         overlay_rect_right: Option<[f32; 4]>,
         cursor_pos_screen: [f32; 2],
+        snap_distance_px: f64,
         left_rect_dragged: bool,
         right_rect_dragged: bool,
         _left_origin_dragged: bool,
@@ -349,19 +416,30 @@ impl EditState {
         let stack_near_distance = circle_radius * 2.0;
         let stack_near_distance_2 = stack_near_distance * stack_near_distance;
 
-        let cursor_playfield = {
-            let w = layout.playfield_rect.x1 - layout.playfield_rect.x0;
-            let h = layout.playfield_rect.y1 - layout.playfield_rect.y0;
-            if w.abs() > 1e-6 && h.abs() > 1e-6 {
-                Some(Vec2 {
-                    x: (cursor_pos_screen[0] as f64 - layout.playfield_rect.x0) * (512.0 / w),
-                    y: (cursor_pos_screen[1] as f64 - layout.playfield_rect.y0) * (384.0 / h),
-                })
-            } else {
-                None
-            }
+        let w = layout.playfield_rect.x1 - layout.playfield_rect.x0;
+        let h = layout.playfield_rect.y1 - layout.playfield_rect.y0;
+        let cursor_playfield = if w.abs() > 1e-6 && h.abs() > 1e-6 {
+            Some(Vec2 {
+                x: (cursor_pos_screen[0] as f64 - layout.playfield_rect.x0) * (512.0 / w),
+                y: (cursor_pos_screen[1] as f64 - layout.playfield_rect.y0) * (384.0 / h),
+            })
+        } else {
+            None
         };
 
+        if let Some(cursor_pf) = cursor_playfield {
+            let screen_scale = Vec2 {
+                x: w / 512.0,
+                y: h / 384.0,
+            };
+            if left_rect_dragged {
+                self.apply_predicted_drag(true, cursor_pf, screen_scale, snap_distance_px);
+            }
+            if right_rect_dragged {
+                self.apply_predicted_drag(false, cursor_pf, screen_scale, snap_distance_px);
+            }
+        }
+
         self.snap_positions = {
             let left_sel_set = match self.left_selection {
                 Some(ref left_selection) => left_selection.objects.iter().copied().collect(),
@@ -372,17 +450,65 @@ impl EditState {
                 None => HashSet::new(),
             };
 
+            let sources = self.snap_sources;
             let mut snap_positions = SnapPositions::new();
             for (index, obj) in active_export.objects.iter().enumerate() {
                 let instance = obj.instance().unwrap();
                 let from_left = left_sel_set.contains(&index);
                 let from_right = right_sel_set.contains(&index);
+                let selected = from_left || from_right;
+
+                if selected || is_object_currently_visible(instance, time_ms, &self.fade_envelope) {
+                    let mut object_points: Vec<Vec2> = Vec::new();
+                    if instance.is_slider {
+                        for (i, snap_pos) in instance.snap_points.iter().enumerate() {
+                            let wanted = if i == 0 {
+                                sources.slider_ends
+                            } else {
+                                sources.slider_control_points
+                            };
+                            if selected || wanted {
+                                object_points.push(*snap_pos);
+                            }
+                        }
+                        if (selected || sources.slider_ends) && instance.slider_path.is_some() {
+                            let tail = instance.end_pos();
+                            if !object_points.iter().any(|p| p.distance2(tail) < 1e-6) {
+                                object_points.push(tail);
+                            }
+                        }
+                    } else if selected || sources.object_centers {
+                        object_points.extend(instance.snap_points.iter().copied());
+                    }
 
-                if (!from_left && !from_right && is_object_currently_visible(instance, time_ms))
-                    || from_left
-                    || from_right
-                {
-                    for snap_pos in instance.snap_points.iter() {
+                    if !selected && sources.mirrored_positions && !instance.is_spinner {
+                        for mirrored in [
+                            Vec2 {
+                                x: 512.0 - instance.pos.x,
+                                y: instance.pos.y,
+                            },
+                            Vec2 {
+                                x: instance.pos.x,
+                                y: 384.0 - instance.pos.y,
+                            },
+                            Vec2 {
+                                x: 512.0 - instance.pos.x,
+                                y: 384.0 - instance.pos.y,
+                            },
+                        ] {
+                            snap_positions.positions.push(SnapPosition {
+                                pos: mirrored,
+                                virtual_stack: false,
+                                part_of_object: false,
+                                from_left_sel_and_movable: false,
+                                from_right_sel_and_movable: false,
+                                is_left_origin: false,
+                                is_right_origin: false,
+                            });
+                        }
+                    }
+
+                    for snap_pos in object_points.iter() {
                         snap_positions.positions.push(SnapPosition {
                             pos: *snap_pos,
                             virtual_stack: false,
@@ -485,6 +611,7 @@ impl EditState {
                     active_export.objects.iter(),
                     &layout.playfield_rect,
                     time_ms,
+                    &self.fade_envelope,
                     Self::selection_objects(&self.right_selection),
                     &[],
                 )
@@ -496,6 +623,7 @@ impl EditState {
                     active_export.objects.iter(),
                     &layout.playfield_rect,
                     time_ms,
+                    &self.fade_envelope,
                     Self::selection_objects(&self.left_selection),
                     match self.right_selection {
                         Some(ref right_selection) => right_selection.objects.as_slice(),
@@ -596,6 +724,100 @@ impl EditState {
         }
     }
 
+    pub fn selection_drag_pos(&self, left: bool) -> Option<Vec2> {
+        let selection = if left {
+            &self.left_selection
+        } else {
+            &self.right_selection
+        };
+        selection
+            .as_ref()
+            .and_then(|s| s.drag_state.as_ref().map(|d| d.pos))
+    }
+
+    // Where a selection dragged to `unsnapped` lands: the closest snap position within
+    // `snap_distance_px` on screen, leaving out the selection's own positions and, unless
+    // its origin is locked, its origin.
+    pub fn snap_dragged_selection(
+        &self,
+        left: bool,
+        unsnapped: Vec2,
+        part_of_object: bool,
+        screen_scale: Vec2,
+        snap_distance_px: f64,
+    ) -> Vec2 {
+        let selection = if left {
+            &self.left_selection
+        } else {
+            &self.right_selection
+        };
+        let origin_locked = selection.as_ref().map(|s| s.origin_locked).unwrap_or(false);
+        let snap_distance2 = snap_distance_px.max(0.0).powi(2);
+        let mut best: Option<(f64, Vec2)> = None;
+        for snap in self.snap_positions.positions.iter() {
+            if snap.virtual_stack && !part_of_object {
+                continue;
+            }
+            let (from_same_side_selection, from_same_side_origin) = if left {
+                (snap.from_left_sel_and_movable, snap.is_left_origin)
+            } else {
+                (snap.from_right_sel_and_movable, snap.is_right_origin)
+            };
+            if from_same_side_selection || (from_same_side_origin && !origin_locked) {
+                continue;
+            }
+            let offset_screen = Vec2 {
+                x: (snap.pos.x - unsnapped.x) * screen_scale.x,
+                y: (snap.pos.y - unsnapped.y) * screen_scale.y,
+            };
+            let d2 = offset_screen.len2();
+            if d2 > snap_distance2 {
+                continue;
+            }
+            match best {
+                Some((best_d2, _)) if d2 >= best_d2 => {}
+                _ => best = Some((d2, snap.pos)),
+            }
+        }
+        best.map(|(_, pos)| pos).unwrap_or(unsnapped)
+    }
+
+    // Moves a translating selection to where the drag hitbox will put it for the latest
+    // cursor position, snapped the same way, so the frame being built does not lag behind.
+    // The step stays pending in the history like the hitbox's own.
+    fn apply_predicted_drag(
+        &mut self,
+        left: bool,
+        cursor_playfield: Vec2,
+        screen_scale: Vec2,
+        snap_distance_px: f64,
+    ) {
+        let selection = if left {
+            &self.left_selection
+        } else {
+            &self.right_selection
+        };
+        let (pos, grab_offset, part_of_object) =
+            match selection.as_ref().and_then(|s| s.drag_state.as_ref()) {
+                Some(drag_state) if !drag_state.is_rotation => {
+                    (drag_state.pos, drag_state.grab_offset, drag_state.part_of_object)
+                }
+                _ => return,
+            };
+        let target = self.snap_dragged_selection(
+            left,
+            cursor_playfield - grab_offset,
+            part_of_object,
+            screen_scale,
+            snap_distance_px,
+        );
+        let delta = target - pos;
+        if delta.x == 0.0 && delta.y == 0.0 {
+            return;
+        }
+        self.drag_selection(left, delta);
+    }
+
     pub fn toggle_selection_origin_lock(&mut self, left: bool) {
         if left {
             if let Some(selection) = self.left_selection.as_mut() {
@@ -636,6 +858,33 @@ impl EditState {
         transform: Vec2Transform,
         left_selection: bool,
         checkpoint: bool,
+    ) {
+        let checkpoint = if checkpoint {
+            CheckPointInfo::CheckPoint
+        } else {
+            CheckPointInfo::CheckPointAfter(time::Duration::from_millis(50))
+        };
+        self.transform_selection(transform, left_selection, checkpoint);
+    }
+
+    // Moves a selection being dragged. Each step replaces the last one in the history until
+    // `commit_drag` keeps the result when the button is released.
+    pub fn drag_selection(&mut self, left: bool, vec: Vec2) {
+        let transform = Vec2Transform::translate(vec);
+        self.transform_selection(transform, left, CheckPointInfo::Pending);
+    }
+
+    pub fn commit_drag(&mut self) {
+        if self.history.commit_pending() {
+            self.operation_log.end_continuing();
+        }
+    }
+
+    fn transform_selection(
+        &mut self,
+        transform: Vec2Transform,
+        left_selection: bool,
+        checkpoint: CheckPointInfo,
     ) {
         let current_map_state = self.history.get_current_state().clone();
         let selection = if left_selection {
@@ -644,11 +893,15 @@ impl EditState {
             Self::selection_objects(&self.right_selection).to_vec()
         };
         let new_map_state = current_map_state.transform_objects(transform, selection.as_slice());
-        let checkpoint = if checkpoint {
-            CheckPointInfo::CheckPoint
-        } else {
-            CheckPointInfo::CheckPointAfter(time::Duration::from_millis(50))
-        };
+        let side = if left_selection { "left" } else { "right" };
+        self.operation_log.record(
+            format!(
+                "transform {} selection of {} object(s)",
+                side,
+                selection.len()
+            ),
+            !matches!(checkpoint, CheckPointInfo::CheckPoint),
+        );
         self.history.append(Arc::new(new_map_state), checkpoint);
         if left_selection {
             if let Some(selection) = self.left_selection.as_mut() {
@@ -665,8 +918,577 @@ impl EditState {
         let _ = self.hitsound_request_tx.try_send(());
     }
 
+    pub fn split_slider_at_time(&mut self, time: SongTimeMs) {
+        let time_ms = time.0;
+        let current_map_state = self.history.get_current_state();
+        let candidates = Self::selection_objects(&self.left_selection).to_vec();
+        let Some((new_map_state, _)) =
+            current_map_state.split_slider_at_time(time_ms, candidates.as_slice())
+        else {
+            println!("No slider to split at {:.0}ms.", time_ms);
+            return;
+        };
+        self.operation_log
+            .record(format!("split slider at {:.0}ms", time_ms), false);
+        self.history.append(Arc::new(new_map_state), CheckPointInfo::CheckPoint);
+        // Object indices after the split point have shifted.
+        self.left_selection = None;
+        self.right_selection = None;
+        self.export_needs_recalc = true;
+        self.hitsound_needs_recalc = true;
+        let _ = self.export_request_tx.try_send(());
+        let _ = self.hitsound_request_tx.try_send(());
+    }
+
+    // Inserts new objects and makes them the left selection.
+    pub fn insert_objects(&mut self, hit_objects: Vec<HitObject>) {
+        if hit_objects.is_empty() {
+            return;
+        }
+        self.operation_log
+            .record(format!("insert {} object(s)", hit_objects.len()), false);
+        let current_map_state = self.history.get_current_state();
+        let (new_map_state, inserted) = current_map_state.insert_objects(hit_objects);
+        new_map_state.export();
+        self.left_selection = Self::selection_from_objects(&new_map_state, inserted);
+        // Indices after the inserted objects have shifted.
+        self.right_selection = None;
+        self.history.append(Arc::new(new_map_state), CheckPointInfo::CheckPoint);
+        self.export_needs_recalc = true;
+        self.hitsound_needs_recalc = true;
+        let _ = self.export_request_tx.try_send(());
+        let _ = self.hitsound_request_tx.try_send(());
+    }
+
+    pub fn add_break(&mut self, start_ms: f64, end_ms: f64) {
+        self.operation_log.record(
+            format!("add break {:.0}ms - {:.0}ms", start_ms, end_ms),
+            false,
+        );
+        let new_map_state = self.history.get_current_state().with_break(start_ms, end_ms);
+        self.history.append(Arc::new(new_map_state), CheckPointInfo::CheckPoint);
+        self.export_needs_recalc = true;
+        let _ = self.export_request_tx.try_send(());
+    }
+
+    // Adds imported objects to the map, or replaces all objects with them.
+    pub fn import_objects(&mut self, hit_objects: Vec<HitObject>, replace: bool) {
+        if !replace {
+            self.insert_objects(hit_objects);
+            return;
+        }
+        self.operation_log.record(
+            format!("replace all objects with {} imported", hit_objects.len()),
+            false,
+        );
+        let mut cleared = (*self.history.get_current_state()).clone();
+        cleared.objects = Treap::new_empty();
+        let (new_map_state, inserted) = cleared.insert_objects(hit_objects);
+        new_map_state.export();
+        self.left_selection = Self::selection_from_objects(&new_map_state, inserted);
+        self.right_selection = None;
+        self.history.append(Arc::new(new_map_state), CheckPointInfo::CheckPoint);
+        self.export_needs_recalc = true;
+        self.hitsound_needs_recalc = true;
+        let _ = self.export_request_tx.try_send(());
+        let _ = self.hitsound_request_tx.try_send(());
+    }
+
+    // Swaps the timing of the current state for `timing_points` as a single undo step.
+    pub fn replace_timing(&mut self, timing_points: Vec<TimingPoint>) {
+        self.operation_log.record(
+            format!("replace timing with {} point(s)", timing_points.len()),
+            false,
+        );
+        let new_map_state = self.history.get_current_state().with_timing(timing_points);
+        new_map_state.export();
+        self.history.append(Arc::new(new_map_state), CheckPointInfo::CheckPoint);
+        self.export_needs_recalc = true;
+        self.hitsound_needs_recalc = true;
+        let _ = self.export_request_tx.try_send(());
+        let _ = self.hitsound_request_tx.try_send(());
+    }
+
+    // A green line at the start of every object in either selection, as one undo step.
+    // Returns how many were placed.
+    pub fn insert_green_lines_at_selection(&mut self, settings: &GreenLineSettings) -> usize {
+        let mut times: Vec<f64> = self
+            .hit_objects_in_selections()
+            .iter()
+            .map(|hit_object| hit_object.time())
+            .collect();
+        times.dedup();
+        let mut new_map_state = (*self.history.get_current_state()).clone();
+        let mut placed = 0;
+        for time in times {
+            if let Some(map_state) = new_map_state.with_green_line_at(time, settings) {
+                new_map_state = map_state;
+                placed += 1;
+            }
+        }
+        if placed == 0 {
+            return 0;
+        }
+        self.operation_log.record(
+            format!("insert {} green line(s) at the selection", placed),
+            false,
+        );
+        new_map_state.export();
+        self.history
+            .append(Arc::new(new_map_state), CheckPointInfo::CheckPoint);
+        self.export_needs_recalc = true;
+        self.hitsound_needs_recalc = true;
+        let _ = self.export_request_tx.try_send(());
+        let _ = self.hitsound_request_tx.try_send(());
+        placed
+    }
+
+    // Makes the slider `id` last `duration_ms` at its current length with a green line on its
+    // head. The old velocity comes back at the new end when more objects follow before the
+    // next timing point.
+    pub fn fit_slider_duration_with_sv(&mut self, id: usize, duration_ms: f64) -> bool {
+        let current_map_state = self.history.get_current_state();
+        if id >= current_map_state.objects.len() || duration_ms <= 0.0 {
+            return false;
+        }
+        let HitObject::Slider(slider) = &*current_map_state.objects.get(id).hit_object else {
+            return false;
+        };
+        let old_duration_ms = slider.end_time() - slider.time;
+        let Some(previous) = current_map_state.timing.inherited_at(slider.time) else {
+            return false;
+        };
+        if old_duration_ms <= 0.0 {
+            return false;
+        }
+        let settings = GreenLineSettings {
+            sv_multiplier: Some(previous.sv_multiplier * old_duration_ms / duration_ms),
+            volume: None,
+            sample_set: None,
+        };
+        let Some(mut new_map_state) = current_map_state.with_green_line_at(slider.time, &settings)
+        else {
+            return false;
+        };
+        let end_ms = slider.time + duration_ms;
+        let next_point_ms = current_map_state
+            .timing
+            .timing_points
+            .iter()
+            .map(|tp| tp.time())
+            .find(|t| *t > slider.time)
+            .unwrap_or(f64::INFINITY);
+        let objects_follow = current_map_state.objects.iter().any(|object| {
+            let time = object.hit_object.time();
+            time >= end_ms && time < next_point_ms
+        });
+        if objects_follow {
+            let restore = GreenLineSettings {
+                sv_multiplier: Some(previous.sv_multiplier),
+                volume: None,
+                sample_set: None,
+            };
+            if let Some(map_state) = new_map_state.with_green_line_at(end_ms, &restore) {
+                new_map_state = map_state;
+            }
+        }
+        self.operation_log.record(
+            format!(
+                "fit slider at {:.0}ms to {:.0}ms with sv",
+                slider.time, duration_ms
+            ),
+            false,
+        );
+        new_map_state.export();
+        self.history
+            .append(Arc::new(new_map_state), CheckPointInfo::CheckPoint);
+        self.export_needs_recalc = true;
+        self.hitsound_needs_recalc = true;
+        let _ = self.export_request_tx.try_send(());
+        let _ = self.hitsound_request_tx.try_send(());
+        true
+    }
+
+    // Starts over on another difficulty with a fresh history, so undo can't step back into the
+    // one that was open before.
+    pub fn load_map_state(&mut self, map_state: MapState) {
+        self.operation_log
+            .record("open another difficulty".to_string(), false);
+        map_state.export();
+        self.left_selection = None;
+        self.right_selection = None;
+        self.history = History::new(Arc::new(map_state));
+        self.export_needs_recalc = true;
+        self.hitsound_needs_recalc = true;
+        let _ = self.export_request_tx.try_send(());
+        let _ = self.hitsound_request_tx.try_send(());
+    }
+
+    // Swaps in a whole new map state, e.g. the difficulty reloaded from disk, as a single undo
+    // step. Selections are dropped since object indices no longer line up.
+    pub fn replace_map_state(&mut self, map_state: MapState) {
+        self.operation_log
+            .record("replace the whole map".to_string(), false);
+        map_state.export();
+        self.left_selection = None;
+        self.right_selection = None;
+        self.history
+            .append(Arc::new(map_state), CheckPointInfo::CheckPoint);
+        self.export_needs_recalc = true;
+        self.hitsound_needs_recalc = true;
+        let _ = self.export_request_tx.try_send(());
+        let _ = self.hitsound_request_tx.try_send(());
+    }
+
+    // Sets the custom sample file of the left selection. Returns how many objects changed.
+    pub fn set_selection_sample_filename(&mut self, filename: Option<String>) -> usize {
+        let ids = Self::selection_objects(&self.left_selection).to_vec();
+        if ids.is_empty() {
+            return 0;
+        }
+        let new_map_state = self
+            .history
+            .get_current_state()
+            .with_sample_filename(&ids, filename.clone());
+        self.operation_log.record(
+            format!(
+                "set sample file of {} object(s) to {}",
+                ids.len(),
+                filename.as_deref().unwrap_or("none")
+            ),
+            false,
+        );
+        new_map_state.export();
+        self.history
+            .append(Arc::new(new_map_state), CheckPointInfo::CheckPoint);
+        self.export_needs_recalc = true;
+        self.hitsound_needs_recalc = true;
+        let _ = self.export_request_tx.try_send(());
+        let _ = self.hitsound_request_tx.try_send(());
+        ids.len()
+    }
+
+    // See MapState::with_new_combos. Returns how many objects changed.
+    pub fn set_new_combos(&mut self, new_combos: &[bool]) -> usize {
+        let (new_map_state, changed) = self
+            .history
+            .get_current_state()
+            .with_new_combos(new_combos);
+        if changed == 0 {
+            return 0;
+        }
+        self.operation_log.record(
+            format!("set new combos on {} object(s)", changed),
+            false,
+        );
+        new_map_state.export();
+        self.history
+            .append(Arc::new(new_map_state), CheckPointInfo::CheckPoint);
+        self.export_needs_recalc = true;
+        let _ = self.export_request_tx.try_send(());
+        changed
+    }
+
+    // Applies the rhythm of `source` starting at `time` to the left selection, in time
+    // order. Without a selection, placeholders are created at the playfield center instead.
+    pub fn paste_rhythm(&mut self, source: &[HitObject], time: SongTimeMs) {
+        let time_ms = time.0;
+        let Some(source_start) = source.iter().map(|o| o.time()).min_by(|a, b| a.total_cmp(b))
+        else {
+            println!("Nothing copied to paste the rhythm of.");
+            return;
+        };
+        let mut offsets: Vec<f64> = source.iter().map(|o| o.time() - source_start).collect();
+        offsets.sort_by(|a, b| a.total_cmp(b));
+
+        let current_map_state = self.history.get_current_state().clone();
+        let mut selected = Self::selection_objects(&self.left_selection).to_vec();
+        if selected.is_empty() {
+            let center = Vec2 { x: 256.0, y: 192.0 };
+            let mut placeholders: Vec<HitObject> = source
+                .iter()
+                .filter_map(|object| object.placeholder_at(center))
+                .collect();
+            for placeholder in placeholders.iter_mut() {
+                let time = time_ms + placeholder.time() - source_start;
+                placeholder.retime(time, 1.0);
+            }
+            println!("Pasted rhythm as {} placeholder object(s).", placeholders.len());
+            self.insert_objects(placeholders);
+            return;
+        }
+
+        selected.sort_by(|a, b| {
+            let a = current_map_state.objects.get(*a).hit_object.time();
+            let b = current_map_state.objects.get(*b).hit_object.time();
+            a.total_cmp(&b)
+        });
+        if selected.len() != offsets.len() {
+            println!(
+                "Copied rhythm has {} object(s), selection has {}; applying to the first {}.",
+                offsets.len(),
+                selected.len(),
+                selected.len().min(offsets.len())
+            );
+        }
+        let times: Vec<(usize, f64)> = selected
+            .iter()
+            .zip(offsets.iter())
+            .map(|(id, offset)| (*id, time_ms + offset))
+            .collect();
+        let (new_map_state, moved) = current_map_state.retime_objects(&times);
+        self.operation_log.record(
+            format!(
+                "paste rhythm onto {} object(s) at {:.0}ms",
+                times.len(),
+                time_ms
+            ),
+            false,
+        );
+        new_map_state.export();
+        self.left_selection = Self::selection_from_objects(&new_map_state, moved);
+        self.right_selection = None;
+        self.history.append(Arc::new(new_map_state), CheckPointInfo::CheckPoint);
+        self.export_needs_recalc = true;
+        self.hitsound_needs_recalc = true;
+        let _ = self.export_request_tx.try_send(());
+        let _ = self.hitsound_request_tx.try_send(());
+    }
+
+    // Moves every unsnapped object to its closest snap tick and selects the moved objects.
+    pub fn resnap_unsnapped_objects(&mut self) -> usize {
+        let current_map_state = self.history.get_current_state().clone();
+        let times = current_map_state.unsnapped_objects();
+        if times.is_empty() {
+            return 0;
+        }
+        let (new_map_state, moved) = current_map_state.retime_objects(&times);
+        self.operation_log.record(
+            format!("resnap {} unsnapped object(s)", times.len()),
+            false,
+        );
+        new_map_state.export();
+        self.left_selection = Self::selection_from_objects(&new_map_state, moved);
+        self.right_selection = None;
+        self.history.append(Arc::new(new_map_state), CheckPointInfo::CheckPoint);
+        self.export_needs_recalc = true;
+        self.hitsound_needs_recalc = true;
+        let _ = self.export_request_tx.try_send(());
+        let _ = self.hitsound_request_tx.try_send(());
+        times.len()
+    }
+
+    // Moves the left selection in time so that its first object starts at `time`. The step
+    // stays pending in the history until `commit_drag`, like playfield drags.
+    pub fn drag_selection_to_time(&mut self, time: SongTimeMs) -> bool {
+        let time_ms = time.0;
+        let current_map_state = self.history.get_current_state().clone();
+        let selected = Self::selection_objects(&self.left_selection);
+        let Some(start_ms) = selected
+            .iter()
+            .map(|id| current_map_state.objects.get(*id).hit_object.time())
+            .min_by(|a, b| a.total_cmp(b))
+        else {
+            return false;
+        };
+        let delta_ms = time_ms - start_ms;
+        if delta_ms.abs() < 1e-6 {
+            return false;
+        }
+        let times: Vec<(usize, f64)> = selected
+            .iter()
+            .map(|id| (*id, current_map_state.objects.get(*id).hit_object.time() + delta_ms))
+            .collect();
+        let (new_map_state, moved) = current_map_state.retime_objects(&times);
+        self.operation_log.record(
+            format!(
+                "move {} object(s) from {:.0}ms to {:.0}ms",
+                times.len(),
+                start_ms,
+                time_ms
+            ),
+            true,
+        );
+        new_map_state.export();
+        self.left_selection = Self::selection_from_objects(&new_map_state, moved);
+        self.right_selection = None;
+        self.history.append(Arc::new(new_map_state), CheckPointInfo::Pending);
+        self.export_needs_recalc = true;
+        self.hitsound_needs_recalc = true;
+        let _ = self.export_request_tx.try_send(());
+        let _ = self.hitsound_request_tx.try_send(());
+        true
+    }
+
+    // The earliest object of the left selection, which the inspector shows and edits.
+    pub fn inspected_object(&self) -> Option<(usize, HitObject)> {
+        let current_map_state = self.history.get_current_state();
+        Self::selection_objects(&self.left_selection)
+            .iter()
+            .map(|id| (*id, (*current_map_state.objects.get(*id).hit_object).clone()))
+            .min_by(|a, b| a.1.time().total_cmp(&b.1.time()))
+    }
+
+    pub fn replace_object(&mut self, id: usize, hit_object: HitObject) {
+        let current_map_state = self.history.get_current_state();
+        if id >= current_map_state.objects.len() {
+            return;
+        }
+        self.operation_log
+            .record(format!("edit object at {:.0}ms", hit_object.time()), false);
+        let (new_map_state, new_id) = current_map_state.replace_object(id, hit_object);
+        new_map_state.export();
+        let mut selected: Vec<usize> = Self::selection_objects(&self.left_selection)
+            .iter()
+            .filter(|other_id| **other_id != id)
+            .map(|other_id| Self::index_after_replace(*other_id, id, new_id))
+            .collect();
+        selected.push(new_id);
+        self.left_selection = Self::selection_from_objects(&new_map_state, selected);
+        self.right_selection = None;
+        self.history.append(Arc::new(new_map_state), CheckPointInfo::CheckPoint);
+        self.export_needs_recalc = true;
+        self.hitsound_needs_recalc = true;
+        let _ = self.export_request_tx.try_send(());
+        let _ = self.hitsound_request_tx.try_send(());
+    }
+
+    // Indices between the old and new position of a replaced object shift by one.
+    fn index_after_replace(other_id: usize, id: usize, new_id: usize) -> usize {
+        if other_id == id {
+            return new_id;
+        }
+        let shifted = if other_id > id {
+            other_id - 1
+        } else {
+            other_id
+        };
+        if shifted >= new_id {
+            shifted + 1
+        } else {
+            shifted
+        }
+    }
+
+    // Moves one anchor of a slider, the first one moves its head. Returns the slider's index
+    // afterwards, it can change when the slider shares its time with other objects.
+    pub fn move_slider_anchor(
+        &mut self,
+        id: usize,
+        anchor: usize,
+        pos: Vec2,
+        checkpoint: bool,
+    ) -> Option<usize> {
+        let current_map_state = self.history.get_current_state();
+        if id >= current_map_state.objects.len() {
+            return None;
+        }
+        let object = current_map_state.objects.get(id);
+        let HitObject::Slider(slider) = &*object.hit_object else {
+            return None;
+        };
+        let mut slider = slider.clone();
+        slider.control_points = slider.control_points.with_anchor_moved(anchor, pos)?;
+        self.operation_log.record(
+            format!("move anchor {} of slider at {:.0}ms", anchor, slider.time),
+            !checkpoint,
+        );
+        let (new_map_state, new_id) =
+            current_map_state.replace_object(id, HitObject::Slider(slider));
+        new_map_state.export();
+        let remap = |selection: &Option<Selection>| -> Vec<usize> {
+            Self::selection_objects(selection)
+                .iter()
+                .map(|other_id| Self::index_after_replace(*other_id, id, new_id))
+                .collect()
+        };
+        let (left, right) = (remap(&self.left_selection), remap(&self.right_selection));
+        self.left_selection = Self::selection_from_objects(&new_map_state, left);
+        self.right_selection = Self::selection_from_objects(&new_map_state, right);
+        let checkpoint = if checkpoint {
+            CheckPointInfo::CheckPoint
+        } else {
+            CheckPointInfo::CheckPointAfter(time::Duration::from_millis(50))
+        };
+        self.history.append(Arc::new(new_map_state), checkpoint);
+        self.export_needs_recalc = true;
+        self.hitsound_needs_recalc = true;
+        let _ = self.export_request_tx.try_send(());
+        let _ = self.hitsound_request_tx.try_send(());
+        Some(new_id)
+    }
+
+    pub fn selected_hit_objects(&self, left: bool) -> Vec<HitObject> {
+        let selection = if left {
+            &self.left_selection
+        } else {
+            &self.right_selection
+        };
+        let current_map_state = self.history.get_current_state();
+        Self::selection_objects(selection)
+            .iter()
+            .map(|idx| (*current_map_state.objects.get(*idx).hit_object).clone())
+            .collect()
+    }
+
+    // Objects in either selection, in map order.
+    pub fn hit_objects_in_selections(&self) -> Vec<HitObject> {
+        let mut ids: Vec<usize> = Self::selection_objects(&self.left_selection)
+            .iter()
+            .chain(Self::selection_objects(&self.right_selection))
+            .copied()
+            .collect();
+        ids.sort_unstable();
+        ids.dedup();
+        let current_map_state = self.history.get_current_state();
+        ids.iter()
+            .map(|idx| (*current_map_state.objects.get(*idx).hit_object).clone())
+            .collect()
+    }
+
+    // Objects starting between `start_ms` and `end_ms`, in map order.
+    pub fn hit_objects_between(&self, start_ms: f64, end_ms: f64) -> Vec<HitObject> {
+        self.history
+            .get_current_state()
+            .objects
+            .iter()
+            .map(|object| object.hit_object.as_ref())
+            .filter(|hit_object| (start_ms..=end_ms).contains(&hit_object.time()))
+            .cloned()
+            .collect()
+    }
+
+    // From the first selected object's start to the last one's end, over both selections.
+    pub fn selection_time_range(&self) -> Option<(f64, f64)> {
+        Self::objects_time_range(&self.hit_objects_in_selections())
+    }
+
+    // Same as `selection_time_range`, for the left selection only.
+    pub fn left_selection_time_range(&self) -> Option<(f64, f64)> {
+        let current_map_state = self.history.get_current_state();
+        let objects: Vec<HitObject> = Self::selection_objects(&self.left_selection)
+            .iter()
+            .map(|idx| (*current_map_state.objects.get(*idx).hit_object).clone())
+            .collect();
+        Self::objects_time_range(&objects)
+    }
+
+    fn objects_time_range(objects: &[HitObject]) -> Option<(f64, f64)> {
+        let start_ms = objects.iter().map(HitObject::time).reduce(f64::min)?;
+        let end_ms = objects
+            .iter()
+            .map(|object| match object {
+                HitObject::Circle(c) => c.time,
+                HitObject::Slider(s) => s.end_time(),
+                HitObject::Spinner(sp) => sp.end_time,
+            })
+            .fold(start_ms, f64::max);
+        Some((start_ms, end_ms))
+    }
+
     pub fn undo(&mut self) {
         if self.history.undo() {
+            self.operation_log.record("undo".to_string(), false);
             self.export_needs_recalc = true;
             self.hitsound_needs_recalc = true;
             self.left_selection = None;
@@ -678,6 +1500,7 @@ impl EditState {
 
     pub fn redo(&mut self, uuid: Option<u128>) {
         if self.history.redo(uuid) {
+            self.operation_log.record("redo".to_string(), false);
             self.export_needs_recalc = true;
             self.hitsound_needs_recalc = true;
             self.left_selection = None;
@@ -689,6 +1512,127 @@ impl EditState {
 
     pub fn checkpoint_current_state(&mut self) {
         self.history.save_checkpoint();
+        self.operation_log.end_continuing();
+    }
+
+    // Start times of the objects in one selection, in map order.
+    pub fn selected_object_times(&self, left: bool) -> Vec<f64> {
+        let selection = if left {
+            &self.left_selection
+        } else {
+            &self.right_selection
+        };
+        let current_map_state = self.history.get_current_state();
+        Self::selection_objects(selection)
+            .iter()
+            .map(|idx| current_map_state.objects.get(*idx).hit_object.time())
+            .collect()
+    }
+
+    // Replaces both selections with the objects starting at the given times. Each time picks
+    // one object, so stacked objects sharing a time are told apart by how often it appears.
+    // Returns how many times no longer match an object.
+    pub fn select_objects_at_times(&mut self, left_times: &[f64], right_times: &[f64]) -> usize {
+        let current_map_state = self.history.get_current_state();
+        current_map_state.export();
+        let object_times: Vec<f64> = current_map_state
+            .objects
+            .iter()
+            .map(|object| object.hit_object.time())
+            .collect();
+        let mut taken = vec![false; object_times.len()];
+        let mut missing = 0;
+        let mut pick = |times: &[f64]| -> Vec<usize> {
+            let mut picked = Vec::with_capacity(times.len());
+            for time in times {
+                let first = object_times.partition_point(|t| *t < time - 0.5);
+                let found = (first..object_times.len())
+                    .take_while(|idx| object_times[*idx] <= time + 0.5)
+                    .find(|idx| !taken[*idx]);
+                match found {
+                    Some(idx) => {
+                        taken[idx] = true;
+                        picked.push(idx);
+                    }
+                    None => missing += 1,
+                }
+            }
+            picked.sort_unstable();
+            picked
+        };
+        let left = pick(left_times);
+        let right = pick(right_times);
+        self.left_selection = Self::selection_from_objects(&current_map_state, left);
+        self.right_selection = Self::selection_from_objects(&current_map_state, right);
+        missing
+    }
+
+    // Replaces both selections with the objects a modding timestamp points at, on the left:
+    // for each combo number the next object from `time_ms` on with that number, or without
+    // numbers the objects at `time_ms`. Returns how many were selected.
+    pub fn select_timestamp_objects(&mut self, time_ms: f64, combo_numbers: &[u32]) -> usize {
+        let current_map_state = self.history.get_current_state();
+        current_map_state.export();
+        let objects: Vec<(f64, u32)> = current_map_state
+            .objects
+            .iter()
+            .map(|object| object.hit_object.time())
+            .zip(Self::combo_numbers(&current_map_state))
+            .collect();
+
+        let first = objects.partition_point(|(time, _)| *time < time_ms - 0.5);
+        let mut picked: Vec<usize> = Vec::new();
+        if combo_numbers.is_empty() {
+            picked.extend(
+                (first..objects.len()).take_while(|idx| objects[*idx].0 <= time_ms + 0.5),
+            );
+        }
+        let mut next = first;
+        for number in combo_numbers {
+            let Some(idx) = (next..objects.len()).find(|idx| objects[*idx].1 == *number) else {
+                break;
+            };
+            picked.push(idx);
+            next = idx + 1;
+        }
+
+        let selected = picked.len();
+        self.left_selection = Self::selection_from_objects(&current_map_state, picked);
+        self.right_selection = None;
+        selected
+    }
+
+    // Start time of the first object in either selection and the combo numbers of all of them
+    // in map order, what a modding timestamp refers to. None without a selection.
+    pub fn selection_timestamp(&self) -> Option<(f64, Vec<u32>)> {
+        let mut ids: Vec<usize> = Self::selection_objects(&self.left_selection)
+            .iter()
+            .chain(Self::selection_objects(&self.right_selection))
+            .copied()
+            .collect();
+        ids.sort_unstable();
+        ids.dedup();
+        let current_map_state = self.history.get_current_state();
+        let time_ms = current_map_state.objects.get(*ids.first()?).hit_object.time();
+        let combo_numbers = Self::combo_numbers(&current_map_state);
+        Some((time_ms, ids.iter().map(|idx| combo_numbers[*idx]).collect()))
+    }
+
+    // Number of each object within its combo, counted like the playfield draws them.
+    fn combo_numbers(map_state: &MapState) -> Vec<u32> {
+        let mut combo = 0u32;
+        map_state
+            .objects
+            .iter()
+            .map(|object| {
+                combo = if object.hit_object.combo_info().new_combo {
+                    1
+                } else {
+                    combo + 1
+                };
+                combo
+            })
+            .collect()
     }
 
     pub fn clear_selections(&mut self) {
@@ -717,9 +1661,8 @@ impl EditState {
         self.left_selection = Self::selection_from_objects(&state, left_selected_objects);
     }
 
-    pub fn select_visible_to_left(&mut self, time_ms: f64) {
-        const FADE_OUT_MS: f64 = 250.0;
-
+    pub fn select_visible_to_left(&mut self, time: SongTimeMs) {
+        let time_ms = time.0;
         let current_state = self.history.get_current_state();
         current_state.export();
 
@@ -734,19 +1677,7 @@ impl EditState {
                 continue;
             }
             let object = object.instance().unwrap();
-            let appear_ms = if object.is_spinner {
-                object.time
-            } else {
-                object.time - object.preempt
-            };
-            let end_ms = if object.is_slider || object.is_spinner {
-                object.slider_end_time_ms
-            } else {
-                object.time
-            };
-            let disappear_ms = end_ms + FADE_OUT_MS;
-
-            if time_ms >= appear_ms && time_ms <= disappear_ms {
+            if is_object_currently_visible(object, time_ms, &self.fade_envelope) {
                 left_selected_set.insert(idx);
             }
         }
@@ -849,6 +1780,52 @@ impl EditState {
         }
     }
 
+    // Rotates by `degrees` and scales by `scale` around the selection's origin in one undo step.
+    pub fn rotate_and_scale_selection(&mut self, left: bool, degrees: f64, scale: f64) {
+        let selection = if left {
+            &self.left_selection
+        } else {
+            &self.right_selection
+        };
+        if let Some(selection) = selection {
+            let origin = selection.origin;
+            let radians = degrees.to_radians();
+            let transform = Vec2Transform::transform_at_origin(
+                Vec2Transform::multiply_by_complex(Vec2 {
+                    x: scale * radians.cos(),
+                    y: scale * radians.sin(),
+                }),
+                origin,
+            );
+            self.apply_transform(transform, left, true);
+        }
+    }
+
+    // Non-uniform scale around `anchor`, `along` in the direction of `axis` and `across`
+    // perpendicular to it.
+    pub fn scale_selection_along_axis(
+        &mut self,
+        left: bool,
+        anchor: Vec2,
+        axis: Vec2,
+        along: f64,
+        across: f64,
+        checkpoint: bool,
+    ) {
+        let selection = if left {
+            &self.left_selection
+        } else {
+            &self.right_selection
+        };
+        if selection.is_some() && axis.len2() > 1e-12 && along > 0.0 && across > 0.0 {
+            let transform = Vec2Transform::transform_at_origin(
+                Vec2Transform::scale_along_axis(axis, along, across),
+                anchor,
+            );
+            self.apply_transform(transform, left, checkpoint);
+        }
+    }
+
     pub fn flip_selection_horizontal(&mut self) {
         if let Some(left_selection) = &self.left_selection {
             let origin = left_selection.origin;
@@ -1076,3 +2053,293 @@ impl EditState {
         Self::selection_from_parts(objects, bbox, map_state.diff_settings.circle_radius)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_harness::{EditHarness, TEST_MAP, object_positions};
+
+    #[test]
+    fn undo_and_redo_restore_translated_objects() {
+        let harness = EditHarness::new(TEST_MAP);
+        let before = object_positions(&harness.map_state());
+        harness.edit(|edit_state| {
+            edit_state.select_all_to_left();
+            edit_state.translate_selection(true, Vec2 { x: 10.0, y: 0.0 }, true);
+        });
+        let moved = object_positions(&harness.map_state());
+        assert_ne!(before, moved);
+
+        harness.edit(|edit_state| edit_state.undo());
+        assert_eq!(before, object_positions(&harness.map_state()));
+        harness.edit(|edit_state| edit_state.redo(None));
+        assert_eq!(moved, object_positions(&harness.map_state()));
+    }
+
+    #[test]
+    fn selection_sets_restore_objects_by_time() {
+        let harness = EditHarness::new(TEST_MAP);
+        let (left, right) = harness.edit(|edit_state| {
+            edit_state.select_all_to_left();
+            (
+                edit_state.selected_object_times(true),
+                edit_state.selected_object_times(false),
+            )
+        });
+        assert_eq!(left, harness.object_times());
+        assert!(right.is_empty());
+
+        let missing = harness.edit(|edit_state| {
+            edit_state.clear_selections();
+            edit_state.select_objects_at_times(&[1500.0, 2000.0], &[1000.0, 1234.0])
+        });
+        assert_eq!(missing, 1);
+        harness.edit(|edit_state| {
+            assert_eq!(edit_state.selected_object_times(true), vec![1500.0, 2000.0]);
+            assert_eq!(edit_state.selected_object_times(false), vec![1000.0]);
+        });
+    }
+
+    #[test]
+    fn non_uniform_scale_keeps_the_other_axis() {
+        let harness = EditHarness::new(TEST_MAP);
+        let before = object_positions(&harness.map_state());
+        let anchor = Vec2 { x: 256.0, y: 192.0 };
+        harness.edit(|edit_state| {
+            edit_state.select_all_to_left();
+            let axis = Vec2 { x: 1.0, y: 0.0 };
+            edit_state.scale_selection_along_axis(true, anchor, axis, 2.0, 1.0, true);
+        });
+        let scaled = object_positions(&harness.map_state());
+        for (before, scaled) in before.iter().zip(scaled.iter()) {
+            assert!((scaled.x - (anchor.x + (before.x - anchor.x) * 2.0)).abs() < 1e-6);
+            assert!((scaled.y - before.y).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn unchecked_drag_steps_undo_together() {
+        let harness = EditHarness::new(TEST_MAP);
+        let before = object_positions(&harness.map_state());
+        harness.edit(|edit_state| {
+            edit_state.select_all_to_left();
+            for _ in 0..3 {
+                edit_state.translate_selection(true, Vec2 { x: 5.0, y: 5.0 }, false);
+            }
+        });
+        harness.edit(|edit_state| edit_state.undo());
+        assert_eq!(before, object_positions(&harness.map_state()));
+    }
+
+    #[test]
+    fn drag_steps_replace_each_other_until_committed() {
+        let harness = EditHarness::new(TEST_MAP);
+        let before = object_positions(&harness.map_state());
+        harness.edit(|edit_state| {
+            edit_state.select_all_to_left();
+            let depth = edit_state.undo_depth();
+            for _ in 0..3 {
+                edit_state.drag_selection(true, Vec2 { x: 5.0, y: 5.0 });
+            }
+            assert_eq!(edit_state.undo_depth(), depth + 1);
+            edit_state.commit_drag();
+            edit_state.drag_selection(true, Vec2 { x: 5.0, y: 5.0 });
+            assert_eq!(edit_state.undo_depth(), depth + 2);
+        });
+        harness.edit(|edit_state| {
+            edit_state.undo();
+            edit_state.undo();
+        });
+        assert_eq!(before, object_positions(&harness.map_state()));
+    }
+
+    #[test]
+    fn time_drag_steps_stay_pending_until_committed() {
+        let harness = EditHarness::new(TEST_MAP);
+        let before = harness.object_times();
+        harness.edit(|edit_state| {
+            edit_state.select_all_to_left();
+            let depth = edit_state.undo_depth();
+            for time_ms in [1100.0, 1200.0, 1300.0] {
+                assert!(edit_state.drag_selection_to_time(SongTimeMs(time_ms)));
+            }
+            assert_eq!(edit_state.undo_depth(), depth + 1);
+            edit_state.commit_drag();
+        });
+        assert!((harness.object_times()[0] - 1300.0).abs() < 1e-6);
+        harness.edit(|edit_state| edit_state.undo());
+        assert_eq!(before, harness.object_times());
+    }
+
+    #[test]
+    fn inserted_objects_are_exported_in_time_order() {
+        let harness = EditHarness::new(TEST_MAP);
+        let map_state = harness.map_state();
+        let mut copy = map_state
+            .objects
+            .iter()
+            .next()
+            .unwrap()
+            .hit_object
+            .as_ref()
+            .clone();
+        copy.retime(1250.0, 1.0);
+        harness.edit(|edit_state| edit_state.insert_objects(vec![copy]));
+
+        let exported = EditHarness::new(&harness.export_osu_text());
+        let times = exported.object_times();
+        assert_eq!(times.len(), 6);
+        assert!(times.windows(2).all(|pair| pair[0] <= pair[1]));
+        assert!(times.contains(&1250.0));
+
+        harness.edit(|edit_state| edit_state.undo());
+        assert_eq!(harness.object_times().len(), 5);
+    }
+
+    #[test]
+    fn unsnapped_objects_are_resnapped_to_the_closest_tick() {
+        let edited = TEST_MAP.replace("356,192,1500,1,2,", "356,192,1530,1,2,");
+        let harness = EditHarness::new(&edited);
+        assert_eq!(harness.map_state().unsnapped_objects(), vec![(1, 1531.25)]);
+        let resnapped = harness.edit(|edit_state| edit_state.resnap_unsnapped_objects());
+        assert_eq!(resnapped, 1);
+        assert_eq!(harness.object_times()[1], 1531.25);
+        assert!(harness.map_state().unsnapped_objects().is_empty());
+    }
+
+    #[test]
+    fn typed_rotation_and_scale_undo_in_one_step() {
+        let harness = EditHarness::new(TEST_MAP);
+        let before = object_positions(&harness.map_state());
+        harness.edit(|edit_state| {
+            edit_state.select_objects_at_times(&[1000.0, 1500.0], &[]);
+            edit_state.rotate_and_scale_selection(true, 90.0, 2.0);
+        });
+        let after = object_positions(&harness.map_state());
+        let offset = after[1] - after[0];
+        assert!(offset.distance2(Vec2 { x: 0.0, y: 200.0 }) < 1e-6);
+
+        harness.edit(|edit_state| edit_state.undo());
+        assert_eq!(object_positions(&harness.map_state()), before);
+    }
+
+    #[test]
+    fn green_line_here_is_inherited_by_selected_objects() {
+        let harness = EditHarness::new(TEST_MAP);
+        let slider_sv = |map_state: &MapState| {
+            map_state
+                .objects
+                .iter()
+                .find_map(|object| match &*object.hit_object {
+                    HitObject::Slider(slider) => Some(slider.sv_pixels_per_ms),
+                    _ => None,
+                })
+                .unwrap()
+        };
+        let before_sv = slider_sv(&harness.map_state());
+        let settings = GreenLineSettings {
+            sv_multiplier: Some(2.0),
+            volume: Some(0.8),
+            sample_set: None,
+        };
+        let placed = harness.edit(|edit_state| {
+            edit_state.select_all_to_left();
+            edit_state.insert_green_lines_at_selection(&settings)
+        });
+        assert_eq!(placed, 5);
+
+        let map_state = harness.map_state();
+        // The green lines at 2000 and 3000 are changed rather than doubled.
+        assert_eq!(map_state.timing.timing_points.len(), 6);
+        assert!((slider_sv(&map_state) - before_sv * 2.0).abs() < 1e-9);
+        let first = map_state.objects.get(0);
+        let HitObject::Circle(circle) = &*first.hit_object else {
+            panic!("first object is not a circle");
+        };
+        assert!((circle.hitsound_info.volume - 0.8).abs() < 1e-9);
+        let kiai_times: Vec<(f64, f64)> = map_state.kiai_times.iter().cloned().collect();
+        assert_eq!(kiai_times, vec![(2000.0, 3000.0)]);
+
+        harness.edit(|edit_state| edit_state.undo());
+        assert_eq!(harness.map_state().timing.timing_points.len(), 3);
+    }
+
+    #[test]
+    fn sliders_fit_target_beats_by_path_or_sv() {
+        let harness = EditHarness::new(TEST_MAP);
+        let slider = |map_state: &MapState| match &*map_state.objects.get(2).hit_object {
+            HitObject::Slider(slider) => slider.clone(),
+            _ => panic!("third object is not a slider"),
+        };
+
+        // Half a beat at the same velocity halves the path around the head.
+        let mut scaled = slider(&harness.map_state());
+        scaled.scale_path_to_length(70.0);
+        assert!((scaled.end_time() - 2250.0).abs() < 1e-9);
+        assert!((scaled.control_points.size() - 100.0).abs() < 1e-6);
+
+        // Two beats by SV: the spinner sits on the next green line, so nothing is restored.
+        assert!(harness.edit(|edit_state| edit_state.fit_slider_duration_with_sv(2, 1000.0)));
+        let map_state = harness.map_state();
+        assert!((slider(&map_state).end_time() - 3000.0).abs() < 1e-6);
+        assert_eq!(map_state.timing.timing_points.len(), 3);
+        let head_line = map_state.timing.inherited_at(2000.0).unwrap();
+        assert!((head_line.sv_multiplier - 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn selection_time_range_runs_from_first_start_to_last_end() {
+        let harness = EditHarness::new(TEST_MAP);
+        assert_eq!(harness.edit(|s| s.selection_time_range()), None);
+        harness.edit(|s| s.select_all_to_left());
+        let (start_ms, end_ms) = harness.edit(|s| s.selection_time_range()).unwrap();
+        assert_eq!(start_ms, 1000.0);
+        assert_eq!(end_ms, 9500.0);
+    }
+
+    #[test]
+    fn objects_between_keep_those_starting_in_the_range() {
+        let harness = EditHarness::new(TEST_MAP);
+        let times = harness.object_times();
+        let objects = harness.edit(|s| s.hit_objects_between(times[1], times[2]));
+        let object_times: Vec<f64> = objects.iter().map(HitObject::time).collect();
+        assert_eq!(object_times, times[1..=2].to_vec());
+    }
+
+    #[test]
+    fn pasted_rhythm_retimes_the_selection_or_places_placeholders() {
+        let harness = EditHarness::new(TEST_MAP);
+        let map_state = harness.map_state();
+        let copied: Vec<HitObject> = map_state
+            .objects
+            .iter()
+            .take(2)
+            .map(|object| (*object.hit_object).clone())
+            .collect();
+
+        // The circles at 1000 and 1500 give the slider and the last circle their 500ms gap.
+        let selected = harness.edit(|edit_state| {
+            edit_state.select_objects_at_times(&[2000.0, 9500.0], &[]);
+            edit_state.paste_rhythm(&copied, SongTimeMs(6000.0));
+            edit_state.selected_object_times(true)
+        });
+        assert_eq!(selected, vec![6000.0, 6500.0]);
+        assert_eq!(harness.object_times(), vec![1000.0, 1500.0, 3000.0, 6000.0, 6500.0]);
+        harness.edit(|edit_state| edit_state.undo());
+        assert_eq!(harness.object_times(), vec![1000.0, 1500.0, 2000.0, 3000.0, 9500.0]);
+
+        harness.edit(|edit_state| {
+            edit_state.clear_selections();
+            edit_state.paste_rhythm(&copied, SongTimeMs(7000.0));
+        });
+        let map_state = harness.map_state();
+        let placeholders: Vec<(f64, Vec2)> = map_state
+            .objects
+            .iter()
+            .filter(|object| [7000.0, 7500.0].contains(&object.hit_object.time()))
+            .map(|object| (object.hit_object.time(), object.hit_object.position()))
+            .collect();
+        let center = Vec2 { x: 256.0, y: 192.0 };
+        assert_eq!(placeholders, vec![(7000.0, center), (7500.0, center)]);
+    }
+}
@@ -0,0 +1,118 @@
+use crate::{geometry::vec2::Vec2, gpu::gpu::ObjectInstance, treap::Treap};
+
+use super::object::Object;
+
+// Jumps shorter than this (stacks) have no meaningful direction.
+const MIN_JUMP_PX: f64 = 1.0;
+
+pub struct FlowTransition {
+    pub from: Vec2,
+    pub to: Vec2,
+    pub from_end_ms: f64,
+    pub to_time_ms: f64,
+    pub to_preempt_ms: f64,
+    // Angle at `to` between this jump and the next one, 180 is a straight line.
+    pub angle_deg: Option<f64>,
+    pub is_sharp: bool,
+    pub is_anti_flow: bool,
+}
+
+fn end_pos(instance: &ObjectInstance) -> Vec2 {
+    if instance.is_slider && instance.slider_path.is_some() && instance.slides % 2 == 1 {
+        instance.end_pos()
+    } else {
+        instance.pos
+    }
+}
+
+fn end_ms(instance: &ObjectInstance) -> f64 {
+    if instance.is_slider {
+        instance.slider_end_time_ms
+    } else {
+        instance.time
+    }
+}
+
+// Signed turn from `incoming` to `outgoing` in degrees, positive is clockwise on screen.
+fn turn_deg(incoming: Vec2, outgoing: Vec2) -> Option<f64> {
+    if incoming.len() < MIN_JUMP_PX || outgoing.len() < MIN_JUMP_PX {
+        return None;
+    }
+    let cross = incoming.x * outgoing.y - incoming.y * outgoing.x;
+    let dot = incoming.x * outgoing.x + incoming.y * outgoing.y;
+    Some(cross.atan2(dot).to_degrees())
+}
+
+// Jumps between consecutive objects with the angle at each landing object. A transition
+// is sharp when that angle is below `sharp_angle_deg`, and anti-flow when the cursor turns
+// the other way than on the previous object and both turns exceed `anti_flow_turn_deg`.
+// Spinners break the flow. Objects must already be exported.
+pub fn find_flow_transitions(
+    objects: &Treap<Object>,
+    sharp_angle_deg: f64,
+    anti_flow_turn_deg: f64,
+) -> Vec<FlowTransition> {
+    let instances: Vec<&ObjectInstance> = objects
+        .iter()
+        .filter_map(|object| object.instance())
+        .collect();
+
+    let mut transitions: Vec<FlowTransition> = Vec::new();
+    let mut previous_turn: Option<f64> = None;
+    for (i, pair) in instances.windows(2).enumerate() {
+        let (from, to) = (pair[0], pair[1]);
+        if from.is_spinner || to.is_spinner {
+            previous_turn = None;
+            continue;
+        }
+        let from_pos = end_pos(from);
+        let next = instances.get(i + 2).filter(|next| !next.is_spinner);
+        let turn = next.and_then(|next| turn_deg(to.pos - from_pos, next.pos - end_pos(to)));
+
+        let angle_deg = turn.map(|turn| 180.0 - turn.abs());
+        let is_sharp = angle_deg.is_some_and(|angle| angle < sharp_angle_deg);
+        let is_anti_flow = match (previous_turn, turn) {
+            (Some(previous), Some(current)) => {
+                previous.signum() != current.signum()
+                    && previous.abs() > anti_flow_turn_deg
+                    && current.abs() > anti_flow_turn_deg
+            }
+            _ => false,
+        };
+        previous_turn = turn;
+
+        transitions.push(FlowTransition {
+            from: from_pos,
+            to: to.pos,
+            from_end_ms: end_ms(from),
+            to_time_ms: to.time,
+            to_preempt_ms: to.preempt,
+            angle_deg,
+            is_sharp,
+            is_anti_flow,
+        });
+    }
+    transitions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_harness::{EditHarness, TEST_MAP};
+
+    #[test]
+    fn flow_transitions_flag_sharp_angles() {
+        let harness = EditHarness::new(TEST_MAP);
+        let map_state = harness.map_state();
+        map_state.export();
+        let transitions = find_flow_transitions(&map_state.objects, 60.0, 45.0);
+
+        // The spinner breaks the flow, so only circle -> circle -> slider is left.
+        assert_eq!(transitions.len(), 2);
+        let angle = transitions[0].angle_deg.unwrap();
+        assert!((angle - 19.8).abs() < 0.5, "angle was {angle}");
+        assert!(transitions[0].is_sharp);
+        assert!(transitions[1].angle_deg.is_none());
+        assert!(!transitions[1].is_sharp);
+    }
+}
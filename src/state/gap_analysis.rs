@@ -0,0 +1,119 @@
+use crate::{map_format::objects::HitObject, treap::Treap};
+
+use super::object::Object;
+
+// osu! drops breaks shorter than this and starts them this long after the previous object.
+const MIN_BREAK_MS: f64 = 650.0;
+const BREAK_START_DELAY_MS: f64 = 200.0;
+// A pause this long reads as a break to players even when osu! can't show one.
+const NOTICEABLE_GAP_MS: f64 = 1000.0;
+const MIN_SPINNER_MS: f64 = 1000.0;
+// Time to get back to the cursor after a spinner, and to start one after an object.
+const SPINNER_RECOVERY_MS: f64 = 300.0;
+const DEFAULT_PREEMPT_MS: f64 = 1200.0;
+
+#[derive(Clone)]
+pub enum GapSuggestion {
+    InsertBreak { start_ms: f64, end_ms: f64 },
+    TooShortForBreak { start_ms: f64, end_ms: f64 },
+    Spinner { start_ms: f64, end_ms: f64 },
+}
+
+impl GapSuggestion {
+    pub fn start_ms(&self) -> f64 {
+        match self {
+            GapSuggestion::InsertBreak { start_ms, .. }
+            | GapSuggestion::TooShortForBreak { start_ms, .. }
+            | GapSuggestion::Spinner { start_ms, .. } => *start_ms,
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            GapSuggestion::InsertBreak { start_ms, end_ms } => {
+                format!("insert break {:.0}ms - {:.0}ms", start_ms, end_ms)
+            }
+            GapSuggestion::TooShortForBreak { start_ms, end_ms } => format!(
+                "{:.0}ms gap at {:.0}ms too short for a break",
+                end_ms - start_ms,
+                start_ms
+            ),
+            GapSuggestion::Spinner { start_ms, end_ms } => {
+                format!("spinner on held note {:.0}ms - {:.0}ms", start_ms, end_ms)
+            }
+        }
+    }
+}
+
+fn end_time(hit_object: &HitObject) -> f64 {
+    match hit_object {
+        HitObject::Circle(c) => c.time,
+        HitObject::Slider(s) => s.end_time(),
+        HitObject::Spinner(sp) => sp.end_time,
+    }
+}
+
+// Looks at every gap between consecutive objects. Long gaps without a break get a break
+// suggestion, noticeable gaps that can't fit one are flagged. Where the music has an onset
+// followed by a long stretch without new onsets (a held note), a spinner is suggested.
+// `onsets_ms` must be sorted and in map time. Objects must already be exported.
+pub fn find_gap_suggestions(
+    objects: &Treap<Object>,
+    break_times: &Treap<(f64, f64)>,
+    onsets_ms: &[f64],
+) -> Vec<GapSuggestion> {
+    let objects: Vec<&Object> = objects.iter().collect();
+    let mut suggestions = Vec::new();
+    for pair in objects.windows(2) {
+        let gap_start = end_time(&pair[0].hit_object);
+        let gap_end = pair[1].hit_object.time();
+        if gap_end <= gap_start {
+            continue;
+        }
+        let preempt = pair[1]
+            .instance()
+            .map(|instance| instance.preempt)
+            .unwrap_or(DEFAULT_PREEMPT_MS);
+        let has_break = break_times
+            .iter()
+            .any(|(start, end)| *start < gap_end && *end > gap_start);
+
+        let break_start = gap_start + BREAK_START_DELAY_MS;
+        let break_end = gap_end - preempt;
+        if !has_break {
+            if break_end - break_start >= MIN_BREAK_MS {
+                suggestions.push(GapSuggestion::InsertBreak {
+                    start_ms: break_start,
+                    end_ms: break_end,
+                });
+            } else if gap_end - gap_start >= NOTICEABLE_GAP_MS {
+                suggestions.push(GapSuggestion::TooShortForBreak {
+                    start_ms: gap_start,
+                    end_ms: gap_end,
+                });
+            }
+        }
+
+        let first = onsets_ms.partition_point(|t| *t < gap_start + SPINNER_RECOVERY_MS);
+        let last = onsets_ms.partition_point(|t| *t < gap_end - SPINNER_RECOVERY_MS);
+        let mut longest: Option<(f64, f64)> = None;
+        for i in first..last {
+            let start_ms = onsets_ms[i];
+            let end_ms = onsets_ms
+                .get(i + 1)
+                .copied()
+                .unwrap_or(f64::INFINITY)
+                .min(gap_end - SPINNER_RECOVERY_MS);
+            if end_ms - start_ms >= MIN_SPINNER_MS
+                && longest.is_none_or(|(s, e)| end_ms - start_ms > e - s)
+            {
+                longest = Some((start_ms, end_ms));
+            }
+        }
+        if let Some((start_ms, end_ms)) = longest {
+            suggestions.push(GapSuggestion::Spinner { start_ms, end_ms });
+        }
+    }
+    suggestions.sort_by(|a, b| a.start_ms().total_cmp(&b.start_ms()));
+    suggestions
+}
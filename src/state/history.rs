@@ -16,6 +16,8 @@ pub struct HistoryNode {
 pub enum CheckPointInfo {
     CheckPoint,
     CheckPointAfter(time::Duration),
+    // Replaced by the next append until `commit_pending` keeps it, for drags in progress.
+    Pending,
 }
 
 pub struct History {
@@ -173,6 +175,16 @@ impl History {
         self.nodes[self.current_node].checkpoint = CheckPointInfo::CheckPoint;
     }
 
+    // Keeps the current state if it is pending. Returns whether it was.
+    pub fn commit_pending(&mut self) -> bool {
+        let node = &mut self.nodes[self.current_node];
+        if !matches!(node.checkpoint, CheckPointInfo::Pending) {
+            return false;
+        }
+        node.checkpoint = CheckPointInfo::CheckPoint;
+        true
+    }
+
     fn pop_uncheckpointed(&mut self) {
         loop {
             let current_node = &mut self.nodes[self.current_node];
@@ -186,6 +198,7 @@ impl History {
                         return;
                     }
                 }
+                CheckPointInfo::Pending => {}
             }
             match current_node.parent {
                 Some(parent_id) => {
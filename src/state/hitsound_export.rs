@@ -1,4 +1,4 @@
-use crate::map_format::objects::HitsoundInfo;
+use crate::map_format::{objects::HitsoundInfo, timing::SampleSet};
 
 use super::map_state::MapState;
 
@@ -14,4 +14,13 @@ impl HitsoundExport {
         map_state.export_hitsounds(&mut exported);
         exported
     }
+
+    // Puts every hitsound on `sample_set` regardless of its timing point, to audition a base
+    // sample set. Custom sample files named by objects still play as they are.
+    pub fn force_sample_set(&mut self, sample_set: &SampleSet) {
+        for (_, _, hitsound_info) in self.hitsounds.iter_mut() {
+            hitsound_info.hit_sampleset = sample_set.clone();
+            hitsound_info.additions_sampleset = sample_set.clone();
+        }
+    }
 }
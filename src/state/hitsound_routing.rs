@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use crate::map_format::{objects::HitsoundInfo, timing::SampleSet};
 
 use super::hitsound_sampleset_indices::HitsoundSamplesetIndices;
@@ -7,6 +9,8 @@ pub struct HitsoundRouting {
     pub normal: HitsoundSamplesetIndices,
     pub soft: HitsoundSamplesetIndices,
     pub drum: HitsoundSamplesetIndices,
+    // Sample files of the beatmap folder by lowercase name.
+    pub map_samples: HashMap<String, usize>,
 }
 
 impl HitsoundRouting {
@@ -23,6 +27,12 @@ impl HitsoundRouting {
         hitsound_info: &HitsoundInfo,
         position_x: f64,
     ) -> Vec<(usize, f64, f64)> {
+        // osu! plays only the custom file when an object names one it can find.
+        if let Some(filename) = hitsound_info.filename.as_ref() {
+            if let Some(index) = self.map_samples.get(&filename.to_ascii_lowercase()) {
+                return vec![(*index, hitsound_info.volume, position_x)];
+            }
+        }
         let hit_sampleset = self.resolve_sampleset(&hitsound_info.hit_sampleset);
         let addition_sampleset = self.resolve_sampleset(&hitsound_info.additions_sampleset);
 
@@ -47,3 +57,53 @@ impl HitsoundRouting {
         events
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        map_format::objects::HitObject,
+        test_harness::{EditHarness, TEST_MAP},
+    };
+
+    #[test]
+    fn custom_sample_file_replaces_samplesets() {
+        let harness = EditHarness::new(TEST_MAP);
+        harness.edit(|edit_state| {
+            edit_state.select_all_to_left();
+            edit_state.set_selection_sample_filename(Some("bell.wav".to_string()));
+        });
+        let exported = EditHarness::new(&harness.export_osu_text());
+        let map_state = exported.map_state();
+        let slider = map_state
+            .objects
+            .iter()
+            .find_map(|object| match &*object.hit_object {
+                HitObject::Slider(slider) => Some(slider.clone()),
+                _ => None,
+            })
+            .unwrap();
+        assert!(
+            slider
+                .hitsounds
+                .iter()
+                .all(|hitsound| hitsound.filename.as_deref() == Some("bell.wav"))
+        );
+
+        let indices = HitsoundSamplesetIndices {
+            hitclap: 0,
+            hitfinish: 1,
+            hitnormal: 2,
+            hitwhistle: 3,
+        };
+        let routing = HitsoundRouting {
+            normal: indices.clone(),
+            soft: indices.clone(),
+            drum: indices,
+            map_samples: HashMap::from([("bell.wav".to_string(), 7)]),
+        };
+        let events = routing.resolve_audio_events(&slider.hitsounds[0], 0.5);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].0, 7);
+    }
+}
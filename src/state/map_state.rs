@@ -1,22 +1,67 @@
-use std::sync::{Arc, OnceLock};
+use std::{
+    collections::HashSet,
+    sync::{Arc, OnceLock},
+};
 
 use crate::{
+    audio::SongTimeMs,
     config::Config,
+    dotosu::osr_file::OsrFile,
     geometry::vec2_transform::Vec2Transform,
     map_format::{
+        beatmap::Beatmap,
         colors::Color,
         diff_settings::DiffSettings,
+        events::{BreakEvent, Event},
         objects::HitObject,
-        timing::TimingPoint,
+        timing::{GreenLineSettings, Timing, TimingPoint},
     },
     treap::Treap,
 };
 
-use super::{hitsound_export::HitsoundExport, object::Object};
+use super::{
+    combo_suggestions::{ComboProposal, propose_new_combos},
+    gap_analysis::{GapSuggestion, find_gap_suggestions},
+    hitsound_export::HitsoundExport,
+    object::Object,
+    overlap_analysis::{OverlapWarning, find_overlap_warnings},
+    replay_analysis::{ReplayJudgement, judge_replay},
+    silent_hitsounds::{SilentHitsound, find_silent_hitsounds},
+    slider_curve_analysis::{SliderCurveWarning, find_slider_curve_warnings},
+    unsnapped_objects::find_unsnapped_objects,
+};
+
+fn red_line_times(timing_points: &[TimingPoint]) -> Treap<f64> {
+    let red_lines: Vec<f64> = timing_points
+        .iter()
+        .filter_map(|f| match f {
+            TimingPoint::RedLine(r) => Some(r.time),
+            _ => None,
+        })
+        .collect();
+    Treap::from_slice(red_lines.as_slice())
+}
+
+fn kiai_intervals(timing_points: &[TimingPoint]) -> Vec<(f64, f64)> {
+    let mut kiai_times: Vec<(f64, f64)> = Vec::new();
+    let mut kiai_start = None;
+    for timing_point in timing_points {
+        if timing_point.effects().kiai_mode {
+            if kiai_start.is_none() {
+                kiai_start = Some(timing_point.time());
+            }
+        } else if let Some(start) = kiai_start {
+            kiai_times.push((start, timing_point.time()));
+            kiai_start = None;
+        }
+    }
+    kiai_times
+}
 
 #[derive(Clone)]
 pub struct MapState {
     pub objects: Treap<Object>,
+    pub timing: Arc<Timing>,
     pub red_lines: Treap<f64>,
     pub bookmarks: Treap<f64>,
     pub kiai_times: Treap<(f64, f64)>,
@@ -48,16 +93,12 @@ impl MapState {
                 return object;
             })
             .collect();
-        let red_lines: Vec<f64> = timing
-            .iter()
-            .filter_map(|f| match f {
-                TimingPoint::RedLine(r) => Some(r.time),
-                _ => None,
-            })
-            .collect();
         Self {
             objects: Treap::from_slice(objects.as_slice()),
-            red_lines: Treap::from_slice(red_lines.as_slice()),
+            red_lines: red_line_times(&timing),
+            timing: Arc::new(Timing {
+                timing_points: timing,
+            }),
             bookmarks: Treap::from_slice(bookmarks.as_slice()),
             kiai_times: Treap::from_slice(kiai_times.as_slice()),
             break_times: Treap::from_slice(break_times.as_slice()),
@@ -67,12 +108,154 @@ impl MapState {
         }
     }
 
+    pub fn from_beatmap(beatmap: &Beatmap, bookmarks: Vec<f64>, config: Config) -> Self {
+        let mut break_times: Vec<(f64, f64)> = Vec::new();
+        for event in &beatmap.events.events {
+            if let Event::Break(BreakEvent {
+                start_time,
+                end_time,
+            }) = event
+            {
+                break_times.push((*start_time, *end_time));
+            }
+        }
+        MapState::new(
+            beatmap.objects.objects.clone(),
+            beatmap.timing.timing_points.clone(),
+            bookmarks,
+            kiai_intervals(&beatmap.timing.timing_points),
+            break_times,
+            beatmap.colors.combo_colors.clone(),
+            beatmap.diff_settings.clone(),
+            config,
+        )
+    }
+
+    // Events and metadata are not tracked by the editor, those come from `template`.
+    // Breaks are the exception since they can be added in the editor.
+    pub fn to_beatmap(&self, template: &Beatmap) -> Beatmap {
+        let mut beatmap = template.clone();
+        beatmap.timing = (*self.timing).clone();
+        beatmap.events = template.events.without_breaks();
+        for (start_time, end_time) in self.break_times.iter() {
+            beatmap.events.events.push(Event::Break(BreakEvent {
+                start_time: *start_time,
+                end_time: *end_time,
+            }));
+        }
+        beatmap.objects.objects = self
+            .objects
+            .iter()
+            .map(|object| (*object.hit_object).clone())
+            .collect();
+        beatmap.diff_settings = self.diff_settings.clone();
+        beatmap.colors.combo_colors = self.combo_colors.clone();
+        beatmap
+    }
+
     pub fn export(&self) {
         for object in self.objects.iter() {
             object.instance_or_calculate(&self.diff_settings, &self.config);
         }
     }
 
+    pub fn overlap_warnings(&self, max_overlap_percent: f64) -> Vec<OverlapWarning> {
+        self.export();
+        find_overlap_warnings(&self.objects, max_overlap_percent)
+    }
+
+    pub fn gap_suggestions(&self, onsets: &[SongTimeMs]) -> Vec<GapSuggestion> {
+        self.export();
+        let onsets_ms: Vec<f64> = onsets.iter().map(|onset| onset.0).collect();
+        find_gap_suggestions(&self.objects, &self.break_times, &onsets_ms)
+    }
+
+    pub fn combo_proposal(&self, measures_per_combo: usize, color_count: usize) -> ComboProposal {
+        propose_new_combos(
+            &self.objects,
+            &self.timing,
+            &self.bookmarks,
+            measures_per_combo,
+            color_count,
+        )
+    }
+
+    pub fn unsnapped_objects(&self) -> Vec<(usize, f64)> {
+        find_unsnapped_objects(&self.objects, &self.timing)
+    }
+
+    pub fn replay_judgements(&self, replay: &OsrFile) -> Vec<ReplayJudgement> {
+        self.export();
+        judge_replay(&self.objects, &self.diff_settings, replay)
+    }
+
+    pub fn silent_hitsounds(
+        &self,
+        sample_state: impl FnMut(&str) -> Option<bool>,
+    ) -> Vec<SilentHitsound> {
+        find_silent_hitsounds(&self.objects, sample_state)
+    }
+
+    pub fn slider_curve_warnings(
+        &self,
+        max_bezier_anchors: usize,
+        max_diff_px: f64,
+    ) -> Vec<SliderCurveWarning> {
+        find_slider_curve_warnings(&self.objects, max_bezier_anchors, max_diff_px)
+    }
+
+    pub fn with_break(&self, start_ms: f64, end_ms: f64) -> MapState {
+        let mut break_times: Vec<(f64, f64)> = self.break_times.iter().cloned().collect();
+        let idx = break_times.partition_point(|(start, _)| *start <= start_ms);
+        break_times.insert(idx, (start_ms, end_ms));
+        let mut map_state = self.clone();
+        map_state.break_times = Treap::from_slice(break_times.as_slice());
+        map_state
+    }
+
+    // Replaces all timing points. Red lines and kiai ranges follow, objects stay where they are.
+    pub fn with_timing(&self, timing_points: Vec<TimingPoint>) -> MapState {
+        let mut map_state = self.clone();
+        map_state.red_lines = red_line_times(&timing_points);
+        map_state.kiai_times = Treap::from_slice(kiai_intervals(&timing_points).as_slice());
+        map_state.timing = Arc::new(Timing { timing_points });
+        map_state
+    }
+
+    // Puts a green line at `time` and passes it on to the objects it covers, up to the next
+    // timing point. None before the first red line.
+    pub fn with_green_line_at(&self, time: f64, settings: &GreenLineSettings) -> Option<MapState> {
+        let previous = self.timing.inherited_at(time)?;
+        let (timing_points, green_line) = self.timing.with_green_line_at(time, settings)?;
+        let until = timing_points
+            .iter()
+            .map(|tp| tp.time())
+            .find(|t| *t > time)
+            .unwrap_or(f64::INFINITY);
+        let mut map_state = self.with_timing(timing_points);
+        let covered: Vec<usize> = self
+            .objects
+            .iter()
+            .enumerate()
+            .filter(|(_, object)| match &*object.hit_object {
+                HitObject::Slider(s) => s.time < until && s.end_time() >= time,
+                hit_object => hit_object.time() >= time && hit_object.time() < until,
+            })
+            .map(|(id, _)| id)
+            .collect();
+        for id in covered {
+            map_state.objects = map_state.objects.mutate(id, |object| {
+                let mut object = object.clone();
+                let mut hit_object = (*object.hit_object).clone();
+                hit_object.inherit_green_line(time, until, &previous, &green_line);
+                object.hit_object = Arc::new(hit_object);
+                object.instance = Arc::new(OnceLock::new());
+                return object;
+            });
+        }
+        Some(map_state)
+    }
+
     pub fn export_hitsounds(&self, export_into: &mut HitsoundExport) {
         export_into.hitsounds.clear();
         for object in self.objects.iter() {
@@ -121,4 +304,228 @@ impl MapState {
         }
         return map_state;
     }
+
+    pub fn with_sample_filename(&self, ids: &[usize], filename: Option<String>) -> MapState {
+        let mut map_state = self.clone();
+        for id in ids {
+            map_state.objects = map_state.objects.mutate(*id, |object| {
+                let mut object = object.clone();
+                let mut hit_object = (*object.hit_object).clone();
+                hit_object.set_sample_filename(filename.clone());
+                object.hit_object = Arc::new(hit_object);
+                return object;
+            });
+        }
+        return map_state;
+    }
+
+    // Sets the new combo flag of every object, one flag per object in map order. Returns the
+    // new state and how many objects changed.
+    pub fn with_new_combos(&self, new_combos: &[bool]) -> (MapState, usize) {
+        let mut map_state = self.clone();
+        let mut changed = 0;
+        for (id, new_combo) in new_combos.iter().enumerate().take(self.objects.len()) {
+            if self.objects.get(id).hit_object.combo_info().new_combo == *new_combo {
+                continue;
+            }
+            map_state.objects = map_state.objects.mutate(id, |object| {
+                let mut hit_object = (*object.hit_object).clone();
+                hit_object.set_new_combo(*new_combo);
+                Object {
+                    hit_object: Arc::new(hit_object),
+                    instance: Arc::new(OnceLock::new()),
+                }
+            });
+            changed += 1;
+        }
+        (map_state, changed)
+    }
+
+    // Inserts `hit_objects` keeping the objects sorted by time.
+    // Returns the new state and the indices the inserted objects ended up at.
+    pub fn insert_objects(&self, hit_objects: Vec<HitObject>) -> (MapState, Vec<usize>) {
+        let mut new_objects: Vec<Object> = hit_objects
+            .into_iter()
+            .map(|hit_object| Object {
+                hit_object: Arc::new(hit_object),
+                instance: Arc::new(OnceLock::new()),
+            })
+            .collect();
+        new_objects.sort_by(|a, b| a.hit_object.time().total_cmp(&b.hit_object.time()));
+
+        let mut objects: Vec<Object> = Vec::with_capacity(self.objects.len() + new_objects.len());
+        let mut inserted: Vec<usize> = Vec::with_capacity(new_objects.len());
+        let mut existing = self.objects.iter().peekable();
+        for object in new_objects {
+            while let Some(next) = existing.peek() {
+                if next.hit_object.time() > object.hit_object.time() {
+                    break;
+                }
+                objects.push((*next).clone());
+                existing.next();
+            }
+            inserted.push(objects.len());
+            objects.push(object);
+        }
+        objects.extend(existing.cloned());
+
+        let mut map_state = self.clone();
+        map_state.objects = Treap::from_slice(objects.as_slice());
+        (map_state, inserted)
+    }
+
+    // Moves the objects at the given indices to new start times, keeping everything sorted.
+    // Sliders keep their length and take on the velocity of the timing they land in, like
+    // moving them in osu! does, so they export the way they play.
+    // Returns the new state and the indices the moved objects ended up at.
+    pub fn retime_objects(&self, times: &[(usize, f64)]) -> (MapState, Vec<usize>) {
+        let mut moved: Vec<HitObject> = Vec::with_capacity(times.len());
+        for (id, time) in times {
+            let mut hit_object = (*self.objects.get(*id).hit_object).clone();
+            hit_object.retime(*time, 1.0);
+            let sv = self
+                .timing
+                .slider_velocity_at(*time, self.diff_settings.sv_multiplier);
+            if let (HitObject::Slider(s), Some(sv)) = (&mut hit_object, sv) {
+                s.sv_pixels_per_ms = sv;
+            }
+            moved.push(hit_object);
+        }
+        let moved_ids: HashSet<usize> = times.iter().map(|(id, _)| *id).collect();
+        let remaining: Vec<Object> = self
+            .objects
+            .iter()
+            .enumerate()
+            .filter(|(id, _)| !moved_ids.contains(id))
+            .map(|(_, object)| object.clone())
+            .collect();
+        let mut map_state = self.clone();
+        map_state.objects = Treap::from_slice(remaining.as_slice());
+        map_state.insert_objects(moved)
+    }
+
+    // Replaces the object at `id`, keeping everything sorted. Returns the new state and
+    // the index the replacement ended up at.
+    pub fn replace_object(&self, id: usize, hit_object: HitObject) -> (MapState, usize) {
+        let remaining: Vec<Object> = self
+            .objects
+            .iter()
+            .enumerate()
+            .filter(|(other_id, _)| *other_id != id)
+            .map(|(_, object)| object.clone())
+            .collect();
+        let mut map_state = self.clone();
+        map_state.objects = Treap::from_slice(remaining.as_slice());
+        let (map_state, inserted) = map_state.insert_objects(vec![hit_object]);
+        (map_state, inserted[0])
+    }
+
+    // Splits the first slider (among `candidates`, or all objects if empty) that is
+    // being played at `time_ms`. Returns the new state and the index of the head part.
+    pub fn split_slider_at_time(
+        &self,
+        time_ms: f64,
+        candidates: &[usize],
+    ) -> Option<(MapState, usize)> {
+        let ids: Vec<usize> = if candidates.is_empty() {
+            (0..self.objects.len()).collect()
+        } else {
+            candidates.to_vec()
+        };
+        let min_slider_length_px = self.diff_settings.circle_radius * 0.1;
+        for id in ids {
+            let object = self.objects.get(id);
+            let HitObject::Slider(slider) = &*object.hit_object else {
+                continue;
+            };
+            if time_ms <= slider.time || time_ms >= slider.end_time() {
+                continue;
+            }
+            let Some((head, tail)) = slider.split_at_time(time_ms, min_slider_length_px) else {
+                continue;
+            };
+
+            let (left, rest) = self.objects.split(id);
+            let (_, right) = rest.split(1);
+            let mut map_state = self.clone();
+            map_state.objects = left
+                .merge(Treap::new(Object {
+                    hit_object: Arc::new(head),
+                    instance: Arc::new(OnceLock::new()),
+                }))
+                .merge(Treap::new(Object {
+                    hit_object: Arc::new(tail),
+                    instance: Arc::new(OnceLock::new()),
+                }))
+                .merge(right);
+            return Some((map_state, id));
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        map_format::slider_timing::find_desynced_sliders,
+        test_harness::{EditHarness, TEST_MAP, parse_test_map, test_config, test_replay},
+    };
+
+    #[test]
+    fn retimed_sliders_take_on_the_velocity_where_they_land() {
+        // The slider at 2000ms sits under a 2x green line, 1250ms is back at 1x.
+        let osu_text = TEST_MAP.replace("2000,-100,4,2,0,60,0,1", "2000,-50,4,2,0,60,0,1");
+        let (_, template) = parse_test_map(&osu_text);
+        let map_state = MapState::from_beatmap(&template, Vec::new(), test_config());
+        assert!(find_desynced_sliders(&map_state.to_beatmap(&template)).is_empty());
+
+        let (moved, ids) = map_state.retime_objects(&[(2, 1250.0)]);
+        assert_eq!(moved.objects.get(ids[0]).hit_object.time(), 1250.0);
+        assert!(find_desynced_sliders(&moved.to_beatmap(&template)).is_empty());
+    }
+
+    #[test]
+    fn combo_proposal_starts_combos_on_downbeats_and_bookmarks() {
+        let harness = EditHarness::new(TEST_MAP);
+        let proposal = harness.map_state().combo_proposal(1, 2);
+        assert_eq!(proposal.new_combos, vec![true, false, true, true, true]);
+        assert_eq!((proposal.added, proposal.removed), (1, 0));
+        let combos: Vec<(f64, usize, usize)> = proposal
+            .combos
+            .iter()
+            .map(|combo| (combo.start_ms, combo.object_count, combo.color_index))
+            .collect();
+        assert_eq!(
+            combos,
+            vec![(1000.0, 2, 1), (2000.0, 1, 0), (3000.0, 1, 0), (9500.0, 1, 1)]
+        );
+
+        let changed = harness.edit(|edit_state| edit_state.set_new_combos(&proposal.new_combos));
+        assert_eq!(changed, 1);
+        assert!(harness.map_state().objects.get(2).hit_object.combo_info().new_combo);
+    }
+
+    #[test]
+    fn replay_judgements_find_hits_and_misses() {
+        let harness = EditHarness::new(TEST_MAP);
+        let replay = test_replay(
+            0,
+            &[
+                (0.0, 0.0, 0.0, 0),
+                (1010.0, 256.0, 192.0, 1),
+                (1100.0, 256.0, 192.0, 0),
+                (2090.0, 100.0, 100.0, 2),
+                (2200.0, 100.0, 100.0, 0),
+                (9500.0, 400.0, 300.0, 1),
+                (9600.0, 400.0, 300.0, 0),
+            ],
+        );
+        let judgements = harness.map_state().replay_judgements(&replay);
+        let scores: Vec<u32> = judgements.iter().map(|j| j.score).collect();
+        assert_eq!(scores, vec![300, 0, 100, 0]);
+        assert_eq!(judgements[0].hit_error_ms, Some(10.0));
+        assert_eq!(judgements[1].hit_error_ms, None);
+        assert_eq!(judgements[2].hit_error_ms, Some(90.0));
+    }
 }
@@ -1,6 +1,13 @@
+mod anchor_snap;
+mod auto_cursor;
+mod combo_suggestions;
+mod convert_preview;
+mod difficulty_simulation;
 mod drag_state;
 mod edit_state;
 mod export_thread_state;
+mod flow_analysis;
+mod gap_analysis;
 mod history;
 mod hitsound_export;
 mod hitsound_routing;
@@ -8,14 +15,36 @@ mod hitsound_sampleset_indices;
 mod hitsound_thread_config;
 mod map_state;
 mod object;
+mod object_tooltip;
+mod operation_log;
+mod overlap_analysis;
+mod replay_analysis;
 mod selection;
+mod session_tracker;
+mod silent_hitsounds;
+mod slider_curve_analysis;
 mod snap_position;
 mod snap_positions;
+mod unsnapped_objects;
 
-pub use drag_state::DragState;
+pub use anchor_snap::snap_slider_anchor;
+pub use auto_cursor::auto_cursor_trail;
+pub use convert_preview::{CONVERT_MODE_NAMES, ConvertMode, ConvertPreview, ConvertPreviewCache};
+pub use difficulty_simulation::{DifficultySimulation, SimulatedMapStateCache};
+pub use drag_state::{DragState, TimelineDragMarks};
 pub use edit_state::EditState;
+pub use flow_analysis::{FlowTransition, find_flow_transitions};
+pub use gap_analysis::GapSuggestion;
 pub use hitsound_routing::HitsoundRouting;
 pub use hitsound_sampleset_indices::HitsoundSamplesetIndices;
 pub use hitsound_thread_config::HitsoundThreadConfig;
 pub use map_state::MapState;
 pub use object::Object;
+pub use object_tooltip::{object_at_cursor, object_tooltip};
+pub use operation_log::{OperationLog, OperationLogEntry};
+pub use replay_analysis::{ReplayJudgement, ReplayJudgementCache, replay_cursor_trail};
+pub use session_tracker::{SessionTracker, object_changes};
+pub use silent_hitsounds::SilentHitsound;
+pub use slider_curve_analysis::SliderCurveWarning;
+pub use snap_positions::SnapSources;
+pub use unsnapped_objects::UnsnappedObjectsCache;
@@ -0,0 +1,123 @@
+use crate::{
+    geometry::vec2::Vec2,
+    map_format::objects::{HitObject, HitsoundInfo},
+    render::{FadeEnvelope, is_object_currently_visible},
+    time_formats::format_clock_time,
+};
+
+use super::map_state::MapState;
+
+// The object drawn at `time_ms` whose circle, or for sliders whose head, tail or anchors, is
+// under `cursor` (playfield coordinates). The earliest wins where objects overlap, since that
+// is the one a player would hit. Spinners cover the whole playfield and are left out.
+pub fn object_at_cursor(
+    map_state: &MapState,
+    fade: &FadeEnvelope,
+    cursor: Vec2,
+    time_ms: f64,
+) -> Option<usize> {
+    let radius = map_state.diff_settings.circle_radius;
+    map_state.objects.iter().enumerate().find_map(|(id, object)| {
+        if matches!(&*object.hit_object, HitObject::Spinner(_)) {
+            return None;
+        }
+        let instance = object.instance_or_calculate(&map_state.diff_settings, &map_state.config);
+        if !is_object_currently_visible(instance, time_ms, fade) {
+            return None;
+        }
+        std::iter::once(&instance.pos)
+            .chain(instance.snap_points.iter())
+            .any(|point| point.distance2(cursor) <= radius * radius)
+            .then_some(id)
+    })
+}
+
+// One line for the HUD font: time, snap, combo number, hitsounds and for sliders the repeats
+// and length, like `00:01.500 1/2 combo 2 | soft whistle | 1 repeat 140px`.
+pub fn object_tooltip(map_state: &MapState, id: usize) -> String {
+    let object = map_state.objects.get(id);
+    let hit_object = &*object.hit_object;
+    let time = hit_object.time();
+    let snap = match map_state.timing.snap_divisor_at(time) {
+        Some(divisor) => format!("1/{}", divisor),
+        None => "unsnapped".to_string(),
+    };
+    let combo_number = map_state
+        .objects
+        .iter()
+        .take(id + 1)
+        .fold(0, |combo, object| {
+            if object.hit_object.combo_info().new_combo {
+                1
+            } else {
+                combo + 1
+            }
+        });
+    let mut text = format!("{} {} combo {}", format_clock_time(time), snap, combo_number);
+    match hit_object {
+        HitObject::Circle(circle) => {
+            text.push_str(&format!(" | {}", describe_hitsound(&circle.hitsound_info)));
+        }
+        HitObject::Slider(slider) => {
+            if let Some(head) = slider.hitsounds.first() {
+                text.push_str(&format!(" | {}", describe_hitsound(head)));
+            }
+            let repeats = slider.slides.saturating_sub(1);
+            text.push_str(&format!(
+                " | {} repeat{} {:.0}px",
+                repeats,
+                if repeats == 1 { "" } else { "s" },
+                slider.length_pixels
+            ));
+        }
+        HitObject::Spinner(spinner) => {
+            text.push_str(&format!(" | {:.0}ms", spinner.end_time - spinner.time));
+        }
+    }
+    text
+}
+
+// The sample set, with the additions' set after a dash when it differs, then the additions.
+fn describe_hitsound(hitsound_info: &HitsoundInfo) -> String {
+    let mut text = format!("{:?}", hitsound_info.hit_sampleset).to_lowercase();
+    if hitsound_info.additions_sampleset != hitsound_info.hit_sampleset {
+        text.push_str(&format!("-{:?}", hitsound_info.additions_sampleset).to_lowercase());
+    }
+    for (played, name) in [
+        (hitsound_info.play_whistle, "whistle"),
+        (hitsound_info.play_finish, "finish"),
+        (hitsound_info.play_clap, "clap"),
+    ] {
+        if played {
+            text.push(' ');
+            text.push_str(name);
+        }
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_harness::{EditHarness, TEST_MAP};
+
+    #[test]
+    fn hovered_objects_describe_time_snap_combo_and_hitsounds() {
+        let harness = EditHarness::new(TEST_MAP);
+        let map_state = harness.map_state();
+        let hovered = harness.edit(|edit_state| {
+            object_at_cursor(
+                &map_state,
+                &edit_state.fade_envelope,
+                Vec2 { x: 350.0, y: 195.0 },
+                1500.0,
+            )
+        });
+        assert_eq!(hovered, Some(1));
+        assert_eq!(object_tooltip(&map_state, 1), "00:01.500 1/1 combo 2 | soft whistle");
+        assert_eq!(
+            object_tooltip(&map_state, 2),
+            "00:02.000 1/1 combo 3 | soft whistle | 0 repeats 140px"
+        );
+    }
+}
@@ -0,0 +1,88 @@
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+
+#[derive(Serialize, Clone, PartialEq, Debug)]
+pub struct OperationLogEntry {
+    // Since the session started.
+    pub at_ms: f64,
+    pub operation: String,
+}
+
+// Every edit made to a difficulty since it was opened, in order. Entries are only ever
+// appended; a drag is one entry, not one per frame.
+#[derive(Serialize)]
+pub struct OperationLog {
+    pub started_at: u64,
+    pub entries: Vec<OperationLogEntry>,
+    #[serde(skip)]
+    started: Instant,
+    // The last entry is a drag that is still going on.
+    #[serde(skip)]
+    continuing: bool,
+}
+
+impl OperationLog {
+    pub fn new() -> Self {
+        Self {
+            started_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+            entries: Vec::new(),
+            started: Instant::now(),
+            continuing: false,
+        }
+    }
+
+    // `continuing` marks an edit that repeats until the next checkpoint, like a drag. Repeats
+    // of the same operation until then, the checkpointed one included, are left out.
+    pub fn record(&mut self, operation: String, continuing: bool) {
+        let repeat = self.continuing
+            && self.entries.last().map(|entry| &entry.operation) == Some(&operation);
+        self.continuing = continuing;
+        if repeat {
+            return;
+        }
+        self.entries.push(OperationLogEntry {
+            at_ms: self.started.elapsed().as_secs_f64() * 1000.0,
+            operation,
+        });
+    }
+
+    pub fn end_continuing(&mut self) {
+        self.continuing = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        geometry::vec2::Vec2,
+        test_harness::{EditHarness, TEST_MAP},
+    };
+
+    #[test]
+    fn operation_log_keeps_a_drag_as_one_entry() {
+        let harness = EditHarness::new(TEST_MAP);
+        let operations = harness.edit(|edit_state| {
+            edit_state.select_all_to_left();
+            for _ in 0..3 {
+                edit_state.translate_selection(true, Vec2 { x: 5.0, y: 5.0 }, false);
+            }
+            edit_state.checkpoint_current_state();
+            edit_state.translate_selection(true, Vec2 { x: 5.0, y: 5.0 }, true);
+            edit_state.undo();
+            let entries = &edit_state.operation_log.entries;
+            entries
+                .iter()
+                .map(|entry| entry.operation.clone())
+                .collect::<Vec<_>>()
+        });
+        assert_eq!(operations.len(), 3);
+        assert_eq!(operations[0], operations[1]);
+        assert!(operations[0].starts_with("transform left selection"));
+        assert_eq!(operations[2], "undo");
+    }
+}
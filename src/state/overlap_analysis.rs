@@ -0,0 +1,84 @@
+use std::f64::consts::PI;
+
+use crate::{geometry::vec2::Vec2, gpu::gpu::ObjectInstance, treap::Treap};
+
+use super::object::Object;
+
+pub struct OverlapWarning {
+    pub start_ms: f64,
+    pub end_ms: f64,
+    pub max_overlap_percent: f64,
+}
+
+// Share of one circle's area covered by another circle of the same radius.
+fn circle_overlap_percent(a: Vec2, b: Vec2, radius: f64) -> f64 {
+    if radius <= 0.0 {
+        return 0.0;
+    }
+    let d = a.distance2(b).sqrt();
+    if d >= 2.0 * radius {
+        return 0.0;
+    }
+    let lens_area = 2.0 * radius * radius * (d / (2.0 * radius)).acos()
+        - 0.5 * d * (4.0 * radius * radius - d * d).sqrt();
+    100.0 * lens_area / (PI * radius * radius)
+}
+
+fn visible_end_ms(object: &ObjectInstance) -> f64 {
+    if object.is_slider {
+        object.slider_end_time_ms
+    } else {
+        object.time
+    }
+}
+
+// Flags time ranges where an object is still on screen at gameplay AR while a later
+// object fades in on top of it with more than `max_overlap_percent` of overlap.
+// Objects must already be exported.
+pub fn find_overlap_warnings(
+    objects: &Treap<Object>,
+    max_overlap_percent: f64,
+) -> Vec<OverlapWarning> {
+    let instances: Vec<&ObjectInstance> = objects
+        .iter()
+        .filter_map(|object| object.instance())
+        .filter(|instance| !instance.is_spinner)
+        .collect();
+
+    let mut flagged: Vec<OverlapWarning> = Vec::new();
+    for (i, earlier) in instances.iter().enumerate() {
+        let earlier_end_ms = visible_end_ms(earlier);
+        for later in instances[i + 1..].iter() {
+            let later_appear_ms = later.time - later.preempt;
+            if later_appear_ms >= earlier_end_ms {
+                break;
+            }
+            let overlap = circle_overlap_percent(earlier.pos, later.pos, later.radius).max(
+                circle_overlap_percent(earlier.end_pos(), later.pos, later.radius),
+            );
+            if overlap <= max_overlap_percent {
+                continue;
+            }
+
+            flagged.push(OverlapWarning {
+                start_ms: later_appear_ms.max(earlier.time - earlier.preempt),
+                end_ms: earlier_end_ms,
+                max_overlap_percent: overlap,
+            });
+        }
+    }
+
+    flagged.sort_by(|a, b| a.start_ms.total_cmp(&b.start_ms));
+    let mut warnings: Vec<OverlapWarning> = Vec::new();
+    for warning in flagged {
+        match warnings.last_mut() {
+            Some(last) if warning.start_ms <= last.end_ms => {
+                last.end_ms = last.end_ms.max(warning.end_ms);
+                last.max_overlap_percent =
+                    last.max_overlap_percent.max(warning.max_overlap_percent);
+            }
+            _ => warnings.push(warning),
+        }
+    }
+    warnings
+}
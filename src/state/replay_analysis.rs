@@ -0,0 +1,225 @@
+use std::sync::Arc;
+
+use crate::{
+    dotosu::osr_file::{KEYS_CLICK_MASK, MOD_EASY, MOD_HARD_ROCK, OsrFile},
+    geometry::vec2::Vec2,
+    map_format::diff_settings::{DiffSettings, circle_radius_from_cs, circle_radius_to_cs},
+    treap::Treap,
+};
+
+use super::object::Object;
+
+// One circle or slider head as the replay played it.
+pub struct ReplayJudgement {
+    pub object_time_ms: f64,
+    pub object_pos: Vec2,
+    pub radius: f64,
+    // 300, 100, 50 or 0 for a miss.
+    pub score: u32,
+    // Click time minus object time, None for a miss.
+    pub hit_error_ms: Option<f64>,
+    // Where the cursor was on the click, or at the object's time for a miss.
+    pub cursor_pos: Vec2,
+}
+
+// Hard Rock plays the map flipped vertically; flip the cursor back so it lines up with the
+// objects as they are in the editor.
+fn frame_pos(replay: &OsrFile, pos: Vec2) -> Vec2 {
+    if replay.mods & MOD_HARD_ROCK != 0 {
+        Vec2 {
+            x: pos.x,
+            y: 384.0 - pos.y,
+        }
+    } else {
+        pos
+    }
+}
+
+// Cursor between the two frames around `time_ms`, None outside the replay.
+pub fn replay_cursor_position(replay: &OsrFile, time_ms: f64) -> Option<Vec2> {
+    let frames = &replay.frames;
+    let next = frames.partition_point(|frame| frame.time_ms <= time_ms);
+    if next == 0 || next == frames.len() {
+        return None;
+    }
+    let (a, b) = (&frames[next - 1], &frames[next]);
+    let span_ms = b.time_ms - a.time_ms;
+    let t = if span_ms > 0.0 {
+        (time_ms - a.time_ms) / span_ms
+    } else {
+        1.0
+    };
+    Some(frame_pos(replay, a.pos + (b.pos - a.pos) * t))
+}
+
+// Same sampling as auto_cursor_trail, for the replay's cursor.
+pub fn replay_cursor_trail(
+    replay: &OsrFile,
+    time_ms: f64,
+    trail_ms: f64,
+    samples: usize,
+) -> Vec<Vec2> {
+    let samples = samples.max(1);
+    let step_ms = if samples > 1 {
+        trail_ms.max(0.0) / (samples - 1) as f64
+    } else {
+        0.0
+    };
+    (0..samples)
+        .filter_map(|i| replay_cursor_position(replay, time_ms - (samples - 1 - i) as f64 * step_ms))
+        .collect()
+}
+
+// Circle radius and 300/100/50 windows with the replay's Hard Rock or Easy applied.
+fn modded_difficulty(diff_settings: &DiffSettings, mods: u32) -> (f64, [f64; 3]) {
+    let mut cs = circle_radius_to_cs(diff_settings.circle_radius);
+    let mut od = diff_settings.overall_difficulty;
+    if mods & MOD_HARD_ROCK != 0 {
+        cs = (cs * 1.3).min(10.0);
+        od = (od * 1.4).min(10.0);
+    }
+    if mods & MOD_EASY != 0 {
+        cs *= 0.5;
+        od *= 0.5;
+    }
+    let modded = DiffSettings {
+        circle_radius: circle_radius_from_cs(cs),
+        overall_difficulty: od,
+        ..diff_settings.clone()
+    };
+    (modded.circle_radius, modded.hit_windows_ms())
+}
+
+// Time and cursor position of every new M1 / M2 press, in order.
+fn click_presses(replay: &OsrFile) -> Vec<(f64, Vec2)> {
+    let mut presses = Vec::new();
+    let mut held = 0;
+    for frame in replay.frames.iter() {
+        let keys = frame.keys & KEYS_CLICK_MASK;
+        let pressed = keys & !held;
+        for _ in 0..pressed.count_ones() {
+            presses.push((frame.time_ms, frame_pos(replay, frame.pos)));
+        }
+        held = keys;
+    }
+    presses
+}
+
+// Judges circles and slider heads against the replay's clicks: the first unused click inside
+// the 50 window that lands on the object hits it. Spinners, slider ticks and notelock are
+// ignored, so this is a close approximation of the result screen rather than an exact one.
+// Objects must already be exported.
+pub fn judge_replay(
+    objects: &Treap<Object>,
+    diff_settings: &DiffSettings,
+    replay: &OsrFile,
+) -> Vec<ReplayJudgement> {
+    let (radius, hit_windows_ms) = modded_difficulty(diff_settings, replay.mods);
+    let presses = click_presses(replay);
+    let mut next_press = 0;
+    let mut judgements = Vec::new();
+    for object in objects.iter() {
+        let Some(instance) = object.instance() else {
+            continue;
+        };
+        if instance.is_spinner {
+            continue;
+        }
+        let window_start_ms = instance.time - hit_windows_ms[2];
+        let window_end_ms = instance.time + hit_windows_ms[2];
+        while next_press < presses.len() && presses[next_press].0 < window_start_ms {
+            next_press += 1;
+        }
+        let hit = presses[next_press..]
+            .iter()
+            .take_while(|(time_ms, _)| *time_ms <= window_end_ms)
+            .position(|(_, pos)| pos.distance2(instance.pos) <= radius * radius);
+
+        let judgement = match hit {
+            Some(offset) => {
+                let (time_ms, pos) = presses[next_press + offset];
+                next_press += offset + 1;
+                let error_ms = time_ms - instance.time;
+                let score = if error_ms.abs() <= hit_windows_ms[0] {
+                    300
+                } else if error_ms.abs() <= hit_windows_ms[1] {
+                    100
+                } else {
+                    50
+                };
+                ReplayJudgement {
+                    object_time_ms: instance.time,
+                    object_pos: instance.pos,
+                    radius,
+                    score,
+                    hit_error_ms: Some(error_ms),
+                    cursor_pos: pos,
+                }
+            }
+            None => ReplayJudgement {
+                object_time_ms: instance.time,
+                object_pos: instance.pos,
+                radius,
+                score: 0,
+                hit_error_ms: None,
+                cursor_pos: replay_cursor_position(replay, instance.time).unwrap_or(instance.pos),
+            },
+        };
+        judgements.push(judgement);
+    }
+    judgements
+}
+
+pub struct ReplayJudgementCache {
+    objects: Treap<Object>,
+    diff_settings: Option<DiffSettings>,
+    replay: Option<Arc<OsrFile>>,
+    judgements: Vec<ReplayJudgement>,
+}
+
+impl ReplayJudgementCache {
+    pub fn new() -> Self {
+        ReplayJudgementCache {
+            objects: Treap::new_empty(),
+            diff_settings: None,
+            replay: None,
+            judgements: Vec::new(),
+        }
+    }
+
+    pub fn get(
+        &mut self,
+        objects: &Treap<Object>,
+        diff_settings: &DiffSettings,
+        replay: &Arc<OsrFile>,
+    ) -> &[ReplayJudgement] {
+        let is_valid = self.objects.ptr_eq(objects)
+            && self.replay.as_ref().is_some_and(|r| Arc::ptr_eq(r, replay))
+            && self.diff_settings.as_ref() == Some(diff_settings);
+        if !is_valid {
+            self.judgements = judge_replay(objects, diff_settings, replay);
+            self.objects = objects.clone();
+            self.replay = Some(replay.clone());
+            self.diff_settings = Some(diff_settings.clone());
+        }
+        &self.judgements
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_harness::test_replay;
+
+    #[test]
+    fn replay_cursor_is_flipped_back_for_hard_rock() {
+        let frames = [(0.0, 100.0, 100.0, 0), (100.0, 200.0, 100.0, 0)];
+        let at = |replay: &OsrFile| *replay_cursor_trail(replay, 50.0, 0.0, 1).last().unwrap();
+        assert_eq!(at(&test_replay(0, &frames)), Vec2 { x: 150.0, y: 100.0 });
+        assert_eq!(
+            at(&test_replay(MOD_HARD_ROCK, &frames)),
+            Vec2 { x: 150.0, y: 284.0 }
+        );
+        assert!(replay_cursor_trail(&test_replay(0, &frames), 500.0, 0.0, 1).is_empty());
+    }
+}
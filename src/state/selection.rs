@@ -41,6 +41,7 @@ impl Selection {
         self.drag_state = match &self.drag_state {
             Some(state) => Some(DragState {
                 pos: state.pos * transform,
+                grab_offset: state.grab_offset,
                 part_of_object: state.part_of_object,
                 is_rotation: state.is_rotation,
             }),
@@ -0,0 +1,201 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use crate::map_format::{
+    objects::HitObject,
+    statistics::{EditingSession, EditingTotals},
+};
+
+use super::map_state::MapState;
+
+// Longer gaps between inputs are a break, not editing.
+const SESSION_IDLE_CUTOFF: Duration = Duration::from_secs(60);
+
+// Active editing time and what changed since the difficulty was opened. Changes are counted by
+// comparing against the opening state at the end, so an object dragged around for a minute is
+// one edit and one placed then deleted again is nothing.
+pub struct SessionTracker {
+    started_at: u64,
+    start_state: Arc<MapState>,
+    active: Duration,
+    last_activity: Option<Instant>,
+    // Sessions recorded for this difficulty before this one.
+    earlier: EditingTotals,
+    // Changes counted for the last state asked about, so the live totals don't recount every
+    // frame.
+    counted: Option<(Arc<MapState>, (usize, usize, usize))>,
+}
+
+impl SessionTracker {
+    pub fn new(start_state: Arc<MapState>, earlier: EditingTotals) -> Self {
+        SessionTracker {
+            started_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+            start_state,
+            active: Duration::ZERO,
+            last_activity: None,
+            earlier,
+            counted: None,
+        }
+    }
+
+    pub fn note_activity(&mut self, now: Instant) {
+        if let Some(last_activity) = self.last_activity {
+            let gap = now.saturating_duration_since(last_activity);
+            if gap <= SESSION_IDLE_CUTOFF {
+                self.active += gap;
+            }
+        }
+        self.last_activity = Some(now);
+    }
+
+    // None while nothing has happened yet, so opening a map to look at it isn't a session.
+    pub fn session(
+        &mut self,
+        difficulty: &str,
+        current: &Arc<MapState>,
+    ) -> Option<EditingSession> {
+        let (placed, edited, removed) = self.changes(current);
+        if self.active.is_zero() && placed + edited + removed == 0 {
+            return None;
+        }
+        Some(EditingSession {
+            difficulty: difficulty.to_string(),
+            started_at: self.started_at,
+            active_ms: self.active.as_secs_f64() * 1000.0,
+            placed,
+            edited,
+            removed,
+        })
+    }
+
+    // Earlier sessions on this difficulty plus this one so far.
+    pub fn totals(&mut self, difficulty: &str, current: &Arc<MapState>) -> EditingTotals {
+        let mut totals = self.earlier.clone();
+        if let Some(session) = self.session(difficulty, current) {
+            totals.add(&session);
+        }
+        totals
+    }
+
+    fn changes(&mut self, current: &Arc<MapState>) -> (usize, usize, usize) {
+        match &self.counted {
+            Some((state, changes)) if Arc::ptr_eq(state, current) => *changes,
+            _ => {
+                let changes = object_changes(&self.start_state, current);
+                self.counted = Some((Arc::clone(current), changes));
+                changes
+            }
+        }
+    }
+}
+
+// Objects placed, edited and removed going from `before` to `after`. Objects are matched by
+// what they are rather than by allocation, since undo, redo and moving something back all
+// rebuild the hit object. Objects only in `after` pair up with ones only in `before` as edits
+// and the rest are placed or removed.
+pub fn object_changes(before: &MapState, after: &MapState) -> (usize, usize, usize) {
+    let after_objects: HashSet<*const _> = after
+        .objects
+        .iter()
+        .map(|object| Arc::as_ptr(&object.hit_object))
+        .collect();
+    // Untouched objects share their allocation, so only the rest need comparing by content.
+    let mut gone_objects: HashMap<String, usize> = HashMap::new();
+    let mut shared = HashSet::new();
+    for object in before.objects.iter() {
+        let ptr = Arc::as_ptr(&object.hit_object);
+        if after_objects.contains(&ptr) {
+            shared.insert(ptr);
+        } else {
+            *gone_objects.entry(object_key(&object.hit_object)).or_default() += 1;
+        }
+    }
+    let mut added = 0;
+    for object in after.objects.iter() {
+        if shared.contains(&Arc::as_ptr(&object.hit_object)) {
+            continue;
+        }
+        match gone_objects.get_mut(&object_key(&object.hit_object)) {
+            Some(count) if *count > 0 => *count -= 1,
+            _ => added += 1,
+        }
+    }
+    let gone: usize = gone_objects.values().sum();
+    let edited = added.min(gone);
+    (added - edited, edited, gone - edited)
+}
+
+fn object_key(hit_object: &HitObject) -> String {
+    serde_json::to_string(hit_object).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        geometry::vec2::Vec2,
+        test_harness::{EditHarness, TEST_MAP},
+    };
+
+    #[test]
+    fn editing_sessions_count_active_time_and_changed_objects() {
+        let harness = EditHarness::new(TEST_MAP);
+        let start = harness.map_state();
+        let mut tracker = SessionTracker::new(Arc::clone(&start), EditingTotals::default());
+        assert!(tracker.session("Normal", &start).is_none());
+
+        let first_time = harness.object_times()[0];
+        let mut copy = start
+            .objects
+            .iter()
+            .next()
+            .unwrap()
+            .hit_object
+            .as_ref()
+            .clone();
+        copy.retime(1250.0, 1.0);
+        harness.edit(|edit_state| {
+            edit_state.insert_objects(vec![copy]);
+            edit_state.select_objects_at_times(&[first_time], &[]);
+            for _ in 0..3 {
+                edit_state.translate_selection(true, Vec2 { x: 5.0, y: 5.0 }, false);
+            }
+            edit_state.checkpoint_current_state();
+        });
+        let current = harness.map_state();
+        assert_eq!(object_changes(&start, &current), (1, 1, 0));
+
+        let t0 = Instant::now();
+        tracker.note_activity(t0);
+        tracker.note_activity(t0 + Duration::from_secs(30));
+        tracker.note_activity(t0 + Duration::from_secs(600));
+        tracker.note_activity(t0 + Duration::from_secs(615));
+        let session = tracker.session("Normal", &current).unwrap();
+        assert_eq!(session.active_ms, 45_000.0);
+        assert_eq!((session.placed, session.edited, session.removed), (1, 1, 0));
+
+        let totals = EditingTotals::of([session.clone(), session].iter());
+        assert_eq!((totals.sessions, totals.active_ms, totals.placed), (2, 90_000.0, 2));
+    }
+
+    #[test]
+    fn moving_an_object_back_is_not_an_edit() {
+        let harness = EditHarness::new(TEST_MAP);
+        let start = harness.map_state();
+        let first_time = harness.object_times()[0];
+        harness.edit(|edit_state| {
+            edit_state.select_objects_at_times(&[first_time], &[]);
+            edit_state.translate_selection(true, Vec2 { x: 5.0, y: 5.0 }, false);
+            edit_state.checkpoint_current_state();
+            edit_state.translate_selection(true, Vec2 { x: -5.0, y: -5.0 }, false);
+            edit_state.checkpoint_current_state();
+        });
+        assert_eq!(object_changes(&start, &harness.map_state()), (0, 0, 0));
+    }
+}
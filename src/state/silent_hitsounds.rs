@@ -0,0 +1,107 @@
+use crate::{
+    map_format::objects::{HitObject, HitsoundInfo},
+    treap::Treap,
+};
+
+use super::object::Object;
+
+// Ranking criteria treat anything at 5% or below as inaudible.
+const MIN_AUDIBLE_VOLUME: f64 = 0.05;
+const SAMPLE_EXTENSIONS: [&str; 3] = ["wav", "ogg", "mp3"];
+
+pub struct SilentHitsound {
+    pub time_ms: f64,
+    pub reason: String,
+}
+
+// Files a hitsound plays, as osu! resolves them from the beatmap folder. Samples that fall
+// back to the skin are left out, the skin is not part of the map.
+fn map_sample_candidates(hitsound: &HitsoundInfo) -> Vec<Vec<String>> {
+    if let Some(filename) = hitsound.filename.as_ref().filter(|f| !f.trim().is_empty()) {
+        return vec![vec![filename.clone()]];
+    }
+    if hitsound.index <= 0 {
+        return Vec::new();
+    }
+    let index = if hitsound.index == 1 {
+        String::new()
+    } else {
+        hitsound.index.to_string()
+    };
+    let mut sounds = vec![(&hitsound.hit_sampleset, "hitnormal")];
+    for (enabled, sound) in [
+        (hitsound.play_whistle, "hitwhistle"),
+        (hitsound.play_finish, "hitfinish"),
+        (hitsound.play_clap, "hitclap"),
+    ] {
+        if enabled {
+            sounds.push((&hitsound.additions_sampleset, sound));
+        }
+    }
+    sounds
+        .into_iter()
+        .map(|(sample_set, sound)| {
+            SAMPLE_EXTENSIONS
+                .iter()
+                .map(|ext| format!("{}-{}{}.{}", sample_set.file_prefix(), sound, index, ext))
+                .collect()
+        })
+        .collect()
+}
+
+// `sample_state(name)` returns None when the map has no such file, otherwise whether it
+// is blank. A hitsound is silent when every sound it plays resolves to a blank file.
+fn silent_reason(
+    hitsound: &HitsoundInfo,
+    sample_state: &mut impl FnMut(&str) -> Option<bool>,
+) -> Option<String> {
+    if hitsound.volume <= MIN_AUDIBLE_VOLUME {
+        return Some(format!("volume {:.0} pct", hitsound.volume * 100.0));
+    }
+    let mut blank_files = Vec::new();
+    for candidates in map_sample_candidates(hitsound) {
+        let resolved = candidates
+            .iter()
+            .find_map(|name| sample_state(name).map(|blank| (name, blank)));
+        match resolved {
+            Some((name, true)) => blank_files.push(name.clone()),
+            _ => return None,
+        }
+    }
+    if blank_files.is_empty() {
+        return None;
+    }
+    Some(format!("blank sample {}", blank_files.join(", ")))
+}
+
+pub fn find_silent_hitsounds(
+    objects: &Treap<Object>,
+    mut sample_state: impl FnMut(&str) -> Option<bool>,
+) -> Vec<SilentHitsound> {
+    let mut silent = Vec::new();
+    for object in objects.iter() {
+        match object.hit_object.as_ref() {
+            HitObject::Circle(c) => {
+                if let Some(reason) = silent_reason(&c.hitsound_info, &mut sample_state) {
+                    silent.push(SilentHitsound {
+                        time_ms: c.time,
+                        reason,
+                    });
+                }
+            }
+            HitObject::Slider(s) => {
+                for (i, hitsound) in s.hitsounds.iter().enumerate() {
+                    if let Some(reason) = silent_reason(hitsound, &mut sample_state) {
+                        silent.push(SilentHitsound {
+                            time_ms: s.time + i as f64 * s.slide_duration(),
+                            reason,
+                        });
+                    }
+                }
+            }
+            // Spinners keep the raw .osu hitsound and only sound at their end.
+            HitObject::Spinner(_) => {}
+        }
+    }
+    silent
+}
@@ -0,0 +1,35 @@
+use crate::{map_format::objects::HitObject, treap::Treap};
+
+use super::object::Object;
+
+pub struct SliderCurveWarning {
+    pub time_ms: f64,
+    pub reason: String,
+    pub diff_px: f64,
+}
+
+// Sliders whose curve osu!stable and osu!lazer may draw differently, see
+// `ControlPoints::client_divergences`, in map order.
+pub fn find_slider_curve_warnings(
+    objects: &Treap<Object>,
+    max_bezier_anchors: usize,
+    max_diff_px: f64,
+) -> Vec<SliderCurveWarning> {
+    let mut warnings = Vec::new();
+    for object in objects.iter() {
+        let HitObject::Slider(slider) = &*object.hit_object else {
+            continue;
+        };
+        for divergence in slider
+            .control_points
+            .client_divergences(max_bezier_anchors, max_diff_px)
+        {
+            warnings.push(SliderCurveWarning {
+                time_ms: slider.time,
+                reason: divergence.reason,
+                diff_px: divergence.diff_px,
+            });
+        }
+    }
+    warnings
+}
@@ -1,4 +1,4 @@
-use crate::geometry::vec2::Vec2;
+use crate::{config::Config, geometry::vec2::Vec2};
 
 use super::snap_position::SnapPosition;
 
@@ -95,3 +95,36 @@ impl SnapPositions {
         }
     }
 }
+
+// Which points of objects outside the selection are offered as snap targets. Selected
+// objects always offer all of their points, since those are what gets snapped.
+#[derive(Clone, Copy)]
+pub struct SnapSources {
+    pub object_centers: bool,
+    pub slider_control_points: bool,
+    pub slider_ends: bool,
+    // Visible objects reflected across the playfield's vertical and horizontal center lines.
+    pub mirrored_positions: bool,
+}
+
+impl SnapSources {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            object_centers: config.general.snap_to_object_centers,
+            slider_control_points: config.general.snap_to_slider_control_points,
+            slider_ends: config.general.snap_to_slider_ends,
+            mirrored_positions: config.general.snap_to_mirrored_positions,
+        }
+    }
+}
+
+impl Default for SnapSources {
+    fn default() -> Self {
+        Self {
+            object_centers: true,
+            slider_control_points: true,
+            slider_ends: true,
+            mirrored_positions: false,
+        }
+    }
+}
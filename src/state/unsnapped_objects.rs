@@ -0,0 +1,50 @@
+use std::sync::Arc;
+
+use crate::{map_format::timing::Timing, treap::Treap};
+
+use super::object::Object;
+
+// Further than this from every snap tick counts as unsnapped.
+const UNSNAPPED_TOLERANCE_MS: f64 = 1.0;
+
+// Indices of the objects whose start is off every tick of SNAP_DIVISORS, each with the tick
+// it would be resnapped to. Objects before the first red line have nothing to snap to and
+// are left out.
+pub fn find_unsnapped_objects(objects: &Treap<Object>, timing: &Timing) -> Vec<(usize, f64)> {
+    objects
+        .iter()
+        .enumerate()
+        .filter_map(|(id, object)| {
+            let time = object.hit_object.time();
+            let snapped = timing.nearest_snap(time)?;
+            ((snapped - time).abs() > UNSNAPPED_TOLERANCE_MS).then_some((id, snapped))
+        })
+        .collect()
+}
+
+pub struct UnsnappedObjectsCache {
+    objects: Treap<Object>,
+    timing: Option<Arc<Timing>>,
+    unsnapped: Vec<(usize, f64)>,
+}
+
+impl UnsnappedObjectsCache {
+    pub fn new() -> Self {
+        UnsnappedObjectsCache {
+            objects: Treap::new_empty(),
+            timing: None,
+            unsnapped: Vec::new(),
+        }
+    }
+
+    pub fn get(&mut self, objects: &Treap<Object>, timing: &Arc<Timing>) -> &[(usize, f64)] {
+        let is_valid = self.objects.ptr_eq(objects)
+            && self.timing.as_ref().is_some_and(|t| Arc::ptr_eq(t, timing));
+        if !is_valid {
+            self.unsnapped = find_unsnapped_objects(objects, timing);
+            self.objects = objects.clone();
+            self.timing = Some(timing.clone());
+        }
+        &self.unsnapped
+    }
+}
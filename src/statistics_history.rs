@@ -0,0 +1,148 @@
+use std::path::Path;
+use winit::event_loop::EventLoop;
+
+use crate::{
+    dialogue_app::DialogueApp,
+    files::{read_editing_sessions, read_map_statistics, scan_folder},
+    map_format::statistics::{EditingSession, EditingTotals, MapStatistics, format_active_time},
+};
+
+pub fn select_and_show_statistics(event_loop: &mut EventLoop<()>, selector: &mut DialogueApp) {
+    println!("Showing map statistics...");
+    let entries = scan_folder(Path::new("saves"), Some(true), None);
+    if entries.is_empty() {
+        println!("No maps found in saves/");
+        return;
+    }
+
+    println!("Available maps:");
+    let selection = match selector.select(event_loop, "Statistics of map", &entries) {
+        Some(idx) => idx,
+        None => {
+            println!("Map selection cancelled.");
+            return;
+        }
+    };
+    show_statistics(&entries[selection], event_loop, selector);
+}
+
+// Days since 1970-01-01 as year-month-day, from Howard Hinnant's civil_from_days.
+fn format_date(unix_seconds: u64) -> String {
+    let days = (unix_seconds / 86400) as i64 + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+fn format_drain(drain_ms: f64) -> String {
+    let seconds = (drain_ms / 1000.0).round() as u64;
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+fn editing_totals_label(difficulty: &str, totals: &EditingTotals) -> String {
+    format!(
+        "{} editing: {} over {} session(s), {} placed, {} edited, {} removed",
+        difficulty,
+        format_active_time(totals.active_ms),
+        totals.sessions,
+        totals.placed,
+        totals.edited,
+        totals.removed
+    )
+}
+
+// Editing time and changes of every difficulty with sessions, then of the whole map.
+fn editing_totals_labels(sessions: &[EditingSession]) -> Vec<String> {
+    let mut difficulties: Vec<&str> = sessions
+        .iter()
+        .map(|session| session.difficulty.as_str())
+        .collect();
+    difficulties.sort();
+    difficulties.dedup();
+    let mut labels: Vec<String> = difficulties
+        .iter()
+        .map(|difficulty| {
+            let totals = EditingTotals::of(
+                sessions
+                    .iter()
+                    .filter(|session| session.difficulty == *difficulty),
+            );
+            editing_totals_label(difficulty, &totals)
+        })
+        .collect();
+    if difficulties.len() > 1 {
+        labels.push(editing_totals_label("All", &EditingTotals::of(sessions.iter())));
+    }
+    labels
+}
+
+fn snapshot_label(snapshot: &MapStatistics, previous: Option<&MapStatistics>) -> String {
+    let mut label = format!(
+        "{} {}: {} objects ({}/{}/{}), drain {}, ~{:.2}*",
+        snapshot.difficulty,
+        format_date(snapshot.saved_at),
+        snapshot.object_count(),
+        snapshot.circles,
+        snapshot.sliders,
+        snapshot.spinners,
+        format_drain(snapshot.drain_ms),
+        snapshot.star_estimate
+    );
+    if let Some(previous) = previous {
+        let objects = snapshot.object_count() as i64 - previous.object_count() as i64;
+        let drain_seconds = ((snapshot.drain_ms - previous.drain_ms) / 1000.0).round();
+        label.push_str(&format!(
+            " | {:+} objects, {:+}s, {:+.2}*",
+            objects,
+            drain_seconds,
+            snapshot.star_estimate - previous.star_estimate
+        ));
+    }
+    label
+}
+
+// Editing totals, then every snapshot grouped by difficulty, oldest first, with the change
+// since the one before.
+fn show_statistics(map_dir_name: &str, event_loop: &mut EventLoop<()>, selector: &mut DialogueApp) {
+    let mut history = read_map_statistics(map_dir_name);
+    let sessions = read_editing_sessions(map_dir_name);
+    if history.is_empty() && sessions.is_empty() {
+        println!(
+            "No statistics recorded for {} yet, they are taken when it is saved or closed.",
+            map_dir_name
+        );
+        return;
+    }
+    history.sort_by(|a, b| {
+        a.difficulty
+            .cmp(&b.difficulty)
+            .then(a.saved_at.cmp(&b.saved_at))
+    });
+
+    let mut labels = editing_totals_labels(&sessions);
+    labels.extend(history.iter().enumerate().map(|(i, snapshot)| {
+        let previous = i
+            .checked_sub(1)
+            .map(|j| &history[j])
+            .filter(|previous| previous.difficulty == snapshot.difficulty);
+        snapshot_label(snapshot, previous)
+    }));
+    for label in labels.iter() {
+        println!("  {}", label);
+    }
+
+    let title = format!("Statistics of {} (circles/sliders/spinners)", map_dir_name);
+    loop {
+        match selector.select(event_loop, &title, &labels) {
+            Some(idx) => println!("{}", labels[idx]),
+            None => break,
+        }
+    }
+}
@@ -0,0 +1,388 @@
+use std::{
+    collections::HashMap,
+    io::Cursor,
+    sync::{Arc, RwLock},
+};
+
+use winit::dpi::PhysicalSize;
+
+use crate::{
+    audio::{AudioEngine, AudioEngineConfig},
+    config::Config,
+    dotosu::{
+        osr_file::{OsrFile, OsrFrame},
+        osu_file::parse_osu_file,
+    },
+    editor::EditorApp,
+    files::{AssetsFolder, BeatmapsetFolder},
+    geometry::vec2::Vec2,
+    gpu::gpu::GpuRenderer,
+    layout::{self, HudLayout},
+    map_format::{
+        beatmap::Beatmap, beatmapset::Beatmapset,
+        convert_from_osu_format::convert_osu_beatmapset_to_internal,
+        convert_to_osu_format::convert_internal_to_osu_format,
+        objects::HitObject,
+        statistics::EditingTotals,
+    },
+    skin::{Skin, Texture},
+    state::{EditState, MapState, TimelineDragMarks},
+};
+
+// Small map covering circles, a slider, a spinner, a break, kiai and combo colors.
+pub const TEST_MAP: &str = "osu file format v14
+
+[General]
+AudioFilename: audio.mp3
+AudioLeadIn: 0
+PreviewTime: -1
+Countdown: 0
+SampleSet: Soft
+StackLeniency: 0.7
+Mode: 0
+LetterboxInBreaks: 0
+EpilepsyWarning: 0
+WidescreenStoryboard: 0
+
+[Editor]
+Bookmarks: 1000,3000
+
+[Metadata]
+Title:Harness
+TitleUnicode:Harness
+Artist:Test
+ArtistUnicode:Test
+Creator:Test
+Version:Normal
+Source:
+Tags:
+BeatmapID:0
+BeatmapSetID:-1
+
+[Difficulty]
+HPDrainRate:5
+CircleSize:4
+OverallDifficulty:5
+ApproachRate:8
+SliderMultiplier:1.4
+SliderTickRate:1
+
+[Events]
+0,0,\"bg.png\",0,0
+2,5000,9000
+
+[TimingPoints]
+0,500,4,2,0,60,1,0
+2000,-100,4,2,0,60,0,1
+3000,-100,4,2,0,60,0,0
+
+[Colours]
+Combo1 : 255,128,0
+Combo2 : 0,202,0
+
+[HitObjects]
+256,192,1000,5,0,0:0:0:0:
+356,192,1500,1,2,0:0:0:0:
+100,100,2000,2,0,L|300:100,1,140,2|0,0:0|0:0,0:0:0:0:
+256,192,3000,12,0,4000,0:0:0:0:
+200,300,9500,5,0,0:0:0:0:
+";
+
+pub fn test_config() -> Config {
+    serde_json::from_str(include_str!("../config.json")).expect("config.json does not parse")
+}
+
+pub fn parse_test_map(osu_text: &str) -> (Beatmapset, Beatmap) {
+    let osu_file = parse_osu_file("harness.osu".to_string(), osu_text.as_bytes(), &mut |_| {
+        None
+    })
+    .expect("test map does not parse");
+    let (beatmapset, mut beatmaps) =
+        convert_osu_beatmapset_to_internal(&vec![osu_file]).expect("test map does not convert");
+    (beatmapset, beatmaps.remove(0))
+}
+
+pub fn object_positions(map_state: &MapState) -> Vec<Vec2> {
+    map_state
+        .objects
+        .iter()
+        .map(|object| match object.hit_object.as_ref() {
+            HitObject::Circle(c) => c.pos,
+            HitObject::Slider(s) => s.control_points.start,
+            HitObject::Spinner(sp) => Vec2 { x: sp.x, y: sp.y },
+        })
+        .collect()
+}
+
+// Edit state without audio or window. Edits go through EditState like they do in the
+// editor, so history and export behave the same.
+pub struct EditHarness {
+    pub edit_state: Arc<RwLock<EditState>>,
+    beatmapset: Beatmapset,
+    template: Beatmap,
+}
+
+impl EditHarness {
+    pub fn new(osu_text: &str) -> Self {
+        let (beatmapset, template) = parse_test_map(osu_text);
+        let map_state = MapState::from_beatmap(&template, Vec::new(), test_config());
+        EditHarness {
+            edit_state: EditState::new_headless(map_state),
+            beatmapset,
+            template,
+        }
+    }
+
+    pub fn edit<R>(&self, f: impl FnOnce(&mut EditState) -> R) -> R {
+        let mut edit_state = self.edit_state.write().expect("edit_state lock poisoned");
+        f(&mut edit_state)
+    }
+
+    pub fn map_state(&self) -> Arc<MapState> {
+        let edit_state = self.edit_state.read().expect("edit_state lock poisoned");
+        edit_state.current_map_state()
+    }
+
+    pub fn object_times(&self) -> Vec<f64> {
+        self.map_state()
+            .objects
+            .iter()
+            .map(|object| object.hit_object.time())
+            .collect()
+    }
+
+    // The .osu text the current state would be exported as.
+    pub fn export_osu_text(&self) -> String {
+        let beatmap = self.map_state().to_beatmap(&self.template);
+        convert_internal_to_osu_format(self.beatmapset.clone(), beatmap).to_osu_text()
+    }
+}
+
+impl Drop for EditHarness {
+    fn drop(&mut self) {
+        if let Ok(edit_state) = self.edit_state.read() {
+            edit_state.request_export_thread_stop();
+        }
+    }
+}
+
+fn placeholder_texture() -> Texture {
+    Texture {
+        rgba: vec![255, 255, 255, 255],
+        width: 1,
+        height: 1,
+        is_2x: false,
+    }
+}
+
+// Skin made of 1x1 white textures, enough for the renderer to build its pipelines.
+pub fn placeholder_skin() -> Skin {
+    Skin {
+        cursor: placeholder_texture(),
+        hit_circle: placeholder_texture(),
+        hit_circle_overlay: placeholder_texture(),
+        slider_start_circle: placeholder_texture(),
+        slider_start_circle_overlay: placeholder_texture(),
+        reverse_arrow: placeholder_texture(),
+        slider_ball: vec![placeholder_texture()],
+        slider_follow_circle: placeholder_texture(),
+        sliderend_circle: placeholder_texture(),
+        sliderend_circle_overlay: placeholder_texture(),
+        spinner_circle: placeholder_texture(),
+        follow_point: placeholder_texture(),
+        approach_circle: placeholder_texture(),
+        numbers: (0..10).map(|_| placeholder_texture()).collect(),
+        hitsounds: HashMap::new(),
+        combo_colors: Vec::new(),
+    }
+}
+
+// None when the machine has no usable GPU adapter, tests skip rendering then.
+pub fn headless_renderer(width: u32, height: u32) -> Option<GpuRenderer> {
+    match GpuRenderer::new_headless(
+        PhysicalSize::new(width, height),
+        test_config(),
+        placeholder_skin(),
+        placeholder_texture(),
+    ) {
+        Ok(gpu) => Some(gpu),
+        Err(err) => {
+            println!("No headless GPU available, skipping: {err}");
+            None
+        }
+    }
+}
+
+pub fn render_map_state(
+    gpu: &mut GpuRenderer,
+    map_state: &MapState,
+    width: u32,
+    height: u32,
+    time_ms: f64,
+) -> Result<(), wgpu::SurfaceError> {
+    let config = test_config();
+    let frame_layout = layout::compute_layout(
+        width as f64,
+        height as f64,
+        config.general.playfield_scale,
+        &HudLayout::from_config(&config.appearance.layout),
+    );
+    gpu.render(
+        &frame_layout,
+        &map_state.objects,
+        &map_state.combo_colors,
+        &map_state.break_times,
+        &map_state.kiai_times,
+        &map_state.bookmarks,
+        &map_state.red_lines,
+        &map_state.timing,
+        map_state.diff_settings.sv_multiplier,
+        &[],
+        &[],
+        time_ms,
+        10_000.0,
+        0.0,
+        0,
+        None,
+        None,
+        (0, 0, 0),
+        None,
+        false,
+        "",
+        &[],
+        &[],
+        60.0,
+        60.0,
+        1.0,
+        config.audio.sound_volume,
+        config.audio.hitsound_volume,
+        &config,
+        false,
+        false,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        [0.0, 0.0],
+        [0.0, 0.0],
+        false,
+        false,
+        false,
+        false,
+        false,
+        false,
+        false,
+        false,
+        [0.0, 0.0],
+        "",
+        false,
+        false,
+        false,
+        false,
+        false,
+        false,
+        None,
+        None,
+        false,
+        false,
+        1.0,
+        1.0,
+        0.0,
+        0.0,
+        false,
+        false,
+        false,
+        false,
+        &[],
+        &[],
+        false,
+        1.0,
+        None,
+        map_state.diff_settings.hit_windows_ms(),
+        map_state.diff_settings.tick_rate,
+        [0.0; 4],
+        None,
+        (0.0, 10_000.0),
+        None,
+        &[],
+        "",
+        &[],
+        &EditingTotals::default(),
+        &[],
+        &TimelineDragMarks::default(),
+    )
+}
+
+fn placeholder_png() -> Vec<u8> {
+    let mut bytes = Vec::new();
+    image::RgbaImage::from_pixel(1, 1, image::Rgba([0, 0, 0, 255]))
+        .write_to(&mut Cursor::new(&mut bytes), image::ImageFormat::Png)
+        .expect("png encoding failed");
+    bytes
+}
+
+// The full editor without a window. Needs an audio output device, None without one.
+pub fn headless_editor(osu_text: &str) -> Option<EditorApp> {
+    let audio = match AudioEngine::new(AudioEngineConfig {
+        queue_ms: 60,
+        preferred_buffer_frames: 128,
+        fix_pitch: false,
+    }) {
+        Ok(audio) => Arc::new(audio),
+        Err(err) => {
+            println!("No audio device available, skipping: {err}");
+            return None;
+        }
+    };
+    let (beatmapset, beatmap) = parse_test_map(osu_text);
+    let mut assets = AssetsFolder::default();
+    assets.insert(beatmap.events.background_name(), placeholder_png());
+    let mut hitsound_indices: HashMap<String, usize> = HashMap::new();
+    for sampleset in ["normal", "soft", "drum"] {
+        for sound in ["hitnormal", "hitwhistle", "hitfinish", "hitclap"] {
+            let index = hitsound_indices.len();
+            hitsound_indices.insert(format!("{}-{}.wav", sampleset, sound), index);
+        }
+    }
+    EditorApp::new(
+        BeatmapsetFolder {
+            map_dir_name: "harness".to_string(),
+            beatmapset,
+            beatmaps: vec![beatmap],
+            assets,
+        },
+        test_config(),
+        placeholder_skin(),
+        audio,
+        hitsound_indices,
+        0,
+    )
+}
+
+// A replay of the given cursor frames, (time, x, y, keys) each, with nothing else set.
+pub fn test_replay(mods: u32, frames: &[(f64, f64, f64, u32)]) -> OsrFile {
+    OsrFile {
+        mode: 0,
+        player: "Tester".to_string(),
+        count_300: 0,
+        count_100: 0,
+        count_50: 0,
+        count_miss: 0,
+        max_combo: 0,
+        mods,
+        frames: frames
+            .iter()
+            .map(|&(time_ms, x, y, keys)| OsrFrame {
+                time_ms,
+                pos: Vec2 { x, y },
+                keys,
+            })
+            .collect(),
+    }
+}
@@ -0,0 +1,78 @@
+// Counted in characters rather than bytes, so CJK input gets as much room as ASCII.
+const MAX_CHARS: usize = 40;
+
+// The editor's single line text field. Typed, pasted and IME committed text all go through
+// `insert`; the IME's uncommitted composition is kept apart and only shown after the text.
+pub struct TextInput {
+    text: String,
+    preedit: String,
+}
+
+impl TextInput {
+    pub fn new() -> Self {
+        TextInput {
+            text: String::new(),
+            preedit: String::new(),
+        }
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn clear(&mut self) {
+        self.text.clear();
+        self.preedit.clear();
+    }
+
+    pub fn insert(&mut self, text: &str) {
+        let mut len = self.text.chars().count();
+        for ch in text.chars() {
+            if len >= MAX_CHARS {
+                break;
+            }
+            if ch.is_control() {
+                continue;
+            }
+            self.text.push(ch);
+            len += 1;
+        }
+    }
+
+    pub fn backspace(&mut self) {
+        self.text.pop();
+    }
+
+    // While the IME composes, its keys must not also edit the text.
+    pub fn is_composing(&self) -> bool {
+        !self.preedit.is_empty()
+    }
+
+    pub fn set_preedit(&mut self, preedit: &str) {
+        self.preedit = preedit.chars().filter(|ch| !ch.is_control()).collect();
+    }
+
+    pub fn display_text(&self) -> String {
+        format!("{}{}", self.text, self.preedit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn text_input_counts_characters_and_shows_the_composition() {
+        let mut input = TextInput::new();
+        input.insert(&"譜面".repeat(25));
+        assert_eq!(input.text().chars().count(), 40);
+        input.clear();
+        input.insert("名前\n");
+        input.set_preedit("へんこう");
+        assert!(input.is_composing());
+        assert_eq!(input.text(), "名前");
+        assert_eq!(input.display_text(), "名前へんこう");
+        input.backspace();
+        assert_eq!(input.text(), "名");
+    }
+}
@@ -0,0 +1,141 @@
+use crate::map_format::timing::Timing;
+
+// A time in every format the stats box shows and copies: mm:ss.mmm as modders write
+// timestamps, raw milliseconds, and measure:beat:tick.
+pub fn time_formats(time_ms: f64, timing: &Timing) -> [String; 3] {
+    [
+        format_clock_time(time_ms),
+        format!("{}", time_ms.round() as i64),
+        format_measure_beat_tick(time_ms, timing),
+    ]
+}
+
+pub fn format_clock_time(time_ms: f64) -> String {
+    let ms = time_ms.round() as i64;
+    let sign = if ms < 0 { "-" } else { "" };
+    let ms = ms.unsigned_abs();
+    format!("{}{:02}:{:02}.{:03}", sign, ms / 60_000, ms / 1000 % 60, ms % 1000)
+}
+
+// "-" before the first red line.
+pub fn format_measure_beat_tick(time_ms: f64, timing: &Timing) -> String {
+    match timing.measure_beat_tick(time_ms) {
+        Some((measure, beat, tick)) => format!("{}:{}:{:02}", measure, beat, tick),
+        None => "-".to_string(),
+    }
+}
+
+// A modding timestamp like `01:23:456 (1,2,3) - comment`: the time and the combo numbers of
+// the objects it points at, starting from the one at that time.
+#[derive(Debug, PartialEq)]
+pub struct ModdingTimestamp {
+    pub time_ms: f64,
+    pub combo_numbers: Vec<u32>,
+}
+
+// The first modding timestamp in `text`. Entries of the list that are not combo numbers, like
+// mania's `time|column`, are skipped.
+pub fn parse_modding_timestamp(text: &str) -> Option<ModdingTimestamp> {
+    let (start, time_ms) = text.char_indices().find_map(|(start, _)| {
+        let token = text[start..].split(|ch: char| ch.is_whitespace() || ch == '(').next()?;
+        Some((start + token.len(), parse_modding_clock(token)?))
+    })?;
+    let rest = text[start..].trim_start();
+    let combo_numbers = match rest.strip_prefix('(').and_then(|list| list.split_once(')')) {
+        Some((list, _)) => list
+            .split(',')
+            .filter_map(|entry| entry.trim().parse::<u32>().ok())
+            .collect(),
+        None => Vec::new(),
+    };
+    Some(ModdingTimestamp {
+        time_ms,
+        combo_numbers,
+    })
+}
+
+// The way modders refer to objects, `01:23:456 (1,2,3)`.
+pub fn format_modding_timestamp(time_ms: f64, combo_numbers: &[u32]) -> String {
+    let ms = time_ms.max(0.0).round() as u64;
+    let clock = format!("{:02}:{:02}:{:03}", ms / 60_000, ms / 1000 % 60, ms % 1000);
+    if combo_numbers.is_empty() {
+        return clock;
+    }
+    let numbers: Vec<String> = combo_numbers.iter().map(|n| n.to_string()).collect();
+    format!("{} ({})", clock, numbers.join(","))
+}
+
+// mm:ss:mmm, any number of minute digits.
+fn parse_modding_clock(token: &str) -> Option<f64> {
+    let mut parts = token.split(':');
+    let (minutes, seconds, millis) = (parts.next()?, parts.next()?, parts.next()?);
+    let all_digits = |part: &str| !part.is_empty() && part.bytes().all(|b| b.is_ascii_digit());
+    if parts.next().is_some()
+        || !all_digits(minutes)
+        || seconds.len() != 2
+        || !all_digits(seconds)
+        || millis.len() != 3
+        || !all_digits(millis)
+    {
+        return None;
+    }
+    let minutes: f64 = minutes.parse().ok()?;
+    let seconds: f64 = seconds.parse().ok()?;
+    let millis: f64 = millis.parse().ok()?;
+    Some(minutes * 60_000.0 + seconds * 1000.0 + millis)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_harness::{EditHarness, TEST_MAP, parse_test_map};
+
+    #[test]
+    fn times_are_shown_as_clock_ms_and_measure_beat_tick() {
+        let (_, beatmap) = parse_test_map(TEST_MAP);
+        let timing = beatmap.timing;
+        assert_eq!(timing.measure_beat_tick(0.0), Some((1, 1, 0)));
+        assert_eq!(timing.measure_beat_tick(1250.0), Some((1, 3, 24)));
+        assert_eq!(timing.measure_beat_tick(2000.0), Some((2, 1, 0)));
+        assert_eq!(timing.measure_beat_tick(-100.0), None);
+        assert_eq!(
+            time_formats(61_250.0, &timing),
+            ["01:01.250".to_string(), "61250".to_string(), "31:3:24".to_string()]
+        );
+    }
+
+    #[test]
+    fn modding_timestamps_select_their_combo_numbers() {
+        let timestamp = parse_modding_timestamp("see 00:01:500 (2,3) - too far apart");
+        assert_eq!(
+            timestamp,
+            Some(ModdingTimestamp {
+                time_ms: 1500.0,
+                combo_numbers: vec![2, 3],
+            })
+        );
+        assert_eq!(parse_modding_timestamp("01:23.456"), None);
+
+        let harness = EditHarness::new(TEST_MAP);
+        let (selected, times) = harness.edit(|edit_state| {
+            let selected = edit_state.select_timestamp_objects(1500.0, &[2, 3]);
+            (selected, edit_state.selected_object_times(true))
+        });
+        assert_eq!(selected, 2);
+        assert_eq!(times, vec![1500.0, 2000.0]);
+    }
+
+    #[test]
+    fn selection_timestamp_lists_the_combo_numbers() {
+        let harness = EditHarness::new(TEST_MAP);
+        let timestamp = harness.edit(|edit_state| {
+            edit_state.select_objects_at_times(&[1500.0, 2000.0], &[3000.0]);
+            edit_state.selection_timestamp()
+        });
+        let (time_ms, combo_numbers) = timestamp.expect("objects are selected");
+        assert_eq!(
+            format_modding_timestamp(time_ms, &combo_numbers),
+            "00:01:500 (2,3,1)"
+        );
+    }
+}
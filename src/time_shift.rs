@@ -0,0 +1,584 @@
+use std::{collections::HashMap, fs, io::Cursor, path::Path};
+
+use image::{DynamicImage, ImageFormat, Rgba, RgbaImage};
+use winit::event_loop::EventLoop;
+
+use crate::{
+    audio::{Waveform, detect_onsets_in_file},
+    dialogue_app::DialogueApp,
+    files::{
+        BeatmapsetFolder, find_imported_diff, open_beatmapset_folder, scan_folder, write_beatmap,
+        write_beatmapset_folder, write_beatmapset_json, write_bytes_to_file,
+    },
+    map_format::{
+        beatmap::Beatmap, events::Event, objects::HitObject,
+        slider_timing::fit_slider_velocities, timing::{Timing, TimingPoint},
+    },
+};
+
+const ALIGN_BIN_MS: f64 = 5.0;
+const ALIGN_WINDOW_MS: f64 = 60_000.0;
+const MAX_ALIGN_OFFSET_MS: f64 = 60_000.0;
+const ALIGN_MATCH_TOLERANCE_MS: f64 = 20.0;
+const PREVIEW_WIDTH: u32 = 1024;
+const PREVIEW_LANE_HEIGHT: u32 = 64;
+
+pub struct TimeShiftScope {
+    pub timing_points: bool,
+    pub bookmarks: bool,
+    pub breaks: bool,
+}
+
+// t -> t * stretch + offset_ms for every time at or after `from_ms`, earlier times stay.
+pub struct TimeRemap {
+    pub from_ms: f64,
+    pub offset_ms: f64,
+    pub stretch: f64,
+}
+
+impl TimeRemap {
+    // Everything moved by `offset_ms`, for fixing the offset of a whole map.
+    pub fn shifted(offset_ms: f64) -> Self {
+        TimeRemap {
+            from_ms: f64::NEG_INFINITY,
+            offset_ms,
+            stretch: 1.0,
+        }
+    }
+
+    // Every BPM multiplied by `factor`: the first red line stays and everything after it moves
+    // closer or further so it stays on the same beat. None without red lines.
+    pub fn bpm_scaled(timing: &Timing, factor: f64) -> Option<Self> {
+        let first_red_line = timing.timing_points.iter().find_map(|tp| match tp {
+            TimingPoint::RedLine(rl) => Some(rl.time),
+            _ => None,
+        })?;
+        Some(TimeRemap {
+            from_ms: first_red_line,
+            offset_ms: first_red_line - first_red_line / factor,
+            stretch: 1.0 / factor,
+        })
+    }
+
+    fn applies(&self, time: f64) -> bool {
+        time >= self.from_ms
+    }
+
+    pub fn apply(&self, time: f64) -> f64 {
+        if self.applies(time) {
+            time * self.stretch + self.offset_ms
+        } else {
+            time
+        }
+    }
+}
+
+// Moves every object starting at or after `remap.from_ms`, plus the timing points and breaks
+// after it if `scope` says so. Durations and beat lengths follow the stretch.
+// Returns the number of objects moved.
+pub fn remap_beatmap(beatmap: &mut Beatmap, remap: &TimeRemap, scope: &TimeShiftScope) -> usize {
+    if scope.timing_points {
+        for timing_point in beatmap.timing.timing_points.iter_mut() {
+            match timing_point {
+                TimingPoint::RedLine(rl) if remap.applies(rl.time) => {
+                    rl.time = remap.apply(rl.time);
+                    rl.beat_length *= remap.stretch;
+                }
+                TimingPoint::GreenLine(gl) if remap.applies(gl.time) => {
+                    gl.time = remap.apply(gl.time);
+                }
+                _ => {}
+            }
+        }
+        beatmap
+            .timing
+            .timing_points
+            .sort_by(|a, b| a.time().total_cmp(&b.time()));
+    }
+
+    // Stretched sliders keep their duration at whatever velocity the timing has where they
+    // end up, which is the same one when the timing was stretched along.
+    let (mut moved_objects, kept): (Vec<HitObject>, Vec<HitObject>) =
+        std::mem::take(&mut beatmap.objects.objects)
+            .into_iter()
+            .partition(|object| remap.applies(object.time()));
+    let moved = moved_objects.len();
+    for object in moved_objects.iter_mut() {
+        object.retime(remap.apply(object.time()), remap.stretch);
+    }
+    fit_slider_velocities(
+        &mut moved_objects,
+        &beatmap.timing,
+        beatmap.diff_settings.sv_multiplier,
+    );
+    beatmap.objects.objects = kept;
+    beatmap.objects.objects.extend(moved_objects);
+    beatmap
+        .objects
+        .objects
+        .sort_by(|a, b| a.time().total_cmp(&b.time()));
+
+    if scope.breaks {
+        for event in beatmap.events.events.iter_mut() {
+            if let Event::Break(br) = event {
+                if remap.applies(br.start_time) {
+                    br.start_time = remap.apply(br.start_time);
+                    br.end_time = remap.apply(br.end_time);
+                }
+            }
+        }
+    }
+    moved
+}
+
+// Rewrites the Bookmarks line of an .osu file. None if it has no bookmarks.
+fn remap_bookmarks(osu_text: &str, remap: &TimeRemap) -> Option<String> {
+    let mut found = false;
+    let lines: Vec<String> = osu_text
+        .lines()
+        .map(|line| {
+            let Some(value) = line.trim_start().strip_prefix("Bookmarks:") else {
+                return line.to_string();
+            };
+            found = true;
+            let bookmarks: Vec<String> = value
+                .split(',')
+                .filter_map(|chunk| chunk.trim().parse::<f64>().ok())
+                .map(|ms| format!("{}", remap.apply(ms).round() as i64))
+                .collect();
+            format!("Bookmarks: {}", bookmarks.join(","))
+        })
+        .collect();
+    if !found {
+        return None;
+    }
+    let line_ending = if osu_text.contains("\r\n") { "\r\n" } else { "\n" };
+    let mut text = lines.join(line_ending);
+    if osu_text.ends_with('\n') {
+        text.push_str(line_ending);
+    }
+    Some(text)
+}
+
+// Empty input gives `default` if there is one.
+fn prompt_number(
+    event_loop: &mut EventLoop<()>,
+    selector: &mut DialogueApp,
+    title: &str,
+    prompt: &str,
+    default: Option<f64>,
+) -> Option<f64> {
+    let text = selector.prompt_text(event_loop, title, prompt)?;
+    if text.trim().is_empty() {
+        return default;
+    }
+    match text.trim().parse::<f64>() {
+        Ok(value) if value.is_finite() => Some(value),
+        _ => {
+            println!("{} is not a number.", text.trim());
+            None
+        }
+    }
+}
+
+fn select_map(
+    event_loop: &mut EventLoop<()>,
+    selector: &mut DialogueApp,
+    title: &str,
+) -> Option<BeatmapsetFolder> {
+    let saves_path = Path::new("saves");
+    if !saves_path.exists() {
+        println!("No saves/ directory found.");
+        return None;
+    }
+    let entries = scan_folder(saves_path, Some(true), None);
+    if entries.is_empty() {
+        println!("No maps found in saves/");
+        return None;
+    }
+    let selection = selector.select(event_loop, title, &entries)?;
+    let folder = open_beatmapset_folder(&entries[selection]);
+    if folder.is_none() {
+        println!("Failed to open beatmapset folder for {}", entries[selection]);
+    }
+    folder
+}
+
+// Applies `remap` to the chosen difficulties (all if `diff_idx` is None), the caller saves them.
+// The preview point belongs to the song, so it only moves along with every difficulty.
+fn remap_folder(
+    folder: &mut BeatmapsetFolder,
+    diff_idx: Option<usize>,
+    remap: &TimeRemap,
+    scope: &TimeShiftScope,
+) {
+    for (idx, beatmap) in folder.beatmaps.iter_mut().enumerate() {
+        if diff_idx.is_some_and(|diff_idx| diff_idx != idx) {
+            continue;
+        }
+        // Objects moved earlier can land on the ones that stay.
+        let moved_start = remap.apply(remap.from_ms);
+        if moved_start < remap.from_ms {
+            let overlapping = beatmap
+                .objects
+                .objects
+                .iter()
+                .filter(|object| object.time() >= moved_start && object.time() < remap.from_ms)
+                .count();
+            if overlapping > 0 {
+                println!(
+                    "{}: {} object(s) before {:.0}ms are now overlapped by shifted ones.",
+                    beatmap.version, overlapping, remap.from_ms
+                );
+            }
+        }
+        let moved = remap_beatmap(beatmap, remap, scope);
+        println!("{}: moved {} object(s).", beatmap.version, moved);
+
+        if scope.bookmarks {
+            let imported = find_imported_diff(&folder.map_dir_name, &beatmap.version);
+            if let Some((path, osu_text)) = imported {
+                if let Some(text) = remap_bookmarks(&osu_text, remap) {
+                    if let Err(err) = write_bytes_to_file(&path, text.as_bytes()) {
+                        println!("Failed to update bookmarks in {}: {}", path.display(), err);
+                    }
+                }
+            }
+        }
+    }
+
+    let preview_time = folder.beatmapset.preview_time;
+    if diff_idx.is_none() && preview_time >= 0 && remap.applies(preview_time as f64) {
+        folder.beatmapset.preview_time = remap.apply(preview_time as f64).round() as i64;
+    }
+}
+
+// Shifts a section of one or all difficulties, e.g. after a new audio cut changed the intro.
+pub fn select_and_shift_map(event_loop: &mut EventLoop<()>, selector: &mut DialogueApp) {
+    let Some(mut folder) = select_map(event_loop, selector, "Shift objects of map") else {
+        return;
+    };
+
+    let mut options: Vec<String> = folder.beatmaps.iter().map(|b| b.version.clone()).collect();
+    options.push("all difficulties".to_string());
+    let Some(diff_selection) = selector.select(event_loop, "Shift which difficulty", &options)
+    else {
+        return;
+    };
+    let diff_idx = (diff_selection < folder.beatmaps.len()).then_some(diff_selection);
+
+    let title = "Shift objects";
+    let Some(from_ms) =
+        prompt_number(event_loop, selector, title, "Shift everything from this time (ms)", None)
+    else {
+        return;
+    };
+    let Some(delta_ms) =
+        prompt_number(event_loop, selector, title, "Shift by (ms, negative is earlier)", None)
+    else {
+        return;
+    };
+    if delta_ms == 0.0 {
+        return;
+    }
+    let scope = TimeShiftScope {
+        timing_points: selector.confirm(event_loop, "Shift timing points too?"),
+        bookmarks: selector.confirm(event_loop, "Shift bookmarks too?"),
+        breaks: selector.confirm(event_loop, "Shift breaks too?"),
+    };
+    let remap = TimeRemap {
+        from_ms,
+        offset_ms: delta_ms,
+        stretch: 1.0,
+    };
+    remap_folder(&mut folder, diff_idx, &remap, &scope);
+    let saved = match diff_idx {
+        Some(diff_idx) => write_beatmap(&folder.map_dir_name, &folder.beatmaps[diff_idx]),
+        None => write_beatmapset_folder(&folder),
+    };
+    if !saved {
+        println!("Failed to save the shifted map.");
+    }
+}
+
+// Most common difference between onsets of `old` inside the window and nearby onsets of `new`.
+fn dominant_offset(old: &[f64], new: &[f64], window_start_ms: f64) -> Option<f64> {
+    let mut votes: HashMap<i64, usize> = HashMap::new();
+    for &t in old
+        .iter()
+        .filter(|t| **t >= window_start_ms && **t < window_start_ms + ALIGN_WINDOW_MS)
+    {
+        let lo = new.partition_point(|n| *n < t - MAX_ALIGN_OFFSET_MS);
+        let hi = new.partition_point(|n| *n <= t + MAX_ALIGN_OFFSET_MS);
+        for &n in &new[lo..hi] {
+            *votes.entry(((n - t) / ALIGN_BIN_MS).round() as i64).or_default() += 1;
+        }
+    }
+    // Neighbouring bins count too so an offset on a bin edge isn't split in two.
+    let score = |bin: i64| {
+        (bin - 1..=bin + 1)
+            .map(|b| votes.get(&b).copied().unwrap_or(0))
+            .sum::<usize>()
+    };
+    votes
+        .keys()
+        .copied()
+        .max_by_key(|bin| (score(*bin), votes[bin]))
+        .map(|bin| bin as f64 * ALIGN_BIN_MS)
+}
+
+// Share of `old` onsets that have a `new` onset where `remap` puts them.
+fn match_ratio(old: &[f64], new: &[f64], remap: &TimeRemap) -> f64 {
+    if old.is_empty() {
+        return 0.0;
+    }
+    let matched = old
+        .iter()
+        .filter(|t| {
+            let mapped = remap.apply(**t);
+            let idx = new.partition_point(|n| *n < mapped - ALIGN_MATCH_TOLERANCE_MS);
+            new.get(idx)
+                .is_some_and(|n| *n <= mapped + ALIGN_MATCH_TOLERANCE_MS)
+        })
+        .count();
+    matched as f64 / old.len() as f64
+}
+
+// Offset and stretch that map the old audio's timeline onto the new one, measured near the
+// start and near the end of the old audio.
+fn estimate_alignment(old: &[f64], new: &[f64]) -> Option<TimeRemap> {
+    let (first, last) = (*old.first()?, *old.last()?);
+    let start_offset = dominant_offset(old, new, first)?;
+    let start_center = first + ALIGN_WINDOW_MS * 0.5;
+    let end_window_start = last - ALIGN_WINDOW_MS;
+    let mut stretch = 1.0;
+    if end_window_start > first + ALIGN_WINDOW_MS {
+        if let Some(end_offset) = dominant_offset(old, new, end_window_start) {
+            let end_center = end_window_start + ALIGN_WINDOW_MS * 0.5;
+            if (end_offset - start_offset).abs() > ALIGN_BIN_MS {
+                stretch += (end_offset - start_offset) / (end_center - start_center);
+            }
+        }
+    }
+    Some(TimeRemap {
+        from_ms: f64::NEG_INFINITY,
+        offset_ms: start_center + start_offset - start_center * stretch,
+        stretch,
+    })
+}
+
+// The old audio's waveform above the new one's, both along the old audio's timeline: the new
+// one is read where `remap` puts each moment of the old one, so when it is right the peaks
+// of both lanes line up. PNG encoded for the dialogue.
+fn alignment_preview(old: &Waveform, new: &Waveform, remap: &TimeRemap) -> Vec<u8> {
+    let mut canvas = RgbaImage::from_pixel(
+        PREVIEW_WIDTH,
+        PREVIEW_LANE_HEIGHT * 2,
+        Rgba([16, 16, 20, 255]),
+    );
+    let column_ms = old.len_ms() / PREVIEW_WIDTH as f64;
+    let half_lane = PREVIEW_LANE_HEIGHT / 2;
+    for x in 0..PREVIEW_WIDTH {
+        let from_ms = x as f64 * column_ms;
+        let to_ms = from_ms + column_ms;
+        let lanes = [
+            (0, old.peak_between(from_ms, to_ms), Rgba([110, 160, 255, 255])),
+            (
+                PREVIEW_LANE_HEIGHT,
+                new.peak_between(remap.apply(from_ms), remap.apply(to_ms)),
+                Rgba([255, 165, 80, 255]),
+            ),
+        ];
+        for (lane_top, peak, color) in lanes {
+            let extent = ((peak.min(1.0) * half_lane as f32) as u32).min(half_lane - 1);
+            let center = lane_top + half_lane;
+            for y in center - extent..=center + extent {
+                canvas.put_pixel(x, y, color);
+            }
+        }
+    }
+    let mut bytes = Vec::new();
+    let _ = DynamicImage::ImageRgba8(canvas)
+        .write_to(&mut Cursor::new(&mut bytes), ImageFormat::Png);
+    bytes
+}
+
+fn extension_of(name: &str) -> Option<&str> {
+    Path::new(name).extension().and_then(|ext| ext.to_str())
+}
+
+// Swaps the map's audio for a new cut from imports/ and moves every difficulty along with it.
+pub fn select_and_replace_audio(event_loop: &mut EventLoop<()>, selector: &mut DialogueApp) {
+    let Some(mut folder) = select_map(event_loop, selector, "Replace audio of map") else {
+        return;
+    };
+    let audio_files = scan_folder(
+        Path::new("imports"),
+        Some(false),
+        Some(&vec![".mp3", ".ogg", ".wav", ".flac"]),
+    );
+    if audio_files.is_empty() {
+        println!("No audio files found in imports/");
+        return;
+    }
+    let Some(selection) = selector.select(event_loop, "Select the new audio", &audio_files) else {
+        return;
+    };
+    let new_name = audio_files[selection].clone();
+    let new_bytes = match fs::read(Path::new("imports").join(&new_name)) {
+        Ok(bytes) => bytes,
+        Err(err) => {
+            println!("Failed to read imports/{}: {}", new_name, err);
+            return;
+        }
+    };
+    let old_name = folder.beatmapset.audio_filename.clone();
+    let Some(old_bytes) = folder.assets.get(&old_name).cloned() else {
+        println!("Audio file '{}' not found in beatmap assets.", old_name);
+        return;
+    };
+
+    println!("Detecting onsets in {} and {}...", old_name, new_name);
+    let (Some(old_onsets), Some(new_onsets)) = (
+        detect_onsets_in_file(old_bytes.clone(), extension_of(&old_name)),
+        detect_onsets_in_file(new_bytes.clone(), extension_of(&new_name)),
+    ) else {
+        println!("Failed to decode one of the audio files.");
+        return;
+    };
+    let (Some(old_waveform), Some(new_waveform)) = (
+        Waveform::from_file(old_bytes, extension_of(&old_name)),
+        Waveform::from_file(new_bytes.clone(), extension_of(&new_name)),
+    ) else {
+        println!("Failed to decode one of the audio files.");
+        return;
+    };
+    let suggested = estimate_alignment(&old_onsets, &new_onsets);
+    if suggested.is_none() {
+        println!("Could not estimate an alignment, enter it manually.");
+    }
+    let mut remap = suggested.unwrap_or_else(|| TimeRemap::shifted(0.0));
+
+    // Both waveforms are shown lined up with the current offset and stretch until it is
+    // applied, so a wrong suggestion shows before the map is moved.
+    let title = "Replace audio";
+    loop {
+        let question = format!(
+            "Onsets lined up: {:.0}%",
+            match_ratio(&old_onsets, &new_onsets, &remap) * 100.0
+        );
+        let options = vec![
+            format!("Apply {:+.0}ms x{:.4}", remap.offset_ms, remap.stretch),
+            "Change offset/stretch".to_string(),
+            "Cancel".to_string(),
+        ];
+        let preview = alignment_preview(&old_waveform, &new_waveform, &remap);
+        match selector.select_with_preview(event_loop, &question, &preview, &options) {
+            Some(0) => break,
+            Some(1) => {}
+            _ => return,
+        }
+        let Some(offset_ms) = prompt_number(
+            event_loop,
+            selector,
+            title,
+            "Offset in ms (empty keeps the current one)",
+            Some(remap.offset_ms),
+        ) else {
+            continue;
+        };
+        let Some(stretch) = prompt_number(
+            event_loop,
+            selector,
+            title,
+            "Stretch factor (empty keeps the current one)",
+            Some(remap.stretch),
+        ) else {
+            continue;
+        };
+        if stretch <= 0.0 {
+            println!("The stretch factor has to be positive.");
+            continue;
+        }
+        remap = TimeRemap {
+            from_ms: f64::NEG_INFINITY,
+            offset_ms,
+            stretch,
+        };
+    }
+
+    // The new cut gets a name of its own so the current audio stays as it is, and the set
+    // only switches to it once every remapped difficulty is saved.
+    let new_name = folder.assets.free_name_for(&new_name, &new_bytes);
+    let assets_path = Path::new("saves").join(&folder.map_dir_name).join("assets");
+    if let Err(err) = write_bytes_to_file(&assets_path.join(&new_name), &new_bytes) {
+        println!("Failed to copy {} into the map: {}", new_name, err);
+        return;
+    }
+    let scope = TimeShiftScope {
+        timing_points: true,
+        bookmarks: true,
+        breaks: true,
+    };
+    remap_folder(&mut folder, None, &remap, &scope);
+    if !folder.beatmaps.iter().all(|beatmap| write_beatmap(&folder.map_dir_name, beatmap)) {
+        println!("Failed to save the remapped difficulties, the map still uses {}.", old_name);
+        return;
+    }
+    folder.beatmapset.audio_filename = new_name.clone();
+    if !write_beatmapset_json(&folder) {
+        println!("Failed to switch the map's audio to {}.", new_name);
+        return;
+    }
+    println!(
+        "Replaced {} with {}. The old file stays in the map's assets.",
+        old_name, new_name
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        map_format::slider_timing::find_desynced_sliders,
+        test_harness::{TEST_MAP, parse_test_map},
+    };
+
+    fn timing_and_object_times(beatmap: &Beatmap) -> (Vec<f64>, Vec<f64>) {
+        (
+            beatmap.timing.timing_points.iter().map(|tp| tp.time()).collect(),
+            beatmap.objects.objects.iter().map(|object| object.time()).collect(),
+        )
+    }
+
+    #[test]
+    fn timing_tools_move_objects_and_later_timing_points_along() {
+        let scope = TimeShiftScope {
+            timing_points: true,
+            bookmarks: true,
+            breaks: true,
+        };
+        let (_, mut shifted) = parse_test_map(TEST_MAP);
+        remap_beatmap(&mut shifted, &TimeRemap::shifted(10.0), &scope);
+        assert_eq!(
+            timing_and_object_times(&shifted),
+            (
+                vec![10.0, 2010.0, 3010.0],
+                vec![1010.0, 1510.0, 2010.0, 3010.0, 9510.0]
+            )
+        );
+
+        let (_, mut scaled) = parse_test_map(TEST_MAP);
+        let remap = TimeRemap::bpm_scaled(&scaled.timing, 2.0).unwrap();
+        remap_beatmap(&mut scaled, &remap, &scope);
+        assert_eq!(
+            timing_and_object_times(&scaled),
+            (vec![0.0, 1000.0, 1500.0], vec![500.0, 750.0, 1000.0, 1500.0, 4750.0])
+        );
+        assert!(matches!(
+            &scaled.timing.timing_points[0],
+            TimingPoint::RedLine(rl) if rl.beat_length == 250.0
+        ));
+        assert!(find_desynced_sliders(&scaled).is_empty());
+    }
+}
@@ -0,0 +1,113 @@
+use crate::{config::Config, layout::Rect};
+
+// The bottom timeline never zooms in further than this.
+pub const MIN_BOTTOM_TIMELINE_SPAN_MS: f64 = 2000.0;
+
+// The map time range the top timeline shows around `time_ms`: its width in object radii, each
+// radius worth milliseconds_per_object_radius / zoom, with the playhead at
+// current_timestamp_position_percent.
+pub fn top_timeline_window_ms(
+    config: &Config,
+    top_timeline_rect: &Rect,
+    timeline_zoom: f64,
+    time_ms: f64,
+) -> (f64, f64) {
+    let timeline = &config.appearance.timeline;
+    let height_px = (top_timeline_rect.y1 - top_timeline_rect.y0).max(1.0);
+    let width_px = (top_timeline_rect.x1 - top_timeline_rect.x0).max(1.0);
+    let radius_px = (height_px * timeline.object_radius_height_percent.clamp(0.0, 1.0)).max(1.0);
+    let ms_per_radius =
+        timeline.milliseconds_per_object_radius.max(1.0) / timeline_zoom.clamp(0.1, 10.0);
+    let span_ms = ((width_px / radius_px) * ms_per_radius).max(1.0);
+    let start_ms = time_ms - span_ms * timeline.current_timestamp_position_percent.clamp(0.0, 1.0);
+    (start_ms, start_ms + span_ms)
+}
+
+// The part of the song the bottom timeline shows, the whole song until it is zoomed into. It
+// is the coarse level of navigation, the top timeline the fine one.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct BottomTimelineRange {
+    pub start_ms: f64,
+    pub end_ms: f64,
+}
+
+impl BottomTimelineRange {
+    pub fn full(total_ms: f64) -> Self {
+        Self {
+            start_ms: 0.0,
+            end_ms: total_ms.max(0.0),
+        }
+    }
+
+    pub fn span_ms(&self) -> f64 {
+        (self.end_ms - self.start_ms).max(1.0)
+    }
+
+    pub fn is_zoomed(&self, total_ms: f64) -> bool {
+        self.start_ms > 0.0 || self.end_ms < total_ms
+    }
+
+    // The time at `frac` of the bar's width.
+    pub fn time_at(&self, frac: f64) -> f64 {
+        self.start_ms + self.span_ms() * frac.clamp(0.0, 1.0)
+    }
+
+    // Scales the span by `factor`, below 1 zooms in, keeping the time at `anchor_frac` of the
+    // bar's width under the cursor.
+    pub fn zoomed(&self, anchor_frac: f64, factor: f64, total_ms: f64) -> Self {
+        let anchor_frac = anchor_frac.clamp(0.0, 1.0);
+        let anchor_ms = self.time_at(anchor_frac);
+        let span_ms = (self.span_ms() * factor).max(MIN_BOTTOM_TIMELINE_SPAN_MS);
+        Self::clamped(anchor_ms - span_ms * anchor_frac, span_ms, total_ms)
+    }
+
+    // Moves the range as little as it takes to show all of `start_ms..end_ms`, the top
+    // timeline's window, and centers on it when it is wider than the range.
+    pub fn following(&self, start_ms: f64, end_ms: f64, total_ms: f64) -> Self {
+        let span_ms = self.span_ms();
+        let new_start_ms = if end_ms - start_ms > span_ms {
+            (start_ms + end_ms - span_ms) * 0.5
+        } else if start_ms < self.start_ms {
+            start_ms
+        } else if end_ms > self.end_ms {
+            end_ms - span_ms
+        } else {
+            self.start_ms
+        };
+        Self::clamped(new_start_ms, span_ms, total_ms)
+    }
+
+    fn clamped(start_ms: f64, span_ms: f64, total_ms: f64) -> Self {
+        let total_ms = total_ms.max(0.0);
+        let span_ms = span_ms.min(total_ms);
+        let start_ms = start_ms.clamp(0.0, total_ms - span_ms);
+        Self {
+            start_ms,
+            end_ms: start_ms + span_ms,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bottom_timeline_zooms_around_the_cursor_and_follows_the_top_window() {
+        let full = BottomTimelineRange::full(60_000.0);
+        assert!(!full.is_zoomed(60_000.0));
+
+        let zoomed = full.zoomed(0.25, 0.5, 60_000.0);
+        assert_eq!((zoomed.start_ms, zoomed.end_ms), (7_500.0, 37_500.0));
+        assert_eq!(zoomed.time_at(0.25), full.time_at(0.25));
+        assert!(zoomed.is_zoomed(60_000.0));
+        assert_eq!(zoomed.zoomed(0.0, 0.001, 60_000.0).span_ms(), 2_000.0);
+        assert_eq!(zoomed.zoomed(0.5, 4.0, 60_000.0), full);
+
+        let followed = zoomed.following(40_000.0, 42_000.0, 60_000.0);
+        assert_eq!((followed.start_ms, followed.end_ms), (12_000.0, 42_000.0));
+        assert_eq!(zoomed.following(10_000.0, 12_000.0, 60_000.0), zoomed);
+        let at_end = zoomed.following(59_000.0, 61_000.0, 60_000.0);
+        assert_eq!((at_end.start_ms, at_end.end_ms), (30_000.0, 60_000.0));
+    }
+}
@@ -0,0 +1,298 @@
+use crate::{
+    geometry::vec2::Vec2,
+    map_format::{
+        diff_settings::DiffSettings,
+        objects::{Circle, ComboInfo, HitObject, HitsoundInfo, Slider},
+        slider_curve::{ControlPointSegment, ControlPoints},
+        timing::Timing,
+    },
+};
+
+// What the mouse does on the playfield. Select is the editor as it always was. The place
+// modes take over left clicks on the playfield, Edit Path grabs slider anchors without ctrl
+// and Timing locks the objects so only the playhead and timing tools are in play.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ToolMode {
+    Select = 0,
+    PlaceCircle = 1,
+    PlaceSlider = 2,
+    EditPath = 3,
+    Timing = 4,
+}
+
+pub const TOOL_MODE_NAMES: [&str; 5] =
+    ["select", "place circle", "place slider", "edit path", "timing"];
+
+impl ToolMode {
+    pub fn from_index(index: u32) -> Self {
+        match index {
+            1 => ToolMode::PlaceCircle,
+            2 => ToolMode::PlaceSlider,
+            3 => ToolMode::EditPath,
+            4 => ToolMode::Timing,
+            _ => ToolMode::Select,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        TOOL_MODE_NAMES[*self as usize]
+    }
+
+    // Whether clicks on the playfield go to the tool instead of the selection hitboxes.
+    pub fn takes_playfield_clicks(&self) -> bool {
+        matches!(self, ToolMode::PlaceCircle | ToolMode::PlaceSlider | ToolMode::Timing)
+    }
+
+    // Whether slider anchors can be grabbed without holding ctrl.
+    pub fn grabs_anchors(&self) -> bool {
+        *self == ToolMode::EditPath
+    }
+
+    // Whether the selection handles and the keys that change objects are off.
+    pub fn locks_objects(&self) -> bool {
+        *self == ToolMode::Timing
+    }
+}
+
+pub enum ToolEvent {
+    SwitchTo(ToolMode),
+    // Left click on the playfield, in playfield coordinates.
+    PlayfieldClick(Vec2),
+    // Enter or a right click on the playfield.
+    Confirm,
+    // Escape.
+    Cancel,
+    // A key that moves, reshapes or otherwise changes objects.
+    ObjectEdit,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ToolAction {
+    // The event was handled and nothing else has to happen.
+    None,
+    // The event means nothing to the current mode, the editor's usual binding applies.
+    Unhandled,
+    ModeChanged(ToolMode),
+    PlaceCircle(Vec2),
+    AddSliderAnchor(usize),
+    PlaceSlider(Vec<Vec2>),
+    OpenTimingTools,
+}
+
+// The current tool mode and what it has collected so far. Every key and playfield click that
+// depends on the mode goes through `handle`, so a new tool is a new mode and its transitions.
+pub struct ToolState {
+    mode: ToolMode,
+    slider_anchors: Vec<Vec2>,
+}
+
+impl ToolState {
+    pub fn new() -> Self {
+        Self {
+            mode: ToolMode::Select,
+            slider_anchors: Vec::new(),
+        }
+    }
+
+    pub fn handle(&mut self, event: ToolEvent) -> ToolAction {
+        match (self.mode, event) {
+            (_, ToolEvent::SwitchTo(mode)) => {
+                self.slider_anchors.clear();
+                self.mode = mode;
+                ToolAction::ModeChanged(mode)
+            }
+            (ToolMode::PlaceCircle, ToolEvent::PlayfieldClick(pos)) => ToolAction::PlaceCircle(pos),
+            (ToolMode::PlaceSlider, ToolEvent::PlayfieldClick(pos)) => {
+                self.slider_anchors.push(pos);
+                ToolAction::AddSliderAnchor(self.slider_anchors.len())
+            }
+            (ToolMode::PlaceSlider, ToolEvent::Confirm) => {
+                if self.slider_anchors.len() < 2 {
+                    return ToolAction::None;
+                }
+                ToolAction::PlaceSlider(std::mem::take(&mut self.slider_anchors))
+            }
+            (ToolMode::Timing, ToolEvent::Confirm) => ToolAction::OpenTimingTools,
+            // Escape drops an unfinished slider first, then leaves the mode.
+            (ToolMode::PlaceSlider, ToolEvent::Cancel) if !self.slider_anchors.is_empty() => {
+                self.slider_anchors.clear();
+                ToolAction::None
+            }
+            (mode, ToolEvent::ObjectEdit) if mode.locks_objects() => ToolAction::None,
+            (_, ToolEvent::ObjectEdit) => ToolAction::Unhandled,
+            (ToolMode::Select, ToolEvent::Cancel) => ToolAction::Unhandled,
+            (_, ToolEvent::Cancel) => self.handle(ToolEvent::SwitchTo(ToolMode::Select)),
+            (_, ToolEvent::PlayfieldClick(_)) => ToolAction::None,
+            (_, ToolEvent::Confirm) => ToolAction::Unhandled,
+        }
+    }
+
+    // The shortcuts that matter in the current mode, for the hint line at the bottom of the
+    // screen. Only characters the HUD font has.
+    pub fn hint(&self) -> String {
+        let name = self.mode.name();
+        match self.mode {
+            ToolMode::Select => format!(
+                "{} | left or right drag selects - space plays - z undo - alt 1-5 tools",
+                name
+            ),
+            ToolMode::PlaceCircle => {
+                format!("{} | click places a circle at the playhead - esc back to select", name)
+            }
+            ToolMode::PlaceSlider if self.slider_anchors.is_empty() => format!(
+                "{} | click adds anchors - enter or right click places - esc back to select",
+                name
+            ),
+            ToolMode::PlaceSlider => format!(
+                "{} | {} anchors - click adds - enter or right click places - esc drops them",
+                name,
+                self.slider_anchors.len()
+            ),
+            ToolMode::EditPath => {
+                format!("{} | drag slider anchors - esc back to select", name)
+            }
+            ToolMode::Timing => format!(
+                "{} | objects locked - enter opens timing tools - esc back to select",
+                name
+            ),
+        }
+    }
+}
+
+// A circle at `pos`, new combo off and hitsounds from the timing point at `time`. None before
+// the first red line.
+pub fn placed_circle(pos: Vec2, time: f64, timing: &Timing) -> Option<HitObject> {
+    Some(HitObject::Circle(Circle {
+        pos,
+        time,
+        combo_info: ComboInfo {
+            new_combo: false,
+            color_skip: 0,
+        },
+        hitsound_info: default_hitsound_info(time, timing)?,
+    }))
+}
+
+// A one-slide slider through `anchors` as straight segments. Its length follows the path but
+// ends on the nearest snap tick, so the tail is snapped like the head.
+pub fn placed_slider(
+    anchors: &[Vec2],
+    time: f64,
+    timing: &Timing,
+    diff_settings: &DiffSettings,
+) -> Option<HitObject> {
+    let (&start, rest) = anchors.split_first()?;
+    let (&end, middle) = rest.split_last()?;
+    let (red_line, green_line) = timing.get_lines_at_time(time);
+    let sv_multiplier = green_line.map_or(1.0, |gl| gl.sv_multiplier);
+    let sv_pixels_per_ms =
+        (diff_settings.sv_multiplier * 100.0 * sv_multiplier) / red_line?.beat_length;
+    let control_points = ControlPoints {
+        start,
+        slider_segments: vec![ControlPointSegment::Linear(middle.to_vec(), end)],
+    };
+    let path_length = control_points.size();
+    let end_time = timing
+        .nearest_snap(time + path_length / sv_pixels_per_ms)
+        .filter(|&end_time| end_time > time)
+        .unwrap_or(time + path_length / sv_pixels_per_ms);
+    let length_pixels = (end_time - time) * sv_pixels_per_ms;
+    if length_pixels <= 0.0 {
+        return None;
+    }
+    let hitsound_info = default_hitsound_info(time, timing)?;
+    let end_hitsound_info = default_hitsound_info(end_time, timing)?;
+    Some(HitObject::Slider(Slider {
+        time,
+        slides: 1,
+        length_pixels,
+        sv_pixels_per_ms,
+        combo_info: ComboInfo {
+            new_combo: false,
+            color_skip: 0,
+        },
+        hitsounds: vec![hitsound_info.clone(), end_hitsound_info],
+        sliderbody_hitsound: hitsound_info,
+        control_points,
+    }))
+}
+
+fn default_hitsound_info(time: f64, timing: &Timing) -> Option<HitsoundInfo> {
+    let (red_line, green_line) = timing.get_lines_at_time(time);
+    let red_line = red_line?;
+    let (sample_set, volume, index) = match green_line {
+        Some(gl) => (gl.sample_set, gl.volume, gl.sample_index),
+        None => (red_line.sample_set, red_line.volume, red_line.sample_index),
+    };
+    Some(HitsoundInfo {
+        additions_sampleset: sample_set.clone(),
+        hit_sampleset: sample_set,
+        volume,
+        index,
+        play_whistle: false,
+        play_finish: false,
+        play_clap: false,
+        filename: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_harness::{TEST_MAP, parse_test_map};
+
+    #[test]
+    fn slider_tool_places_a_slider_ending_on_a_tick() {
+        let mut tool_state = ToolState::new();
+        let click = |x, y| ToolEvent::PlayfieldClick(Vec2 { x, y });
+        assert_eq!(tool_state.handle(click(100.0, 100.0)), ToolAction::None);
+        tool_state.handle(ToolEvent::SwitchTo(ToolMode::PlaceSlider));
+        assert_eq!(tool_state.handle(click(100.0, 100.0)), ToolAction::AddSliderAnchor(1));
+        assert_eq!(tool_state.handle(ToolEvent::Confirm), ToolAction::None);
+        tool_state.handle(click(180.0, 100.0));
+        tool_state.handle(click(180.0, 163.0));
+        let ToolAction::PlaceSlider(anchors) = tool_state.handle(ToolEvent::Confirm) else {
+            panic!("three anchors make a slider");
+        };
+        assert_eq!(
+            tool_state.handle(ToolEvent::Cancel),
+            ToolAction::ModeChanged(ToolMode::Select)
+        );
+        assert_eq!(tool_state.handle(ToolEvent::Cancel), ToolAction::Unhandled);
+
+        // 143px at 0.28px/ms would end at 1010.7ms, the closest tick is 1000ms.
+        let (_, beatmap) = parse_test_map(TEST_MAP);
+        let slider = placed_slider(&anchors, 500.0, &beatmap.timing, &beatmap.diff_settings);
+        let Some(HitObject::Slider(slider)) = slider else {
+            panic!("the slider is after the red line");
+        };
+        assert!((slider.end_time() - 1000.0).abs() < 1e-6);
+        assert!((slider.length_pixels - 140.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn timing_mode_locks_object_edits() {
+        let mut tool_state = ToolState::new();
+        assert_eq!(tool_state.handle(ToolEvent::ObjectEdit), ToolAction::Unhandled);
+        tool_state.handle(ToolEvent::SwitchTo(ToolMode::Timing));
+        assert_eq!(tool_state.handle(ToolEvent::ObjectEdit), ToolAction::None);
+        tool_state.handle(ToolEvent::Cancel);
+        assert_eq!(tool_state.handle(ToolEvent::ObjectEdit), ToolAction::Unhandled);
+    }
+
+    #[test]
+    fn hint_line_follows_the_tool_mode() {
+        let mut tool_state = ToolState::new();
+        assert!(tool_state.hint().starts_with("select | "));
+        tool_state.handle(ToolEvent::SwitchTo(ToolMode::PlaceSlider));
+        tool_state.handle(ToolEvent::PlayfieldClick(Vec2 { x: 100.0, y: 100.0 }));
+        tool_state.handle(ToolEvent::PlayfieldClick(Vec2 { x: 200.0, y: 100.0 }));
+        assert!(tool_state.hint().starts_with("place slider | 2 anchors - "));
+        let in_hud_font = |ch: char| ch.is_ascii_alphanumeric() || " -./:_|".contains(ch);
+        for index in 0..5 {
+            tool_state.handle(ToolEvent::SwitchTo(ToolMode::from_index(index)));
+            let hint = tool_state.hint();
+            assert!(hint.len() <= 96 && hint.chars().all(in_hud_font), "{hint}");
+        }
+    }
+}
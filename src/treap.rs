@@ -26,6 +26,14 @@ impl<T: Clone> Treap<T> {
         self.size()
     }
 
+    pub fn ptr_eq(&self, other: &Treap<T>) -> bool {
+        match (&self.0, &other.0) {
+            (None, None) => true,
+            (Some(a), Some(b)) => Arc::ptr_eq(a, b),
+            _ => false,
+        }
+    }
+
     pub fn new_empty() -> Self {
         Treap(None)
     }